@@ -0,0 +1,117 @@
+//! Errors from the library facade, split by kind so hosts can react to each without
+//! matching on message text.
+
+use crate::interpreter::batch::SourceForm;
+use crate::interpreter::evaluator::INTERRUPTED;
+use crate::interpreter::source_map::Span;
+use crate::interpreter::types::EvalError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// A form that was being evaluated when an error happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceFrame {
+    pub form: SourceForm,
+    /// The innermost list read from the source that the error came out of, which may be in
+    /// a function defined elsewhere
+    pub span: Option<Span>,
+}
+
+/// Everything that can go wrong between reading a program and getting its value.
+///
+/// ```
+/// use zeus::ZeusError;
+///
+/// match zeus::eval_str("(+ 1 2)\n(list 1 (car 5))") {
+///     Err(ZeusError::Eval { trace, .. }) => {
+///         assert_eq!(trace[0].form.line, 2);
+///         assert_eq!(trace[0].span.unwrap().start.column, 9);
+///     }
+///     other => panic!("expected an evaluation error, got {:?}", other),
+/// }
+/// assert!(matches!(
+///     zeus::eval_str("(list 1"),
+///     Err(ZeusError::Parse { line: 1, .. })
+/// ));
+/// ```
+#[derive(Debug)]
+pub enum ZeusError {
+    /// Source that doesn't read as Lisp: unbalanced parentheses, an unterminated string or
+    /// a malformed token. `line` and `column` are 1-based and point at where reading stopped.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// An error raised while evaluating. `trace` holds the forms being evaluated when it
+    /// happened, outermost first; for now that is only the top-level form.
+    Eval {
+        error: EvalError,
+        trace: Vec<TraceFrame>,
+    },
+    /// A file that couldn't be read
+    Io { path: PathBuf, error: io::Error },
+    /// A zeus.toml that couldn't be understood
+    Config { path: PathBuf, message: String },
+    /// The evaluation was stopped through `Evaluator::interrupt_handle` or `interrupt_poll`
+    Interrupt,
+}
+
+impl ZeusError {
+    /// The evaluation error underneath, if this is one
+    pub fn eval_error(&self) -> Option<&EvalError> {
+        match self {
+            ZeusError::Eval { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+
+    // Records that the error happened while evaluating `form`, as the new outermost frame
+    pub(crate) fn within(self, form: &SourceForm, span: Option<Span>) -> Self {
+        match self {
+            ZeusError::Eval { error, mut trace } => {
+                let form = form.clone();
+                trace.insert(0, TraceFrame { form, span });
+                ZeusError::Eval { error, trace }
+            }
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for ZeusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZeusError::Parse { line, message, .. } => write!(f, "line {}: {}", line, message),
+            ZeusError::Eval { error, .. } => write!(f, "{}", error),
+            ZeusError::Io { path, error } => {
+                write!(f, "Error reading file '{}': {}", path.display(), error)
+            }
+            ZeusError::Config { path, message } => write!(f, "{}: {}", path.display(), message),
+            ZeusError::Interrupt => write!(f, "{}", INTERRUPTED),
+        }
+    }
+}
+
+impl std::error::Error for ZeusError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ZeusError::Eval { error, .. } => Some(error),
+            ZeusError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<EvalError> for ZeusError {
+    fn from(error: EvalError) -> Self {
+        match error {
+            EvalError::Interrupted => ZeusError::Interrupt,
+            error => ZeusError::Eval {
+                error,
+                trace: Vec::new(),
+            },
+        }
+    }
+}
@@ -1,8 +1,22 @@
 use crate::ide::editor::EditorPane;
 use crate::ide::file_tree::FileTreePane;
 use crate::ide::fonts::IdeFonts;
+use crate::ide::history_pane::HistoryPane;
 use crate::ide::ide_state::IdeState;
+use crate::ide::inspector::InspectorPane;
+use crate::ide::layout::Side;
+use crate::ide::outline_pane::OutlinePane;
+use crate::ide::repl_pane::ReplPane;
+use crate::ide::results_pane::ResultsPane;
+use crate::ide::symbol_browser::SymbolBrowserPane;
+use crate::ide::transcript_pane::TranscriptPane;
+use crate::interpreter::ports::{InputPort, OutputPort, StringInputPort};
+use crate::interpreter::remote::{definition_notice, RemoteServer, Response};
+use crate::interpreter::xref::Location;
+use crate::interpreter::Evaluator;
 use raylib::prelude::*;
+use std::path::PathBuf;
+use std::rc::Rc;
 
 const WINDOW_WIDTH: i32 = 1200;
 const WINDOW_HEIGHT: i32 = 800;
@@ -10,12 +24,19 @@ const MIN_WINDOW_WIDTH: i32 = 800;
 const MIN_WINDOW_HEIGHT: i32 = 600;
 const IDE_ATLAS_BASE_SIZE: f32 = 16.0;
 const STATUS_BAR_HEIGHT: f32 = 25.0;
+const SWITCHER_ROW_HEIGHT: f32 = 24.0;
+const SWITCHER_WIDTH: f32 = 560.0;
 
 pub struct IdeApp {
     rl: RaylibHandle,
     thread: RaylibThread,
     state: IdeState,
     fonts: IdeFonts,
+    // Selected row of the Ctrl+E recent-files switcher while it is open
+    switcher_selection: Option<usize>,
+    last_recorded_file: Option<PathBuf>,
+    // Accepts `zeus repl --connect` clients, which evaluate in the REPL pane's environment
+    remote_server: Option<RemoteServer>,
 }
 
 impl IdeApp {
@@ -40,6 +61,9 @@ impl IdeApp {
             thread,
             state,
             fonts,
+            switcher_selection: None,
+            last_recorded_file: None,
+            remote_server: None,
         };
 
         // Set minimum window size
@@ -48,9 +72,200 @@ impl IdeApp {
         }
 
         app.rl.set_target_fps(60);
+        app.install_stop_key();
+        app.install_transcript();
         app
     }
 
+    // Evaluation runs inside the frame loop, so a runaway form would freeze the window.
+    // The evaluators poll for Esc themselves and stop with an "interrupted" error.
+    fn install_stop_key(&mut self) {
+        let poll: Rc<dyn Fn() -> bool> = Rc::new(escape_pressed);
+        if let Some(repl) = self.repl_pane_mut() {
+            repl.evaluator_mut().interrupt_poll = Some(Rc::clone(&poll));
+        }
+        if let Some(editor) = self
+            .state
+            .panes
+            .get_mut("editor")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<EditorPane>())
+        {
+            editor.evaluator_mut().interrupt_poll = Some(poll);
+        }
+    }
+
+    // There's no terminal, so program output and error output from the REPL and the
+    // editor go to the transcript pane instead, and input reads find nothing
+    fn install_transcript(&mut self) {
+        let Some(port) = self
+            .state
+            .panes
+            .get("transcript")
+            .and_then(|pane| pane.as_any().downcast_ref::<TranscriptPane>())
+            .map(|transcript| transcript.port())
+        else {
+            return;
+        };
+        let output: Rc<dyn OutputPort> = Rc::new(port);
+        let input: Rc<dyn InputPort> = Rc::new(StringInputPort::default());
+        let connect = |evaluator: &mut Evaluator| {
+            evaluator.output = Rc::clone(&output);
+            evaluator.error_output = Rc::clone(&output);
+            evaluator.input = Rc::clone(&input);
+        };
+        if let Some(repl) = self.repl_pane_mut() {
+            connect(repl.evaluator_mut());
+        }
+        if let Some(editor) = self
+            .state
+            .panes
+            .get_mut("editor")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<EditorPane>())
+        {
+            connect(editor.evaluator_mut());
+        }
+    }
+
+    pub fn serve_remote(&mut self, addr: &str) -> Result<(), String> {
+        let server = RemoteServer::bind(addr)
+            .map_err(|e| format!("Failed to start remote REPL on {}: {}", addr, e))?;
+        let bound = server
+            .local_addr()
+            .map(|a| a.to_string())
+            .unwrap_or_else(|_| addr.to_string());
+        if let Some(repl) = self.repl_pane_mut() {
+            repl.append_output(&format!("Remote REPL listening on {}", bound), false);
+        }
+        self.remote_server = Some(server);
+        Ok(())
+    }
+
+    fn repl_pane_mut(&mut self) -> Option<&mut ReplPane> {
+        self.state
+            .panes
+            .get_mut("repl")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<ReplPane>())
+    }
+
+    fn inspector_pane_mut(&mut self) -> Option<&mut InspectorPane> {
+        self.state
+            .panes
+            .get_mut("inspector")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<InspectorPane>())
+    }
+
+    fn results_pane_mut(&mut self) -> Option<&mut ResultsPane> {
+        self.state
+            .panes
+            .get_mut("results")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<ResultsPane>())
+    }
+
+    // Rebuilds the outline when the editor's text has changed and returns the entry
+    // picked in it, if any. Large files aren't outlined: each keystroke would reparse them.
+    fn update_outline(&mut self) -> Option<Location> {
+        let editor = self
+            .state
+            .panes
+            .get("editor")
+            .and_then(|pane| pane.as_any().downcast_ref::<EditorPane>())?;
+        let version = editor.content_version();
+        let stale = self
+            .state
+            .panes
+            .get("outline")
+            .and_then(|pane| pane.as_any().downcast_ref::<OutlinePane>())
+            .is_some_and(|outline| !outline.is_current(version));
+        let buffer = (stale && !editor.is_large_file())
+            .then(|| (editor.buffer_name(), editor.content().to_string()));
+        let outline = self
+            .state
+            .panes
+            .get_mut("outline")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<OutlinePane>())?;
+        if stale {
+            let (file, source) = buffer.unwrap_or_default();
+            outline.refresh(version, &file, &source);
+        }
+        outline.take_jump_request()
+    }
+
+    // Rebuilds the history timeline when the REPL's recording has moved on
+    fn update_history(&mut self) {
+        let Some(repl) = self
+            .state
+            .panes
+            .get("repl")
+            .and_then(|pane| pane.as_any().downcast_ref::<ReplPane>())
+        else {
+            return;
+        };
+        let recording = repl.evaluator().recording.clone();
+        if let Some(history) = self
+            .state
+            .panes
+            .get_mut("history")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<HistoryPane>())
+        {
+            if !history.is_current(recording.as_ref()) {
+                history.refresh(recording.as_ref());
+            }
+        }
+    }
+
+    // Sends the values asked for to the inspector: Enter in the symbol browser inspects
+    // that variable's value in the REPL, and Ctrl+Shift+I in the REPL or the editor its
+    // last result. An edit in the inspector that replaces the value of a REPL variable is bound
+    // back to it there.
+    fn update_inspector(&mut self) {
+        let symbol = self
+            .state
+            .panes
+            .get_mut("symbols")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<SymbolBrowserPane>())
+            .and_then(|symbols| symbols.take_inspect_request());
+        let from_repl = self.repl_pane_mut().and_then(|repl| {
+            let (value, origin) = match symbol {
+                Some(name) => (
+                    repl.evaluator().get_environment().get(&name).ok()?,
+                    Some(name),
+                ),
+                None => repl.take_inspect_request()?,
+            };
+            Some((value, origin, repl.evaluator().get_environment().clone()))
+        });
+        let from_editor = self
+            .state
+            .panes
+            .get_mut("editor")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<EditorPane>())
+            .and_then(|editor| {
+                let value = editor.take_inspect_request()?;
+                Some((
+                    value,
+                    None,
+                    editor.evaluator_mut().get_environment().clone(),
+                ))
+            });
+        if let Some((value, origin, environment)) = from_repl.or(from_editor) {
+            if let Some(inspector) = self.inspector_pane_mut() {
+                match origin {
+                    Some(name) => inspector.inspect_binding(&name, value, &environment),
+                    None => inspector.inspect(value, &environment),
+                }
+                self.state.focus_pane("inspector".to_string());
+            }
+        }
+
+        let rebind = self
+            .inspector_pane_mut()
+            .and_then(|inspector| inspector.take_rebind());
+        if let (Some((name, value)), Some(repl)) = (rebind, self.repl_pane_mut()) {
+            repl.evaluator_mut().environment.define(name.clone(), value);
+            repl.append_output(&format!("Set {} from the inspector", name), false);
+        }
+    }
+
     pub fn run(&mut self) {
         // Initial layout calculation
         let available_height = (self.rl.get_screen_height() as f32 - STATUS_BAR_HEIGHT).max(0.0);
@@ -70,6 +285,8 @@ impl IdeApp {
             self.update();
             self.draw();
         }
+
+        self.state.autosave_environment();
     }
 
     fn handle_input(&mut self) {
@@ -77,6 +294,68 @@ impl IdeApp {
         let is_ctrl_or_cmd = self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
             || self.rl.is_key_down(KeyboardKey::KEY_LEFT_SUPER);
 
+        // The switcher is modal: it takes all keyboard input while open
+        if self.switcher_selection.is_some() {
+            self.handle_switcher_input(is_ctrl_or_cmd);
+            return;
+        }
+
+        // A split line being dragged takes the mouse until the button is released
+        let mouse = self.rl.get_mouse_position();
+        if self.state.layout_manager.is_dragging() {
+            if self.rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+                self.state.layout_manager.drag_to(mouse.x, mouse.y);
+            } else {
+                self.state.layout_manager.end_drag();
+                self.state.layout_changed();
+            }
+            return;
+        }
+        if self
+            .rl
+            .is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            && self.state.layout_manager.begin_drag(mouse.x, mouse.y)
+        {
+            return;
+        }
+
+        // Layout shortcuts are consumed here so the focused pane doesn't also see them
+        let shift = self.rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+            || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+        if is_ctrl_or_cmd && shift {
+            if let Some(focused) = self.state.layout_manager.get_focused_pane().cloned() {
+                // Ctrl/Cmd+Shift+M: Maximize or restore the focused pane
+                if self.rl.is_key_pressed(KeyboardKey::KEY_M) {
+                    self.state.layout_manager.toggle_maximize(&focused);
+                    return;
+                }
+                // Ctrl/Cmd+Shift+Arrow: Swap the focused pane with its neighbour
+                for (key, side) in [
+                    (KeyboardKey::KEY_LEFT, Side::Left),
+                    (KeyboardKey::KEY_RIGHT, Side::Right),
+                    (KeyboardKey::KEY_UP, Side::Up),
+                    (KeyboardKey::KEY_DOWN, Side::Down),
+                ] {
+                    if self.rl.is_key_pressed(key) {
+                        if self.state.layout_manager.swap_with_neighbor(&focused, side) {
+                            self.state.layout_changed();
+                        }
+                        return;
+                    }
+                }
+            }
+        }
+
+        if is_ctrl_or_cmd && self.rl.is_key_pressed(KeyboardKey::KEY_E) {
+            if self.state.recent_files.paths().is_empty() {
+                return;
+            }
+            // Start on the previous file so Ctrl+E, Enter flips between the last two
+            let selection = usize::from(self.state.recent_files.paths().len() > 1);
+            self.switcher_selection = Some(selection);
+            return;
+        }
+
         if is_ctrl_or_cmd {
             // Ctrl/Cmd+1: Focus file tree
             if self.rl.is_key_pressed(KeyboardKey::KEY_ONE) {
@@ -98,6 +377,22 @@ impl IdeApp {
             else if self.rl.is_key_pressed(KeyboardKey::KEY_FIVE) {
                 self.state.focus_pane("inspector".to_string());
             }
+            // Ctrl/Cmd+6: Focus search results
+            else if self.rl.is_key_pressed(KeyboardKey::KEY_SIX) {
+                self.state.focus_pane("results".to_string());
+            }
+            // Ctrl/Cmd+7: Focus outline
+            else if self.rl.is_key_pressed(KeyboardKey::KEY_SEVEN) {
+                self.state.focus_pane("outline".to_string());
+            }
+            // Ctrl/Cmd+8: Focus transcript
+            else if self.rl.is_key_pressed(KeyboardKey::KEY_EIGHT) {
+                self.state.focus_pane("transcript".to_string());
+            }
+            // Ctrl/Cmd+9: Focus history
+            else if self.rl.is_key_pressed(KeyboardKey::KEY_NINE) {
+                self.state.focus_pane("history".to_string());
+            }
             // Ctrl/Cmd+U: Update symbol browser
             else if self.rl.is_key_pressed(KeyboardKey::KEY_U) {
                 self.state.update_symbol_browser();
@@ -119,8 +414,16 @@ impl IdeApp {
             }
         }
 
-        // Handle Tab key to cycle through panes
-        if self.rl.is_key_pressed(KeyboardKey::KEY_TAB) {
+        // Handle Tab key to cycle through panes; the editor keeps Tab for indentation
+        // and path completion, and the REPL for completion
+        let keeps_tab = matches!(
+            self.state
+                .layout_manager
+                .get_focused_pane()
+                .map(String::as_str),
+            Some("editor" | "repl")
+        );
+        if !keeps_tab && self.rl.is_key_pressed(KeyboardKey::KEY_TAB) {
             let pane_ids: Vec<String> = self.state.panes.keys().cloned().collect();
             if let Some(current_id) = self.state.layout_manager.get_focused_pane() {
                 if let Some(current_index) = pane_ids.iter().position(|id| id == current_id) {
@@ -146,7 +449,139 @@ impl IdeApp {
         }
     }
 
+    fn handle_switcher_input(&mut self, is_ctrl_or_cmd: bool) {
+        let count = self.state.recent_files.paths().len();
+        let Some(selection) = self.switcher_selection else {
+            return;
+        };
+        if count == 0 || self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.switcher_selection = None;
+            return;
+        }
+
+        if self.rl.is_key_pressed(KeyboardKey::KEY_DOWN)
+            || (is_ctrl_or_cmd && self.rl.is_key_pressed(KeyboardKey::KEY_E))
+        {
+            self.switcher_selection = Some((selection + 1) % count);
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.switcher_selection = Some((selection + count - 1) % count);
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            self.switcher_selection = None;
+            let path = self.state.recent_files.paths()[selection.min(count - 1)].clone();
+            self.open_recent_file(path);
+        }
+    }
+
+    fn open_recent_file(&mut self, path: PathBuf) {
+        if !path.is_file() {
+            self.state.forget_recent_file(&path);
+            return;
+        }
+        if let Some(editor) = self
+            .state
+            .panes
+            .get_mut("editor")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<EditorPane>())
+        {
+            editor.load_file_from_path(path);
+        }
+        self.state.focus_pane("editor".to_string());
+    }
+
     fn update(&mut self) {
+        // Every file the editor shows goes to the front of the recent list
+        let current_file = self
+            .state
+            .panes
+            .get("editor")
+            .and_then(|pane| pane.as_any().downcast_ref::<EditorPane>())
+            .and_then(|editor| editor.current_file().cloned());
+        if current_file.is_some() && current_file != self.last_recorded_file {
+            if let Some(path) = &current_file {
+                self.state.record_recent_file(path);
+            }
+            self.last_recorded_file = current_file;
+        }
+
+        // Find-references fills the results pane; picking a result opens it in the editor
+        let reference_results = self
+            .state
+            .panes
+            .get_mut("editor")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<EditorPane>())
+            .and_then(|editor| editor.take_reference_results());
+        if let Some((heading, items)) = reference_results {
+            if let Some(results) = self.results_pane_mut() {
+                results.show(heading, items);
+            }
+            self.state.focus_pane("results".to_string());
+        }
+        self.update_history();
+        self.update_inspector();
+        let jump = self
+            .results_pane_mut()
+            .and_then(|results| results.take_jump_request())
+            .or_else(|| self.update_outline());
+        if let Some(location) = jump {
+            if let Some(editor) = self
+                .state
+                .panes
+                .get_mut("editor")
+                .and_then(|pane| pane.as_any_mut().downcast_mut::<EditorPane>())
+            {
+                editor.open_location(&location);
+            }
+            self.state.focus_pane("editor".to_string());
+        }
+
+        // Saves and git commands in the editor change git status, so refresh the tree markers
+        let (git_changed, vcs_output) = self
+            .state
+            .panes
+            .get_mut("editor")
+            .and_then(|pane| pane.as_any_mut().downcast_mut::<EditorPane>())
+            .map(|editor| (editor.take_git_refresh_request(), editor.take_vcs_output()))
+            .unwrap_or_default();
+        if !vcs_output.is_empty() {
+            if let Some(repl) = self.repl_pane_mut() {
+                for (text, is_error) in vcs_output {
+                    repl.append_output(&text, is_error);
+                }
+            }
+        }
+        if git_changed {
+            if let Some(file_tree) = self
+                .state
+                .panes
+                .get_mut("file_tree")
+                .and_then(|pane| pane.as_any_mut().downcast_mut::<FileTreePane>())
+            {
+                file_tree.refresh_git_status();
+            }
+        }
+
+        if let Some(mut server) = self.remote_server.take() {
+            // Definitions typed into the REPL pane go to every client; those made by a
+            // client go to everyone else
+            let mut changes: Vec<(String, Vec<String>)> = Vec::new();
+            if let Some(repl) = self.repl_pane_mut() {
+                changes.push(("ide".to_string(), repl.take_changed_definitions()));
+                server.poll(&mut |peer, source| {
+                    let response = repl.evaluate_remote(peer, source);
+                    changes.push((peer.to_string(), repl.take_changed_definitions()));
+                    response
+                });
+            } else {
+                server.poll(&mut |_, _| Response::Err("REPL pane is not available".to_string()));
+            }
+            for (origin, names) in changes {
+                for name in names {
+                    server.broadcast(&definition_notice(&origin, &name), Some(&origin));
+                }
+            }
+            self.remote_server = Some(server);
+        }
+
         // Update layout if window was resized
         if self.rl.is_window_resized() {
             let available_height =
@@ -196,6 +631,15 @@ impl IdeApp {
             }
         }
 
+        let mouse = d.get_mouse_position();
+        if let Some(rect) = self
+            .state
+            .layout_manager
+            .splitter_highlight(mouse.x, mouse.y)
+        {
+            d.draw_rectangle_rec(rect, self.state.theme.focus_indicator);
+        }
+
         // Draw status bar
         let screen_width = d.get_screen_width() as f32;
         let screen_height = d.get_screen_height() as f32;
@@ -211,7 +655,11 @@ impl IdeApp {
 
         // Draw status text
         let status_text = if let Some(focused_id) = self.state.layout_manager.get_focused_pane() {
-            format!("Focus: {} | Ctrl+1-5: Switch Panes | Tab: Cycle | Ctrl+E: Evaluate | F5: Refresh Files",
+            let focused_id = match self.state.layout_manager.maximized_pane() {
+                Some(maximized) if maximized == focused_id => format!("{} (maximized)", focused_id),
+                _ => focused_id.clone(),
+            };
+            format!("Focus: {} | Ctrl+1-7: Switch Panes | Ctrl+Shift+M: Maximize | Ctrl+Shift+Arrows: Move Pane | Tab: Cycle | Ctrl+Enter: Evaluate | Ctrl+E: Recent Files | F12/Shift+F12: Definition/References | Ctrl+Shift+G/K/D: Stage/Commit/Discard | F5: Refresh Files",
                     focused_id)
         } else {
             "Zeus LISP IDE - Phase 1".to_string()
@@ -225,6 +673,17 @@ impl IdeApp {
             self.state.theme.text,
         );
 
+        if let Some(selection) = self.switcher_selection {
+            Self::draw_switcher(
+                &mut d,
+                &self.state,
+                &self.fonts,
+                selection,
+                screen_width,
+                screen_height,
+            );
+        }
+
         // Draw FPS in top-right corner (for debugging)
         let fps_text = format!("FPS: {}", d.get_fps());
         self.fonts.draw_text(
@@ -235,4 +694,78 @@ impl IdeApp {
             self.state.theme.text_dim,
         );
     }
+
+    fn draw_switcher(
+        d: &mut RaylibDrawHandle,
+        state: &IdeState,
+        fonts: &IdeFonts,
+        selection: usize,
+        screen_width: f32,
+        screen_height: f32,
+    ) {
+        let theme = &state.theme;
+        let paths = state.recent_files.paths();
+        let width = SWITCHER_WIDTH.min(screen_width - 40.0);
+        let height = SWITCHER_ROW_HEIGHT * (paths.len() as f32 + 1.0) + 10.0;
+        let rect = Rectangle {
+            x: (screen_width - width) / 2.0,
+            y: (screen_height * 0.2).min((screen_height - height).max(0.0)),
+            width,
+            height,
+        };
+
+        d.draw_rectangle_rec(rect, theme.panel);
+        d.draw_rectangle_lines_ex(rect, 2.0, theme.focus_indicator);
+        fonts.draw_text(
+            d,
+            "Recent files",
+            Vector2::new(rect.x + 10.0, rect.y + 6.0),
+            14.0,
+            theme.text_dim,
+        );
+
+        for (index, path) in paths.iter().enumerate() {
+            let y = rect.y + 5.0 + SWITCHER_ROW_HEIGHT * (index as f32 + 1.0);
+            if index == selection {
+                d.draw_rectangle_rec(
+                    Rectangle {
+                        x: rect.x + 2.0,
+                        y,
+                        width: rect.width - 4.0,
+                        height: SWITCHER_ROW_HEIGHT,
+                    },
+                    theme.selection,
+                );
+            }
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string());
+            fonts.draw_text(
+                d,
+                &name,
+                Vector2::new(rect.x + 10.0, y + 4.0),
+                14.0,
+                theme.text,
+            );
+            if let Some(parent) = path.parent() {
+                let name_width = fonts.measure_text(&name, 14.0).x;
+                fonts.draw_text(
+                    d,
+                    &parent.display().to_string(),
+                    Vector2::new(rect.x + 24.0 + name_width, y + 6.0),
+                    12.0,
+                    theme.text_dim,
+                );
+            }
+        }
+    }
+}
+
+// Called from inside eval while the frame loop is blocked, so it pumps input events itself
+fn escape_pressed() -> bool {
+    unsafe {
+        raylib::ffi::PollInputEvents();
+        raylib::ffi::IsKeyDown(KeyboardKey::KEY_ESCAPE as i32)
+    }
 }
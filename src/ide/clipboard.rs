@@ -0,0 +1,59 @@
+use crate::interpreter::pretty::{pretty_print, DEFAULT_PRETTY_WIDTH};
+use crate::interpreter::snapshot::value_source;
+use crate::interpreter::types::Expr;
+
+// Values copied from the REPL or the inspector. The system clipboard only holds text, so a
+// value goes on it pretty-printed for other programs, followed by a comment line holding
+// source that evaluates back to the same value (`snapshot::value_source`), which the editor
+// pastes instead. The line is left off when the pretty text is that source already, as for
+// numbers and strings, or when the value has no readable form, such as an iterator.
+const VALUE_MARKER: &str = ";; zeus-value: ";
+
+pub fn value_text(value: &Expr) -> String {
+    let pretty = pretty_print(value, DEFAULT_PRETTY_WIDTH);
+    match value_source(value) {
+        Some(source) if source != pretty => format!("{}\n{}{}", pretty, VALUE_MARKER, source),
+        _ => pretty,
+    }
+}
+
+// What the editor inserts for clipboard text: the readable form of a copied value, or the
+// text as it is
+pub fn paste_text(clipboard: &str) -> &str {
+    match clipboard.rsplit_once(VALUE_MARKER) {
+        Some((_, source)) if !source.contains('\n') => source,
+        _ => clipboard,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{paste_text, value_text};
+    use crate::interpreter::evaluator::Evaluator;
+    use crate::interpreter::printer::write_string;
+
+    #[test]
+    fn test_values_round_trip_through_the_clipboard() {
+        let mut evaluator = Evaluator::new();
+        for source in [
+            "(alist->hash (list (cons \"a\" 1) (cons \"b\" (list 2 3))))",
+            "(make-queue 1 2 3)",
+            "(list 1 \"two\" (vector 3))",
+            "(/ 1 3)",
+            "(string->number \"-5/3\")",
+        ] {
+            let value = evaluator.eval_str(source).unwrap();
+            let text = value_text(&value);
+            let pasted = evaluator.eval_str(paste_text(&text)).unwrap();
+            assert_eq!(write_string(&pasted), write_string(&value), "{}", text);
+        }
+
+        // Text that reads back as itself goes on the clipboard alone
+        let value = evaluator.eval_str("\"hi\"").unwrap();
+        assert_eq!(value_text(&value), "\"hi\"");
+        assert_eq!(paste_text("plain text"), "plain text");
+        // Unreadable values are just their printed form
+        let value = evaluator.eval_str("(range 3)").unwrap();
+        assert_eq!(paste_text(&value_text(&value)), value_text(&value));
+    }
+}
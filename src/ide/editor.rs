@@ -1,13 +1,25 @@
+use crate::ide::clipboard;
 use crate::ide::fonts::IdeFonts;
+use crate::ide::git::{self, LineChange};
 use crate::ide::pane::Pane;
+use crate::ide::results_pane::ResultItem;
 use crate::ide::syntax::{SyntaxHighlighter, SyntaxKind};
 use crate::ide::theme::Theme;
+use crate::interpreter::check::{check_source, CheckConfig, Diagnostic, Severity};
 use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::introspect::{describe_symbol, SymbolKind};
+use crate::interpreter::path_completion::{common_prefix, complete_path};
+use crate::interpreter::printer::write_string;
+use crate::interpreter::profile::profile_program;
+use crate::interpreter::types::Expr;
+use crate::interpreter::xref::{find_references, Location, XrefIndex};
 use raylib::prelude::*;
 use std::any::Any;
 use std::cmp::{max, min};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
 
 const MAX_UNDO_STACK: usize = 200;
 const TITLE_HEIGHT: f32 = 25.0;
@@ -16,11 +28,23 @@ const LINE_HEIGHT: f32 = 20.0;
 const H_PADDING: f32 = 5.0;
 const V_PADDING: f32 = 5.0;
 const CARET_WIDTH: i32 = 2;
+const MAX_COMPLETION_ROWS: usize = 8;
 const KEY_REPEAT_INITIAL_DELAY: f32 = 0.35;
 const KEY_REPEAT_INTERVAL: f32 = 0.05;
 const SCROLLBAR_THICKNESS: f32 = 12.0;
 const SCROLLBAR_MIN_THUMB: f32 = 20.0;
 const SCROLL_WHEEL_LINES: f32 = 3.0;
+const GIT_GUTTER_WIDTH: f32 = 3.0;
+// Files at least this big open read-only, without undo history or syntax highlighting
+const LARGE_FILE_BYTES: u64 = 10 * 1024 * 1024;
+// Leading bytes inspected for NULs when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8192;
+const HEX_PREVIEW_BYTES: usize = 4096;
+// Seconds the mouse must rest on a symbol before its tooltip appears
+const HOVER_DELAY: f64 = 0.5;
+const TOOLTIP_FONT_SIZE: f32 = 12.0;
+const TOOLTIP_LINE_HEIGHT: f32 = 16.0;
+const TOOLTIP_MAX_CHARS: usize = 80;
 
 #[derive(Clone, Copy)]
 struct ScrollbarMetrics {
@@ -73,9 +97,22 @@ struct EditorSnapshot {
 enum PendingCommand {
     Open { buffer: String },
     SaveAs { buffer: String },
+    Commit { buffer: String },
+    ConfirmDiscard { buffer: String },
+    ConfirmEnableEditing { buffer: String },
 }
 
 #[derive(Clone)]
+// The symbol under the mouse; `lines` is filled in once the mouse has rested there for
+// HOVER_DELAY, and is empty when there's nothing worth showing
+struct HoverState {
+    range: (usize, usize),
+    name: String,
+    since: f64,
+    anchor: Vector2,
+    lines: Option<Vec<String>>,
+}
+
 struct KeyRepeatState {
     key: KeyboardKey,
     timer: f32,
@@ -103,6 +140,8 @@ pub struct EditorPane {
     smart_indent: bool,
     preferred_column: Option<usize>,
     pending_command: Option<PendingCommand>,
+    path_completions: Vec<String>,
+    selected_completion: Option<usize>,
     key_repeat_state: Option<KeyRepeatState>,
     scroll_x: f32,
     scroll_y: f32,
@@ -111,6 +150,31 @@ pub struct EditorPane {
     scroll_drag_state: Option<ScrollDragState>,
     syntax_highlighter: SyntaxHighlighter,
     mouse_selection_state: Option<MouseSelectionState>,
+    hover: Option<HoverState>,
+    git_changes: HashMap<usize, LineChange>,
+    git_refresh_pending: bool,
+    vcs_output: Vec<(String, bool)>,
+    reference_results: Option<(String, Vec<ResultItem>)>,
+    // The value of the last successful Ctrl+Enter, which Ctrl+Shift+I sends to the
+    // inspector without evaluating again
+    last_value: Option<Expr>,
+    inspect_request: Option<Expr>,
+    // Bumped on every change to the text so views of it know when to refresh
+    content_version: u64,
+    // From the last profile of the buffer (F5): the content version it ran on and each
+    // line's heat, 0.0 to 1.0 for the hottest form. Any edit hides it.
+    heatmap: Option<(u64, HashMap<usize, f32>)>,
+    // What `zeus check` found in the buffer when it was last opened or saved, and the
+    // content version it checked. Like the heatmap, any edit hides them until the next save.
+    diagnostics: Option<(u64, Vec<Diagnostic>)>,
+    large_file: bool,
+    read_only: bool,
+    // Buffer holds a hex preview of a binary file, which must never be saved back
+    binary_file: bool,
+    // Byte offset of every line start, kept only for large files so drawing can jump
+    // straight to the visible lines
+    line_starts: Vec<usize>,
+    longest_line_chars: usize,
 }
 
 impl EditorPane {
@@ -136,6 +200,8 @@ impl EditorPane {
             smart_indent: true,
             preferred_column: None,
             pending_command: None,
+            path_completions: Vec::new(),
+            selected_completion: None,
             key_repeat_state: None,
             scroll_x: 0.0,
             scroll_y: 0.0,
@@ -144,6 +210,21 @@ impl EditorPane {
             scroll_drag_state: None,
             syntax_highlighter: SyntaxHighlighter::new(),
             mouse_selection_state: None,
+            hover: None,
+            git_changes: HashMap::new(),
+            git_refresh_pending: false,
+            vcs_output: Vec::new(),
+            reference_results: None,
+            last_value: None,
+            inspect_request: None,
+            content_version: 0,
+            heatmap: None,
+            diagnostics: None,
+            large_file: false,
+            read_only: false,
+            binary_file: false,
+            line_starts: Vec::new(),
+            longest_line_chars: 0,
         };
         pane.capture_initial_state();
         pane.syntax_highlighter.reset(&pane.content);
@@ -154,6 +235,152 @@ impl EditorPane {
         self.load_file(path);
     }
 
+    pub fn evaluator_mut(&mut self) -> &mut Evaluator {
+        &mut self.evaluator
+    }
+
+    pub fn current_file(&self) -> Option<&PathBuf> {
+        self.current_file.as_ref()
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn content_version(&self) -> u64 {
+        self.content_version
+    }
+
+    pub fn is_large_file(&self) -> bool {
+        self.large_file
+    }
+
+    // True once after each save or git command so the IDE can refresh git decorations
+    pub fn take_git_refresh_request(&mut self) -> bool {
+        std::mem::take(&mut self.git_refresh_pending)
+    }
+
+    // Output of git commands, as (text, is_error), for the REPL transcript
+    pub fn take_vcs_output(&mut self) -> Vec<(String, bool)> {
+        std::mem::take(&mut self.vcs_output)
+    }
+
+    // The heading and hits of the last find-references, for the results pane
+    pub fn take_reference_results(&mut self) -> Option<(String, Vec<ResultItem>)> {
+        self.reference_results.take()
+    }
+
+    pub fn take_inspect_request(&mut self) -> Option<Expr> {
+        self.inspect_request.take()
+    }
+
+    fn refresh_git_changes(&mut self) {
+        if self.large_file || self.binary_file {
+            return;
+        }
+        self.git_changes = match &self.current_file {
+            Some(path) => git::changed_lines(&fs::canonicalize(path).unwrap_or(path.clone())),
+            None => HashMap::new(),
+        };
+    }
+
+    // Runs `zeus check` on the buffer, with the zeus.toml next to its file if there is one
+    fn refresh_diagnostics(&mut self) {
+        if self.large_file || self.binary_file {
+            self.diagnostics = None;
+            return;
+        }
+        let config = self
+            .current_file
+            .as_deref()
+            .and_then(Path::parent)
+            .and_then(|dir| CheckConfig::load(dir).ok())
+            .unwrap_or_default();
+        let diagnostics = check_source(&self.buffer_name(), &self.content, &config);
+        self.diagnostics = Some((self.content_version, diagnostics));
+    }
+
+    fn current_diagnostics(&self) -> &[Diagnostic] {
+        match &self.diagnostics {
+            Some((version, diagnostics)) if *version == self.content_version => diagnostics,
+            _ => &[],
+        }
+    }
+
+    // " (check: 1 error, 2 warnings)" for the status line, or nothing for a clean buffer
+    fn diagnostic_summary(&self) -> String {
+        let diagnostics = self.current_diagnostics();
+        if diagnostics.is_empty() {
+            return String::new();
+        }
+        let errors = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .count();
+        let plural = |count: usize, what: &str| {
+            format!("{} {}{}", count, what, if count == 1 { "" } else { "s" })
+        };
+        format!(
+            " (check: {}, {})",
+            plural(errors, "error"),
+            plural(diagnostics.len() - errors, "warning")
+        )
+    }
+
+    // The byte ranges of `line` (the 0-based `line_index`) that diagnostics cover, to be
+    // underlined
+    fn diagnostic_ranges(&self, line_index: usize, line: &str) -> Vec<(usize, usize, Severity)> {
+        let byte_at = |column: usize| {
+            line.char_indices()
+                .nth(column)
+                .map_or(line.len(), |(index, _)| index)
+        };
+        let number = line_index + 1;
+        self.current_diagnostics()
+            .iter()
+            .filter(|diagnostic| diagnostic.line <= number && number <= diagnostic.end_line)
+            .map(|diagnostic| {
+                let start = if diagnostic.line == number {
+                    byte_at(diagnostic.column.saturating_sub(1))
+                } else {
+                    0
+                };
+                let end = if diagnostic.end_line == number {
+                    byte_at(diagnostic.end_column.saturating_sub(1))
+                } else {
+                    line.len()
+                };
+                (start, end.max(start), diagnostic.severity)
+            })
+            .collect()
+    }
+
+    // Tooltip lines for the diagnostics whose span holds `position`
+    fn diagnostic_lines(&self, position: usize) -> Vec<String> {
+        let at = (
+            self.line_index_at(position) + 1,
+            self.content[self.line_start(position)..position]
+                .chars()
+                .count()
+                + 1,
+        );
+        self.current_diagnostics()
+            .iter()
+            .filter(|diagnostic| {
+                (diagnostic.line, diagnostic.column) <= at
+                    && at < (diagnostic.end_line, diagnostic.end_column)
+            })
+            .map(|diagnostic| {
+                format!(
+                    "{}[{}]: {}",
+                    diagnostic.severity.as_str(),
+                    diagnostic.rule,
+                    diagnostic.message
+                )
+            })
+            .collect()
+    }
+
     fn capture_initial_state(&mut self) {
         self.undo_stack.clear();
         self.redo_stack.clear();
@@ -170,6 +397,9 @@ impl EditorPane {
 
     fn restore_snapshot(&mut self, snapshot: EditorSnapshot) {
         self.content = snapshot.content;
+        if self.large_file {
+            self.rebuild_line_index();
+        }
         let len = self.content.len();
         self.cursor_position = min(snapshot.cursor, len);
         self.selection = snapshot.selection.and_then(|(start, end)| {
@@ -186,6 +416,23 @@ impl EditorPane {
         self.request_scroll_to_cursor();
     }
 
+    // Called before every edit: refuses edits to read-only buffers and skips the
+    // whole-buffer undo snapshot for large files
+    fn begin_edit(&mut self) -> bool {
+        if self.binary_file {
+            self.show_status_message("Cannot edit binary file");
+            return false;
+        }
+        if self.read_only {
+            self.show_status_message("Read-only large file. Press Ctrl+Shift+R to enable editing");
+            return false;
+        }
+        if !self.large_file {
+            self.push_undo_state();
+        }
+        true
+    }
+
     fn push_undo_state(&mut self) {
         let snapshot = self.snapshot();
         if self
@@ -204,14 +451,114 @@ impl EditorPane {
     }
 
     fn mark_dirty(&mut self) {
-        self.is_dirty = match &self.saved_content {
-            Some(saved) => *saved != self.content,
-            None => !self.content.is_empty(),
-        };
-        self.syntax_highlighter.invalidate();
+        self.content_version += 1;
+        if self.large_file {
+            // Large files keep no saved copy to compare against
+            self.is_dirty = true;
+        } else {
+            self.is_dirty = match &self.saved_content {
+                Some(saved) => *saved != self.content,
+                None => !self.content.is_empty(),
+            };
+            self.syntax_highlighter.invalidate();
+        }
         self.update_title();
     }
 
+    // Replaces content[start..end] with `text`. Every edit goes through here, so a large
+    // file's line index is kept in step without rescanning the buffer.
+    fn splice(&mut self, start: usize, end: usize, text: &str) {
+        self.content.replace_range(start..end, text);
+        if self.large_file {
+            self.shift_line_index(start, end, text);
+        }
+    }
+
+    // Drops the line starts inside the replaced range, adds those in `text` and moves the
+    // rest by the change in length. The longest line only grows here; it's exact again
+    // the next time the index is rebuilt.
+    fn shift_line_index(&mut self, start: usize, end: usize, text: &str) {
+        let first = self.line_starts.partition_point(|&line| line <= start);
+        let last = self.line_starts.partition_point(|&line| line <= end);
+        let added = text
+            .match_indices('\n')
+            .map(|(idx, _)| start + idx + 1)
+            .collect::<Vec<_>>();
+        let added_count = added.len();
+        self.line_starts.splice(first..last, added);
+        for line in &mut self.line_starts[first + added_count..] {
+            *line = *line - end + start + text.len();
+        }
+
+        let first_line = first - 1;
+        let last_line = first_line + added_count;
+        for line in first_line..=last_line {
+            let line_start = self.line_starts[line];
+            let line_end = self
+                .line_starts
+                .get(line + 1)
+                .map_or(self.content.len(), |next| next - 1);
+            let chars = self.content[line_start..line_end].chars().count();
+            self.longest_line_chars = self.longest_line_chars.max(chars);
+        }
+    }
+
+    fn rebuild_line_index(&mut self) {
+        self.line_starts.clear();
+        self.line_starts.push(0);
+        self.longest_line_chars = 0;
+        let mut line_start = 0;
+        for (idx, byte) in self.content.bytes().enumerate() {
+            if byte == b'\n' {
+                let chars = self.content[line_start..idx].chars().count();
+                self.longest_line_chars = self.longest_line_chars.max(chars);
+                line_start = idx + 1;
+                self.line_starts.push(line_start);
+            }
+        }
+        let chars = self.content[line_start..].chars().count();
+        self.longest_line_chars = self.longest_line_chars.max(chars);
+    }
+
+    fn line_count(&self) -> usize {
+        if self.large_file {
+            self.line_starts.len()
+        } else {
+            self.content.split('\n').count()
+        }
+    }
+
+    // Zero-based line containing byte offset `idx`
+    fn line_index_at(&self, idx: usize) -> usize {
+        if self.large_file {
+            self.line_starts
+                .partition_point(|&start| start <= idx)
+                .saturating_sub(1)
+        } else {
+            let line_start = self.line_start(idx);
+            self.content[..line_start]
+                .chars()
+                .filter(|c| *c == '\n')
+                .count()
+        }
+    }
+
+    // Byte offset where line `line_index` starts, clamped to the end of the buffer
+    fn line_start_of(&self, line_index: usize) -> usize {
+        if self.large_file {
+            return self
+                .line_starts
+                .get(line_index)
+                .copied()
+                .unwrap_or(self.content.len());
+        }
+        let mut line_start = 0usize;
+        for line in self.content.split('\n').take(line_index) {
+            line_start += line.len() + 1;
+        }
+        min(line_start, self.content.len())
+    }
+
     fn update_title(&mut self) {
         let mut title = if let Some(path) = &self.current_file {
             match path.file_name().and_then(|n| n.to_str()) {
@@ -225,6 +572,9 @@ impl EditorPane {
         if self.is_dirty {
             title.push('*');
         }
+        if self.read_only {
+            title.push_str(" [read-only]");
+        }
         self.title = title;
     }
 
@@ -290,7 +640,7 @@ impl EditorPane {
         if let Some((start, end)) = self.selection_range() {
             if start < end {
                 let removed = self.content[start..end].to_string();
-                self.content.replace_range(start..end, "");
+                self.splice(start, end, "");
                 self.cursor_position = start;
                 self.clear_selection();
                 self.request_scroll_to_cursor();
@@ -401,22 +751,13 @@ impl EditorPane {
             line_index = 0;
         }
 
-        let lines: Vec<&str> = self.content.split('\n').collect();
-        if lines.is_empty() {
-            return 0;
-        }
-
-        let max_line_index = lines.len() as isize - 1;
+        let max_line_index = self.line_count() as isize - 1;
         if line_index > max_line_index {
             line_index = max_line_index;
         }
 
-        let line_index = line_index as usize;
-        let mut line_start = 0usize;
-        for line in lines.iter().take(line_index) {
-            line_start += line.len() + 1;
-        }
-        let line_text = lines[line_index];
+        let line_start = self.line_start_of(line_index as usize);
+        let line_text = &self.content[line_start..self.line_end(line_start)];
         let line_char_count = line_text.chars().count();
 
         let mut column = (local_x / char_width).round() as isize;
@@ -528,18 +869,21 @@ impl EditorPane {
     }
 
     fn insert_char(&mut self, ch: char) {
-        self.push_undo_state();
+        if !self.begin_edit() {
+            return;
+        }
         self.delete_selection_internal();
         let insert_at = self.cursor_position;
-        self.content.insert(insert_at, ch);
+        self.splice(insert_at, insert_at, ch.encode_utf8(&mut [0; 4]));
         self.cursor_position = insert_at + ch.len_utf8();
 
+        let at = self.cursor_position;
         if ch == '(' {
-            self.content.insert(self.cursor_position, ')');
+            self.splice(at, at, ")");
         } else if ch == '[' {
-            self.content.insert(self.cursor_position, ']');
+            self.splice(at, at, "]");
         } else if ch == '"' && !self.in_string() {
-            self.content.insert(self.cursor_position, '"');
+            self.splice(at, at, "\"");
         }
 
         self.clear_selection();
@@ -552,9 +896,11 @@ impl EditorPane {
         if text.is_empty() {
             return;
         }
-        self.push_undo_state();
+        if !self.begin_edit() {
+            return;
+        }
         self.delete_selection_internal();
-        self.content.insert_str(self.cursor_position, text);
+        self.splice(self.cursor_position, self.cursor_position, text);
         self.cursor_position += text.len();
         self.clear_selection();
         self.preferred_column = None;
@@ -563,13 +909,15 @@ impl EditorPane {
     }
 
     fn insert_newline(&mut self) {
-        self.push_undo_state();
+        if !self.begin_edit() {
+            return;
+        }
         self.delete_selection_internal();
         let indent = self.indent_for_newline();
-        self.content.insert(self.cursor_position, '\n');
+        self.splice(self.cursor_position, self.cursor_position, "\n");
         self.cursor_position += 1;
         if !indent.is_empty() {
-            self.content.insert_str(self.cursor_position, &indent);
+            self.splice(self.cursor_position, self.cursor_position, &indent);
             self.cursor_position += indent.len();
         }
         self.clear_selection();
@@ -649,10 +997,12 @@ impl EditorPane {
             line_start = line_end + 1;
         }
 
-        self.push_undo_state();
+        if !self.begin_edit() {
+            return;
+        }
 
         for &insert_idx in indices.iter().rev() {
-            self.content.insert_str(insert_idx, &indent);
+            self.splice(insert_idx, insert_idx, &indent);
         }
 
         let indent_len = indent.len();
@@ -712,7 +1062,9 @@ impl EditorPane {
             line_start = line_end + 1;
         }
 
-        self.push_undo_state();
+        if !self.begin_edit() {
+            return;
+        }
 
         let mut total_shift = 0usize;
         let mut new_start = start;
@@ -725,7 +1077,7 @@ impl EditorPane {
             if removal == 0 {
                 continue;
             }
-            self.content.drain(adjusted_idx..adjusted_idx + removal);
+            self.splice(adjusted_idx, adjusted_idx + removal, "");
             total_shift += removal;
 
             if had_selection {
@@ -805,7 +1157,9 @@ impl EditorPane {
 
     fn delete_backward(&mut self) {
         if self.has_selection() {
-            self.push_undo_state();
+            if !self.begin_edit() {
+                return;
+            }
             self.delete_selection_internal();
             self.mark_dirty();
             return;
@@ -814,8 +1168,10 @@ impl EditorPane {
             return;
         }
         let prev = self.prev_char_boundary(self.cursor_position);
-        self.push_undo_state();
-        self.content.drain(prev..self.cursor_position);
+        if !self.begin_edit() {
+            return;
+        }
+        self.splice(prev, self.cursor_position, "");
         self.cursor_position = prev;
         self.preferred_column = None;
         self.mark_dirty();
@@ -824,7 +1180,9 @@ impl EditorPane {
 
     fn delete_forward(&mut self) {
         if self.has_selection() {
-            self.push_undo_state();
+            if !self.begin_edit() {
+                return;
+            }
             self.delete_selection_internal();
             self.mark_dirty();
             return;
@@ -833,8 +1191,10 @@ impl EditorPane {
             return;
         }
         let next = self.next_char_boundary(self.cursor_position);
-        self.push_undo_state();
-        self.content.drain(self.cursor_position..next);
+        if !self.begin_edit() {
+            return;
+        }
+        self.splice(self.cursor_position, next, "");
         self.preferred_column = None;
         self.mark_dirty();
         self.request_scroll_to_cursor();
@@ -859,8 +1219,10 @@ impl EditorPane {
                     self.show_status_message(format!("Clipboard error: {}", err));
                     return;
                 }
-                self.push_undo_state();
-                self.content.replace_range(start..end, "");
+                if !self.begin_edit() {
+                    return;
+                }
+                self.splice(start, end, "");
                 self.cursor_position = start;
                 self.clear_selection();
                 self.preferred_column = None;
@@ -870,10 +1232,11 @@ impl EditorPane {
         }
     }
 
+    // A value copied from the REPL or inspector pastes as its readable source
     fn paste_from_clipboard(&mut self, rl: &mut RaylibHandle) {
         if let Ok(text) = rl.get_clipboard_text() {
             if !text.is_empty() {
-                self.insert_text(&text);
+                self.insert_text(clipboard::paste_text(&text));
             }
         }
     }
@@ -921,11 +1284,15 @@ impl EditorPane {
         match self.pending_command.as_mut() {
             Some(PendingCommand::Open { buffer }) => Some(buffer),
             Some(PendingCommand::SaveAs { buffer }) => Some(buffer),
+            Some(PendingCommand::Commit { buffer }) => Some(buffer),
+            Some(PendingCommand::ConfirmDiscard { buffer }) => Some(buffer),
+            Some(PendingCommand::ConfirmEnableEditing { buffer }) => Some(buffer),
             None => None,
         }
     }
 
     fn update_command_status(&mut self) {
+        self.refresh_path_completions();
         if let Some(command) = &self.pending_command {
             match command {
                 PendingCommand::Open { buffer } => {
@@ -934,11 +1301,86 @@ impl EditorPane {
                 PendingCommand::SaveAs { buffer } => {
                     self.show_status_message(format!("Save as: {}", buffer));
                 }
+                PendingCommand::Commit { buffer } => {
+                    self.show_status_message(format!("Commit message: {}", buffer));
+                }
+                PendingCommand::ConfirmDiscard { buffer } => {
+                    let name = self
+                        .current_file
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default();
+                    self.show_status_message(format!(
+                        "Discard all changes to {}? (y/n): {}",
+                        name, buffer
+                    ));
+                }
+                PendingCommand::ConfirmEnableEditing { buffer } => {
+                    self.show_status_message(format!(
+                        "Edit this large file? Undo will be unavailable (y/n): {}",
+                        buffer
+                    ));
+                }
+            }
+        }
+    }
+
+    fn refresh_path_completions(&mut self) {
+        self.path_completions = match &self.pending_command {
+            Some(PendingCommand::Open { buffer }) | Some(PendingCommand::SaveAs { buffer }) => {
+                complete_path(buffer)
+            }
+            _ => Vec::new(),
+        };
+        self.selected_completion = None;
+    }
+
+    // Tab: take the only match, else extend to the shared prefix, else cycle through matches
+    fn complete_pending_path(&mut self) {
+        if self.path_completions.is_empty() {
+            return;
+        }
+        if self.path_completions.len() == 1 {
+            self.accept_path_completion(0);
+            return;
+        }
+        let prefix = common_prefix(&self.path_completions);
+        let current_len = self.pending_buffer_mut().map(|b| b.len()).unwrap_or(0);
+        if self.selected_completion.is_none() && prefix.len() > current_len {
+            if let Some(buffer) = self.pending_buffer_mut() {
+                *buffer = prefix;
             }
+            self.update_command_status();
+        } else {
+            self.move_completion_selection(1);
+        }
+    }
+
+    fn move_completion_selection(&mut self, delta: isize) {
+        let count = self.path_completions.len() as isize;
+        if count == 0 {
+            return;
+        }
+        let next = match self.selected_completion {
+            Some(index) => (index as isize + delta).rem_euclid(count),
+            None if delta < 0 => count - 1,
+            None => 0,
+        };
+        self.selected_completion = Some(next as usize);
+    }
+
+    fn accept_path_completion(&mut self, index: usize) {
+        if let Some(candidate) = self.path_completions.get(index).cloned() {
+            if let Some(buffer) = self.pending_buffer_mut() {
+                *buffer = candidate;
+            }
+            self.update_command_status();
         }
     }
 
     fn cancel_pending_command(&mut self) {
+        self.path_completions.clear();
+        self.selected_completion = None;
         if self.pending_command.is_some() {
             self.pending_command = None;
             self.show_status_message("Command cancelled");
@@ -946,6 +1388,8 @@ impl EditorPane {
     }
 
     fn execute_pending_command(&mut self) {
+        self.path_completions.clear();
+        self.selected_completion = None;
         if let Some(command) = self.pending_command.take() {
             match command {
                 PendingCommand::Open { buffer } => {
@@ -964,6 +1408,40 @@ impl EditorPane {
                         self.write_to_path(PathBuf::from(trimmed));
                     }
                 }
+                PendingCommand::Commit { buffer } => {
+                    let message = buffer.trim();
+                    if message.is_empty() {
+                        self.show_status_message("Commit cancelled: empty message");
+                    } else {
+                        let dir = self.vcs_directory();
+                        let result = git::commit(&dir, message);
+                        self.report_vcs_result("git commit", result);
+                    }
+                }
+                PendingCommand::ConfirmDiscard { buffer } => {
+                    let answer = buffer.trim().to_lowercase();
+                    match self.current_file.clone() {
+                        Some(path) if answer == "y" || answer == "yes" => {
+                            let result = git::discard(&path);
+                            let succeeded = result.is_ok();
+                            self.report_vcs_result("git checkout", result);
+                            if succeeded {
+                                self.load_file(path);
+                            }
+                        }
+                        _ => self.show_status_message("Discard cancelled"),
+                    }
+                }
+                PendingCommand::ConfirmEnableEditing { buffer } => {
+                    let answer = buffer.trim().to_lowercase();
+                    if answer == "y" || answer == "yes" {
+                        self.read_only = false;
+                        self.update_title();
+                        self.show_status_message("Editing enabled (no undo for large files)");
+                    } else {
+                        self.show_status_message("File stays read-only");
+                    }
+                }
             }
         }
     }
@@ -987,10 +1465,37 @@ impl EditorPane {
         }
 
         if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            // Enter on a highlighted directory descends into it instead of running the command
+            if let Some(index) = self.selected_completion {
+                self.accept_path_completion(index);
+                if self.path_completions.len() != 1
+                    || self
+                        .path_completions
+                        .first()
+                        .is_some_and(|c| c.ends_with('/'))
+                {
+                    return true;
+                }
+            }
             self.execute_pending_command();
             return true;
         }
 
+        if self.key_triggered(rl, KeyboardKey::KEY_TAB) {
+            self.complete_pending_path();
+            return true;
+        }
+
+        if self.key_triggered(rl, KeyboardKey::KEY_DOWN) {
+            self.move_completion_selection(1);
+            return true;
+        }
+
+        if self.key_triggered(rl, KeyboardKey::KEY_UP) {
+            self.move_completion_selection(-1);
+            return true;
+        }
+
         if self.key_triggered(rl, KeyboardKey::KEY_BACKSPACE) {
             if let Some(buffer) = self.pending_buffer_mut() {
                 buffer.pop();
@@ -1027,21 +1532,69 @@ impl EditorPane {
     }
 
     fn load_file(&mut self, path: PathBuf) {
+        let head = match read_head(&path, BINARY_SNIFF_BYTES) {
+            Ok(head) => head,
+            Err(err) => {
+                self.show_status_message(format!("Failed to open file: {}", err));
+                return;
+            }
+        };
+        if looks_binary(&head) {
+            self.show_binary_preview(path, &head);
+            return;
+        }
+
+        let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        let large_file = size >= LARGE_FILE_BYTES;
         match fs::read_to_string(&path) {
             Ok(contents) => {
                 self.content = contents;
-                self.cursor_position = self.content.len();
+                self.content_version += 1;
+                self.binary_file = false;
+                self.large_file = large_file;
+                self.read_only = large_file;
+                self.cursor_position = if large_file { 0 } else { self.content.len() };
                 self.clear_selection();
                 self.scroll_x = 0.0;
                 self.scroll_y = 0.0;
                 self.request_scroll_to_cursor();
                 self.current_file = Some(path.clone());
-                self.saved_content = Some(self.content.clone());
                 self.is_dirty = false;
-                self.capture_initial_state();
-                self.syntax_highlighter.reset(&self.content);
+                if large_file {
+                    // No saved copy, undo snapshots or highlighting: each would
+                    // duplicate or rescan the whole buffer
+                    self.saved_content = None;
+                    self.undo_stack.clear();
+                    self.redo_stack.clear();
+                    self.syntax_highlighter.reset("");
+                    self.rebuild_line_index();
+                    self.git_changes.clear();
+                } else {
+                    self.saved_content = Some(self.content.clone());
+                    self.capture_initial_state();
+                    self.syntax_highlighter.reset(&self.content);
+                    self.line_starts.clear();
+                    self.refresh_git_changes();
+                }
+                self.refresh_diagnostics();
                 self.update_title();
-                self.show_status_message(format!("Opened {}", path.display()));
+                if large_file {
+                    self.show_status_message(format!(
+                        "Opened {} read-only ({:.1} MB). Press Ctrl+Shift+R to enable editing",
+                        path.display(),
+                        size as f64 / (1024.0 * 1024.0)
+                    ));
+                } else {
+                    self.show_status_message(format!(
+                        "Opened {}{}",
+                        path.display(),
+                        self.diagnostic_summary()
+                    ));
+                }
+            }
+            // Not valid UTF-8 somewhere past the sniffed prefix
+            Err(err) if err.kind() == ErrorKind::InvalidData => {
+                self.show_binary_preview(path, &head);
             }
             Err(err) => {
                 self.show_status_message(format!("Failed to open file: {}", err));
@@ -1049,6 +1602,43 @@ impl EditorPane {
         }
     }
 
+    fn show_binary_preview(&mut self, path: PathBuf, head: &[u8]) {
+        let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        let shown = &head[..head.len().min(HEX_PREVIEW_BYTES)];
+        let mut preview = format!(
+            ";; {} is a binary file ({} bytes) and cannot be edited\n\n{}",
+            path.display(),
+            size,
+            hex_dump(shown)
+        );
+        if (shown.len() as u64) < size {
+            preview.push_str(&format!(";; ... {} more bytes", size - shown.len() as u64));
+        }
+
+        self.content = preview;
+        self.content_version += 1;
+        self.binary_file = true;
+        self.large_file = false;
+        self.read_only = true;
+        self.cursor_position = 0;
+        self.clear_selection();
+        self.scroll_x = 0.0;
+        self.scroll_y = 0.0;
+        self.request_scroll_to_cursor();
+        self.current_file = Some(path.clone());
+        self.saved_content = Some(self.content.clone());
+        self.is_dirty = false;
+        self.capture_initial_state();
+        self.syntax_highlighter.reset("");
+        self.line_starts.clear();
+        self.git_changes.clear();
+        self.update_title();
+        self.show_status_message(format!(
+            "Binary file {} opened as a hex preview",
+            path.display()
+        ));
+    }
+
     fn save_file(&mut self) {
         if let Some(path) = self.current_file.clone() {
             self.write_to_path(path);
@@ -1058,15 +1648,479 @@ impl EditorPane {
     }
 
     fn write_to_path(&mut self, path: PathBuf) {
+        if self.binary_file {
+            self.show_status_message("Cannot save binary file preview");
+            return;
+        }
         if let Err(err) = fs::write(&path, &self.content) {
             self.show_status_message(format!("Failed to save file: {}", err));
             return;
         }
         self.current_file = Some(path.clone());
-        self.saved_content = Some(self.content.clone());
+        if !self.large_file {
+            self.saved_content = Some(self.content.clone());
+        }
         self.is_dirty = false;
+        self.git_refresh_pending = true;
+        self.refresh_git_changes();
+        self.refresh_diagnostics();
         self.update_title();
-        self.show_status_message(format!("Saved {}", path.display()));
+        self.show_status_message(format!(
+            "Saved {}{}",
+            path.display(),
+            self.diagnostic_summary()
+        ));
+    }
+
+    fn vcs_directory(&self) -> PathBuf {
+        self.current_file
+            .as_ref()
+            .and_then(|p| p.parent())
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    fn stage_current_file(&mut self) {
+        let Some(path) = self.current_file.clone() else {
+            self.show_status_message("Stage failed: buffer has no file");
+            return;
+        };
+        if self.is_dirty {
+            self.save_file();
+        }
+        let result = git::stage(&path).map(|output| {
+            if output.is_empty() {
+                format!("Staged {}", path.display())
+            } else {
+                output
+            }
+        });
+        self.report_vcs_result("git add", result);
+    }
+
+    fn begin_commit_command(&mut self) {
+        self.pending_command = Some(PendingCommand::Commit {
+            buffer: String::new(),
+        });
+        self.update_command_status();
+    }
+
+    fn begin_discard_command(&mut self) {
+        if self.current_file.is_none() {
+            self.show_status_message("Discard failed: buffer has no file");
+            return;
+        }
+        self.pending_command = Some(PendingCommand::ConfirmDiscard {
+            buffer: String::new(),
+        });
+        self.update_command_status();
+    }
+
+    fn report_vcs_result(&mut self, command: &str, result: Result<String, String>) {
+        self.vcs_output.push((format!("$ {}", command), false));
+        match result {
+            Ok(output) => {
+                let summary = output.lines().next().unwrap_or(command).to_string();
+                for line in output.lines() {
+                    self.vcs_output.push((line.to_string(), false));
+                }
+                self.show_status_message(summary);
+            }
+            Err(error) => {
+                for line in error.lines() {
+                    self.vcs_output.push((line.to_string(), true));
+                }
+                self.show_status_message(format!("Error: {}", error.lines().next().unwrap_or("")));
+            }
+        }
+        self.refresh_git_changes();
+        self.git_refresh_pending = true;
+    }
+
+    // Dropdown of path matches, drawn upward from the status bar
+    // Byte range of the symbol drawn under `point`, if any
+    fn symbol_at_point(&self, layout: &EditorViewLayout, point: Vector2) -> Option<(usize, usize)> {
+        let local_x = point.x - layout.text_rect.x + self.scroll_x;
+        let local_y = point.y - layout.text_rect.y + self.scroll_y;
+        if !(local_x >= 0.0 && local_y >= 0.0) {
+            return None;
+        }
+        let line_index = (local_y / LINE_HEIGHT).floor() as usize;
+        if line_index >= self.line_count() {
+            return None;
+        }
+        let line_start = self.line_start_of(line_index);
+        let line = &self.content[line_start..self.line_end(line_start)];
+        let column = (local_x / layout.char_width.max(1.0)).floor() as usize;
+        if column >= line.chars().count() {
+            return None;
+        }
+        let offset = self.index_for_column(line_start, column) - line_start;
+        self.syntax_highlighter
+            .symbol_at(line_index, line, offset)
+            .map(|(start, end)| (line_start + start, line_start + end))
+    }
+
+    fn update_hover(&mut self, rl: &RaylibHandle, layout: &EditorViewLayout) {
+        let mouse = rl.get_mouse_position();
+        let target = if self.large_file
+            || self.pending_command.is_some()
+            || rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
+            || !rect_contains(layout.text_rect, mouse)
+        {
+            None
+        } else {
+            self.symbol_at_point(layout, mouse)
+        };
+        let Some(range) = target else {
+            self.hover = None;
+            return;
+        };
+
+        let name = &self.content[range.0..range.1];
+        let now = rl.get_time();
+        match &self.hover {
+            Some(hover) if hover.range == range && hover.name == name => {
+                if hover.lines.is_none() && now - hover.since >= HOVER_DELAY {
+                    let mut lines = self.diagnostic_lines(hover.range.0);
+                    lines.extend(self.tooltip_lines(&hover.name));
+                    if let Some(hover) = &mut self.hover {
+                        hover.lines = Some(lines);
+                    }
+                }
+            }
+            _ => {
+                self.hover = Some(HoverState {
+                    range,
+                    name: name.to_string(),
+                    since: now,
+                    anchor: mouse,
+                    lines: None,
+                })
+            }
+        }
+    }
+
+    // Kind and signature, docstring, value and where the buffer defines it, looked up in
+    // the environment this editor evaluates in
+    fn tooltip_lines(&self, name: &str) -> Vec<String> {
+        let file = self
+            .current_file
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or("untitled")
+            .to_string();
+        let index = XrefIndex::build(&[(file, self.content.clone())]);
+        let info = describe_symbol(&self.evaluator.environment, name, &index.definitions);
+        if info.kind == SymbolKind::Unbound && info.definition.is_none() {
+            return Vec::new();
+        }
+
+        let heading = info.signature.as_deref().unwrap_or(name);
+        let mut lines = vec![format!("{}  [{}]", heading, info.kind.label())];
+        if let Some(doc) = &info.doc {
+            lines.extend(doc.lines().map(str::to_string));
+        }
+        if let Some(value) = &info.value {
+            lines.push(format!("= {}", value));
+        }
+        if let Some(location) = &info.definition {
+            lines.push(format!(
+                "defined at {}:{}:{}",
+                location.file, location.line, location.column
+            ));
+        }
+        lines
+            .into_iter()
+            .map(|line| match line.char_indices().nth(TOOLTIP_MAX_CHARS) {
+                Some((cut, _)) => format!("{}...", &line[..cut]),
+                None => line,
+            })
+            .collect()
+    }
+
+    // Byte range of the symbol under the cursor, or just before it at the end of a name
+    fn symbol_at_cursor(&self) -> Option<(usize, usize)> {
+        if self.large_file {
+            return None;
+        }
+        let line_start = self.line_start(self.cursor_position);
+        let line = &self.content[line_start..self.line_end(line_start)];
+        let line_index = self.line_index_at(self.cursor_position);
+        let offset = self.cursor_position - line_start;
+        self.syntax_highlighter
+            .symbol_at(line_index, line, offset)
+            .or_else(|| {
+                let before = self.prev_char_boundary(self.cursor_position);
+                (before < self.cursor_position && before >= line_start)
+                    .then(|| {
+                        self.syntax_highlighter
+                            .symbol_at(line_index, line, before - line_start)
+                    })
+                    .flatten()
+            })
+            .map(|(start, end)| (line_start + start, line_start + end))
+    }
+
+    // How results name this buffer: its path, or "untitled" before the first save
+    pub fn buffer_name(&self) -> String {
+        self.current_file
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "untitled".to_string())
+    }
+
+    // The buffer as it stands, then the other .lisp files next to it as saved on disk
+    fn project_sources(&self) -> Vec<(String, String)> {
+        let mut sources = vec![(self.buffer_name(), self.content.clone())];
+        let Some(dir) = self.current_file.as_deref().and_then(Path::parent) else {
+            return sources;
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let current = self
+            .current_file
+            .as_ref()
+            .and_then(|path| fs::canonicalize(path).ok());
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "lisp"))
+            .filter(|path| fs::canonicalize(path).ok() != current)
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Ok(source) = fs::read_to_string(&path) {
+                sources.push((path.display().to_string(), source));
+            }
+        }
+        sources
+    }
+
+    // F12: the buffer's own definition wins over one in a neighbouring file
+    fn go_to_definition(&mut self) {
+        let Some((start, end)) = self.symbol_at_cursor() else {
+            self.show_status_message("No symbol at the cursor");
+            return;
+        };
+        let name = self.content[start..end].to_string();
+        let buffer = self.buffer_name();
+        let index = XrefIndex::build(&self.project_sources());
+        let definition = index
+            .definitions
+            .iter()
+            .rev()
+            .filter(|definition| definition.name == name)
+            .min_by_key(|definition| definition.location.file != buffer)
+            .map(|definition| definition.location.clone());
+        match definition {
+            Some(location) => self.open_location(&location),
+            None => {
+                let kind = describe_symbol(&self.evaluator.environment, &name, &[]).kind;
+                self.show_status_message(match kind {
+                    SymbolKind::Unbound => format!("No definition found for {}", name),
+                    kind => format!("{} is a {} with no source here", name, kind.label()),
+                });
+            }
+        }
+    }
+
+    // Shift+F12: every use of the symbol at the cursor in the buffer and its neighbours
+    fn find_references(&mut self) {
+        let Some((start, end)) = self.symbol_at_cursor() else {
+            self.show_status_message("No symbol at the cursor");
+            return;
+        };
+        let name = self.content[start..end].to_string();
+        let mut items = Vec::new();
+        for (file, source) in self.project_sources() {
+            let lines: Vec<&str> = source.split('\n').collect();
+            for location in find_references(&file, &source, &name) {
+                let text = lines.get(location.line - 1).copied().unwrap_or("");
+                items.push(ResultItem {
+                    location,
+                    text: text.to_string(),
+                });
+            }
+        }
+        let files = items
+            .iter()
+            .map(|item| item.location.file.as_str())
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        self.show_status_message(format!(
+            "{} references to {} in {} files",
+            items.len(),
+            name,
+            files
+        ));
+        self.reference_results = Some((format!("{} ({})", name, items.len()), items));
+    }
+
+    // Moves the cursor to a 1-based line and column, opening the file first if it isn't
+    // the one in the buffer. Unsaved changes are never dropped to get there.
+    pub fn open_location(&mut self, location: &Location) {
+        if location.file != self.buffer_name() {
+            if self.is_dirty {
+                self.show_status_message(format!(
+                    "Save or revert this buffer before opening {}",
+                    location.file
+                ));
+                return;
+            }
+            self.load_file(PathBuf::from(&location.file));
+            if self.buffer_name() != location.file {
+                return;
+            }
+        }
+        let line_start = self.line_start_of(location.line.saturating_sub(1));
+        let position = self.index_for_column(line_start, location.column.saturating_sub(1));
+        self.move_cursor_to(position, false);
+        self.show_status_message(format!(
+            "{}:{}:{}",
+            location.file, location.line, location.column
+        ));
+    }
+
+    fn draw_hover(
+        &self,
+        d: &mut RaylibDrawHandle,
+        bounds: Rectangle,
+        theme: &Theme,
+        fonts: &IdeFonts,
+    ) {
+        let Some(hover) = &self.hover else {
+            return;
+        };
+        let Some(lines) = hover.lines.as_ref().filter(|lines| !lines.is_empty()) else {
+            return;
+        };
+
+        let text_width = lines
+            .iter()
+            .map(|line| fonts.measure_text(line, TOOLTIP_FONT_SIZE).x)
+            .fold(0.0, f32::max);
+        let width = (text_width + H_PADDING * 2.0).min(bounds.width);
+        let height = lines.len() as f32 * TOOLTIP_LINE_HEIGHT + V_PADDING * 2.0;
+        // Below the mouse, or above it when that would run off the pane
+        let below = hover.anchor.y + LINE_HEIGHT;
+        let y = if below + height <= bounds.y + bounds.height {
+            below
+        } else {
+            (hover.anchor.y - height - V_PADDING).max(bounds.y)
+        };
+        let x = hover
+            .anchor
+            .x
+            .min(bounds.x + bounds.width - width)
+            .max(bounds.x);
+        let rect = Rectangle {
+            x,
+            y,
+            width,
+            height,
+        };
+        d.draw_rectangle_rec(rect, theme.panel);
+        d.draw_rectangle_lines_ex(rect, 1.0, theme.border);
+
+        for (row, line) in lines.iter().enumerate() {
+            let color = if row == 0 { theme.text } else { theme.text_dim };
+            fonts.draw_text(
+                d,
+                line,
+                Vector2::new(
+                    rect.x + H_PADDING,
+                    rect.y + V_PADDING + row as f32 * TOOLTIP_LINE_HEIGHT,
+                ),
+                TOOLTIP_FONT_SIZE,
+                color,
+            );
+        }
+    }
+
+    fn draw_path_completions(
+        &self,
+        d: &mut RaylibDrawHandle,
+        status_rect: Rectangle,
+        theme: &Theme,
+        fonts: &IdeFonts,
+    ) {
+        if self.path_completions.is_empty() {
+            return;
+        }
+
+        let total = self.path_completions.len();
+        let visible = total.min(MAX_COMPLETION_ROWS);
+        let selected = self.selected_completion.unwrap_or(0);
+        let first = (selected + 1).saturating_sub(visible).min(total - visible);
+        let dir_len = self.path_completions[0]
+            .trim_end_matches('/')
+            .rfind('/')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+
+        let height = visible as f32 * LINE_HEIGHT + if total > visible { LINE_HEIGHT } else { 0.0 };
+        let rect = Rectangle {
+            x: status_rect.x + H_PADDING,
+            y: status_rect.y - height,
+            width: (status_rect.width * 0.6)
+                .max(200.0)
+                .min(status_rect.width - H_PADDING * 2.0),
+            height,
+        };
+        d.draw_rectangle_rec(rect, theme.panel);
+        d.draw_rectangle_lines_ex(rect, 1.0, theme.border);
+
+        for (row, candidate) in self.path_completions[first..first + visible]
+            .iter()
+            .enumerate()
+        {
+            let y = rect.y + row as f32 * LINE_HEIGHT;
+            if self.selected_completion == Some(first + row) {
+                d.draw_rectangle_rec(
+                    Rectangle {
+                        x: rect.x + 1.0,
+                        y,
+                        width: rect.width - 2.0,
+                        height: LINE_HEIGHT,
+                    },
+                    theme.selection,
+                );
+            }
+            let label = candidate.get(dir_len..).unwrap_or(candidate);
+            let color = if candidate.ends_with('/') {
+                theme.info
+            } else {
+                theme.text
+            };
+            fonts.draw_text(
+                d,
+                label,
+                Vector2::new(rect.x + H_PADDING, y + 3.0),
+                12.0,
+                color,
+            );
+        }
+
+        if total > visible {
+            fonts.draw_text(
+                d,
+                &format!("{} more...", total - visible),
+                Vector2::new(
+                    rect.x + H_PADDING,
+                    rect.y + visible as f32 * LINE_HEIGHT + 3.0,
+                ),
+                12.0,
+                theme.text_dim,
+            );
+        }
     }
 
     fn show_status_message<S: Into<String>>(&mut self, message: S) {
@@ -1091,6 +2145,7 @@ impl EditorPane {
         in_string
     }
 
+    // Evaluates the selection, or else the buffer, showing the result in the status line
     fn evaluate_expression(&mut self) {
         let expr = if let Some((start, end)) = self.selection_range() {
             self.content[start..end].to_string()
@@ -1104,8 +2159,9 @@ impl EditorPane {
 
         match self.evaluator.eval_str(&expr) {
             Ok(result) => {
-                let formatted = self.format_expr(&result);
+                let formatted = write_string(&result);
                 self.show_status_message(format!("=> {}", formatted));
+                self.last_value = Some(result);
             }
             Err(error) => {
                 self.show_status_message(format!("Error: {}", error));
@@ -1113,31 +2169,43 @@ impl EditorPane {
         }
     }
 
-    fn format_expr(&self, expr: &crate::interpreter::types::Expr) -> String {
-        use crate::interpreter::types::{Expr, SymbolData};
+    fn inspect_last_value(&mut self) {
+        match &self.last_value {
+            Some(value) => self.inspect_request = Some(value.clone()),
+            None => self.show_status_message("Nothing to inspect; evaluate with Ctrl+Enter first"),
+        }
+    }
 
-        match expr {
-            Expr::Integer(n) => format!("{}", n),
-            Expr::Float(f) => format!("{}", f),
-            Expr::Rational {
-                numerator,
-                denominator,
-            } => format!("{}/{}", numerator, denominator),
-            Expr::Symbol(sym_data) => match sym_data {
-                SymbolData::Keyword(name) => format!(":{}", name),
-                SymbolData::Uninterned(name, id) => format!("#:{}#{}", name, id),
-                SymbolData::Interned(name) => name.clone(),
-            },
-            Expr::String(s) => format!("\"{}\"", s),
-            Expr::List(list) => {
-                if list.is_empty() {
-                    "()".to_string()
-                } else {
-                    let items: Vec<String> = list.iter().map(|e| self.format_expr(e)).collect();
-                    format!("({})", items.join(" "))
-                }
+    // Evaluates the buffer form by form, timing each, and shades the lines of every form
+    // by its share of the time
+    fn profile_buffer(&mut self) {
+        let profile = profile_program(&mut self.evaluator, &self.content);
+        let heat = profile
+            .line_heat()
+            .into_iter()
+            .map(|(line, heat)| (line - 1, heat as f32))
+            .collect();
+        self.heatmap = Some((self.content_version, heat));
+        let summary = match (&profile.error, profile.hottest()) {
+            (Some((line, error)), _) => format!("Profile stopped at line {}: {}", line, error),
+            (None, Some(hottest)) => format!(
+                "Profiled {} forms in {:.1} ms; hottest: line {} ({:.0}%)",
+                profile.forms.len(),
+                profile.total().as_secs_f64() * 1000.0,
+                hottest.line,
+                profile.share(hottest) * 100.0
+            ),
+            (None, None) => "Nothing to profile".to_string(),
+        };
+        self.show_status_message(summary);
+    }
+
+    fn line_heat(&self, line_index: usize) -> Option<f32> {
+        match &self.heatmap {
+            Some((version, heat)) if *version == self.content_version => {
+                heat.get(&line_index).copied()
             }
-            _ => format!("{:?}", expr),
+            _ => None,
         }
     }
 
@@ -1483,11 +2551,7 @@ impl EditorPane {
         if layout.text_rect.height <= 0.0 {
             self.scroll_y = 0.0;
         } else {
-            let cursor_line_start = self.line_start(self.cursor_position);
-            let cursor_line_index = self.content[..cursor_line_start]
-                .chars()
-                .filter(|c| *c == '\n')
-                .count();
+            let cursor_line_index = self.line_index_at(self.cursor_position);
             let cursor_y = cursor_line_index as f32 * LINE_HEIGHT;
 
             if cursor_y < self.scroll_y {
@@ -1594,11 +2658,17 @@ impl Pane for EditorPane {
         let char_width = fonts.measure_text("M", CONTENT_FONT_SIZE).x.max(1.0);
         let mut line_count = 0usize;
         let mut max_line_width = 0.0f32;
-        for line in self.content.split('\n') {
-            line_count += 1;
-            let width = fonts.measure_text(line, CONTENT_FONT_SIZE).x;
-            if width > max_line_width {
-                max_line_width = width;
+        if self.large_file {
+            // Measuring every line each frame is too slow; approximate with the widest glyph
+            line_count = self.line_starts.len();
+            max_line_width = self.longest_line_chars as f32 * char_width;
+        } else {
+            for line in self.content.split('\n') {
+                line_count += 1;
+                let width = fonts.measure_text(line, CONTENT_FONT_SIZE).x;
+                if width > max_line_width {
+                    max_line_width = width;
+                }
             }
         }
         if line_count == 0 {
@@ -1606,7 +2676,9 @@ impl Pane for EditorPane {
         }
         let total_content_height = line_count as f32 * LINE_HEIGHT;
 
-        self.syntax_highlighter.ensure(&self.content);
+        if !self.large_file {
+            self.syntax_highlighter.ensure(&self.content);
+        }
 
         let mut layout =
             self.compute_layout(bounds, total_content_height, max_line_width, char_width);
@@ -1620,6 +2692,7 @@ impl Pane for EditorPane {
         self.sync_drag_with_layout(&layout);
 
         let selection = self.selection_range();
+        let mut gutter_marks = Vec::new();
 
         if layout.text_rect.width > 0.5 && layout.text_rect.height > 0.5 {
             let mut scissor = d.begin_scissor_mode(
@@ -1631,24 +2704,34 @@ impl Pane for EditorPane {
 
             let first_visible_line = (self.scroll_y / LINE_HEIGHT).floor().max(0.0) as usize;
             let offset_within_line = self.scroll_y - first_visible_line as f32 * LINE_HEIGHT;
-            let mut line_start_idx = 0usize;
-            for line in self.content.split('\n').take(first_visible_line) {
-                line_start_idx += line.len() + 1;
-            }
+            let mut line_start_idx = self.line_start_of(first_visible_line);
 
             let mut line_y = layout.text_rect.y - offset_within_line;
             let text_origin_x = layout.text_rect.x;
             let visible_bottom = layout.text_rect.y + layout.text_rect.height;
 
-            for (line_index, line) in self
-                .content
+            for (line_index, line) in self.content[line_start_idx..]
                 .split('\n')
                 .enumerate()
-                .skip(first_visible_line)
+                .map(|(offset, line)| (first_visible_line + offset, line))
             {
                 if line_y > visible_bottom {
                     break;
                 }
+                if let Some(change) = self.git_changes.get(&line_index) {
+                    gutter_marks.push((line_y, *change));
+                }
+                if let Some(heat) = self.line_heat(line_index).filter(|heat| *heat > 0.0) {
+                    scissor.draw_rectangle_rec(
+                        Rectangle {
+                            x: text_origin_x,
+                            y: line_y,
+                            width: layout.text_rect.width,
+                            height: LINE_HEIGHT,
+                        },
+                        theme.error.fade(0.08 + 0.4 * heat),
+                    );
+                }
                 if let Some(sel) = selection {
                     self.draw_selection(
                         &mut scissor,
@@ -1671,6 +2754,23 @@ impl Pane for EditorPane {
                     line_y,
                     text_origin_x,
                 );
+                for (start, end, severity) in self.diagnostic_ranges(line_index, line) {
+                    let x = text_origin_x - self.scroll_x
+                        + fonts.measure_text(&line[..start], CONTENT_FONT_SIZE).x;
+                    let width = fonts.measure_text(&line[start..end], CONTENT_FONT_SIZE).x;
+                    scissor.draw_rectangle_rec(
+                        Rectangle {
+                            x,
+                            y: line_y + CONTENT_FONT_SIZE + 2.0,
+                            width: width.max(CARET_WIDTH as f32 * 2.0),
+                            height: 2.0,
+                        },
+                        match severity {
+                            Severity::Error => theme.error,
+                            Severity::Warning => theme.warning,
+                        },
+                    );
+                }
 
                 line_y += LINE_HEIGHT;
                 line_start_idx += line.len() + 1;
@@ -1678,10 +2778,7 @@ impl Pane for EditorPane {
 
             if self.has_focus {
                 let cursor_line_start = self.line_start(self.cursor_position);
-                let cursor_line_index = self.content[..cursor_line_start]
-                    .chars()
-                    .filter(|c| *c == '\n')
-                    .count();
+                let cursor_line_index = self.line_index_at(self.cursor_position);
                 let prefix = &self.content[cursor_line_start..self.cursor_position];
                 let prefix_width = fonts.measure_text(prefix, CONTENT_FONT_SIZE).x;
                 let cursor_x = text_origin_x - self.scroll_x + prefix_width;
@@ -1704,6 +2801,29 @@ impl Pane for EditorPane {
             drop(scissor);
         }
 
+        // Git change markers in the left padding
+        for (line_y, change) in gutter_marks {
+            let top = line_y.max(layout.text_rect.y);
+            let bottom = (line_y + LINE_HEIGHT).min(layout.text_rect.y + layout.text_rect.height);
+            if bottom <= top {
+                continue;
+            }
+            let (color, height) = match change {
+                LineChange::Added => (theme.success, bottom - top),
+                LineChange::Modified => (theme.warning, bottom - top),
+                LineChange::Removed => (theme.error, 3.0_f32.min(bottom - top)),
+            };
+            d.draw_rectangle_rec(
+                Rectangle {
+                    x: bounds.x + 1.0,
+                    y: top,
+                    width: GIT_GUTTER_WIDTH,
+                    height,
+                },
+                color,
+            );
+        }
+
         let track_color = Color::new(theme.border.r, theme.border.g, theme.border.b, 160);
         let thumb_color = Color::new(theme.text_dim.r, theme.text_dim.g, theme.text_dim.b, 220);
 
@@ -1744,6 +2864,13 @@ impl Pane for EditorPane {
             }
         }
 
+        if let Some(status_rect) = layout.status_rect {
+            self.draw_path_completions(d, status_rect, theme, fonts);
+        }
+
+        self.update_hover(d, &layout);
+        self.draw_hover(d, bounds, theme, fonts);
+
         self.last_layout = Some(layout);
     }
 
@@ -1942,6 +3069,42 @@ impl Pane for EditorPane {
         let shift = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
             || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
 
+        if ctrl && shift {
+            if rl.is_key_pressed(KeyboardKey::KEY_G) {
+                self.stage_current_file();
+                handled = true;
+            } else if rl.is_key_pressed(KeyboardKey::KEY_K) {
+                self.begin_commit_command();
+                handled = true;
+            } else if rl.is_key_pressed(KeyboardKey::KEY_I) {
+                self.inspect_last_value();
+                handled = true;
+            } else if rl.is_key_pressed(KeyboardKey::KEY_D) {
+                self.begin_discard_command();
+                handled = true;
+            } else if rl.is_key_pressed(KeyboardKey::KEY_R) && self.read_only && !self.binary_file {
+                self.pending_command = Some(PendingCommand::ConfirmEnableEditing {
+                    buffer: String::new(),
+                });
+                self.update_command_status();
+                handled = true;
+            }
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_F5) {
+            self.profile_buffer();
+            handled = true;
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_F12) {
+            if shift {
+                self.find_references();
+            } else {
+                self.go_to_definition();
+            }
+            handled = true;
+        }
+
         if ctrl {
             if rl.is_key_pressed(KeyboardKey::KEY_S) {
                 if shift {
@@ -1975,7 +3138,7 @@ impl Pane for EditorPane {
             } else if rl.is_key_pressed(KeyboardKey::KEY_A) {
                 self.select_all();
                 handled = true;
-            } else if rl.is_key_pressed(KeyboardKey::KEY_E) {
+            } else if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
                 self.evaluate_expression();
                 handled = true;
             }
@@ -2007,7 +3170,7 @@ impl Pane for EditorPane {
             handled = true;
         }
 
-        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+        if !ctrl && rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
             self.insert_newline();
             handled = true;
         }
@@ -2083,6 +3246,53 @@ impl Pane for EditorPane {
     }
 }
 
+fn read_head(path: &std::path::Path, limit: usize) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(limit);
+    fs::File::open(path)?
+        .take(limit as u64)
+        .read_to_end(&mut head)?;
+    Ok(head)
+}
+
+// Text files essentially never contain NUL; invalid UTF-8 in the prefix is also binary
+// unless it is only a multi-byte character cut off at the sniff boundary
+fn looks_binary(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        Err(err) => err.error_len().is_some(),
+    }
+}
+
+// Classic 16-bytes-per-row dump: offset, hex bytes, printable ASCII
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        out.push_str(&format!("{:08x}  ", row * 16));
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(byte) => out.push_str(&format!("{:02x} ", byte)),
+                None => out.push_str("   "),
+            }
+            if i == 7 {
+                out.push(' ');
+            }
+        }
+        out.push_str(" |");
+        for byte in chunk {
+            out.push(if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte as char
+            } else {
+                '.'
+            });
+        }
+        out.push_str("|\n");
+    }
+    out
+}
+
 fn rect_contains(rect: Rectangle, point: Vector2) -> bool {
     if rect.width <= 0.0 || rect.height <= 0.0 {
         return false;
@@ -2092,3 +3302,265 @@ fn rect_contains(rect: Rectangle, point: Vector2) -> bool {
         && point.y >= rect.y
         && point.y <= rect.y + rect.height
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{hex_dump, looks_binary, EditorPane, Expr, Severity};
+    use std::fs;
+
+    #[test]
+    fn test_large_file_line_index_matches_scan() {
+        let mut pane = EditorPane::new("editor".to_string());
+        pane.content = "(define x 1)\n\nλ wide line\nlast".to_string();
+
+        let expected: Vec<(usize, usize)> = (0..=pane.content.len())
+            .filter(|idx| pane.content.is_char_boundary(*idx))
+            .map(|idx| (idx, pane.line_index_at(idx)))
+            .collect();
+        let starts: Vec<usize> = (0..5).map(|line| pane.line_start_of(line)).collect();
+        let count = pane.line_count();
+
+        pane.large_file = true;
+        pane.rebuild_line_index();
+        for (idx, line) in expected {
+            assert_eq!(pane.line_index_at(idx), line, "offset {}", idx);
+        }
+        assert_eq!(
+            (0..5).map(|l| pane.line_start_of(l)).collect::<Vec<_>>(),
+            starts
+        );
+        assert_eq!(pane.line_count(), count);
+        assert_eq!(pane.longest_line_chars, 12);
+    }
+
+    #[test]
+    fn test_large_file_edits_keep_the_line_index_in_step() {
+        let mut pane = EditorPane::new("editor".to_string());
+        pane.content = "(define x 1)\n\nλ wide line\nlast".to_string();
+        pane.large_file = true;
+        pane.rebuild_line_index();
+
+        let check = |pane: &mut EditorPane, step: &str| {
+            let (starts, longest) = (pane.line_starts.clone(), pane.longest_line_chars);
+            pane.rebuild_line_index();
+            assert_eq!(pane.line_starts, starts, "{}", step);
+            assert!(longest >= pane.longest_line_chars, "{}", step);
+        };
+        pane.cursor_position = 3;
+        pane.insert_text("ab\ncd\n");
+        check(&mut pane, "insert lines");
+        pane.insert_char('(');
+        check(&mut pane, "insert paren");
+        pane.insert_newline();
+        check(&mut pane, "newline");
+        pane.cursor_position = pane.line_start_of(2);
+        pane.delete_backward();
+        check(&mut pane, "join lines");
+        pane.selection = Some((2, pane.content.len() - 2));
+        pane.delete_forward();
+        check(&mut pane, "delete selection");
+        pane.cursor_position = pane.content.len();
+        pane.insert_text("\nλλ\n");
+        pane.selection = Some((0, pane.content.len()));
+        pane.indent_selection_or_line();
+        check(&mut pane, "indent");
+        pane.outdent_selection_or_line();
+        check(&mut pane, "outdent");
+    }
+
+    #[test]
+    fn test_profile_heatmap_lasts_until_an_edit() {
+        let mut pane = EditorPane::new("editor".to_string());
+        pane.insert_text("(define n 300)\n(for ((x in (range n)))\n  (* x x))\n");
+        pane.profile_buffer();
+        assert_eq!(pane.line_heat(1), Some(1.0));
+        assert_eq!(pane.line_heat(2), Some(1.0));
+        assert!(pane.line_heat(0).unwrap() < 1.0);
+        assert_eq!(pane.line_heat(3), None);
+
+        pane.insert_text(" ");
+        assert_eq!(pane.line_heat(1), None);
+    }
+
+    #[test]
+    fn test_inspect_sends_the_last_value_without_evaluating() {
+        let mut pane = EditorPane::new("editor".to_string());
+        pane.inspect_last_value();
+        assert_eq!(pane.take_inspect_request(), None);
+
+        pane.evaluator.eval_str("(define hits 0)").unwrap();
+        pane.insert_text("(progn (define hits (+ hits 1)) hits)");
+        pane.evaluate_expression();
+        pane.inspect_last_value();
+        assert_eq!(pane.take_inspect_request(), Some(Expr::Integer(1)));
+        assert_eq!(pane.evaluator.eval_str("hits").unwrap(), Expr::Integer(1));
+    }
+
+    #[test]
+    fn test_read_only_buffer_rejects_edits() {
+        let mut pane = EditorPane::new("editor".to_string());
+        pane.content = "abc".to_string();
+        pane.large_file = true;
+        pane.read_only = true;
+        pane.rebuild_line_index();
+
+        pane.insert_text("x");
+        pane.delete_backward();
+        assert_eq!(pane.content, "abc");
+
+        // Once editing is enabled, edits apply but take no undo snapshots
+        let undo_depth = pane.undo_stack.len();
+        pane.read_only = false;
+        pane.cursor_position = 3;
+        pane.insert_text("d");
+        assert_eq!(pane.content, "abcd");
+        assert_eq!(pane.undo_stack.len(), undo_depth);
+    }
+
+    #[test]
+    fn test_tooltip_lines() {
+        let mut pane = EditorPane::new("editor".to_string());
+        pane.content = "(defun twice (n)\n  \"Doubles n.\"\n  (* 2 n))\n".to_string();
+        pane.evaluator.eval_str(&pane.content.clone()).unwrap();
+        pane.evaluator
+            .eval_str("(define big (make-vector 100 \"padding\"))")
+            .unwrap();
+
+        assert_eq!(
+            pane.tooltip_lines("twice"),
+            vec![
+                "(twice n)  [function]".to_string(),
+                "Doubles n.".to_string(),
+                "defined at untitled:1:8".to_string(),
+            ]
+        );
+        assert_eq!(
+            pane.tooltip_lines("car"),
+            vec!["car  [builtin]".to_string()]
+        );
+        assert!(pane.tooltip_lines("n").is_empty());
+
+        let value = &pane.tooltip_lines("big")[1];
+        assert!(value.starts_with("= [\"padding\" "));
+        assert!(value.ends_with("..."));
+        assert_eq!(value.chars().count(), 83);
+    }
+
+    #[test]
+    fn test_definitions_and_references_across_files() {
+        let dir = std::env::temp_dir().join(format!("zeus_editor_xref_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.lisp");
+        let main = dir.join("main.lisp");
+        fs::write(&lib, "(defun square (x) (* x x))\n; square in a comment\n").unwrap();
+        fs::write(&main, "(println (square 3))\n(println \"square\")\n").unwrap();
+
+        let mut pane = EditorPane::new("editor".to_string());
+        pane.load_file(main.clone());
+        // Cursor just past the name still counts as on it
+        pane.cursor_position = "(println (square".len();
+        pane.find_references();
+        let (heading, items) = pane.take_reference_results().unwrap();
+        assert_eq!(heading, "square (2)");
+        let found: Vec<(String, usize, usize)> = items
+            .iter()
+            .map(|item| {
+                let location = &item.location;
+                (location.file.clone(), location.line, location.column)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (main.display().to_string(), 1, 11),
+                (lib.display().to_string(), 1, 8),
+            ]
+        );
+        assert_eq!(items[1].text, "(defun square (x) (* x x))");
+
+        // Unsaved edits keep the buffer where it is
+        pane.insert_text(" ");
+        pane.cursor_position = 11;
+        pane.go_to_definition();
+        assert_eq!(pane.current_file.as_ref(), Some(&main));
+
+        pane.undo();
+        pane.is_dirty = false;
+        pane.cursor_position = 11;
+        pane.go_to_definition();
+        assert_eq!(pane.current_file.as_ref(), Some(&lib));
+        assert_eq!(pane.cursor_position, "(defun ".len());
+
+        // A definition in the buffer beats one next to it
+        pane.content = "(defun square (n) n)\n(square 2)".to_string();
+        pane.syntax_highlighter.reset(&pane.content);
+        pane.is_dirty = true;
+        pane.cursor_position = pane.content.len() - 4;
+        pane.go_to_definition();
+        assert_eq!(pane.cursor_position, "(defun ".len());
+        assert_eq!(pane.current_file.as_ref(), Some(&lib));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_diagnostics_follow_loads_and_saves() {
+        let dir = std::env::temp_dir().join(format!("zeus_editor_check_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("typed.lisp");
+        fs::write(&path, "(the foo 1)\n(+ 1 \"2\")\n").unwrap();
+        fs::write(dir.join("zeus.toml"), "[check]\nstrict-types = true\n").unwrap();
+
+        let mut pane = EditorPane::new("editor".to_string());
+        pane.load_file(path.clone());
+        let found: Vec<(usize, &str)> = pane
+            .current_diagnostics()
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.rule))
+            .collect();
+        assert_eq!(found, vec![(1, "unknown-type"), (2, "type-mismatch")]);
+        assert!(pane
+            .last_result
+            .as_deref()
+            .unwrap()
+            .ends_with(" (check: 2 errors, 0 warnings)"));
+        assert_eq!(
+            pane.diagnostic_ranges(0, "(the foo 1)"),
+            vec![(0, 11, Severity::Error)]
+        );
+        assert_eq!(
+            pane.diagnostic_lines("(the f".len()),
+            vec!["error[unknown-type]: unknown type foo".to_string()]
+        );
+        assert_eq!(pane.diagnostic_lines("(the foo 1)\n".len()).len(), 1);
+
+        // An edit hides them until the buffer is saved again
+        pane.selection = Some(("(the ".len(), "(the foo".len()));
+        pane.insert_text("integer");
+        assert!(pane.current_diagnostics().is_empty());
+        pane.write_to_path(path.clone());
+        assert_eq!(pane.current_diagnostics().len(), 1);
+        assert_eq!(pane.diagnostic_ranges(0, "(the integer 1)"), vec![]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_binary_detection() {
+        assert!(!looks_binary(b"(define x 1)\n"));
+        assert!(!looks_binary("λ".as_bytes()));
+        // A multi-byte character cut off at the end of the sniffed prefix is still text
+        assert!(!looks_binary(&"aλ".as_bytes()[..2]));
+        assert!(looks_binary(b"\x7fELF\x02\x01\x00\x00"));
+        assert!(looks_binary(&[0xff, 0xfe, b'a']));
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(
+            hex_dump(b"Zeus\x00\x01 lisp\xffABCDEFG"),
+            "00000000  5a 65 75 73 00 01 20 6c  69 73 70 ff 41 42 43 44  |Zeus.. lisp.ABCD|\n\
+             00000010  45 46 47                                          |EFG|\n"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::ide::fonts::IdeFonts;
+use crate::ide::git::{FileStatus, GitStatus};
 use crate::ide::pane::Pane;
 use crate::ide::theme::Theme;
 use raylib::prelude::*;
@@ -84,6 +85,7 @@ pub struct FileTreePane {
     has_focus: bool,
     file_to_open: Option<PathBuf>,
     pending_toggle: Option<PathBuf>,
+    git_status: Option<GitStatus>,
 }
 
 impl FileTreePane {
@@ -101,6 +103,7 @@ impl FileTreePane {
             children: Vec::new(),
         };
         root_node.load_children();
+        let git_status = GitStatus::load(&root_path);
 
         Self {
             id,
@@ -112,9 +115,31 @@ impl FileTreePane {
             has_focus: false,
             file_to_open: None,
             pending_toggle: None,
+            git_status,
         }
     }
 
+    pub fn refresh_git_status(&mut self) {
+        self.git_status = GitStatus::load(&self.root_path);
+    }
+
+    fn git_marker(&self, node: &FileNode, theme: &Theme) -> Option<(&'static str, Color)> {
+        let status = self.git_status.as_ref()?;
+        if node.is_directory {
+            return status
+                .dir_has_changes(&node.path)
+                .then_some(("•", theme.warning));
+        }
+        status.status_for(&node.path).map(|file_status| {
+            let color = match file_status {
+                FileStatus::Added | FileStatus::Untracked => theme.success,
+                FileStatus::Deleted | FileStatus::Conflicted => theme.error,
+                FileStatus::Modified | FileStatus::Renamed => theme.warning,
+            };
+            (file_status.marker(), color)
+        })
+    }
+
     pub fn take_file_to_open(&mut self) -> Option<PathBuf> {
         self.file_to_open.take()
     }
@@ -261,6 +286,18 @@ impl FileTreePane {
             name_color,
         );
 
+        // Git status marker, right-aligned
+        if let Some((marker, color)) = self.git_marker(node, theme) {
+            let marker_width = fonts.measure_text(marker, 14.0).x;
+            fonts.draw_text(
+                d,
+                marker,
+                Vector2::new(bounds.x + bounds.width - marker_width - 8.0, *y),
+                14.0,
+                color,
+            );
+        }
+
         *y += line_height;
 
         // Draw children if expanded
@@ -413,6 +450,7 @@ impl Pane for FileTreePane {
         // Handle refresh with F5
         if rl.is_key_pressed(KeyboardKey::KEY_F5) {
             self.root_node.load_children();
+            self.refresh_git_status();
             handled = true;
         }
 
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileStatus {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+impl FileStatus {
+    pub fn marker(&self) -> &'static str {
+        match self {
+            FileStatus::Modified => "M",
+            FileStatus::Added => "A",
+            FileStatus::Deleted => "D",
+            FileStatus::Renamed => "R",
+            FileStatus::Untracked => "?",
+            FileStatus::Conflicted => "U",
+        }
+    }
+
+    // Porcelain v1 uses two columns: index status then worktree status
+    fn from_porcelain(index: char, worktree: char) -> Option<Self> {
+        match (index, worktree) {
+            ('?', '?') => Some(FileStatus::Untracked),
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => Some(FileStatus::Conflicted),
+            (_, 'D') | ('D', _) => Some(FileStatus::Deleted),
+            ('R', _) => Some(FileStatus::Renamed),
+            ('A', _) => Some(FileStatus::Added),
+            (_, 'M') | ('M', _) | (_, 'T') | ('T', _) => Some(FileStatus::Modified),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    Removed,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GitStatus {
+    files: HashMap<PathBuf, FileStatus>,
+}
+
+impl GitStatus {
+    // Returns None when `dir` isn't inside a git work tree or git isn't installed
+    pub fn load(dir: &Path) -> Option<Self> {
+        // Walk up from `dir` rather than trusting --show-toplevel, which resolves symlinks
+        // and would no longer match the paths the file tree holds
+        let prefix = run_git(dir, &["rev-parse", "--show-prefix"])?;
+        let mut root = dir.to_path_buf();
+        for _ in Path::new(prefix.trim()).components() {
+            root.pop();
+        }
+        let output = run_git(&root, &["status", "--porcelain", "--untracked-files=all"])?;
+
+        let files = parse_porcelain(&output)
+            .into_iter()
+            .map(|(path, status)| (root.join(path), status))
+            .collect();
+
+        Some(Self { files })
+    }
+
+    pub fn status_for(&self, path: &Path) -> Option<FileStatus> {
+        self.files.get(path).copied()
+    }
+
+    pub fn dir_has_changes(&self, dir: &Path) -> bool {
+        self.files.keys().any(|path| path.starts_with(dir))
+    }
+}
+
+// Changed lines of `path` relative to HEAD, keyed by zero-based line index
+pub fn changed_lines(path: &Path) -> HashMap<usize, LineChange> {
+    let Some(dir) = path.parent() else {
+        return HashMap::new();
+    };
+    let path_str = path.to_string_lossy();
+
+    let tracked = run_git(dir, &["ls-files", "--error-unmatch", path_str.as_ref()]).is_some();
+    if !tracked {
+        // Untracked files inside a repository are entirely new
+        if run_git(dir, &["rev-parse", "--is-inside-work-tree"]).is_none() {
+            return HashMap::new();
+        }
+        let line_count = std::fs::read_to_string(path)
+            .map(|text| text.lines().count())
+            .unwrap_or(0);
+        return (0..line_count).map(|i| (i, LineChange::Added)).collect();
+    }
+
+    run_git(dir, &["diff", "-U0", "HEAD", "--", path_str.as_ref()])
+        .map(|diff| parse_diff_hunks(&diff))
+        .unwrap_or_default()
+}
+
+pub fn stage(path: &Path) -> Result<String, String> {
+    let (dir, file) = split_path(path)?;
+    run_git_command(dir, &["add", "--", file])
+}
+
+pub fn commit(dir: &Path, message: &str) -> Result<String, String> {
+    run_git_command(dir, &["commit", "-m", message])
+}
+
+// Throws away unstaged and staged edits to a single file, restoring it from HEAD
+pub fn discard(path: &Path) -> Result<String, String> {
+    let (dir, file) = split_path(path)?;
+    run_git_command(dir, &["checkout", "HEAD", "--", file])
+}
+
+fn split_path(path: &Path) -> Result<(&Path, &str), String> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+    Ok((dir, file))
+}
+
+// Like run_git, but keeps git's own output (stdout and stderr) for the transcript
+fn run_git_command(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    let text = text.trim_end().to_string();
+
+    if output.status.success() {
+        Ok(text)
+    } else if text.is_empty() {
+        Err(format!("git {} failed", args.join(" ")))
+    } else {
+        Err(text)
+    }
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        None
+    }
+}
+
+fn parse_porcelain(output: &str) -> Vec<(String, FileStatus)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut chars = line.chars();
+            let index = chars.next()?;
+            let worktree = chars.next()?;
+            let path = line.get(3..)?;
+            // Renames are reported as "old -> new"
+            let path = path.rsplit(" -> ").next().unwrap_or(path);
+            let path = path.trim_matches('"').to_string();
+            FileStatus::from_porcelain(index, worktree).map(|status| (path, status))
+        })
+        .collect()
+}
+
+fn parse_diff_hunks(diff: &str) -> HashMap<usize, LineChange> {
+    let mut changes = HashMap::new();
+
+    for line in diff.lines().filter(|l| l.starts_with("@@")) {
+        // @@ -old_start[,old_count] +new_start[,new_count] @@
+        let mut parts = line.split_whitespace().skip(1);
+        let (Some(old), Some(new)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let (_, old_count) = parse_range(old.trim_start_matches('-'));
+        let (new_start, new_count) = parse_range(new.trim_start_matches('+'));
+
+        if new_count == 0 {
+            // Pure deletion: mark the line that now follows the removed block
+            changes.entry(new_start).or_insert(LineChange::Removed);
+            continue;
+        }
+
+        let kind = if old_count == 0 {
+            LineChange::Added
+        } else {
+            LineChange::Modified
+        };
+        for line_number in new_start..new_start + new_count {
+            changes.insert(line_number.saturating_sub(1), kind);
+        }
+    }
+
+    changes
+}
+
+fn parse_range(range: &str) -> (usize, usize) {
+    let mut parts = range.splitn(2, ',');
+    let start = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let count = parts.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+    (start, count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_diff_hunks, parse_porcelain, FileStatus, LineChange};
+
+    #[test]
+    fn test_parse_porcelain() {
+        let output = " M src/main.rs\n?? notes.lisp\nA  src/new.rs\nR  old.rs -> renamed.rs\n D gone.rs\nUU both.rs\n";
+        let parsed = parse_porcelain(output);
+        assert_eq!(
+            parsed,
+            vec![
+                ("src/main.rs".to_string(), FileStatus::Modified),
+                ("notes.lisp".to_string(), FileStatus::Untracked),
+                ("src/new.rs".to_string(), FileStatus::Added),
+                ("renamed.rs".to_string(), FileStatus::Renamed),
+                ("gone.rs".to_string(), FileStatus::Deleted),
+                ("both.rs".to_string(), FileStatus::Conflicted),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_diff_hunks() {
+        let diff = "diff --git a/f b/f\n--- a/f\n+++ b/f\n@@ -2 +2 @@\n-a\n+b\n@@ -5,0 +6,2 @@\n+x\n+y\n@@ -10,3 +11,0 @@\n-gone\n";
+        let changes = parse_diff_hunks(diff);
+        assert_eq!(changes.get(&1), Some(&LineChange::Modified));
+        assert_eq!(changes.get(&5), Some(&LineChange::Added));
+        assert_eq!(changes.get(&6), Some(&LineChange::Added));
+        assert_eq!(changes.get(&11), Some(&LineChange::Removed));
+        assert_eq!(changes.len(), 4);
+    }
+}
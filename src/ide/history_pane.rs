@@ -0,0 +1,287 @@
+use crate::ide::fonts::IdeFonts;
+use crate::ide::pane::Pane;
+use crate::ide::theme::Theme;
+use crate::interpreter::printer::{PrintMode, Printer};
+use crate::interpreter::recording::Recording;
+use crate::interpreter::types::Expr;
+use raylib::prelude::*;
+use std::any::Any;
+
+const TITLE_HEIGHT: f32 = 25.0;
+const ROW_HEIGHT: f32 = 18.0;
+// Longer sources are cut short on their row
+const MAX_SOURCE_CHARS: usize = 60;
+
+// A timeline of the REPL's recording (see interpreter/recording.rs): each form evaluated
+// since (start-recording), numbered as (rewind n) takes them, with the globals it added,
+// changed or removed under it. The IDE hands it the recording whenever it has moved on.
+pub struct HistoryPane {
+    id: String,
+    title: String,
+    // Revision of the recording the rows were built from; None when not recording
+    revision: Option<u64>,
+    rows: Vec<TimelineRow>,
+    scroll_offset: f32,
+    has_focus: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineRow {
+    pub kind: RowKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowKind {
+    Form { succeeded: bool },
+    Added,
+    Changed,
+    Removed,
+}
+
+impl HistoryPane {
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            title: "History".to_string(),
+            revision: None,
+            rows: Vec::new(),
+            scroll_offset: 0.0,
+            has_focus: false,
+        }
+    }
+
+    pub fn is_current(&self, recording: Option<&Recording>) -> bool {
+        self.revision == recording.map(Recording::revision)
+    }
+
+    pub fn refresh(&mut self, recording: Option<&Recording>) {
+        self.revision = recording.map(Recording::revision);
+        self.rows = recording.map(timeline_rows).unwrap_or_default();
+    }
+
+    pub fn rows(&self) -> &[TimelineRow] {
+        &self.rows
+    }
+
+    fn scroll_by(&mut self, delta: f32, view_height: f32) {
+        let max_scroll = (self.rows.len() as f32 * ROW_HEIGHT - view_height).max(0.0);
+        self.scroll_offset = (self.scroll_offset + delta).clamp(0.0, max_scroll);
+    }
+}
+
+fn timeline_rows(recording: &Recording) -> Vec<TimelineRow> {
+    let mut rows = Vec::new();
+    for (index, form) in recording.forms().iter().enumerate() {
+        let first_line = form.source.lines().next().unwrap_or("");
+        let mut source: String = first_line.chars().take(MAX_SOURCE_CHARS).collect();
+        if source.len() < form.source.len() {
+            source.push('…');
+        }
+        rows.push(TimelineRow {
+            kind: RowKind::Form {
+                succeeded: form.succeeded,
+            },
+            text: format!("{}  {}", index + 1, source),
+        });
+        for change in &form.changes {
+            let (kind, text) = match (&change.before, &change.after) {
+                (None, Some(after)) => (
+                    RowKind::Added,
+                    format!("+ {} = {}", change.name, short_value(after)),
+                ),
+                (Some(before), Some(after)) => (
+                    RowKind::Changed,
+                    format!(
+                        "~ {}: {} → {}",
+                        change.name,
+                        short_value(before),
+                        short_value(after)
+                    ),
+                ),
+                _ => (RowKind::Removed, format!("- {}", change.name)),
+            };
+            rows.push(TimelineRow {
+                kind,
+                text: format!("    {}", text),
+            });
+        }
+    }
+    rows
+}
+
+fn short_value(value: &Expr) -> String {
+    Printer::new(PrintMode::Write)
+        .with_max_depth(2)
+        .with_max_length(4)
+        .print(value)
+}
+
+impl Pane for HistoryPane {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn draw(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        bounds: Rectangle,
+        theme: &Theme,
+        fonts: &IdeFonts,
+    ) {
+        d.draw_rectangle_rec(bounds, theme.surface);
+        if self.has_focus {
+            d.draw_rectangle_lines_ex(bounds, 2.0, theme.focus_indicator);
+        } else {
+            d.draw_rectangle_lines_ex(bounds, 1.0, theme.border);
+        }
+
+        d.draw_rectangle(
+            bounds.x as i32,
+            bounds.y as i32,
+            bounds.width as i32,
+            TITLE_HEIGHT as i32,
+            theme.panel,
+        );
+        let heading = match self.revision {
+            Some(_) => format!("{} (recording)", self.title),
+            None => self.title.clone(),
+        };
+        fonts.draw_text(
+            d,
+            &heading,
+            Vector2::new(bounds.x + 5.0, bounds.y + 5.0),
+            16.0,
+            theme.text,
+        );
+
+        let content_y = bounds.y + TITLE_HEIGHT + 2.0;
+        let content_height = (bounds.height - TITLE_HEIGHT - 4.0).max(0.0);
+        if self.revision.is_none() {
+            fonts.draw_text(
+                d,
+                "Evaluate (start-recording) in the REPL",
+                Vector2::new(bounds.x + 5.0, content_y + 2.0),
+                14.0,
+                theme.text_dim,
+            );
+            return;
+        }
+
+        let mut scissor = d.begin_scissor_mode(
+            bounds.x as i32,
+            content_y as i32,
+            bounds.width as i32,
+            content_height as i32,
+        );
+        let mut y = content_y - self.scroll_offset;
+        for row in &self.rows {
+            if y >= content_y - ROW_HEIGHT && y < content_y + content_height {
+                let color = match row.kind {
+                    RowKind::Form { succeeded: true } => theme.text,
+                    RowKind::Form { succeeded: false } => theme.error,
+                    RowKind::Added => theme.success,
+                    RowKind::Changed => theme.warning,
+                    RowKind::Removed => theme.text_dim,
+                };
+                fonts.draw_text(
+                    &mut scissor,
+                    &row.text,
+                    Vector2::new(bounds.x + 5.0, y + 2.0),
+                    14.0,
+                    color,
+                );
+            }
+            y += ROW_HEIGHT;
+        }
+    }
+
+    fn handle_input(&mut self, rl: &mut RaylibHandle, bounds: Rectangle) -> bool {
+        if !self.has_focus {
+            return false;
+        }
+        let view_height = (bounds.height - TITLE_HEIGHT - 4.0).max(0.0);
+
+        let wheel_move = rl.get_mouse_wheel_move();
+        if wheel_move != 0.0 {
+            self.scroll_by(-wheel_move * 20.0, view_height);
+            return true;
+        }
+
+        match rl.get_key_pressed() {
+            Some(KeyboardKey::KEY_UP) => self.scroll_by(-ROW_HEIGHT, view_height),
+            Some(KeyboardKey::KEY_DOWN) => self.scroll_by(ROW_HEIGHT, view_height),
+            Some(KeyboardKey::KEY_HOME) => self.scroll_by(f32::NEG_INFINITY, view_height),
+            Some(KeyboardKey::KEY_END) => self.scroll_by(f32::INFINITY, view_height),
+            _ => return false,
+        }
+        true
+    }
+
+    fn on_focus(&mut self) {
+        self.has_focus = true;
+    }
+
+    fn on_blur(&mut self) {
+        self.has_focus = false;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryPane, RowKind};
+    use crate::interpreter::evaluator::Evaluator;
+
+    #[test]
+    fn test_timeline_follows_the_recording() {
+        let mut evaluator = Evaluator::new();
+        let mut pane = HistoryPane::new("history".to_string());
+        assert!(pane.is_current(evaluator.recording.as_ref()));
+
+        evaluator.start_recording();
+        assert!(!pane.is_current(evaluator.recording.as_ref()));
+        evaluator.eval_str("(define x 1)").unwrap();
+        evaluator.eval_str("(define x (list 1 2 3 4 5 6))").unwrap();
+        assert!(evaluator.eval_str("(car 1)").is_err());
+        pane.refresh(evaluator.recording.as_ref());
+        assert!(pane.is_current(evaluator.recording.as_ref()));
+
+        let rows: Vec<(RowKind, &str)> = pane
+            .rows()
+            .iter()
+            .map(|row| (row.kind, row.text.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (RowKind::Form { succeeded: true }, "1  (define x 1)"),
+                (RowKind::Added, "    + x = 1"),
+                (
+                    RowKind::Form { succeeded: true },
+                    "2  (define x (list 1 2 3 4 5 6))"
+                ),
+                (RowKind::Changed, "    ~ x: 1 → (1 2 3 4 ...)"),
+                (RowKind::Form { succeeded: false }, "3  (car 1)"),
+            ]
+        );
+
+        // A rewind to the same number of forms is still news
+        evaluator.rewind(3).unwrap();
+        assert!(!pane.is_current(evaluator.recording.as_ref()));
+        evaluator.stop_recording();
+        pane.refresh(evaluator.recording.as_ref());
+        assert!(pane.rows().is_empty());
+    }
+}
@@ -1,24 +1,72 @@
 use crate::ide::editor::EditorPane;
 use crate::ide::file_tree::FileTreePane;
+use crate::ide::history_pane::HistoryPane;
 use crate::ide::inspector::InspectorPane;
 use crate::ide::layout::LayoutManager;
+use crate::ide::outline_pane::OutlinePane;
 use crate::ide::pane::Pane;
+use crate::ide::recent_files::RecentFiles;
 use crate::ide::repl_pane::ReplPane;
+use crate::ide::results_pane::ResultsPane;
 use crate::ide::symbol_browser::SymbolBrowserPane;
 use crate::ide::theme::Theme;
+use crate::ide::transcript_pane::TranscriptPane;
 use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::snapshot;
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 pub struct IdeState {
     pub layout_manager: LayoutManager,
     pub panes: HashMap<String, Box<dyn Pane>>,
     pub theme: Theme,
     pub shared_evaluator: Evaluator,
+    pub recent_files: RecentFiles,
+    // Save the REPL's global bindings on exit and restore them on the next launch.
+    // Enabled with an `autosave-environment on` line in the state file.
+    pub autosave_environment: bool,
+    state_path: Option<PathBuf>,
 }
 
+const AUTOSAVE_SETTING: &str = "autosave-environment";
+// Pane arrangement and split ratios, saved once they differ from the default
+const LAYOUT_SETTING: &str = "layout";
+
 impl IdeState {
     pub fn new() -> Self {
-        let shared_evaluator = Evaluator::new();
+        // Missing state files just mean a fresh start
+        let state_path = Self::default_state_path();
+        let state_text = state_path
+            .as_deref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        let mut recent_files = RecentFiles::parse(&state_text);
+        recent_files.drop_missing();
+        let autosave_environment = parse_autosave_setting(&state_text);
+
+        let mut shared_evaluator = Evaluator::new();
+        let mut restore_message = None;
+        if autosave_environment {
+            if let Some(script) = state_path
+                .as_deref()
+                .and_then(|path| fs::read_to_string(Self::snapshot_path(path)).ok())
+            {
+                let (restored, errors) = snapshot::restore_snapshot(&mut shared_evaluator, &script);
+                restore_message = Some(match errors.first() {
+                    None => (format!("Restored {} saved bindings", restored), false),
+                    Some(err) => (
+                        format!(
+                            "Restored {} saved bindings, {} failed: {}",
+                            restored,
+                            errors.len(),
+                            err
+                        ),
+                        true,
+                    ),
+                });
+            }
+        }
 
         let mut panes: HashMap<String, Box<dyn Pane>> = HashMap::new();
 
@@ -33,10 +81,11 @@ impl IdeState {
             Box::new(EditorPane::new("editor".to_string())),
         );
 
-        panes.insert(
-            "repl".to_string(),
-            Box::new(ReplPane::new("repl".to_string(), shared_evaluator.clone())),
-        );
+        let mut repl = ReplPane::new("repl".to_string(), shared_evaluator.clone());
+        if let Some((message, is_error)) = restore_message {
+            repl.append_output(&message, is_error);
+        }
+        panes.insert("repl".to_string(), Box::new(repl));
 
         panes.insert(
             "symbols".to_string(),
@@ -48,12 +97,115 @@ impl IdeState {
             Box::new(InspectorPane::new("inspector".to_string())),
         );
 
+        panes.insert(
+            "outline".to_string(),
+            Box::new(OutlinePane::new("outline".to_string())),
+        );
+
+        panes.insert(
+            "history".to_string(),
+            Box::new(HistoryPane::new("history".to_string())),
+        );
+
+        panes.insert(
+            "results".to_string(),
+            Box::new(ResultsPane::new("results".to_string())),
+        );
+
+        panes.insert(
+            "transcript".to_string(),
+            Box::new(TranscriptPane::new("transcript".to_string())),
+        );
+
+        let mut layout_manager = LayoutManager::create_default();
+        if let Some(layout) = parse_layout_setting(&state_text) {
+            let pane_ids: Vec<&str> = panes.keys().map(String::as_str).collect();
+            layout_manager.restore(layout, &pane_ids);
+        }
+
         Self {
-            layout_manager: LayoutManager::create_default(),
+            layout_manager,
             panes,
             theme: Theme::dark(),
             shared_evaluator,
+            recent_files,
+            autosave_environment,
+            state_path,
+        }
+    }
+
+    // IDE state persists in ~/.zeus/ide_state between sessions
+    fn default_state_path() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".zeus").join("ide_state"))
+    }
+
+    // The environment snapshot lives next to the state file
+    fn snapshot_path(state_path: &Path) -> PathBuf {
+        state_path.with_file_name("environment.lisp")
+    }
+
+    // Called when the IDE closes; the REPL pane holds the live bindings
+    pub fn autosave_environment(&self) {
+        if !self.autosave_environment {
+            return;
         }
+        let Some(state_path) = &self.state_path else {
+            return;
+        };
+        let Some(repl) = self
+            .panes
+            .get("repl")
+            .and_then(|pane| pane.as_any().downcast_ref::<ReplPane>())
+        else {
+            return;
+        };
+        if let Err(err) = snapshot::save_snapshot(repl.evaluator(), Self::snapshot_path(state_path))
+        {
+            eprintln!("{}", err);
+        }
+    }
+
+    pub fn record_recent_file(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.recent_files.touch(&path) {
+            self.save_state();
+        }
+    }
+
+    pub fn forget_recent_file(&mut self, path: &Path) {
+        self.recent_files.remove(path);
+        self.save_state();
+    }
+
+    // Called after the panes are dragged or rearranged
+    pub fn layout_changed(&self) {
+        self.save_state();
+    }
+
+    fn save_state(&self) {
+        if let Some(state_path) = &self.state_path {
+            if let Err(err) = self.write_state(state_path) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+
+    fn write_state(&self, state_path: &Path) -> Result<(), String> {
+        if let Some(dir) = state_path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let mut text = String::new();
+        if self.autosave_environment {
+            text.push_str(&format!("{} on\n", AUTOSAVE_SETTING));
+        }
+        let layout = self.layout_manager.root().to_text();
+        if layout != LayoutManager::create_default().root().to_text() {
+            text.push_str(&format!("{} {}\n", LAYOUT_SETTING, layout));
+        }
+        text.push_str(&self.recent_files.to_text());
+        fs::write(state_path, text)
+            .map_err(|e| format!("Failed to write {}: {}", state_path.display(), e))
     }
 
     pub fn get_pane(&self, id: &str) -> Option<&dyn Pane> {
@@ -98,8 +250,45 @@ impl IdeState {
     pub fn inspect_value(&mut self, expr: crate::interpreter::types::Expr) {
         if let Some(pane) = self.panes.get_mut("inspector") {
             if let Some(inspector) = pane.as_any_mut().downcast_mut::<InspectorPane>() {
-                inspector.inspect(expr);
+                inspector.inspect(expr, self.shared_evaluator.get_environment());
             }
         }
     }
 }
+
+fn parse_autosave_setting(state_text: &str) -> bool {
+    state_text.lines().any(|line| {
+        line.strip_prefix(AUTOSAVE_SETTING)
+            .is_some_and(|value| value.trim() == "on")
+    })
+}
+
+fn parse_layout_setting(state_text: &str) -> Option<&str> {
+    state_text
+        .lines()
+        .find_map(|line| line.strip_prefix(LAYOUT_SETTING)?.strip_prefix(' '))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_autosave_setting, parse_layout_setting};
+
+    #[test]
+    fn test_parse_autosave_setting() {
+        assert!(parse_autosave_setting(
+            "recent a.lisp\nautosave-environment on\n"
+        ));
+        assert!(!parse_autosave_setting("autosave-environment off\n"));
+        assert!(!parse_autosave_setting("recent autosave-environment on\n"));
+        assert!(!parse_autosave_setting(""));
+    }
+
+    #[test]
+    fn test_parse_layout_setting() {
+        assert_eq!(
+            parse_layout_setting("recent a.lisp\nlayout (h 0.300 a b)\n"),
+            Some("(h 0.300 a b)")
+        );
+        assert_eq!(parse_layout_setting("layouts x\n"), None);
+    }
+}
@@ -1,16 +1,111 @@
+use crate::ide::clipboard;
 use crate::ide::fonts::IdeFonts;
 use crate::ide::pane::Pane;
 use crate::ide::theme::Theme;
-use crate::interpreter::types::Expr;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::introspect::{describe_function, FunctionInfo};
+use crate::interpreter::matrix::Matrix;
+use crate::interpreter::printer::{format_float, quote_string, write_string, PrintMode, Printer};
+use crate::interpreter::types::{Expr, HashKey, SymbolData, HASH_PRINT_LIMIT};
 use raylib::prelude::*;
 use std::any::Any;
 use std::collections::HashMap;
 
+// Rows and columns of a matrix the inspector lays out before eliding the rest
+const MATRIX_ROW_LIMIT: usize = 20;
+const MATRIX_COL_LIMIT: usize = 8;
+
+// Width a function's body is pretty-printed to
+const FUNCTION_BODY_WIDTH: usize = 60;
+
+// Seconds between two clicks on a row for them to count as a double-click
+const DOUBLE_CLICK_TIME: f64 = 0.4;
+
+const LINE_HEIGHT: f32 = 18.0;
+
+// Values the back button can return to
+const MAX_HISTORY: usize = 50;
+
+// One step from a value to one of the children the inspector shows under it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathStep {
+    // An element of a list, vector, queue or stack, counted in the order shown: a queue
+    // from its front, a stack from its top
+    Index(usize),
+    // A struct field, by position
+    Field(usize),
+    Key(HashKey),
+    Car,
+    Cdr,
+    // The item a heap gives out next
+    HeapNext,
+}
+
+// A leaf being edited in place: where it is, the text typed so far and, once a commit has
+// failed, why
+struct LeafEdit {
+    path: Vec<PathStep>,
+    text: String,
+    error: Option<String>,
+}
+
+// A line of the drawn value that clicks act on: a leaf, which a double-click edits, or a
+// container, which a click expands or collapses and a double-click opens
+struct Row {
+    top: f32,
+    path: Vec<PathStep>,
+    // For a container, whether it starts out expanded
+    node: Option<bool>,
+}
+
+// What drawing the value found out: the path to the node being drawn, and the rows so far
+#[derive(Default)]
+struct Walk {
+    path: Vec<PathStep>,
+    rows: Vec<Row>,
+}
+
+// Everything about what the inspector shows that back and forward return to
+struct View {
+    value: Expr,
+    function: Option<FunctionInfo>,
+    origin: Option<String>,
+    focus: Vec<PathStep>,
+    expanded_nodes: HashMap<Vec<PathStep>, bool>,
+    scroll_offset: i32,
+}
+
+// Parts of the breadcrumb line a click acts on
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Crumb {
+    Back,
+    Forward,
+    // The value that many steps along the focus path
+    Depth(usize),
+}
+
 pub struct InspectorPane {
     id: String,
     title: String,
     current_value: Option<Expr>,
-    expanded_nodes: HashMap<String, bool>,
+    // Set when the value is a function, with its free variables' values as of inspect
+    function: Option<FunctionInfo>,
+    // The variable the value was read from, which edits that replace it are written back to
+    origin: Option<String>,
+    // The path from the inspected value to the part shown, which the breadcrumb spells out
+    focus: Vec<PathStep>,
+    editing: Option<LeafEdit>,
+    // A new value for the origin variable, waiting for the app to bind it
+    pending_rebind: Option<(String, Expr)>,
+    rows: Vec<Row>,
+    crumbs: Vec<(Rectangle, Crumb)>,
+    last_click: Option<(f64, Vec<PathStep>)>,
+    // Keyed by path from the inspected value, so a node stays open or shut however the
+    // rows above it move
+    expanded_nodes: HashMap<Vec<PathStep>, bool>,
+    back: Vec<View>,
+    forward: Vec<View>,
     scroll_offset: i32,
     has_focus: bool,
 }
@@ -21,21 +116,279 @@ impl InspectorPane {
             id,
             title: "Inspector".to_string(),
             current_value: None,
+            function: None,
+            origin: None,
+            focus: Vec::new(),
+            editing: None,
+            pending_rebind: None,
+            rows: Vec::new(),
+            crumbs: Vec::new(),
+            last_click: None,
             expanded_nodes: HashMap::new(),
+            back: Vec::new(),
+            forward: Vec::new(),
             scroll_offset: 0,
             has_focus: false,
         }
     }
 
-    pub fn inspect(&mut self, value: Expr) {
-        self.current_value = Some(value);
-        self.scroll_offset = 0;
+    // `environment` gives the values a function's free variables have. What was shown
+    // before goes on the back history.
+    pub fn inspect(&mut self, value: Expr, environment: &Environment) {
+        self.visit(View {
+            function: describe_function(environment, &value, FUNCTION_BODY_WIDTH),
+            value,
+            origin: None,
+            focus: Vec::new(),
+            expanded_nodes: HashMap::new(),
+            scroll_offset: 0,
+        });
+    }
+
+    // inspect for the value of a variable, so edits can be written back to it
+    pub fn inspect_binding(&mut self, name: &str, value: Expr, environment: &Environment) {
+        self.inspect(value, environment);
+        self.origin = Some(name.to_string());
     }
 
     pub fn clear(&mut self) {
-        self.current_value = None;
-        self.expanded_nodes.clear();
-        self.scroll_offset = 0;
+        self.take_view();
+        self.back.clear();
+        self.forward.clear();
+    }
+
+    // Shows the part of the inspected value at `path` on its own, with the breadcrumb
+    // leading back up
+    fn open_path(&mut self, path: Vec<PathStep>) {
+        let Some(value) = self.current_value.clone() else {
+            return;
+        };
+        self.visit(View {
+            value,
+            function: None,
+            origin: self.origin.clone(),
+            focus: path,
+            expanded_nodes: self.expanded_nodes.clone(),
+            scroll_offset: 0,
+        });
+    }
+
+    fn visit(&mut self, view: View) {
+        if let Some(current) = self.take_view() {
+            if self.back.len() == MAX_HISTORY {
+                self.back.remove(0);
+            }
+            self.back.push(current);
+        }
+        self.forward.clear();
+        self.show_view(view);
+    }
+
+    fn go_back(&mut self) {
+        if let Some(view) = self.back.pop() {
+            if let Some(current) = self.take_view() {
+                self.forward.push(current);
+            }
+            self.show_view(view);
+        }
+    }
+
+    fn go_forward(&mut self) {
+        if let Some(view) = self.forward.pop() {
+            if let Some(current) = self.take_view() {
+                self.back.push(current);
+            }
+            self.show_view(view);
+        }
+    }
+
+    fn take_view(&mut self) -> Option<View> {
+        self.editing = None;
+        self.rows.clear();
+        let function = self.function.take();
+        let origin = self.origin.take();
+        let focus = std::mem::take(&mut self.focus);
+        let expanded_nodes = std::mem::take(&mut self.expanded_nodes);
+        let scroll_offset = std::mem::take(&mut self.scroll_offset);
+        Some(View {
+            value: self.current_value.take()?,
+            function,
+            origin,
+            focus,
+            expanded_nodes,
+            scroll_offset,
+        })
+    }
+
+    fn show_view(&mut self, view: View) {
+        self.current_value = Some(view.value);
+        self.function = view.function;
+        self.origin = view.origin;
+        self.focus = view.focus;
+        self.expanded_nodes = view.expanded_nodes;
+        self.scroll_offset = view.scroll_offset;
+    }
+
+    // The breadcrumb: the variable or "value", then a label for each step of the focus path
+    fn breadcrumb(&self) -> Vec<String> {
+        let Some(root) = &self.current_value else {
+            return Vec::new();
+        };
+        let mut labels = vec![self.origin.clone().unwrap_or_else(|| "value".to_string())];
+        let mut node = Some(root.clone());
+        for step in &self.focus {
+            labels.push(match (step, &node) {
+                (PathStep::Index(i), _) => format!("[{}]", i),
+                (PathStep::Field(i), Some(Expr::Struct(instance))) => instance
+                    .borrow()
+                    .fields
+                    .get(*i)
+                    .map_or_else(|| format!("#{}", i), |field| field.to_string()),
+                (PathStep::Field(i), _) => format!("#{}", i),
+                (PathStep::Key(key), _) => write_string(&key.to_expr()),
+                (PathStep::Car, _) => "car".to_string(),
+                (PathStep::Cdr, _) => "cdr".to_string(),
+                (PathStep::HeapNext, _) => "next".to_string(),
+            });
+            node = node.and_then(|node| value_at(&node, std::slice::from_ref(step)));
+        }
+        labels
+    }
+
+    fn shown_value(&self) -> Option<Expr> {
+        value_at(self.current_value.as_ref()?, &self.focus)
+    }
+
+    // A click on the breadcrumb moves along it; one on a container expands or collapses
+    // it, and a second soon after opens it on its own instead. Double-clicking a leaf
+    // opens an edit field on it.
+    fn click(&mut self, mouse: Vector2, now: f64) {
+        let crumb = self
+            .crumbs
+            .iter()
+            .find(|(area, _)| area.check_collision_point_rec(mouse))
+            .map(|(_, crumb)| *crumb);
+        match crumb {
+            Some(Crumb::Back) => return self.go_back(),
+            Some(Crumb::Forward) => return self.go_forward(),
+            Some(Crumb::Depth(depth)) => {
+                if depth < self.focus.len() {
+                    self.open_path(self.focus[..depth].to_vec());
+                }
+                return;
+            }
+            None => {}
+        }
+
+        let Some(row) = self
+            .rows
+            .iter()
+            .find(|row| mouse.y >= row.top && mouse.y < row.top + LINE_HEIGHT)
+        else {
+            self.editing = None;
+            self.last_click = None;
+            return;
+        };
+        let (path, node) = (row.path.clone(), row.node);
+        let double = matches!(&self.last_click,
+            Some((time, last)) if *last == path && now - time < DOUBLE_CLICK_TIME);
+        self.last_click = if double {
+            None
+        } else {
+            Some((now, path.clone()))
+        };
+        match (node, double) {
+            (None, true) => self.begin_edit(path),
+            (None, false) => {}
+            (Some(default), false) => self.toggle_node(&path, default),
+            // Undoing the first click's toggle, so the node is left as it was
+            (Some(default), true) => {
+                self.toggle_node(&path, default);
+                self.open_path(path);
+            }
+        }
+    }
+
+    // Whether the container being drawn is expanded; notes its row for clicks
+    fn node_expanded(&self, walk: &mut Walk, top: f32, default: bool) -> bool {
+        walk.rows.push(Row {
+            top,
+            path: walk.path.clone(),
+            node: Some(default),
+        });
+        self.expanded_nodes
+            .get(&walk.path)
+            .copied()
+            .unwrap_or(default)
+    }
+
+    fn toggle_node(&mut self, path: &[PathStep], default: bool) {
+        let expanded = self.expanded_nodes.get(path).copied().unwrap_or(default);
+        self.expanded_nodes.insert(path.to_vec(), !expanded);
+    }
+
+    // The variable and its new value after an edit replaced the inspected value rather than
+    // changing it in place
+    pub fn take_rebind(&mut self) -> Option<(String, Expr)> {
+        self.pending_rebind.take()
+    }
+
+    // Opens the edit field on the leaf at `path`, holding its readable form
+    fn begin_edit(&mut self, path: Vec<PathStep>) {
+        let Some(leaf) = self
+            .current_value
+            .as_ref()
+            .and_then(|root| value_at(root, &path))
+        else {
+            return;
+        };
+        self.editing = Some(LeafEdit {
+            path,
+            text: write_string(&leaf),
+            error: None,
+        });
+    }
+
+    // Reads the edit text and puts the value in place of the leaf, closing the field; if
+    // that fails the field stays open with the error under it
+    fn commit_edit(&mut self) {
+        let Some(mut edit) = self.editing.take() else {
+            return;
+        };
+        if let Err(err) = self.apply_edit(&edit.path, &edit.text) {
+            edit.error = Some(err);
+            self.editing = Some(edit);
+        }
+    }
+
+    fn apply_edit(&mut self, path: &[PathStep], text: &str) -> Result<(), String> {
+        if text.trim().is_empty() {
+            return Err("Enter a value".to_string());
+        }
+        let value = Evaluator::parse(text)?;
+        let root = self
+            .current_value
+            .as_ref()
+            .ok_or("Nothing is being inspected")?;
+        // Below a container that can change in place, the edit shows through every reference
+        // to it; otherwise the lists and conses down to the leaf are rebuilt, and the
+        // variable has to be bound to the new value
+        let replaces_root = !changes_in_place(root, path);
+        if replaces_root && self.origin.is_none() {
+            return Err(
+                "Not read from a variable: only values inside vectors, hash tables, structs, \
+                 queues and stacks can be changed"
+                    .to_string(),
+            );
+        }
+        let root = replace_at(root, path, value)?;
+        if replaces_root {
+            if let Some(name) = &self.origin {
+                self.pending_rebind = Some((name.clone(), root.clone()));
+            }
+        }
+        self.current_value = Some(root);
+        Ok(())
     }
 
     fn draw_expr(
@@ -48,11 +401,10 @@ impl InspectorPane {
         theme: &Theme,
         bounds: &Rectangle,
         fonts: &IdeFonts,
+        walk: &mut Walk,
     ) {
-        use crate::interpreter::types::{Expr, SymbolData};
-
         let indent_width = 20.0;
-        let line_height = 18.0;
+        let line_height = LINE_HEIGHT;
         let x_pos = x + (indent as f32 * indent_width);
 
         if *y > bounds.y + bounds.height {
@@ -60,71 +412,53 @@ impl InspectorPane {
         }
 
         match expr {
-            Expr::Integer(n) => {
-                fonts.draw_text(
+            Expr::Integer(_) | Expr::BigInt(_) | Expr::Float(_) | Expr::Rational { .. } => {
+                self.draw_leaf(
                     d,
-                    &format!("{}", n),
-                    Vector2::new(x_pos, *y),
-                    14.0,
+                    &write_string(expr),
                     theme.number,
+                    x_pos,
+                    y,
+                    theme,
+                    fonts,
+                    walk,
                 );
-                *y += line_height;
             }
-            Expr::Float(f) => {
-                fonts.draw_text(
+            Expr::Character(_) => {
+                self.draw_leaf(
                     d,
-                    &format!("{}", f),
-                    Vector2::new(x_pos, *y),
-                    14.0,
-                    theme.number,
+                    &write_string(expr),
+                    theme.string,
+                    x_pos,
+                    y,
+                    theme,
+                    fonts,
+                    walk,
                 );
-                *y += line_height;
             }
-            Expr::Rational {
-                numerator,
-                denominator,
-            } => {
-                fonts.draw_text(
+            Expr::Symbol(_) => {
+                self.draw_leaf(
                     d,
-                    &format!("{}/{}", numerator, denominator),
-                    Vector2::new(x_pos, *y),
-                    14.0,
-                    theme.number,
+                    &write_string(expr),
+                    theme.keyword,
+                    x_pos,
+                    y,
+                    theme,
+                    fonts,
+                    walk,
                 );
-                *y += line_height;
-            }
-            Expr::Character(ch) => {
-                let repr = match *ch {
-                    ' ' => "#\\space".to_string(),
-                    '\n' => "#\\newline".to_string(),
-                    '\t' => "#\\tab".to_string(),
-                    '\r' => "#\\return".to_string(),
-                    c => format!("#\\{}", c),
-                };
-                fonts.draw_text(d, &repr, Vector2::new(x_pos, *y), 14.0, theme.string);
-                *y += line_height;
-            }
-            Expr::Symbol(sym_data) => {
-                let text = match sym_data {
-                    SymbolData::Keyword(name) => format!(":{}", name),
-                    SymbolData::Uninterned(name, id) => format!("#:{}#{}", name, id),
-                    SymbolData::Interned(name) => name.clone(),
-                };
-                fonts.draw_text(d, &text, Vector2::new(x_pos, *y), 14.0, theme.keyword);
-                *y += line_height;
             }
             Expr::String(s) => {
-                let display = if s.len() > 50 {
-                    format!("\"{}...\"", &s[..50])
+                let display = if s.chars().count() > 50 {
+                    let head: String = s.chars().take(50).collect();
+                    format!("{}...", quote_string(&head))
                 } else {
-                    format!("\"{}\"", s)
+                    quote_string(s)
                 };
-                fonts.draw_text(d, &display, Vector2::new(x_pos, *y), 14.0, theme.string);
-                *y += line_height;
+                self.draw_leaf(d, &display, theme.string, x_pos, y, theme, fonts, walk);
             }
             Expr::List(list) => {
-                let node_id = format!("list_{}", *y as i32);
-                let expanded = self.expanded_nodes.get(&node_id).copied().unwrap_or(true);
+                let expanded = self.node_expanded(walk, *y, true);
 
                 // Draw expand/collapse indicator
                 let indicator = if expanded { "▼" } else { "▶" };
@@ -162,14 +496,15 @@ impl InspectorPane {
                                 theme.text_dim,
                             );
                             *y += line_height;
-                            self.draw_expr(d, item, x, y, indent + 2, theme, bounds, fonts);
+                            walk.path.push(PathStep::Index(i));
+                            self.draw_expr(d, item, x, y, indent + 2, theme, bounds, fonts, walk);
+                            walk.path.pop();
                         }
                     }
                 }
             }
             Expr::Vector(vec) => {
-                let node_id = format!("vec_{}", *y as i32);
-                let expanded = self.expanded_nodes.get(&node_id).copied().unwrap_or(true);
+                let expanded = self.node_expanded(walk, *y, true);
 
                 let indicator = if expanded { "▼" } else { "▶" };
                 fonts.draw_text(
@@ -182,7 +517,7 @@ impl InspectorPane {
 
                 fonts.draw_text(
                     d,
-                    &format!("Vector [{}]", vec.len()),
+                    &format!("Vector [{}]", vec.borrow().len()),
                     Vector2::new(x_pos, *y),
                     14.0,
                     theme.text,
@@ -190,7 +525,7 @@ impl InspectorPane {
                 *y += line_height;
 
                 if expanded {
-                    for (i, item) in vec.iter().enumerate() {
+                    for (i, item) in vec.borrow().iter().enumerate() {
                         if *y > bounds.y + bounds.height {
                             break;
                         }
@@ -202,13 +537,157 @@ impl InspectorPane {
                             theme.text_dim,
                         );
                         *y += line_height;
-                        self.draw_expr(d, item, x, y, indent + 2, theme, bounds, fonts);
+                        walk.path.push(PathStep::Index(i));
+                        self.draw_expr(d, item, x, y, indent + 2, theme, bounds, fonts, walk);
+                        walk.path.pop();
+                    }
+                }
+            }
+            Expr::Queue(items) | Expr::Stack(items) => {
+                let kind = match expr {
+                    Expr::Queue(_) => "Queue",
+                    _ => "Stack",
+                };
+                let expanded = self.node_expanded(walk, *y, true);
+
+                let indicator = if expanded { "▼" } else { "▶" };
+                fonts.draw_text(
+                    d,
+                    indicator,
+                    Vector2::new(x_pos - 15.0, *y),
+                    14.0,
+                    theme.text,
+                );
+
+                fonts.draw_text(
+                    d,
+                    &format!("{} [{}]", kind, items.borrow().len()),
+                    Vector2::new(x_pos, *y),
+                    14.0,
+                    theme.text,
+                );
+                *y += line_height;
+
+                if expanded {
+                    // Starting with the element the next dequeue or pop! returns
+                    let items = items.borrow();
+                    let ordered: Vec<&Expr> = match expr {
+                        Expr::Queue(_) => items.iter().collect(),
+                        _ => items.iter().rev().collect(),
+                    };
+                    for (i, item) in ordered.into_iter().enumerate() {
+                        if *y > bounds.y + bounds.height {
+                            break;
+                        }
+                        fonts.draw_text(
+                            d,
+                            &format!("[{}]:", i),
+                            Vector2::new(x_pos + indent_width, *y),
+                            12.0,
+                            theme.text_dim,
+                        );
+                        *y += line_height;
+                        walk.path.push(PathStep::Index(i));
+                        self.draw_expr(d, item, x, y, indent + 2, theme, bounds, fonts, walk);
+                        walk.path.pop();
+                    }
+                }
+            }
+            Expr::Heap(heap) => {
+                let heap = heap.borrow();
+                fonts.draw_text(
+                    d,
+                    &format!("Heap [{}]", heap.items.len()),
+                    Vector2::new(x_pos, *y),
+                    14.0,
+                    theme.text,
+                );
+                *y += line_height;
+                // Only the next item out is meaningful; the rest are in storage order
+                if let Some(first) = heap.items.first() {
+                    fonts.draw_text(
+                        d,
+                        "next:",
+                        Vector2::new(x_pos + indent_width, *y),
+                        12.0,
+                        theme.text_dim,
+                    );
+                    *y += line_height;
+                    walk.path.push(PathStep::HeapNext);
+                    self.draw_expr(d, first, x, y, indent + 2, theme, bounds, fonts, walk);
+                    walk.path.pop();
+                }
+            }
+            Expr::Iterator(cursor) => {
+                let state = if cursor.borrow().is_done() {
+                    "done"
+                } else {
+                    "in progress"
+                };
+                fonts.draw_text(
+                    d,
+                    &format!("Iterator ({})", state),
+                    Vector2::new(x_pos, *y),
+                    14.0,
+                    theme.text,
+                );
+                *y += line_height;
+            }
+            Expr::Port(port) => {
+                fonts.draw_text(
+                    d,
+                    &format!("Port {} ({})", port.path, port.status()),
+                    Vector2::new(x_pos, *y),
+                    14.0,
+                    theme.text,
+                );
+                *y += line_height;
+            }
+            Expr::Struct(instance) => {
+                let expanded = self.node_expanded(walk, *y, true);
+
+                let indicator = if expanded { "▼" } else { "▶" };
+                fonts.draw_text(
+                    d,
+                    indicator,
+                    Vector2::new(x_pos - 15.0, *y),
+                    14.0,
+                    theme.text,
+                );
+
+                let instance = instance.borrow();
+                fonts.draw_text(
+                    d,
+                    &format!("Struct {}", instance.type_name),
+                    Vector2::new(x_pos, *y),
+                    14.0,
+                    theme.text,
+                );
+                *y += line_height;
+
+                if expanded {
+                    for (i, (field, value)) in
+                        instance.fields.iter().zip(&instance.values).enumerate()
+                    {
+                        if *y > bounds.y + bounds.height {
+                            break;
+                        }
+                        fonts.draw_text(
+                            d,
+                            &format!("{}:", field),
+                            Vector2::new(x_pos + indent_width, *y),
+                            12.0,
+                            theme.text_dim,
+                        );
+                        *y += line_height;
+                        walk.path.push(PathStep::Field(i));
+                        self.draw_expr(d, value, x, y, indent + 2, theme, bounds, fonts, walk);
+                        walk.path.pop();
                     }
                 }
             }
             Expr::HashTable(h) => {
-                let node_id = format!("hash_{}", *y as i32);
-                let expanded = self.expanded_nodes.get(&node_id).copied().unwrap_or(false);
+                let expanded = self.node_expanded(walk, *y, false);
 
                 let indicator = if expanded { "▼" } else { "▶" };
                 fonts.draw_text(
@@ -221,7 +700,7 @@ impl InspectorPane {
 
                 fonts.draw_text(
                     d,
-                    &format!("HashTable [{}]", h.len()),
+                    &format!("HashTable [{}]", h.borrow().len()),
                     Vector2::new(x_pos, *y),
                     14.0,
                     theme.text,
@@ -229,25 +708,42 @@ impl InspectorPane {
                 *y += line_height;
 
                 if expanded {
-                    for (key, value) in h.iter() {
+                    let table = h.borrow();
+                    let entries: Vec<(String, &HashKey, &Expr)> = table
+                        .iter()
+                        .map(|(key, value)| (write_string(&key.to_expr()), key, value))
+                        .collect();
+
+                    for (label, key, value) in entries.iter().take(HASH_PRINT_LIMIT) {
                         if *y > bounds.y + bounds.height {
                             break;
                         }
                         fonts.draw_text(
                             d,
-                            &format!("{:?}:", key),
+                            &format!("{}:", label),
+                            Vector2::new(x_pos + indent_width, *y),
+                            12.0,
+                            theme.text_dim,
+                        );
+                        *y += line_height;
+                        walk.path.push(PathStep::Key((*key).clone()));
+                        self.draw_expr(d, value, x, y, indent + 2, theme, bounds, fonts, walk);
+                        walk.path.pop();
+                    }
+                    if entries.len() > HASH_PRINT_LIMIT {
+                        fonts.draw_text(
+                            d,
+                            &format!("... {} more", entries.len() - HASH_PRINT_LIMIT),
                             Vector2::new(x_pos + indent_width, *y),
                             12.0,
                             theme.text_dim,
                         );
                         *y += line_height;
-                        self.draw_expr(d, value, x, y, indent + 2, theme, bounds, fonts);
                     }
                 }
             }
             Expr::Cons(car, cdr) => {
-                let node_id = format!("cons_{}", *y as i32);
-                let expanded = self.expanded_nodes.get(&node_id).copied().unwrap_or(true);
+                let expanded = self.node_expanded(walk, *y, true);
 
                 let indicator = if expanded { "▼" } else { "▶" };
                 fonts.draw_text(
@@ -270,7 +766,9 @@ impl InspectorPane {
                         theme.text_dim,
                     );
                     *y += line_height;
-                    self.draw_expr(d, car, x, y, indent + 2, theme, bounds, fonts);
+                    walk.path.push(PathStep::Car);
+                    self.draw_expr(d, car, x, y, indent + 2, theme, bounds, fonts, walk);
+                    walk.path.pop();
 
                     fonts.draw_text(
                         d,
@@ -280,16 +778,208 @@ impl InspectorPane {
                         theme.text_dim,
                     );
                     *y += line_height;
-                    self.draw_expr(d, cdr, x, y, indent + 2, theme, bounds, fonts);
+                    walk.path.push(PathStep::Cdr);
+                    self.draw_expr(d, cdr, x, y, indent + 2, theme, bounds, fonts, walk);
+                    walk.path.pop();
+                }
+            }
+            Expr::Matrix(matrix) => {
+                let matrix = matrix.borrow();
+                fonts.draw_text(
+                    d,
+                    &format!("Matrix [{}]", matrix.shape()),
+                    Vector2::new(x_pos, *y),
+                    14.0,
+                    theme.text,
+                );
+                *y += line_height;
+                for line in matrix_grid(&matrix, MATRIX_ROW_LIMIT, MATRIX_COL_LIMIT) {
+                    if *y > bounds.y + bounds.height {
+                        break;
+                    }
+                    fonts.draw_text(
+                        d,
+                        &line,
+                        Vector2::new(x_pos + indent_width, *y),
+                        14.0,
+                        theme.number,
+                    );
+                    *y += line_height;
                 }
             }
         }
     }
 
-    fn toggle_node(&mut self, y: i32) {
-        let node_id = format!("node_{}", y);
-        let expanded = self.expanded_nodes.get(&node_id).copied().unwrap_or(true);
-        self.expanded_nodes.insert(node_id, !expanded);
+    // Back and forward, then the breadcrumb, each part noted for clicks
+    fn draw_breadcrumb(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        y: f32,
+        theme: &Theme,
+        bounds: &Rectangle,
+        fonts: &IdeFonts,
+    ) {
+        let labels = self.breadcrumb();
+        let mut crumbs = Vec::new();
+        let mut x = bounds.x + 10.0;
+        let mut part =
+            |d: &mut RaylibDrawHandle, text: &str, color: Color, crumb: Option<Crumb>| {
+                let width = fonts.measure_text(text, 14.0).x;
+                fonts.draw_text(d, text, Vector2::new(x, y), 14.0, color);
+                if let Some(crumb) = crumb {
+                    crumbs.push((Rectangle::new(x, y, width, LINE_HEIGHT), crumb));
+                }
+                x += width + 6.0;
+            };
+        let arrow_color = |enabled: bool| if enabled { theme.text } else { theme.text_dim };
+        part(
+            d,
+            "◀",
+            arrow_color(!self.back.is_empty()),
+            Some(Crumb::Back),
+        );
+        part(
+            d,
+            "▶",
+            arrow_color(!self.forward.is_empty()),
+            Some(Crumb::Forward),
+        );
+        for (depth, label) in labels.iter().enumerate() {
+            if depth > 0 {
+                part(d, "›", theme.text_dim, None);
+            }
+            let current = depth == labels.len() - 1;
+            let color = if current { theme.text } else { theme.keyword };
+            part(d, label, color, Some(Crumb::Depth(depth)));
+        }
+        self.crumbs = crumbs;
+    }
+
+    // A value that has no parts, or the edit field in its place while it is being edited,
+    // with the reason under it when the last commit failed
+    #[allow(clippy::too_many_arguments)]
+    fn draw_leaf(
+        &self,
+        d: &mut RaylibDrawHandle,
+        text: &str,
+        color: Color,
+        x: f32,
+        y: &mut f32,
+        theme: &Theme,
+        fonts: &IdeFonts,
+        walk: &mut Walk,
+    ) {
+        let position = Vector2::new(x, *y);
+        walk.rows.push(Row {
+            top: position.y,
+            path: walk.path.clone(),
+            node: None,
+        });
+        *y += LINE_HEIGHT;
+        let Some(edit) = self.editing.as_ref().filter(|edit| edit.path == walk.path) else {
+            fonts.draw_text(d, text, position, 14.0, color);
+            return;
+        };
+        let width = fonts.measure_text(&edit.text, 14.0).x + 12.0;
+        let field = Rectangle::new(
+            position.x - 3.0,
+            position.y - 1.0,
+            width.max(80.0),
+            LINE_HEIGHT,
+        );
+        d.draw_rectangle_rec(field, theme.background);
+        d.draw_rectangle_lines_ex(field, 1.0, theme.focus_indicator);
+        fonts.draw_text(d, &edit.text, position, 14.0, theme.text);
+        let cursor_x = position.x + fonts.measure_text(&edit.text, 14.0).x;
+        d.draw_rectangle(
+            cursor_x.round() as i32,
+            position.y as i32,
+            2,
+            16,
+            theme.cursor,
+        );
+        if let Some(error) = &edit.error {
+            fonts.draw_text(d, error, Vector2::new(position.x, *y), 12.0, theme.error);
+            *y += LINE_HEIGHT;
+        }
+    }
+
+    // A function: its parameters, its body as source, then a table of the free variables
+    fn draw_function(
+        &self,
+        d: &mut RaylibDrawHandle,
+        function: &FunctionInfo,
+        y: &mut f32,
+        theme: &Theme,
+        bounds: &Rectangle,
+        fonts: &IdeFonts,
+    ) {
+        let x = bounds.x + 20.0;
+        let indent_width = 20.0;
+        let line_height = 18.0;
+
+        fonts.draw_text(
+            d,
+            &format!("Function {}", function.params),
+            Vector2::new(x, *y),
+            14.0,
+            theme.text,
+        );
+        *y += line_height;
+
+        fonts.draw_text(
+            d,
+            "body:",
+            Vector2::new(x + indent_width, *y),
+            12.0,
+            theme.text_dim,
+        );
+        *y += line_height;
+        for line in function.body.lines() {
+            if *y > bounds.y + bounds.height {
+                return;
+            }
+            fonts.draw_text(
+                d,
+                line,
+                Vector2::new(x + indent_width * 2.0, *y),
+                14.0,
+                theme.text,
+            );
+            *y += line_height;
+        }
+
+        if function.free_variables.is_empty() {
+            return;
+        }
+        fonts.draw_text(
+            d,
+            "free variables:",
+            Vector2::new(x + indent_width, *y),
+            12.0,
+            theme.text_dim,
+        );
+        *y += line_height;
+        for (name, value) in free_variable_rows(&function.free_variables) {
+            if *y > bounds.y + bounds.height {
+                break;
+            }
+            fonts.draw_text(
+                d,
+                &name,
+                Vector2::new(x + indent_width * 2.0, *y),
+                14.0,
+                theme.keyword,
+            );
+            fonts.draw_text(
+                d,
+                &value,
+                Vector2::new(x + indent_width * 2.0 + 140.0, *y),
+                14.0,
+                theme.text,
+            );
+            *y += line_height;
+        }
     }
 }
 
@@ -336,9 +1026,13 @@ impl Pane for InspectorPane {
             theme.text,
         );
 
-        // Draw content
-        let content_y = bounds.y + title_height + 5.0;
-        let content_height = bounds.height - title_height - 10.0;
+        // Draw content, under the breadcrumb line once there is a value
+        let mut content_y = bounds.y + title_height + 5.0;
+        if self.current_value.is_some() {
+            self.draw_breadcrumb(d, content_y, theme, &bounds, fonts);
+            content_y += LINE_HEIGHT + 4.0;
+        }
+        let content_height = bounds.y + bounds.height - content_y - 5.0;
 
         // Use scissor mode to clip content
         let mut scissor = d.begin_scissor_mode(
@@ -348,17 +1042,34 @@ impl Pane for InspectorPane {
             content_height as i32,
         );
 
-        if let Some(ref value) = self.current_value {
+        if let Some(function) = &self.function {
+            let mut y = content_y - (self.scroll_offset as f32);
+            self.draw_function(&mut scissor, function, &mut y, theme, &bounds, fonts);
+        } else if let Some(value) = self.shown_value() {
             let mut y = content_y - (self.scroll_offset as f32);
+            let mut walk = Walk {
+                path: self.focus.clone(),
+                rows: Vec::new(),
+            };
             self.draw_expr(
                 &mut scissor,
-                value,
+                &value,
                 bounds.x + 20.0,
                 &mut y,
                 0,
                 theme,
                 &bounds,
                 fonts,
+                &mut walk,
+            );
+            self.rows = walk.rows;
+        } else if self.current_value.is_some() {
+            fonts.draw_text(
+                &mut scissor,
+                "No longer part of the value",
+                Vector2::new(bounds.x + 10.0, content_y),
+                14.0,
+                theme.text_dim,
             );
         } else {
             fonts.draw_text(
@@ -393,6 +1104,50 @@ impl Pane for InspectorPane {
             handled = true;
         }
 
+        // Alt+Left and Alt+Right go back and forward through the values inspected
+        let alt =
+            rl.is_key_down(KeyboardKey::KEY_LEFT_ALT) || rl.is_key_down(KeyboardKey::KEY_RIGHT_ALT);
+        if alt && self.editing.is_none() {
+            if rl.is_key_pressed(KeyboardKey::KEY_LEFT) {
+                self.go_back();
+                handled = true;
+            } else if rl.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+                self.go_forward();
+                handled = true;
+            }
+        }
+
+        // An open edit field takes the keyboard: Enter commits, Escape cancels
+        if let Some(edit) = &mut self.editing {
+            match rl.get_key_pressed() {
+                Some(KeyboardKey::KEY_ENTER) => self.commit_edit(),
+                Some(KeyboardKey::KEY_ESCAPE) => self.editing = None,
+                Some(KeyboardKey::KEY_BACKSPACE) => {
+                    edit.text.pop();
+                }
+                _ => {}
+            }
+            if let (Some(edit), Some(char)) = (&mut self.editing, rl.get_char_pressed()) {
+                if !char.is_control() {
+                    edit.text.push(char);
+                }
+            }
+            handled = true;
+        }
+
+        // Ctrl/Cmd+C copies the inspected value, pretty-printed and readable (clipboard.rs)
+        let ctrl = rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL)
+            || rl.is_key_down(KeyboardKey::KEY_LEFT_SUPER);
+        if ctrl && rl.is_key_pressed(KeyboardKey::KEY_C) {
+            if let Some(value) = self.shown_value() {
+                if let Err(err) = rl.set_clipboard_text(&clipboard::value_text(&value)) {
+                    eprintln!("Clipboard error: {}", err);
+                }
+            }
+            handled = true;
+        }
+
         // Handle mouse clicks to expand/collapse nodes
         if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
             let mouse_pos = rl.get_mouse_position();
@@ -401,8 +1156,7 @@ impl Pane for InspectorPane {
                 && mouse_pos.y >= bounds.y
                 && mouse_pos.y <= bounds.y + bounds.height
             {
-                let clicked_y = (mouse_pos.y - bounds.y + self.scroll_offset as f32) as i32;
-                self.toggle_node(clicked_y);
+                self.click(mouse_pos, rl.get_time());
                 handled = true;
             }
         }
@@ -426,3 +1180,388 @@ impl Pane for InspectorPane {
         self
     }
 }
+
+// The matrix as text rows with right-aligned columns, cut to `max_rows` by `max_cols` with
+// "..." marking what was left out
+fn matrix_grid(matrix: &Matrix, max_rows: usize, max_cols: usize) -> Vec<String> {
+    let shown_cols = matrix.cols.min(max_cols);
+    let cells: Vec<Vec<String>> = (0..matrix.rows.min(max_rows))
+        .map(|row| {
+            matrix.row(row)[..shown_cols]
+                .iter()
+                .map(|value| format_float(*value))
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = (0..shown_cols)
+        .map(|col| cells.iter().map(|row| row[col].len()).max().unwrap_or(0))
+        .collect();
+
+    let mut lines: Vec<String> = cells
+        .iter()
+        .map(|row| {
+            let mut line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            if matrix.cols > shown_cols {
+                line.push_str("  ...");
+            }
+            line
+        })
+        .collect();
+    if matrix.rows > max_rows {
+        lines.push(format!("... {} more rows", matrix.rows - max_rows));
+    }
+    lines
+}
+
+// The free variable table as text: each name and its value cut short, or "unbound"
+fn free_variable_rows(free_variables: &[(String, Option<Expr>)]) -> Vec<(String, String)> {
+    free_variables
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Some(value) => Printer::new(PrintMode::Write)
+                    .with_max_depth(3)
+                    .with_max_length(8)
+                    .print(value),
+                None => "unbound".to_string(),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+// The variable a source names when it is nothing but that name, so inspecting its result
+// can write edits back to the variable
+pub fn binding_name(source: &str) -> Option<String> {
+    match Evaluator::parse(source).ok()? {
+        Expr::Symbol(SymbolData::Interned(symbol)) => Some(symbol.to_string()),
+        _ => None,
+    }
+}
+
+// The value at `path` under `root`, if it's still there
+fn value_at(root: &Expr, path: &[PathStep]) -> Option<Expr> {
+    let Some((step, rest)) = path.split_first() else {
+        return Some(root.clone());
+    };
+    let child = match (root, step) {
+        (Expr::List(items), PathStep::Index(i)) => items.get(*i).cloned(),
+        (Expr::Vector(items), PathStep::Index(i)) => items.borrow().get(*i).cloned(),
+        (Expr::Queue(items), PathStep::Index(i)) => items.borrow().get(*i).cloned(),
+        (Expr::Stack(items), PathStep::Index(i)) => {
+            let items = items.borrow();
+            items.len().checked_sub(i + 1).map(|i| items[i].clone())
+        }
+        (Expr::Struct(instance), PathStep::Field(i)) => instance.borrow().values.get(*i).cloned(),
+        (Expr::HashTable(table), PathStep::Key(key)) => table.borrow().get(key).cloned(),
+        (Expr::Heap(heap), PathStep::HeapNext) => heap.borrow().items.first().cloned(),
+        (Expr::Cons(car, _), PathStep::Car) => Some(car.as_ref().clone()),
+        (Expr::Cons(_, cdr), PathStep::Cdr) => Some(cdr.as_ref().clone()),
+        _ => None,
+    }?;
+    value_at(&child, rest)
+}
+
+// Whether some container on the way to the value at `path` is one that changes in place
+fn changes_in_place(root: &Expr, path: &[PathStep]) -> bool {
+    let mut node = root.clone();
+    for step in path {
+        if matches!(
+            node,
+            Expr::Vector(_)
+                | Expr::HashTable(_)
+                | Expr::Struct(_)
+                | Expr::Queue(_)
+                | Expr::Stack(_)
+        ) {
+            return true;
+        }
+        match value_at(&node, std::slice::from_ref(step)) {
+            Some(child) => node = child,
+            None => return false,
+        }
+    }
+    false
+}
+
+const VALUE_CHANGED: &str = "The value has changed since it was shown; inspect it again";
+
+// `root` with the value at `path` replaced by `value`. Vectors, hash tables, structs, queues
+// and stacks on the way are changed in place, as vector-set! and friends would; lists and
+// conses can't be, so they're rebuilt around the new value and a new root is returned.
+fn replace_at(root: &Expr, path: &[PathStep], value: Expr) -> Result<Expr, String> {
+    let Some((step, rest)) = path.split_first() else {
+        return Ok(value);
+    };
+    if *step == PathStep::HeapNext {
+        return Err("A heap's items stay in priority order and can't be edited".to_string());
+    }
+    let child = value_at(root, std::slice::from_ref(step)).ok_or(VALUE_CHANGED)?;
+    let child = replace_at(&child, rest, value)?;
+    match (root, step) {
+        (Expr::List(items), PathStep::Index(i)) => {
+            let mut items = items.as_ref().clone();
+            items[*i] = child;
+            Ok(Expr::new_list(items))
+        }
+        (Expr::Vector(items), PathStep::Index(i)) => {
+            items.borrow_mut()[*i] = child;
+            Ok(root.clone())
+        }
+        (Expr::Queue(items), PathStep::Index(i)) => {
+            items.borrow_mut()[*i] = child;
+            Ok(root.clone())
+        }
+        (Expr::Stack(items), PathStep::Index(i)) => {
+            let mut items = items.borrow_mut();
+            let top = items.len() - 1;
+            items[top - i] = child;
+            Ok(root.clone())
+        }
+        (Expr::Struct(instance), PathStep::Field(i)) => {
+            instance.borrow_mut().values[*i] = child;
+            Ok(root.clone())
+        }
+        (Expr::HashTable(table), PathStep::Key(key)) => {
+            table.borrow_mut().insert(key.clone(), child);
+            Ok(root.clone())
+        }
+        (Expr::Cons(_, cdr), PathStep::Car) => Ok(Expr::Cons(Box::new(child), cdr.clone())),
+        (Expr::Cons(car, _), PathStep::Cdr) => Ok(Expr::Cons(car.clone(), Box::new(child))),
+        _ => Err(VALUE_CHANGED.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Edits the leaf at `path` to `text`, returning the error the field shows if that failed
+    fn edit(inspector: &mut InspectorPane, path: Vec<PathStep>, text: &str) -> Option<String> {
+        inspector.begin_edit(path);
+        let edit = inspector.editing.as_mut().expect("no leaf there");
+        edit.text = text.to_string();
+        inspector.commit_edit();
+        inspector.editing.take().and_then(|edit| edit.error)
+    }
+
+    #[test]
+    fn test_editing_leaves_in_place() {
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .eval_str("(define shared (vector 1 (list 2 3)))")
+            .unwrap();
+        let value = evaluator.eval_str("shared").unwrap();
+        let mut inspector = InspectorPane::new("inspector".to_string());
+        inspector.inspect(value, evaluator.get_environment());
+
+        // The edit field starts out holding the leaf's readable form
+        inspector.begin_edit(vec![PathStep::Index(1), PathStep::Index(1)]);
+        assert_eq!(inspector.editing.as_ref().unwrap().text, "3");
+        inspector.editing = None;
+
+        // A vector changes in place, so every reference to it sees the edit, and the list
+        // inside it is rebuilt
+        let path = vec![PathStep::Index(1), PathStep::Index(0)];
+        assert_eq!(edit(&mut inspector, path, "\"two\""), None);
+        assert_eq!(
+            write_string(&evaluator.eval_str("shared").unwrap()),
+            "[1 (\"two\" 3)]"
+        );
+        assert!(inspector.take_rebind().is_none());
+
+        // The top of a stack comes first, and hash tables and structs change in place too
+        evaluator.eval_str("(defstruct point x y)").unwrap();
+        let value = evaluator
+            .eval_str(
+                "(list (make-stack 1 2) (alist->hash (list (cons \"k\" 1))) (make-point 1 2))",
+            )
+            .unwrap();
+        let key = HashKey::String("k".to_string());
+        inspector.inspect_binding("parts", value, evaluator.get_environment());
+        for (path, text) in [
+            (vec![PathStep::Index(0), PathStep::Index(0)], "top"),
+            (vec![PathStep::Index(1), PathStep::Key(key)], "10"),
+            (vec![PathStep::Index(2), PathStep::Field(1)], "20"),
+        ] {
+            assert_eq!(edit(&mut inspector, path, text), None);
+        }
+        assert_eq!(
+            write_string(inspector.current_value.as_ref().unwrap()),
+            "(#<stack top 1> #<hash-table \"k\" 10> #<point x: 1 y: 20>)"
+        );
+        assert!(inspector.take_rebind().is_none());
+
+        // An element of a list means a new list, which goes back in the variable
+        let value = evaluator.eval_str("(list 1 2)").unwrap();
+        inspector.inspect_binding("numbers", value, evaluator.get_environment());
+        assert_eq!(edit(&mut inspector, vec![PathStep::Index(0)], "9"), None);
+        let (name, value) = inspector.take_rebind().unwrap();
+        assert_eq!(name, "numbers");
+        assert_eq!(write_string(&value), "(9 2)");
+    }
+
+    #[test]
+    fn test_edit_errors_keep_the_field_open() {
+        let mut evaluator = Evaluator::new();
+        let value = evaluator.eval_str("(list 1 (vector 2))").unwrap();
+        let mut inspector = InspectorPane::new("inspector".to_string());
+        inspector.inspect(value, evaluator.get_environment());
+
+        let path = vec![PathStep::Index(1), PathStep::Index(0)];
+        assert_eq!(
+            edit(&mut inspector, path.clone(), "(1 2"),
+            Some("Unexpected end of input".to_string())
+        );
+        assert_eq!(
+            edit(&mut inspector, path.clone(), "  "),
+            Some("Enter a value".to_string())
+        );
+        // A list can only be replaced, and there's no variable to put the new one in
+        let error = edit(&mut inspector, vec![PathStep::Index(0)], "5").unwrap();
+        assert!(error.starts_with("Not read from a variable"), "{}", error);
+        assert_eq!(edit(&mut inspector, path, "3"), None);
+        assert_eq!(
+            write_string(inspector.current_value.as_ref().unwrap()),
+            "(1 [3])"
+        );
+
+        let value = evaluator.eval_str("(make-heap < 1 2)").unwrap();
+        inspector.inspect_binding("heap", value, evaluator.get_environment());
+        let error = edit(&mut inspector, vec![PathStep::HeapNext], "0").unwrap();
+        assert!(error.contains("priority order"), "{}", error);
+        // A failed commit leaves the field open with the error under it
+        inspector.begin_edit(vec![PathStep::HeapNext]);
+        inspector.commit_edit();
+        assert!(inspector.editing.unwrap().error.is_some());
+    }
+
+    #[test]
+    fn test_history_and_breadcrumb() {
+        let mut evaluator = Evaluator::new();
+        let mut inspector = InspectorPane::new("inspector".to_string());
+        inspector.inspect(Expr::Integer(1), evaluator.get_environment());
+        let config = evaluator
+            .eval_str("(alist->hash (list (cons \"db\" (vector \"host\" 5432))))")
+            .unwrap();
+        inspector.inspect_binding("config", config, evaluator.get_environment());
+        assert_eq!(inspector.breadcrumb(), vec!["config"]);
+
+        let db = vec![PathStep::Key(HashKey::String("db".to_string()))];
+        inspector.open_path(db.clone());
+        assert_eq!(inspector.breadcrumb(), vec!["config", "\"db\""]);
+        assert_eq!(
+            write_string(&inspector.shown_value().unwrap()),
+            "[\"host\" 5432]"
+        );
+        // Leaf paths still start at the inspected value, so edits reach the variable's table
+        let port = [db.clone(), vec![PathStep::Index(1)]].concat();
+        assert_eq!(edit(&mut inspector, port, "5433"), None);
+        inspector.open_path([db.clone(), vec![PathStep::Index(0)]].concat());
+        assert_eq!(inspector.breadcrumb(), vec!["config", "\"db\"", "[0]"]);
+
+        inspector.go_back();
+        assert_eq!(inspector.focus, db);
+        inspector.go_back();
+        assert!(inspector.focus.is_empty());
+        assert_eq!(
+            write_string(inspector.current_value.as_ref().unwrap()),
+            "#<hash-table \"db\" [\"host\" 5433]>"
+        );
+        inspector.go_back();
+        assert_eq!(inspector.current_value, Some(Expr::Integer(1)));
+        assert_eq!(inspector.breadcrumb(), vec!["value"]);
+        // Nothing further back
+        inspector.go_back();
+        assert_eq!(inspector.current_value, Some(Expr::Integer(1)));
+
+        inspector.go_forward();
+        assert_eq!(inspector.origin.as_deref(), Some("config"));
+        // Inspecting something new drops the forward history
+        inspector.inspect(Expr::Integer(2), evaluator.get_environment());
+        inspector.go_forward();
+        assert_eq!(inspector.current_value, Some(Expr::Integer(2)));
+        assert_eq!(inspector.back.len(), 2);
+    }
+
+    #[test]
+    fn test_clicks_are_resolved_by_path() {
+        let mut evaluator = Evaluator::new();
+        let value = evaluator.eval_str("(list (vector 1 2) 3)").unwrap();
+        let mut inspector = InspectorPane::new("inspector".to_string());
+        inspector.inspect(value, evaluator.get_environment());
+        let row = |top: f32, path: Vec<PathStep>, node: Option<bool>| Row { top, path, node };
+        inspector.rows = vec![
+            row(0.0, vec![], Some(true)),
+            row(36.0, vec![PathStep::Index(0)], Some(true)),
+            row(72.0, vec![PathStep::Index(0), PathStep::Index(0)], None),
+        ];
+        let first = vec![PathStep::Index(0)];
+
+        // A click collapses the vector, wherever its row is drawn next time
+        inspector.click(Vector2::new(40.0, 40.0), 1.0);
+        assert_eq!(inspector.expanded_nodes.get(&first), Some(&false));
+        let mut walk = Walk {
+            path: first.clone(),
+            rows: Vec::new(),
+        };
+        assert!(!inspector.node_expanded(&mut walk, 500.0, true));
+        // A second click straight after leaves it as it was and opens it instead
+        inspector.click(Vector2::new(40.0, 40.0), 1.2);
+        assert_eq!(inspector.focus, first);
+        inspector.go_back();
+        assert_eq!(inspector.expanded_nodes.get(&first), Some(&true));
+
+        // Double-clicking a leaf edits it; clicks a while apart don't
+        inspector.rows = vec![row(72.0, vec![PathStep::Index(1)], None)];
+        inspector.click(Vector2::new(40.0, 80.0), 5.0);
+        inspector.click(Vector2::new(40.0, 80.0), 6.0);
+        assert!(inspector.editing.is_none());
+        inspector.click(Vector2::new(40.0, 80.0), 6.1);
+        assert_eq!(inspector.editing.as_ref().unwrap().text, "3");
+        // Clicking off the rows closes the field
+        inspector.click(Vector2::new(40.0, 300.0), 7.0);
+        assert!(inspector.editing.is_none());
+    }
+
+    #[test]
+    fn test_binding_name() {
+        assert_eq!(binding_name(" scores "), Some("scores".to_string()));
+        assert_eq!(binding_name(":key"), None);
+        assert_eq!(binding_name("(car scores)"), None);
+        assert_eq!(binding_name("42"), None);
+    }
+
+    #[test]
+    fn test_matrix_grid_aligns_and_elides() {
+        let matrix = Matrix::from_rows(&[vec![1.0, -2.5, 3.0], vec![10.0, 0.0, 0.25]]).unwrap();
+        assert_eq!(
+            matrix_grid(&matrix, 20, 8),
+            vec![" 1.0  -2.5   3.0", "10.0   0.0  0.25"]
+        );
+        assert_eq!(
+            matrix_grid(&matrix, 1, 2),
+            vec!["1.0  -2.5  ...", "... 1 more rows"]
+        );
+    }
+
+    #[test]
+    fn test_free_variable_rows() {
+        let rows = free_variable_rows(&[
+            ("count".to_string(), Some(Expr::Integer(3))),
+            (
+                "items".to_string(),
+                Some(Expr::new_list((0..20).map(Expr::Integer).collect())),
+            ),
+            ("missing".to_string(), None),
+        ]);
+        assert_eq!(rows[0], ("count".to_string(), "3".to_string()));
+        assert_eq!(rows[1].1, "(0 1 2 3 4 5 6 7 ...)");
+        assert_eq!(rows[2], ("missing".to_string(), "unbound".to_string()));
+    }
+}
@@ -1,7 +1,14 @@
 use crate::ide::pane::PaneBounds;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{Expr, SymbolData};
 use raylib::prelude::*;
 use std::collections::HashMap;
 
+// Width of the strip around a split line that can be grabbed to drag it
+const SPLITTER_GRAB: f32 = 6.0;
+// Dragging a split line stops short of making either side smaller than this
+const MIN_PANE_SIZE: f32 = 80.0;
+
 #[derive(Debug, Clone)]
 pub enum LayoutNode {
     Leaf(String), // Pane ID
@@ -23,10 +30,150 @@ pub enum SplitDirection {
     Vertical,
 }
 
+// Where to look for a pane's neighbour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+// A split line as laid out. `path` leads from the root to its Split node, false taking
+// the first child and true the second; `bounds` is the area the split divides.
+#[derive(Debug, Clone)]
+struct Splitter {
+    path: Vec<bool>,
+    direction: SplitDirection,
+    bounds: Rectangle,
+    rect: Rectangle,
+}
+
+impl LayoutNode {
+    pub fn pane_ids(&self) -> Vec<&str> {
+        match self {
+            LayoutNode::Leaf(id) => vec![id.as_str()],
+            LayoutNode::Split { first, second, .. } => {
+                let mut ids = first.pane_ids();
+                ids.extend(second.pane_ids());
+                ids
+            }
+            LayoutNode::Tabs { panes, .. } => panes.iter().map(String::as_str).collect(),
+        }
+    }
+
+    fn contains(&self, pane_id: &str) -> bool {
+        self.pane_ids().contains(&pane_id)
+    }
+
+    // Puts each of the two panes in the other's slot
+    fn swap_panes(&mut self, a: &str, b: &str) {
+        let swap = |id: &mut String| {
+            if id == a {
+                *id = b.to_string();
+            } else if id == b {
+                *id = a.to_string();
+            }
+        };
+        match self {
+            LayoutNode::Leaf(id) => swap(id),
+            LayoutNode::Split { first, second, .. } => {
+                first.swap_panes(a, b);
+                second.swap_panes(a, b);
+            }
+            LayoutNode::Tabs { panes, .. } => panes.iter_mut().for_each(swap),
+        }
+    }
+
+    fn node_at_mut(&mut self, path: &[bool]) -> Option<&mut LayoutNode> {
+        let Some((&second_child, rest)) = path.split_first() else {
+            return Some(self);
+        };
+        match self {
+            LayoutNode::Split { first, second, .. } => {
+                if second_child { second } else { first }.node_at_mut(rest)
+            }
+            _ => None,
+        }
+    }
+
+    // Written as an s-expression the LISP reader reads back: a pane id, (h ratio first
+    // second) for side by side, (v ratio first second) for stacked, (tabs active id...)
+    pub fn to_text(&self) -> String {
+        match self {
+            LayoutNode::Leaf(id) => id.clone(),
+            LayoutNode::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => format!(
+                "({} {:.3} {} {})",
+                match direction {
+                    SplitDirection::Horizontal => "h",
+                    SplitDirection::Vertical => "v",
+                },
+                ratio,
+                first.to_text(),
+                second.to_text()
+            ),
+            LayoutNode::Tabs { active, panes } => format!("(tabs {} {})", active, panes.join(" ")),
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::from_expr(&Evaluator::parse(text).ok()?)
+    }
+
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        let name = |expr: &Expr| match expr {
+            Expr::Symbol(SymbolData::Interned(name)) => Some(name.to_string()),
+            _ => None,
+        };
+        let Expr::List(items) = expr else {
+            return name(expr).map(LayoutNode::Leaf);
+        };
+        let head = name(items.first()?)?;
+        if head == "tabs" {
+            let Some(Expr::Integer(active)) = items.get(1) else {
+                return None;
+            };
+            let panes = items[2..].iter().map(name).collect::<Option<Vec<_>>>()?;
+            let active = usize::try_from(*active).ok().filter(|a| *a < panes.len())?;
+            return Some(LayoutNode::Tabs { active, panes });
+        }
+        let direction = match head.as_str() {
+            "h" => SplitDirection::Horizontal,
+            "v" => SplitDirection::Vertical,
+            _ => return None,
+        };
+        let [_, ratio, first, second] = items.as_slice() else {
+            return None;
+        };
+        let ratio = match ratio {
+            Expr::Float(ratio) if ratio.is_finite() => *ratio as f32,
+            Expr::Integer(ratio) => *ratio as f32,
+            _ => return None,
+        };
+        Some(LayoutNode::Split {
+            direction,
+            ratio: ratio.clamp(0.05, 0.95),
+            first: Box::new(Self::from_expr(first)?),
+            second: Box::new(Self::from_expr(second)?),
+        })
+    }
+}
+
 pub struct LayoutManager {
     root: LayoutNode,
     pane_bounds: HashMap<String, PaneBounds>,
     focused_pane: Option<String>,
+    // Set by calculate_bounds so the layout can be redone after a drag or rearrangement
+    window_bounds: Option<Rectangle>,
+    splitters: Vec<Splitter>,
+    dragging: Option<Splitter>,
+    // A pane shown over the whole window until it's restored
+    maximized: Option<String>,
 }
 
 impl LayoutManager {
@@ -35,6 +182,10 @@ impl LayoutManager {
             root: LayoutNode::Leaf(initial_pane),
             pane_bounds: HashMap::new(),
             focused_pane: None,
+            window_bounds: None,
+            splitters: Vec::new(),
+            dragging: None,
+            maximized: None,
         }
     }
 
@@ -51,27 +202,95 @@ impl LayoutManager {
                         direction: SplitDirection::Vertical,
                         ratio: 0.7,
                         first: Box::new(LayoutNode::Leaf("editor".to_string())),
-                        second: Box::new(LayoutNode::Leaf("repl".to_string())),
+                        // REPL, program output and search results share the strip under
+                        // the editor
+                        second: Box::new(LayoutNode::Split {
+                            direction: SplitDirection::Horizontal,
+                            ratio: 0.55,
+                            first: Box::new(LayoutNode::Leaf("repl".to_string())),
+                            second: Box::new(LayoutNode::Split {
+                                direction: SplitDirection::Vertical,
+                                ratio: 0.5,
+                                first: Box::new(LayoutNode::Leaf("transcript".to_string())),
+                                second: Box::new(LayoutNode::Leaf("results".to_string())),
+                            }),
+                        }),
                     }),
                     second: Box::new(LayoutNode::Split {
                         direction: SplitDirection::Vertical,
-                        ratio: 0.5,
-                        first: Box::new(LayoutNode::Leaf("symbols".to_string())),
-                        second: Box::new(LayoutNode::Leaf("inspector".to_string())),
+                        ratio: 0.45,
+                        first: Box::new(LayoutNode::Split {
+                            direction: SplitDirection::Vertical,
+                            ratio: 0.5,
+                            first: Box::new(LayoutNode::Leaf("outline".to_string())),
+                            second: Box::new(LayoutNode::Leaf("history".to_string())),
+                        }),
+                        second: Box::new(LayoutNode::Split {
+                            direction: SplitDirection::Vertical,
+                            ratio: 0.5,
+                            first: Box::new(LayoutNode::Leaf("symbols".to_string())),
+                            second: Box::new(LayoutNode::Leaf("inspector".to_string())),
+                        }),
                     }),
                 }),
             },
             pane_bounds: HashMap::new(),
             focused_pane: Some("editor".to_string()),
+            window_bounds: None,
+            splitters: Vec::new(),
+            dragging: None,
+            maximized: None,
         }
     }
 
+    pub fn root(&self) -> &LayoutNode {
+        &self.root
+    }
+
+    // Replaces the arrangement with one saved by `LayoutNode::to_text`, provided it holds
+    // exactly the panes in `pane_ids`; a layout saved before a pane was added is ignored
+    pub fn restore(&mut self, text: &str, pane_ids: &[&str]) -> bool {
+        let Some(root) = LayoutNode::parse(text) else {
+            return false;
+        };
+        let mut saved = root.pane_ids();
+        let mut expected = pane_ids.to_vec();
+        saved.sort_unstable();
+        expected.sort_unstable();
+        if saved != expected {
+            return false;
+        }
+        self.root = root;
+        self.relayout();
+        true
+    }
+
     pub fn calculate_bounds(&mut self, window_bounds: Rectangle) {
+        self.window_bounds = Some(window_bounds);
         self.pane_bounds.clear();
-        self.calculate_node_bounds(&self.root.clone(), window_bounds);
+        self.splitters.clear();
+        if let Some(pane_id) = self.maximized.clone() {
+            if self.root.contains(&pane_id) {
+                self.calculate_node_bounds(&LayoutNode::Leaf(pane_id), window_bounds, &mut vec![]);
+                return;
+            }
+            self.maximized = None;
+        }
+        self.calculate_node_bounds(&self.root.clone(), window_bounds, &mut vec![]);
+    }
+
+    fn relayout(&mut self) {
+        if let Some(window_bounds) = self.window_bounds {
+            self.calculate_bounds(window_bounds);
+        }
     }
 
-    fn calculate_node_bounds(&mut self, node: &LayoutNode, bounds: Rectangle) {
+    fn calculate_node_bounds(
+        &mut self,
+        node: &LayoutNode,
+        bounds: Rectangle,
+        path: &mut Vec<bool>,
+    ) {
         match node {
             LayoutNode::Leaf(pane_id) => {
                 self.pane_bounds.insert(
@@ -89,50 +308,60 @@ impl LayoutManager {
                 ratio,
                 first,
                 second,
-            } => match direction {
-                SplitDirection::Horizontal => {
-                    let first_width = bounds.width * ratio;
-                    self.calculate_node_bounds(
-                        first,
-                        Rectangle {
-                            x: bounds.x,
-                            y: bounds.y,
-                            width: first_width,
-                            height: bounds.height,
-                        },
-                    );
-                    self.calculate_node_bounds(
-                        second,
-                        Rectangle {
-                            x: bounds.x + first_width,
-                            y: bounds.y,
-                            width: bounds.width - first_width,
-                            height: bounds.height,
-                        },
-                    );
-                }
-                SplitDirection::Vertical => {
-                    let first_height = bounds.height * ratio;
-                    self.calculate_node_bounds(
-                        first,
-                        Rectangle {
-                            x: bounds.x,
-                            y: bounds.y,
-                            width: bounds.width,
-                            height: first_height,
-                        },
-                    );
-                    self.calculate_node_bounds(
-                        second,
-                        Rectangle {
-                            x: bounds.x,
-                            y: bounds.y + first_height,
-                            width: bounds.width,
-                            height: bounds.height - first_height,
-                        },
-                    );
-                }
-            },
+            } => {
+                let (first_bounds, second_bounds, rect) = match direction {
+                    SplitDirection::Horizontal => {
+                        let first_width = bounds.width * ratio;
+                        (
+                            Rectangle {
+                                width: first_width,
+                                ..bounds
+                            },
+                            Rectangle {
+                                x: bounds.x + first_width,
+                                width: bounds.width - first_width,
+                                ..bounds
+                            },
+                            Rectangle {
+                                x: bounds.x + first_width - SPLITTER_GRAB / 2.0,
+                                width: SPLITTER_GRAB,
+                                ..bounds
+                            },
+                        )
+                    }
+                    SplitDirection::Vertical => {
+                        let first_height = bounds.height * ratio;
+                        (
+                            Rectangle {
+                                height: first_height,
+                                ..bounds
+                            },
+                            Rectangle {
+                                y: bounds.y + first_height,
+                                height: bounds.height - first_height,
+                                ..bounds
+                            },
+                            Rectangle {
+                                y: bounds.y + first_height - SPLITTER_GRAB / 2.0,
+                                height: SPLITTER_GRAB,
+                                ..bounds
+                            },
+                        )
+                    }
+                };
+                self.splitters.push(Splitter {
+                    path: path.clone(),
+                    direction: *direction,
+                    bounds,
+                    rect,
+                });
+                path.push(false);
+                self.calculate_node_bounds(first, first_bounds, path);
+                path.pop();
+                path.push(true);
+                self.calculate_node_bounds(second, second_bounds, path);
+                path.pop();
+            }
             LayoutNode::Tabs { active, panes } => {
                 if let Some(pane_id) = panes.get(*active) {
                     self.pane_bounds.insert(
@@ -157,10 +386,124 @@ impl LayoutManager {
         &self.pane_bounds
     }
 
+    // Focusing a pane hidden behind a maximized one brings the whole layout back
     pub fn focus_pane(&mut self, pane_id: String) {
+        if self.maximized.as_ref().is_some_and(|id| *id != pane_id) {
+            self.maximized = None;
+            self.relayout();
+        }
         self.focused_pane = Some(pane_id);
     }
 
+    pub fn maximized_pane(&self) -> Option<&String> {
+        self.maximized.as_ref()
+    }
+
+    pub fn toggle_maximize(&mut self, pane_id: &str) {
+        self.maximized = match &self.maximized {
+            Some(id) if id == pane_id => None,
+            _ => Some(pane_id.to_string()),
+        };
+        self.relayout();
+    }
+
+    // The split line under the point, or the one being dragged, for highlighting
+    pub fn splitter_highlight(&self, x: f32, y: f32) -> Option<Rectangle> {
+        if let Some(dragging) = &self.dragging {
+            return self
+                .splitters
+                .iter()
+                .find(|splitter| splitter.path == dragging.path)
+                .map(|splitter| splitter.rect);
+        }
+        self.splitter_at(x, y).map(|splitter| splitter.rect)
+    }
+
+    fn splitter_at(&self, x: f32, y: f32) -> Option<&Splitter> {
+        self.splitters
+            .iter()
+            .find(|splitter| splitter.rect.check_collision_point_rec(Vector2::new(x, y)))
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging.is_some()
+    }
+
+    // Starts dragging the split line under the point, if there is one
+    pub fn begin_drag(&mut self, x: f32, y: f32) -> bool {
+        self.dragging = self.splitter_at(x, y).cloned();
+        self.dragging.is_some()
+    }
+
+    pub fn drag_to(&mut self, x: f32, y: f32) {
+        let Some(splitter) = &self.dragging else {
+            return;
+        };
+        let bounds = splitter.bounds;
+        let (offset, length) = match splitter.direction {
+            SplitDirection::Horizontal => (x - bounds.x, bounds.width),
+            SplitDirection::Vertical => (y - bounds.y, bounds.height),
+        };
+        if length <= 0.0 {
+            return;
+        }
+        let min = (MIN_PANE_SIZE / length).min(0.5);
+        let new_ratio = (offset / length).clamp(min, 1.0 - min);
+        if let Some(LayoutNode::Split { ratio, .. }) = self.root.node_at_mut(&splitter.path) {
+            *ratio = new_ratio;
+        }
+        self.relayout();
+    }
+
+    // Returns whether a drag was in progress
+    pub fn end_drag(&mut self) -> bool {
+        self.dragging.take().is_some()
+    }
+
+    // The pane across the given edge of `pane_id`, sharing the most of that edge
+    pub fn neighbor(&self, pane_id: &str, side: Side) -> Option<String> {
+        let from = self.pane_bounds.get(pane_id)?;
+        let overlap = |start: f32, length: f32, other_start: f32, other_length: f32| {
+            (start + length).min(other_start + other_length) - start.max(other_start)
+        };
+        self.pane_bounds
+            .iter()
+            .filter(|(id, _)| id.as_str() != pane_id)
+            .filter_map(|(id, b)| {
+                let (gap, shared) = match side {
+                    Side::Left => (
+                        b.x + b.width - from.x,
+                        overlap(from.y, from.height, b.y, b.height),
+                    ),
+                    Side::Right => (
+                        from.x + from.width - b.x,
+                        overlap(from.y, from.height, b.y, b.height),
+                    ),
+                    Side::Up => (
+                        b.y + b.height - from.y,
+                        overlap(from.x, from.width, b.x, b.width),
+                    ),
+                    Side::Down => (
+                        from.y + from.height - b.y,
+                        overlap(from.x, from.width, b.x, b.width),
+                    ),
+                };
+                (gap.abs() < 1.0 && shared > 0.0).then_some((id, shared))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(id, _)| id.clone())
+    }
+
+    // Moves a pane into its neighbour's slot and the neighbour into its own
+    pub fn swap_with_neighbor(&mut self, pane_id: &str, side: Side) -> bool {
+        let Some(neighbor) = self.neighbor(pane_id, side) else {
+            return false;
+        };
+        self.root.swap_panes(pane_id, &neighbor);
+        self.relayout();
+        true
+    }
+
     pub fn get_focused_pane(&self) -> Option<&String> {
         self.focused_pane.as_ref()
     }
@@ -214,3 +557,117 @@ impl LayoutManager {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Rectangle = Rectangle {
+        x: 0.0,
+        y: 0.0,
+        width: 1000.0,
+        height: 800.0,
+    };
+
+    fn default_layout() -> LayoutManager {
+        let mut layout = LayoutManager::create_default();
+        layout.calculate_bounds(WINDOW);
+        layout
+    }
+
+    fn width(layout: &LayoutManager, pane_id: &str) -> f32 {
+        layout.get_pane_bounds(pane_id).unwrap().width
+    }
+
+    #[test]
+    fn test_dragging_a_splitter() {
+        let mut layout = default_layout();
+        assert_eq!(width(&layout, "file_tree"), 200.0);
+        assert!(!layout.begin_drag(100.0, 400.0));
+
+        assert!(layout.begin_drag(201.0, 400.0));
+        layout.drag_to(300.0, 10.0);
+        assert_eq!(width(&layout, "file_tree"), 300.0);
+        // The dragged line is highlighted wherever the mouse goes
+        assert!(layout.splitter_highlight(900.0, 10.0).is_some());
+        // Neither side shrinks below the minimum
+        layout.drag_to(-50.0, 10.0);
+        assert_eq!(width(&layout, "file_tree"), MIN_PANE_SIZE);
+        assert!(layout.end_drag());
+        assert!(!layout.end_drag());
+        assert!(layout.splitter_highlight(900.0, 10.0).is_none());
+    }
+
+    #[test]
+    fn test_maximize_and_restore() {
+        let mut layout = default_layout();
+        layout.toggle_maximize("repl");
+        assert_eq!(layout.get_all_pane_bounds().len(), 1);
+        assert_eq!(width(&layout, "repl"), 1000.0);
+        assert!(layout.splitter_highlight(200.0, 400.0).is_none());
+
+        layout.toggle_maximize("repl");
+        assert_eq!(layout.get_all_pane_bounds().len(), 9);
+
+        // Switching to a hidden pane restores the layout
+        layout.toggle_maximize("repl");
+        layout.focus_pane("outline".to_string());
+        assert_eq!(layout.maximized_pane(), None);
+        assert_eq!(layout.get_all_pane_bounds().len(), 9);
+    }
+
+    #[test]
+    fn test_swapping_neighbors() {
+        let mut layout = default_layout();
+        assert_eq!(
+            layout.neighbor("editor", Side::Left).as_deref(),
+            Some("file_tree")
+        );
+        assert_eq!(
+            layout.neighbor("editor", Side::Down).as_deref(),
+            Some("repl")
+        );
+        assert_eq!(
+            layout.neighbor("repl", Side::Right).as_deref(),
+            Some("results")
+        );
+        assert_eq!(layout.neighbor("file_tree", Side::Left), None);
+
+        let editor_bounds = layout.get_pane_bounds("editor").unwrap().clone();
+        assert!(layout.swap_with_neighbor("editor", Side::Left));
+        let moved = layout.get_pane_bounds("file_tree").unwrap();
+        assert_eq!(
+            (moved.x, moved.width),
+            (editor_bounds.x, editor_bounds.width)
+        );
+        assert_eq!(layout.get_pane_bounds("editor").unwrap().x, 0.0);
+        assert!(!layout.swap_with_neighbor("editor", Side::Left));
+    }
+
+    #[test]
+    fn test_layout_text_round_trip() {
+        let mut layout = default_layout();
+        layout.begin_drag(201.0, 400.0);
+        layout.drag_to(250.0, 0.0);
+        layout.end_drag();
+        layout.swap_with_neighbor("symbols", Side::Down);
+        let text = layout.root().to_text();
+        assert!(text.starts_with("(h 0.250 file_tree (h 0.625 (v 0.700 editor"));
+
+        let pane_ids = layout.root().pane_ids();
+        let mut restored = default_layout();
+        assert!(restored.restore(&text, &pane_ids));
+        assert_eq!(restored.root().to_text(), text);
+        assert_eq!(width(&restored, "file_tree"), 250.0);
+
+        // A saved layout missing a pane, or that doesn't read, leaves the layout alone
+        assert!(!restored.restore("(h 0.5 editor repl)", &pane_ids));
+        assert!(!restored.restore("(h 0.5 editor", &pane_ids));
+        assert!(!restored.restore("(x 0.5 editor repl)", &["editor", "repl"]));
+        assert_eq!(restored.root().to_text(), text);
+        assert_eq!(
+            LayoutNode::parse("(tabs 1 a b)").map(|node| node.to_text()),
+            Some("(tabs 1 a b)".to_string())
+        );
+    }
+}
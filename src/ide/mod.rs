@@ -1,14 +1,21 @@
 pub mod app;
+pub mod clipboard;
 pub mod editor;
 pub mod file_tree;
 pub mod fonts;
+pub mod git;
+pub mod history_pane;
 pub mod ide_state;
 pub mod inspector;
 pub mod layout;
+pub mod outline_pane;
 pub mod pane;
+pub mod recent_files;
 pub mod repl_pane;
+pub mod results_pane;
 pub mod symbol_browser;
 pub mod syntax;
 pub mod theme;
+pub mod transcript_pane;
 
 pub use app::IdeApp;
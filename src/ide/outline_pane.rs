@@ -0,0 +1,280 @@
+use crate::ide::fonts::IdeFonts;
+use crate::ide::pane::Pane;
+use crate::ide::theme::Theme;
+use crate::interpreter::xref::{Definition, Location, XrefIndex};
+use raylib::prelude::*;
+use std::any::Any;
+
+const TITLE_HEIGHT: f32 = 25.0;
+const ROW_HEIGHT: f32 = 18.0;
+
+// The defuns, defines and defmacros of the editor buffer in source order. The IDE hands
+// it the buffer whenever the text changes; Enter or a click on an entry asks the IDE to
+// move the editor there.
+pub struct OutlinePane {
+    id: String,
+    title: String,
+    // Editor content version the entries were built from
+    version: Option<u64>,
+    entries: Vec<Definition>,
+    selected_index: usize,
+    scroll_offset: f32,
+    has_focus: bool,
+    jump_request: Option<Location>,
+}
+
+impl OutlinePane {
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            title: "Outline".to_string(),
+            version: None,
+            entries: Vec::new(),
+            selected_index: 0,
+            scroll_offset: 0.0,
+            has_focus: false,
+            jump_request: None,
+        }
+    }
+
+    pub fn is_current(&self, version: u64) -> bool {
+        self.version == Some(version)
+    }
+
+    // Forms that don't parse mid-edit are left out until they do
+    pub fn refresh(&mut self, version: u64, file: &str, source: &str) {
+        self.version = Some(version);
+        self.entries = XrefIndex::build(&[(file.to_string(), source.to_string())]).definitions;
+        self.selected_index = self
+            .selected_index
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn entries(&self) -> &[Definition] {
+        &self.entries
+    }
+
+    // The entry picked since the last call, for the IDE to show in the editor
+    pub fn take_jump_request(&mut self) -> Option<Location> {
+        self.jump_request.take()
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let last = self.entries.len() as isize - 1;
+        self.selected_index = (self.selected_index as isize + delta).clamp(0, last) as usize;
+    }
+
+    fn open_selected(&mut self) {
+        if let Some(entry) = self.entries.get(self.selected_index) {
+            self.jump_request = Some(entry.location.clone());
+        }
+    }
+
+    fn ensure_selection_visible(&mut self, view_height: f32) {
+        let top = self.selected_index as f32 * ROW_HEIGHT;
+        if top < self.scroll_offset {
+            self.scroll_offset = top;
+        } else if top + ROW_HEIGHT > self.scroll_offset + view_height {
+            self.scroll_offset = top + ROW_HEIGHT - view_height;
+        }
+    }
+}
+
+impl Pane for OutlinePane {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn draw(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        bounds: Rectangle,
+        theme: &Theme,
+        fonts: &IdeFonts,
+    ) {
+        d.draw_rectangle_rec(bounds, theme.surface);
+        if self.has_focus {
+            d.draw_rectangle_lines_ex(bounds, 2.0, theme.focus_indicator);
+        } else {
+            d.draw_rectangle_lines_ex(bounds, 1.0, theme.border);
+        }
+
+        d.draw_rectangle(
+            bounds.x as i32,
+            bounds.y as i32,
+            bounds.width as i32,
+            TITLE_HEIGHT as i32,
+            theme.panel,
+        );
+        fonts.draw_text(
+            d,
+            &format!("{} ({})", self.title, self.entries.len()),
+            Vector2::new(bounds.x + 5.0, bounds.y + 5.0),
+            16.0,
+            theme.text,
+        );
+
+        let content_y = bounds.y + TITLE_HEIGHT + 2.0;
+        let content_height = (bounds.height - TITLE_HEIGHT - 4.0).max(0.0);
+        let mut scissor = d.begin_scissor_mode(
+            bounds.x as i32,
+            content_y as i32,
+            bounds.width as i32,
+            content_height as i32,
+        );
+
+        let mut y = content_y - self.scroll_offset;
+        for (index, entry) in self.entries.iter().enumerate() {
+            if y >= content_y - ROW_HEIGHT && y < content_y + content_height {
+                if index == self.selected_index {
+                    scissor.draw_rectangle(
+                        bounds.x as i32,
+                        y as i32,
+                        bounds.width as i32,
+                        ROW_HEIGHT as i32,
+                        theme.selection,
+                    );
+                }
+                // Same icons as the symbol browser
+                let (icon, color) = match entry.kind.as_str() {
+                    "defun" => ("ƒ", theme.function),
+                    "defmacro" => ("M", theme.macro_color),
+                    _ => ("v", theme.text),
+                };
+                fonts.draw_text(
+                    &mut scissor,
+                    icon,
+                    Vector2::new(bounds.x + 5.0, y + 2.0),
+                    14.0,
+                    color,
+                );
+                fonts.draw_text(
+                    &mut scissor,
+                    &entry.name,
+                    Vector2::new(bounds.x + 25.0, y + 2.0),
+                    14.0,
+                    theme.text,
+                );
+                if bounds.width > 150.0 {
+                    fonts.draw_text(
+                        &mut scissor,
+                        &entry.location.line.to_string(),
+                        Vector2::new(bounds.x + bounds.width - 45.0, y + 3.0),
+                        12.0,
+                        theme.text_dim,
+                    );
+                }
+            }
+            y += ROW_HEIGHT;
+        }
+    }
+
+    fn handle_input(&mut self, rl: &mut RaylibHandle, bounds: Rectangle) -> bool {
+        if !self.has_focus {
+            return false;
+        }
+        let view_height = (bounds.height - TITLE_HEIGHT - 4.0).max(0.0);
+        let mut handled = false;
+
+        let wheel_move = rl.get_mouse_wheel_move();
+        if wheel_move != 0.0 {
+            let max_scroll = (self.entries.len() as f32 * ROW_HEIGHT - view_height).max(0.0);
+            self.scroll_offset = (self.scroll_offset - wheel_move * 20.0).clamp(0.0, max_scroll);
+            handled = true;
+        }
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            let mouse = rl.get_mouse_position();
+            let local_y = mouse.y - (bounds.y + TITLE_HEIGHT + 2.0) + self.scroll_offset;
+            if mouse.y > bounds.y + TITLE_HEIGHT && local_y >= 0.0 {
+                let row = (local_y / ROW_HEIGHT) as usize;
+                if row < self.entries.len() {
+                    self.selected_index = row;
+                    self.open_selected();
+                    handled = true;
+                }
+            }
+        }
+
+        if let Some(key) = rl.get_key_pressed() {
+            match key {
+                KeyboardKey::KEY_UP => self.move_selection(-1),
+                KeyboardKey::KEY_DOWN => self.move_selection(1),
+                KeyboardKey::KEY_ENTER => self.open_selected(),
+                _ => return handled,
+            }
+            self.ensure_selection_visible(view_height);
+            handled = true;
+        }
+
+        handled
+    }
+
+    fn on_focus(&mut self) {
+        self.has_focus = true;
+    }
+
+    fn on_blur(&mut self) {
+        self.has_focus = false;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutlinePane;
+
+    #[test]
+    fn test_refresh_lists_definitions_in_order() {
+        let mut pane = OutlinePane::new("outline".to_string());
+        assert!(!pane.is_current(0));
+        let source = "(define limit 10)\n\n(defun clamp (x)\n  (min x limit))\n(clamp 12)\n(defmacro twice (x) x)\n";
+        pane.refresh(1, "untitled", source);
+        assert!(pane.is_current(1));
+        let names: Vec<(&str, &str, usize)> = pane
+            .entries()
+            .iter()
+            .map(|entry| {
+                (
+                    entry.kind.as_str(),
+                    entry.name.as_str(),
+                    entry.location.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("define", "limit", 1),
+                ("defun", "clamp", 3),
+                ("defmacro", "twice", 6)
+            ]
+        );
+
+        pane.move_selection(1);
+        pane.open_selected();
+        let location = pane.take_jump_request().unwrap();
+        assert_eq!((location.line, location.column), (3, 8));
+
+        // An unfinished form drops out until it parses, and the selection stays in range
+        pane.move_selection(1);
+        pane.refresh(2, "untitled", "(define limit 10)\n(defun clamp (x)");
+        assert_eq!(pane.entries().len(), 1);
+        pane.open_selected();
+        assert_eq!(pane.take_jump_request().unwrap().line, 1);
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+
+const MAX_RECENT_FILES: usize = 20;
+
+// Most-recently-used files, newest first
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    // Moves `path` to the front, returning false if it was already there
+    pub fn touch(&mut self, path: &Path) -> bool {
+        if self.paths.first().map(|p| p.as_path()) == Some(path) {
+            return false;
+        }
+        self.paths.retain(|p| p != path);
+        self.paths.insert(0, path.to_path_buf());
+        self.paths.truncate(MAX_RECENT_FILES);
+        true
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.paths.retain(|p| p != path);
+    }
+
+    // Reads the `recent <path>` lines of an IDE state file, skipping anything else
+    pub fn parse(text: &str) -> Self {
+        let mut recent = Self::new();
+        for line in text.lines() {
+            if let Some(path) = line.strip_prefix("recent ") {
+                let path = PathBuf::from(path);
+                if !recent.paths.contains(&path) && recent.paths.len() < MAX_RECENT_FILES {
+                    recent.paths.push(path);
+                }
+            }
+        }
+        recent
+    }
+
+    pub fn to_text(&self) -> String {
+        self.paths
+            .iter()
+            .map(|path| format!("recent {}\n", path.display()))
+            .collect()
+    }
+
+    // Files deleted since the list was saved are dropped on load
+    pub fn drop_missing(&mut self) {
+        self.paths.retain(|path| path.is_file());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecentFiles, MAX_RECENT_FILES};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_touch_orders_by_recency() {
+        let mut recent = RecentFiles::new();
+        assert!(recent.touch(Path::new("a.lisp")));
+        assert!(recent.touch(Path::new("b.lisp")));
+        assert!(recent.touch(Path::new("a.lisp")));
+        assert!(!recent.touch(Path::new("a.lisp")));
+        assert_eq!(
+            recent.paths(),
+            &[PathBuf::from("a.lisp"), PathBuf::from("b.lisp")]
+        );
+
+        for i in 0..MAX_RECENT_FILES + 5 {
+            recent.touch(Path::new(&format!("{}.lisp", i)));
+        }
+        assert_eq!(recent.paths().len(), MAX_RECENT_FILES);
+    }
+
+    #[test]
+    fn test_state_text_round_trip() {
+        let mut recent = RecentFiles::new();
+        recent.touch(Path::new("/tmp/one.lisp"));
+        recent.touch(Path::new("/tmp/two words.lisp"));
+
+        let text = recent.to_text();
+        assert_eq!(text, "recent /tmp/two words.lisp\nrecent /tmp/one.lisp\n");
+        assert_eq!(RecentFiles::parse(&text), recent);
+        assert_eq!(
+            RecentFiles::parse("theme dark\nrecent x.lisp\nrecent x.lisp\n").paths(),
+            &[PathBuf::from("x.lisp")]
+        );
+    }
+}
@@ -1,11 +1,22 @@
+use crate::ide::clipboard;
 use crate::ide::fonts::IdeFonts;
+use crate::ide::inspector::binding_name;
 use crate::ide::pane::Pane;
 use crate::ide::theme::Theme;
+use crate::interpreter::completion::complete;
+use crate::interpreter::environment::Checkpoint;
 use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::pretty::{pretty_print, DEFAULT_PRETTY_WIDTH};
+use crate::interpreter::printer::write_string;
+use crate::interpreter::remote::Response;
+use crate::interpreter::types::Expr;
 use raylib::prelude::*;
 use std::any::Any;
 use std::collections::VecDeque;
 
+// How many evaluations Ctrl+Z can revert, most recent first
+const MAX_CHECKPOINTS: usize = 50;
+
 struct ReplLine {
     text: String,
     is_input: bool,
@@ -23,6 +34,16 @@ pub struct ReplPane {
     scroll_offset: i32,
     has_focus: bool,
     evaluator: Evaluator,
+    // The globals before each recent evaluation, with its source, newest last
+    checkpoints: Vec<(String, Checkpoint)>,
+    // The value of the last successful evaluation, which Ctrl+C copies, and the variable
+    // it came from when the input was just that name
+    last_value: Option<Expr>,
+    last_binding: Option<String>,
+    // Set by Ctrl+Shift+I: the last value and its variable, for the app to inspect
+    inspect_request: Option<(Expr, Option<String>)>,
+    // Characters that fit across the pane, measured at draw time for pretty-printing
+    columns: usize,
 }
 
 impl ReplPane {
@@ -50,6 +71,11 @@ impl ReplPane {
             scroll_offset: 0,
             has_focus: false,
             evaluator,
+            checkpoints: Vec::new(),
+            last_value: None,
+            last_binding: None,
+            inspect_request: None,
+            columns: DEFAULT_PRETTY_WIDTH,
         }
     }
 
@@ -69,15 +95,27 @@ impl ReplPane {
         self.command_history.push(self.current_input.clone());
         self.command_history_index = None;
 
+        if self.checkpoints.len() == MAX_CHECKPOINTS {
+            self.checkpoints.remove(0);
+        }
+        self.checkpoints.push((
+            self.current_input.clone(),
+            self.evaluator.environment.checkpoint(),
+        ));
+
         // Evaluate the expression
         match self.evaluator.eval_str(&self.current_input) {
             Ok(result) => {
-                let formatted = self.format_expr(&result);
-                self.history.push_back(ReplLine {
-                    text: formatted,
-                    is_input: false,
-                    is_error: false,
-                });
+                let formatted = self.format_result(&result);
+                for line in formatted.lines() {
+                    self.history.push_back(ReplLine {
+                        text: line.to_string(),
+                        is_input: false,
+                        is_error: false,
+                    });
+                }
+                self.last_value = Some(result);
+                self.last_binding = binding_name(&self.current_input);
             }
             Err(error) => {
                 self.history.push_back(ReplLine {
@@ -101,80 +139,93 @@ impl ReplPane {
         self.scroll_offset = 0;
     }
 
-    fn format_expr(&self, expr: &crate::interpreter::types::Expr) -> String {
-        use crate::interpreter::types::{Expr, SymbolData};
-
-        match expr {
-            Expr::Integer(n) => format!("{}", n),
-            Expr::Float(f) => format!("{}", f),
-            Expr::Rational {
-                numerator,
-                denominator,
-            } => format!("{}/{}", numerator, denominator),
-            Expr::Character(ch) => format!(
-                "#\\{}",
-                match *ch {
-                    ' ' => "space".to_string(),
-                    '\n' => "newline".to_string(),
-                    '\t' => "tab".to_string(),
-                    '\r' => "return".to_string(),
-                    c => c.to_string(),
-                }
-            ),
-            Expr::Symbol(sym_data) => match sym_data {
-                SymbolData::Keyword(name) => format!(":{}", name),
-                SymbolData::Uninterned(name, id) => format!("#:{}#{}", name, id),
-                SymbolData::Interned(name) => name.clone(),
-            },
-            Expr::String(s) => format!("\"{}\"", s),
-            Expr::Vector(vec) => {
-                let items: Vec<String> = vec.iter().map(|e| self.format_expr(e)).collect();
-                format!("[{}]", items.join(" "))
-            }
-            Expr::HashTable(h) => {
-                format!("#<hash-table:{}>", h.len())
-            }
-            Expr::List(list) => {
-                if list.is_empty() {
-                    "()".to_string()
+    // Ctrl+Z: puts the globals back as they were before the last evaluation not yet
+    // reverted, e.g. to undo a bad redefinition. Side effects other than definitions, such
+    // as output or a hash table filled in place, stay.
+    pub fn revert_last_evaluation(&mut self) {
+        let line = match self.checkpoints.pop() {
+            Some((source, checkpoint)) => {
+                let restored = self.evaluator.environment.restore(checkpoint);
+                if restored.is_empty() {
+                    format!("Reverted {} (no definitions changed)", source)
                 } else {
-                    let items: Vec<String> = list.iter().map(|e| self.format_expr(e)).collect();
-                    format!("({})", items.join(" "))
+                    format!("Reverted {}: restored {}", source, restored.join(", "))
                 }
             }
-            Expr::Cons(car, cdr) => {
-                let mut repr = String::from("(");
-                repr.push_str(&self.format_expr(car));
-
-                let mut tail = cdr.as_ref();
-                loop {
-                    match tail {
-                        Expr::Cons(next_car, next_cdr) => {
-                            repr.push(' ');
-                            repr.push_str(&self.format_expr(next_car));
-                            tail = next_cdr.as_ref();
-                        }
-                        Expr::List(list) => {
-                            for item in list {
-                                repr.push(' ');
-                                repr.push_str(&self.format_expr(item));
-                            }
-                            repr.push(')');
-                            break;
-                        }
-                        other => {
-                            repr.push_str(" . ");
-                            repr.push_str(&self.format_expr(other));
-                            repr.push(')');
-                            break;
-                        }
-                    }
-                }
-                repr
-            }
+            None => "Nothing to revert".to_string(),
+        };
+        self.history.push_back(ReplLine {
+            text: line,
+            is_input: false,
+            is_error: false,
+        });
+        self.scroll_offset = 0;
+    }
+
+    // Results too wide for the pane are pretty-printed across several lines
+    fn format_result(&self, expr: &Expr) -> String {
+        let formatted = write_string(expr);
+        if formatted.chars().count() <= self.columns {
+            formatted
+        } else {
+            pretty_print(expr, self.columns)
         }
     }
 
+    pub fn last_value(&self) -> Option<&Expr> {
+        self.last_value.as_ref()
+    }
+
+    pub fn take_inspect_request(&mut self) -> Option<(Expr, Option<String>)> {
+        self.inspect_request.take()
+    }
+
+    pub fn evaluator(&self) -> &Evaluator {
+        &self.evaluator
+    }
+
+    pub fn evaluator_mut(&mut self) -> &mut Evaluator {
+        &mut self.evaluator
+    }
+
+    pub fn take_changed_definitions(&mut self) -> Vec<String> {
+        self.evaluator.environment.take_changed_globals()
+    }
+
+    // Evaluates a form sent by a remote client against this pane's environment. The
+    // exchange is echoed into the transcript tagged with the client's address.
+    pub fn evaluate_remote(&mut self, peer: &str, source: &str) -> Response {
+        self.history.push_back(ReplLine {
+            text: format!("[{}] > {}", peer, source),
+            is_input: true,
+            is_error: false,
+        });
+        let response = match self.evaluator.eval_str(source) {
+            Ok(result) => Response::Ok(self.format_result(&result)),
+            Err(error) => Response::Err(error),
+        };
+        match &response {
+            Response::Ok(text) => self.append_output(text, false),
+            Response::Err(error) => self.append_output(&format!("Error: {}", error), true),
+        }
+        response
+    }
+
+    // Adds non-REPL output (such as git command results) to the transcript
+    pub fn append_output(&mut self, text: &str, is_error: bool) {
+        for line in text.lines() {
+            self.history.push_back(ReplLine {
+                text: line.to_string(),
+                is_input: false,
+                is_error,
+            });
+        }
+        while self.history.len() > 500 {
+            self.history.pop_front();
+        }
+        self.scroll_offset = 0;
+    }
+
     fn insert_char(&mut self, ch: char) {
         self.current_input.insert(self.cursor_position, ch);
         self.cursor_position += 1;
@@ -199,6 +250,25 @@ impl ReplPane {
         }
     }
 
+    // Tab: extend the input at the cursor as far as the candidates agree, otherwise list
+    // them in the history
+    fn complete_input(&mut self) {
+        let Some(completion) = complete(&self.current_input, self.cursor_position) else {
+            return;
+        };
+        match completion.extend(&self.current_input, self.cursor_position) {
+            Some((edited, cursor)) => {
+                self.current_input = edited;
+                self.cursor_position = cursor;
+            }
+            None if !completion.candidates.is_empty() => {
+                let labels = completion.labels(&self.current_input, self.cursor_position);
+                self.append_output(&labels.join("  "), false);
+            }
+            None => {}
+        }
+    }
+
     fn history_up(&mut self) {
         if self.command_history.is_empty() {
             return;
@@ -275,6 +345,9 @@ impl Pane for ReplPane {
             theme.text,
         );
 
+        let char_width = fonts.measure_text("M", 14.0).x.max(1.0);
+        self.columns = (((bounds.width - 10.0) / char_width) as usize).max(20);
+
         // Calculate content area
         let content_y = bounds.y + title_height + 5.0;
         let content_height = bounds.height - title_height - 40.0; // Leave space for input
@@ -357,6 +430,12 @@ impl Pane for ReplPane {
         }
 
         let mut handled = false;
+        let ctrl = rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL)
+            || rl.is_key_down(KeyboardKey::KEY_LEFT_SUPER)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_SUPER);
+        let shift = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
 
         // Handle scrolling
         let wheel_move = rl.get_mouse_wheel_move();
@@ -375,6 +454,27 @@ impl Pane for ReplPane {
         // Handle keyboard input
         if let Some(key) = rl.get_key_pressed() {
             match key {
+                // Inspect result: sends the last value to the inspector
+                KeyboardKey::KEY_I if ctrl && shift => {
+                    self.inspect_request = self
+                        .last_value
+                        .clone()
+                        .map(|value| (value, self.last_binding.clone()));
+                    handled = true;
+                }
+                KeyboardKey::KEY_Z if ctrl => {
+                    self.revert_last_evaluation();
+                    handled = true;
+                }
+                // Copies the last result, pretty-printed and readable (clipboard.rs)
+                KeyboardKey::KEY_C if ctrl => {
+                    if let Some(value) = &self.last_value {
+                        if let Err(err) = rl.set_clipboard_text(&clipboard::value_text(value)) {
+                            self.append_output(&format!("Clipboard error: {}", err), true);
+                        }
+                    }
+                    handled = true;
+                }
                 KeyboardKey::KEY_ENTER => {
                     self.evaluate_input();
                     handled = true;
@@ -383,6 +483,10 @@ impl Pane for ReplPane {
                     self.delete_char();
                     handled = true;
                 }
+                KeyboardKey::KEY_TAB => {
+                    self.complete_input();
+                    handled = true;
+                }
                 KeyboardKey::KEY_LEFT => {
                     self.move_cursor_left();
                     handled = true;
@@ -413,7 +517,7 @@ impl Pane for ReplPane {
 
         // Handle text input
         if let Some(char) = rl.get_char_pressed() {
-            if char.is_ascii() && !char.is_control() {
+            if !ctrl && char.is_ascii() && !char.is_control() {
                 self.insert_char(char);
                 handled = true;
             }
@@ -438,3 +542,45 @@ impl Pane for ReplPane {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ReplPane;
+    use crate::interpreter::evaluator::Evaluator;
+    use crate::interpreter::types::Expr;
+
+    #[test]
+    fn test_revert_last_evaluation_restores_definitions() {
+        let mut pane = ReplPane::new("repl".to_string(), Evaluator::new());
+        for input in ["(defun area (r) (* r r))", "(defun area (r) (car r))"] {
+            pane.current_input = input.to_string();
+            pane.evaluate_input();
+        }
+        assert!(pane.evaluator.eval_str("(area 3)").is_err());
+
+        pane.revert_last_evaluation();
+        assert_eq!(pane.evaluator.eval_str("(area 3)"), Ok(Expr::Integer(9)));
+        assert_eq!(
+            pane.history.back().unwrap().text,
+            "Reverted (defun area (r) (car r)): restored area"
+        );
+        pane.revert_last_evaluation();
+        assert!(pane.evaluator.eval_str("(area 3)").is_err());
+        pane.revert_last_evaluation();
+        assert_eq!(pane.history.back().unwrap().text, "Nothing to revert");
+    }
+
+    #[test]
+    fn test_last_value_is_the_last_successful_result() {
+        let mut pane = ReplPane::new("repl".to_string(), Evaluator::new());
+        assert_eq!(pane.last_value(), None);
+        for input in ["(list 1 2)", "(car 5)"] {
+            pane.current_input = input.to_string();
+            pane.evaluate_input();
+        }
+        assert_eq!(
+            pane.last_value(),
+            Some(&Expr::new_list(vec![Expr::Integer(1), Expr::Integer(2)]))
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use crate::ide::fonts::IdeFonts;
+use crate::ide::pane::Pane;
+use crate::ide::theme::Theme;
+use crate::interpreter::xref::Location;
+use raylib::prelude::*;
+use std::any::Any;
+
+const TITLE_HEIGHT: f32 = 25.0;
+const ROW_HEIGHT: f32 = 18.0;
+
+// One hit in a search over source files, shown as file:line:column and the line's text
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultItem {
+    pub location: Location,
+    pub text: String,
+}
+
+// Lists the results of an editor search such as find-references. Enter or a click on a
+// row asks the IDE to open that location in the editor.
+pub struct ResultsPane {
+    id: String,
+    title: String,
+    heading: String,
+    items: Vec<ResultItem>,
+    selected_index: usize,
+    scroll_offset: f32,
+    has_focus: bool,
+    jump_request: Option<Location>,
+}
+
+impl ResultsPane {
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            title: "Results".to_string(),
+            heading: "No results".to_string(),
+            items: Vec::new(),
+            selected_index: 0,
+            scroll_offset: 0.0,
+            has_focus: false,
+            jump_request: None,
+        }
+    }
+
+    pub fn show(&mut self, heading: String, items: Vec<ResultItem>) {
+        self.heading = heading;
+        self.items = items;
+        self.selected_index = 0;
+        self.scroll_offset = 0.0;
+    }
+
+    pub fn items(&self) -> &[ResultItem] {
+        &self.items
+    }
+
+    // The location picked since the last call, for the IDE to open
+    pub fn take_jump_request(&mut self) -> Option<Location> {
+        self.jump_request.take()
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+        let last = self.items.len() as isize - 1;
+        self.selected_index = (self.selected_index as isize + delta).clamp(0, last) as usize;
+    }
+
+    fn open_selected(&mut self) {
+        if let Some(item) = self.items.get(self.selected_index) {
+            self.jump_request = Some(item.location.clone());
+        }
+    }
+
+    fn ensure_selection_visible(&mut self, view_height: f32) {
+        let top = self.selected_index as f32 * ROW_HEIGHT;
+        if top < self.scroll_offset {
+            self.scroll_offset = top;
+        } else if top + ROW_HEIGHT > self.scroll_offset + view_height {
+            self.scroll_offset = top + ROW_HEIGHT - view_height;
+        }
+    }
+
+    fn max_scroll(&self, view_height: f32) -> f32 {
+        (self.items.len() as f32 * ROW_HEIGHT - view_height).max(0.0)
+    }
+}
+
+// file:line:column with the file shortened to its name
+fn location_label(location: &Location) -> String {
+    let file = location
+        .file
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(&location.file);
+    format!("{}:{}:{}", file, location.line, location.column)
+}
+
+impl Pane for ResultsPane {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn draw(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        bounds: Rectangle,
+        theme: &Theme,
+        fonts: &IdeFonts,
+    ) {
+        d.draw_rectangle_rec(bounds, theme.surface);
+        if self.has_focus {
+            d.draw_rectangle_lines_ex(bounds, 2.0, theme.focus_indicator);
+        } else {
+            d.draw_rectangle_lines_ex(bounds, 1.0, theme.border);
+        }
+
+        d.draw_rectangle(
+            bounds.x as i32,
+            bounds.y as i32,
+            bounds.width as i32,
+            TITLE_HEIGHT as i32,
+            theme.panel,
+        );
+        fonts.draw_text(
+            d,
+            &format!("{}: {}", self.title, self.heading),
+            Vector2::new(bounds.x + 5.0, bounds.y + 5.0),
+            16.0,
+            theme.text,
+        );
+
+        let content_y = bounds.y + TITLE_HEIGHT + 2.0;
+        let content_height = (bounds.height - TITLE_HEIGHT - 4.0).max(0.0);
+        let mut scissor = d.begin_scissor_mode(
+            bounds.x as i32,
+            content_y as i32,
+            bounds.width as i32,
+            content_height as i32,
+        );
+
+        let mut y = content_y - self.scroll_offset;
+        for (index, item) in self.items.iter().enumerate() {
+            if y >= content_y - ROW_HEIGHT && y < content_y + content_height {
+                if index == self.selected_index {
+                    scissor.draw_rectangle(
+                        bounds.x as i32,
+                        y as i32,
+                        bounds.width as i32,
+                        ROW_HEIGHT as i32,
+                        theme.selection,
+                    );
+                }
+                let label = location_label(&item.location);
+                fonts.draw_text(
+                    &mut scissor,
+                    &label,
+                    Vector2::new(bounds.x + 5.0, y + 2.0),
+                    14.0,
+                    theme.function,
+                );
+                let label_width = fonts.measure_text(&label, 14.0).x;
+                fonts.draw_text(
+                    &mut scissor,
+                    item.text.trim(),
+                    Vector2::new(bounds.x + 15.0 + label_width, y + 2.0),
+                    14.0,
+                    theme.text,
+                );
+            }
+            y += ROW_HEIGHT;
+        }
+    }
+
+    fn handle_input(&mut self, rl: &mut RaylibHandle, bounds: Rectangle) -> bool {
+        if !self.has_focus {
+            return false;
+        }
+        let view_height = (bounds.height - TITLE_HEIGHT - 4.0).max(0.0);
+        let mut handled = false;
+
+        let wheel_move = rl.get_mouse_wheel_move();
+        if wheel_move != 0.0 {
+            self.scroll_offset =
+                (self.scroll_offset - wheel_move * 20.0).clamp(0.0, self.max_scroll(view_height));
+            handled = true;
+        }
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            let mouse = rl.get_mouse_position();
+            let local_y = mouse.y - (bounds.y + TITLE_HEIGHT + 2.0) + self.scroll_offset;
+            if mouse.y > bounds.y + TITLE_HEIGHT && local_y >= 0.0 {
+                let row = (local_y / ROW_HEIGHT) as usize;
+                if row < self.items.len() {
+                    self.selected_index = row;
+                    self.open_selected();
+                    handled = true;
+                }
+            }
+        }
+
+        if let Some(key) = rl.get_key_pressed() {
+            match key {
+                KeyboardKey::KEY_UP => self.move_selection(-1),
+                KeyboardKey::KEY_DOWN => self.move_selection(1),
+                KeyboardKey::KEY_ENTER => self.open_selected(),
+                _ => return handled,
+            }
+            self.ensure_selection_visible(view_height);
+            handled = true;
+        }
+
+        handled
+    }
+
+    fn on_focus(&mut self) {
+        self.has_focus = true;
+    }
+
+    fn on_blur(&mut self) {
+        self.has_focus = false;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(file: &str, line: usize) -> ResultItem {
+        ResultItem {
+            location: Location {
+                file: file.to_string(),
+                line,
+                column: 1,
+            },
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_selection_and_jump() {
+        let mut pane = ResultsPane::new("results".to_string());
+        pane.show(
+            "square (2)".to_string(),
+            vec![item("/src/a.lisp", 3), item("/src/b.lisp", 7)],
+        );
+        assert_eq!(pane.take_jump_request(), None);
+
+        pane.move_selection(5);
+        pane.open_selected();
+        assert_eq!(
+            pane.take_jump_request().map(|location| location.line),
+            Some(7)
+        );
+        assert_eq!(pane.take_jump_request(), None);
+        assert_eq!(location_label(&pane.items()[1].location), "b.lisp:7:1");
+
+        // New results start at the top again
+        pane.show("nothing".to_string(), Vec::new());
+        pane.move_selection(1);
+        pane.open_selected();
+        assert_eq!(pane.take_jump_request(), None);
+    }
+}
@@ -2,6 +2,7 @@ use crate::ide::fonts::IdeFonts;
 use crate::ide::pane::Pane;
 use crate::ide::theme::Theme;
 use crate::interpreter::environment::Environment;
+use crate::interpreter::printer::{PrintMode, Printer};
 use raylib::prelude::*;
 use std::any::Any;
 
@@ -30,6 +31,8 @@ pub struct SymbolBrowserPane {
     scroll_offset: i32,
     has_focus: bool,
     environment: Option<Environment>,
+    // Set by Enter: the selected name, for the app to show its value in the inspector
+    inspect_request: Option<String>,
 }
 
 impl SymbolBrowserPane {
@@ -44,6 +47,7 @@ impl SymbolBrowserPane {
             scroll_offset: 0,
             has_focus: false,
             environment: None,
+            inspect_request: None,
         }
     }
 
@@ -155,25 +159,19 @@ impl SymbolBrowserPane {
         }
     }
 
+    // Short summary for the list: functions by kind, data cut off after a few levels
     fn format_value(&self, value: &crate::interpreter::types::Expr) -> String {
         use crate::interpreter::types::Expr;
 
-        match value {
-            Expr::Integer(n) => format!("{}", n),
-            Expr::Float(f) => format!("{}", f),
-            Expr::String(s) => format!("\"{}\"", s),
-            Expr::List(list) if !list.is_empty() => {
-                if let Expr::Symbol(sym) = &list[0] {
-                    if sym.as_str() == "lambda" {
-                        return "Function".to_string();
-                    }
-                }
-                "List".to_string()
+        if let Expr::List(list) = value {
+            if matches!(list.first(), Some(Expr::Symbol(sym)) if sym.as_str() == "lambda") {
+                return "Function".to_string();
             }
-            Expr::List(_) => "()".to_string(),
-            Expr::Symbol(s) => s.as_str().to_string(),
-            _ => "...".to_string(),
         }
+        Printer::new(PrintMode::Write)
+            .with_max_depth(2)
+            .with_max_length(5)
+            .print(value)
     }
 
     fn filter_symbols(&mut self) {
@@ -218,6 +216,10 @@ impl SymbolBrowserPane {
     pub fn get_selected_symbol(&self) -> Option<&Symbol> {
         self.filtered_symbols.get(self.selected_index)
     }
+
+    pub fn take_inspect_request(&mut self) -> Option<String> {
+        self.inspect_request.take()
+    }
 }
 
 impl Pane for SymbolBrowserPane {
@@ -398,6 +400,10 @@ impl Pane for SymbolBrowserPane {
                     self.move_selection_down();
                     handled = true;
                 }
+                KeyboardKey::KEY_ENTER => {
+                    self.inspect_request = self.get_selected_symbol().map(|s| s.name.clone());
+                    handled = true;
+                }
                 KeyboardKey::KEY_BACKSPACE => {
                     if !self.search_query.is_empty() {
                         self.search_query.pop();
@@ -1,3 +1,4 @@
+use crate::interpreter::evaluator::SPECIAL_FORMS;
 use std::collections::HashSet;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -29,45 +30,21 @@ struct LineState {
     in_string: bool,
 }
 
-const SPECIAL_FORMS: &[&str] = &[
-    "define",
-    "defun",
-    "if",
-    "quote",
-    "lambda",
-    "let",
-    "let*",
-    "cond",
-    "and",
-    "or",
-    "progn",
-    "when",
-    "unless",
-    "case",
-    "letrec",
-    "begin",
-    "do",
-    "loop",
-    "catch",
-    "throw",
-    "unwind-protect",
-    "block",
-    "return-from",
-    "tagbody",
-    "go",
-];
-
 const BUILTIN_FUNCTIONS: &[&str] = &[
     "+",
     "-",
     "*",
     "/",
+    "expt",
     "=",
     "/=",
     "<",
     "<=",
     ">",
     ">=",
+    "eq?",
+    "eqv?",
+    "equal?",
     "list",
     "car",
     "cdr",
@@ -80,31 +57,175 @@ const BUILTIN_FUNCTIONS: &[&str] = &[
     "mapcar",
     "filter",
     "remove",
+    "remove-if",
+    "remove-if-not",
+    "member-if",
+    "find-if",
     "member",
+    "assoc",
+    "assq",
+    "rassoc",
+    "assoc-if",
+    "getf",
+    "plist-get",
+    "plist-put",
+    "alist->hash",
+    "hash->alist",
     "reduce",
+    "foldl",
+    "foldr",
+    "map-indexed",
+    "zip",
+    "unzip",
+    "iota",
+    "take",
+    "drop",
+    "partition",
+    "flatten",
+    "last",
+    "transduce",
+    "map-filter-reduce",
+    "sort",
+    "stable-sort",
+    "sort-by",
     "apply",
     "funcall",
+    "identity",
+    "partial",
+    "compose",
+    "constantly",
+    "dynamic-wind",
+    "invoke-restart",
+    "compute-restarts",
     "print",
     "println",
+    "eprint",
+    "eprintln",
+    "read-line",
+    "write-line",
+    "read-char",
+    "peek-char",
+    "format",
+    "pprint",
     "gensym",
     "get",
     "put",
     "symbol-plist",
+    "export-session",
+    "session-script",
+    "start-recording",
+    "stop-recording",
+    "recorded-forms",
+    "global-history",
+    "rewind",
+    "begin-transaction",
+    "commit-transaction",
+    "rollback-transaction",
     "vector",
     "make-vector",
     "vector-ref",
     "vector-set!",
     "vector-length",
+    "vector-map",
+    "vector-fill!",
+    "subvector",
+    "vector->list",
+    "list->vector",
+    "vector-push",
+    "vector-pop",
+    "vector-sort!",
+    "vector-add",
+    "vector-scale",
+    "vector-dot",
+    "vector-sum",
+    "make-matrix",
+    "list->matrix",
+    "matrix->list",
+    "matrix-rows",
+    "matrix-cols",
+    "matrix-ref",
+    "matrix-set!",
+    "matrix-multiply",
+    "matrix-transpose",
+    "make-queue",
+    "enqueue",
+    "dequeue",
+    "queue-front",
+    "make-stack",
+    "push!",
+    "pop!",
+    "stack-top",
+    "make-heap",
+    "heap-push!",
+    "heap-pop!",
+    "heap-peek",
+    "iterate",
+    "next",
+    "iter-done?",
+    "iter->list",
+    "range",
+    "make-struct",
+    "struct-ref",
+    "struct-set!",
+    "struct-type",
+    "struct-fields",
+    "call-generic",
+    "call-next-method",
+    "next-method-p",
+    "type-of",
+    "mean",
+    "median",
+    "variance",
+    "stddev",
+    "percentile",
     "make-hash-table",
     "hash-set!",
     "hash-ref",
     "hash-remove!",
     "hash-keys",
+    "hash-values",
+    "hash-count",
+    "hash-map",
+    "hash-for-each",
+    "hash-update!",
     "char=",
     "char<",
     "char>",
+    "char<=",
+    "char>=",
+    "string=",
+    "string<",
+    "string>",
+    "string<=",
+    "string>=",
+    "string-ci=",
+    "string-split",
+    "regex-match?",
+    "regex-find",
+    "regex-find-all",
+    "regex-replace",
+    "getenv",
+    "setenv",
+    "command-line",
+    "exit",
+    "run-process",
+    "require",
+    "tcp-connect",
+    "tcp-send",
+    "tcp-receive",
+    "tcp-listen",
+    "tcp-accept",
+    "tcp-port",
+    "tcp-close",
+    "http-get",
+    "http-post",
     "char->integer",
     "integer->char",
+    "alpha?",
+    "digit?",
+    "whitespace?",
+    "string->number",
+    "number->string",
     "integerp",
     "floatp",
     "rationalp",
@@ -112,6 +233,24 @@ const BUILTIN_FUNCTIONS: &[&str] = &[
     "characterp",
     "vectorp",
     "hash-table-p",
+    "matrixp",
+    "queuep",
+    "stackp",
+    "heapp",
+    "iteratorp",
+    "structp",
+    "first",
+    "rest",
+    "second",
+    "third",
+    "fourth",
+    "assoc-default",
+    "count-if",
+    "some",
+    "every",
+    "curry",
+    "complement",
+    "flip",
 ];
 
 const CONSTANTS: &[&str] = &["t", "nil"];
@@ -149,6 +288,29 @@ impl SyntaxHighlighter {
         self.cache.get(index)
     }
 
+    // Byte range within `line` (line `index` of the highlighted content) of the symbol
+    // token covering `offset`, if there is one there
+    pub fn symbol_at(&self, index: usize, line: &str, offset: usize) -> Option<(usize, usize)> {
+        let span = self
+            .line(index)?
+            .spans
+            .iter()
+            .find(|span| span.start <= offset && offset < span.end)?;
+        let text = line.get(span.start..span.end)?;
+        let is_symbol = match span.kind {
+            SyntaxKind::Normal => text
+                .chars()
+                .all(|ch| !ch.is_whitespace() && !is_delimiter(ch)),
+            // #t and #f are reader literals, not names
+            SyntaxKind::Keyword
+            | SyntaxKind::SpecialForm
+            | SyntaxKind::Function
+            | SyntaxKind::Constant => !text.starts_with('#'),
+            SyntaxKind::Comment | SyntaxKind::String | SyntaxKind::Number => false,
+        };
+        is_symbol.then_some((span.start, span.end))
+    }
+
     pub fn reset(&mut self, content: &str) {
         self.recompute(content);
     }
@@ -207,6 +369,14 @@ impl SyntaxHighlighter {
                 });
                 idx = end;
                 continue;
+            } else if let Some((end, kind)) = classify_reader_literal(line, idx) {
+                spans.push(HighlightSpan {
+                    start: idx,
+                    end,
+                    kind,
+                });
+                idx = end;
+                continue;
             } else if is_delimiter(ch) {
                 spans.push(HighlightSpan {
                     start: idx,
@@ -319,6 +489,44 @@ fn is_delimiter(ch: char) -> bool {
     )
 }
 
+// `#\a`, `#t`/`#f` and `#x1F`-style literals; other `#` forms fall through as delimiters
+fn classify_reader_literal(line: &str, start: usize) -> Option<(usize, SyntaxKind)> {
+    let rest = line[start..].strip_prefix('#')?;
+    let mut chars = rest.chars();
+    let dispatch = chars.next()?;
+
+    if dispatch == '\\' {
+        let first = chars.next()?;
+        let mut end = start + 2 + first.len_utf8();
+        if first.is_alphanumeric() {
+            end = consume_while(line, end, char::is_alphanumeric);
+        }
+        return Some((end, SyntaxKind::String));
+    }
+
+    let end = consume_while(line, start + 1, |c| {
+        !c.is_whitespace() && !is_delimiter(c) && c != '"' && c != ';'
+    });
+    let body = &line[start + 1..end];
+    match body {
+        "t" | "f" | "true" | "false" => Some((end, SyntaxKind::Constant)),
+        _ => {
+            let radix = match body.chars().next()?.to_ascii_lowercase() {
+                'x' => 16,
+                'o' => 8,
+                'b' => 2,
+                _ => return None,
+            };
+            let digits = body[1..].trim_start_matches(['-', '+']);
+            if !digits.is_empty() && digits.chars().all(|c| c.is_digit(radix)) {
+                Some((end, SyntaxKind::Number))
+            } else {
+                None
+            }
+        }
+    }
+}
+
 fn is_number(token: &str) -> bool {
     if token.is_empty() {
         return false;
@@ -355,7 +563,10 @@ fn is_integer(token: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{consume_string, is_delimiter, is_number, SyntaxHighlighter, SyntaxKind};
+    use super::{
+        classify_reader_literal, consume_string, is_delimiter, is_number, SyntaxHighlighter,
+        SyntaxKind,
+    };
 
     #[test]
     fn test_is_number_variants() {
@@ -388,6 +599,28 @@ mod tests {
         assert!(!is_delimiter('a'));
     }
 
+    #[test]
+    fn test_reader_literals() {
+        assert_eq!(
+            classify_reader_literal("#\\newline)", 0),
+            Some((9, SyntaxKind::String))
+        );
+        assert_eq!(
+            classify_reader_literal("#\\( ", 0),
+            Some((3, SyntaxKind::String))
+        );
+        assert_eq!(
+            classify_reader_literal("#t)", 0),
+            Some((2, SyntaxKind::Constant))
+        );
+        assert_eq!(
+            classify_reader_literal("#xFF ", 0),
+            Some((4, SyntaxKind::Number))
+        );
+        assert_eq!(classify_reader_literal("#b102", 0), None);
+        assert_eq!(classify_reader_literal("#", 0), None);
+    }
+
     #[test]
     fn test_highlight_line_categories() {
         let mut highlighter = SyntaxHighlighter::new();
@@ -427,4 +660,29 @@ mod tests {
             .iter()
             .any(|span| span.kind == SyntaxKind::String));
     }
+
+    #[test]
+    fn test_symbol_at() {
+        let mut highlighter = SyntaxHighlighter::new();
+        let line = "(square x) ; x \"s\" 12 #t :key";
+        highlighter.ensure(line);
+        let symbol_at = |offset| highlighter.symbol_at(0, line, offset);
+        assert_eq!(symbol_at(0), None);
+        assert_eq!(symbol_at(1), Some((1, 7)));
+        assert_eq!(symbol_at(6), Some((1, 7)));
+        assert_eq!(symbol_at(7), None);
+        assert_eq!(symbol_at(8), Some((8, 9)));
+        assert_eq!(symbol_at(13), None);
+        assert_eq!(symbol_at(1000), None);
+        assert_eq!(highlighter.symbol_at(1, line, 1), None);
+
+        let line = "(print \"s\" 12 #t :key)";
+        highlighter.reset(line);
+        let symbol_at = |offset| highlighter.symbol_at(0, line, offset);
+        assert_eq!(symbol_at(1), Some((1, 6)));
+        assert_eq!(symbol_at(8), None);
+        assert_eq!(symbol_at(12), None);
+        assert_eq!(symbol_at(15), None);
+        assert_eq!(symbol_at(19), Some((17, 21)));
+    }
 }
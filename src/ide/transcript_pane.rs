@@ -0,0 +1,255 @@
+use crate::ide::fonts::IdeFonts;
+use crate::ide::pane::Pane;
+use crate::ide::theme::Theme;
+use crate::interpreter::ports::StringPort;
+use raylib::prelude::*;
+use std::any::Any;
+use std::collections::VecDeque;
+
+const TITLE_HEIGHT: f32 = 25.0;
+const ROW_HEIGHT: f32 = 18.0;
+// Older lines are dropped once the scrollback holds this many
+const MAX_LINES: usize = 5000;
+
+// Shows what programs print. The IDE gives each evaluator a clone of `port()`, and the pane
+// drains it every frame. The view follows new output unless it has been scrolled back, in
+// which case it stays on the same lines.
+pub struct TranscriptPane {
+    id: String,
+    title: String,
+    output: StringPort,
+    lines: VecDeque<String>,
+    // The last line hasn't been ended by a newline yet, so the next output continues it
+    line_open: bool,
+    // How many lines the bottom of the view is above the newest line
+    scroll_from_bottom: usize,
+    has_focus: bool,
+}
+
+impl TranscriptPane {
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            title: "Transcript".to_string(),
+            output: StringPort::new(),
+            lines: VecDeque::new(),
+            line_open: false,
+            scroll_from_bottom: 0,
+            has_focus: false,
+        }
+    }
+
+    pub fn port(&self) -> StringPort {
+        self.output.clone()
+    }
+
+    // Moves whatever has been written to the port into the scrollback
+    pub fn pull(&mut self) {
+        let text = self.output.take();
+        self.append(&text);
+    }
+
+    pub fn append(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let body = text.strip_suffix('\n').unwrap_or(text);
+        let mut added = 0;
+        for (index, piece) in body.split('\n').enumerate() {
+            match self.lines.back_mut() {
+                Some(last) if index == 0 && self.line_open => last.push_str(piece),
+                _ => {
+                    self.lines.push_back(piece.to_string());
+                    added += 1;
+                }
+            }
+        }
+        self.line_open = !text.ends_with('\n');
+
+        while self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+        if self.scroll_from_bottom > 0 {
+            self.scroll_from_bottom = (self.scroll_from_bottom + added).min(self.max_scroll());
+        }
+    }
+
+    pub fn lines(&self) -> &VecDeque<String> {
+        &self.lines
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.line_open = false;
+        self.scroll_from_bottom = 0;
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(1)
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        self.scroll_from_bottom = (self.scroll_from_bottom as isize + delta)
+            .clamp(0, self.max_scroll() as isize) as usize;
+    }
+
+    fn visible_rows(bounds: Rectangle) -> usize {
+        ((bounds.height - TITLE_HEIGHT - 4.0).max(0.0) / ROW_HEIGHT) as usize
+    }
+}
+
+impl Pane for TranscriptPane {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn draw(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        bounds: Rectangle,
+        theme: &Theme,
+        fonts: &IdeFonts,
+    ) {
+        self.pull();
+
+        d.draw_rectangle_rec(bounds, theme.surface);
+        if self.has_focus {
+            d.draw_rectangle_lines_ex(bounds, 2.0, theme.focus_indicator);
+        } else {
+            d.draw_rectangle_lines_ex(bounds, 1.0, theme.border);
+        }
+
+        d.draw_rectangle(
+            bounds.x as i32,
+            bounds.y as i32,
+            bounds.width as i32,
+            TITLE_HEIGHT as i32,
+            theme.panel,
+        );
+        let heading = if self.scroll_from_bottom > 0 {
+            format!(
+                "{} ({} lines above newest)",
+                self.title, self.scroll_from_bottom
+            )
+        } else {
+            self.title.clone()
+        };
+        fonts.draw_text(
+            d,
+            &heading,
+            Vector2::new(bounds.x + 5.0, bounds.y + 5.0),
+            16.0,
+            theme.text,
+        );
+
+        let content_y = bounds.y + TITLE_HEIGHT + 2.0;
+        let content_height = (bounds.height - TITLE_HEIGHT - 4.0).max(0.0);
+        let mut scissor = d.begin_scissor_mode(
+            bounds.x as i32,
+            content_y as i32,
+            bounds.width as i32,
+            content_height as i32,
+        );
+
+        let end = self.lines.len() - self.scroll_from_bottom.min(self.lines.len());
+        let start = end.saturating_sub(Self::visible_rows(bounds));
+        let mut y = content_y;
+        for line in self.lines.range(start..end) {
+            fonts.draw_text(
+                &mut scissor,
+                line,
+                Vector2::new(bounds.x + 5.0, y + 2.0),
+                14.0,
+                theme.text,
+            );
+            y += ROW_HEIGHT;
+        }
+    }
+
+    fn handle_input(&mut self, rl: &mut RaylibHandle, bounds: Rectangle) -> bool {
+        if !self.has_focus {
+            return false;
+        }
+        let page = Self::visible_rows(bounds).max(1) as isize;
+        let mut handled = false;
+
+        let wheel_move = rl.get_mouse_wheel_move();
+        if wheel_move != 0.0 {
+            self.scroll_by((wheel_move * 3.0) as isize);
+            handled = true;
+        }
+
+        let ctrl = rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL)
+            || rl.is_key_down(KeyboardKey::KEY_LEFT_SUPER)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_SUPER);
+
+        if let Some(key) = rl.get_key_pressed() {
+            match key {
+                KeyboardKey::KEY_UP => self.scroll_by(1),
+                KeyboardKey::KEY_DOWN => self.scroll_by(-1),
+                KeyboardKey::KEY_PAGE_UP => self.scroll_by(page),
+                KeyboardKey::KEY_PAGE_DOWN => self.scroll_by(-page),
+                KeyboardKey::KEY_HOME => self.scroll_from_bottom = self.max_scroll(),
+                KeyboardKey::KEY_END => self.scroll_from_bottom = 0,
+                KeyboardKey::KEY_L if ctrl => self.clear(),
+                _ => return handled,
+            }
+            handled = true;
+        }
+
+        handled
+    }
+
+    fn on_focus(&mut self) {
+        self.has_focus = true;
+    }
+
+    fn on_blur(&mut self) {
+        self.has_focus = false;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::Evaluator;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_collects_evaluator_output() {
+        let mut pane = TranscriptPane::new("transcript".to_string());
+        let mut evaluator = Evaluator::new();
+        evaluator.output = Rc::new(pane.port());
+        evaluator
+            .eval_str("(progn (print \"a\") (print \"b\") (println \"\") (println \"c\"))")
+            .unwrap();
+        pane.pull();
+        assert_eq!(pane.lines(), &["ab", "c"]);
+
+        // Scrolled back, the view stays put as more lines arrive
+        pane.scroll_by(1);
+        evaluator.eval_str("(println \"d\")").unwrap();
+        pane.pull();
+        assert_eq!(pane.lines().back().map(String::as_str), Some("d"));
+        assert_eq!(pane.scroll_from_bottom, 2);
+
+        pane.append(&"x\n".repeat(MAX_LINES));
+        assert_eq!(pane.lines().len(), MAX_LINES);
+        assert_eq!(pane.scroll_from_bottom, MAX_LINES - 1);
+        pane.clear();
+        assert!(pane.lines().is_empty());
+    }
+}
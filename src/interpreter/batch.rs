@@ -0,0 +1,339 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::types::EvalError;
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Headless runs (`zeus run [--json] file.lisp`): evaluates a program's top-level forms in
+// order, stopping at the first error, and records what each one did.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceForm {
+    pub line: usize,
+    pub column: usize,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormOutcome {
+    Value(String),
+    Error(String),
+    // The form called (exit code); nothing after it runs
+    Exit(i32),
+}
+
+#[derive(Debug, Clone)]
+pub struct FormReport {
+    pub line: usize,
+    pub source: String,
+    pub outcome: FormOutcome,
+    // Everything the form printed with print/println/format/pprint
+    pub output: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    pub file: String,
+    pub forms: Vec<FormReport>,
+    pub warnings: Vec<Warning>,
+    pub duration: Duration,
+}
+
+impl RunReport {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code() == 0
+    }
+
+    // The code passed to exit, otherwise 1 if a form failed
+    pub fn exit_code(&self) -> i32 {
+        let mut code = 0;
+        for form in &self.forms {
+            match form.outcome {
+                FormOutcome::Value(_) => {}
+                FormOutcome::Error(_) => code = 1,
+                FormOutcome::Exit(exit) => return exit,
+            }
+        }
+        code
+    }
+
+    pub fn to_json(&self) -> String {
+        let forms: Vec<String> = self
+            .forms
+            .iter()
+            .map(|form| {
+                let outcome = match &form.outcome {
+                    FormOutcome::Value(value) => {
+                        format!("\"status\": \"ok\", \"value\": {}", json_string(value))
+                    }
+                    FormOutcome::Error(error) => {
+                        format!("\"status\": \"error\", \"error\": {}", json_string(error))
+                    }
+                    FormOutcome::Exit(code) => format!("\"status\": \"exit\", \"code\": {}", code),
+                };
+                format!(
+                    "    {{\"line\": {}, \"source\": {}, {}, \"output\": {}, \"duration_ms\": {}}}",
+                    form.line,
+                    json_string(&form.source),
+                    outcome,
+                    json_string(&form.output),
+                    milliseconds(form.duration)
+                )
+            })
+            .collect();
+        let warnings: Vec<String> = self
+            .warnings
+            .iter()
+            .map(|warning| {
+                format!(
+                    "    {{\"line\": {}, \"message\": {}}}",
+                    warning.line,
+                    json_string(&warning.message)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\n  \"file\": {},\n  \"status\": \"{}\",\n  \"exit_code\": {},\n  \"duration_ms\": {},\n  \"forms\": [{}],\n  \"warnings\": [{}]\n}}\n",
+            json_string(&self.file),
+            if self.succeeded() { "ok" } else { "error" },
+            self.exit_code(),
+            milliseconds(self.duration),
+            json_list(&forms),
+            json_list(&warnings)
+        )
+    }
+}
+
+// Evaluates `source` form by form with output captured, so the report (not stdout) holds
+// everything the program printed
+pub fn run_program(file: &str, source: &str) -> RunReport {
+    let started = Instant::now();
+    let mut evaluator = Evaluator::new();
+    if let Some(dir) = Path::new(file).parent() {
+        evaluator.set_require_dir(dir);
+    }
+    let builtins: HashSet<String> = evaluator
+        .get_environment()
+        .get_all_bindings()
+        .into_keys()
+        .collect();
+    let mut defined_at: Vec<(String, usize)> = Vec::new();
+    let mut forms = Vec::new();
+    let mut warnings = Vec::new();
+
+    let captured = evaluator.capture_output();
+    let (parsed, read_error) = split_forms(source);
+    for form in parsed {
+        let form_started = Instant::now();
+        let result = evaluator.eval_source(&form.source);
+        let duration = form_started.elapsed();
+        let output = captured.take();
+
+        for name in evaluator.environment.take_changed_globals() {
+            if builtins.contains(&name) {
+                warnings.push(Warning {
+                    line: form.line,
+                    message: format!("redefines builtin `{}`", name),
+                });
+            } else if let Some((_, first)) = defined_at.iter().find(|(n, _)| *n == name) {
+                warnings.push(Warning {
+                    line: form.line,
+                    message: format!("`{}` was already defined on line {}", name, first),
+                });
+            } else {
+                defined_at.push((name, form.line));
+            }
+        }
+
+        let failed = result.is_err();
+        forms.push(FormReport {
+            line: form.line,
+            source: form.source,
+            outcome: match result {
+                Ok(value) => FormOutcome::Value(write_string(&value)),
+                Err(EvalError::Exit(code)) => FormOutcome::Exit(code),
+                Err(error) => FormOutcome::Error(error.to_string()),
+            },
+            output,
+            duration,
+        });
+        if failed {
+            break;
+        }
+    }
+
+    let completed = forms
+        .iter()
+        .all(|form| matches!(form.outcome, FormOutcome::Value(_)));
+    // An unterminated last form only matters if everything before it ran
+    if let (Some(error), true) = (read_error, completed) {
+        forms.push(FormReport {
+            line: error.line,
+            source: error.source,
+            outcome: FormOutcome::Error(
+                "unexpected end of file (unbalanced parentheses or unterminated string)"
+                    .to_string(),
+            ),
+            output: String::new(),
+            duration: Duration::ZERO,
+        });
+    }
+
+    RunReport {
+        file: file.to_string(),
+        forms,
+        warnings,
+        duration: started.elapsed(),
+    }
+}
+
+// Cuts a program into its top-level forms, each with the line and column it starts on. A
+// form left open at the end of the file comes back separately as the error.
+pub fn split_forms(source: &str) -> (Vec<SourceForm>, Option<SourceForm>) {
+    let chars: Vec<char> = source.chars().collect();
+    let mut forms = Vec::new();
+    let mut line = 1;
+    let mut line_start = 0;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        if ch.is_whitespace() {
+            if ch == '\n' {
+                line += 1;
+                line_start = index + 1;
+            }
+            index += 1;
+            continue;
+        }
+        if ch == ';' {
+            while index < chars.len() && chars[index] != '\n' {
+                index += 1;
+            }
+            continue;
+        }
+
+        let start = index;
+        let complete = match ch {
+            '(' | '[' => scan_list(&chars, &mut index),
+            '"' => {
+                index += 1;
+                scan_string(&chars, &mut index)
+            }
+            _ => {
+                scan_atom(&chars, &mut index);
+                true
+            }
+        };
+
+        let text: String = chars[start..index].iter().collect();
+        let form = SourceForm {
+            line,
+            column: start - line_start + 1,
+            source: text.trim().to_string(),
+        };
+        if let Some(offset) = chars[start..index].iter().rposition(|&c| c == '\n') {
+            line += text.matches('\n').count();
+            line_start = start + offset + 1;
+        }
+        if !complete {
+            return (forms, Some(form));
+        }
+        forms.push(form);
+    }
+    (forms, None)
+}
+
+// Advances past a balanced list starting at `index`; false if the source ends first
+fn scan_list(chars: &[char], index: &mut usize) -> bool {
+    let mut depth = 0;
+    while *index < chars.len() {
+        let ch = chars[*index];
+        *index += 1;
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            }
+            '"' if !scan_string(chars, index) => return false,
+            ';' => {
+                while *index < chars.len() && chars[*index] != '\n' {
+                    *index += 1;
+                }
+            }
+            '#' if chars.get(*index) == Some(&'\\') => *index += 2,
+            _ => {}
+        }
+    }
+    *index = chars.len();
+    false
+}
+
+// Advances past the closing quote of a string whose opening quote is already consumed
+fn scan_string(chars: &[char], index: &mut usize) -> bool {
+    while *index < chars.len() {
+        let ch = chars[*index];
+        *index += 1;
+        match ch {
+            '\\' => *index += 1,
+            '"' => return true,
+            _ => {}
+        }
+    }
+    *index = chars.len();
+    false
+}
+
+fn scan_atom(chars: &[char], index: &mut usize) {
+    if chars[*index] == '#' && chars.get(*index + 1) == Some(&'\\') {
+        *index += 3;
+    } else {
+        *index += 1;
+    }
+    while *index < chars.len() && !chars[*index].is_whitespace() && !"()[];".contains(chars[*index])
+    {
+        *index += 1;
+    }
+    *index = (*index).min(chars.len());
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+fn json_list(items: &[String]) -> String {
+    if items.is_empty() {
+        String::new()
+    } else {
+        format!("\n{}\n  ", items.join(",\n"))
+    }
+}
+
+pub(crate) fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
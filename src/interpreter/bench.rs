@@ -0,0 +1,111 @@
+use crate::interpreter::allocations::AllocationCount;
+use crate::interpreter::batch::{run_program, FormOutcome};
+use std::time::Duration;
+
+// `zeus bench`: runs a program repeatedly, each time in a fresh evaluator with output
+// captured, and summarizes how long the runs took and how much they allocated.
+
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub file: String,
+    pub durations: Vec<Duration>,
+    pub allocations: Vec<AllocationCount>,
+}
+
+// One untimed warm-up run comes first; a run that fails stops the benchmark with the
+// first error as file:line: message
+pub fn bench_program(file: &str, source: &str, runs: usize) -> Result<BenchReport, String> {
+    let mut report = BenchReport {
+        file: file.to_string(),
+        durations: Vec::new(),
+        allocations: Vec::new(),
+    };
+    for run in 0..=runs {
+        let before = AllocationCount::now();
+        let result = run_program(file, source);
+        let allocated = AllocationCount::now().since(before);
+        let error = result.forms.iter().find_map(|form| match &form.outcome {
+            FormOutcome::Error(error) => Some((form.line, error)),
+            FormOutcome::Value(_) | FormOutcome::Exit(_) => None,
+        });
+        if let Some((line, error)) = error {
+            return Err(format!("{}:{}: {}", file, line, error));
+        }
+        if run > 0 {
+            report.durations.push(result.duration);
+            report.allocations.push(allocated);
+        }
+    }
+    Ok(report)
+}
+
+impl BenchReport {
+    pub fn min(&self) -> Duration {
+        self.durations.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.durations.iter().max().copied().unwrap_or_default()
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.durations.is_empty() {
+            return Duration::ZERO;
+        }
+        self.durations.iter().sum::<Duration>() / self.durations.len() as u32
+    }
+
+    pub fn median(&self) -> Duration {
+        let mut sorted = self.durations.clone();
+        sorted.sort();
+        match sorted.len() {
+            0 => Duration::ZERO,
+            n if n % 2 == 0 => (sorted[n / 2 - 1] + sorted[n / 2]) / 2,
+            n => sorted[n / 2],
+        }
+    }
+
+    // Sample standard deviation; zero for fewer than two runs
+    pub fn std_dev(&self) -> Duration {
+        if self.durations.len() < 2 {
+            return Duration::ZERO;
+        }
+        let mean = self.mean().as_secs_f64();
+        let variance = self
+            .durations
+            .iter()
+            .map(|duration| (duration.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / (self.durations.len() - 1) as f64;
+        Duration::from_secs_f64(variance.sqrt())
+    }
+
+    // Mean allocations per run
+    pub fn mean_allocations(&self) -> AllocationCount {
+        let runs = self.allocations.len().max(1) as u64;
+        AllocationCount {
+            allocations: self.allocations.iter().map(|a| a.allocations).sum::<u64>() / runs,
+            bytes: self.allocations.iter().map(|a| a.bytes).sum::<u64>() / runs,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let allocated = self.mean_allocations();
+        format!(
+            "{}: {} runs\n  mean {} ± {}, median {}, min {}, max {}\n  {} allocations, {} bytes per run\n",
+            self.file,
+            self.durations.len(),
+            milliseconds(self.mean()),
+            milliseconds(self.std_dev()),
+            milliseconds(self.median()),
+            milliseconds(self.min()),
+            milliseconds(self.max()),
+            allocated.allocations,
+            allocated.bytes
+        )
+    }
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.3} ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,327 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Arbitrary-precision integer used once i64 arithmetic would overflow.
+// The magnitude is stored little-endian in base 2^32 with no trailing zero limbs,
+// so zero is an empty vector and is never negative.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        BigInt {
+            negative: false,
+            magnitude: Vec::new(),
+        }
+    }
+
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        let negative = negative && !magnitude.is_empty();
+        BigInt {
+            negative,
+            magnitude,
+        }
+    }
+
+    pub fn from_i64(value: i64) -> Self {
+        let abs = value.unsigned_abs();
+        Self::from_parts(value < 0, vec![abs as u32, (abs >> 32) as u32])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    // Bits in the magnitude, without the sign
+    pub fn bits(&self) -> u64 {
+        match self.magnitude.last() {
+            Some(top) => 32 * (self.magnitude.len() as u64 - 1) + (32 - top.leading_zeros()) as u64,
+            None => 0,
+        }
+    }
+
+    // Narrow back to an i64 when the value fits
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let mut abs: u64 = 0;
+        for (i, limb) in self.magnitude.iter().enumerate() {
+            abs |= (*limb as u64) << (32 * i);
+        }
+        if self.negative {
+            if abs <= i64::MAX as u64 + 1 {
+                Some((abs as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else {
+            i64::try_from(abs).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let mut result = 0.0;
+        for limb in self.magnitude.iter().rev() {
+            result = result * 4_294_967_296.0 + *limb as f64;
+        }
+        if self.negative {
+            -result
+        } else {
+            result
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::parse_radix(text, 10)
+    }
+
+    // An optionally signed run of digits in radix (2 to 36, either case)
+    pub fn parse_radix(text: &str, radix: u32) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+
+        let mut magnitude = Vec::new();
+        for digit in digits.chars() {
+            mul_small_add(&mut magnitude, radix, digit.to_digit(radix)?);
+        }
+        Some(Self::from_parts(negative, magnitude))
+    }
+
+    // Lowercase digits in radix (2 to 36), peeled off a u32's worth at a time
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let mut chunk = radix;
+        let mut chunk_digits = 1;
+        while let Some(next) = chunk.checked_mul(radix) {
+            chunk = next;
+            chunk_digits += 1;
+        }
+
+        let mut magnitude = self.magnitude.clone();
+        let mut digits = Vec::new();
+        while !magnitude.is_empty() {
+            let mut remainder = div_small(&mut magnitude, chunk);
+            for _ in 0..chunk_digits {
+                digits.push(std::char::from_digit(remainder % radix, radix).unwrap());
+                remainder /= radix;
+                if magnitude.is_empty() && remainder == 0 {
+                    break;
+                }
+            }
+        }
+        if self.negative {
+            digits.push('-');
+        }
+        digits.iter().rev().collect()
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut result = BigInt::from_i64(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        BigInt::from_i64(value)
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+// Requires |a| >= |b|
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, limb) in a.iter().enumerate() {
+        let mut diff = *limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        } else {
+            borrow = 0;
+        }
+        result.push(diff as u32);
+    }
+    result
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let current = result[i + j] as u64 + (*x as u64) * (*y as u64) + carry;
+            result[i + j] = current as u32;
+            carry = current >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    result
+}
+
+fn mul_small_add(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for limb in magnitude.iter_mut() {
+        let current = (*limb as u64) * factor as u64 + carry;
+        *limb = current as u32;
+        carry = current >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn div_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for limb in magnitude.iter_mut().rev() {
+        let current = (remainder << 32) | *limb as u64;
+        *limb = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    remainder as u32
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(
+                self.negative,
+                add_magnitude(&self.magnitude, &other.magnitude),
+            );
+        }
+        match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(
+                other.negative,
+                sub_magnitude(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::from_parts(
+                self.negative,
+                sub_magnitude(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &(-other)
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            mul_magnitude(&self.magnitude, &other.magnitude),
+        )
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Peel off base-10^9 chunks, least significant first
+        let mut magnitude = self.magnitude.clone();
+        let mut chunks = Vec::new();
+        while !magnitude.is_empty() {
+            chunks.push(div_small(&mut magnitude, 1_000_000_000));
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut iter = chunks.iter().rev();
+        if let Some(first) = iter.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in iter {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,452 @@
+use crate::error::ZeusError;
+use crate::interpreter::batch::{json_string, split_forms, SourceForm};
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::source_map::Position;
+use crate::interpreter::typecheck::check_types;
+use crate::interpreter::xref::{advance, definition_head, name_position, XrefIndex};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+// Static checks behind `zeus check`: reads a file without evaluating it and reports
+// problems with their source span, severity and rule id.
+
+// Every rule `zeus check` knows; all run unless zeus.toml turns them off
+pub const RULES: &[&str] = &[
+    "syntax-error",
+    "unterminated-form",
+    "redefined-builtin",
+    "duplicate-definition",
+    "trailing-whitespace",
+    "unused-definition",
+    "type-mismatch",
+    "unknown-type",
+];
+
+pub const CONFIG_FILE: &str = "zeus.toml";
+
+// Marks a comment that silences findings on the line it ends, or on the next line when it
+// stands alone: `;; zeus-check: ignore rule-a, rule-b`, or every rule with a bare `ignore`
+const SUPPRESSION_MARKER: &str = "zeus-check:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+// Lines and columns are 1-based; the end is exclusive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub severity: Severity,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    // file:line:column: severity[rule]: message
+    Text,
+    Json,
+    // Workflow commands that GitHub Actions shows as annotations on the diff
+    Github,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            "github" => Some(OutputFormat::Github),
+            _ => None,
+        }
+    }
+}
+
+// Rule switches read from the [check.rules] table of zeus.toml, and settings from [check]:
+//   [check]
+//   strict-types = true
+//   [check.rules]
+//   trailing-whitespace = false
+// Other tables in the file are left to the tools that own them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CheckConfig {
+    rules: HashMap<String, bool>,
+    // Type mismatches are errors rather than warnings
+    strict_types: bool,
+}
+
+impl CheckConfig {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut in_rules = false;
+        let mut in_check = false;
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_rules = line == "[check.rules]";
+                in_check = line == "[check]";
+                continue;
+            }
+            if !in_rules && !in_check {
+                continue;
+            }
+
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+            let Some((rule, value)) = line.split_once('=') else {
+                return Err(error(if in_check {
+                    "expected `setting = value`".to_string()
+                } else {
+                    "expected `rule = true` or `rule = false`".to_string()
+                }));
+            };
+            let rule = rule.trim().trim_matches('"');
+            if in_check && rule != "strict-types" {
+                return Err(error(format!("unknown setting `{}`", rule)));
+            }
+            if in_rules && !RULES.contains(&rule) {
+                return Err(error(format!("unknown rule `{}`", rule)));
+            }
+            let enabled = match value.trim() {
+                "true" => true,
+                "false" => false,
+                other => {
+                    return Err(error(format!(
+                        "expected true or false for `{}`, got `{}`",
+                        rule, other
+                    )))
+                }
+            };
+            if in_check {
+                config.strict_types = enabled;
+            } else {
+                config.rules.insert(rule.to_string(), enabled);
+            }
+        }
+        Ok(config)
+    }
+
+    // The nearest zeus.toml in `dir` or one of its parents, or every rule on if there is none
+    pub fn load(dir: &Path) -> Result<Self, ZeusError> {
+        for ancestor in dir.ancestors() {
+            let path = ancestor.join(CONFIG_FILE);
+            if path.is_file() {
+                let text = match fs::read_to_string(&path) {
+                    Ok(text) => text,
+                    Err(error) => return Err(ZeusError::Io { path, error }),
+                };
+                return Self::parse(&text).map_err(|message| ZeusError::Config { path, message });
+            }
+        }
+        Ok(Self::default())
+    }
+
+    pub fn set_enabled(&mut self, rule: &str, enabled: bool) {
+        self.rules.insert(rule.to_string(), enabled);
+    }
+
+    pub fn is_enabled(&self, rule: &str) -> bool {
+        self.rules.get(rule).copied().unwrap_or(true)
+    }
+
+    pub fn set_strict_types(&mut self, strict: bool) {
+        self.strict_types = strict;
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+pub fn check_source(file: &str, source: &str, config: &CheckConfig) -> Vec<Diagnostic> {
+    let builtins: HashSet<String> = Evaluator::new()
+        .get_environment()
+        .get_all_bindings()
+        .into_keys()
+        .collect();
+    let mut diagnostics = Vec::new();
+    let mut defined_at: Vec<(String, usize)> = Vec::new();
+    // Read again with spans for the type checker, which reports positions inside forms
+    let reader = Evaluator::new();
+    let mut typed_forms = Vec::new();
+
+    let (forms, unterminated) = split_forms(source);
+    for form in &forms {
+        if let Ok(expr) = reader.read_at(&form.source, Position::new(form.line, form.column)) {
+            typed_forms.push(expr);
+        }
+        let expr = match Evaluator::parse(&form.source) {
+            Ok(expr) => expr,
+            Err(error) => {
+                diagnostics.push(form_diagnostic(
+                    file,
+                    form,
+                    Severity::Error,
+                    "syntax-error",
+                    error,
+                ));
+                continue;
+            }
+        };
+
+        let Some((_, name)) = definition_head(&expr) else {
+            continue;
+        };
+        let (line, column) = name_position(form, name);
+        let mut report = |rule, message| {
+            diagnostics.push(Diagnostic {
+                file: file.to_string(),
+                line,
+                column,
+                end_line: line,
+                end_column: column + name.chars().count(),
+                severity: Severity::Warning,
+                rule,
+                message,
+            })
+        };
+        if builtins.contains(name) {
+            report("redefined-builtin", format!("redefines builtin `{}`", name));
+        } else if let Some((_, first)) = defined_at.iter().find(|(n, _)| n == name) {
+            report(
+                "duplicate-definition",
+                format!("`{}` was already defined on line {}", name, first),
+            );
+        } else {
+            defined_at.push((name.to_string(), line));
+        }
+    }
+
+    let severity = if config.strict_types {
+        Severity::Error
+    } else {
+        Severity::Warning
+    };
+    for finding in check_types(&typed_forms, &reader.source_map) {
+        let Some(span) = finding.span else {
+            continue;
+        };
+        diagnostics.push(Diagnostic {
+            file: file.to_string(),
+            line: span.start.line,
+            column: span.start.column,
+            end_line: span.end.line,
+            end_column: span.end.column,
+            severity,
+            rule: finding.rule,
+            message: finding.message,
+        });
+    }
+
+    if let Some(form) = unterminated {
+        diagnostics.push(form_diagnostic(
+            file,
+            &form,
+            Severity::Error,
+            "unterminated-form",
+            "unexpected end of file (unbalanced parentheses or unterminated string)".to_string(),
+        ));
+    }
+
+    for (index, text) in source.lines().enumerate() {
+        let trimmed = text.trim_end().chars().count();
+        let length = text.chars().count();
+        if trimmed < length {
+            diagnostics.push(Diagnostic {
+                file: file.to_string(),
+                line: index + 1,
+                column: trimmed + 1,
+                end_line: index + 1,
+                end_column: length + 1,
+                severity: Severity::Warning,
+                rule: "trailing-whitespace",
+                message: "trailing whitespace".to_string(),
+            });
+        }
+    }
+
+    filter_findings(&mut diagnostics, source, config);
+    diagnostics
+}
+
+// `zeus check --dead-code`: definitions in `file` that no entry point reaches, where
+// `index` covers every file being checked and entry points are the top-level forms that
+// run when those files load
+pub fn check_dead_code(
+    index: &XrefIndex,
+    file: &str,
+    source: &str,
+    config: &CheckConfig,
+) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = index
+        .unreachable()
+        .into_iter()
+        .filter(|definition| definition.location.file == file)
+        .map(|definition| Diagnostic {
+            file: file.to_string(),
+            line: definition.location.line,
+            column: definition.location.column,
+            end_line: definition.location.line,
+            end_column: definition.location.column + definition.name.chars().count(),
+            severity: Severity::Warning,
+            rule: "unused-definition",
+            message: format!("`{}` is never used from any entry point", definition.name),
+        })
+        .collect();
+    filter_findings(&mut diagnostics, source, config);
+    diagnostics
+}
+
+// Drops findings for rules switched off in `config` or silenced by comments in `source`,
+// then sorts the rest by position
+fn filter_findings(diagnostics: &mut Vec<Diagnostic>, source: &str, config: &CheckConfig) {
+    let suppressions = suppressions(source);
+    diagnostics.retain(|diagnostic| {
+        config.is_enabled(diagnostic.rule)
+            && !suppressions.iter().any(|(line, rules)| {
+                *line == diagnostic.line
+                    && rules
+                        .as_ref()
+                        .is_none_or(|rules| rules.iter().any(|rule| rule == diagnostic.rule))
+            })
+    });
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+}
+
+pub fn format_diagnostics(diagnostics: &[Diagnostic], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => diagnostics
+            .iter()
+            .map(|d| {
+                format!(
+                    "{}:{}:{}: {}[{}]: {}\n",
+                    d.file,
+                    d.line,
+                    d.column,
+                    d.severity.as_str(),
+                    d.rule,
+                    d.message
+                )
+            })
+            .collect(),
+        OutputFormat::Json => {
+            let items: Vec<String> = diagnostics
+                .iter()
+                .map(|d| {
+                    format!(
+                        "  {{\"file\": {}, \"line\": {}, \"column\": {}, \"end_line\": {}, \"end_column\": {}, \"severity\": \"{}\", \"rule\": \"{}\", \"message\": {}}}",
+                        json_string(&d.file),
+                        d.line,
+                        d.column,
+                        d.end_line,
+                        d.end_column,
+                        d.severity.as_str(),
+                        d.rule,
+                        json_string(&d.message)
+                    )
+                })
+                .collect();
+            if items.is_empty() {
+                "[]\n".to_string()
+            } else {
+                format!("[\n{}\n]\n", items.join(",\n"))
+            }
+        }
+        OutputFormat::Github => diagnostics
+            .iter()
+            .map(|d| {
+                format!(
+                    "::{} file={},line={},col={},endLine={},endColumn={},title={}::{}\n",
+                    d.severity.as_str(),
+                    github_property(&d.file),
+                    d.line,
+                    d.column,
+                    d.end_line,
+                    d.end_column,
+                    github_property(d.rule),
+                    github_data(&d.message)
+                )
+            })
+            .collect(),
+    }
+}
+
+fn form_diagnostic(
+    file: &str,
+    form: &SourceForm,
+    severity: Severity,
+    rule: &'static str,
+    message: String,
+) -> Diagnostic {
+    let (end_line, end_column) = advance(form.line, form.column, &form.source);
+    Diagnostic {
+        file: file.to_string(),
+        line: form.line,
+        column: form.column,
+        end_line,
+        end_column,
+        severity,
+        rule,
+        message,
+    }
+}
+
+// Lines silenced by a suppression comment, with the rules named (None for all of them)
+fn suppressions(source: &str) -> Vec<(usize, Option<Vec<String>>)> {
+    let mut found = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let Some(start) = text.find(SUPPRESSION_MARKER) else {
+            continue;
+        };
+        let Some(comment) = text[..start].find(';') else {
+            continue;
+        };
+        let Some(rest) = text[start + SUPPRESSION_MARKER.len()..]
+            .trim()
+            .strip_prefix("ignore")
+        else {
+            continue;
+        };
+        let rules: Vec<String> = rest
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|rule| !rule.is_empty())
+            .map(str::to_string)
+            .collect();
+        let line = if text[..comment].trim().is_empty() {
+            index + 2
+        } else {
+            index + 1
+        };
+        found.push((line, if rules.is_empty() { None } else { Some(rules) }));
+    }
+    found
+}
+
+fn github_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn github_property(text: &str) -> String {
+    github_data(text).replace(':', "%3A").replace(',', "%2C")
+}
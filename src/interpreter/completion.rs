@@ -0,0 +1,180 @@
+use crate::interpreter::path_completion::{common_prefix, complete_path};
+use crate::interpreter::types::Symbol;
+
+// Tab completion for the REPLs, from the text before the cursor alone: a string argument
+// completes as a filesystem path, and a token starting with ':' as one of the keywords the
+// reader has seen.
+
+// Functions whose string arguments name files or programs
+pub const PATH_FUNCTIONS: &[&str] = &["run-process"];
+
+// Forms whose first argument is a list holding a path, as in (with-open-file (f "path") ...)
+pub const PATH_SPEC_FORMS: &[&str] = &["with-open-file"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    // Byte offset where the text the candidates replace begins; it ends at the cursor
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+impl Completion {
+    // What Tab does first: the line and cursor with the typed text extended to the only
+    // candidate or to what all the candidates share. None when that adds nothing, and the
+    // candidates should be listed instead.
+    pub fn extend(&self, line: &str, cursor: usize) -> Option<(String, usize)> {
+        let prefix = match self.candidates.as_slice() {
+            [] => return None,
+            [only] => only.clone(),
+            candidates => common_prefix(candidates),
+        };
+        if prefix.len() <= cursor - self.start {
+            return None;
+        }
+        let edited = format!("{}{}{}", &line[..self.start], prefix, &line[cursor..]);
+        Some((edited, self.start + prefix.len()))
+    }
+
+    // The candidates as listed: paths without the directories already typed
+    pub fn labels(&self, line: &str, cursor: usize) -> Vec<String> {
+        let typed = &line[self.start..cursor];
+        let dir_len = typed.rfind('/').map_or(0, |index| index + 1);
+        self.candidates
+            .iter()
+            .map(|candidate| candidate[dir_len.min(candidate.len())..].to_string())
+            .collect()
+    }
+}
+
+// What could replace the text before `cursor`, a byte offset into `line`. None when the
+// cursor isn't somewhere completion applies.
+pub fn complete(line: &str, cursor: usize) -> Option<Completion> {
+    let before = &line[..cursor];
+    match scan(before) {
+        Context::String { start, list, outer } => {
+            let content = &before[start..];
+            let head = list.map(|open| list_head(&before[open + 1..]));
+            // The list is the form's first argument when only the form's name comes before it
+            let in_path_spec = match (outer, list) {
+                (Some(outer), Some(list)) => {
+                    PATH_SPEC_FORMS.contains(&before[outer + 1..list].trim())
+                }
+                _ => false,
+            };
+            let wants_path = head.is_some_and(|head| PATH_FUNCTIONS.contains(&head))
+                || in_path_spec
+                || looks_like_path(content);
+            // Escapes would have to be undone and redone around the path
+            if !wants_path || content.contains('\\') {
+                return None;
+            }
+            Some(Completion {
+                start,
+                candidates: complete_path(content),
+            })
+        }
+        Context::Code => {
+            let start = before
+                .char_indices()
+                .rev()
+                .find(|(_, ch)| is_delimiter(*ch))
+                .map_or(0, |(index, ch)| index + ch.len_utf8());
+            let prefix = before[start..].strip_prefix(':')?;
+            let mut candidates: Vec<String> = Symbol::keywords()
+                .into_iter()
+                .filter(|keyword| keyword.starts_with(prefix))
+                .map(|keyword| format!(":{}", keyword))
+                .collect();
+            candidates.sort();
+            Some(Completion { start, candidates })
+        }
+        Context::Comment => None,
+    }
+}
+
+enum Context {
+    // Inside a string whose contents start at `start`, within the list opened at `list`,
+    // which is itself within the one opened at `outer`
+    String {
+        start: usize,
+        list: Option<usize>,
+        outer: Option<usize>,
+    },
+    Code,
+    Comment,
+}
+
+// Where the end of `text` is: inside a string, a comment or ordinary code
+fn scan(text: &str) -> Context {
+    let mut open = Vec::new();
+    let mut index = 0;
+    while let Some(ch) = text[index..].chars().next() {
+        let next = index + ch.len_utf8();
+        match ch {
+            '(' | '[' => open.push(index),
+            ')' | ']' => {
+                open.pop();
+            }
+            ';' => match text[index..].find('\n') {
+                Some(end) => {
+                    index += end;
+                    continue;
+                }
+                None => return Context::Comment,
+            },
+            // #\c, which may be #\" or #\(
+            '#' if text[next..].starts_with('\\') => {
+                let after = next + 1;
+                index = after + text[after..].chars().next().map_or(0, char::len_utf8);
+                continue;
+            }
+            '"' => match string_end(&text[next..]) {
+                Some(length) => {
+                    index = next + length;
+                    continue;
+                }
+                None => {
+                    return Context::String {
+                        start: next,
+                        list: open.last().copied(),
+                        outer: open.iter().rev().nth(1).copied(),
+                    }
+                }
+            },
+            _ => {}
+        }
+        index = next;
+    }
+    Context::Code
+}
+
+// The length of a string's contents and closing quote, if it has one
+fn string_end(rest: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, ch) in rest.char_indices() {
+        match ch {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(index + 1),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn is_delimiter(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, '(' | ')' | '[' | ']' | '"' | '\'' | ';')
+}
+
+// The first token of a list's text
+fn list_head(text: &str) -> &str {
+    let text = text.trim_start();
+    let end = text.find(is_delimiter).unwrap_or(text.len());
+    &text[..end]
+}
+
+fn looks_like_path(text: &str) -> bool {
+    text.starts_with('/')
+        || text.starts_with("./")
+        || text.starts_with("../")
+        || text.starts_with("~/")
+}
@@ -0,0 +1,198 @@
+use crate::interpreter::batch::{split_forms, SourceForm};
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::source_map::Position;
+use crate::interpreter::tokenizer::Tokenizer;
+use crate::interpreter::types::{Expr, SymbolData, Token};
+
+// `zeus test --doc`: runs the examples written in function docstrings. An example is a
+// docstring line holding a form, `=>` and the value it should produce:
+//
+//   (defun square (x)
+//     "The square of x.
+//      (square 3) => 9"
+//     (* x x))
+//
+// The file is loaded first, with output captured, then each example is evaluated in it and
+// its value compared, as printed by write, with the expected value as read.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocExample {
+    // The function whose docstring holds the example
+    pub function: String,
+    pub line: usize,
+    pub source: String,
+    pub expected: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocOutcome {
+    Passed,
+    // What the example produced instead, as printed by write
+    Failed(String),
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocReport {
+    pub file: String,
+    pub results: Vec<(DocExample, DocOutcome)>,
+    // Set when the file itself failed to load, in which case no examples ran
+    pub load_error: Option<String>,
+}
+
+impl DocReport {
+    pub fn failures(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, outcome)| *outcome != DocOutcome::Passed)
+            .count()
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.load_error.is_none() && self.failures() == 0
+    }
+
+    // A line per failing example, then a count
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        if let Some(error) = &self.load_error {
+            out.push_str(&format!("{}: {}\n", self.file, error));
+            return out;
+        }
+        for (example, outcome) in &self.results {
+            let problem = match outcome {
+                DocOutcome::Passed => continue,
+                DocOutcome::Failed(actual) => format!("got {}", actual),
+                DocOutcome::Error(error) => format!("error: {}", error),
+            };
+            out.push_str(&format!(
+                "{}:{}: {} => {} ({}): {}\n",
+                self.file,
+                example.line,
+                example.source,
+                example.expected,
+                example.function,
+                problem
+            ));
+        }
+        out.push_str(&format!(
+            "{}: {} examples, {} passed, {} failed\n",
+            self.file,
+            self.results.len(),
+            self.results.len() - self.failures(),
+            self.failures()
+        ));
+        out
+    }
+}
+
+// Every example in the docstrings of the file's top-level defuns, in order
+pub fn extract_examples(source: &str) -> Vec<DocExample> {
+    let (forms, _) = split_forms(source);
+    let mut examples = Vec::new();
+    for form in &forms {
+        let Some((function, doc, doc_line)) = defun_docstring(form) else {
+            continue;
+        };
+        for (offset, line) in doc.lines().enumerate() {
+            let line = line.trim();
+            let Some((example, expected)) = line.split_once("=>") else {
+                continue;
+            };
+            let (example, expected) = (example.trim(), expected.trim());
+            if !example.starts_with('(') || expected.is_empty() {
+                continue;
+            }
+            examples.push(DocExample {
+                function: function.clone(),
+                line: doc_line + offset,
+                source: example.to_string(),
+                expected: expected.to_string(),
+            });
+        }
+    }
+    examples
+}
+
+// The name, docstring and the line the docstring starts on of a (defun name (params)
+// "doc" body...) form. As for describe_symbol, the string is only a docstring when more
+// of the body follows it.
+fn defun_docstring(form: &SourceForm) -> Option<(String, String, usize)> {
+    let expr = Evaluator::parse(&form.source).ok()?;
+    let Expr::List(items) = &expr else {
+        return None;
+    };
+    let [Expr::Symbol(SymbolData::Interned(head)), Expr::Symbol(name), Expr::List(_), Expr::String(doc), _, ..] =
+        items.as_slice()
+    else {
+        return None;
+    };
+    if head != "defun" {
+        return None;
+    }
+
+    // The docstring is the first string token after the parameter list closes
+    let origin = Position::new(form.line, form.column);
+    let (tokens, spans) = Tokenizer::with_origin(&form.source, origin)
+        .tokenize_spanned()
+        .ok()?;
+    let mut depth = 0;
+    let mut params_closed = false;
+    for (token, span) in tokens.iter().zip(&spans) {
+        match token {
+            Token::LeftParen | Token::LeftBracket => depth += 1,
+            Token::RightParen | Token::RightBracket => {
+                depth -= 1;
+                params_closed = depth == 1;
+            }
+            Token::String(_) if params_closed && depth == 1 => {
+                return Some((name.name().to_string(), doc.clone(), span.start.line));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Loads `source` and runs its examples
+pub fn run_doctests(file: &str, source: &str) -> DocReport {
+    let mut report = DocReport {
+        file: file.to_string(),
+        results: Vec::new(),
+        load_error: None,
+    };
+    let mut evaluator = Evaluator::new();
+    evaluator.capture_output();
+
+    let (forms, read_error) = split_forms(source);
+    for form in forms {
+        let origin = Position::new(form.line, form.column);
+        if let Err(error) = evaluator.eval_source_at(&form.source, origin) {
+            report.load_error = Some(format!("line {}: {}", form.line, error));
+            return report;
+        }
+    }
+    if let Some(form) = read_error {
+        report.load_error = Some(format!(
+            "line {}: unbalanced parentheses or unterminated string",
+            form.line
+        ));
+        return report;
+    }
+
+    for example in extract_examples(source) {
+        let outcome = match Evaluator::parse(&example.expected) {
+            Err(error) => DocOutcome::Error(format!("can't read the expected value: {}", error)),
+            Ok(expected) => match evaluator.eval_source(&example.source) {
+                Ok(actual) if write_string(&actual) == write_string(&expected) => {
+                    DocOutcome::Passed
+                }
+                Ok(actual) => DocOutcome::Failed(write_string(&actual)),
+                Err(error) => DocOutcome::Error(error.to_string()),
+            },
+        };
+        report.results.push((example, outcome));
+    }
+    report
+}
@@ -1,121 +1,455 @@
-use crate::interpreter::types::{Expr, SymbolData};
-use std::collections::HashMap;
+use crate::interpreter::symbol_map::SymbolMap;
+use crate::interpreter::types::{Expr, Symbol, SymbolData};
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
 
+// Every builtin function name, in the order their symbols are interned at startup
+pub const BUILTINS: &[&str] = &[
+    "+",
+    "-",
+    "*",
+    "/",
+    "expt",
+    "=",
+    "/=",
+    "<",
+    "<=",
+    ">",
+    ">=",
+    "eq?",
+    "eqv?",
+    "equal?",
+    "list",
+    "car",
+    "cdr",
+    "cons",
+    "append",
+    "reverse",
+    "length",
+    "nth",
+    "nthcdr",
+    "mapcar",
+    "filter",
+    "remove",
+    "remove-if",
+    "remove-if-not",
+    "member-if",
+    "find-if",
+    "member",
+    // Association and property lists
+    "assoc",
+    "assq",
+    "rassoc",
+    "assoc-if",
+    "getf",
+    "plist-get",
+    "plist-put",
+    "alist->hash",
+    "hash->alist",
+    "reduce",
+    "foldl",
+    "foldr",
+    "map-indexed",
+    "zip",
+    "unzip",
+    "iota",
+    "take",
+    "drop",
+    "partition",
+    "flatten",
+    "last",
+    "transduce",
+    "map-filter-reduce",
+    "sort",
+    "stable-sort",
+    "sort-by",
+    "apply",
+    "funcall",
+    "identity",
+    "partial",
+    "compose",
+    "constantly",
+    "dynamic-wind",
+    "invoke-restart",
+    "compute-restarts",
+    "print",
+    "println",
+    "eprint",
+    "eprintln",
+    "read-line",
+    "write-line",
+    "read-char",
+    "peek-char",
+    "format",
+    "pprint",
+    "gensym",
+    "get",
+    "put",
+    "symbol-plist",
+    // Session operations
+    "export-session",
+    "session-script",
+    "start-recording",
+    "stop-recording",
+    "recorded-forms",
+    "global-history",
+    "rewind",
+    // Environment transactions
+    "begin-transaction",
+    "commit-transaction",
+    "rollback-transaction",
+    // Vector operations
+    "vector",
+    "make-vector",
+    "vector-ref",
+    "vector-set!",
+    "vector-length",
+    "vector-map",
+    "vector-fill!",
+    "subvector",
+    "vector->list",
+    "list->vector",
+    "vector-push",
+    "vector-pop",
+    "vector-sort!",
+    "vector-add",
+    "vector-scale",
+    "vector-dot",
+    "vector-sum",
+    "make-matrix",
+    "list->matrix",
+    "matrix->list",
+    "matrix-rows",
+    "matrix-cols",
+    "matrix-ref",
+    "matrix-set!",
+    "matrix-multiply",
+    "matrix-transpose",
+    "make-queue",
+    "enqueue",
+    "dequeue",
+    "queue-front",
+    "make-stack",
+    "push!",
+    "pop!",
+    "stack-top",
+    "make-heap",
+    "heap-push!",
+    "heap-pop!",
+    "heap-peek",
+    "iterate",
+    "next",
+    "iter-done?",
+    "iter->list",
+    "range",
+    "make-struct",
+    "struct-ref",
+    "struct-set!",
+    "struct-type",
+    "struct-fields",
+    "call-generic",
+    "call-next-method",
+    "next-method-p",
+    "type-of",
+    "mean",
+    "median",
+    "variance",
+    "stddev",
+    "percentile",
+    // Hash table operations
+    "make-hash-table",
+    "hash-set!",
+    "hash-ref",
+    "hash-remove!",
+    "hash-keys",
+    "hash-values",
+    "hash-count",
+    "hash-map",
+    "hash-for-each",
+    "hash-update!",
+    // Character operations
+    "char=",
+    "char<",
+    "char>",
+    "char<=",
+    "char>=",
+    "string=",
+    "string<",
+    "string>",
+    "string<=",
+    "string>=",
+    "string-ci=",
+    "string-split",
+    "regex-match?",
+    "regex-find",
+    "regex-find-all",
+    "regex-replace",
+    "getenv",
+    "setenv",
+    "command-line",
+    "exit",
+    "run-process",
+    "require",
+    "tcp-connect",
+    "tcp-send",
+    "tcp-receive",
+    "tcp-listen",
+    "tcp-accept",
+    "tcp-port",
+    "tcp-close",
+    "http-get",
+    "http-post",
+    "char->integer",
+    "integer->char",
+    "alpha?",
+    "digit?",
+    "whitespace?",
+    "string->number",
+    "number->string",
+    // Type predicates
+    "integerp",
+    "floatp",
+    "rationalp",
+    "numberp",
+    "characterp",
+    "vectorp",
+    "hash-table-p",
+    "matrixp",
+    "queuep",
+    "stackp",
+    "heapp",
+    "iteratorp",
+    "structp",
+];
+
+// Variables are keyed by interned symbol, so a lookup compares ids and never hashes or
+// compares the name. Scoping is dynamic (a function body sees its caller's bindings), so
+// lookups walk the frames innermost first before falling back to the globals.
+//
+// Globals and symbol properties are persistent or shared copy-on-write, so cloning an
+// environment (or fork, for a sandbox) costs the same however many definitions it holds.
 #[derive(Clone)]
 pub struct Environment {
-    scopes: Vec<HashMap<String, Expr>>,
-    symbol_properties: HashMap<String, HashMap<String, Expr>>,
+    globals: SymbolMap<Expr>,
+    // One frame per let or function call; frames hold a handful of bindings, where a
+    // linear scan of ids beats hashing
+    frames: Vec<Frame>,
+    symbol_properties: Rc<HashMap<String, HashMap<String, Expr>>>,
     gensym_counter: u64,
+    // Globals created or replaced by define/defun since the last take_changed_globals
+    changed_globals: BTreeSet<String>,
+    // Where each open transaction began, innermost last
+    transactions: Vec<Checkpoint>,
+}
+
+// The globals and symbol properties at some moment, to go back to; O(1) to take
+#[derive(Clone)]
+pub struct Checkpoint {
+    globals: SymbolMap<Expr>,
+    symbol_properties: Rc<HashMap<String, HashMap<String, Expr>>>,
+}
+
+// What opened a local scope, so the debugger can label each one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeKind {
+    // let, let* or letrec
+    Let,
+    // A function's parameters
+    Call,
+    // do, for or dolist
+    Loop,
+    // A restart-case clause's parameters
+    Restart,
+}
+
+impl ScopeKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ScopeKind::Let => "let",
+            ScopeKind::Call => "function call",
+            ScopeKind::Loop => "loop",
+            ScopeKind::Restart => "restart",
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Frame {
+    kind: ScopeKind,
+    bindings: Vec<(Symbol, Expr)>,
 }
 
 impl Environment {
     pub fn new() -> Self {
         Environment {
-            scopes: vec![HashMap::new()],
-            symbol_properties: HashMap::new(),
+            globals: SymbolMap::new(),
+            frames: Vec::new(),
+            symbol_properties: Rc::new(HashMap::new()),
             gensym_counter: 0,
+            changed_globals: BTreeSet::new(),
+            transactions: Vec::new(),
         }
     }
 
+    // Builtins need no bindings: a builtin's symbol evaluates to itself until it's
+    // redefined (Symbol::is_builtin), so only t is set here. nil needs no binding either,
+    // since the reader turns it into the empty list.
     pub fn define_builtins(&mut self) {
-        let builtins = vec![
-            "+",
-            "-",
-            "*",
-            "/",
-            "=",
-            "/=",
-            "<",
-            "<=",
-            ">",
-            ">=",
-            "list",
-            "car",
-            "cdr",
-            "cons",
-            "append",
-            "reverse",
-            "length",
-            "nth",
-            "nthcdr",
-            "mapcar",
-            "filter",
-            "remove",
-            "member",
-            "reduce",
-            "apply",
-            "funcall",
-            "print",
-            "println",
-            "gensym",
-            "get",
-            "put",
-            "symbol-plist",
-            // Vector operations
-            "vector",
-            "make-vector",
-            "vector-ref",
-            "vector-set!",
-            "vector-length",
-            // Hash table operations
-            "make-hash-table",
-            "hash-set!",
-            "hash-ref",
-            "hash-remove!",
-            "hash-keys",
-            // Character operations
-            "char=",
-            "char<",
-            "char>",
-            "char->integer",
-            "integer->char",
-            // Type predicates
-            "integerp",
-            "floatp",
-            "rationalp",
-            "numberp",
-            "characterp",
-            "vectorp",
-            "hash-table-p",
-        ];
-        for builtin in builtins {
-            self.set(
-                builtin.to_string(),
-                Expr::Symbol(SymbolData::Interned(builtin.to_string())),
-            );
-        }
-
-        // Standard Lisp truth constants
         self.set(
             "t".to_string(),
-            Expr::Symbol(SymbolData::Interned("t".to_string())),
+            Expr::Symbol(SymbolData::Interned(Symbol::intern("t"))),
         );
-        self.set("nil".to_string(), Expr::List(vec![]));
     }
 
-    pub fn push_scope(&mut self) {
-        self.scopes.push(HashMap::new());
+    // An independent copy for a sandbox: definitions made in either one don't show up in
+    // the other, though mutable values they share (vectors, hash tables) are still shared.
+    // Starts with no changed globals of its own.
+    pub fn fork(&self) -> Self {
+        Environment {
+            changed_globals: BTreeSet::new(),
+            transactions: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    pub fn push_scope(&mut self, kind: ScopeKind) {
+        self.frames.push(Frame {
+            kind,
+            bindings: Vec::new(),
+        });
     }
 
+    // The global scope is never popped
     pub fn pop_scope(&mut self) {
-        if self.scopes.len() > 1 {
-            self.scopes.pop();
-        }
+        self.frames.pop();
+    }
+
+    // How many local scopes are open; `unwind_to` closes the ones opened since
+    pub fn scope_depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn unwind_to(&mut self, depth: usize) {
+        self.frames.truncate(depth);
     }
 
     pub fn set(&mut self, name: String, value: Expr) {
-        if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(name, value);
+        self.set_symbol(Symbol::intern(&name), value);
+    }
+
+    // Binds in the innermost scope
+    pub fn set_symbol(&mut self, symbol: Symbol, value: Expr) {
+        let Some(frame) = self.frames.last_mut() else {
+            self.globals.insert(symbol, value);
+            return;
+        };
+        match frame
+            .bindings
+            .iter_mut()
+            .find(|(bound, _)| *bound == symbol)
+        {
+            Some(binding) => binding.1 = value,
+            None => frame.bindings.push((symbol, value)),
+        }
+    }
+
+    // set for define/defun, which also records top-level definitions so front-ends
+    // sharing this environment can be told about them
+    pub fn define(&mut self, name: String, value: Expr) {
+        if self.frames.is_empty() {
+            self.changed_globals.insert(name.clone());
         }
+        self.set(name, value);
+    }
+
+    // Removes the global binding of `name`; false if there wasn't one. A builtin's name
+    // goes back to meaning the builtin.
+    pub fn undefine(&mut self, name: &str) -> bool {
+        let removed = self.globals.remove(Symbol::intern(name)).is_some();
+        if removed {
+            self.changed_globals.insert(name.to_string());
+        }
+        removed
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            globals: self.globals.clone(),
+            symbol_properties: Rc::clone(&self.symbol_properties),
+        }
+    }
+
+    // Puts the globals and symbol properties back as they were at `checkpoint`, returning
+    // the names of the globals that changed since, which front-ends are told about too
+    pub fn restore(&mut self, checkpoint: Checkpoint) -> Vec<String> {
+        let mut names: Vec<String> = checkpoint
+            .globals
+            .diff(&self.globals)
+            .into_iter()
+            .filter(|(_, now, then)| now != then)
+            .map(|(symbol, _, _)| symbol.to_string())
+            .collect();
+        names.sort();
+        self.changed_globals.extend(names.iter().cloned());
+        self.globals = checkpoint.globals;
+        self.symbol_properties = checkpoint.symbol_properties;
+        names
+    }
+
+    // Transactions nest: each commit or rollback ends the innermost one open
+    pub fn begin_transaction(&mut self) {
+        self.transactions.push(self.checkpoint());
+    }
+
+    pub fn commit_transaction(&mut self) -> Result<(), String> {
+        self.transactions
+            .pop()
+            .map(|_| ())
+            .ok_or_else(|| "No transaction to commit".to_string())
+    }
+
+    pub fn rollback_transaction(&mut self) -> Result<Vec<String>, String> {
+        let checkpoint = self
+            .transactions
+            .pop()
+            .ok_or_else(|| "No transaction to roll back".to_string())?;
+        Ok(self.restore(checkpoint))
+    }
+
+    pub fn transaction_depth(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn take_changed_globals(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.changed_globals)
+            .into_iter()
+            .collect()
     }
 
     pub fn get(&self, name: &str) -> Result<Expr, String> {
-        for scope in self.scopes.iter().rev() {
-            if let Some(value) = scope.get(name) {
+        // A name that was never interned can't have been bound
+        match Symbol::lookup(name) {
+            Some(symbol) => self.get_symbol(symbol),
+            None => Err(format!("Undefined variable: {}", name)),
+        }
+    }
+
+    pub fn get_symbol(&self, symbol: Symbol) -> Result<Expr, String> {
+        for frame in self.frames.iter().rev() {
+            if let Some((_, value)) = frame
+                .bindings
+                .iter()
+                .rev()
+                .find(|(bound, _)| *bound == symbol)
+            {
                 return Ok(value.clone());
             }
         }
-        Err(format!("Undefined variable: {}", name))
+        match self.globals.get(symbol) {
+            Some(value) => Ok(value.clone()),
+            None if symbol.is_builtin() => Ok(Expr::Symbol(SymbolData::Interned(symbol))),
+            None => Err(format!("Undefined variable: {}", symbol)),
+        }
     }
 
     pub fn get_property(&self, symbol: &str, property: &str) -> Option<Expr> {
@@ -126,7 +460,7 @@ impl Environment {
     }
 
     pub fn set_property(&mut self, symbol: String, property: String, value: Expr) {
-        self.symbol_properties
+        Rc::make_mut(&mut self.symbol_properties)
             .entry(symbol)
             .or_insert_with(HashMap::new)
             .insert(property, value);
@@ -136,7 +470,7 @@ impl Environment {
         if let Some(props) = self.symbol_properties.get(symbol) {
             let mut plist = Vec::new();
             for (key, value) in props.iter() {
-                plist.push(Expr::Symbol(SymbolData::Keyword(key.clone())));
+                plist.push(Expr::Symbol(SymbolData::Keyword(Symbol::intern(key))));
                 plist.push(value.clone());
             }
             plist
@@ -160,14 +494,39 @@ impl Environment {
         SymbolData::Uninterned(name, id)
     }
 
+    pub fn globals(&self) -> impl Iterator<Item = (Symbol, &Expr)> {
+        self.globals.iter()
+    }
+
+    // The globals as they stand, to compare with later; O(1)
+    pub fn globals_snapshot(&self) -> SymbolMap<Expr> {
+        self.globals.clone()
+    }
+
+    // The local scopes, outermost first
+    pub fn scopes(&self) -> impl Iterator<Item = (ScopeKind, &[(Symbol, Expr)])> {
+        self.frames
+            .iter()
+            .map(|frame| (frame.kind, frame.bindings.as_slice()))
+    }
+
     pub fn get_all_bindings(&self) -> HashMap<String, Expr> {
-        let mut all_bindings = HashMap::new();
+        let mut all_bindings: HashMap<String, Expr> = BUILTINS
+            .iter()
+            .map(|name| {
+                let symbol = Expr::Symbol(SymbolData::Interned(Symbol::intern(name)));
+                (name.to_string(), symbol)
+            })
+            .collect();
 
-        // Iterate through scopes from global to local
-        for scope in &self.scopes {
-            for (key, value) in scope {
-                all_bindings.insert(key.clone(), value.clone());
-            }
+        // From global to local, so inner bindings shadow outer ones
+        let frames = self
+            .frames
+            .iter()
+            .flat_map(|frame| &frame.bindings)
+            .map(|(symbol, value)| (*symbol, value));
+        for (symbol, value) in self.globals.iter().chain(frames) {
+            all_bindings.insert(symbol.to_string(), value.clone());
         }
 
         all_bindings
@@ -1,20 +1,292 @@
+use crate::interpreter::environment::ScopeKind;
 use crate::interpreter::{
     environment::Environment,
+    evaluator_generics::MethodFrame,
+    net::SocketTable,
+    packages::RequireState,
     parser::Parser,
+    ports::{InputPort, OutputPort, StderrPort, StdinPort, StdoutPort, StringPort},
+    prelude,
+    recording::Recording,
+    session::SessionLog,
+    source_map::{Position, ReadError, SourceMap, Span},
     tokenizer::Tokenizer,
-    types::{EvalError, EvalResult, Expr, HashKey, SymbolData},
+    types::{EvalError, EvalResult, Expr, HashKey, Symbol, SymbolData},
 };
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// How deeply eval may recurse, and how deeply equal? may descend into nested data, before
+// giving up with a "nesting too deep" error. A user function call costs about three
+// levels; debug builds need ~4KB of stack per level, so the full depth needs the stack
+// with_eval_stack gives. On other threads the stack budget below runs out first.
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+// Stack size of the threads with_eval_stack starts; the command-line front-ends run on one
+pub const EVAL_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+// Stack an evaluator on the main thread may use, leaving the rest of the 8MB to the
+// front-end calling it
+const MAIN_THREAD_STACK_BUDGET: usize = 6 * 1024 * 1024;
+
+thread_local! {
+    // The stack budget evaluators created on this thread start with
+    static STACK_BUDGET: Cell<usize> = const { Cell::new(MAIN_THREAD_STACK_BUDGET) };
+}
+
+// Runs `f` on a thread with EVAL_STACK_SIZE of stack, so evaluators created there can
+// recurse the full DEFAULT_MAX_DEPTH
+pub fn with_eval_stack<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let thread = std::thread::Builder::new()
+        .stack_size(EVAL_STACK_SIZE)
+        .spawn(move || {
+            STACK_BUDGET.set(EVAL_STACK_SIZE - MAIN_THREAD_STACK_BUDGET);
+            f()
+        })
+        .expect("failed to start the evaluator thread");
+    match thread.join() {
+        Ok(value) => value,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+// The address of a local in a fresh frame: how far down the stack the caller is
+#[inline(never)]
+pub(crate) fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+// Every special form name, in the order eval dispatches on them
+pub const SPECIAL_FORMS: &[&str] = &[
+    "define",
+    "defun",
+    "if",
+    "quote",
+    "lambda",
+    "let",
+    "let*",
+    "cond",
+    "and",
+    "or",
+    "progn",
+    "when",
+    "unless",
+    "case",
+    "letrec",
+    "begin",
+    "do",
+    "loop",
+    "while",
+    "until",
+    "break",
+    "continue",
+    "catch",
+    "throw",
+    "unwind-protect",
+    "block",
+    "return-from",
+    "tagbody",
+    "go",
+    "time",
+    "with-output-to-string",
+    "restart-case",
+    "for",
+    "dolist",
+    "with-open-file",
+    "defstruct",
+    "defgeneric",
+    "defmethod",
+    "the",
+    "declare",
+    "undefine",
+];
+
+// How many evaluation steps pass between calls to `interrupt_poll`
+pub const INTERRUPT_POLL_INTERVAL: usize = 1024;
+
+// How EvalError::Interrupted reads
+pub const INTERRUPTED: &str = "interrupted";
+
+// Limits on a single top-level evaluation, for running untrusted or experimental code.
+// Running past either one fails with EvalError::Timeout.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EvalBudget {
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+// A restart established by a restart-case clause: (name (params...) body...)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Restart {
+    pub id: usize,
+    pub name: String,
+    pub params: Vec<String>,
+}
+
+// Given an error on its way out of a restart-case and the active restarts, innermost
+// first, picks the id of one to take and the arguments to pass it, or None to let the
+// error keep unwinding. It may evaluate code, e.g. to compute those arguments.
+pub type RestartChooser =
+    Rc<dyn Fn(&mut Evaluator, &EvalError, &[Restart]) -> Option<(usize, Vec<Expr>)>>;
 
 #[derive(Clone)]
 pub struct Evaluator {
     pub environment: Environment,
+    pub session: SessionLog,
+    // Set when a session command (session-script, rewind, ...) runs, so the top-level form
+    // that called it stays out of the session log and the recording
+    pub(crate) session_command_ran: bool,
+    // While recording, each top-level form and the globals it changed; see recording.rs
+    pub recording: Option<Recording>,
+    // Set by (rewind n), which takes effect once the top-level form it's in is done
+    pub(crate) pending_rewind: Option<usize>,
+    pub max_depth: usize,
+    pub(crate) depth: usize,
+    // Bytes of stack eval may use below the top-level call, and where that call started
+    pub max_stack: usize,
+    pub(crate) stack_base: usize,
+    // Program I/O; see ports.rs. Forks share them.
+    pub output: Rc<dyn OutputPort>,
+    pub error_output: Rc<dyn OutputPort>,
+    pub input: Rc<dyn InputPort>,
+    // The rest of the last line read from `input` by read-char or peek-char
+    pub(crate) input_buffer: String,
+    // Set from another thread or a signal handler to stop the running evaluation with
+    // EvalError::Interrupted; cleared whenever eval_str starts and finishes
+    interrupt: Arc<AtomicBool>,
+    // Front-ends that evaluate on their UI thread can't see input until eval returns, so
+    // they check for a stop key here instead; returning true interrupts
+    pub interrupt_poll: Option<Rc<dyn Fn() -> bool>>,
+    steps: usize,
+    pub budget: EvalBudget,
+    // Run each top-level form through optimize_expr before evaluating it
+    pub optimize: bool,
+    // Steps taken and the deadline for the top-level evaluation in progress
+    budget_steps: u64,
+    deadline: Option<Instant>,
+    // Established by the restart-case forms being evaluated, innermost last
+    pub(crate) restarts: Vec<Restart>,
+    pub(crate) next_restart_id: usize,
+    // Front-ends install this to offer the active restarts when an error happens inside
+    // restart-case. Once it has declined an error, outer restart-cases don't ask again.
+    pub restart_chooser: Option<RestartChooser>,
+    pub(crate) restart_declined: bool,
+    // What (command-line) returns: the arguments given after `--` when zeus was started
+    pub command_line: Vec<String>,
+    // Off in sandboxes that mustn't start other programs with run-process
+    pub allow_processes: bool,
+    // Off in sandboxes that mustn't open sockets or make HTTP requests
+    pub allow_network: bool,
+    // Sockets opened by the networking builtins, by number
+    pub(crate) sockets: Rc<RefCell<SocketTable>>,
+    // The files (require ...) has loaded and where it's loading from; see packages.rs
+    pub(crate) requires: RequireState,
+    // The methods being run by generic functions, innermost last, for call-next-method
+    pub(crate) method_frames: Vec<MethodFrame>,
+    // Where the lists read by eval_source came from; shared with forks
+    pub source_map: SourceMap,
+    // The innermost form with a known span that the error in flight has passed through
+    error_span: Option<Span>,
 }
 
 impl Evaluator {
     pub fn new() -> Self {
+        let mut evaluator = Self::without_prelude();
+        if prelude::is_enabled() {
+            evaluator.environment = prelude::environment();
+        }
+        evaluator
+    }
+
+    // Just the builtins, without the functions prelude.lisp defines
+    pub fn without_prelude() -> Self {
         let mut env = Environment::new();
         env.define_builtins();
-        Evaluator { environment: env }
+        Evaluator {
+            environment: env,
+            session: SessionLog::new(),
+            session_command_ran: false,
+            recording: None,
+            pending_rewind: None,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
+            max_stack: STACK_BUDGET.get(),
+            stack_base: 0,
+            output: Rc::new(StdoutPort),
+            error_output: Rc::new(StderrPort),
+            input: Rc::new(StdinPort),
+            input_buffer: String::new(),
+            interrupt: Arc::new(AtomicBool::new(false)),
+            interrupt_poll: None,
+            steps: 0,
+            budget: EvalBudget::default(),
+            optimize: false,
+            budget_steps: 0,
+            deadline: None,
+            restarts: Vec::new(),
+            next_restart_id: 0,
+            restart_chooser: None,
+            restart_declined: false,
+            command_line: std::env::args()
+                .skip_while(|arg| arg != "--")
+                .skip(1)
+                .collect(),
+            allow_processes: true,
+            allow_network: true,
+            sockets: Rc::new(RefCell::new(SocketTable::default())),
+            requires: RequireState::default(),
+            method_frames: Vec::new(),
+            source_map: SourceMap::new(),
+            error_span: None,
+        }
+    }
+
+    // A sandbox for a test, a watch expression or a completion query: starts from this
+    // evaluator's definitions in O(1), keeps its settings and I/O ports, and has its own
+    // environment, session log and interrupt flag
+    pub fn fork(&self) -> Self {
+        Evaluator {
+            environment: self.environment.fork(),
+            session: SessionLog::new(),
+            session_command_ran: false,
+            recording: None,
+            pending_rewind: None,
+            max_depth: self.max_depth,
+            depth: 0,
+            max_stack: self.max_stack,
+            stack_base: 0,
+            output: Rc::clone(&self.output),
+            error_output: Rc::clone(&self.error_output),
+            input: Rc::clone(&self.input),
+            input_buffer: String::new(),
+            interrupt: Arc::new(AtomicBool::new(false)),
+            interrupt_poll: self.interrupt_poll.clone(),
+            steps: 0,
+            budget: self.budget,
+            optimize: self.optimize,
+            budget_steps: 0,
+            deadline: None,
+            restarts: Vec::new(),
+            next_restart_id: 0,
+            restart_chooser: None,
+            restart_declined: false,
+            command_line: self.command_line.clone(),
+            allow_processes: self.allow_processes,
+            allow_network: self.allow_network,
+            sockets: Rc::clone(&self.sockets),
+            requires: self.requires.clone(),
+            method_frames: Vec::new(),
+            source_map: self.source_map.clone(),
+            error_span: None,
+        }
     }
 
     pub fn get_environment(&self) -> &Environment {
@@ -26,7 +298,7 @@ impl Evaluator {
         let tokens = tokenizer.tokenize()?;
 
         if tokens.is_empty() {
-            return Ok(Expr::List(vec![]));
+            return Ok(Expr::new_list(vec![]));
         }
 
         let mut parser = Parser::new(tokens);
@@ -34,8 +306,151 @@ impl Evaluator {
     }
 
     pub fn eval_str(&mut self, input: &str) -> Result<Expr, String> {
-        let expr = Self::parse(input)?;
-        self.eval(&expr).map_err(|e| e.to_string())
+        self.eval_source(input).map_err(|e| e.to_string())
+    }
+
+    // parse, recording the span of every list in `source_map`, with lines and columns
+    // counted from `origin`, where `input` starts in its file
+    pub fn read_at(&self, input: &str, origin: Position) -> Result<Expr, ReadError> {
+        let (tokens, spans) = Tokenizer::with_origin(input, origin).tokenize_spanned()?;
+        if tokens.is_empty() {
+            return Ok(Expr::new_list(vec![]));
+        }
+        Parser::with_spans(tokens, spans, self.source_map.clone()).parse_spanned()
+    }
+
+    // eval_str for embedders that need to tell errors apart, e.g. EvalError::Timeout
+    pub fn eval_source(&mut self, input: &str) -> EvalResult {
+        self.eval_source_at(input, Position::START)
+    }
+
+    // eval_source for a form found at `origin` in a larger source, so `error_span` and the
+    // source map use that source's positions
+    pub fn eval_source_at(&mut self, input: &str, origin: Position) -> EvalResult {
+        let expr = self
+            .read_at(input, origin)
+            .map_err(|error| EvalError::message(error.message));
+        self.eval_read(input, expr)
+    }
+
+    // Evaluates `expr`, already read from `input`, as a top-level form
+    pub(crate) fn eval_read(&mut self, input: &str, expr: EvalResult) -> EvalResult {
+        self.interrupt.store(false, Ordering::SeqCst);
+        let scope_depth = self.environment.scope_depth();
+        // Kept for the enclosing form when this one is nested in it (by load, say), which
+        // leaves the log if either runs a session command
+        let outer_session_command = std::mem::take(&mut self.session_command_ran);
+        self.restart_declined = false;
+        self.error_span = None;
+        let globals_before = self
+            .recording
+            .is_some()
+            .then(|| self.environment.globals_snapshot());
+        let result = expr.and_then(|expr| {
+            let expr = if self.optimize {
+                let optimized = self.optimize_expr(&expr);
+                self.source_map.inherit(&optimized, &expr);
+                optimized
+            } else {
+                expr
+            };
+            self.eval(&expr)
+        });
+        self.environment.unwind_to(scope_depth);
+        self.interrupt.store(false, Ordering::SeqCst);
+        let session_command = self.session_command_ran;
+        self.session_command_ran |= outer_session_command;
+        if !session_command {
+            self.session.record(input, result.is_ok());
+        }
+        if let Some(form) = self.pending_rewind.take() {
+            return self
+                .rewind(form)
+                .map(|replayed| Expr::Integer(replayed as i64))
+                .map_err(EvalError::message);
+        }
+        if let (Some(recording), Some(before), false) =
+            (&mut self.recording, globals_before, session_command)
+        {
+            recording.record(input, result.is_ok(), &before, &self.environment);
+        }
+        result
+    }
+
+    // Where the last top-level evaluation failed: the innermost form read with a span that
+    // the error passed through on its way out
+    pub fn error_span(&self) -> Option<Span> {
+        self.error_span
+    }
+
+    // How many evaluation steps have been taken, ever; wraps around
+    pub fn step_count(&self) -> usize {
+        self.steps
+    }
+
+    // For builtins that wait on the outside world: whether the evaluation should stop
+    pub(crate) fn interrupt_requested(&self) -> bool {
+        if let Some(poll) = &self.interrupt_poll {
+            if poll() {
+                self.interrupt.store(true, Ordering::SeqCst);
+            }
+        }
+        self.interrupt.load(Ordering::SeqCst)
+    }
+
+    // Storing true in the returned flag interrupts the evaluation in progress
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupt)
+    }
+
+    // Runs `body` and then `cleanup`, however `body` exits: normally, by throw or
+    // return-from, with an error, or because it was interrupted or ran out of budget. Scopes
+    // `body` left open are closed before `cleanup` runs. An interrupt is held off until
+    // `cleanup` is done, and a timed-out body's cleanup gets a fresh budget, so cleanups
+    // still run on the way out of a runaway form. An error from `cleanup` replaces the
+    // body's outcome.
+    pub fn with_cleanup(
+        &mut self,
+        body: impl FnOnce(&mut Self) -> EvalResult,
+        cleanup: impl FnOnce(&mut Self) -> EvalResult,
+    ) -> EvalResult {
+        let scope_depth = self.environment.scope_depth();
+        let result = body(self);
+        self.environment.unwind_to(scope_depth);
+
+        let interrupted = self.interrupt.swap(false, Ordering::SeqCst);
+        let timed_out = matches!(result, Err(EvalError::Timeout(_)));
+        let exhausted = (self.budget_steps, self.deadline);
+        if timed_out {
+            self.budget_steps = 0;
+            self.deadline = self.budget.timeout.map(|timeout| Instant::now() + timeout);
+        }
+        let cleanup_result = cleanup(self);
+        if interrupted {
+            self.interrupt.store(true, Ordering::SeqCst);
+        }
+        if timed_out {
+            (self.budget_steps, self.deadline) = exhausted;
+        }
+
+        cleanup_result.and(result)
+    }
+
+    // Innermost first, the order a chooser should offer them in
+    pub fn active_restarts(&self) -> Vec<Restart> {
+        self.restarts.iter().rev().cloned().collect()
+    }
+
+    pub fn write_output(&mut self, text: &str) {
+        self.output.write(text);
+    }
+
+    // Sends program output to a fresh string port from here on and returns it, for callers
+    // that want to collect what a program prints
+    pub fn capture_output(&mut self) -> StringPort {
+        let port = StringPort::new();
+        self.output = Rc::new(port.clone());
+        port
     }
 
     pub fn eval_once(input: &str) -> Result<Expr, String> {
@@ -47,6 +462,7 @@ impl Evaluator {
     pub fn to_number(expr: &Expr) -> Result<f64, String> {
         match expr {
             Expr::Integer(n) => Ok(*n as f64),
+            Expr::BigInt(n) => Ok(n.to_f64()),
             Expr::Float(f) => Ok(*f),
             Expr::Rational {
                 numerator,
@@ -63,9 +479,9 @@ impl Evaluator {
 
     pub fn bool_to_expr(value: bool) -> Expr {
         if value {
-            Expr::Symbol(SymbolData::Interned("t".to_string()))
+            Expr::Symbol(SymbolData::Interned(Symbol::intern("t")))
         } else {
-            Expr::List(vec![])
+            Expr::new_list(vec![])
         }
     }
 
@@ -75,42 +491,133 @@ impl Evaluator {
             Expr::Integer(n) => Some(HashKey::Integer(*n)),
             Expr::String(s) => Some(HashKey::String(s.clone())),
             Expr::Character(ch) => Some(HashKey::Character(*ch)),
-            Expr::Symbol(SymbolData::Keyword(name)) => Some(HashKey::Keyword(name.clone())),
-            Expr::Symbol(SymbolData::Interned(name)) => Some(HashKey::Symbol(name.clone())),
+            Expr::Symbol(SymbolData::Keyword(name)) => Some(HashKey::Keyword(*name)),
+            Expr::Symbol(SymbolData::Interned(name)) => Some(HashKey::Symbol(*name)),
             _ => None,
         }
     }
 
+    pub fn hashkey_to_expr(key: &HashKey) -> Expr {
+        key.to_expr()
+    }
+
+    pub fn nesting_too_deep(&self) -> EvalError {
+        EvalError::message(format!("nesting too deep (limit {})", self.max_depth))
+    }
+
+    // Whether recursion from `base` has used up this evaluator's stack budget
+    pub(crate) fn out_of_stack(&self, base: usize) -> bool {
+        base.abs_diff(stack_address()) > self.max_stack
+    }
+
+    pub(crate) fn out_of_stack_error(&self) -> EvalError {
+        EvalError::message("nesting too deep (out of stack space)")
+    }
+
     pub fn eval(&mut self, expr: &Expr) -> EvalResult {
+        // Each top-level evaluation gets a fresh budget
+        if self.depth == 0 {
+            self.stack_base = stack_address();
+            self.budget_steps = 0;
+            self.deadline = self.budget.timeout.map(|timeout| Instant::now() + timeout);
+        }
+        self.budget_steps += 1;
+        if let Some(max_steps) = self.budget.max_steps {
+            if self.budget_steps > max_steps {
+                return Err(EvalError::Timeout(format!(
+                    "exceeded {} evaluation steps",
+                    max_steps
+                )));
+            }
+        }
+
+        self.steps = self.steps.wrapping_add(1);
+        if self.steps.is_multiple_of(INTERRUPT_POLL_INTERVAL) {
+            if let Some(poll) = &self.interrupt_poll {
+                if poll() {
+                    self.interrupt.store(true, Ordering::SeqCst);
+                }
+            }
+            if let (Some(deadline), Some(timeout)) = (self.deadline, self.budget.timeout) {
+                if Instant::now() >= deadline {
+                    return Err(EvalError::Timeout(format!(
+                        "exceeded {}ms",
+                        timeout.as_millis()
+                    )));
+                }
+            }
+        }
+        if self.interrupt.load(Ordering::Relaxed) {
+            return Err(EvalError::Interrupted);
+        }
+        if self.depth >= self.max_depth {
+            return Err(self.nesting_too_deep());
+        }
+        if self.out_of_stack(self.stack_base) {
+            return Err(self.out_of_stack_error());
+        }
+        self.depth += 1;
+        let result = self.eval_expr(expr);
+        self.depth -= 1;
+        match &result {
+            // Anything that succeeds has handled whatever error was in flight below it
+            Ok(_) => {
+                if self.error_span.is_some() {
+                    self.error_span = None;
+                }
+            }
+            Err(EvalError::Message(_) | EvalError::Timeout(_)) if self.error_span.is_none() => {
+                self.error_span = self.source_map.span_of(expr);
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    fn eval_expr(&mut self, expr: &Expr) -> EvalResult {
         match expr {
             Expr::Integer(_)
+            | Expr::BigInt(_)
             | Expr::Float(_)
             | Expr::Rational { .. }
             | Expr::String(_)
             | Expr::Character(_)
-            | Expr::Vector(_)
             | Expr::HashTable(_)
+            | Expr::Matrix(_)
+            | Expr::Queue(_)
+            | Expr::Stack(_)
+            | Expr::Heap(_)
+            | Expr::Iterator(_)
+            | Expr::Struct(_)
+            | Expr::Port(_)
             | Expr::Cons(_, _) => Ok(expr.clone()),
+            // Each evaluation of a vector literal makes a fresh vector, so mutating one
+            // inside a function body doesn't change the literal for the next call
+            Expr::Vector(items) => Ok(Expr::new_vector(items.borrow().clone())),
             Expr::Symbol(sym_data) => {
                 match sym_data {
                     SymbolData::Keyword(_) => {
                         // Keywords are self-evaluating
                         Ok(expr.clone())
                     }
-                    SymbolData::Interned(name) | SymbolData::Uninterned(name, _) => {
+                    SymbolData::Interned(_) | SymbolData::Uninterned(_, _) => {
                         // Regular symbols and uninterned symbols evaluate to their values
-                        self.environment.get(name).map_err(EvalError::message)
+                        self.environment
+                            .get_symbol(sym_data.symbol())
+                            .map_err(EvalError::message)
                     }
                 }
             }
             Expr::List(list) => {
                 if list.is_empty() {
-                    return Ok(Expr::List(vec![]));
+                    return Ok(Expr::new_list(vec![]));
                 }
 
                 let first = &list[0];
                 match first {
-                    Expr::Symbol(sym_data) => match sym_data.name() {
+                    // Keywords are stored without the colon, so (:if x) must not reach
+                    // the special forms
+                    Expr::Symbol(sym_data) if !sym_data.is_keyword() => match sym_data.name() {
                         "define" => self.eval_define(list),
                         "defun" => self.eval_defun(list),
                         "if" => self.eval_if(list),
@@ -129,6 +636,10 @@ impl Evaluator {
                         "begin" => self.eval_begin(list),
                         "do" => self.eval_do(list),
                         "loop" => self.eval_loop(list),
+                        "while" => self.eval_while(list, true),
+                        "until" => self.eval_while(list, false),
+                        "break" => self.eval_break(list),
+                        "continue" => self.eval_continue(list),
                         "catch" => self.eval_catch(list),
                         "throw" => self.eval_throw(list),
                         "unwind-protect" => self.eval_unwind_protect(list),
@@ -136,6 +647,18 @@ impl Evaluator {
                         "return-from" => self.eval_return_from(list),
                         "tagbody" => self.eval_tagbody(list),
                         "go" => self.eval_go(list),
+                        "time" => self.eval_time(list),
+                        "with-output-to-string" => self.eval_with_output_to_string(list),
+                        "restart-case" => self.eval_restart_case(list),
+                        "for" => self.eval_for(list),
+                        "dolist" => self.eval_dolist(list),
+                        "with-open-file" => self.eval_with_open_file(list),
+                        "defstruct" => self.eval_defstruct(list),
+                        "defgeneric" => self.eval_defgeneric(list),
+                        "defmethod" => self.eval_defmethod(list),
+                        "the" => self.eval_the(list),
+                        "declare" => self.eval_declare(list),
+                        "undefine" => self.eval_undefine(list),
                         _ => self.eval_application(list),
                     },
                     _ => self.eval_application(list),
@@ -155,7 +678,7 @@ impl Evaluator {
             }
             let value = self.eval(&list[2])?;
             self.environment
-                .set(sym_data.name().to_string(), value.clone());
+                .define(sym_data.name().to_string(), value.clone());
             Ok(value)
         } else {
             Err(EvalError::message(
@@ -164,6 +687,17 @@ impl Evaluator {
         }
     }
 
+    // (undefine name): removes the global definition of name, returning t if there was one
+    fn eval_undefine(&mut self, list: &[Expr]) -> EvalResult {
+        match list {
+            [_, Expr::Symbol(SymbolData::Interned(name))] => {
+                Ok(Self::bool_to_expr(self.environment.undefine(name.as_str())))
+            }
+            [_, _] => Err(EvalError::message("undefine requires a symbol")),
+            _ => Err(EvalError::message("undefine requires exactly 1 argument")),
+        }
+    }
+
     fn eval_defun(&mut self, list: &[Expr]) -> EvalResult {
         if list.len() < 4 {
             return Err(EvalError::message(
@@ -187,12 +721,7 @@ impl Evaluator {
 
         let params = match &list[2] {
             Expr::List(params) => {
-                // Verify all params are symbols
-                for param in params {
-                    if !matches!(param, Expr::Symbol(_)) {
-                        return Err(EvalError::message("All parameters must be symbols"));
-                    }
-                }
+                Self::split_rest_param(params)?;
                 list[2].clone()
             }
             _ => {
@@ -204,7 +733,7 @@ impl Evaluator {
 
         // Build the lambda expression: (lambda params body...)
         let mut lambda_expr = vec![
-            Expr::Symbol(SymbolData::Interned("lambda".to_string())),
+            Expr::Symbol(SymbolData::Interned(Symbol::intern("lambda"))),
             params,
         ];
 
@@ -214,20 +743,20 @@ impl Evaluator {
             lambda_expr.push(list[3].clone());
         } else {
             // Multiple body expressions - wrap in progn
-            let mut progn_expr = vec![Expr::Symbol(SymbolData::Interned("progn".to_string()))];
+            let mut progn_expr = vec![Expr::Symbol(SymbolData::Interned(Symbol::intern("progn")))];
             for body_expr in &list[3..] {
                 progn_expr.push(body_expr.clone());
             }
-            lambda_expr.push(Expr::List(progn_expr));
+            lambda_expr.push(Expr::new_list(progn_expr));
         }
 
-        let lambda = Expr::List(lambda_expr);
+        let lambda = Expr::new_list(lambda_expr);
 
         // Store the lambda in the environment
-        self.environment.set(name.clone(), lambda.clone());
+        self.environment.define(name.clone(), lambda.clone());
 
         // Return the function name as a symbol
-        Ok(Expr::Symbol(SymbolData::Interned(name)))
+        Ok(Expr::Symbol(SymbolData::Interned(Symbol::intern(&name))))
     }
 
     fn eval_if(&mut self, list: &[Expr]) -> EvalResult {
@@ -257,23 +786,14 @@ impl Evaluator {
             return Err(EvalError::message("lambda requires exactly 2 arguments"));
         }
 
-        // Validate parameters - they must be symbols and not keywords
+        // Parameters must be non-keyword symbols, with at most a trailing &rest one
         if let Expr::List(params) = &list[1] {
-            for param in params {
-                match param {
-                    Expr::Symbol(sym_data) => {
-                        if sym_data.is_keyword() {
-                            return Err(EvalError::message("Lambda parameter cannot be a keyword"));
-                        }
-                    }
-                    _ => return Err(EvalError::message("Lambda parameters must be symbols")),
-                }
-            }
+            Self::split_rest_param(params)?;
         } else {
             return Err(EvalError::message("Lambda parameters must be a list"));
         }
 
-        Ok(Expr::List(list.to_vec()))
+        Ok(Expr::new_list(list.to_vec()))
     }
 
     fn eval_let(&mut self, list: &[Expr]) -> EvalResult {
@@ -286,48 +806,44 @@ impl Evaluator {
             _ => return Err(EvalError::message("let bindings must be a list")),
         };
 
-        self.environment.push_scope();
-
-        // Process all bindings in parallel (standard let behavior)
-        let mut binding_values = Vec::new();
-        for binding in bindings {
-            match binding {
-                Expr::List(pair) if pair.len() == 2 => {
-                    if let Expr::Symbol(_) = &pair[0] {
-                        let value = self.eval(&pair[1])?;
-                        binding_values.push((pair[0].clone(), value));
-                    } else {
-                        self.environment.pop_scope();
-                        return Err(EvalError::message("let binding must start with a symbol"));
+        self.environment.push_scope(ScopeKind::Let);
+        let result = (|| -> EvalResult {
+            // Process all bindings in parallel (standard let behavior)
+            let mut binding_values = Vec::new();
+            for binding in bindings.iter() {
+                match binding {
+                    Expr::List(pair) if pair.len() == 2 => {
+                        if let Expr::Symbol(_) = &pair[0] {
+                            let value = self.eval(&pair[1])?;
+                            binding_values.push((pair[0].clone(), value));
+                        } else {
+                            return Err(EvalError::message("let binding must start with a symbol"));
+                        }
+                    }
+                    _ => {
+                        return Err(EvalError::message(
+                            "let binding must be a list of two elements",
+                        ))
                     }
-                }
-                _ => {
-                    self.environment.pop_scope();
-                    return Err(EvalError::message(
-                        "let binding must be a list of two elements",
-                    ));
                 }
             }
-        }
 
-        // Now set all the bindings
-        for (symbol, value) in binding_values {
-            if let Expr::Symbol(sym_data) = symbol {
-                if !sym_data.is_keyword() {
-                    self.environment.set(sym_data.name().to_string(), value);
+            // Now set all the bindings
+            for (symbol, value) in binding_values {
+                if let Expr::Symbol(sym_data) = symbol {
+                    if !sym_data.is_keyword() {
+                        self.environment.set_symbol(sym_data.symbol(), value);
+                    }
                 }
             }
-        }
 
-        // Evaluate body expressions
-        let mut result = Ok(Expr::List(vec![]));
-        for body_expr in &list[2..] {
-            result = self.eval(body_expr);
-            if result.is_err() {
-                break;
+            // Evaluate body expressions
+            let mut last = Expr::new_list(vec![]);
+            for body_expr in &list[2..] {
+                last = self.eval(body_expr)?;
             }
-        }
-
+            Ok(last)
+        })();
         self.environment.pop_scope();
         result
     }
@@ -342,42 +858,39 @@ impl Evaluator {
             _ => return Err(EvalError::message("let* bindings must be a list")),
         };
 
-        self.environment.push_scope();
-
-        // Process bindings sequentially (let* behavior)
-        for binding in bindings {
-            match binding {
-                Expr::List(pair) if pair.len() == 2 => {
-                    if let Expr::Symbol(sym_data) = &pair[0] {
-                        if sym_data.is_keyword() {
-                            self.environment.pop_scope();
-                            return Err(EvalError::message("Cannot bind to a keyword"));
+        self.environment.push_scope(ScopeKind::Let);
+        let result = (|| -> EvalResult {
+            // Process bindings sequentially (let* behavior)
+            for binding in bindings.iter() {
+                match binding {
+                    Expr::List(pair) if pair.len() == 2 => {
+                        if let Expr::Symbol(sym_data) = &pair[0] {
+                            if sym_data.is_keyword() {
+                                return Err(EvalError::message("Cannot bind to a keyword"));
+                            }
+                            let value = self.eval(&pair[1])?;
+                            self.environment.set_symbol(sym_data.symbol(), value);
+                        } else {
+                            return Err(EvalError::message(
+                                "let* binding must start with a symbol",
+                            ));
                         }
-                        let value = self.eval(&pair[1])?;
-                        self.environment.set(sym_data.name().to_string(), value);
-                    } else {
-                        self.environment.pop_scope();
-                        return Err(EvalError::message("let* binding must start with a symbol"));
                     }
-                }
-                _ => {
-                    self.environment.pop_scope();
-                    return Err(EvalError::message(
-                        "let* binding must be a list of two elements",
-                    ));
+                    _ => {
+                        return Err(EvalError::message(
+                            "let* binding must be a list of two elements",
+                        ))
+                    }
                 }
             }
-        }
 
-        // Evaluate body expressions
-        let mut result = Ok(Expr::List(vec![]));
-        for body_expr in &list[2..] {
-            result = self.eval(body_expr);
-            if result.is_err() {
-                break;
+            // Evaluate body expressions
+            let mut last = Expr::new_list(vec![]);
+            for body_expr in &list[2..] {
+                last = self.eval(body_expr)?;
             }
-        }
-
+            Ok(last)
+        })();
         self.environment.pop_scope();
         result
     }
@@ -392,10 +905,10 @@ impl Evaluator {
             _ => return Err(EvalError::message("letrec bindings must be a list")),
         };
 
-        self.environment.push_scope();
+        self.environment.push_scope(ScopeKind::Let);
         let result = (|| -> EvalResult {
             // Pre-bind all variables to nil so they are visible during initialization
-            for binding in bindings {
+            for binding in bindings.iter() {
                 match binding {
                     Expr::List(pair) if !pair.is_empty() => {
                         if let Expr::Symbol(sym_data) = &pair[0] {
@@ -403,7 +916,7 @@ impl Evaluator {
                                 return Err(EvalError::message("Cannot bind to a keyword"));
                             }
                             self.environment
-                                .set(sym_data.name().to_string(), Expr::List(vec![]));
+                                .set_symbol(sym_data.symbol(), Expr::new_list(vec![]));
                         } else {
                             return Err(EvalError::message(
                                 "letrec binding must start with a symbol",
@@ -419,7 +932,7 @@ impl Evaluator {
             }
 
             // Evaluate initial values with access to all bindings
-            for binding in bindings {
+            for binding in bindings.iter() {
                 match binding {
                     Expr::List(pair) if pair.len() >= 2 => {
                         if let Expr::Symbol(sym_data) = &pair[0] {
@@ -427,7 +940,7 @@ impl Evaluator {
                                 return Err(EvalError::message("Cannot bind to a keyword"));
                             }
                             let value = self.eval(&pair[1])?;
-                            self.environment.set(sym_data.name().to_string(), value);
+                            self.environment.set_symbol(sym_data.symbol(), value);
                         } else {
                             return Err(EvalError::message(
                                 "letrec binding must start with a symbol",
@@ -443,7 +956,7 @@ impl Evaluator {
             }
 
             // Evaluate body expressions
-            let mut last = Expr::List(vec![]);
+            let mut last = Expr::new_list(vec![]);
             for body_expr in &list[2..] {
                 last = self.eval(body_expr)?;
             }
@@ -455,10 +968,10 @@ impl Evaluator {
 
     fn eval_begin(&mut self, list: &[Expr]) -> EvalResult {
         if list.len() == 1 {
-            return Ok(Expr::List(vec![]));
+            return Ok(Expr::new_list(vec![]));
         }
 
-        let mut result = Expr::List(vec![]);
+        let mut result = Expr::new_list(vec![]);
         for expr in &list[1..] {
             result = self.eval(expr)?;
         }
@@ -1,8 +1,18 @@
+use crate::interpreter::bigint::BigInt;
 use crate::interpreter::evaluator::Evaluator;
-use crate::interpreter::types::{EvalError, EvalResult, Expr, HashKey, SymbolData};
+use crate::interpreter::keyword_args::parse_keyword_args;
+use crate::interpreter::ordered_map::OrderedMap;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::types::{EvalError, EvalResult, Expr, SymbolData};
 use std::char;
-use std::collections::HashMap;
-use std::rc::Rc;
+use std::cmp::Ordering;
+
+// The longest vector make-vector will allocate; a larger request fails with an error
+// rather than aborting the process when the allocation can't be satisfied
+pub const MAX_VECTOR_LENGTH: usize = 1 << 24;
+
+// The largest exact result expt will compute, in bits; bigger powers would take minutes
+pub const MAX_EXPT_BITS: u64 = 1 << 20;
 
 impl Evaluator {
     pub fn apply_builtin(&mut self, name: &str, args: &[Expr]) -> EvalResult {
@@ -12,6 +22,7 @@ impl Evaluator {
             "-" => self.builtin_subtract(args),
             "*" => self.builtin_multiply(args),
             "/" => self.builtin_divide(args),
+            "expt" => self.builtin_expt(args),
 
             // Comparison operations
             "=" => self.builtin_equal(args),
@@ -21,8 +32,13 @@ impl Evaluator {
             ">" => self.builtin_greater(args),
             ">=" => self.builtin_greater_equal(args),
 
+            // Equality predicates
+            "eq?" => self.builtin_equality(args, "eq?", |_, a, b| Ok(Self::expr_eq(a, b))),
+            "eqv?" => self.builtin_equality(args, "eqv?", |_, a, b| Ok(Self::expr_eqv(a, b))),
+            "equal?" => self.builtin_equality(args, "equal?", |eval, a, b| eval.expr_equal(a, b)),
+
             // List operations
-            "list" => Ok(Expr::List(args.to_vec())),
+            "list" => Ok(Expr::new_list(args.to_vec())),
             "car" => self.builtin_car(args),
             "cdr" => self.builtin_cdr(args),
             "cons" => self.builtin_cons(args),
@@ -33,19 +49,62 @@ impl Evaluator {
             "nthcdr" => self.builtin_nthcdr(args),
             "member" => self.builtin_member(args),
 
+            // Association and property lists
+            "assoc" => self.builtin_assoc(args),
+            "assq" => self.builtin_assq(args),
+            "rassoc" => self.builtin_rassoc(args),
+            "assoc-if" => self.builtin_assoc_if(args),
+            "getf" => self.builtin_getf(args),
+            "plist-get" => self.builtin_plist_get(args),
+            "plist-put" => self.builtin_plist_put(args),
+            "alist->hash" => self.builtin_alist_to_hash(args),
+            "hash->alist" => self.builtin_hash_to_alist(args),
+
             // Higher-order functions
             "mapcar" => self.builtin_mapcar(args),
-            "filter" => self.builtin_filter(args),
-            "remove" => self.builtin_remove(args),
+            "filter" | "remove-if-not" => self.builtin_filter(args, name, true),
+            "remove" | "remove-if" => self.builtin_filter(args, name, false),
+            "member-if" => self.builtin_member_if(args),
+            "find-if" => self.builtin_find_if(args),
             "reduce" => self.builtin_reduce(args),
+            "foldl" => self.builtin_foldl(args),
+            "foldr" => self.builtin_foldr(args),
+            "map-indexed" => self.builtin_map_indexed(args),
+            "zip" => self.builtin_zip(args),
+            "unzip" => self.builtin_unzip(args),
+            "iota" => self.builtin_iota(args),
+            "take" => self.builtin_take(args),
+            "drop" => self.builtin_drop(args),
+            "partition" => self.builtin_partition(args),
+            "flatten" => self.builtin_flatten(args),
+            "last" => self.builtin_last(args),
+            "transduce" => self.builtin_transduce(args),
+            "map-filter-reduce" => self.builtin_map_filter_reduce(args),
+            "sort" | "stable-sort" => self.builtin_sort(args),
+            "sort-by" => self.builtin_sort_by(args),
 
             // Function application
             "apply" => self.builtin_apply(args),
             "funcall" => self.builtin_funcall(args),
+            "identity" => self.builtin_identity(args),
+            "partial" => self.builtin_partial(args),
+            "compose" => self.builtin_compose(args),
+            "constantly" => self.builtin_constantly(args),
+            "dynamic-wind" => self.builtin_dynamic_wind(args),
+            "invoke-restart" => self.builtin_invoke_restart(args),
+            "compute-restarts" => self.builtin_compute_restarts(args),
 
             // I/O
             "print" => self.builtin_print(args),
             "println" => self.builtin_println(args),
+            "eprint" => self.builtin_eprint(args),
+            "eprintln" => self.builtin_eprintln(args),
+            "read-line" => self.builtin_read_line(args),
+            "write-line" => self.builtin_write_line(args),
+            "read-char" => self.builtin_read_char(args, true),
+            "peek-char" => self.builtin_read_char(args, false),
+            "format" => self.builtin_format(args),
+            "pprint" => self.builtin_pprint(args),
 
             // Symbol operations
             "gensym" => self.builtin_gensym(args),
@@ -53,12 +112,73 @@ impl Evaluator {
             "put" => self.builtin_put(args),
             "symbol-plist" => self.builtin_symbol_plist(args),
 
+            // Session operations
+            "export-session" | "session-script" | "start-recording" | "stop-recording"
+            | "recorded-forms" | "global-history" | "rewind" => {
+                self.apply_session_command(name, args)
+            }
+            "begin-transaction" => self.builtin_begin_transaction(args),
+            "commit-transaction" => self.builtin_commit_transaction(args),
+            "rollback-transaction" => self.builtin_rollback_transaction(args),
+
             // Vector operations
-            "vector" => Ok(Expr::Vector(args.to_vec())),
+            "vector" => Ok(Expr::new_vector(args.to_vec())),
             "make-vector" => self.builtin_make_vector(args),
             "vector-ref" => self.builtin_vector_ref(args),
             "vector-set!" => self.builtin_vector_set(args),
             "vector-length" => self.builtin_vector_length(args),
+            "vector-map" => self.builtin_vector_map(args),
+            "vector-fill!" => self.builtin_vector_fill(args),
+            "subvector" => self.builtin_subvector(args),
+            "vector->list" => self.builtin_vector_to_list(args),
+            "list->vector" => self.builtin_list_to_vector(args),
+            "vector-push" => self.builtin_vector_push(args),
+            "vector-pop" => self.builtin_vector_pop(args),
+            "vector-sort!" => self.builtin_vector_sort(args),
+            "vector-add" => self.builtin_vector_add(args),
+            "vector-scale" => self.builtin_vector_scale(args),
+            "vector-dot" => self.builtin_vector_dot(args),
+            "vector-sum" => self.builtin_vector_sum(args),
+            "make-matrix" => self.builtin_make_matrix(args),
+            "list->matrix" => self.builtin_list_to_matrix(args),
+            "matrix->list" => self.builtin_matrix_to_list(args),
+            "matrix-rows" => self.builtin_matrix_rows(args),
+            "matrix-cols" => self.builtin_matrix_cols(args),
+            "matrix-ref" => self.builtin_matrix_ref(args),
+            "matrix-set!" => self.builtin_matrix_set(args),
+            "matrix-multiply" => self.builtin_matrix_multiply(args),
+            "matrix-transpose" => self.builtin_matrix_transpose(args),
+            "make-queue" => self.builtin_make_queue(args),
+            "enqueue" => self.builtin_enqueue(args),
+            "dequeue" => self.builtin_dequeue(args),
+            "queue-front" => self.builtin_queue_front(args),
+            "make-stack" => self.builtin_make_stack(args),
+            "push!" => self.builtin_push(args),
+            "pop!" => self.builtin_pop(args),
+            "stack-top" => self.builtin_stack_top(args),
+            "make-heap" => self.builtin_make_heap(args),
+            "heap-push!" => self.builtin_heap_push(args),
+            "heap-pop!" => self.builtin_heap_pop(args),
+            "heap-peek" => self.builtin_heap_peek(args),
+            "iterate" => self.builtin_iterate(args),
+            "next" => self.builtin_next(args),
+            "iter-done?" => self.builtin_iter_done(args),
+            "iter->list" => self.builtin_iter_to_list(args),
+            "range" => self.builtin_range(args),
+            "make-struct" => self.builtin_make_struct(args),
+            "struct-ref" => self.builtin_struct_ref(args),
+            "struct-set!" => self.builtin_struct_set(args),
+            "struct-type" => self.builtin_struct_type(args),
+            "struct-fields" => self.builtin_struct_fields(args),
+            "call-generic" => self.builtin_call_generic(args),
+            "call-next-method" => self.builtin_call_next_method(args),
+            "next-method-p" => self.builtin_next_method_p(args),
+            "type-of" => self.builtin_type_of(args),
+            "mean" => self.builtin_mean(args),
+            "median" => self.builtin_median(args),
+            "variance" => self.builtin_variance(args),
+            "stddev" => self.builtin_stddev(args),
+            "percentile" => self.builtin_percentile(args),
 
             // Hash table operations
             "make-hash-table" => self.builtin_make_hash_table(args),
@@ -66,13 +186,47 @@ impl Evaluator {
             "hash-ref" => self.builtin_hash_ref(args),
             "hash-remove!" => self.builtin_hash_remove(args),
             "hash-keys" => self.builtin_hash_keys(args),
+            "hash-values" => self.builtin_hash_values(args),
+            "hash-count" => self.builtin_hash_count(args),
+            "hash-map" => self.builtin_hash_map(args, "hash-map"),
+            "hash-for-each" => self.builtin_hash_map(args, "hash-for-each"),
+            "hash-update!" => self.builtin_hash_update(args),
 
             // Character operations
-            "char=" => self.builtin_char_equal(args),
-            "char<" => self.builtin_char_less(args),
-            "char>" => self.builtin_char_greater(args),
+            "char=" | "char<" | "char>" | "char<=" | "char>=" => {
+                Self::compare_chain(args, name, "character", Self::char_arg)
+            }
+            "string=" | "string<" | "string>" | "string<=" | "string>=" => {
+                Self::compare_chain(args, name, "string", Self::string_arg)
+            }
+            "string-ci=" => Self::compare_chain(args, name, "string", |arg| {
+                Self::string_arg(arg).map(|text| text.to_lowercase())
+            }),
+            "string-split" => self.builtin_string_split(args),
+            "regex-match?" => self.builtin_regex_match(args),
+            "regex-find" => self.builtin_regex_find(args),
+            "regex-find-all" => self.builtin_regex_find_all(args),
+            "regex-replace" => self.builtin_regex_replace(args),
+            "getenv" => self.builtin_getenv(args),
+            "setenv" => self.builtin_setenv(args),
+            "command-line" => self.builtin_command_line(args),
+            "exit" => self.builtin_exit(args),
+            "run-process" => self.builtin_run_process(args),
+            "require" => self.builtin_require(args),
+            "tcp-connect" => self.builtin_tcp_connect(args),
+            "tcp-send" => self.builtin_tcp_send(args),
+            "tcp-receive" => self.builtin_tcp_receive(args),
+            "tcp-listen" => self.builtin_tcp_listen(args),
+            "tcp-accept" => self.builtin_tcp_accept(args),
+            "tcp-port" => self.builtin_tcp_port(args),
+            "tcp-close" => self.builtin_tcp_close(args),
+            "http-get" => self.builtin_http_get(args),
+            "http-post" => self.builtin_http_post(args),
             "char->integer" => self.builtin_char_to_integer(args),
             "integer->char" => self.builtin_integer_to_char(args),
+            "alpha?" | "digit?" | "whitespace?" => self.builtin_char_class(args, name),
+            "string->number" => self.builtin_string_to_number(args),
+            "number->string" => self.builtin_number_to_string(args),
 
             // Type predicates
             "integerp" => self.builtin_integerp(args),
@@ -82,30 +236,31 @@ impl Evaluator {
             "characterp" => self.builtin_characterp(args),
             "vectorp" => self.builtin_vectorp(args),
             "hash-table-p" => self.builtin_hash_table_p(args),
+            "matrixp" => self.builtin_matrixp(args),
+            "queuep" => self.builtin_queuep(args),
+            "stackp" => self.builtin_stackp(args),
+            "heapp" => self.builtin_heapp(args),
+            "iteratorp" => self.builtin_iteratorp(args),
+            "structp" => self.builtin_structp(args),
 
             _ => Err(EvalError::message(format!("Unknown function: {}", name))),
         }
     }
 
     fn builtin_add(&mut self, args: &[Expr]) -> EvalResult {
-        let mut sum = 0.0;
-        let mut all_integers = true;
+        if let Some(result) =
+            Self::fold_exact_integers(args, Expr::Integer(0), i64::checked_add, |a, b| a + b)
+        {
+            return Ok(result);
+        }
 
+        let mut sum = 0.0;
         for arg in args {
-            let n = Self::to_number(arg).map_err(|_| {
+            sum += Self::to_number(arg).map_err(|_| {
                 EvalError::message(format!("+ requires numeric arguments, got {:?}", arg))
             })?;
-            sum += n;
-            if !matches!(arg, Expr::Integer(_)) {
-                all_integers = false;
-            }
-        }
-
-        if all_integers && sum.fract() == 0.0 {
-            Ok(Expr::Integer(sum as i64))
-        } else {
-            Ok(Expr::Float(sum))
         }
+        Ok(Expr::Float(sum))
     }
 
     fn builtin_subtract(&mut self, args: &[Expr]) -> EvalResult {
@@ -119,52 +274,121 @@ impl Evaluator {
 
         if args.len() == 1 {
             return match &args[0] {
-                Expr::Integer(n) => Ok(Expr::Integer(-n)),
+                Expr::Integer(n) => Ok(n
+                    .checked_neg()
+                    .map(Expr::Integer)
+                    .unwrap_or_else(|| Expr::from_bigint(-&BigInt::from(*n)))),
+                Expr::BigInt(n) => Ok(Expr::from_bigint(-n)),
                 _ => Ok(Expr::Float(-first)),
             };
         }
 
-        let mut result = first;
-        let mut all_integers = matches!(&args[0], Expr::Integer(_));
+        if let Some(result) =
+            Self::fold_exact_integers(&args[1..], args[0].clone(), i64::checked_sub, |a, b| a - b)
+        {
+            return Ok(result);
+        }
 
+        let mut result = first;
         for arg in &args[1..] {
-            let n = Self::to_number(arg).map_err(|_| {
+            result -= Self::to_number(arg).map_err(|_| {
                 EvalError::message(format!("- requires numeric arguments, got {:?}", arg))
             })?;
-            result -= n;
-            if !matches!(arg, Expr::Integer(_)) {
-                all_integers = false;
-            }
-        }
-
-        if all_integers && result.fract() == 0.0 {
-            Ok(Expr::Integer(result as i64))
-        } else {
-            Ok(Expr::Float(result))
         }
+        Ok(Expr::Float(result))
     }
 
     fn builtin_multiply(&mut self, args: &[Expr]) -> EvalResult {
-        let mut product = 1.0;
-        let mut all_integers = true;
+        if let Some(result) =
+            Self::fold_exact_integers(args, Expr::Integer(1), i64::checked_mul, |a, b| a * b)
+        {
+            return Ok(result);
+        }
 
+        let mut product = 1.0;
         for arg in args {
-            let n = Self::to_number(arg).map_err(|_| {
+            product *= Self::to_number(arg).map_err(|_| {
                 EvalError::message(format!("* requires numeric arguments, got {:?}", arg))
             })?;
-            product *= n;
-            if !matches!(arg, Expr::Integer(_)) {
-                all_integers = false;
-            }
         }
+        Ok(Expr::Float(product))
+    }
 
-        if all_integers && product.fract() == 0.0 {
-            Ok(Expr::Integer(product as i64))
-        } else {
-            Ok(Expr::Float(product))
+    // Exact integer arithmetic for + - *: stays on i64 until an operation overflows,
+    // then continues as a BigInt. Returns None when any operand is not an integer.
+    fn fold_exact_integers(
+        args: &[Expr],
+        initial: Expr,
+        small: fn(i64, i64) -> Option<i64>,
+        big: fn(&BigInt, &BigInt) -> BigInt,
+    ) -> Option<Expr> {
+        let mut acc = initial;
+        for arg in args {
+            acc = match (&acc, arg) {
+                (Expr::Integer(a), Expr::Integer(b)) => match small(*a, *b) {
+                    Some(n) => Expr::Integer(n),
+                    None => Expr::from_bigint(big(&BigInt::from(*a), &BigInt::from(*b))),
+                },
+                (Expr::Integer(_) | Expr::BigInt(_), Expr::Integer(_) | Expr::BigInt(_)) => {
+                    Expr::from_bigint(big(&acc.to_bigint()?, &arg.to_bigint()?))
+                }
+                _ => return None,
+            };
+        }
+        match acc {
+            Expr::Integer(_) | Expr::BigInt(_) => Some(acc),
+            _ => None,
         }
     }
 
+    fn builtin_expt(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 2 {
+            return Err(EvalError::message("expt requires exactly 2 arguments"));
+        }
+
+        if let (Some(base), Expr::Integer(power)) = (args[0].to_bigint(), &args[1]) {
+            if *power >= 0 {
+                if let Expr::Integer(b) = &args[0] {
+                    if let Some(n) = u32::try_from(*power).ok().and_then(|p| b.checked_pow(p)) {
+                        return Ok(Expr::Integer(n));
+                    }
+                }
+                // 0, 1 and -1 stay small however large the power
+                if let Some(b @ -1..=1) = base.to_i64() {
+                    return Ok(Expr::Integer(match b {
+                        _ if *power == 0 => 1,
+                        -1 if power % 2 == 0 => 1,
+                        _ => b,
+                    }));
+                }
+                let bits = base.bits().saturating_mul(*power as u64);
+                if bits > MAX_EXPT_BITS {
+                    return Err(EvalError::message(format!(
+                        "expt: the result would have about {} bits, over the limit of {}",
+                        bits, MAX_EXPT_BITS
+                    )));
+                }
+                let power = u32::try_from(*power)
+                    .map_err(|_| EvalError::message("expt exponent is too large"))?;
+                return Ok(Expr::from_bigint(base.pow(power)));
+            }
+        }
+
+        let base = Self::to_number(&args[0]).map_err(|_| {
+            EvalError::message(format!(
+                "expt requires numeric arguments, got {:?}",
+                args[0]
+            ))
+        })?;
+        let power = Self::to_number(&args[1]).map_err(|_| {
+            EvalError::message(format!(
+                "expt requires numeric arguments, got {:?}",
+                args[1]
+            ))
+        })?;
+        Ok(Expr::Float(base.powf(power)))
+    }
+
     fn builtin_divide(&mut self, args: &[Expr]) -> EvalResult {
         if args.is_empty() {
             return Err(EvalError::message("/ requires at least 1 argument"));
@@ -196,6 +420,10 @@ impl Evaluator {
     }
 
     fn builtin_equal(&mut self, args: &[Expr]) -> EvalResult {
+        if let Some(result) = Self::compare_big_integers(args, |o| o == Ordering::Equal) {
+            return Ok(result);
+        }
+
         if args.len() < 2 {
             return Err(EvalError::message("= requires at least 2 arguments"));
         }
@@ -203,7 +431,7 @@ impl Evaluator {
         let all_numeric = args.iter().all(|arg| {
             matches!(
                 arg,
-                Expr::Integer(_) | Expr::Float(_) | Expr::Rational { .. }
+                Expr::Integer(_) | Expr::BigInt(_) | Expr::Float(_) | Expr::Rational { .. }
             )
         });
 
@@ -241,6 +469,10 @@ impl Evaluator {
     }
 
     fn builtin_less(&mut self, args: &[Expr]) -> EvalResult {
+        if let Some(result) = Self::compare_big_integers(args, |o| o == Ordering::Less) {
+            return Ok(result);
+        }
+
         let numbers = Self::collect_numeric_args(args, "<", 2)?;
 
         for window in numbers.windows(2) {
@@ -253,6 +485,10 @@ impl Evaluator {
     }
 
     fn builtin_less_equal(&mut self, args: &[Expr]) -> EvalResult {
+        if let Some(result) = Self::compare_big_integers(args, |o| o != Ordering::Greater) {
+            return Ok(result);
+        }
+
         let numbers = Self::collect_numeric_args(args, "<=", 2)?;
 
         for window in numbers.windows(2) {
@@ -265,6 +501,10 @@ impl Evaluator {
     }
 
     fn builtin_greater(&mut self, args: &[Expr]) -> EvalResult {
+        if let Some(result) = Self::compare_big_integers(args, |o| o == Ordering::Greater) {
+            return Ok(result);
+        }
+
         let numbers = Self::collect_numeric_args(args, ">", 2)?;
 
         for window in numbers.windows(2) {
@@ -277,6 +517,10 @@ impl Evaluator {
     }
 
     fn builtin_greater_equal(&mut self, args: &[Expr]) -> EvalResult {
+        if let Some(result) = Self::compare_big_integers(args, |o| o != Ordering::Less) {
+            return Ok(result);
+        }
+
         let numbers = Self::collect_numeric_args(args, ">=", 2)?;
 
         for window in numbers.windows(2) {
@@ -288,6 +532,34 @@ impl Evaluator {
         Ok(Evaluator::bool_to_expr(true))
     }
 
+    fn builtin_equality(
+        &mut self,
+        args: &[Expr],
+        name: &str,
+        test: fn(&Evaluator, &Expr, &Expr) -> Result<bool, EvalError>,
+    ) -> EvalResult {
+        if args.len() != 2 {
+            return Err(EvalError::message(format!(
+                "{} requires exactly 2 arguments",
+                name
+            )));
+        }
+        Ok(Evaluator::bool_to_expr(test(self, &args[0], &args[1])?))
+    }
+
+    // f64 comparisons lose precision past 2^53, so chains involving a BigInt compare exactly
+    fn compare_big_integers(args: &[Expr], accept: fn(Ordering) -> bool) -> Option<Expr> {
+        if args.len() < 2 || !args.iter().any(|arg| matches!(arg, Expr::BigInt(_))) {
+            return None;
+        }
+        let values = args
+            .iter()
+            .map(Expr::to_bigint)
+            .collect::<Option<Vec<BigInt>>>()?;
+        let result = values.windows(2).all(|pair| accept(pair[0].cmp(&pair[1])));
+        Some(Evaluator::bool_to_expr(result))
+    }
+
     fn collect_numeric_args(
         args: &[Expr],
         name: &str,
@@ -324,6 +596,12 @@ impl Evaluator {
                 ))
             }
         };
+        if size > MAX_VECTOR_LENGTH {
+            return Err(EvalError::message(format!(
+                "make-vector: size {} is over the limit of {}",
+                size, MAX_VECTOR_LENGTH
+            )));
+        }
 
         let init = if args.len() == 2 {
             args[1].clone()
@@ -331,7 +609,7 @@ impl Evaluator {
             Expr::Integer(0)
         };
 
-        Ok(Expr::Vector(vec![init; size]))
+        Ok(Expr::new_vector(vec![init; size]))
     }
 
     fn builtin_vector_ref(&mut self, args: &[Expr]) -> EvalResult {
@@ -359,7 +637,8 @@ impl Evaluator {
             }
         };
 
-        vec.get(index)
+        vec.borrow()
+            .get(index)
             .cloned()
             .ok_or_else(|| EvalError::message(format!("vector-ref: index {} out of bounds", index)))
     }
@@ -371,8 +650,8 @@ impl Evaluator {
             ));
         }
 
-        let mut vec = match &args[0] {
-            Expr::Vector(v) => v.clone(),
+        let vec = match &args[0] {
+            Expr::Vector(v) => v,
             _ => {
                 return Err(EvalError::message(
                     "vector-set! requires a vector as first argument",
@@ -389,15 +668,16 @@ impl Evaluator {
             }
         };
 
-        if index >= vec.len() {
+        let mut items = vec.borrow_mut();
+        if index >= items.len() {
             return Err(EvalError::message(format!(
                 "vector-set!: index {} out of bounds",
                 index
             )));
         }
 
-        vec[index] = args[2].clone();
-        Ok(Expr::Vector(vec))
+        items[index] = args[2].clone();
+        Ok(args[0].clone())
     }
 
     fn builtin_vector_length(&mut self, args: &[Expr]) -> EvalResult {
@@ -408,14 +688,151 @@ impl Evaluator {
         }
 
         match &args[0] {
-            Expr::Vector(v) => Ok(Expr::Integer(v.len() as i64)),
+            Expr::Vector(v) => Ok(Expr::Integer(v.borrow().len() as i64)),
             _ => Err(EvalError::message("vector-length requires a vector")),
         }
     }
 
-    // Hash table operations
-    fn builtin_make_hash_table(&mut self, _args: &[Expr]) -> EvalResult {
-        Ok(Expr::HashTable(Rc::new(HashMap::new())))
+    fn builtin_vector_fill(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 2 {
+            return Err(EvalError::message(
+                "vector-fill! requires exactly 2 arguments",
+            ));
+        }
+
+        match &args[0] {
+            Expr::Vector(v) => {
+                v.borrow_mut().fill(args[1].clone());
+                Ok(args[0].clone())
+            }
+            _ => Err(EvalError::message(
+                "vector-fill! requires a vector as first argument",
+            )),
+        }
+    }
+
+    // (subvector vec start [end]) copies the elements from start up to, not including, end
+    fn builtin_subvector(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 2 || args.len() > 3 {
+            return Err(EvalError::message("subvector requires 2 or 3 arguments"));
+        }
+
+        let vec = match &args[0] {
+            Expr::Vector(v) => v.borrow(),
+            _ => {
+                return Err(EvalError::message(
+                    "subvector requires a vector as first argument",
+                ))
+            }
+        };
+
+        let mut bounds = Vec::new();
+        for arg in &args[1..] {
+            match arg {
+                Expr::Integer(n) if *n >= 0 => bounds.push(*n as usize),
+                _ => {
+                    return Err(EvalError::message(
+                        "subvector requires non-negative integer indices",
+                    ))
+                }
+            }
+        }
+        let start = bounds[0];
+        let end = bounds.get(1).copied().unwrap_or(vec.len());
+
+        if start > end || end > vec.len() {
+            return Err(EvalError::message(format!(
+                "subvector: range {}..{} out of bounds for length {}",
+                start,
+                end,
+                vec.len()
+            )));
+        }
+
+        Ok(Expr::new_vector(vec[start..end].to_vec()))
+    }
+
+    fn builtin_vector_to_list(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message(
+                "vector->list requires exactly 1 argument",
+            ));
+        }
+
+        match &args[0] {
+            Expr::Vector(v) => Ok(Expr::new_list(v.borrow().clone())),
+            _ => Err(EvalError::message("vector->list requires a vector")),
+        }
+    }
+
+    fn builtin_list_to_vector(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message(
+                "list->vector requires exactly 1 argument",
+            ));
+        }
+
+        match &args[0] {
+            Expr::List(items) => Ok(Expr::new_vector(items.to_vec())),
+            _ => Err(EvalError::message("list->vector requires a list")),
+        }
+    }
+
+    // (vector-push vec value) appends in place, growing the vector
+    fn builtin_vector_push(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 2 {
+            return Err(EvalError::message(
+                "vector-push requires exactly 2 arguments",
+            ));
+        }
+
+        match &args[0] {
+            Expr::Vector(v) => {
+                v.borrow_mut().push(args[1].clone());
+                Ok(args[0].clone())
+            }
+            _ => Err(EvalError::message(
+                "vector-push requires a vector as first argument",
+            )),
+        }
+    }
+
+    // (vector-pop vec) removes and returns the last element
+    fn builtin_vector_pop(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message("vector-pop requires exactly 1 argument"));
+        }
+
+        match &args[0] {
+            Expr::Vector(v) => v
+                .borrow_mut()
+                .pop()
+                .ok_or_else(|| EvalError::message("vector-pop: vector is empty")),
+            _ => Err(EvalError::message("vector-pop requires a vector")),
+        }
+    }
+
+    // Hash table operations. (make-hash-table [:size n] [:test f]): keys are atoms compared
+    // by value, which eq?, eqv? and equal? all agree on, so :test only accepts those. :size
+    // is a hint for how many entries to make room for.
+    fn builtin_make_hash_table(&mut self, args: &[Expr]) -> EvalResult {
+        let (_, keyword_args) =
+            parse_keyword_args(args, "make-hash-table", 0..=0, &["size", "test"])?;
+        if let Some(test) = keyword_args.get("test") {
+            if !matches!(test, Expr::Symbol(SymbolData::Interned(name))
+                if matches!(name.as_str(), "eq?" | "eqv?" | "equal?"))
+            {
+                return Err(EvalError::message(format!(
+                    "make-hash-table: :test must be eq?, eqv? or equal?, got {}",
+                    write_string(test)
+                )));
+            }
+        }
+        let size = keyword_args
+            .count("size")?
+            .unwrap_or(0)
+            .min(MAX_VECTOR_LENGTH);
+        Ok(Expr::new_hash_table(OrderedMap::with_capacity(size)))
     }
 
     fn builtin_hash_set(&mut self, args: &[Expr]) -> EvalResult {
@@ -423,8 +840,8 @@ impl Evaluator {
             return Err(EvalError::message("hash-set! requires exactly 3 arguments"));
         }
 
-        let mut table = match &args[0] {
-            Expr::HashTable(h) => (**h).clone(),
+        let table = match &args[0] {
+            Expr::HashTable(h) => h,
             _ => {
                 return Err(EvalError::message(
                     "hash-set! requires a hash table as first argument",
@@ -435,8 +852,8 @@ impl Evaluator {
         let key = Self::expr_to_hashkey(&args[1])
             .ok_or_else(|| EvalError::message("hash-set! requires a hashable key"))?;
 
-        table.insert(key, args[2].clone());
-        Ok(Expr::HashTable(Rc::new(table)))
+        table.borrow_mut().insert(key, args[2].clone());
+        Ok(args[0].clone())
     }
 
     fn builtin_hash_ref(&mut self, args: &[Expr]) -> EvalResult {
@@ -457,6 +874,7 @@ impl Evaluator {
             .ok_or_else(|| EvalError::message("hash-ref requires a hashable key"))?;
 
         table
+            .borrow()
             .get(&key)
             .cloned()
             .or_else(|| {
@@ -476,8 +894,8 @@ impl Evaluator {
             ));
         }
 
-        let mut table = match &args[0] {
-            Expr::HashTable(h) => (**h).clone(),
+        let table = match &args[0] {
+            Expr::HashTable(h) => h,
             _ => {
                 return Err(EvalError::message(
                     "hash-remove! requires a hash table as first argument",
@@ -488,8 +906,8 @@ impl Evaluator {
         let key = Self::expr_to_hashkey(&args[1])
             .ok_or_else(|| EvalError::message("hash-remove! requires a hashable key"))?;
 
-        table.remove(&key);
-        Ok(Expr::HashTable(Rc::new(table)))
+        table.borrow_mut().remove(&key);
+        Ok(args[0].clone())
     }
 
     fn builtin_hash_keys(&mut self, args: &[Expr]) -> EvalResult {
@@ -503,50 +921,174 @@ impl Evaluator {
         };
 
         let keys: Vec<Expr> = table
+            .borrow()
             .keys()
-            .map(|k| match k {
-                HashKey::Integer(n) => Expr::Integer(*n),
-                HashKey::Symbol(s) => Expr::Symbol(SymbolData::Interned(s.clone())),
-                HashKey::String(s) => Expr::String(s.clone()),
-                HashKey::Character(c) => Expr::Character(*c),
-                HashKey::Keyword(s) => Expr::Symbol(SymbolData::Keyword(s.clone())),
-            })
+            .map(Evaluator::hashkey_to_expr)
             .collect();
 
-        Ok(Expr::List(keys))
+        Ok(Expr::new_list(keys))
     }
 
-    // Character operations
-    fn builtin_char_equal(&mut self, args: &[Expr]) -> EvalResult {
-        if args.len() != 2 {
-            return Err(EvalError::message("char= requires exactly 2 arguments"));
+    fn builtin_hash_values(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message(
+                "hash-values requires exactly 1 argument",
+            ));
         }
 
-        match (&args[0], &args[1]) {
-            (Expr::Character(a), Expr::Character(b)) => Ok(Evaluator::bool_to_expr(a == b)),
-            _ => Err(EvalError::message("char= requires character arguments")),
+        match &args[0] {
+            Expr::HashTable(h) => Ok(Expr::new_list(h.borrow().values().cloned().collect())),
+            _ => Err(EvalError::message("hash-values requires a hash table")),
         }
     }
 
-    fn builtin_char_less(&mut self, args: &[Expr]) -> EvalResult {
-        if args.len() != 2 {
-            return Err(EvalError::message("char< requires exactly 2 arguments"));
+    fn builtin_hash_count(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message("hash-count requires exactly 1 argument"));
         }
 
-        match (&args[0], &args[1]) {
-            (Expr::Character(a), Expr::Character(b)) => Ok(Evaluator::bool_to_expr(a < b)),
-            _ => Err(EvalError::message("char< requires character arguments")),
+        match &args[0] {
+            Expr::HashTable(h) => Ok(Expr::Integer(h.borrow().len() as i64)),
+            _ => Err(EvalError::message("hash-count requires a hash table")),
         }
     }
 
-    fn builtin_char_greater(&mut self, args: &[Expr]) -> EvalResult {
+    // (hash-map table f) collects (f key value) for every entry; hash-for-each
+    // calls f the same way for its side effects and returns nil
+    fn builtin_hash_map(&mut self, args: &[Expr], name: &str) -> EvalResult {
         if args.len() != 2 {
-            return Err(EvalError::message("char> requires exactly 2 arguments"));
+            return Err(EvalError::message(format!(
+                "{} requires exactly 2 arguments",
+                name
+            )));
+        }
+
+        let table = match &args[0] {
+            Expr::HashTable(h) => h,
+            _ => {
+                return Err(EvalError::message(format!(
+                    "{} requires a hash table as first argument",
+                    name
+                )))
+            }
+        };
+        let func = &args[1];
+        if !Self::is_callable(func) {
+            return Err(EvalError::message(format!(
+                "{} requires a function as second argument",
+                name
+            )));
         }
 
-        match (&args[0], &args[1]) {
-            (Expr::Character(a), Expr::Character(b)) => Ok(Evaluator::bool_to_expr(a > b)),
-            _ => Err(EvalError::message("char> requires character arguments")),
+        // Snapshot the entries so f may modify the table while we walk it
+        let entries: Vec<(Expr, Expr)> = table
+            .borrow()
+            .iter()
+            .map(|(key, value)| (key.to_expr(), value.clone()))
+            .collect();
+
+        let mut results = Vec::new();
+        for (key, value) in entries {
+            let result = self.call_callable(func, &[key, value])?;
+            if name == "hash-map" {
+                results.push(result);
+            }
+        }
+        Ok(Expr::new_list(results))
+    }
+
+    // (hash-update! table key f [default]) stores (f current) in place, starting from
+    // default when key is missing
+    fn builtin_hash_update(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 3 || args.len() > 4 {
+            return Err(EvalError::message("hash-update! requires 3 or 4 arguments"));
+        }
+
+        let table = match &args[0] {
+            Expr::HashTable(h) => h,
+            _ => {
+                return Err(EvalError::message(
+                    "hash-update! requires a hash table as first argument",
+                ))
+            }
+        };
+        let key = Self::expr_to_hashkey(&args[1])
+            .ok_or_else(|| EvalError::message("hash-update! requires a hashable key"))?;
+        let func = &args[2];
+        if !Self::is_callable(func) {
+            return Err(EvalError::message(
+                "hash-update! requires a function as third argument",
+            ));
+        }
+
+        let current = table.borrow().get(&key).cloned();
+        let current = match (current, args.get(3)) {
+            (Some(value), _) => value,
+            (None, Some(default)) => default.clone(),
+            (None, None) => {
+                return Err(EvalError::message(format!(
+                    "hash-update!: key not found: {:?}",
+                    args[1]
+                )))
+            }
+        };
+
+        let updated = self.call_callable(func, &[current])?;
+        table.borrow_mut().insert(key, updated.clone());
+        Ok(updated)
+    }
+
+    // Character and string comparisons chain like the numeric ones: (char< a b c) is true
+    // when each argument is less than the next. The relation comes from the name's suffix.
+    fn compare_chain<T: Ord>(
+        args: &[Expr],
+        name: &str,
+        kind: &str,
+        value: impl Fn(&Expr) -> Option<T>,
+    ) -> EvalResult {
+        if args.len() < 2 {
+            return Err(EvalError::message(format!(
+                "{} requires at least 2 arguments",
+                name
+            )));
+        }
+        let values = args
+            .iter()
+            .map(|arg| {
+                value(arg).ok_or_else(|| {
+                    EvalError::message(format!("{} requires {} arguments", name, kind))
+                })
+            })
+            .collect::<Result<Vec<T>, _>>()?;
+        let holds = |order: Ordering| {
+            if name.ends_with("<=") {
+                order != Ordering::Greater
+            } else if name.ends_with(">=") {
+                order != Ordering::Less
+            } else if name.ends_with('<') {
+                order == Ordering::Less
+            } else if name.ends_with('>') {
+                order == Ordering::Greater
+            } else {
+                order == Ordering::Equal
+            }
+        };
+        Ok(Evaluator::bool_to_expr(
+            values.windows(2).all(|pair| holds(pair[0].cmp(&pair[1]))),
+        ))
+    }
+
+    fn char_arg(arg: &Expr) -> Option<char> {
+        match arg {
+            Expr::Character(ch) => Some(*ch),
+            _ => None,
+        }
+    }
+
+    fn string_arg(arg: &Expr) -> Option<String> {
+        match arg {
+            Expr::String(text) => Some(text.clone()),
+            _ => None,
         }
     }
 
@@ -586,7 +1128,10 @@ impl Evaluator {
             return Err(EvalError::message("integerp requires exactly 1 argument"));
         }
 
-        Ok(Evaluator::bool_to_expr(matches!(args[0], Expr::Integer(_))))
+        Ok(Evaluator::bool_to_expr(matches!(
+            args[0],
+            Expr::Integer(_) | Expr::BigInt(_)
+        )))
     }
 
     fn builtin_floatp(&mut self, args: &[Expr]) -> EvalResult {
@@ -615,7 +1160,7 @@ impl Evaluator {
 
         let is_number = matches!(
             args[0],
-            Expr::Integer(_) | Expr::Float(_) | Expr::Rational { .. }
+            Expr::Integer(_) | Expr::BigInt(_) | Expr::Float(_) | Expr::Rational { .. }
         );
         Ok(Evaluator::bool_to_expr(is_number))
     }
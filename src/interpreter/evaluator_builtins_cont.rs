@@ -1,5 +1,19 @@
+use crate::interpreter::environment::ScopeKind;
 use crate::interpreter::evaluator::Evaluator;
-use crate::interpreter::types::{EvalError, EvalResult, Expr, SymbolData};
+use crate::interpreter::evaluator_builtins::MAX_VECTOR_LENGTH;
+use crate::interpreter::iterator::SeqIter;
+use crate::interpreter::keyword_args::parse_keyword_args;
+use crate::interpreter::ordered_map::OrderedMap;
+use crate::interpreter::pretty::{pretty_print, DEFAULT_PRETTY_WIDTH};
+use crate::interpreter::printer::{display_string, write_string};
+use crate::interpreter::types::{EvalError, EvalResult, Expr, Symbol, SymbolData};
+use std::rc::Rc;
+
+#[derive(Default)]
+struct SequenceOptions {
+    key: Option<Expr>,
+    test: Option<Expr>,
+}
 
 impl Evaluator {
     // Basic list operations
@@ -10,7 +24,7 @@ impl Evaluator {
 
         match &args[0] {
             Expr::List(list) if !list.is_empty() => Ok(list[0].clone()),
-            Expr::List(_) => Ok(Expr::List(vec![])),
+            Expr::List(_) => Ok(Expr::new_list(vec![])),
             Expr::Cons(car, _) => Ok((**car).clone()),
             _ => Err(EvalError::message("car requires a list or cons cell")),
         }
@@ -22,8 +36,8 @@ impl Evaluator {
         }
 
         match &args[0] {
-            Expr::List(list) if !list.is_empty() => Ok(Expr::List(list[1..].to_vec())),
-            Expr::List(_) => Ok(Expr::List(vec![])),
+            Expr::List(list) if !list.is_empty() => Ok(Expr::new_list(list[1..].to_vec())),
+            Expr::List(_) => Ok(Expr::new_list(vec![])),
             Expr::Cons(_, cdr) => Ok((**cdr).clone()),
             _ => Err(EvalError::message("cdr requires a list or cons cell")),
         }
@@ -38,7 +52,7 @@ impl Evaluator {
             Expr::List(list) => {
                 let mut new_list = vec![args[0].clone()];
                 new_list.extend_from_slice(list);
-                Ok(Expr::List(new_list))
+                Ok(Expr::new_list(new_list))
             }
             other => Ok(Expr::Cons(
                 Box::new(args[0].clone()),
@@ -57,7 +71,7 @@ impl Evaluator {
             }
         }
 
-        Ok(Expr::List(result))
+        Ok(Expr::new_list(result))
     }
 
     pub fn builtin_reverse(&mut self, args: &[Expr]) -> EvalResult {
@@ -68,7 +82,7 @@ impl Evaluator {
         match &args[0] {
             Expr::List(list) => {
                 let mut reversed = list.clone();
-                reversed.reverse();
+                Rc::make_mut(&mut reversed).reverse();
                 Ok(Expr::List(reversed))
             }
             _ => Err(EvalError::message("reverse requires a list")),
@@ -82,10 +96,14 @@ impl Evaluator {
 
         match &args[0] {
             Expr::List(list) => Ok(Expr::Integer(list.len() as i64)),
-            Expr::Vector(vec) => Ok(Expr::Integer(vec.len() as i64)),
+            Expr::Vector(vec) => Ok(Expr::Integer(vec.borrow().len() as i64)),
             Expr::String(s) => Ok(Expr::Integer(s.len() as i64)),
+            Expr::Queue(items) | Expr::Stack(items) => {
+                Ok(Expr::Integer(items.borrow().len() as i64))
+            }
+            Expr::Heap(heap) => Ok(Expr::Integer(heap.borrow().items.len() as i64)),
             _ => Err(EvalError::message(
-                "length requires a list, vector, or string",
+                "length requires a list, vector, string, queue, stack or heap",
             )),
         }
     }
@@ -129,9 +147,9 @@ impl Evaluator {
         match &args[1] {
             Expr::List(list) => {
                 if n >= list.len() {
-                    Ok(Expr::List(vec![]))
+                    Ok(Expr::new_list(vec![]))
                 } else {
-                    Ok(Expr::List(list[n..].to_vec()))
+                    Ok(Expr::new_list(list[n..].to_vec()))
                 }
             }
             _ => Err(EvalError::message(
@@ -140,149 +158,443 @@ impl Evaluator {
         }
     }
 
-    pub fn builtin_member(&mut self, args: &[Expr]) -> EvalResult {
-        if args.len() != 2 {
-            return Err(EvalError::message("member requires exactly 2 arguments"));
+    // Sequence builtins take :key and :test keyword arguments after their `positional`
+    // ones, as (member "A" names :test string-ci=). :key projects each element before it
+    // is compared or tested; :test replaces equal? as the comparison.
+    fn sequence_options<'a>(
+        args: &'a [Expr],
+        name: &'a str,
+        positional: usize,
+        keywords: &[&str],
+    ) -> Result<(&'a [Expr], SequenceOptions), EvalError> {
+        let (args, keyword_args) =
+            parse_keyword_args(args, name, positional..=positional, keywords)?;
+        let options = SequenceOptions {
+            key: keyword_args.function("key")?.cloned(),
+            test: keyword_args.function("test")?.cloned(),
+        };
+        Ok((args, options))
+    }
+
+    // Applies the :key function, if any
+    fn project(&mut self, options: &SequenceOptions, item: &Expr) -> EvalResult {
+        match &options.key {
+            Some(key) => self.call_callable(key, std::slice::from_ref(item)),
+            None => Ok(item.clone()),
+        }
+    }
+
+    // Whether `candidate` matches `item` under the :key and :test options, with `same` as
+    // the test when none is given
+    fn matches_item(
+        &mut self,
+        options: &SequenceOptions,
+        item: &Expr,
+        candidate: &Expr,
+        same: fn(&Self, &Expr, &Expr) -> Result<bool, EvalError>,
+    ) -> Result<bool, EvalError> {
+        let candidate = self.project(options, candidate)?;
+        match &options.test {
+            Some(test) => Ok(Evaluator::is_truthy(
+                &self.call_callable(test, &[item.clone(), candidate])?,
+            )),
+            None => same(self, item, &candidate),
+        }
+    }
+
+    fn satisfies(
+        &mut self,
+        options: &SequenceOptions,
+        pred: &Expr,
+        item: &Expr,
+    ) -> Result<bool, EvalError> {
+        let value = self.project(options, item)?;
+        Ok(Evaluator::is_truthy(&self.call_callable(pred, &[value])?))
+    }
+
+    fn predicate_and_seq<'a>(
+        args: &'a [Expr],
+        name: &str,
+    ) -> Result<(&'a Expr, Box<dyn SeqIter>), EvalError> {
+        if !Self::is_callable(&args[0]) {
+            return Err(EvalError::message(format!(
+                "{} requires a predicate function",
+                name
+            )));
         }
+        Ok((&args[0], Self::seq_arg(&args[1], name, "second")?))
+    }
+
+    // The list of `first` and the items after it, for the member functions
+    fn tail_list(first: Expr, seq: &mut dyn SeqIter, name: &str) -> EvalResult {
+        let mut tail = vec![first];
+        Self::drain_seq(seq, &mut tail, name)?;
+        Ok(Expr::new_list(tail))
+    }
 
+    pub fn builtin_member(&mut self, args: &[Expr]) -> EvalResult {
+        let (args, options) = Self::sequence_options(args, "member", 2, &["key", "test"])?;
         let item = &args[0];
-        let list = match &args[1] {
-            Expr::List(l) => l,
-            _ => {
-                return Err(EvalError::message(
-                    "member requires a list as second argument",
-                ))
-            }
-        };
+        let mut seq = Self::seq_arg(&args[1], "member", "second")?;
 
-        for (i, elem) in list.iter().enumerate() {
-            if self.expr_equal(item, elem) {
-                return Ok(Expr::List(list[i..].to_vec()));
+        while let Some(elem) = seq.next_item() {
+            if self.matches_item(&options, item, &elem, Self::expr_equal)? {
+                return Self::tail_list(elem, seq.as_mut(), "member");
             }
         }
 
-        Ok(Expr::List(vec![])) // Not found returns empty list
+        Ok(Expr::new_list(vec![])) // Not found returns empty list
     }
 
-    pub fn builtin_mapcar(&mut self, args: &[Expr]) -> EvalResult {
-        if args.len() < 2 {
-            return Err(EvalError::message("mapcar requires at least 2 arguments"));
+    // (member-if pred list): the tail of list starting at the first element pred accepts
+    pub fn builtin_member_if(&mut self, args: &[Expr]) -> EvalResult {
+        let (args, options) = Self::sequence_options(args, "member-if", 2, &["key"])?;
+        let (pred, mut seq) = Self::predicate_and_seq(args, "member-if")?;
+        while let Some(elem) = seq.next_item() {
+            if self.satisfies(&options, pred, &elem)? {
+                return Self::tail_list(elem, seq.as_mut(), "member-if");
+            }
         }
+        Ok(Expr::new_list(vec![]))
+    }
 
-        let func = &args[0];
-        let mut lists: Vec<&Vec<Expr>> = Vec::new();
-        for arg in &args[1..] {
-            match arg {
-                Expr::List(l) => lists.push(l),
-                _ => return Err(EvalError::message("mapcar requires list arguments")),
+    // (find-if pred list): the first element pred accepts, or nil. Only takes items from
+    // an iterator up to that element.
+    pub fn builtin_find_if(&mut self, args: &[Expr]) -> EvalResult {
+        let (args, options) = Self::sequence_options(args, "find-if", 2, &["key"])?;
+        let (pred, mut seq) = Self::predicate_and_seq(args, "find-if")?;
+        while let Some(elem) = seq.next_item() {
+            if self.satisfies(&options, pred, &elem)? {
+                return Ok(elem);
             }
         }
+        Ok(Expr::new_list(vec![]))
+    }
 
-        if lists.is_empty() {
-            return Ok(Expr::List(vec![]));
+    // Association lists hold (key . value) cons cells or (key value) lists
+    fn alist_entries<'a>(alist: &'a Expr, name: &str) -> Result<&'a [Expr], EvalError> {
+        match alist {
+            Expr::List(items) => Ok(items),
+            _ => Err(EvalError::message(format!(
+                "{} requires an association list",
+                name
+            ))),
         }
+    }
 
-        let min_len = lists.iter().map(|l| l.len()).min().unwrap_or(0);
-        let mut result = Vec::new();
+    fn pair_key(pair: &Expr) -> Option<&Expr> {
+        match pair {
+            Expr::Cons(car, _) => Some(car),
+            Expr::List(items) => items.first(),
+            _ => None,
+        }
+    }
 
-        for i in 0..min_len {
-            let func_args: Vec<Expr> = lists.iter().map(|l| l[i].clone()).collect();
-
-            let val = match func {
-                Expr::Symbol(sym_data) => self.apply_builtin(sym_data.name(), &func_args)?,
-                Expr::List(lambda)
-                    if lambda.len() == 3
-                        && matches!(&lambda[0], Expr::Symbol(sym_data) if sym_data.name() == "lambda") =>
-                {
-                    self.apply_lambda(lambda, &func_args)?
+    // Two-element lists are treated as (key value) so their value is the second element
+    fn pair_value(pair: &Expr) -> Option<Expr> {
+        match pair {
+            Expr::Cons(_, cdr) => Some((**cdr).clone()),
+            Expr::List(items) if items.len() == 2 => Some(items[1].clone()),
+            Expr::List(items) if !items.is_empty() => Some(Expr::new_list(items[1..].to_vec())),
+            _ => None,
+        }
+    }
+
+    fn find_pair(
+        &mut self,
+        args: &[Expr],
+        name: &str,
+        keywords: &[&str],
+        same: fn(&Self, &Expr, &Expr) -> Result<bool, EvalError>,
+        select: fn(&Expr) -> Option<Expr>,
+    ) -> EvalResult {
+        let (args, options) = Self::sequence_options(args, name, 2, keywords)?;
+        for pair in Self::alist_entries(&args[1], name)? {
+            if let Some(candidate) = select(pair) {
+                if self.matches_item(&options, &args[0], &candidate, same)? {
+                    return Ok(pair.clone());
                 }
-                _ => {
-                    return Err(EvalError::message(
-                        "mapcar requires a function as first argument",
-                    ))
+            }
+        }
+        Ok(Expr::new_list(vec![]))
+    }
+
+    pub fn builtin_assoc(&mut self, args: &[Expr]) -> EvalResult {
+        self.find_pair(args, "assoc", &["key", "test"], Self::expr_equal, |pair| {
+            Self::pair_key(pair).cloned()
+        })
+    }
+
+    // Compares keys with eq?, so it takes :key but not :test
+    pub fn builtin_assq(&mut self, args: &[Expr]) -> EvalResult {
+        self.find_pair(
+            args,
+            "assq",
+            &["key"],
+            |_, a, b| Ok(Self::expr_eq(a, b)),
+            |pair| Self::pair_key(pair).cloned(),
+        )
+    }
+
+    pub fn builtin_rassoc(&mut self, args: &[Expr]) -> EvalResult {
+        self.find_pair(
+            args,
+            "rassoc",
+            &["key", "test"],
+            Self::expr_equal,
+            Self::pair_value,
+        )
+    }
+
+    // (assoc-if pred alist): the first pair whose key pred accepts
+    pub fn builtin_assoc_if(&mut self, args: &[Expr]) -> EvalResult {
+        let (args, options) = Self::sequence_options(args, "assoc-if", 2, &["key"])?;
+        if !Self::is_callable(&args[0]) {
+            return Err(EvalError::message("assoc-if requires a predicate function"));
+        }
+        for pair in Self::alist_entries(&args[1], "assoc-if")? {
+            if let Some(key) = Self::pair_key(pair) {
+                if self.satisfies(&options, &args[0], key)? {
+                    return Ok(pair.clone());
                 }
-            };
-            result.push(val);
+            }
+        }
+        Ok(Expr::new_list(vec![]))
+    }
+
+    fn plist_items<'a>(plist: &'a Expr, name: &str) -> Result<&'a [Expr], EvalError> {
+        match plist {
+            Expr::List(items) if items.len() % 2 == 0 => Ok(items),
+            Expr::List(_) => Err(EvalError::message(format!(
+                "{} requires a property list with an even number of elements",
+                name
+            ))),
+            _ => Err(EvalError::message(format!(
+                "{} requires a property list",
+                name
+            ))),
         }
+    }
 
-        Ok(Expr::List(result))
+    fn plist_lookup(&self, items: &[Expr], key: &Expr) -> Result<Option<Expr>, EvalError> {
+        for entry in items.chunks(2) {
+            if self.expr_equal(&entry[0], key)? {
+                return Ok(Some(entry[1].clone()));
+            }
+        }
+        Ok(None)
     }
 
-    pub fn builtin_filter(&mut self, args: &[Expr]) -> EvalResult {
-        if args.len() != 2 {
-            return Err(EvalError::message("filter requires exactly 2 arguments"));
+    pub fn builtin_getf(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 2 || args.len() > 3 {
+            return Err(EvalError::message("getf requires 2 or 3 arguments"));
         }
 
-        let pred = &args[0];
-        let list = match &args[1] {
-            Expr::List(l) => l,
-            _ => {
-                return Err(EvalError::message(
-                    "filter requires a list as second argument",
-                ))
+        let items = Self::plist_items(&args[0], "getf")?;
+        Ok(self
+            .plist_lookup(items, &args[1])?
+            .or_else(|| args.get(2).cloned())
+            .unwrap_or(Expr::new_list(vec![])))
+    }
+
+    // (:key record [default]): a keyword called as a function reads its own entry from a
+    // hash table, as hash-ref does, or from a property list, as getf does. A missing key
+    // gives the default, or () without one.
+    pub fn keyword_lookup(&mut self, keyword: &Expr, args: &[Expr]) -> EvalResult {
+        let name = write_string(keyword);
+        if args.is_empty() || args.len() > 2 {
+            return Err(EvalError::message(format!(
+                "{} requires 1 or 2 arguments: a hash table or property list and an optional default",
+                name
+            )));
+        }
+
+        let found = match &args[0] {
+            Expr::HashTable(table) => {
+                Self::expr_to_hashkey(keyword).and_then(|key| table.borrow().get(&key).cloned())
+            }
+            Expr::List(_) => {
+                let items = Self::plist_items(&args[0], &name)?;
+                self.plist_lookup(items, keyword)?
+            }
+            other => {
+                return Err(EvalError::message(format!(
+                    "{} requires a hash table or property list, got {}",
+                    name,
+                    write_string(other)
+                )))
             }
         };
+        Ok(found
+            .or_else(|| args.get(1).cloned())
+            .unwrap_or(Expr::new_list(vec![])))
+    }
 
-        let mut result = Vec::new();
-        for item in list {
-            let test_result = match pred {
-                Expr::Symbol(sym_data) => self.apply_builtin(sym_data.name(), &[item.clone()])?,
-                Expr::List(lambda)
-                    if lambda.len() == 3
-                        && matches!(&lambda[0], Expr::Symbol(sym_data) if sym_data.name() == "lambda") =>
-                {
-                    self.apply_lambda(lambda, &[item.clone()])?
-                }
-                _ => return Err(EvalError::message("filter requires a predicate function")),
+    pub fn builtin_plist_get(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 2 {
+            return Err(EvalError::message("plist-get requires exactly 2 arguments"));
+        }
+
+        let items = Self::plist_items(&args[0], "plist-get")?;
+        Ok(self
+            .plist_lookup(items, &args[1])?
+            .unwrap_or(Expr::new_list(vec![])))
+    }
+
+    pub fn builtin_plist_put(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 3 {
+            return Err(EvalError::message("plist-put requires exactly 3 arguments"));
+        }
+
+        let mut items = Self::plist_items(&args[0], "plist-put")?.to_vec();
+        let mut found = None;
+        for i in (0..items.len()).step_by(2) {
+            if self.expr_equal(&items[i], &args[1])? {
+                found = Some(i);
+                break;
+            }
+        }
+        match found {
+            Some(i) => items[i + 1] = args[2].clone(),
+            None => {
+                items.push(args[1].clone());
+                items.push(args[2].clone());
+            }
+        }
+        Ok(Expr::new_list(items))
+    }
+
+    pub fn builtin_alist_to_hash(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message(
+                "alist->hash requires exactly 1 argument",
+            ));
+        }
+
+        let mut table = OrderedMap::new();
+        for pair in Self::alist_entries(&args[0], "alist->hash")? {
+            let (key, value) = match (Self::pair_key(pair), Self::pair_value(pair)) {
+                (Some(key), Some(value)) => (key, value),
+                _ => return Err(EvalError::message("alist->hash requires key/value pairs")),
             };
+            let key = Evaluator::expr_to_hashkey(key)
+                .ok_or_else(|| EvalError::message(format!("Invalid hash key: {:?}", key)))?;
+            // Earlier entries shadow later ones, as with assoc
+            if !table.contains_key(&key) {
+                table.insert(key, value);
+            }
+        }
+        Ok(Expr::new_hash_table(table))
+    }
+
+    pub fn builtin_hash_to_alist(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message(
+                "hash->alist requires exactly 1 argument",
+            ));
+        }
+
+        match &args[0] {
+            Expr::HashTable(table) => Ok(Expr::new_list(
+                table
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| {
+                        Expr::Cons(
+                            Box::new(Evaluator::hashkey_to_expr(key)),
+                            Box::new(value.clone()),
+                        )
+                    })
+                    .collect(),
+            )),
+            _ => Err(EvalError::message("hash->alist requires a hash table")),
+        }
+    }
 
-            let is_true = Evaluator::is_truthy(&test_result);
+    pub fn builtin_mapcar(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 2 {
+            return Err(EvalError::message("mapcar requires at least 2 arguments"));
+        }
+
+        let func = &args[0];
+        if !Self::is_callable(func) {
+            return Err(EvalError::message(
+                "mapcar requires a function as first argument",
+            ));
+        }
+        let mut seqs = Vec::new();
+        for arg in &args[1..] {
+            seqs.push(Self::seq_iter(arg).ok_or_else(|| {
+                EvalError::message(
+                    "mapcar requires list, vector, string, hash table or iterator arguments",
+                )
+            })?);
+        }
 
-            if is_true {
-                result.push(item.clone());
+        // Stops at the end of the shortest sequence, so one of them can be an unending
+        // iterator
+        let mut result = Vec::new();
+        while let Some(func_args) = seqs
+            .iter_mut()
+            .map(|seq| seq.next_item())
+            .collect::<Option<Vec<Expr>>>()
+        {
+            if result.len() >= MAX_VECTOR_LENGTH {
+                return Err(EvalError::message("mapcar: more items than fit in a list"));
             }
+            let val = self.call_callable(func, &func_args)?;
+            result.push(val);
         }
 
-        Ok(Expr::List(result))
+        Ok(Expr::new_list(result))
     }
 
-    pub fn builtin_remove(&mut self, args: &[Expr]) -> EvalResult {
-        if args.len() != 2 {
-            return Err(EvalError::message("remove requires exactly 2 arguments"));
+    // (vector-map f vec ...) is mapcar over vectors, stopping at the shortest
+    pub fn builtin_vector_map(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 2 {
+            return Err(EvalError::message(
+                "vector-map requires at least 2 arguments",
+            ));
         }
 
-        let pred = &args[0];
-        let list = match &args[1] {
-            Expr::List(l) => l,
-            _ => {
-                return Err(EvalError::message(
-                    "remove requires a list as second argument",
-                ))
+        let func = &args[0];
+        if !Self::is_callable(func) {
+            return Err(EvalError::message(
+                "vector-map requires a function as first argument",
+            ));
+        }
+        // Copied up front so f is free to modify the vectors it is mapping over
+        let mut vectors: Vec<Vec<Expr>> = Vec::new();
+        for arg in &args[1..] {
+            match arg {
+                Expr::Vector(v) => vectors.push(v.borrow().clone()),
+                _ => return Err(EvalError::message("vector-map requires vector arguments")),
             }
-        };
+        }
 
-        let mut result = Vec::new();
-        for item in list {
-            let test_result = match pred {
-                Expr::Symbol(sym_data) => self.apply_builtin(sym_data.name(), &[item.clone()])?,
-                Expr::List(lambda)
-                    if lambda.len() == 3
-                        && matches!(&lambda[0], Expr::Symbol(sym_data) if sym_data.name() == "lambda") =>
-                {
-                    self.apply_lambda(lambda, &[item.clone()])?
-                }
-                _ => return Err(EvalError::message("remove requires a predicate function")),
-            };
+        let min_len = vectors.iter().map(|v| v.len()).min().unwrap_or(0);
+        let mut result = Vec::with_capacity(min_len);
+        for i in 0..min_len {
+            let func_args: Vec<Expr> = vectors.iter().map(|v| v[i].clone()).collect();
+            result.push(self.call_callable(func, &func_args)?);
+        }
 
-            let is_true = Evaluator::is_truthy(&test_result);
+        Ok(Expr::new_vector(result))
+    }
+
+    // filter and remove-if-not keep the elements pred accepts; remove and remove-if drop them
+    pub fn builtin_filter(&mut self, args: &[Expr], name: &str, keep: bool) -> EvalResult {
+        let (args, options) = Self::sequence_options(args, name, 2, &["key"])?;
+        let (pred, mut seq) = Self::predicate_and_seq(args, name)?;
 
-            if !is_true {
-                // Note: opposite of filter
-                result.push(item.clone());
+        let mut result = Vec::new();
+        while let Some(item) = seq.next_item() {
+            if self.satisfies(&options, pred, &item)? == keep {
+                result.push(item);
             }
         }
 
-        Ok(Expr::List(result))
+        Ok(Expr::new_list(result))
     }
 
     pub fn builtin_reduce(&mut self, args: &[Expr]) -> EvalResult {
@@ -291,80 +603,196 @@ impl Evaluator {
         }
 
         let func = &args[0];
-        let list = match &args[1] {
-            Expr::List(l) => l,
+        if !Self::is_callable(func) {
+            return Err(EvalError::message(
+                "reduce requires a function as first argument",
+            ));
+        }
+        let mut seq = Self::seq_arg(&args[1], "reduce", "second")?;
+
+        let mut acc = match args.get(2) {
+            Some(init) => init.clone(),
+            None => seq
+                .next_item()
+                .ok_or_else(|| EvalError::message("reduce of empty list with no initial value"))?,
+        };
+
+        while let Some(item) = seq.next_item() {
+            acc = self.call_callable(func, &[acc, item])?;
+        }
+
+        Ok(acc)
+    }
+
+    // (sort seq pred) returns a new list or vector ordered by pred. The merge sort is
+    // stable, so stable-sort is the same builtin and equal elements keep their order.
+    // With :key, pred compares (key item), and key is called once per element.
+    pub fn builtin_sort(&mut self, args: &[Expr]) -> EvalResult {
+        let (args, options) = Self::sequence_options(args, "sort", 2, &["key"])?;
+
+        let pred = &args[1];
+        if !Self::is_callable(pred) {
+            return Err(EvalError::message(
+                "sort requires a function as second argument",
+            ));
+        }
+        let (items, is_vector) = match &args[0] {
+            Expr::List(l) => (l.to_vec(), false),
+            Expr::Vector(v) => (v.borrow().clone(), true),
             _ => {
                 return Err(EvalError::message(
-                    "reduce requires a list as second argument",
+                    "sort requires a list or vector as first argument",
                 ))
             }
         };
 
-        if list.is_empty() {
-            if args.len() == 3 {
-                return Ok(args[2].clone()); // Return initial value
-            } else {
-                return Err(EvalError::message(
-                    "reduce of empty list with no initial value",
-                ));
-            }
+        let mut keyed = Vec::with_capacity(items.len());
+        for item in items {
+            let key = self.project(&options, &item)?;
+            keyed.push((item, key));
         }
-
-        let (mut acc, start_idx) = if args.len() == 3 {
-            (args[2].clone(), 0)
+        let sorted = self.merge_sort(keyed, pred)?;
+        let result = sorted.into_iter().map(|(item, _)| item).collect();
+        Ok(if is_vector {
+            Expr::new_vector(result)
         } else {
-            (list[0].clone(), 1)
+            Expr::new_list(result)
+        })
+    }
+
+    // (sort-by seq key [pred]) orders by (key item), calling key once per element.
+    // pred defaults to <.
+    pub fn builtin_sort_by(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 2 || args.len() > 3 {
+            return Err(EvalError::message("sort-by requires 2 or 3 arguments"));
+        }
+
+        let key_fn = &args[1];
+        if !Self::is_callable(key_fn) {
+            return Err(EvalError::message(
+                "sort-by requires a key function as second argument",
+            ));
+        }
+        let pred = args
+            .get(2)
+            .cloned()
+            .unwrap_or_else(|| Expr::Symbol(SymbolData::Interned(Symbol::intern("<"))));
+        let (items, is_vector) = match &args[0] {
+            Expr::List(l) => (l.to_vec(), false),
+            Expr::Vector(v) => (v.borrow().clone(), true),
+            _ => {
+                return Err(EvalError::message(
+                    "sort-by requires a list or vector as first argument",
+                ))
+            }
         };
 
-        for item in &list[start_idx..] {
-            acc = match func {
-                Expr::Symbol(sym_data) => {
-                    self.apply_builtin(sym_data.name(), &[acc, item.clone()])?
-                }
-                Expr::List(lambda)
-                    if lambda.len() == 3
-                        && matches!(&lambda[0], Expr::Symbol(sym_data) if sym_data.name() == "lambda") =>
-                {
-                    self.apply_lambda(lambda, &[acc, item.clone()])?
-                }
-                _ => {
-                    return Err(EvalError::message(
-                        "reduce requires a function as first argument",
-                    ))
-                }
-            };
+        let mut keyed = Vec::with_capacity(items.len());
+        for item in items {
+            let key = self.call_callable(key_fn, std::slice::from_ref(&item))?;
+            keyed.push((item, key));
         }
 
-        Ok(acc)
+        let sorted = self.merge_sort(keyed, &pred)?;
+        let result = sorted.into_iter().map(|(item, _)| item).collect();
+        Ok(if is_vector {
+            Expr::new_vector(result)
+        } else {
+            Expr::new_list(result)
+        })
     }
 
-    pub fn builtin_apply(&mut self, args: &[Expr]) -> EvalResult {
+    // (vector-sort! vec pred) sorts in place with the same stable merge as sort
+    pub fn builtin_vector_sort(&mut self, args: &[Expr]) -> EvalResult {
         if args.len() != 2 {
-            return Err(EvalError::message("apply requires exactly 2 arguments"));
+            return Err(EvalError::message(
+                "vector-sort! requires exactly 2 arguments",
+            ));
         }
 
-        let func = &args[0];
-        let list_args = match &args[1] {
-            Expr::List(l) => l.clone(),
+        let vec = match &args[0] {
+            Expr::Vector(v) => v,
             _ => {
                 return Err(EvalError::message(
-                    "apply requires a list as second argument",
+                    "vector-sort! requires a vector as first argument",
                 ))
             }
         };
+        let pred = &args[1];
+        if !Self::is_callable(pred) {
+            return Err(EvalError::message(
+                "vector-sort! requires a function as second argument",
+            ));
+        }
+
+        // Sort a copy so the predicate can still read the vector while we work
+        let keyed = vec
+            .borrow()
+            .iter()
+            .map(|item| (item.clone(), item.clone()))
+            .collect();
+        let sorted = self.merge_sort(keyed, pred)?;
+        *vec.borrow_mut() = sorted.into_iter().map(|(item, _)| item).collect();
+        Ok(args[0].clone())
+    }
+
+    // Sorts (item, key) pairs by key. std's sort_by can't propagate predicate errors,
+    // so this is a plain top-down merge that only takes from the right on a strict
+    // "right before left", which keeps it stable.
+    fn merge_sort(
+        &mut self,
+        mut items: Vec<(Expr, Expr)>,
+        pred: &Expr,
+    ) -> Result<Vec<(Expr, Expr)>, EvalError> {
+        if items.len() <= 1 {
+            return Ok(items);
+        }
 
-        match func {
-            Expr::Symbol(sym_data) => self.apply_builtin(sym_data.name(), &list_args),
-            Expr::List(lambda)
-                if lambda.len() == 3
-                    && matches!(&lambda[0], Expr::Symbol(sym_data) if sym_data.name() == "lambda") =>
-            {
-                self.apply_lambda(lambda, &list_args)
+        let right = items.split_off(items.len() / 2);
+        let left = self.merge_sort(items, pred)?;
+        let right = self.merge_sort(right, pred)?;
+
+        let mut merged = Vec::with_capacity(left.len() + right.len());
+        let mut left = left.into_iter().peekable();
+        let mut right = right.into_iter().peekable();
+        while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+            let args = [r.1.clone(), l.1.clone()];
+            let right_first = Evaluator::is_truthy(&self.call_callable(pred, &args)?);
+            if right_first {
+                merged.extend(right.next());
+            } else {
+                merged.extend(left.next());
             }
-            _ => Err(EvalError::message(
-                "apply requires a function as first argument",
-            )),
         }
+        merged.extend(left);
+        merged.extend(right);
+        Ok(merged)
+    }
+
+    // (apply f a b ... list): calls f with a, b, ... followed by the elements of list
+    pub fn builtin_apply(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 2 {
+            return Err(EvalError::message("apply requires at least 2 arguments"));
+        }
+
+        let func = &args[0];
+        if !Self::is_callable(func) {
+            return Err(EvalError::message(format!(
+                "apply: {} is not a function",
+                write_string(func)
+            )));
+        }
+        let (last, spread) = args[1..].split_last().unwrap();
+        let Expr::List(rest) = last else {
+            return Err(EvalError::message(format!(
+                "apply: last argument must be a list, got {}",
+                write_string(last)
+            )));
+        };
+        let mut call_args = spread.to_vec();
+        call_args.extend(rest.iter().cloned());
+
+        self.call_callable(func, &call_args)
     }
 
     pub fn builtin_funcall(&mut self, args: &[Expr]) -> EvalResult {
@@ -373,36 +801,366 @@ impl Evaluator {
         }
 
         let func = &args[0];
+        if !Self::is_callable(func) {
+            return Err(EvalError::message(format!(
+                "funcall: {} is not a function",
+                write_string(func)
+            )));
+        }
         let func_args = &args[1..];
 
-        match func {
-            Expr::Symbol(sym_data) => self.apply_builtin(sym_data.name(), func_args),
-            Expr::List(lambda)
-                if lambda.len() == 3
-                    && matches!(&lambda[0], Expr::Symbol(sym_data) if sym_data.name() == "lambda") =>
-            {
-                self.apply_lambda(lambda, func_args)
+        self.call_callable(func, func_args)
+    }
+
+    pub fn builtin_identity(&mut self, args: &[Expr]) -> EvalResult {
+        match args {
+            [value] => Ok(value.clone()),
+            _ => Err(EvalError::message("identity requires exactly 1 argument")),
+        }
+    }
+
+    // partial, compose and constantly build ordinary lambdas taking &rest arguments, so
+    // the functions they return print, compare and save like any other. Captured values
+    // are quoted into the body, since they have been evaluated already.
+    fn variadic_lambda(rest: &str, body: Expr) -> Expr {
+        Expr::new_list(vec![
+            Self::symbol("lambda"),
+            Expr::new_list(vec![Self::symbol("&rest"), Self::symbol(rest)]),
+            body,
+        ])
+    }
+
+    fn symbol(name: &str) -> Expr {
+        Expr::Symbol(SymbolData::Interned(Symbol::intern(name)))
+    }
+
+    fn quoted(value: &Expr) -> Expr {
+        Expr::new_list(vec![Self::symbol("quote"), value.clone()])
+    }
+
+    // (partial f a b): a function that calls f with a and b before its own arguments
+    pub fn builtin_partial(&mut self, args: &[Expr]) -> EvalResult {
+        let Some((func, fixed)) = args.split_first() else {
+            return Err(EvalError::message("partial requires at least 1 argument"));
+        };
+        if !Self::is_callable(func) {
+            return Err(EvalError::message(format!(
+                "partial: {} is not a function",
+                write_string(func)
+            )));
+        }
+        if fixed.is_empty() {
+            return Ok(func.clone());
+        }
+        let mut call = vec![Self::symbol("apply")];
+        call.extend(args.iter().map(Self::quoted));
+        call.push(Self::symbol("partial-args"));
+        Ok(Self::variadic_lambda("partial-args", Expr::new_list(call)))
+    }
+
+    // (compose f g h): a function that applies h to its arguments, then g, then f
+    pub fn builtin_compose(&mut self, args: &[Expr]) -> EvalResult {
+        if let Some(func) = args.iter().find(|func| !Self::is_callable(func)) {
+            return Err(EvalError::message(format!(
+                "compose: {} is not a function",
+                write_string(func)
+            )));
+        }
+        let Some((innermost, outer)) = args.split_last() else {
+            return Ok(Self::symbol("identity"));
+        };
+        if outer.is_empty() {
+            return Ok(innermost.clone());
+        }
+        let mut body = Expr::new_list(vec![
+            Self::symbol("apply"),
+            Self::quoted(innermost),
+            Self::symbol("composed-args"),
+        ]);
+        for func in outer.iter().rev() {
+            body = Expr::new_list(vec![Self::symbol("funcall"), Self::quoted(func), body]);
+        }
+        Ok(Self::variadic_lambda("composed-args", body))
+    }
+
+    // (constantly x): a function that ignores its arguments and returns x
+    pub fn builtin_constantly(&mut self, args: &[Expr]) -> EvalResult {
+        match args {
+            [value] => Ok(Self::variadic_lambda("ignored-args", Self::quoted(value))),
+            _ => Err(EvalError::message("constantly requires exactly 1 argument")),
+        }
+    }
+
+    // (dynamic-wind before thunk after): calls the three functions with no arguments and
+    // returns thunk's value. after runs however thunk exits, including by throw,
+    // return-from or an error; an error in before skips the other two.
+    pub fn builtin_dynamic_wind(&mut self, args: &[Expr]) -> EvalResult {
+        let [before, thunk, after] = args else {
+            return Err(EvalError::message(
+                "dynamic-wind requires exactly 3 arguments",
+            ));
+        };
+        for f in args {
+            if !Self::is_callable(f) {
+                return Err(EvalError::message(format!(
+                    "dynamic-wind: {} is not a function",
+                    write_string(f)
+                )));
             }
-            _ => Err(EvalError::message(
-                "funcall requires a function as first argument",
-            )),
         }
+        self.call_callable(before, &[])?;
+        self.with_cleanup(
+            |evaluator| evaluator.call_callable(thunk, &[]),
+            |evaluator| evaluator.call_callable(after, &[]),
+        )
     }
 
     pub fn builtin_print(&mut self, args: &[Expr]) -> EvalResult {
-        use std::io::{self, Write};
         for arg in args {
-            print!("{}", self.format_expr_for_print(arg));
+            self.write_output(&display_string(arg));
         }
-        io::stdout().flush().unwrap();
-        Ok(args.last().cloned().unwrap_or(Expr::List(vec![])))
+        Ok(args.last().cloned().unwrap_or(Expr::new_list(vec![])))
     }
 
     pub fn builtin_println(&mut self, args: &[Expr]) -> EvalResult {
         for arg in args {
-            println!("{}", self.format_expr_for_print(arg));
+            self.write_output(&format!("{}\n", display_string(arg)));
+        }
+        Ok(args.last().cloned().unwrap_or(Expr::new_list(vec![])))
+    }
+
+    // (invoke-restart name args...): unwinds to the innermost active restart called name
+    // and runs it with args
+    pub fn builtin_invoke_restart(&mut self, args: &[Expr]) -> EvalResult {
+        let name = match args.first() {
+            Some(Expr::Symbol(sym_data)) => sym_data.name(),
+            Some(other) => {
+                return Err(EvalError::message(format!(
+                    "invoke-restart: restart name must be a symbol, got {}",
+                    write_string(other)
+                )))
+            }
+            None => return Err(EvalError::message("invoke-restart requires a restart name")),
+        };
+        match self
+            .restarts
+            .iter()
+            .rev()
+            .find(|restart| restart.name == name)
+        {
+            Some(restart) => Err(EvalError::InvokeRestart {
+                id: restart.id,
+                args: args[1..].to_vec(),
+            }),
+            None => Err(EvalError::message(format!(
+                "invoke-restart: no active restart named {}",
+                name
+            ))),
+        }
+    }
+
+    // (compute-restarts): the names of the active restarts, innermost first
+    pub fn builtin_compute_restarts(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("compute-restarts takes no arguments"));
+        }
+        Ok(Expr::new_list(
+            self.active_restarts()
+                .into_iter()
+                .map(|restart| Self::symbol(&restart.name))
+                .collect(),
+        ))
+    }
+
+    // print and println to the error port
+    pub fn builtin_eprint(&mut self, args: &[Expr]) -> EvalResult {
+        for arg in args {
+            self.error_output.write(&display_string(arg));
+        }
+        Ok(args.last().cloned().unwrap_or(Expr::new_list(vec![])))
+    }
+
+    pub fn builtin_eprintln(&mut self, args: &[Expr]) -> EvalResult {
+        for arg in args {
+            self.error_output
+                .write(&format!("{}\n", display_string(arg)));
+        }
+        Ok(args.last().cloned().unwrap_or(Expr::new_list(vec![])))
+    }
+
+    // (read-line [port]): the next line from the input port, or from a file port, without
+    // its line ending; nil at the end of input. Finishes the line read-char or peek-char
+    // started, if any.
+    pub fn builtin_read_line(&mut self, args: &[Expr]) -> EvalResult {
+        match args {
+            [] => {}
+            [port] => return self.read_line_from(port),
+            _ => return Err(EvalError::message("read-line takes at most a port")),
+        }
+        let line = if self.input_buffer.is_empty() {
+            self.input.read_line()
+        } else {
+            Some(std::mem::take(&mut self.input_buffer))
+        };
+        Ok(match line {
+            Some(line) => {
+                let line = line.strip_suffix('\n').unwrap_or(&line);
+                Expr::String(line.strip_suffix('\r').unwrap_or(line).to_string())
+            }
+            None => Expr::new_list(vec![]),
+        })
+    }
+
+    // (read-char) and (peek-char): the next character from the input port, or nil at the
+    // end of input; peek-char leaves it to be read again
+    pub fn builtin_read_char(&mut self, args: &[Expr], consume: bool) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message(format!(
+                "{} takes no arguments",
+                if consume { "read-char" } else { "peek-char" }
+            )));
+        }
+        if self.input_buffer.is_empty() {
+            self.input_buffer = self.input.read_line().unwrap_or_default();
+        }
+        let Some(ch) = self.input_buffer.chars().next() else {
+            return Ok(Expr::new_list(vec![]));
+        };
+        if consume {
+            self.input_buffer.drain(..ch.len_utf8());
+        }
+        Ok(Expr::Character(ch))
+    }
+
+    // (format dest control args...) with ~a (display), ~s (write), ~d (integer),
+    // ~f / ~,Nf (float), ~% (newline) and ~~. A nil destination returns the string;
+    // t prints it to the output port and returns nil.
+    pub fn builtin_format(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 2 {
+            return Err(EvalError::message("format requires at least 2 arguments"));
+        }
+
+        let to_stdout = match &args[0] {
+            Expr::List(l) if l.is_empty() => false,
+            Expr::Symbol(SymbolData::Interned(name)) if name == "t" => true,
+            _ => return Err(EvalError::message("format destination must be nil or t")),
+        };
+        let control = match &args[1] {
+            Expr::String(s) => s,
+            _ => {
+                return Err(EvalError::message(
+                    "format requires a control string as second argument",
+                ))
+            }
+        };
+
+        let mut values = args[2..].iter();
+        let mut next_value = |directive: char| {
+            values.next().ok_or_else(|| {
+                EvalError::message(format!("format: no argument left for ~{}", directive))
+            })
+        };
+
+        let mut output = String::new();
+        let mut chars = control.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '~' {
+                output.push(ch);
+                continue;
+            }
+
+            // Optional ,N precision prefix, only meaningful for ~f
+            let mut precision = None;
+            let mut directive = chars.next();
+            if directive == Some(',') {
+                let mut digits = String::new();
+                directive = None;
+                for next in chars.by_ref() {
+                    if next.is_ascii_digit() {
+                        digits.push(next);
+                    } else {
+                        directive = Some(next);
+                        break;
+                    }
+                }
+                precision = Some(
+                    digits
+                        .parse::<usize>()
+                        .map_err(|_| EvalError::message("format: expected digits after ~,"))?,
+                );
+            }
+
+            match directive.map(|d| d.to_ascii_lowercase()) {
+                Some('a') => output.push_str(&display_string(next_value('a')?)),
+                Some('s') => output.push_str(&write_string(next_value('s')?)),
+                Some('d') => match next_value('d')? {
+                    value @ (Expr::Integer(_) | Expr::BigInt(_)) => {
+                        output.push_str(&display_string(value))
+                    }
+                    other => {
+                        return Err(EvalError::message(format!(
+                            "format: ~d requires an integer, got {}",
+                            write_string(other)
+                        )))
+                    }
+                },
+                Some('f') => {
+                    let value = Evaluator::to_number(next_value('f')?)
+                        .map_err(|_| EvalError::message("format: ~f requires a number"))?;
+                    match precision {
+                        Some(digits) => output.push_str(&format!("{:.*}", digits, value)),
+                        None => output.push_str(&Self::format_float(value)),
+                    }
+                }
+                Some('%') => output.push('\n'),
+                Some('~') => output.push('~'),
+                Some(other) => {
+                    return Err(EvalError::message(format!(
+                        "format: unknown directive ~{}",
+                        other
+                    )))
+                }
+                None => return Err(EvalError::message("format: control string ends with ~")),
+            }
+        }
+
+        if to_stdout {
+            self.write_output(&output);
+            Ok(Expr::new_list(vec![]))
+        } else {
+            Ok(Expr::String(output))
+        }
+    }
+
+    // (pprint expr [width]) prints expr in readable form, broken across lines to fit
+    // width columns (80 by default), and returns nil
+    pub fn builtin_pprint(&mut self, args: &[Expr]) -> EvalResult {
+        if args.is_empty() || args.len() > 2 {
+            return Err(EvalError::message("pprint requires 1 or 2 arguments"));
+        }
+
+        let width = match args.get(1) {
+            None => DEFAULT_PRETTY_WIDTH,
+            Some(Expr::Integer(n)) if *n > 0 => *n as usize,
+            Some(_) => {
+                return Err(EvalError::message(
+                    "pprint width must be a positive integer",
+                ))
+            }
+        };
+
+        self.write_output(&format!("{}\n", pretty_print(&args[0], width)));
+        Ok(Expr::new_list(vec![]))
+    }
+
+    // ~f without a precision always shows a decimal point
+    fn format_float(value: f64) -> String {
+        let text = value.to_string();
+        if value.is_finite() && !text.contains('.') {
+            format!("{}.0", text)
+        } else {
+            text
         }
-        Ok(args.last().cloned().unwrap_or(Expr::List(vec![])))
     }
 
     // Symbol operations
@@ -435,7 +1193,7 @@ impl Evaluator {
         }
 
         let symbol_name = match &args[0] {
-            Expr::Symbol(SymbolData::Interned(name)) => name.clone(),
+            Expr::Symbol(SymbolData::Interned(name)) => name.to_string(),
             _ => {
                 return Err(EvalError::message(
                     "get requires an interned symbol as first argument",
@@ -451,7 +1209,7 @@ impl Evaluator {
         Ok(self
             .environment
             .get_property(&symbol_name, &property_name)
-            .unwrap_or_else(|| Expr::List(vec![])))
+            .unwrap_or_else(|| Expr::new_list(vec![])))
     }
 
     pub fn builtin_put(&mut self, args: &[Expr]) -> EvalResult {
@@ -460,7 +1218,7 @@ impl Evaluator {
         }
 
         let symbol_name = match &args[0] {
-            Expr::Symbol(SymbolData::Interned(name)) => name.clone(),
+            Expr::Symbol(SymbolData::Interned(name)) => name.to_string(),
             _ => {
                 return Err(EvalError::message(
                     "put requires an interned symbol as first argument",
@@ -487,7 +1245,7 @@ impl Evaluator {
         }
 
         let symbol_name = match &args[0] {
-            Expr::Symbol(SymbolData::Interned(name)) => name.clone(),
+            Expr::Symbol(SymbolData::Interned(name)) => name.to_string(),
             _ => {
                 return Err(EvalError::message(
                     "symbol-plist requires an interned symbol",
@@ -496,98 +1254,201 @@ impl Evaluator {
         };
 
         let plist = self.environment.get_symbol_plist(&symbol_name);
-        Ok(Expr::List(plist))
-    }
-
-    pub fn format_expr_for_print(&self, expr: &Expr) -> String {
-        match expr {
-            Expr::Integer(n) => format!("{}", n),
-            Expr::Float(f) => format!("{}", f),
-            Expr::Rational {
-                numerator,
-                denominator,
-            } => format!("{}/{}", numerator, denominator),
-            Expr::Character(ch) => format!("{}", ch),
-            Expr::Symbol(sym_data) => sym_data.name().to_string(),
-            Expr::String(s) => s.clone(), // Print strings without quotes
-            Expr::List(list) => {
-                let items: Vec<String> =
-                    list.iter().map(|e| self.format_expr_for_print(e)).collect();
-                format!("({})", items.join(" "))
-            }
-            Expr::Cons(car, cdr) => {
-                let mut repr = String::from("(");
-                repr.push_str(&self.format_expr_for_print(car));
-
-                let mut tail = cdr.as_ref();
-                loop {
-                    match tail {
-                        Expr::Cons(next_car, next_cdr) => {
-                            repr.push(' ');
-                            repr.push_str(&self.format_expr_for_print(next_car));
-                            tail = next_cdr.as_ref();
-                        }
-                        Expr::List(list) => {
-                            for item in list {
-                                repr.push(' ');
-                                repr.push_str(&self.format_expr_for_print(item));
-                            }
-                            repr.push(')');
-                            break;
-                        }
-                        other => {
-                            repr.push_str(" . ");
-                            repr.push_str(&self.format_expr_for_print(other));
-                            repr.push(')');
-                            break;
-                        }
-                    }
-                }
+        Ok(Expr::new_list(plist))
+    }
 
-                repr
-            }
-            Expr::Vector(vec) => {
-                let items: Vec<String> =
-                    vec.iter().map(|e| self.format_expr_for_print(e)).collect();
-                format!("[{}]", items.join(" "))
-            }
-            Expr::HashTable(h) => {
-                format!("#<hash-table:{}>", h.len())
-            }
+    // Commands that only make sense interactively: the form that runs one is left out of
+    // the session log, exported scripts and the recording
+    pub(crate) fn apply_session_command(&mut self, name: &str, args: &[Expr]) -> EvalResult {
+        self.session_command_ran = true;
+        match name {
+            "export-session" => self.builtin_export_session(args),
+            "session-script" => self.builtin_session_script(args),
+            "start-recording" => self.builtin_start_recording(args),
+            "stop-recording" => self.builtin_stop_recording(args),
+            "recorded-forms" => self.builtin_recorded_forms(args),
+            "global-history" => self.builtin_global_history(args),
+            "rewind" => self.builtin_rewind(args),
+            _ => Err(EvalError::message(format!("Unknown function: {}", name))),
+        }
+    }
+
+    pub fn builtin_export_session(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message(
+                "export-session requires exactly 1 argument",
+            ));
+        }
+
+        let path = match &args[0] {
+            Expr::String(s) => s.clone(),
+            _ => return Err(EvalError::message("export-session requires a string path")),
+        };
+
+        let count = self.session.export(&path)?;
+        Ok(Expr::Integer(count as i64))
+    }
+
+    pub fn builtin_session_script(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("session-script takes no arguments"));
+        }
+        Ok(Expr::String(self.session.to_script()))
+    }
+
+    pub fn builtin_start_recording(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("start-recording takes no arguments"));
+        }
+        self.start_recording();
+        Ok(Evaluator::bool_to_expr(true))
+    }
+
+    // Returns how many forms were recorded, or nil if recording wasn't on
+    pub fn builtin_stop_recording(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("stop-recording takes no arguments"));
+        }
+        Ok(match self.stop_recording() {
+            Some(recording) => Expr::Integer(recording.forms().len() as i64),
+            None => Expr::new_list(vec![]),
+        })
+    }
+
+    pub fn builtin_recorded_forms(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("recorded-forms takes no arguments"));
+        }
+        Ok(match &self.recording {
+            Some(recording) => recording.to_expr(),
+            None => Expr::new_list(vec![]),
+        })
+    }
+
+    // (global-history 'name): each recorded change to the global, as (n :added value),
+    // (n :changed old new) or (n :removed old) where n is the form that made it
+    pub fn builtin_global_history(&mut self, args: &[Expr]) -> EvalResult {
+        let [Expr::Symbol(SymbolData::Interned(name))] = args else {
+            return Err(EvalError::message("global-history requires a symbol"));
+        };
+        let Some(recording) = &self.recording else {
+            return Ok(Expr::new_list(vec![]));
+        };
+        let changes = recording
+            .history_of(name.as_str())
+            .into_iter()
+            .map(|(form, change)| change.to_expr(Expr::Integer(form as i64)))
+            .collect();
+        Ok(Expr::new_list(changes))
+    }
+
+    // (rewind n) goes back to before recorded form n once the top-level form it's in has
+    // finished, since the environment it's running in is about to be replaced
+    pub fn builtin_rewind(&mut self, args: &[Expr]) -> EvalResult {
+        let [Expr::Integer(form)] = args else {
+            return Err(EvalError::message("rewind requires a recorded form number"));
+        };
+        let form = usize::try_from(*form).unwrap_or(0);
+        self.check_rewind(form)?;
+        self.pending_rewind = Some(form);
+        Ok(Expr::Integer(form as i64))
+    }
+
+    // Transactions over the globals: (rollback-transaction) puts back every definition as
+    // it was at the matching (begin-transaction), returning the names it restored
+    pub fn builtin_begin_transaction(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("begin-transaction takes no arguments"));
+        }
+        self.environment.begin_transaction();
+        Ok(Expr::Integer(self.environment.transaction_depth() as i64))
+    }
+
+    pub fn builtin_commit_transaction(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("commit-transaction takes no arguments"));
+        }
+        self.environment.commit_transaction()?;
+        Ok(Evaluator::bool_to_expr(true))
+    }
+
+    pub fn builtin_rollback_transaction(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message(
+                "rollback-transaction takes no arguments",
+            ));
         }
+        let restored = self.environment.rollback_transaction()?;
+        Ok(Expr::new_list(
+            restored
+                .iter()
+                .map(|name| Expr::Symbol(SymbolData::Interned(Symbol::intern(name))))
+                .collect(),
+        ))
     }
 
     pub fn apply_lambda(&mut self, lambda: &[Expr], args: &[Expr]) -> EvalResult {
-        if let Expr::List(params) = &lambda[1] {
-            if params.len() != args.len() {
+        let Expr::List(params) = &lambda[1] else {
+            return Err(EvalError::message("Lambda parameters must be a list"));
+        };
+        let (required, rest) = Self::split_rest_param(params)?;
+        match rest {
+            None if required.len() != args.len() => {
                 return Err(EvalError::message(format!(
                     "Lambda expects {} arguments, got {}",
-                    params.len(),
+                    required.len(),
                     args.len()
-                )));
+                )))
+            }
+            Some(_) if args.len() < required.len() => {
+                return Err(EvalError::message(format!(
+                    "Lambda expects at least {} arguments, got {}",
+                    required.len(),
+                    args.len()
+                )))
+            }
+            _ => {}
+        }
+
+        self.environment.push_scope(ScopeKind::Call);
+        for (param, arg) in required.iter().zip(args.iter()) {
+            if let Expr::Symbol(sym_data) = param {
+                self.environment.set_symbol(sym_data.symbol(), arg.clone());
             }
+        }
+        if let Some(Expr::Symbol(sym_data)) = rest {
+            let extra = Expr::new_list(args[required.len()..].to_vec());
+            self.environment.set_symbol(sym_data.symbol(), extra);
+        }
 
-            self.environment.push_scope();
+        let result = self.eval(&lambda[2]);
+        self.environment.pop_scope();
+        result
+    }
 
-            for (param, arg) in params.iter().zip(args.iter()) {
-                if let Expr::Symbol(sym_data) = param {
-                    if sym_data.is_keyword() {
-                        self.environment.pop_scope();
-                        return Err(EvalError::message("Cannot use keyword as parameter"));
-                    }
-                    self.environment
-                        .set(sym_data.name().to_string(), arg.clone());
-                } else {
-                    self.environment.pop_scope();
-                    return Err(EvalError::message("Lambda parameters must be symbols"));
+    // Splits a parameter list into the required parameters and the one after &rest, which
+    // collects the remaining arguments in a list. Every parameter must be a non-keyword
+    // symbol.
+    pub fn split_rest_param(params: &[Expr]) -> Result<(&[Expr], Option<&Expr>), EvalError> {
+        for param in params {
+            match param {
+                Expr::Symbol(sym_data) if sym_data.is_keyword() => {
+                    return Err(EvalError::message("Cannot use keyword as parameter"))
                 }
+                Expr::Symbol(_) => {}
+                _ => return Err(EvalError::message("Lambda parameters must be symbols")),
             }
-
-            let result = self.eval(&lambda[2]);
-            self.environment.pop_scope();
-            result
-        } else {
-            Err(EvalError::message("Lambda parameters must be a list"))
+        }
+        let is_rest =
+            |param: &Expr| matches!(param, Expr::Symbol(sym_data) if sym_data.name() == "&rest");
+        match params.iter().position(is_rest) {
+            None => Ok((params, None)),
+            Some(index) if index + 2 == params.len() && !is_rest(&params[index + 1]) => {
+                Ok((&params[..index], Some(&params[index + 1])))
+            }
+            Some(_) => Err(EvalError::message(
+                "&rest must be followed by exactly one parameter, at the end of the list",
+            )),
         }
     }
 }
@@ -0,0 +1,387 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::types::{EvalError, EvalResult, Expr, Symbol, SymbolData};
+
+// Types, and generic functions dispatched on the type of their first argument. (defgeneric area
+// (shape)) binds area to a function that hands its arguments to call-generic; each
+// (defmethod area ((s circle)) ...) adds a (type method) entry to the `methods` property of
+// area. A call runs the most specific method whose type the first argument has, and
+// (call-next-method) inside it runs the next one.
+
+// The property of a generic function's symbol holding its methods
+const METHODS: &str = "methods";
+
+// The one parameter of the function defgeneric binds, as it's seen by method bodies
+const GENERIC_ARGUMENTS: &str = "generic-function-arguments";
+
+// The method being run by call-generic and those less specific than it
+#[derive(Clone)]
+pub(crate) struct MethodFrame {
+    generic: Symbol,
+    next: Vec<Expr>,
+    args: Vec<Expr>,
+}
+
+fn symbol(name: &str) -> Expr {
+    Expr::Symbol(SymbolData::Interned(Symbol::intern(name)))
+}
+
+// Every type name the reader of a method, `the` or `declare` can use besides struct names
+pub const TYPE_NAMES: &[&str] = &[
+    "integer",
+    "float",
+    "rational",
+    "number",
+    "string",
+    "character",
+    "keyword",
+    "symbol",
+    "null",
+    "cons",
+    "list",
+    "vector",
+    "hash-table",
+    "matrix",
+    "queue",
+    "stack",
+    "heap",
+    "iterator",
+    "port",
+    "struct",
+    "t",
+];
+
+// `type_name` followed by the types it belongs to, up to t. A name that isn't one of
+// TYPE_NAMES is taken to be a struct type.
+pub fn supertypes(type_name: &str) -> Vec<&str> {
+    match type_name {
+        "integer" | "float" | "rational" => vec![type_name, "number", "t"],
+        "keyword" => vec![type_name, "symbol", "t"],
+        "null" | "cons" => vec![type_name, "list", "t"],
+        "t" => vec!["t"],
+        _ if TYPE_NAMES.contains(&type_name) => vec![type_name, "t"],
+        _ => vec![type_name, "struct", "t"],
+    }
+}
+
+// The types `value` has, most specific first. Methods on `t` apply to everything.
+pub fn type_chain(value: &Expr) -> Vec<Symbol> {
+    let name = match value {
+        Expr::Integer(_) | Expr::BigInt(_) => "integer",
+        Expr::Float(_) => "float",
+        Expr::Rational { .. } => "rational",
+        Expr::String(_) => "string",
+        Expr::Character(_) => "character",
+        Expr::Symbol(SymbolData::Keyword(_)) => "keyword",
+        Expr::Symbol(_) => "symbol",
+        Expr::List(items) if items.is_empty() => "null",
+        Expr::List(_) | Expr::Cons(_, _) => "cons",
+        Expr::Vector(_) => "vector",
+        Expr::HashTable(_) => "hash-table",
+        Expr::Matrix(_) => "matrix",
+        Expr::Queue(_) => "queue",
+        Expr::Stack(_) => "stack",
+        Expr::Heap(_) => "heap",
+        Expr::Iterator(_) => "iterator",
+        Expr::Port(_) => "port",
+        Expr::Struct(instance) => instance.borrow().type_name.as_str(),
+    };
+    supertypes(name).into_iter().map(Symbol::intern).collect()
+}
+
+impl Evaluator {
+    // (type-of x): the most specific type a method can be defined on
+    pub fn builtin_type_of(&mut self, args: &[Expr]) -> EvalResult {
+        let [value] = args else {
+            return Err(EvalError::message("type-of requires exactly 1 argument"));
+        };
+        Ok(Expr::Symbol(SymbolData::Interned(type_chain(value)[0])))
+    }
+
+    // The function defgeneric binds `name` to
+    fn generic_function(name: Symbol) -> Expr {
+        Expr::new_list(vec![
+            symbol("lambda"),
+            Expr::new_list(vec![symbol("&rest"), symbol(GENERIC_ARGUMENTS)]),
+            Expr::new_list(vec![
+                symbol("call-generic"),
+                Expr::new_list(vec![
+                    symbol("quote"),
+                    Expr::Symbol(SymbolData::Interned(name)),
+                ]),
+                symbol(GENERIC_ARGUMENTS),
+            ]),
+        ])
+    }
+
+    // Binds `name` to its generic function unless it already is, keeping any methods
+    fn ensure_generic(&mut self, name: Symbol) {
+        let generic = Self::generic_function(name);
+        if self.environment.get_symbol(name).ok() != Some(generic.clone()) {
+            self.environment.define(name.to_string(), generic);
+        }
+    }
+
+    fn generic_name(arg: &Expr, form: &str) -> Result<Symbol, EvalError> {
+        match arg {
+            Expr::Symbol(SymbolData::Interned(name)) => Ok(*name),
+            _ => Err(EvalError::message(format!(
+                "{} requires a symbol naming the generic function",
+                form
+            ))),
+        }
+    }
+
+    // (defgeneric name (param...) ["doc"]): defines a generic function with no methods yet
+    pub(crate) fn eval_defgeneric(&mut self, list: &[Expr]) -> EvalResult {
+        let (name, params) = match list {
+            [_, name, params] | [_, name, params, Expr::String(_)] => (name, params),
+            _ => {
+                return Err(EvalError::message(
+                    "defgeneric requires a name, a parameter list and optionally a docstring",
+                ))
+            }
+        };
+        let name = Self::generic_name(name, "defgeneric")?;
+        match params {
+            Expr::List(params) if !params.is_empty() => {
+                Self::split_rest_param(params)?;
+            }
+            _ => {
+                return Err(EvalError::message(
+                    "defgeneric requires at least one parameter to dispatch on",
+                ))
+            }
+        }
+        self.ensure_generic(name);
+        Ok(Expr::Symbol(SymbolData::Interned(name)))
+    }
+
+    // (defmethod name ((param type) param...) body...): adds or replaces the method for
+    // `type`; a plain first parameter is a method on t. Defines the generic function too if
+    // there isn't one.
+    pub(crate) fn eval_defmethod(&mut self, list: &[Expr]) -> EvalResult {
+        if list.len() < 4 {
+            return Err(EvalError::message(
+                "defmethod requires a name, a parameter list and a body",
+            ));
+        }
+        let name = Self::generic_name(&list[1], "defmethod")?;
+        let Expr::List(params) = &list[2] else {
+            return Err(EvalError::message("defmethod requires a parameter list"));
+        };
+        let (type_name, first) = match params.first() {
+            Some(Expr::List(specialized)) => match specialized.as_slice() {
+                [param @ Expr::Symbol(_), Expr::Symbol(SymbolData::Interned(type_name))] => {
+                    (*type_name, param.clone())
+                }
+                _ => {
+                    return Err(EvalError::message(
+                        "defmethod: a specialized parameter is written (name type)",
+                    ))
+                }
+            },
+            Some(param) => (Symbol::intern("t"), param.clone()),
+            None => {
+                return Err(EvalError::message(
+                    "defmethod requires at least one parameter to dispatch on",
+                ))
+            }
+        };
+        let mut plain = vec![first];
+        plain.extend(params[1..].iter().cloned());
+        Self::split_rest_param(&plain)?;
+
+        let body = if list.len() == 4 {
+            list[3].clone()
+        } else {
+            let mut progn = vec![symbol("progn")];
+            progn.extend(list[3..].iter().cloned());
+            Expr::new_list(progn)
+        };
+        let method = Expr::new_list(vec![symbol("lambda"), Expr::new_list(plain), body]);
+
+        let type_expr = Expr::Symbol(SymbolData::Interned(type_name));
+        let mut methods: Vec<Expr> = match self.environment.get_property(name.as_str(), METHODS) {
+            Some(Expr::List(methods)) => methods
+                .iter()
+                .filter(
+                    |entry| !matches!(entry, Expr::List(pair) if pair.first() == Some(&type_expr)),
+                )
+                .cloned()
+                .collect(),
+            _ => Vec::new(),
+        };
+        methods.push(Expr::new_list(vec![type_expr, method]));
+        self.environment.set_property(
+            name.to_string(),
+            METHODS.to_string(),
+            Expr::new_list(methods),
+        );
+        self.ensure_generic(name);
+        Ok(Expr::Symbol(SymbolData::Interned(name)))
+    }
+
+    // (call-generic name args): runs the methods of `name` that apply to (car args), most
+    // specific first
+    pub fn builtin_call_generic(&mut self, args: &[Expr]) -> EvalResult {
+        let [name, Expr::List(arguments)] = args else {
+            return Err(EvalError::message(
+                "call-generic requires a generic function name and a list of arguments",
+            ));
+        };
+        let name = Self::generic_name(name, "call-generic")?;
+        let Some(first) = arguments.first() else {
+            return Err(EvalError::message(format!(
+                "{} requires at least one argument",
+                name
+            )));
+        };
+
+        let chain = type_chain(first);
+        let mut applicable: Vec<(usize, Expr)> = Vec::new();
+        if let Some(Expr::List(methods)) = self.environment.get_property(name.as_str(), METHODS) {
+            for entry in methods.iter() {
+                let Expr::List(pair) = entry else {
+                    continue;
+                };
+                if let [Expr::Symbol(SymbolData::Interned(type_name)), method] = pair.as_slice() {
+                    if let Some(rank) = chain.iter().position(|t| t == type_name) {
+                        applicable.push((rank, method.clone()));
+                    }
+                }
+            }
+        }
+        if applicable.is_empty() {
+            return Err(EvalError::message(format!(
+                "{}: no method for {} of type {}",
+                name,
+                write_string(first),
+                chain[0]
+            )));
+        }
+        applicable.sort_by_key(|(rank, _)| *rank);
+        let methods = applicable.into_iter().map(|(_, method)| method).collect();
+        self.run_methods(name, methods, arguments.to_vec())
+    }
+
+    fn run_methods(
+        &mut self,
+        generic: Symbol,
+        mut methods: Vec<Expr>,
+        args: Vec<Expr>,
+    ) -> EvalResult {
+        let method = methods.remove(0);
+        self.method_frames.push(MethodFrame {
+            generic,
+            next: methods,
+            args: args.clone(),
+        });
+        let result = self.call_callable(&method, &args);
+        self.method_frames.pop();
+        result
+    }
+
+    // (call-next-method [arg...]): runs the next most specific method, with the arguments
+    // the current one was called with unless others are given
+    pub fn builtin_call_next_method(&mut self, args: &[Expr]) -> EvalResult {
+        let Some(frame) = self.method_frames.last().cloned() else {
+            return Err(EvalError::message(
+                "call-next-method can only be called from a method",
+            ));
+        };
+        if frame.next.is_empty() {
+            return Err(EvalError::message(format!(
+                "call-next-method: no next method for {}",
+                frame.generic
+            )));
+        }
+        let args = if args.is_empty() {
+            frame.args
+        } else {
+            args.to_vec()
+        };
+        self.run_methods(frame.generic, frame.next, args)
+    }
+
+    // (next-method-p): whether call-next-method has a method to run
+    pub fn builtin_next_method_p(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("next-method-p takes no arguments"));
+        }
+        Ok(Evaluator::bool_to_expr(
+            self.method_frames
+                .last()
+                .is_some_and(|frame| !frame.next.is_empty()),
+        ))
+    }
+
+    // (the type expr): the value of expr, which has to be of `type`
+    pub(crate) fn eval_the(&mut self, list: &[Expr]) -> EvalResult {
+        let [_, Expr::Symbol(SymbolData::Interned(type_name)), expr] = list else {
+            return Err(EvalError::message(
+                "the requires a type name and an expression",
+            ));
+        };
+        let value = self.eval(expr)?;
+        if !type_chain(&value).contains(type_name) {
+            return Err(EvalError::message(format!(
+                "the: expected {}, got {} of type {}",
+                type_name,
+                write_string(&value),
+                type_chain(&value)[0]
+            )));
+        }
+        Ok(value)
+    }
+
+    // (declare (type type name...) ...): checks that each name is bound to a value of
+    // `type`, so a declaration at the start of a function body checks its arguments. ignore,
+    // ignorable and optimize declarations are accepted and do nothing. Returns nil.
+    pub(crate) fn eval_declare(&mut self, list: &[Expr]) -> EvalResult {
+        for declaration in &list[1..] {
+            let Expr::List(parts) = declaration else {
+                return Err(EvalError::message(format!(
+                    "declare: expected a declaration list, got {}",
+                    write_string(declaration)
+                )));
+            };
+            match parts.as_slice() {
+                [Expr::Symbol(SymbolData::Interned(kind)), Expr::Symbol(SymbolData::Interned(type_name)), names @ ..]
+                    if kind == "type" =>
+                {
+                    for name in names {
+                        let Expr::Symbol(SymbolData::Interned(name)) = name else {
+                            return Err(EvalError::message(
+                                "declare: type declarations name variables with symbols",
+                            ));
+                        };
+                        let value = self
+                            .environment
+                            .get_symbol(*name)
+                            .map_err(EvalError::message)?;
+                        let chain = type_chain(&value);
+                        if !chain.contains(type_name) {
+                            return Err(EvalError::message(format!(
+                                "declare: {} should be of type {}, got {} of type {}",
+                                name,
+                                type_name,
+                                write_string(&value),
+                                chain[0]
+                            )));
+                        }
+                    }
+                }
+                [Expr::Symbol(SymbolData::Interned(kind)), ..]
+                    if matches!(kind.as_str(), "ignore" | "ignorable" | "optimize") => {}
+                _ => {
+                    return Err(EvalError::message(format!(
+                        "declare: unknown declaration {}",
+                        write_string(declaration)
+                    )))
+                }
+            }
+        }
+        Ok(Expr::new_list(vec![]))
+    }
+}
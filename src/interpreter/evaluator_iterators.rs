@@ -0,0 +1,364 @@
+use crate::interpreter::environment::ScopeKind;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::evaluator_builtins::MAX_VECTOR_LENGTH;
+use crate::interpreter::iterator::{Cursor, SeqIter};
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// The iteration protocol: (iterate collection) makes an iterator, (next it) takes the next
+// item and (iter-done? it) says whether any are left. Iterators are shared and stateful, so
+// every reference sees the same position.
+impl Evaluator {
+    fn iterator_arg<'a>(arg: &'a Expr, name: &str) -> Result<&'a Rc<RefCell<Cursor>>, EvalError> {
+        match arg {
+            Expr::Iterator(cursor) => Ok(cursor),
+            _ => Err(EvalError::message(format!("{} requires an iterator", name))),
+        }
+    }
+
+    // An iterator over the items of `collection`, or None if it isn't iterable. Hash
+    // tables yield (key . value) pairs in insertion order, strings their characters.
+    pub fn iterator_over(collection: &Expr) -> Option<Expr> {
+        match collection {
+            Expr::Iterator(_) => Some(collection.clone()),
+            _ => Self::cursor_over(collection).map(Expr::new_iterator),
+        }
+    }
+
+    fn cursor_over(collection: &Expr) -> Option<Cursor> {
+        let cursor = match collection {
+            Expr::List(items) => Cursor::Items {
+                items: items.clone(),
+                index: 0,
+            },
+            Expr::Vector(vector) => Cursor::Vector {
+                vector: vector.clone(),
+                index: 0,
+            },
+            Expr::String(s) => Cursor::Items {
+                items: Rc::new(s.chars().map(Expr::Character).collect()),
+                index: 0,
+            },
+            Expr::HashTable(table) => Cursor::Items {
+                items: Rc::new(
+                    table
+                        .borrow()
+                        .iter()
+                        .map(|(key, value)| {
+                            Expr::Cons(Box::new(key.to_expr()), Box::new(value.clone()))
+                        })
+                        .collect(),
+                ),
+                index: 0,
+            },
+            _ => return None,
+        };
+        Some(cursor)
+    }
+
+    // The items a sequence builtin walks: what iterate gives, except that a vector is
+    // copied first, as vector-map does, so the function is free to change it. An iterator
+    // is used up as far as the builtin gets.
+    pub fn seq_iter(collection: &Expr) -> Option<Box<dyn SeqIter>> {
+        match collection {
+            Expr::Iterator(cursor) => Some(Box::new(cursor.clone())),
+            Expr::Vector(vector) => Some(Box::new(Cursor::Items {
+                items: Rc::new(vector.borrow().clone()),
+                index: 0,
+            })),
+            _ => Self::cursor_over(collection).map(|cursor| Box::new(cursor) as Box<dyn SeqIter>),
+        }
+    }
+
+    // `seq_iter` for an argument of builtin `name`
+    pub fn seq_arg(
+        collection: &Expr,
+        name: &str,
+        position: &str,
+    ) -> Result<Box<dyn SeqIter>, EvalError> {
+        Self::seq_iter(collection).ok_or_else(|| {
+            EvalError::message(format!(
+                "{} requires a list, vector, string, hash table or iterator as {} argument",
+                name, position
+            ))
+        })
+    }
+
+    // All the items of a sequence argument; a list is shared rather than copied
+    pub fn seq_items(
+        collection: &Expr,
+        name: &str,
+        position: &str,
+    ) -> Result<Rc<Vec<Expr>>, EvalError> {
+        if let Expr::List(items) = collection {
+            return Ok(items.clone());
+        }
+        let mut seq = Self::seq_arg(collection, name, position)?;
+        let mut items = Vec::new();
+        Self::drain_seq(seq.as_mut(), &mut items, name)?;
+        Ok(Rc::new(items))
+    }
+
+    // Appends what's left of `seq` to `items`
+    pub fn drain_seq(
+        seq: &mut dyn SeqIter,
+        items: &mut Vec<Expr>,
+        name: &str,
+    ) -> Result<(), EvalError> {
+        while let Some(item) = seq.next_item() {
+            if items.len() >= MAX_VECTOR_LENGTH {
+                return Err(EvalError::message(format!(
+                    "{}: more items than fit in a list",
+                    name
+                )));
+            }
+            items.push(item);
+        }
+        Ok(())
+    }
+
+    // (iterate collection): a list, vector, string, hash table or iterator (returned as is)
+    pub fn builtin_iterate(&mut self, args: &[Expr]) -> EvalResult {
+        let [collection] = args else {
+            return Err(EvalError::message("iterate requires exactly 1 argument"));
+        };
+        Self::iterator_over(collection).ok_or_else(|| {
+            EvalError::message("iterate requires a list, vector, string, hash table or iterator")
+        })
+    }
+
+    // (next it): the next item, an error once the iterator is used up
+    pub fn builtin_next(&mut self, args: &[Expr]) -> EvalResult {
+        let [iterator] = args else {
+            return Err(EvalError::message("next requires exactly 1 argument"));
+        };
+        Self::iterator_arg(iterator, "next")?
+            .borrow_mut()
+            .advance()
+            .ok_or_else(|| EvalError::message("next: iterator is exhausted"))
+    }
+
+    pub fn builtin_iter_done(&mut self, args: &[Expr]) -> EvalResult {
+        let [iterator] = args else {
+            return Err(EvalError::message("iter-done? requires exactly 1 argument"));
+        };
+        let done = Self::iterator_arg(iterator, "iter-done?")?
+            .borrow()
+            .is_done();
+        Ok(Evaluator::bool_to_expr(done))
+    }
+
+    // (iter->list it): the items left, consuming the iterator. Also accepts anything
+    // iterate does.
+    pub fn builtin_iter_to_list(&mut self, args: &[Expr]) -> EvalResult {
+        let [collection] = args else {
+            return Err(EvalError::message("iter->list requires exactly 1 argument"));
+        };
+        let Some(Expr::Iterator(mut cursor)) = Self::iterator_over(collection) else {
+            return Err(EvalError::message(
+                "iter->list requires a list, vector, string, hash table or iterator",
+            ));
+        };
+        let mut items = Vec::new();
+        Self::drain_seq(&mut cursor, &mut items, "iter->list")?;
+        Ok(Expr::new_list(items))
+    }
+
+    // (range end), (range start end) or (range start end step): a lazy iterator over the
+    // numbers from start (default 0) up to but not including end. A negative step counts
+    // down; any float argument makes every item a float.
+    pub fn builtin_range(&mut self, args: &[Expr]) -> EvalResult {
+        let (start, end, step) = match args {
+            [end] => (&Expr::Integer(0), end, &Expr::Integer(1)),
+            [start, end] => (start, end, &Expr::Integer(1)),
+            [start, end, step] => (start, end, step),
+            _ => return Err(EvalError::message("range requires 1 to 3 arguments")),
+        };
+        let cursor = match (start, end, step) {
+            (Expr::Integer(start), Expr::Integer(end), Expr::Integer(step)) => {
+                if *step == 0 {
+                    return Err(EvalError::message("range: step must not be zero"));
+                }
+                Cursor::int_range(*start, *end, *step)
+            }
+            _ => {
+                let number = |arg: &Expr| {
+                    Self::to_number(arg)
+                        .map_err(|_| EvalError::message("range requires numeric arguments"))
+                };
+                let step = number(step)?;
+                if step == 0.0 {
+                    return Err(EvalError::message("range: step must not be zero"));
+                }
+                Cursor::float_range(number(start)?, number(end)?, step)
+            }
+        };
+        Ok(Expr::new_iterator(cursor))
+    }
+
+    pub fn builtin_iteratorp(&mut self, args: &[Expr]) -> EvalResult {
+        let [value] = args else {
+            return Err(EvalError::message("iteratorp requires exactly 1 argument"));
+        };
+        Ok(Evaluator::bool_to_expr(matches!(value, Expr::Iterator(_))))
+    }
+
+    // (for ((x in xs) (y in ys) :when test ...) body...): runs body for every combination of
+    // items, later clauses nested inside earlier ones, and collects its values in a list.
+    // A clause's collection is evaluated afresh for each item of the clauses before it, so
+    // it can use their variables; :when skips the combinations its test rejects.
+    pub fn eval_for(&mut self, list: &[Expr]) -> EvalResult {
+        let [_, Expr::List(clause_forms), body @ ..] = list else {
+            return Err(EvalError::message(
+                "for requires a list of clauses and a body",
+            ));
+        };
+        if body.is_empty() {
+            return Err(EvalError::message(
+                "for requires a list of clauses and a body",
+            ));
+        }
+        let clauses = Self::for_clauses(clause_forms)?;
+
+        self.environment.push_scope(ScopeKind::Loop);
+        let mut results = Vec::new();
+        let result = self.for_nest(&clauses, body, &mut results);
+        self.environment.pop_scope();
+        result.map(|()| Expr::new_list(results))
+    }
+
+    fn for_clauses(forms: &[Expr]) -> Result<Vec<ForClause<'_>>, EvalError> {
+        let mut clauses = Vec::new();
+        let mut forms = forms.iter();
+        while let Some(form) = forms.next() {
+            match form {
+                Expr::Symbol(keyword) if keyword.is_keyword() && keyword.name() == "when" => {
+                    let test = forms
+                        .next()
+                        .ok_or_else(|| EvalError::message("for: :when requires a test"))?;
+                    clauses.push(ForClause::When(test));
+                }
+                Expr::List(parts) => match parts.as_slice() {
+                    [Expr::Symbol(name), Expr::Symbol(in_word), collection]
+                        if !name.is_keyword() && in_word.name() == "in" =>
+                    {
+                        clauses.push(ForClause::In {
+                            name: name.name().to_string(),
+                            collection,
+                        });
+                    }
+                    _ => {
+                        return Err(EvalError::message(
+                            "for: each clause must be (name in collection) or :when test",
+                        ))
+                    }
+                },
+                _ => {
+                    return Err(EvalError::message(
+                        "for: each clause must be (name in collection) or :when test",
+                    ))
+                }
+            }
+        }
+        Ok(clauses)
+    }
+
+    fn for_nest(
+        &mut self,
+        clauses: &[ForClause],
+        body: &[Expr],
+        results: &mut Vec<Expr>,
+    ) -> Result<(), EvalError> {
+        let Some((clause, rest)) = clauses.split_first() else {
+            let mut value = Expr::new_list(vec![]);
+            for form in body {
+                value = self.eval(form)?;
+            }
+            results.push(value);
+            return Ok(());
+        };
+        match clause {
+            ForClause::When(test) => {
+                let passed = self.eval(test)?;
+                if Self::is_truthy(&passed) {
+                    self.for_nest(rest, body, results)?;
+                }
+            }
+            ForClause::In { name, collection } => {
+                let collection = self.eval(collection)?;
+                let Some(Expr::Iterator(cursor)) = Self::iterator_over(&collection) else {
+                    return Err(EvalError::message(
+                        "for requires a list, vector, string, hash table or iterator",
+                    ));
+                };
+                loop {
+                    // Released before the body runs, which may advance the same iterator
+                    let item = cursor.borrow_mut().advance();
+                    let Some(item) = item else {
+                        break;
+                    };
+                    self.environment.set(name.clone(), item);
+                    self.for_nest(rest, body, results)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // (dolist (x sequence [result]) body...): runs body with x bound to each item of a list,
+    // vector, string, hash table or iterator, then returns result (evaluated with x bound to
+    // nil) or nil. break and continue work as they do in while.
+    pub fn eval_dolist(&mut self, list: &[Expr]) -> EvalResult {
+        const USAGE: &str = "dolist requires (variable sequence [result]) and a body";
+        let [_, Expr::List(spec), body @ ..] = list else {
+            return Err(EvalError::message(USAGE));
+        };
+        let (name, sequence, result) = match spec.as_slice() {
+            [Expr::Symbol(name), sequence, result @ ..]
+                if !name.is_keyword() && result.len() <= 1 =>
+            {
+                (name.name().to_string(), sequence, result.first())
+            }
+            _ => return Err(EvalError::message(USAGE)),
+        };
+        let sequence = self.eval(sequence)?;
+        let mut seq = Self::seq_iter(&sequence).ok_or_else(|| {
+            EvalError::message("dolist requires a list, vector, string, hash table or iterator")
+        })?;
+
+        self.environment.push_scope(ScopeKind::Loop);
+        let value = self.dolist_loop(&name, seq.as_mut(), body, result);
+        self.environment.pop_scope();
+        value
+    }
+
+    fn dolist_loop(
+        &mut self,
+        name: &str,
+        seq: &mut dyn SeqIter,
+        body: &[Expr],
+        result: Option<&Expr>,
+    ) -> EvalResult {
+        while let Some(item) = seq.next_item() {
+            self.environment.set(name.to_string(), item);
+            match body.iter().try_for_each(|form| self.eval(form).map(drop)) {
+                Ok(()) | Err(EvalError::Continue) => {}
+                Err(EvalError::Break { value }) => return Ok(value),
+                Err(err) => return Err(err),
+            }
+        }
+        self.environment
+            .set(name.to_string(), Expr::new_list(vec![]));
+        match result {
+            Some(form) => self.eval(form),
+            None => Ok(Expr::new_list(vec![])),
+        }
+    }
+}
+
+// One clause of a for form
+enum ForClause<'a> {
+    In { name: String, collection: &'a Expr },
+    When(&'a Expr),
+}
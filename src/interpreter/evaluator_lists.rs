@@ -0,0 +1,255 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::evaluator_builtins::MAX_VECTOR_LENGTH;
+use crate::interpreter::iterator::SeqIter;
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+
+// List utilities: folds, indexed mapping, zipping, slicing and reshaping. Each takes any
+// sequence (see `Evaluator::seq_iter`) and returns a new list, leaving its arguments alone.
+impl Evaluator {
+    fn function_arg<'a>(args: &'a [Expr], name: &str, count: usize) -> Result<&'a Expr, EvalError> {
+        if args.len() != count {
+            return Err(EvalError::message(format!(
+                "{} requires exactly {} arguments",
+                name, count
+            )));
+        }
+        if !Self::is_callable(&args[0]) {
+            return Err(EvalError::message(format!(
+                "{} requires a function as first argument",
+                name
+            )));
+        }
+        Ok(&args[0])
+    }
+
+    // (foldl f init list): (f (f (f init a) b) c), from the left
+    pub fn builtin_foldl(&mut self, args: &[Expr]) -> EvalResult {
+        let func = Self::function_arg(args, "foldl", 3)?;
+        let mut seq = Self::seq_arg(&args[2], "foldl", "third")?;
+        let mut acc = args[1].clone();
+        while let Some(item) = seq.next_item() {
+            acc = self.call_callable(func, &[acc, item])?;
+        }
+        Ok(acc)
+    }
+
+    // (foldr f init list): (f a (f b (f c init))), from the right
+    pub fn builtin_foldr(&mut self, args: &[Expr]) -> EvalResult {
+        let func = Self::function_arg(args, "foldr", 3)?;
+        let list = Self::seq_items(&args[2], "foldr", "third")?;
+        let mut acc = args[1].clone();
+        for item in list.iter().rev() {
+            acc = self.call_callable(func, &[item.clone(), acc])?;
+        }
+        Ok(acc)
+    }
+
+    // (map-indexed f list): (f 0 a), (f 1 b), ...
+    pub fn builtin_map_indexed(&mut self, args: &[Expr]) -> EvalResult {
+        let func = Self::function_arg(args, "map-indexed", 2)?;
+        let mut seq = Self::seq_arg(&args[1], "map-indexed", "second")?;
+        let mut result = Vec::new();
+        while let Some(item) = seq.next_item() {
+            result.push(self.call_callable(func, &[Expr::Integer(result.len() as i64), item])?);
+        }
+        Ok(Expr::new_list(result))
+    }
+
+    // (zip (1 2 3) (a b c)) is ((1 a) (2 b) (3 c)), stopping at the shortest sequence
+    pub fn builtin_zip(&mut self, args: &[Expr]) -> EvalResult {
+        let mut seqs = args
+            .iter()
+            .map(|arg| {
+                Self::seq_iter(arg).ok_or_else(|| {
+                    EvalError::message(
+                        "zip requires list, vector, string, hash table or iterator arguments",
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut rows = Vec::new();
+        if seqs.is_empty() {
+            return Ok(Expr::new_list(rows));
+        }
+        while let Some(row) = seqs
+            .iter_mut()
+            .map(|seq| seq.next_item())
+            .collect::<Option<Vec<_>>>()
+        {
+            rows.push(Expr::new_list(row));
+        }
+        Ok(Expr::new_list(rows))
+    }
+
+    // (unzip ((1 a) (2 b))) is ((1 2) (a b)), undoing zip
+    pub fn builtin_unzip(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message("unzip requires exactly 1 argument"));
+        }
+        let rows = Self::seq_items(&args[0], "unzip", "its")?;
+        let rows = rows
+            .iter()
+            .map(|row| match row {
+                Expr::List(items) => Ok(&items[..]),
+                _ => Err(EvalError::message("unzip requires a list of lists")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(transpose(&rows))
+    }
+
+    // (iota count [start [step]]): count numbers from start (0), step (1) apart, as a list;
+    // range is the lazy version. Integers unless start or step is a float.
+    pub fn builtin_iota(&mut self, args: &[Expr]) -> EvalResult {
+        if args.is_empty() || args.len() > 3 {
+            return Err(EvalError::message("iota requires 1 to 3 arguments"));
+        }
+        let count = match &args[0] {
+            Expr::Integer(n) if *n >= 0 => *n,
+            _ => {
+                return Err(EvalError::message(
+                    "iota requires a non-negative integer count",
+                ))
+            }
+        };
+        if count > MAX_VECTOR_LENGTH as i64 {
+            return Err(EvalError::message(format!(
+                "iota: count {} is over the limit of {}",
+                count, MAX_VECTOR_LENGTH
+            )));
+        }
+        let start = args.get(1).cloned().unwrap_or(Expr::Integer(0));
+        let step = args.get(2).cloned().unwrap_or(Expr::Integer(1));
+        let items = match (&start, &step) {
+            (Expr::Integer(start), Expr::Integer(step)) => (0..count)
+                .map(|i| {
+                    step.checked_mul(i)
+                        .and_then(|offset| start.checked_add(offset))
+                        .map(Expr::Integer)
+                        .ok_or_else(|| EvalError::message("iota: integer overflow"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => {
+                let start = Self::to_number(&start)
+                    .map_err(|_| EvalError::message("iota requires a number as start"))?;
+                let step = Self::to_number(&step)
+                    .map_err(|_| EvalError::message("iota requires a number as step"))?;
+                (0..count)
+                    .map(|i| Expr::Float(start + step * i as f64))
+                    .collect()
+            }
+        };
+        Ok(Expr::new_list(items))
+    }
+
+    // (take n list): the first n elements, or all of them if there are fewer. Takes only
+    // those n from an iterator.
+    pub fn builtin_take(&mut self, args: &[Expr]) -> EvalResult {
+        let (count, mut seq) = Self::count_and_seq(args, "take")?;
+        let mut result = Vec::new();
+        while result.len() < count {
+            let Some(item) = seq.next_item() else {
+                break;
+            };
+            result.push(item);
+        }
+        Ok(Expr::new_list(result))
+    }
+
+    // (drop n list): what's left after the first n elements
+    pub fn builtin_drop(&mut self, args: &[Expr]) -> EvalResult {
+        let (count, mut seq) = Self::count_and_seq(args, "drop")?;
+        for _ in 0..count {
+            if seq.next_item().is_none() {
+                break;
+            }
+        }
+        let mut result = Vec::new();
+        Self::drain_seq(seq.as_mut(), &mut result, "drop")?;
+        Ok(Expr::new_list(result))
+    }
+
+    fn count_and_seq(args: &[Expr], name: &str) -> Result<(usize, Box<dyn SeqIter>), EvalError> {
+        if args.len() != 2 {
+            return Err(EvalError::message(format!(
+                "{} requires exactly 2 arguments",
+                name
+            )));
+        }
+        let count = match &args[0] {
+            Expr::Integer(n) if *n >= 0 => *n as usize,
+            _ => {
+                return Err(EvalError::message(format!(
+                    "{} requires a non-negative integer count",
+                    name
+                )))
+            }
+        };
+        Ok((count, Self::seq_arg(&args[1], name, "second")?))
+    }
+
+    // (partition pred list): (satisfying rest), each in the original order
+    pub fn builtin_partition(&mut self, args: &[Expr]) -> EvalResult {
+        let pred = Self::function_arg(args, "partition", 2)?;
+        let mut seq = Self::seq_arg(&args[1], "partition", "second")?;
+        let (mut matching, mut rest) = (Vec::new(), Vec::new());
+        while let Some(item) = seq.next_item() {
+            if Self::is_truthy(&self.call_callable(pred, std::slice::from_ref(&item))?) {
+                matching.push(item);
+            } else {
+                rest.push(item);
+            }
+        }
+        Ok(Expr::new_list(vec![
+            Expr::new_list(matching),
+            Expr::new_list(rest),
+        ]))
+    }
+
+    // (flatten tree): the atoms of nested lists in order; empty lists vanish. Only the
+    // outermost sequence may be something other than a list.
+    pub fn builtin_flatten(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message("flatten requires exactly 1 argument"));
+        }
+        let list = Self::seq_items(&args[0], "flatten", "its")?;
+        let mut result = Vec::new();
+        // Explicit stack of the lists being walked, so deep nesting can't overflow
+        let mut stack = vec![list.iter()];
+        while let Some(items) = stack.last_mut() {
+            match items.next() {
+                Some(Expr::List(inner)) => stack.push(inner.iter()),
+                Some(atom) => result.push(atom.clone()),
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+        Ok(Expr::new_list(result))
+    }
+
+    // (last list): the final element, or nil for an empty list
+    pub fn builtin_last(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message("last requires exactly 1 argument"));
+        }
+        let mut seq = Self::seq_arg(&args[0], "last", "its")?;
+        let mut last = Expr::new_list(vec![]);
+        while let Some(item) = seq.next_item() {
+            last = item;
+        }
+        Ok(last)
+    }
+}
+
+// Row i of the result holds element i of each list, up to the shortest list's length
+fn transpose(lists: &[&[Expr]]) -> Expr {
+    if lists.is_empty() {
+        return Expr::new_list(vec![]);
+    }
+    let len = lists.iter().map(|list| list.len()).min().unwrap_or(0);
+    Expr::new_list(
+        (0..len)
+            .map(|i| Expr::new_list(lists.iter().map(|list| list[i].clone()).collect()))
+            .collect(),
+    )
+}
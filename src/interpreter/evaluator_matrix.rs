@@ -0,0 +1,195 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::evaluator_builtins::MAX_VECTOR_LENGTH;
+use crate::interpreter::matrix::Matrix;
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Matrix builtins. Elements are always floats; integer arguments are converted on the way
+// in, and rows and columns are counted from 0.
+impl Evaluator {
+    fn matrix_arg<'a>(arg: &'a Expr, name: &str) -> Result<&'a Rc<RefCell<Matrix>>, EvalError> {
+        match arg {
+            Expr::Matrix(matrix) => Ok(matrix),
+            _ => Err(EvalError::message(format!("{} requires a matrix", name))),
+        }
+    }
+
+    fn index_arg(arg: &Expr, name: &str) -> Result<usize, EvalError> {
+        match arg {
+            Expr::Integer(n) if *n >= 0 => Ok(*n as usize),
+            _ => Err(EvalError::message(format!(
+                "{} requires non-negative integer indices",
+                name
+            ))),
+        }
+    }
+
+    fn element_arg(arg: &Expr, name: &str) -> Result<f64, EvalError> {
+        Self::to_number(arg)
+            .map_err(|_| EvalError::message(format!("{} requires numeric elements", name)))
+    }
+
+    // (make-matrix rows cols [fill]): every element is fill, 0.0 by default
+    pub fn builtin_make_matrix(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() < 2 || args.len() > 3 {
+            return Err(EvalError::message("make-matrix requires 2 or 3 arguments"));
+        }
+        let rows = Self::index_arg(&args[0], "make-matrix")?;
+        let cols = Self::index_arg(&args[1], "make-matrix")?;
+        let fill = match args.get(2) {
+            Some(fill) => Self::element_arg(fill, "make-matrix")?,
+            None => 0.0,
+        };
+        if rows.saturating_mul(cols) > MAX_VECTOR_LENGTH {
+            return Err(EvalError::message(format!(
+                "make-matrix: {}x{} is over the limit of {} elements",
+                rows, cols, MAX_VECTOR_LENGTH
+            )));
+        }
+        Ok(Expr::new_matrix(Matrix::new(rows, cols, fill)))
+    }
+
+    // (list->matrix rows): a matrix from a list of equally long lists of numbers
+    pub fn builtin_list_to_matrix(&mut self, args: &[Expr]) -> EvalResult {
+        let [Expr::List(rows)] = args else {
+            return Err(EvalError::message("list->matrix requires a list of rows"));
+        };
+        let rows = rows
+            .iter()
+            .map(|row| match row {
+                Expr::List(items) => items
+                    .iter()
+                    .map(|item| Self::element_arg(item, "list->matrix"))
+                    .collect(),
+                _ => Err(EvalError::message(
+                    "list->matrix requires each row to be a list",
+                )),
+            })
+            .collect::<Result<Vec<Vec<f64>>, _>>()?;
+        Matrix::from_rows(&rows)
+            .map(Expr::new_matrix)
+            .ok_or_else(|| EvalError::message("list->matrix requires rows of equal length"))
+    }
+
+    // (matrix->list m): the rows as lists
+    pub fn builtin_matrix_to_list(&mut self, args: &[Expr]) -> EvalResult {
+        let [matrix] = args else {
+            return Err(EvalError::message(
+                "matrix->list requires exactly 1 argument",
+            ));
+        };
+        let matrix = Self::matrix_arg(matrix, "matrix->list")?.borrow();
+        Ok(Expr::new_list(
+            (0..matrix.rows)
+                .map(|row| {
+                    Expr::new_list(matrix.row(row).iter().copied().map(Expr::Float).collect())
+                })
+                .collect(),
+        ))
+    }
+
+    pub fn builtin_matrix_rows(&mut self, args: &[Expr]) -> EvalResult {
+        let [matrix] = args else {
+            return Err(EvalError::message(
+                "matrix-rows requires exactly 1 argument",
+            ));
+        };
+        let rows = Self::matrix_arg(matrix, "matrix-rows")?.borrow().rows;
+        Ok(Expr::Integer(rows as i64))
+    }
+
+    pub fn builtin_matrix_cols(&mut self, args: &[Expr]) -> EvalResult {
+        let [matrix] = args else {
+            return Err(EvalError::message(
+                "matrix-cols requires exactly 1 argument",
+            ));
+        };
+        let cols = Self::matrix_arg(matrix, "matrix-cols")?.borrow().cols;
+        Ok(Expr::Integer(cols as i64))
+    }
+
+    // (matrix-ref m row col)
+    pub fn builtin_matrix_ref(&mut self, args: &[Expr]) -> EvalResult {
+        let [matrix, row, col] = args else {
+            return Err(EvalError::message(
+                "matrix-ref requires exactly 3 arguments",
+            ));
+        };
+        let matrix = Self::matrix_arg(matrix, "matrix-ref")?.borrow();
+        let (row, col) = (
+            Self::index_arg(row, "matrix-ref")?,
+            Self::index_arg(col, "matrix-ref")?,
+        );
+        matrix.get(row, col).map(Expr::Float).ok_or_else(|| {
+            EvalError::message(format!(
+                "matrix-ref: ({} {}) out of bounds for a {} matrix",
+                row,
+                col,
+                matrix.shape()
+            ))
+        })
+    }
+
+    // (matrix-set! m row col value): updates m in place and returns it
+    pub fn builtin_matrix_set(&mut self, args: &[Expr]) -> EvalResult {
+        let [matrix_expr, row, col, value] = args else {
+            return Err(EvalError::message(
+                "matrix-set! requires exactly 4 arguments",
+            ));
+        };
+        let mut matrix = Self::matrix_arg(matrix_expr, "matrix-set!")?.borrow_mut();
+        let (row, col) = (
+            Self::index_arg(row, "matrix-set!")?,
+            Self::index_arg(col, "matrix-set!")?,
+        );
+        let value = Self::element_arg(value, "matrix-set!")?;
+        if !matrix.set(row, col, value) {
+            return Err(EvalError::message(format!(
+                "matrix-set!: ({} {}) out of bounds for a {} matrix",
+                row,
+                col,
+                matrix.shape()
+            )));
+        }
+        Ok(matrix_expr.clone())
+    }
+
+    // (matrix-multiply a b): a new matrix, the product a×b
+    pub fn builtin_matrix_multiply(&mut self, args: &[Expr]) -> EvalResult {
+        let [a, b] = args else {
+            return Err(EvalError::message(
+                "matrix-multiply requires exactly 2 arguments",
+            ));
+        };
+        let a = Self::matrix_arg(a, "matrix-multiply")?.borrow();
+        let b = Self::matrix_arg(b, "matrix-multiply")?.borrow();
+        a.multiply(&b).map(Expr::new_matrix).ok_or_else(|| {
+            EvalError::message(format!(
+                "matrix-multiply: cannot multiply a {} matrix by a {} matrix",
+                a.shape(),
+                b.shape()
+            ))
+        })
+    }
+
+    // (matrix-transpose m): a new matrix with rows and columns swapped
+    pub fn builtin_matrix_transpose(&mut self, args: &[Expr]) -> EvalResult {
+        let [matrix] = args else {
+            return Err(EvalError::message(
+                "matrix-transpose requires exactly 1 argument",
+            ));
+        };
+        let transposed = Self::matrix_arg(matrix, "matrix-transpose")?
+            .borrow()
+            .transpose();
+        Ok(Expr::new_matrix(transposed))
+    }
+
+    pub fn builtin_matrixp(&mut self, args: &[Expr]) -> EvalResult {
+        let [value] = args else {
+            return Err(EvalError::message("matrixp requires exactly 1 argument"));
+        };
+        Ok(Evaluator::bool_to_expr(matches!(value, Expr::Matrix(_))))
+    }
+}
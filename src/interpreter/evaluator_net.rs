@@ -0,0 +1,226 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::net::{self, HttpResponse, Socket, TcpConnection};
+use crate::interpreter::ordered_map::OrderedMap;
+use crate::interpreter::types::{EvalError, EvalResult, Expr, HashKey};
+use std::net::{TcpListener, TcpStream};
+use std::rc::Rc;
+
+// Networking builtins. Sockets are numbers into `Evaluator::sockets`; receiving and
+// accepting block until the peer answers, though an interrupt stops a receive.
+impl Evaluator {
+    fn check_network(&self, name: &str) -> Result<(), EvalError> {
+        if self.allow_network {
+            Ok(())
+        } else {
+            Err(EvalError::message(format!(
+                "{} is disabled in this evaluator",
+                name
+            )))
+        }
+    }
+
+    // A failed network call's error, or the interrupt that made it give up
+    fn network_error(&self, name: &str, err: impl std::fmt::Display) -> EvalError {
+        if self.interrupt_requested() {
+            EvalError::Interrupted
+        } else {
+            EvalError::message(format!("{}: {}", name, err))
+        }
+    }
+
+    fn socket_id(args: &[Expr], name: &str, count: usize) -> Result<i64, EvalError> {
+        if args.len() != count {
+            return Err(EvalError::message(format!(
+                "{} requires exactly {} argument{}",
+                name,
+                count,
+                if count == 1 { "" } else { "s" }
+            )));
+        }
+        match &args[0] {
+            Expr::Integer(id) => Ok(*id),
+            _ => Err(EvalError::message(format!(
+                "{} requires a socket number",
+                name
+            ))),
+        }
+    }
+
+    fn connection(&self, id: i64, name: &str) -> Result<Rc<TcpConnection>, EvalError> {
+        match self.sockets.borrow().get(id) {
+            Some(Socket::Connection(connection)) => Ok(Rc::clone(connection)),
+            _ => Err(EvalError::message(format!(
+                "{}: {} is not an open connection",
+                name, id
+            ))),
+        }
+    }
+
+    fn add_connection(&mut self, stream: TcpStream, name: &str) -> EvalResult {
+        let connection = TcpConnection::new(stream)
+            .map_err(|err| EvalError::message(format!("{}: {}", name, err)))?;
+        let id = self
+            .sockets
+            .borrow_mut()
+            .add(Socket::Connection(Rc::new(connection)));
+        Ok(Expr::Integer(id))
+    }
+
+    fn port_arg(arg: &Expr, name: &str) -> Result<u16, EvalError> {
+        match arg {
+            Expr::Integer(port) => u16::try_from(*port)
+                .map_err(|_| EvalError::message(format!("{}: port out of range", name))),
+            _ => Err(EvalError::message(format!(
+                "{} requires an integer port",
+                name
+            ))),
+        }
+    }
+
+    // (tcp-connect host port): a connection number
+    pub fn builtin_tcp_connect(&mut self, args: &[Expr]) -> EvalResult {
+        self.check_network("tcp-connect")?;
+        let [Expr::String(host), port] = args else {
+            return Err(EvalError::message(
+                "tcp-connect requires a host string and a port",
+            ));
+        };
+        let port = Self::port_arg(port, "tcp-connect")?;
+        let stream = TcpStream::connect((host.as_str(), port)).map_err(|err| {
+            EvalError::message(format!("tcp-connect: {}:{}: {}", host, port, err))
+        })?;
+        self.add_connection(stream, "tcp-connect")
+    }
+
+    // (tcp-send conn text): writes text as is, so lines need their own newline
+    pub fn builtin_tcp_send(&mut self, args: &[Expr]) -> EvalResult {
+        let id = Self::socket_id(args, "tcp-send", 2)?;
+        let Expr::String(text) = &args[1] else {
+            return Err(EvalError::message("tcp-send requires a string to send"));
+        };
+        self.connection(id, "tcp-send")?
+            .send(text)
+            .map_err(|err| EvalError::message(format!("tcp-send: {}", err)))?;
+        Ok(args[1].clone())
+    }
+
+    // (tcp-receive conn): the next line without its line ending, or nil once the peer
+    // has closed the connection
+    pub fn builtin_tcp_receive(&mut self, args: &[Expr]) -> EvalResult {
+        let id = Self::socket_id(args, "tcp-receive", 1)?;
+        let line = self
+            .connection(id, "tcp-receive")?
+            .receive_line(&|| self.interrupt_requested())
+            .map_err(|err| self.network_error("tcp-receive", err))?;
+        Ok(match line {
+            Some(line) => {
+                let line = line.strip_suffix('\n').unwrap_or(&line);
+                Expr::String(line.strip_suffix('\r').unwrap_or(line).to_string())
+            }
+            None => Expr::new_list(vec![]),
+        })
+    }
+
+    // (tcp-listen port) listens on every interface; port 0 picks a free one, which
+    // tcp-port reports
+    pub fn builtin_tcp_listen(&mut self, args: &[Expr]) -> EvalResult {
+        self.check_network("tcp-listen")?;
+        if args.len() != 1 {
+            return Err(EvalError::message("tcp-listen requires exactly 1 argument"));
+        }
+        let port = Self::port_arg(&args[0], "tcp-listen")?;
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|err| EvalError::message(format!("tcp-listen: {}", err)))?;
+        let id = self.sockets.borrow_mut().add(Socket::Listener(listener));
+        Ok(Expr::Integer(id))
+    }
+
+    // (tcp-accept listener): waits for the next client and returns its connection
+    pub fn builtin_tcp_accept(&mut self, args: &[Expr]) -> EvalResult {
+        let id = Self::socket_id(args, "tcp-accept", 1)?;
+        let accepted = match self.sockets.borrow().get(id) {
+            Some(Socket::Listener(listener)) => listener.accept(),
+            _ => {
+                return Err(EvalError::message(format!(
+                    "tcp-accept: {} is not a listener",
+                    id
+                )))
+            }
+        };
+        let (stream, _) =
+            accepted.map_err(|err| EvalError::message(format!("tcp-accept: {}", err)))?;
+        self.add_connection(stream, "tcp-accept")
+    }
+
+    // (tcp-port socket): the local port of a listener or connection
+    pub fn builtin_tcp_port(&mut self, args: &[Expr]) -> EvalResult {
+        let id = Self::socket_id(args, "tcp-port", 1)?;
+        let port = match self.sockets.borrow().get(id) {
+            Some(Socket::Listener(listener)) => listener.local_addr().map(|addr| addr.port()),
+            Some(Socket::Connection(connection)) => connection.local_port(),
+            None => {
+                return Err(EvalError::message(format!(
+                    "tcp-port: {} is not an open socket",
+                    id
+                )))
+            }
+        };
+        port.map(|port| Expr::Integer(port as i64))
+            .map_err(|err| EvalError::message(format!("tcp-port: {}", err)))
+    }
+
+    // (tcp-close socket): closes a connection or stops listening; true if it was open
+    pub fn builtin_tcp_close(&mut self, args: &[Expr]) -> EvalResult {
+        let id = Self::socket_id(args, "tcp-close", 1)?;
+        let removed = self.sockets.borrow_mut().remove(id);
+        if let Some(Socket::Connection(connection)) = &removed {
+            connection.close();
+        }
+        Ok(Evaluator::bool_to_expr(removed.is_some()))
+    }
+
+    fn response_to_expr(response: HttpResponse) -> Expr {
+        let mut headers = OrderedMap::new();
+        for (name, value) in response.headers {
+            headers.insert(HashKey::String(name), Expr::String(value));
+        }
+        Expr::new_list(vec![
+            Expr::Integer(response.status as i64),
+            Expr::new_hash_table(headers),
+            Expr::String(response.body),
+        ])
+    }
+
+    // (http-get url): (status headers body), with the headers in a hash table keyed by
+    // lowercased name. Only plain http:// URLs are supported.
+    pub fn builtin_http_get(&mut self, args: &[Expr]) -> EvalResult {
+        self.check_network("http-get")?;
+        let [Expr::String(url)] = args else {
+            return Err(EvalError::message("http-get requires a URL string"));
+        };
+        net::http_fetch("GET", url, None, &|| self.interrupt_requested())
+            .map(Self::response_to_expr)
+            .map_err(|err| self.network_error("http-get", err))
+    }
+
+    // (http-post url body [content-type]): like http-get; the content type defaults to
+    // text/plain
+    pub fn builtin_http_post(&mut self, args: &[Expr]) -> EvalResult {
+        self.check_network("http-post")?;
+        let (url, body, content_type) =
+            match args {
+                [Expr::String(url), Expr::String(body)] => (url, body, "text/plain"),
+                [Expr::String(url), Expr::String(body), Expr::String(content_type)] => {
+                    (url, body, content_type.as_str())
+                }
+                _ => return Err(EvalError::message(
+                    "http-post requires a URL, a body and optionally a content type, all strings",
+                )),
+            };
+        net::http_fetch("POST", url, Some((content_type, body)), &|| {
+            self.interrupt_requested()
+        })
+        .map(Self::response_to_expr)
+        .map_err(|err| self.network_error("http-post", err))
+    }
+}
@@ -0,0 +1,121 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+use std::process::{Command, Stdio};
+
+impl Evaluator {
+    fn string_args<'a>(args: &'a [Expr], name: &str) -> Result<Vec<&'a str>, EvalError> {
+        args.iter()
+            .map(|arg| match arg {
+                Expr::String(text) => Ok(text.as_str()),
+                _ => Err(EvalError::message(format!(
+                    "{} requires string arguments",
+                    name
+                ))),
+            })
+            .collect()
+    }
+
+    // (getenv name): the variable's value, or nil when it isn't set
+    pub fn builtin_getenv(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 1 {
+            return Err(EvalError::message("getenv requires exactly 1 argument"));
+        }
+        let name = Self::string_args(args, "getenv")?[0];
+        Ok(match std::env::var(name) {
+            Ok(value) => Expr::String(value),
+            Err(_) => Expr::new_list(vec![]),
+        })
+    }
+
+    // (setenv name value) sets a variable for this process and the programs it runs;
+    // (setenv name nil) removes it. Returns the value.
+    pub fn builtin_setenv(&mut self, args: &[Expr]) -> EvalResult {
+        if args.len() != 2 {
+            return Err(EvalError::message("setenv requires exactly 2 arguments"));
+        }
+        let name = Self::string_args(&args[..1], "setenv")?[0];
+        if name.is_empty() || name.contains(['=', '\0']) {
+            return Err(EvalError::message(format!(
+                "setenv: invalid variable name {:?}",
+                name
+            )));
+        }
+        match &args[1] {
+            Expr::String(value) if value.contains('\0') => {
+                Err(EvalError::message("setenv: value contains a NUL character"))
+            }
+            Expr::String(value) => {
+                std::env::set_var(name, value);
+                Ok(args[1].clone())
+            }
+            value if !Evaluator::is_truthy(value) => {
+                std::env::remove_var(name);
+                Ok(Expr::new_list(vec![]))
+            }
+            _ => Err(EvalError::message(
+                "setenv requires a string or nil as the value",
+            )),
+        }
+    }
+
+    // (command-line): the arguments given after `--` when zeus was started, as strings
+    pub fn builtin_command_line(&mut self, args: &[Expr]) -> EvalResult {
+        if !args.is_empty() {
+            return Err(EvalError::message("command-line takes no arguments"));
+        }
+        Ok(Expr::new_list(
+            self.command_line
+                .iter()
+                .cloned()
+                .map(Expr::String)
+                .collect(),
+        ))
+    }
+
+    // (exit) or (exit code): unwinds like an error, running unwind-protect cleanups on the
+    // way out. `zeus run` and the terminal REPL then end the process with the code; the
+    // IDE reports it and keeps going.
+    pub fn builtin_exit(&mut self, args: &[Expr]) -> EvalResult {
+        let code = match args {
+            [] => 0,
+            [Expr::Integer(code)] => {
+                i32::try_from(*code).map_err(|_| EvalError::message("exit code out of range"))?
+            }
+            [_] => return Err(EvalError::message("exit requires an integer code")),
+            _ => return Err(EvalError::message("exit takes at most 1 argument")),
+        };
+        Err(EvalError::Exit(code))
+    }
+
+    // (run-process cmd args...): runs a program to completion with no input and returns
+    // (stdout stderr status), where status is the exit code or nil if a signal ended it.
+    // The command is run directly, not through a shell.
+    pub fn builtin_run_process(&mut self, args: &[Expr]) -> EvalResult {
+        if !self.allow_processes {
+            return Err(EvalError::message(
+                "run-process is disabled in this evaluator",
+            ));
+        }
+        if args.is_empty() {
+            return Err(EvalError::message(
+                "run-process requires at least 1 argument",
+            ));
+        }
+        let strings = Self::string_args(args, "run-process")?;
+        let output = Command::new(strings[0])
+            .args(&strings[1..])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| {
+                EvalError::message(format!("run-process: cannot run {}: {}", strings[0], err))
+            })?;
+        Ok(Expr::new_list(vec![
+            Expr::String(String::from_utf8_lossy(&output.stdout).into_owned()),
+            Expr::String(String::from_utf8_lossy(&output.stderr).into_owned()),
+            match output.status.code() {
+                Some(code) => Expr::Integer(code as i64),
+                None => Expr::new_list(vec![]),
+            },
+        ]))
+    }
+}
@@ -0,0 +1,136 @@
+use crate::interpreter::environment::ScopeKind;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::keyword_args::parse_keyword_args;
+use crate::interpreter::ports::{Direction, FilePort};
+use crate::interpreter::printer::{display_string, write_string};
+use crate::interpreter::types::{EvalError, EvalResult, Expr, SymbolData};
+use std::rc::Rc;
+
+// File ports: with-open-file opens one for its body and closes it however the body exits,
+// and read-line and write-line take one as an optional last argument in place of the
+// evaluator's input and output ports.
+impl Evaluator {
+    // (with-open-file (f path [:direction :input|:output] [:if-exists :supersede|:append])
+    // body...): the body's value, with f bound to a port on the file. The path and options
+    // are evaluated; output replaces the file unless :if-exists is :append.
+    pub fn eval_with_open_file(&mut self, list: &[Expr]) -> EvalResult {
+        const USAGE: &str = "with-open-file requires (variable path options...) and a body";
+        let [_, Expr::List(spec), body @ ..] = list else {
+            return Err(EvalError::message(USAGE));
+        };
+        let (name, spec_args) = match spec.split_first() {
+            Some((Expr::Symbol(name), rest)) if !name.is_keyword() && !rest.is_empty() => {
+                (name.name().to_string(), rest)
+            }
+            _ => return Err(EvalError::message(USAGE)),
+        };
+        let spec_args = spec_args
+            .iter()
+            .map(|arg| self.eval(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (path, direction) = Self::open_options(&spec_args)?;
+        let port = FilePort::open(path, direction)
+            .map(Rc::new)
+            .map_err(|err| EvalError::message(format!("with-open-file: {}: {}", path, err)))?;
+
+        self.environment.push_scope(ScopeKind::Let);
+        self.environment.set(name, Expr::Port(Rc::clone(&port)));
+        let result = self.with_cleanup(
+            |evaluator| {
+                let mut value = Expr::new_list(vec![]);
+                for form in body {
+                    value = evaluator.eval(form)?;
+                }
+                Ok(value)
+            },
+            |_| {
+                port.close()
+                    .map(|()| Expr::new_list(vec![]))
+                    .map_err(|err| EvalError::message(format!("with-open-file: {}", err)))
+            },
+        );
+        self.environment.pop_scope();
+        result
+    }
+
+    fn open_options(args: &[Expr]) -> Result<(&str, Direction), EvalError> {
+        let (positional, options) =
+            parse_keyword_args(args, "with-open-file", 1..=1, &["direction", "if-exists"])?;
+        let Expr::String(path) = &positional[0] else {
+            return Err(EvalError::message(
+                "with-open-file requires a string as path",
+            ));
+        };
+        // Whether the option is `other` rather than its default
+        let choice = |option: &str, default: &str, other: &str| match options.get(option) {
+            None => Ok(false),
+            Some(Expr::Symbol(SymbolData::Keyword(value))) if value.as_str() == default => {
+                Ok(false)
+            }
+            Some(Expr::Symbol(SymbolData::Keyword(value))) if value.as_str() == other => Ok(true),
+            Some(value) => Err(EvalError::message(format!(
+                "with-open-file: :{} must be :{} or :{}, got {}",
+                option,
+                default,
+                other,
+                write_string(value)
+            ))),
+        };
+        let output = choice("direction", "input", "output")?;
+        let append = choice("if-exists", "supersede", "append")?;
+        if append && !output {
+            return Err(EvalError::message(
+                "with-open-file: :if-exists needs :direction :output",
+            ));
+        }
+        let direction = if output {
+            Direction::Output { append }
+        } else {
+            Direction::Input
+        };
+        Ok((path, direction))
+    }
+
+    // (read-line port): the next line of the file without its line ending, or nil at the end
+    pub fn read_line_from(&mut self, port: &Expr) -> EvalResult {
+        let line = Self::file_port_arg(port, "read-line")?
+            .read_line()
+            .map_err(|err| EvalError::message(format!("read-line: {}", err)))?;
+        Ok(match line {
+            Some(line) => {
+                let line = line.strip_suffix('\n').unwrap_or(&line);
+                Expr::String(line.strip_suffix('\r').unwrap_or(line).to_string())
+            }
+            None => Expr::new_list(vec![]),
+        })
+    }
+
+    // (write-line value [port]): value as display prints it and a newline, to the port or
+    // else the evaluator's output. Returns value.
+    pub fn builtin_write_line(&mut self, args: &[Expr]) -> EvalResult {
+        let (value, port) = match args {
+            [value] => (value, None),
+            [value, port] => (value, Some(Self::file_port_arg(port, "write-line")?)),
+            _ => {
+                return Err(EvalError::message(
+                    "write-line requires a value and an optional port",
+                ))
+            }
+        };
+        let line = format!("{}\n", display_string(value));
+        match port {
+            Some(port) => port
+                .write_text(&line)
+                .map_err(|err| EvalError::message(format!("write-line: {}", err)))?,
+            None => self.output.write(&line),
+        }
+        Ok(value.clone())
+    }
+
+    fn file_port_arg<'a>(arg: &'a Expr, name: &str) -> Result<&'a FilePort, EvalError> {
+        match arg {
+            Expr::Port(port) => Ok(port),
+            _ => Err(EvalError::message(format!("{} requires a port", name))),
+        }
+    }
+}
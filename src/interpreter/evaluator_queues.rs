@@ -0,0 +1,286 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{EvalError, EvalResult, Expr, Heap};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+// Queues and stacks, both a shared VecDeque so every operation is O(1) and updates the
+// value in place. A queue takes from the front and adds at the back; a stack adds and
+// takes at the back. Heaps are priority queues ordered by a Lisp comparison function,
+// with O(log n) pushes and pops.
+impl Evaluator {
+    fn queue_arg<'a>(
+        arg: &'a Expr,
+        name: &str,
+    ) -> Result<&'a Rc<RefCell<VecDeque<Expr>>>, EvalError> {
+        match arg {
+            Expr::Queue(items) => Ok(items),
+            _ => Err(EvalError::message(format!("{} requires a queue", name))),
+        }
+    }
+
+    fn stack_arg<'a>(
+        arg: &'a Expr,
+        name: &str,
+    ) -> Result<&'a Rc<RefCell<VecDeque<Expr>>>, EvalError> {
+        match arg {
+            Expr::Stack(items) => Ok(items),
+            _ => Err(EvalError::message(format!("{} requires a stack", name))),
+        }
+    }
+
+    // (make-queue item...): the items in order, the first at the front
+    pub fn builtin_make_queue(&mut self, args: &[Expr]) -> EvalResult {
+        Ok(Expr::new_queue(args.iter().cloned().collect()))
+    }
+
+    // (enqueue q item): adds item at the back and returns q
+    pub fn builtin_enqueue(&mut self, args: &[Expr]) -> EvalResult {
+        let [queue, item] = args else {
+            return Err(EvalError::message("enqueue requires exactly 2 arguments"));
+        };
+        Self::queue_arg(queue, "enqueue")?
+            .borrow_mut()
+            .push_back(item.clone());
+        Ok(queue.clone())
+    }
+
+    // (dequeue q): removes and returns the front item
+    pub fn builtin_dequeue(&mut self, args: &[Expr]) -> EvalResult {
+        let [queue] = args else {
+            return Err(EvalError::message("dequeue requires exactly 1 argument"));
+        };
+        Self::queue_arg(queue, "dequeue")?
+            .borrow_mut()
+            .pop_front()
+            .ok_or_else(|| EvalError::message("dequeue: queue is empty"))
+    }
+
+    // (queue-front q): the front item, left in place
+    pub fn builtin_queue_front(&mut self, args: &[Expr]) -> EvalResult {
+        let [queue] = args else {
+            return Err(EvalError::message(
+                "queue-front requires exactly 1 argument",
+            ));
+        };
+        Self::queue_arg(queue, "queue-front")?
+            .borrow()
+            .front()
+            .cloned()
+            .ok_or_else(|| EvalError::message("queue-front: queue is empty"))
+    }
+
+    // (make-stack item...): the items pushed in order, so the last is on top
+    pub fn builtin_make_stack(&mut self, args: &[Expr]) -> EvalResult {
+        Ok(Expr::new_stack(args.iter().cloned().collect()))
+    }
+
+    // (push! s item): puts item on top and returns s
+    pub fn builtin_push(&mut self, args: &[Expr]) -> EvalResult {
+        let [stack, item] = args else {
+            return Err(EvalError::message("push! requires exactly 2 arguments"));
+        };
+        Self::stack_arg(stack, "push!")?
+            .borrow_mut()
+            .push_back(item.clone());
+        Ok(stack.clone())
+    }
+
+    // (pop! s): removes and returns the top item
+    pub fn builtin_pop(&mut self, args: &[Expr]) -> EvalResult {
+        let [stack] = args else {
+            return Err(EvalError::message("pop! requires exactly 1 argument"));
+        };
+        Self::stack_arg(stack, "pop!")?
+            .borrow_mut()
+            .pop_back()
+            .ok_or_else(|| EvalError::message("pop!: stack is empty"))
+    }
+
+    // (stack-top s): the top item, left in place
+    pub fn builtin_stack_top(&mut self, args: &[Expr]) -> EvalResult {
+        let [stack] = args else {
+            return Err(EvalError::message("stack-top requires exactly 1 argument"));
+        };
+        Self::stack_arg(stack, "stack-top")?
+            .borrow()
+            .back()
+            .cloned()
+            .ok_or_else(|| EvalError::message("stack-top: stack is empty"))
+    }
+
+    pub fn builtin_queuep(&mut self, args: &[Expr]) -> EvalResult {
+        let [value] = args else {
+            return Err(EvalError::message("queuep requires exactly 1 argument"));
+        };
+        Ok(Evaluator::bool_to_expr(matches!(value, Expr::Queue(_))))
+    }
+
+    pub fn builtin_stackp(&mut self, args: &[Expr]) -> EvalResult {
+        let [value] = args else {
+            return Err(EvalError::message("stackp requires exactly 1 argument"));
+        };
+        Ok(Evaluator::bool_to_expr(matches!(value, Expr::Stack(_))))
+    }
+
+    fn heap_arg<'a>(arg: &'a Expr, name: &str) -> Result<&'a Rc<RefCell<Heap>>, EvalError> {
+        match arg {
+            Expr::Heap(heap) => Ok(heap),
+            _ => Err(EvalError::message(format!("{} requires a heap", name))),
+        }
+    }
+
+    // Whether `a` comes out of the heap before `b`
+    fn heap_before(&mut self, compare: &Expr, a: &Expr, b: &Expr) -> Result<bool, EvalError> {
+        let result = self.call_callable(compare, &[a.clone(), b.clone()])?;
+        Ok(Self::is_truthy(&result))
+    }
+
+    // Finds where `item` settles on its way up from the end before moving anything, so an
+    // error from `compare` leaves `items` as it was
+    fn heap_insert(
+        &mut self,
+        compare: &Expr,
+        items: &mut Vec<Expr>,
+        item: Expr,
+    ) -> Result<(), EvalError> {
+        let mut target = items.len();
+        while target > 0 {
+            let parent = (target - 1) / 2;
+            if !self.heap_before(compare, &item, &items[parent])? {
+                break;
+            }
+            target = parent;
+        }
+        items.push(item);
+        let mut index = items.len() - 1;
+        while index > target {
+            let parent = (index - 1) / 2;
+            items.swap(index, parent);
+            index = parent;
+        }
+        Ok(())
+    }
+
+    // Removes the first item, sifting the last one down into its place. As with
+    // heap_insert, every comparison happens before anything moves.
+    fn heap_remove_first(
+        &mut self,
+        compare: &Expr,
+        items: &mut Vec<Expr>,
+    ) -> Result<Option<Expr>, EvalError> {
+        if items.len() <= 1 {
+            return Ok(items.pop());
+        }
+        let last = items.len() - 1;
+        let mut path = Vec::new();
+        let mut index = 0;
+        loop {
+            let left = 2 * index + 1;
+            if left >= last {
+                break;
+            }
+            let (right, mut child) = (left + 1, left);
+            if right < last && self.heap_before(compare, &items[right], &items[left])? {
+                child = right;
+            }
+            if !self.heap_before(compare, &items[child], &items[last])? {
+                break;
+            }
+            path.push(child);
+            index = child;
+        }
+
+        let moved = items.pop().unwrap();
+        let first = std::mem::replace(&mut items[0], moved);
+        for child in path {
+            items.swap((child - 1) / 2, child);
+        }
+        Ok(Some(first))
+    }
+
+    // Runs `operation` on the heap's items with them taken out of the heap, so `compare`
+    // can't observe or change them midway (it sees an empty heap)
+    fn with_heap_items<T>(
+        &mut self,
+        heap: &Rc<RefCell<Heap>>,
+        operation: impl FnOnce(&mut Self, &Expr, &mut Vec<Expr>) -> Result<T, EvalError>,
+    ) -> Result<T, EvalError> {
+        let (compare, mut items) = {
+            let mut heap = heap.borrow_mut();
+            (heap.compare.clone(), std::mem::take(&mut heap.items))
+        };
+        let result = operation(self, &compare, &mut items);
+        heap.borrow_mut().items = items;
+        result
+    }
+
+    // (make-heap compare item...): a heap that yields first whichever item `compare` says
+    // comes before the others, so (make-heap <) pops the smallest
+    pub fn builtin_make_heap(&mut self, args: &[Expr]) -> EvalResult {
+        let Some((compare, initial)) = args.split_first() else {
+            return Err(EvalError::message(
+                "make-heap requires a comparison function",
+            ));
+        };
+        if !Self::is_callable(compare) {
+            return Err(EvalError::message(
+                "make-heap requires a comparison function",
+            ));
+        }
+        let mut items = Vec::with_capacity(initial.len());
+        for item in initial {
+            self.heap_insert(compare, &mut items, item.clone())?;
+        }
+        Ok(Expr::new_heap(Heap {
+            compare: compare.clone(),
+            items,
+        }))
+    }
+
+    // (heap-push! h item): adds item and returns h
+    pub fn builtin_heap_push(&mut self, args: &[Expr]) -> EvalResult {
+        let [heap_expr, item] = args else {
+            return Err(EvalError::message(
+                "heap-push! requires exactly 2 arguments",
+            ));
+        };
+        let heap = Self::heap_arg(heap_expr, "heap-push!")?;
+        self.with_heap_items(heap, |evaluator, compare, items| {
+            evaluator.heap_insert(compare, items, item.clone())
+        })?;
+        Ok(heap_expr.clone())
+    }
+
+    // (heap-pop! h): removes and returns the first item
+    pub fn builtin_heap_pop(&mut self, args: &[Expr]) -> EvalResult {
+        let [heap] = args else {
+            return Err(EvalError::message("heap-pop! requires exactly 1 argument"));
+        };
+        let heap = Self::heap_arg(heap, "heap-pop!")?;
+        self.with_heap_items(heap, |evaluator, compare, items| {
+            evaluator.heap_remove_first(compare, items)
+        })?
+        .ok_or_else(|| EvalError::message("heap-pop!: heap is empty"))
+    }
+
+    // (heap-peek h): the first item, left in place
+    pub fn builtin_heap_peek(&mut self, args: &[Expr]) -> EvalResult {
+        let [heap] = args else {
+            return Err(EvalError::message("heap-peek requires exactly 1 argument"));
+        };
+        Self::heap_arg(heap, "heap-peek")?
+            .borrow()
+            .items
+            .first()
+            .cloned()
+            .ok_or_else(|| EvalError::message("heap-peek: heap is empty"))
+    }
+
+    pub fn builtin_heapp(&mut self, args: &[Expr]) -> EvalResult {
+        let [value] = args else {
+            return Err(EvalError::message("heapp requires exactly 1 argument"));
+        };
+        Ok(Evaluator::bool_to_expr(matches!(value, Expr::Heap(_))))
+    }
+}
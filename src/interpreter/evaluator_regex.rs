@@ -0,0 +1,81 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+use regex::{Captures, Regex};
+
+// Regular expressions use the regex crate's syntax. A match is returned as its text when
+// the pattern has no capture groups, and otherwise as a list of the whole match followed
+// by each group (nil for a group that didn't take part).
+impl Evaluator {
+    fn regex_args<'a>(
+        args: &'a [Expr],
+        name: &str,
+        count: usize,
+    ) -> Result<(Regex, Vec<&'a str>), EvalError> {
+        if args.len() != count {
+            return Err(EvalError::message(format!(
+                "{} requires exactly {} arguments",
+                name, count
+            )));
+        }
+        let strings = args
+            .iter()
+            .map(|arg| match arg {
+                Expr::String(text) => Ok(text.as_str()),
+                _ => Err(EvalError::message(format!(
+                    "{} requires string arguments",
+                    name
+                ))),
+            })
+            .collect::<Result<Vec<&str>, _>>()?;
+        let regex = Regex::new(strings[0])
+            .map_err(|err| EvalError::message(format!("{}: invalid pattern: {}", name, err)))?;
+        Ok((regex, strings))
+    }
+
+    fn match_to_expr(regex: &Regex, captures: &Captures) -> Expr {
+        let text = |index: usize| match captures.get(index) {
+            Some(found) => Expr::String(found.as_str().to_string()),
+            None => Expr::new_list(vec![]),
+        };
+        if regex.captures_len() == 1 {
+            text(0)
+        } else {
+            Expr::new_list((0..regex.captures_len()).map(text).collect())
+        }
+    }
+
+    // (regex-match? pattern string): true when the pattern matches anywhere in the string
+    pub fn builtin_regex_match(&mut self, args: &[Expr]) -> EvalResult {
+        let (regex, strings) = Self::regex_args(args, "regex-match?", 2)?;
+        Ok(Evaluator::bool_to_expr(regex.is_match(strings[1])))
+    }
+
+    // (regex-find pattern string): the first match, or nil
+    pub fn builtin_regex_find(&mut self, args: &[Expr]) -> EvalResult {
+        let (regex, strings) = Self::regex_args(args, "regex-find", 2)?;
+        Ok(match regex.captures(strings[1]) {
+            Some(captures) => Self::match_to_expr(&regex, &captures),
+            None => Expr::new_list(vec![]),
+        })
+    }
+
+    // (regex-find-all pattern string): every non-overlapping match, in order
+    pub fn builtin_regex_find_all(&mut self, args: &[Expr]) -> EvalResult {
+        let (regex, strings) = Self::regex_args(args, "regex-find-all", 2)?;
+        Ok(Expr::new_list(
+            regex
+                .captures_iter(strings[1])
+                .map(|captures| Self::match_to_expr(&regex, &captures))
+                .collect(),
+        ))
+    }
+
+    // (regex-replace pattern string replacement): replaces every match. The replacement
+    // can refer to groups as $1 or ${name}; $$ is a literal dollar sign.
+    pub fn builtin_regex_replace(&mut self, args: &[Expr]) -> EvalResult {
+        let (regex, strings) = Self::regex_args(args, "regex-replace", 3)?;
+        Ok(Expr::String(
+            regex.replace_all(strings[1], strings[2]).into_owned(),
+        ))
+    }
+}
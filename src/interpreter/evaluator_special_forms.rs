@@ -1,6 +1,10 @@
-use crate::interpreter::evaluator::Evaluator;
-use crate::interpreter::types::{EvalError, EvalResult, Expr};
+use crate::interpreter::allocations::AllocationCount;
+use crate::interpreter::environment::ScopeKind;
+use crate::interpreter::evaluator::{stack_address, Evaluator, Restart};
+use crate::interpreter::types::{EvalError, EvalResult, Expr, SymbolData};
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
 
 impl Evaluator {
     pub fn eval_cond(&mut self, list: &[Expr]) -> EvalResult {
@@ -24,7 +28,7 @@ impl Evaluator {
                         if clause_list.len() < 2 {
                             return Ok(Expr::Integer(1)); // else with no body returns true
                         }
-                        let mut result = Ok(Expr::List(vec![]));
+                        let mut result = Ok(Expr::new_list(vec![]));
                         for expr in &clause_list[1..] {
                             result = self.eval(expr);
                             if result.is_err() {
@@ -43,7 +47,7 @@ impl Evaluator {
                             return Ok(cond_result); // Return condition value if no body
                         }
                         // Execute this branch
-                        let mut result = Ok(Expr::List(vec![]));
+                        let mut result = Ok(Expr::new_list(vec![]));
                         for expr in &clause_list[1..] {
                             result = self.eval(expr);
                             if result.is_err() {
@@ -58,7 +62,7 @@ impl Evaluator {
         }
 
         // No condition was true
-        Ok(Expr::List(vec![]))
+        Ok(Expr::new_list(vec![]))
     }
 
     pub fn eval_and(&mut self, list: &[Expr]) -> EvalResult {
@@ -81,7 +85,7 @@ impl Evaluator {
 
     pub fn eval_or(&mut self, list: &[Expr]) -> EvalResult {
         if list.len() == 1 {
-            return Ok(Expr::List(vec![])); // (or) with no args returns false
+            return Ok(Expr::new_list(vec![])); // (or) with no args returns false
         }
 
         for expr in &list[1..] {
@@ -93,15 +97,15 @@ impl Evaluator {
             }
         }
 
-        Ok(Expr::List(vec![])) // All were falsy
+        Ok(Expr::new_list(vec![])) // All were falsy
     }
 
     pub fn eval_progn(&mut self, list: &[Expr]) -> EvalResult {
         if list.len() == 1 {
-            return Ok(Expr::List(vec![])); // (progn) with no args returns nil
+            return Ok(Expr::new_list(vec![])); // (progn) with no args returns nil
         }
 
-        let mut result = Ok(Expr::List(vec![]));
+        let mut result = Ok(Expr::new_list(vec![]));
         for expr in &list[1..] {
             result = self.eval(expr);
             if result.is_err() {
@@ -120,7 +124,7 @@ impl Evaluator {
         let is_true = Evaluator::is_truthy(&condition);
 
         if is_true {
-            let mut result = Ok(Expr::List(vec![]));
+            let mut result = Ok(Expr::new_list(vec![]));
             for expr in &list[2..] {
                 result = self.eval(expr);
                 if result.is_err() {
@@ -129,7 +133,7 @@ impl Evaluator {
             }
             result
         } else {
-            Ok(Expr::List(vec![]))
+            Ok(Expr::new_list(vec![]))
         }
     }
 
@@ -142,7 +146,7 @@ impl Evaluator {
         let is_false = !Evaluator::is_truthy(&condition);
 
         if is_false {
-            let mut result = Ok(Expr::List(vec![]));
+            let mut result = Ok(Expr::new_list(vec![]));
             for expr in &list[2..] {
                 result = self.eval(expr);
                 if result.is_err() {
@@ -151,7 +155,7 @@ impl Evaluator {
             }
             result
         } else {
-            Ok(Expr::List(vec![]))
+            Ok(Expr::new_list(vec![]))
         }
     }
 
@@ -179,7 +183,7 @@ impl Evaluator {
 
                     if is_else {
                         // Execute else branch
-                        let mut result = Ok(Expr::List(vec![]));
+                        let mut result = Ok(Expr::new_list(vec![]));
                         for expr in &clause_list[1..] {
                             result = self.eval(expr);
                             if result.is_err() {
@@ -191,13 +195,22 @@ impl Evaluator {
 
                     // Check if key matches any value in the test list
                     let matches = match test_value {
-                        Expr::List(values) => values.iter().any(|v| self.expr_equal(&key, v)),
-                        single_value => self.expr_equal(&key, single_value),
+                        Expr::List(values) => {
+                            let mut any = false;
+                            for value in values.iter() {
+                                if self.expr_equal(&key, value)? {
+                                    any = true;
+                                    break;
+                                }
+                            }
+                            any
+                        }
+                        single_value => self.expr_equal(&key, single_value)?,
                     };
 
                     if matches {
                         // Execute this branch
-                        let mut result = Ok(Expr::List(vec![]));
+                        let mut result = Ok(Expr::new_list(vec![]));
                         for expr in &clause_list[1..] {
                             result = self.eval(expr);
                             if result.is_err() {
@@ -212,25 +225,168 @@ impl Evaluator {
         }
 
         // No case matched
-        Ok(Expr::List(vec![]))
+        Ok(Expr::new_list(vec![]))
     }
 
-    pub fn expr_equal(&self, a: &Expr, b: &Expr) -> bool {
-        match (a, b) {
-            (x, y) if Evaluator::to_number(x).is_ok() && Evaluator::to_number(y).is_ok() => {
+    pub fn expr_equal(&self, a: &Expr, b: &Expr) -> Result<bool, EvalError> {
+        // Inside eval the stack budget counts from the top-level call
+        let base = if self.depth > 0 {
+            self.stack_base
+        } else {
+            stack_address()
+        };
+        self.expr_equal_at(a, b, 0, base)
+    }
+
+    fn expr_equal_at(
+        &self,
+        a: &Expr,
+        b: &Expr,
+        depth: usize,
+        base: usize,
+    ) -> Result<bool, EvalError> {
+        if depth >= self.max_depth {
+            return Err(self.nesting_too_deep());
+        }
+        if self.out_of_stack(base) {
+            return Err(self.out_of_stack_error());
+        }
+        let all_equal = |x: &[Expr], y: &[Expr]| -> Result<bool, EvalError> {
+            if x.len() != y.len() {
+                return Ok(false);
+            }
+            for (a, b) in x.iter().zip(y.iter()) {
+                if !self.expr_equal_at(a, b, depth + 1, base)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        };
+
+        Ok(match (a, b) {
+            (Expr::BigInt(_), Expr::BigInt(_) | Expr::Integer(_))
+            | (Expr::Integer(_), Expr::BigInt(_)) => a == b,
+            // Checked before calling to_number, whose error message would print (and
+            // possibly loop through) a self-referential vector
+            (x, y) if Self::is_number(x) && Self::is_number(y) => {
                 let x_val = Evaluator::to_number(x).unwrap();
                 let y_val = Evaluator::to_number(y).unwrap();
                 (x_val - y_val).abs() < f64::EPSILON
             }
             (Expr::String(x), Expr::String(y)) => x == y,
             (Expr::Symbol(x), Expr::Symbol(y)) => x == y,
-            (Expr::List(x), Expr::List(y)) => {
-                x.len() == y.len() && x.iter().zip(y.iter()).all(|(a, b)| self.expr_equal(a, b))
-            }
+            (Expr::List(x), Expr::List(y)) => all_equal(x, y)?,
             (Expr::Cons(ax, ay), Expr::Cons(bx, by)) => {
-                self.expr_equal(ax, bx) && self.expr_equal(ay, by)
+                self.expr_equal_at(ax, bx, depth + 1, base)?
+                    && self.expr_equal_at(ay, by, depth + 1, base)?
+            }
+            (Expr::Character(x), Expr::Character(y)) => x == y,
+            // Shared storage is equal without looking inside, so a vector or table that
+            // contains itself still compares equal to itself
+            (Expr::Vector(x), Expr::Vector(y)) => {
+                Rc::ptr_eq(x, y) || all_equal(&x.borrow(), &y.borrow())?
+            }
+            (Expr::Matrix(x), Expr::Matrix(y)) => Rc::ptr_eq(x, y) || *x.borrow() == *y.borrow(),
+            (Expr::Queue(x), Expr::Queue(y)) | (Expr::Stack(x), Expr::Stack(y)) => {
+                if Rc::ptr_eq(x, y) {
+                    return Ok(true);
+                }
+                let (x, y) = (x.borrow(), y.borrow());
+                if x.len() != y.len() {
+                    return Ok(false);
+                }
+                for (a, b) in x.iter().zip(y.iter()) {
+                    if !self.expr_equal_at(a, b, depth + 1, base)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            // A heap's storage order depends on its history, so only the same heap is equal
+            (Expr::Heap(x), Expr::Heap(y)) => Rc::ptr_eq(x, y),
+            (Expr::Iterator(x), Expr::Iterator(y)) => Rc::ptr_eq(x, y),
+            (Expr::Port(x), Expr::Port(y)) => Rc::ptr_eq(x, y),
+            (Expr::Struct(x), Expr::Struct(y)) => {
+                if Rc::ptr_eq(x, y) {
+                    return Ok(true);
+                }
+                let (x, y) = (x.borrow(), y.borrow());
+                if x.type_name != y.type_name || x.fields != y.fields {
+                    return Ok(false);
+                }
+                for (a, b) in x.values.iter().zip(y.values.iter()) {
+                    if !self.expr_equal_at(a, b, depth + 1, base)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            (Expr::HashTable(x), Expr::HashTable(y)) => {
+                if Rc::ptr_eq(x, y) {
+                    return Ok(true);
+                }
+                let (x, y) = (x.borrow(), y.borrow());
+                if x.len() != y.len() {
+                    return Ok(false);
+                }
+                for (key, value) in x.iter() {
+                    match y.get(key) {
+                        Some(other) if self.expr_equal_at(value, other, depth + 1, base)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                true
             }
             _ => false,
+        })
+    }
+
+    fn is_number(expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::Integer(_) | Expr::BigInt(_) | Expr::Float(_) | Expr::Rational { .. }
+        )
+    }
+
+    // Identity comparison (eq?, assq). Values are copied on assignment, so only atoms
+    // with a single representation match, plus lists, vectors, hash tables, matrices,
+    // queues, stacks, heaps, iterators and structs that share storage. Strings and cons
+    // cells are never eq?.
+    pub fn expr_eq(a: &Expr, b: &Expr) -> bool {
+        match (a, b) {
+            (Expr::Symbol(x), Expr::Symbol(y)) => x == y,
+            (Expr::Integer(x), Expr::Integer(y)) => x == y,
+            (Expr::Character(x), Expr::Character(y)) => x == y,
+            (Expr::List(x), Expr::List(y)) => Rc::ptr_eq(x, y) || (x.is_empty() && y.is_empty()),
+            (Expr::Vector(x), Expr::Vector(y)) => Rc::ptr_eq(x, y),
+            (Expr::HashTable(x), Expr::HashTable(y)) => Rc::ptr_eq(x, y),
+            (Expr::Matrix(x), Expr::Matrix(y)) => Rc::ptr_eq(x, y),
+            (Expr::Queue(x), Expr::Queue(y)) | (Expr::Stack(x), Expr::Stack(y)) => Rc::ptr_eq(x, y),
+            (Expr::Heap(x), Expr::Heap(y)) => Rc::ptr_eq(x, y),
+            (Expr::Iterator(x), Expr::Iterator(y)) => Rc::ptr_eq(x, y),
+            (Expr::Port(x), Expr::Port(y)) => Rc::ptr_eq(x, y),
+            (Expr::Struct(x), Expr::Struct(y)) => Rc::ptr_eq(x, y),
+            _ => false,
+        }
+    }
+
+    // eq? plus numbers of the same exactness and value: (eqv? 2 2.0) is false,
+    // bignums compare by value and floats bitwise, so NaN is eqv? to itself
+    pub fn expr_eqv(a: &Expr, b: &Expr) -> bool {
+        match (a, b) {
+            (Expr::BigInt(x), Expr::BigInt(y)) => x == y,
+            (Expr::Float(x), Expr::Float(y)) => x.to_bits() == y.to_bits(),
+            (
+                Expr::Rational {
+                    numerator: n1,
+                    denominator: d1,
+                },
+                Expr::Rational {
+                    numerator: n2,
+                    denominator: d2,
+                },
+            ) => n1 == n2 && d1 == d2,
+            _ => Self::expr_eq(a, b),
         }
     }
 
@@ -253,11 +409,11 @@ impl Evaluator {
             return Err(EvalError::message("do test clause cannot be empty"));
         }
 
-        self.environment.push_scope();
+        self.environment.push_scope(ScopeKind::Loop);
         let result = (|| -> EvalResult {
             let mut binding_info: Vec<(String, Option<Expr>)> = Vec::new();
 
-            for binding in bindings {
+            for binding in bindings.iter() {
                 let items = match binding {
                     Expr::List(items) if !items.is_empty() => items,
                     _ => {
@@ -286,7 +442,7 @@ impl Evaluator {
                 let init_value = if items.len() >= 2 {
                     self.eval(&items[1])?
                 } else {
-                    Expr::List(vec![])
+                    Expr::new_list(vec![])
                 };
                 self.environment.set(name.clone(), init_value);
 
@@ -307,7 +463,7 @@ impl Evaluator {
                         return Ok(test_result);
                     }
 
-                    let mut final_value = Expr::List(vec![]);
+                    let mut final_value = Expr::new_list(vec![]);
                     for expr in &test_clause[1..] {
                         final_value = self.eval(expr)?;
                     }
@@ -347,23 +503,76 @@ impl Evaluator {
         }
 
         if list.len() == 1 {
-            return Ok(Expr::List(vec![]));
+            return Ok(Expr::new_list(vec![]));
         }
 
         loop {
-            for expr in &list[1..] {
-                self.eval(expr)?;
+            match self.eval_loop_body(&list[1..]) {
+                Ok(()) | Err(EvalError::Continue) => {}
+                Err(EvalError::Break { value }) => return Ok(value),
+                Err(err) => return Err(err),
             }
         }
     }
 
+    // (while test body...) repeats body while test is truthy; (until test body...) while it
+    // is falsy. Both return nil unless left with (break value).
+    pub fn eval_while(&mut self, list: &[Expr], run_while: bool) -> EvalResult {
+        let name = if run_while { "while" } else { "until" };
+        if list.len() < 2 {
+            return Err(EvalError::message(format!("{} requires a test", name)));
+        }
+
+        loop {
+            let test_result = self.eval(&list[1])?;
+            if Evaluator::is_truthy(&test_result) != run_while {
+                return Ok(Expr::new_list(vec![]));
+            }
+            match self.eval_loop_body(&list[2..]) {
+                Ok(()) | Err(EvalError::Continue) => {}
+                Err(EvalError::Break { value }) => return Ok(value),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn eval_loop_body(&mut self, body: &[Expr]) -> Result<(), EvalError> {
+        for expr in body {
+            self.eval(expr)?;
+        }
+        Ok(())
+    }
+
+    pub fn eval_break(&mut self, list: &[Expr]) -> EvalResult {
+        if list.len() > 2 {
+            return Err(EvalError::message("break takes at most one value"));
+        }
+
+        let value = if list.len() == 2 {
+            self.eval(&list[1])?
+        } else {
+            Expr::new_list(vec![])
+        };
+
+        Err(EvalError::Break { value })
+    }
+
+    pub fn eval_continue(&mut self, list: &[Expr]) -> EvalResult {
+        if list.len() != 1 {
+            return Err(EvalError::message("continue takes no arguments"));
+        }
+
+        Err(EvalError::Continue)
+    }
+
     pub fn eval_catch(&mut self, list: &[Expr]) -> EvalResult {
         if list.len() < 2 {
             return Err(EvalError::message("catch requires a tag and optional body"));
         }
 
         let tag = self.eval(&list[1])?;
-        let mut last_value = Expr::List(vec![]);
+        let scope_depth = self.environment.scope_depth();
+        let mut last_value = Expr::new_list(vec![]);
 
         for expr in &list[2..] {
             match self.eval(expr) {
@@ -372,7 +581,8 @@ impl Evaluator {
                     tag: thrown_tag,
                     value,
                 }) => {
-                    if self.expr_equal(&tag, &thrown_tag) {
+                    if self.expr_equal(&tag, &thrown_tag)? {
+                        self.environment.unwind_to(scope_depth);
                         return Ok(value);
                     } else {
                         return Err(EvalError::Throw {
@@ -397,7 +607,7 @@ impl Evaluator {
         let value = if list.len() > 2 {
             self.eval(&list[2])?
         } else {
-            Expr::List(vec![])
+            Expr::new_list(vec![])
         };
 
         Err(EvalError::Throw { tag, value })
@@ -410,20 +620,15 @@ impl Evaluator {
             ));
         }
 
-        let protected_result = self.eval(&list[1]);
-
-        let cleanup_result = list[2..].iter().try_for_each(|expr| match self.eval(expr) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(err),
-        });
-
-        match cleanup_result {
-            Err(err) => Err(err),
-            Ok(_) => match protected_result {
-                Ok(value) => Ok(value),
-                Err(err) => Err(err),
+        self.with_cleanup(
+            |evaluator| evaluator.eval(&list[1]),
+            |evaluator| {
+                for expr in &list[2..] {
+                    evaluator.eval(expr)?;
+                }
+                Ok(Expr::new_list(vec![]))
             },
-        }
+        )
     }
 
     pub fn eval_block(&mut self, list: &[Expr]) -> EvalResult {
@@ -436,7 +641,8 @@ impl Evaluator {
             _ => return Err(EvalError::message("block name must be a symbol")),
         };
 
-        let mut last_value = Expr::List(vec![]);
+        let scope_depth = self.environment.scope_depth();
+        let mut last_value = Expr::new_list(vec![]);
         for expr in &list[2..] {
             match self.eval(expr) {
                 Ok(value) => last_value = value,
@@ -445,6 +651,7 @@ impl Evaluator {
                     value,
                 }) => {
                     if target == name {
+                        self.environment.unwind_to(scope_depth);
                         return Ok(value);
                     } else {
                         return Err(EvalError::ReturnFrom {
@@ -477,7 +684,7 @@ impl Evaluator {
         let value = if list.len() > 2 {
             self.eval(&list[2])?
         } else {
-            Expr::List(vec![])
+            Expr::new_list(vec![])
         };
 
         Err(EvalError::ReturnFrom { name, value })
@@ -495,6 +702,7 @@ impl Evaluator {
             }
         }
 
+        let scope_depth = self.environment.scope_depth();
         let mut index = 0usize;
         while index < forms.len() {
             match &forms[index] {
@@ -505,6 +713,7 @@ impl Evaluator {
                     Ok(_) => index += 1,
                     Err(EvalError::Go { label }) => {
                         if let Some(target) = labels.get(&label) {
+                            self.environment.unwind_to(scope_depth);
                             index = target + 1;
                         } else {
                             return Err(EvalError::Go { label });
@@ -515,7 +724,7 @@ impl Evaluator {
             }
         }
 
-        Ok(Expr::List(vec![]))
+        Ok(Expr::new_list(vec![]))
     }
 
     pub fn eval_go(&mut self, list: &[Expr]) -> EvalResult {
@@ -531,6 +740,155 @@ impl Evaluator {
         Err(EvalError::Go { label })
     }
 
+    // (time expr): evaluates expr, prints how long it took and what it allocated, and
+    // returns its value
+    pub fn eval_time(&mut self, list: &[Expr]) -> EvalResult {
+        if list.len() != 2 {
+            return Err(EvalError::message("time requires exactly 1 argument"));
+        }
+
+        let before = AllocationCount::now();
+        let started = Instant::now();
+        let result = self.eval(&list[1]);
+        let elapsed = started.elapsed();
+        let allocated = AllocationCount::now().since(before);
+        self.write_output(&format!(
+            "; time: {:.3} ms, {} allocations, {} bytes\n",
+            elapsed.as_secs_f64() * 1000.0,
+            allocated.allocations,
+            allocated.bytes
+        ));
+        result
+    }
+
+    // (with-output-to-string body...): evaluates body with program output going to a fresh
+    // string and returns the string. Output goes back where it was however body exits.
+    pub fn eval_with_output_to_string(&mut self, list: &[Expr]) -> EvalResult {
+        let previous = Rc::clone(&self.output);
+        let port = self.capture_output();
+        self.with_cleanup(
+            |evaluator| {
+                for expr in &list[1..] {
+                    evaluator.eval(expr)?;
+                }
+                Ok(Expr::String(port.take()))
+            },
+            |evaluator| {
+                evaluator.output = previous;
+                Ok(Expr::new_list(vec![]))
+            },
+        )
+    }
+
+    // (restart-case form (name (params...) body...)...): evaluates form with the clauses
+    // established as restarts. invoke-restart, or the restart chooser when form fails,
+    // unwinds back here and runs the chosen clause's body with its params bound to the
+    // arguments, and that is the value of the restart-case.
+    pub fn eval_restart_case(&mut self, list: &[Expr]) -> EvalResult {
+        if list.len() < 2 {
+            return Err(EvalError::message("restart-case requires a form"));
+        }
+
+        let mut clauses = Vec::new();
+        for clause in &list[2..] {
+            let parts = match clause {
+                Expr::List(parts) if parts.len() >= 2 => parts,
+                _ => {
+                    return Err(EvalError::message(
+                        "restart-case clause must be (name (params...) body...)",
+                    ))
+                }
+            };
+            let name = match &parts[0] {
+                Expr::Symbol(sym_data) => sym_data.name().to_string(),
+                _ => return Err(EvalError::message("restart name must be a symbol")),
+            };
+            let params = match &parts[1] {
+                Expr::List(params) => params
+                    .iter()
+                    .map(|param| match param {
+                        Expr::Symbol(sym_data) if !sym_data.is_keyword() => {
+                            Ok(sym_data.name().to_string())
+                        }
+                        _ => Err(EvalError::message(format!(
+                            "restart {} parameters must be symbols",
+                            name
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                _ => {
+                    return Err(EvalError::message(format!(
+                        "restart {} needs a parameter list",
+                        name
+                    )))
+                }
+            };
+            let id = self.next_restart_id;
+            self.next_restart_id += 1;
+            clauses.push((Restart { id, name, params }, &parts[2..]));
+        }
+
+        // Innermost last overall, and in clause order when read from the end
+        let depth = self.restarts.len();
+        self.restarts
+            .extend(clauses.iter().rev().map(|(restart, _)| restart.clone()));
+        let scope_depth = self.environment.scope_depth();
+        let mut result = self.eval(&list[1]);
+
+        if let Err(err @ EvalError::Message(_)) = &result {
+            if !self.restart_declined {
+                if let Some(chooser) = self.restart_chooser.clone() {
+                    let restarts = self.active_restarts();
+                    result = match chooser(self, err, &restarts) {
+                        Some((id, args)) => Err(EvalError::InvokeRestart { id, args }),
+                        None => {
+                            self.restart_declined = true;
+                            result
+                        }
+                    };
+                }
+            }
+        }
+        self.restarts.truncate(depth);
+
+        let (id, args) = match result {
+            Err(EvalError::InvokeRestart { id, args })
+                if clauses.iter().any(|(restart, _)| restart.id == id) =>
+            {
+                (id, args)
+            }
+            other => return other,
+        };
+        self.environment.unwind_to(scope_depth);
+        self.restart_declined = false;
+
+        let (restart, body) = clauses
+            .iter()
+            .find(|(restart, _)| restart.id == id)
+            .unwrap();
+        if args.len() != restart.params.len() {
+            return Err(EvalError::message(format!(
+                "restart {} expects {} arguments, got {}",
+                restart.name,
+                restart.params.len(),
+                args.len()
+            )));
+        }
+        self.environment.push_scope(ScopeKind::Restart);
+        for (param, arg) in restart.params.iter().zip(args) {
+            self.environment.set(param.clone(), arg);
+        }
+        let result = (|| -> EvalResult {
+            let mut last = Expr::new_list(vec![]);
+            for expr in body.iter() {
+                last = self.eval(expr)?;
+            }
+            Ok(last)
+        })();
+        self.environment.pop_scope();
+        result
+    }
+
     fn is_do_binding_list(expr: &Expr) -> bool {
         match expr {
             Expr::List(items) => items.iter().all(|item| match item {
@@ -546,15 +904,67 @@ impl Evaluator {
         let args: Result<Vec<_>, _> = list[1..].iter().map(|e| self.eval(e)).collect();
         let args = args?;
 
-        match func {
-            Expr::Symbol(sym_data) => self.apply_builtin(sym_data.name(), &args),
-            Expr::List(lambda)
-                if lambda.len() == 3
-                    && matches!(&lambda[0], Expr::Symbol(sym_data) if sym_data.name() == "lambda") =>
-            {
-                self.apply_lambda(&lambda, &args)
+        self.call_callable(&func, &args)
+    }
+
+    // Single entry point for invoking a function value: builtin symbols, lambda lists,
+    // symbols naming a user function (e.g. (mapcar (quote square) xs)), and keywords,
+    // which look themselves up in a record.
+    pub fn call_callable(&mut self, f: &Expr, args: &[Expr]) -> EvalResult {
+        match f {
+            Expr::Symbol(SymbolData::Keyword(_)) => self.keyword_lookup(f, args),
+            Expr::Symbol(sym_data) => match self.resolve_function(sym_data)? {
+                Expr::Symbol(builtin) => self.apply_builtin(builtin.name(), args),
+                lambda => self.call_callable(&lambda, args),
+            },
+            Expr::List(lambda) if Self::is_lambda(f) => self.apply_lambda(lambda, args),
+            _ => Err(EvalError::message(format!("Cannot apply: {:?}", f))),
+        }
+    }
+
+    // What a symbol names as a function: the lambda it's bound to, or the builtin it is.
+    // A variable holding another function's symbol, as after (define first car), is
+    // followed to that function.
+    fn resolve_function(&self, name: &SymbolData) -> Result<Expr, EvalError> {
+        let mut seen = vec![name.symbol()];
+        loop {
+            let symbol = seen[seen.len() - 1];
+            match self.environment.get_symbol(symbol) {
+                Ok(value) if Self::is_lambda(&value) => return Ok(value),
+                Ok(Expr::Symbol(SymbolData::Interned(target))) if target != symbol => {
+                    if seen.contains(&target) {
+                        return Err(EvalError::message(format!(
+                            "{} is not a function (its binding refers back to itself)",
+                            name.name()
+                        )));
+                    }
+                    seen.push(target);
+                }
+                // A builtin, or a name apply_builtin will report as unknown
+                _ if seen.len() == 1 || symbol.is_builtin() => {
+                    return Ok(Expr::Symbol(SymbolData::Interned(symbol)))
+                }
+                _ => {
+                    return Err(EvalError::message(format!(
+                        "{} is not a function (it is bound to {})",
+                        name.name(),
+                        seen[1]
+                    )))
+                }
             }
-            _ => Err(EvalError::message(format!("Cannot apply: {:?}", func))),
         }
     }
+
+    pub fn is_callable(f: &Expr) -> bool {
+        matches!(f, Expr::Symbol(_)) || Self::is_lambda(f)
+    }
+
+    fn is_lambda(expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::List(lambda)
+                if lambda.len() == 3
+                    && matches!(&lambda[0], Expr::Symbol(sym_data) if sym_data.name() == "lambda")
+        )
+    }
 }
@@ -0,0 +1,77 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+
+// Descriptive statistics over a list or vector of numbers. Results are always floats, and
+// variance and stddev are for the whole population (divided by n, not n - 1).
+impl Evaluator {
+    fn sample_arg(args: &[Expr], name: &str, count: usize) -> Result<Vec<f64>, EvalError> {
+        if args.len() != count {
+            return Err(EvalError::message(format!(
+                "{} requires exactly {} argument{}",
+                name,
+                count,
+                if count == 1 { "" } else { "s" }
+            )));
+        }
+        let values: Result<Vec<f64>, String> = match &args[0] {
+            Expr::List(items) => items.iter().map(Self::to_number).collect(),
+            Expr::Vector(items) => items.borrow().iter().map(Self::to_number).collect(),
+            _ => Err(String::new()),
+        };
+        let values = values.map_err(|_| {
+            EvalError::message(format!("{} requires a list or vector of numbers", name))
+        })?;
+        if values.is_empty() {
+            return Err(EvalError::message(format!("{}: no values", name)));
+        }
+        Ok(values)
+    }
+
+    fn mean_of(values: &[f64]) -> f64 {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    fn variance_of(values: &[f64]) -> f64 {
+        let mean = Self::mean_of(values);
+        values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64
+    }
+
+    // Linear interpolation between the two nearest ranks, so the 50th percentile is the
+    // median and the 0th and 100th are the minimum and maximum
+    fn percentile_of(mut values: Vec<f64>, percent: f64) -> f64 {
+        values.sort_by(f64::total_cmp);
+        let rank = percent / 100.0 * (values.len() - 1) as f64;
+        let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+        values[low] + (values[high] - values[low]) * (rank - low as f64)
+    }
+
+    pub fn builtin_mean(&mut self, args: &[Expr]) -> EvalResult {
+        let values = Self::sample_arg(args, "mean", 1)?;
+        Ok(Expr::Float(Self::mean_of(&values)))
+    }
+
+    pub fn builtin_median(&mut self, args: &[Expr]) -> EvalResult {
+        let values = Self::sample_arg(args, "median", 1)?;
+        Ok(Expr::Float(Self::percentile_of(values, 50.0)))
+    }
+
+    pub fn builtin_variance(&mut self, args: &[Expr]) -> EvalResult {
+        let values = Self::sample_arg(args, "variance", 1)?;
+        Ok(Expr::Float(Self::variance_of(&values)))
+    }
+
+    pub fn builtin_stddev(&mut self, args: &[Expr]) -> EvalResult {
+        let values = Self::sample_arg(args, "stddev", 1)?;
+        Ok(Expr::Float(Self::variance_of(&values).sqrt()))
+    }
+
+    // (percentile values p) with p from 0 to 100
+    pub fn builtin_percentile(&mut self, args: &[Expr]) -> EvalResult {
+        let values = Self::sample_arg(args, "percentile", 2)?;
+        let percent = Self::to_number(&args[1])
+            .ok()
+            .filter(|p| (0.0..=100.0).contains(p))
+            .ok_or_else(|| EvalError::message("percentile requires a percentage from 0 to 100"))?;
+        Ok(Expr::Float(Self::percentile_of(values, percent)))
+    }
+}
@@ -0,0 +1,240 @@
+use crate::interpreter::bigint::BigInt;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::keyword_args::parse_keyword_args;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+
+impl Evaluator {
+    // (string-split s [separator] [:limit n]): the pieces of s between occurrences of
+    // separator, empty ones included. Without a separator s splits on runs of whitespace and
+    // leading or trailing whitespace makes no empty pieces. With :limit there are at most n
+    // pieces, the last holding the rest of s unsplit.
+    pub fn builtin_string_split(&mut self, args: &[Expr]) -> EvalResult {
+        let (args, keyword_args) = parse_keyword_args(args, "string-split", 1..=2, &["limit"])?;
+        let Expr::String(text) = &args[0] else {
+            return Err(EvalError::message(
+                "string-split requires a string as first argument",
+            ));
+        };
+        let limit = match keyword_args.count("limit")? {
+            Some(0) => {
+                return Err(EvalError::message(
+                    "string-split requires a positive :limit",
+                ))
+            }
+            Some(limit) => limit,
+            None => usize::MAX,
+        };
+
+        let pieces: Vec<&str> = match args.get(1) {
+            Some(Expr::String(separator)) if !separator.is_empty() => {
+                text.splitn(limit, separator.as_str()).collect()
+            }
+            Some(_) => {
+                return Err(EvalError::message(
+                    "string-split requires a non-empty string as separator",
+                ))
+            }
+            None => split_whitespace(text, limit),
+        };
+        Ok(Expr::new_list(
+            pieces
+                .into_iter()
+                .map(|piece| Expr::String(piece.to_string()))
+                .collect(),
+        ))
+    }
+
+    // alpha?, whitespace? and (digit? c [radix]) classify a character; any Unicode letter
+    // or space counts, but digits are only those of the radix (10 by default)
+    pub fn builtin_char_class(&mut self, args: &[Expr], name: &str) -> EvalResult {
+        let c = match args {
+            [Expr::Character(c)] => c,
+            [Expr::Character(c), _] if name == "digit?" => c,
+            _ if name == "digit?" => {
+                return Err(EvalError::message(
+                    "digit? requires a character and an optional radix",
+                ))
+            }
+            _ => {
+                return Err(EvalError::message(format!(
+                    "{} requires exactly 1 character",
+                    name
+                )))
+            }
+        };
+        let matches = match name {
+            "alpha?" => c.is_alphabetic(),
+            "whitespace?" => c.is_whitespace(),
+            _ => c.is_digit(Self::radix_arg(args.get(1), name)?),
+        };
+        Ok(Evaluator::bool_to_expr(matches))
+    }
+
+    // (string->number s [radix]): the number s is written as, or nil if it isn't one.
+    // Reads what the reader does for numbers: integers of any size, floats with or without
+    // an exponent, +inf.0, -inf.0 and +nan.0, and n/d as a rational in lowest terms. A #x,
+    // #o, #b or #d prefix overrides radix; floats are only read in radix 10.
+    pub fn builtin_string_to_number(&mut self, args: &[Expr]) -> EvalResult {
+        let text = match args {
+            [Expr::String(text)] | [Expr::String(text), _] => text,
+            _ => {
+                return Err(EvalError::message(
+                    "string->number requires a string and an optional radix",
+                ))
+            }
+        };
+        let radix = Self::radix_arg(args.get(1), "string->number")?;
+        Ok(parse_number(text, radix).unwrap_or_else(|| Expr::new_list(vec![])))
+    }
+
+    // (number->string n [radix]): n written in radix (10 by default), in lowercase digits.
+    // Floats can only be written in radix 10, as they print.
+    pub fn builtin_number_to_string(&mut self, args: &[Expr]) -> EvalResult {
+        if args.is_empty() || args.len() > 2 {
+            return Err(EvalError::message(
+                "number->string requires a number and an optional radix",
+            ));
+        }
+        let radix = Self::radix_arg(args.get(1), "number->string")?;
+        let text = match &args[0] {
+            Expr::Integer(n) => integer_to_string(*n, radix),
+            Expr::Rational {
+                numerator,
+                denominator,
+            } => format!(
+                "{}/{}",
+                integer_to_string(*numerator, radix),
+                integer_to_string(*denominator, radix)
+            ),
+            Expr::BigInt(n) => n.to_string_radix(radix),
+            Expr::Float(_) if radix == 10 => write_string(&args[0]),
+            Expr::Float(_) => {
+                return Err(EvalError::message(format!(
+                    "number->string can't write {} in radix {}",
+                    write_string(&args[0]),
+                    radix
+                )))
+            }
+            _ => return Err(EvalError::message("number->string requires a number")),
+        };
+        Ok(Expr::String(text))
+    }
+
+    fn radix_arg(arg: Option<&Expr>, name: &str) -> Result<u32, EvalError> {
+        match arg {
+            None => Ok(10),
+            Some(Expr::Integer(radix)) if (2..=36).contains(radix) => Ok(*radix as u32),
+            Some(_) => Err(EvalError::message(format!(
+                "{} requires a radix from 2 to 36",
+                name
+            ))),
+        }
+    }
+}
+
+fn parse_number(text: &str, radix: u32) -> Option<Expr> {
+    let prefix = text.get(..2).map(|prefix| prefix.to_ascii_lowercase());
+    let radix = match prefix.as_deref() {
+        Some("#x") => 16,
+        Some("#o") => 8,
+        Some("#b") => 2,
+        Some("#d") => 10,
+        _ => return parse_unprefixed(text, radix),
+    };
+    parse_unprefixed(&text[2..], radix)
+}
+
+fn parse_unprefixed(text: &str, radix: u32) -> Option<Expr> {
+    match text {
+        "+inf.0" if radix == 10 => return Some(Expr::Float(f64::INFINITY)),
+        "-inf.0" if radix == 10 => return Some(Expr::Float(f64::NEG_INFINITY)),
+        "+nan.0" if radix == 10 => return Some(Expr::Float(f64::NAN)),
+        _ => {}
+    }
+    if let Some((numerator, denominator)) = text.split_once('/') {
+        if denominator.starts_with(['+', '-']) {
+            return None;
+        }
+        let numerator = i64::from_str_radix(valid_digits(numerator, radix)?, radix).ok()?;
+        let denominator = i64::from_str_radix(valid_digits(denominator, radix)?, radix).ok()?;
+        return rational(numerator, denominator);
+    }
+    if let Some(digits) = valid_digits(text, radix) {
+        return match i64::from_str_radix(digits, radix) {
+            Ok(n) => Some(Expr::Integer(n)),
+            Err(_) => BigInt::parse_radix(digits, radix).map(Expr::from_bigint),
+        };
+    }
+    // Rust's float syntax also takes words like "inf" and "NaN", which Zeus doesn't
+    let float_syntax = text.chars().any(|c| c.is_ascii_digit())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+    if radix == 10 && float_syntax {
+        return text.parse().ok().map(Expr::Float);
+    }
+    None
+}
+
+// `text` if it is an optionally signed run of digits in radix
+fn valid_digits(text: &str, radix: u32) -> Option<&str> {
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    (!digits.is_empty() && digits.chars().all(|c| c.is_digit(radix))).then_some(text)
+}
+
+// numerator/denominator in lowest terms, with the sign on the numerator; an integer when
+// the denominator divides it
+fn rational(numerator: i64, denominator: i64) -> Option<Expr> {
+    if denominator == 0 {
+        return None;
+    }
+    let divisor = gcd(numerator.unsigned_abs(), denominator.unsigned_abs());
+    let (numerator, denominator) = (numerator / divisor as i64, denominator / divisor as i64);
+    Some(if denominator == 1 {
+        Expr::Integer(numerator)
+    } else {
+        Expr::Rational {
+            numerator,
+            denominator,
+        }
+    })
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn integer_to_string(n: i64, radix: u32) -> String {
+    let mut magnitude = n.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        digits.push(std::char::from_digit((magnitude % radix as u64) as u32, radix).unwrap());
+        magnitude /= radix as u64;
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if n < 0 {
+        digits.push('-');
+    }
+    digits.iter().rev().collect()
+}
+
+fn split_whitespace(text: &str, limit: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        if pieces.len() + 1 == limit {
+            pieces.push(rest);
+            break;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        pieces.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    pieces
+}
@@ -0,0 +1,248 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::types::{EvalError, EvalResult, Expr, Struct, Symbol, SymbolData};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Record types. (defstruct point x y) defines make-point, point-p, point-x, point-y,
+// set-point-x! and set-point-y! as ordinary functions over the generic make-struct,
+// struct-ref and struct-set! builtins. Instances carry their type name and field names, so
+// they print, compare and show in the inspector without looking the type up anywhere.
+impl Evaluator {
+    fn struct_arg<'a>(arg: &'a Expr, name: &str) -> Result<&'a Rc<RefCell<Struct>>, EvalError> {
+        match arg {
+            Expr::Struct(instance) => Ok(instance),
+            _ => Err(EvalError::message(format!(
+                "{} requires a struct, got {}",
+                name,
+                write_string(arg)
+            ))),
+        }
+    }
+
+    fn struct_symbol(arg: &Expr, name: &str, what: &str) -> Result<Symbol, EvalError> {
+        match arg {
+            Expr::Symbol(SymbolData::Interned(symbol)) => Ok(*symbol),
+            _ => Err(EvalError::message(format!(
+                "{}: {} must be a symbol, got {}",
+                name,
+                what,
+                write_string(arg)
+            ))),
+        }
+    }
+
+    // The index of `field` in `instance`, checking first that the instance has the type the
+    // accessor was defined for when one is given
+    fn struct_field_index(
+        instance: &Struct,
+        field: &Expr,
+        type_name: Option<&Expr>,
+        name: &str,
+    ) -> Result<usize, EvalError> {
+        if let Some(type_name) = type_name {
+            let expected = Self::struct_symbol(type_name, name, "the type")?;
+            if instance.type_name != expected {
+                return Err(EvalError::message(format!(
+                    "{}: expected a {}, got a {}",
+                    name, expected, instance.type_name
+                )));
+            }
+        }
+        let field = Self::struct_symbol(field, name, "the field")?;
+        instance.index_of(field).ok_or_else(|| {
+            EvalError::message(format!(
+                "{}: {} has no field {}",
+                name, instance.type_name, field
+            ))
+        })
+    }
+
+    // (make-struct type (field...) value...): an instance with a value per field
+    pub fn builtin_make_struct(&mut self, args: &[Expr]) -> EvalResult {
+        let [type_name, fields, values @ ..] = args else {
+            return Err(EvalError::message(
+                "make-struct requires a type, a list of fields and a value per field",
+            ));
+        };
+        let type_name = Self::struct_symbol(type_name, "make-struct", "the type")?;
+        let Expr::List(fields) = fields else {
+            return Err(EvalError::message(
+                "make-struct: the fields must be a list of symbols",
+            ));
+        };
+        let fields = fields
+            .iter()
+            .map(|field| Self::struct_symbol(field, "make-struct", "each field"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if fields.len() != values.len() {
+            return Err(EvalError::message(format!(
+                "make-struct: {} has {} fields but was given {} values",
+                type_name,
+                fields.len(),
+                values.len()
+            )));
+        }
+        Ok(Expr::new_struct(Struct {
+            type_name,
+            fields: Rc::new(fields),
+            values: values.to_vec(),
+        }))
+    }
+
+    // (struct-ref s field [type]): the value of a field, checking the type when given
+    pub fn builtin_struct_ref(&mut self, args: &[Expr]) -> EvalResult {
+        let (instance, field, type_name) = match args {
+            [instance, field] => (instance, field, None),
+            [instance, field, type_name] => (instance, field, Some(type_name)),
+            _ => return Err(EvalError::message("struct-ref requires 2 or 3 arguments")),
+        };
+        let instance = Self::struct_arg(instance, "struct-ref")?.borrow();
+        let index = Self::struct_field_index(&instance, field, type_name, "struct-ref")?;
+        Ok(instance.values[index].clone())
+    }
+
+    // (struct-set! s field value [type]): replaces a field's value in place and returns it
+    pub fn builtin_struct_set(&mut self, args: &[Expr]) -> EvalResult {
+        let (instance, field, value, type_name) = match args {
+            [instance, field, value] => (instance, field, value, None),
+            [instance, field, value, type_name] => (instance, field, value, Some(type_name)),
+            _ => return Err(EvalError::message("struct-set! requires 3 or 4 arguments")),
+        };
+        let mut instance = Self::struct_arg(instance, "struct-set!")?.borrow_mut();
+        let index = Self::struct_field_index(&instance, field, type_name, "struct-set!")?;
+        instance.values[index] = value.clone();
+        Ok(value.clone())
+    }
+
+    // (struct-type x): the type name of a struct, nil for anything else
+    pub fn builtin_struct_type(&mut self, args: &[Expr]) -> EvalResult {
+        let [value] = args else {
+            return Err(EvalError::message(
+                "struct-type requires exactly 1 argument",
+            ));
+        };
+        Ok(match value {
+            Expr::Struct(instance) => {
+                Expr::Symbol(SymbolData::Interned(instance.borrow().type_name))
+            }
+            _ => Expr::new_list(vec![]),
+        })
+    }
+
+    // (struct-fields s): the field names, in order
+    pub fn builtin_struct_fields(&mut self, args: &[Expr]) -> EvalResult {
+        let [instance] = args else {
+            return Err(EvalError::message(
+                "struct-fields requires exactly 1 argument",
+            ));
+        };
+        let instance = Self::struct_arg(instance, "struct-fields")?.borrow();
+        Ok(Expr::new_list(
+            instance
+                .fields
+                .iter()
+                .map(|field| Expr::Symbol(SymbolData::Interned(*field)))
+                .collect(),
+        ))
+    }
+
+    pub fn builtin_structp(&mut self, args: &[Expr]) -> EvalResult {
+        let [value] = args else {
+            return Err(EvalError::message("structp requires exactly 1 argument"));
+        };
+        Ok(Evaluator::bool_to_expr(matches!(value, Expr::Struct(_))))
+    }
+
+    // (defstruct name field...): defines the constructor, predicate, accessors and setters
+    // and returns the name
+    pub(crate) fn eval_defstruct(&mut self, list: &[Expr]) -> EvalResult {
+        let Some(type_name) = list.get(1) else {
+            return Err(EvalError::message(
+                "defstruct requires a name, then the field names",
+            ));
+        };
+        let type_name = Self::struct_symbol(type_name, "defstruct", "the name")?;
+        let mut fields: Vec<Symbol> = Vec::new();
+        for field in &list[2..] {
+            let field = Self::struct_symbol(field, "defstruct", "each field")?;
+            if fields.contains(&field) {
+                return Err(EvalError::message(format!(
+                    "defstruct: {} has field {} more than once",
+                    type_name, field
+                )));
+            }
+            fields.push(field);
+        }
+
+        let symbol = |name: &str| Expr::Symbol(SymbolData::Interned(Symbol::intern(name)));
+        let quoted = |expr: Expr| Expr::new_list(vec![symbol("quote"), expr]);
+        let lambda = |params: Vec<Expr>, body: Vec<Expr>| {
+            Expr::new_list(vec![
+                symbol("lambda"),
+                Expr::new_list(params),
+                Expr::new_list(body),
+            ])
+        };
+        let field_symbols: Vec<Expr> = fields
+            .iter()
+            .map(|field| Expr::Symbol(SymbolData::Interned(*field)))
+            .collect();
+        let quoted_type = quoted(Expr::Symbol(SymbolData::Interned(type_name)));
+
+        // (make-point x y) takes the fields in order
+        let mut make = vec![
+            symbol("make-struct"),
+            quoted_type.clone(),
+            quoted(Expr::new_list(field_symbols.clone())),
+        ];
+        make.extend(field_symbols.iter().cloned());
+        self.environment.define(
+            format!("make-{}", type_name),
+            lambda(field_symbols.clone(), make),
+        );
+
+        self.environment.define(
+            format!("{}-p", type_name),
+            lambda(
+                vec![symbol("object")],
+                vec![
+                    symbol("eq?"),
+                    Expr::new_list(vec![symbol("struct-type"), symbol("object")]),
+                    quoted_type.clone(),
+                ],
+            ),
+        );
+
+        for (field, field_symbol) in fields.iter().zip(&field_symbols) {
+            let name = format!("{}-{}", type_name, field);
+            self.environment.define(
+                name.clone(),
+                lambda(
+                    vec![symbol("instance")],
+                    vec![
+                        symbol("struct-ref"),
+                        symbol("instance"),
+                        quoted(field_symbol.clone()),
+                        quoted_type.clone(),
+                    ],
+                ),
+            );
+            self.environment.define(
+                format!("set-{}!", name),
+                lambda(
+                    vec![symbol("instance"), symbol("value")],
+                    vec![
+                        symbol("struct-set!"),
+                        symbol("instance"),
+                        quoted(field_symbol.clone()),
+                        symbol("value"),
+                        quoted_type.clone(),
+                    ],
+                ),
+            );
+        }
+
+        Ok(Expr::Symbol(SymbolData::Interned(type_name)))
+    }
+}
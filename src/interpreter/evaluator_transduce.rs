@@ -0,0 +1,146 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::types::{EvalError, EvalResult, Expr, SymbolData};
+use std::slice;
+
+// Fused list pipelines: each element goes through every step before the next element is
+// looked at, so a chain of maps and filters ending in a reduce builds no intermediate
+// lists. The optimizer rewrites nested mapcar/filter/remove/reduce calls into transduce.
+
+pub enum Step {
+    Map(Expr),
+    Filter(Expr),
+    Remove(Expr),
+}
+
+// How a pipeline finishes: collect what comes out, or fold it with a reducer
+pub enum Finish {
+    Collect,
+    Reduce { reducer: Expr, init: Option<Expr> },
+}
+
+impl Evaluator {
+    // The element after every step, or None if a filter dropped it
+    fn run_steps(&mut self, steps: &[Step], item: &Expr) -> Result<Option<Expr>, EvalError> {
+        let mut value = item.clone();
+        for step in steps {
+            match step {
+                Step::Map(f) => value = self.call_callable(f, slice::from_ref(&value))?,
+                Step::Filter(pred) | Step::Remove(pred) => {
+                    let keep =
+                        Evaluator::is_truthy(&self.call_callable(pred, slice::from_ref(&value))?);
+                    if keep != matches!(step, Step::Filter(_)) {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+        Ok(Some(value))
+    }
+
+    pub fn run_pipeline(&mut self, items: &[Expr], steps: &[Step], finish: Finish) -> EvalResult {
+        match finish {
+            Finish::Collect => {
+                let mut result = Vec::new();
+                for item in items {
+                    result.extend(self.run_steps(steps, item)?);
+                }
+                Ok(Expr::new_list(result))
+            }
+            Finish::Reduce { reducer, init } => {
+                let mut acc = init;
+                for item in items {
+                    if let Some(value) = self.run_steps(steps, item)? {
+                        acc = Some(match acc {
+                            Some(acc) => self.call_callable(&reducer, &[acc, value])?,
+                            None => value,
+                        });
+                    }
+                }
+                acc.ok_or_else(|| EvalError::message("reduce of empty list with no initial value"))
+            }
+        }
+    }
+
+    // (transduce list step...) where the steps are :map f, :filter pred and :remove pred
+    // in the order they apply, optionally ending with :reduce f [init]. Without :reduce
+    // it returns the list of elements that made it through.
+    pub fn builtin_transduce(&mut self, args: &[Expr]) -> EvalResult {
+        let Some(sequence) = args.first() else {
+            return Err(EvalError::message(
+                "transduce requires a sequence as first argument",
+            ));
+        };
+        let items = Self::seq_items(sequence, "transduce", "first")?;
+        let mut steps = Vec::new();
+        let mut finish = Finish::Collect;
+        let mut rest = &args[1..];
+        while let [keyword, function, more @ ..] = rest {
+            if !Self::is_callable(function) {
+                return Err(EvalError::message(format!(
+                    "transduce requires a function after {}",
+                    write_string(keyword)
+                )));
+            }
+            rest = more;
+            let name = match keyword {
+                Expr::Symbol(SymbolData::Keyword(name)) => name.as_str(),
+                _ => "",
+            };
+            match name {
+                "map" => steps.push(Step::Map(function.clone())),
+                "filter" => steps.push(Step::Filter(function.clone())),
+                "remove" => steps.push(Step::Remove(function.clone())),
+                "reduce" if rest.len() <= 1 => {
+                    finish = Finish::Reduce {
+                        reducer: function.clone(),
+                        init: rest.first().cloned(),
+                    };
+                    rest = &[];
+                }
+                "reduce" => {
+                    return Err(EvalError::message(
+                        "transduce takes :reduce f [init] only as its last step",
+                    ))
+                }
+                _ => {
+                    return Err(EvalError::message(format!(
+                        "transduce expected :map, :filter, :remove or :reduce but got {}",
+                        write_string(keyword)
+                    )))
+                }
+            }
+        }
+        if !rest.is_empty() {
+            return Err(EvalError::message(format!(
+                "transduce is missing a function after {}",
+                write_string(&rest[0])
+            )));
+        }
+        self.run_pipeline(&items, &steps, finish)
+    }
+
+    // (map-filter-reduce f pred reducer init list): reduces the (f x) that pred accepts,
+    // in one pass over list
+    pub fn builtin_map_filter_reduce(&mut self, args: &[Expr]) -> EvalResult {
+        let [f, pred, reducer, init, list] = args else {
+            return Err(EvalError::message(
+                "map-filter-reduce requires exactly 5 arguments",
+            ));
+        };
+        if ![f, pred, reducer].iter().all(|arg| Self::is_callable(arg)) {
+            return Err(EvalError::message(
+                "map-filter-reduce requires functions for f, pred and reducer",
+            ));
+        }
+        let items = Self::seq_items(list, "map-filter-reduce", "last")?;
+        self.run_pipeline(
+            &items,
+            &[Step::Map(f.clone()), Step::Filter(pred.clone())],
+            Finish::Reduce {
+                reducer: reducer.clone(),
+                init: Some(init.clone()),
+            },
+        )
+    }
+}
@@ -0,0 +1,253 @@
+use crate::interpreter::bigint::BigInt;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+
+// Arithmetic over whole numeric vectors. Each vector is unpacked into a Vec<i64> or a
+// Vec<f64> once, so the loops run on machine numbers rather than matching an Expr per
+// element. All-integer vectors stay exact, going through BigInt only for an element
+// that overflows; anything else is computed in floating point, as + and * would.
+
+enum Numbers {
+    Integers(Vec<i64>),
+    Floats(Vec<f64>),
+}
+
+impl Numbers {
+    fn len(&self) -> usize {
+        match self {
+            Numbers::Integers(values) => values.len(),
+            Numbers::Floats(values) => values.len(),
+        }
+    }
+
+    fn into_floats(self) -> Vec<f64> {
+        match self {
+            Numbers::Integers(values) => values.into_iter().map(|n| n as f64).collect(),
+            Numbers::Floats(values) => values,
+        }
+    }
+
+    fn into_exprs(self) -> Vec<Expr> {
+        match self {
+            Numbers::Integers(values) => values.into_iter().map(Expr::Integer).collect(),
+            Numbers::Floats(values) => values.into_iter().map(Expr::Float).collect(),
+        }
+    }
+
+    fn into_vector(self) -> Expr {
+        Expr::new_vector(self.into_exprs())
+    }
+}
+
+fn big_add(a: i64, b: i64) -> Expr {
+    Expr::from_bigint(&BigInt::from(a) + &BigInt::from(b))
+}
+
+fn big_mul(a: i64, b: i64) -> Expr {
+    Expr::from_bigint(&BigInt::from(a) * &BigInt::from(b))
+}
+
+// Folds exactly: i64 while it fits, then BigInt for the rest
+fn exact_total(terms: impl Iterator<Item = Expr>) -> Expr {
+    let mut total = Expr::Integer(0);
+    for term in terms {
+        total = match (&total, &term) {
+            (Expr::Integer(a), Expr::Integer(b)) => match a.checked_add(*b) {
+                Some(n) => Expr::Integer(n),
+                None => big_add(*a, *b),
+            },
+            _ => Expr::from_bigint(
+                &total.to_bigint().unwrap_or_else(BigInt::zero)
+                    + &term.to_bigint().unwrap_or_else(BigInt::zero),
+            ),
+        };
+    }
+    total
+}
+
+impl Evaluator {
+    fn numbers(arg: &Expr, name: &str) -> Result<Numbers, EvalError> {
+        let not_numeric = || EvalError::message(format!("{} requires numeric vectors", name));
+        let Expr::Vector(items) = arg else {
+            return Err(not_numeric());
+        };
+        let items = items.borrow();
+        let integers: Option<Vec<i64>> = items
+            .iter()
+            .map(|item| match item {
+                Expr::Integer(n) => Some(*n),
+                _ => None,
+            })
+            .collect();
+        if let Some(integers) = integers {
+            return Ok(Numbers::Integers(integers));
+        }
+        items
+            .iter()
+            .map(Self::to_number)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Numbers::Floats)
+            .map_err(|_| not_numeric())
+    }
+
+    fn same_length(a: &Numbers, b: &Numbers, name: &str) -> Result<(), EvalError> {
+        if a.len() == b.len() {
+            Ok(())
+        } else {
+            Err(EvalError::message(format!(
+                "{}: vector lengths {} and {} differ",
+                name,
+                a.len(),
+                b.len()
+            )))
+        }
+    }
+
+    // (vector-add v1 v2 ...): a new vector of the element-wise sums
+    pub fn builtin_vector_add(&mut self, args: &[Expr]) -> EvalResult {
+        if args.is_empty() {
+            return Err(EvalError::message(
+                "vector-add requires at least 1 argument",
+            ));
+        }
+        let mut sum = Self::numbers(&args[0], "vector-add")?;
+        for arg in &args[1..] {
+            let next = Self::numbers(arg, "vector-add")?;
+            Self::same_length(&sum, &next, "vector-add")?;
+            sum = match (sum, next) {
+                (Numbers::Integers(a), Numbers::Integers(b)) => {
+                    let exact: Option<Vec<i64>> =
+                        a.iter().zip(&b).map(|(x, y)| x.checked_add(*y)).collect();
+                    match exact {
+                        Some(values) => Numbers::Integers(values),
+                        // Overflow leaves the fast path for the rest of the call
+                        None => {
+                            let items: Vec<Expr> = a
+                                .iter()
+                                .zip(&b)
+                                .map(|(x, y)| {
+                                    x.checked_add(*y)
+                                        .map(Expr::Integer)
+                                        .unwrap_or_else(|| big_add(*x, *y))
+                                })
+                                .collect();
+                            return self.add_remaining(items, &args[2..]);
+                        }
+                    }
+                }
+                (a, b) => Numbers::Floats(
+                    a.into_floats()
+                        .iter()
+                        .zip(b.into_floats())
+                        .map(|(x, y)| x + y)
+                        .collect(),
+                ),
+            };
+        }
+        Ok(sum.into_vector())
+    }
+
+    // Element by element through +, once a sum no longer fits in i64
+    fn add_remaining(&mut self, mut sum: Vec<Expr>, rest: &[Expr]) -> EvalResult {
+        for arg in rest {
+            let next = Self::numbers(arg, "vector-add")?;
+            if next.len() != sum.len() {
+                return Err(EvalError::message(format!(
+                    "vector-add: vector lengths {} and {} differ",
+                    sum.len(),
+                    next.len()
+                )));
+            }
+            for (total, item) in sum.iter_mut().zip(next.into_exprs()) {
+                *total = self.apply_builtin("+", &[total.clone(), item])?;
+            }
+        }
+        Ok(Expr::new_vector(sum))
+    }
+
+    // (vector-scale v k): a new vector of each element times k
+    pub fn builtin_vector_scale(&mut self, args: &[Expr]) -> EvalResult {
+        let [vector, factor] = args else {
+            return Err(EvalError::message(
+                "vector-scale requires exactly 2 arguments",
+            ));
+        };
+        let values = Self::numbers(vector, "vector-scale")?;
+        match (values, factor) {
+            (Numbers::Integers(values), Expr::Integer(k)) => Ok(Expr::new_vector(
+                values
+                    .into_iter()
+                    .map(|n| {
+                        n.checked_mul(*k)
+                            .map(Expr::Integer)
+                            .unwrap_or_else(|| big_mul(n, *k))
+                    })
+                    .collect(),
+            )),
+            (values, factor) => {
+                let k = Self::to_number(factor)
+                    .map_err(|_| EvalError::message("vector-scale requires a numeric factor"))?;
+                Ok(
+                    Numbers::Floats(values.into_floats().into_iter().map(|n| n * k).collect())
+                        .into_vector(),
+                )
+            }
+        }
+    }
+
+    // (vector-dot v1 v2): the sum of the element-wise products
+    pub fn builtin_vector_dot(&mut self, args: &[Expr]) -> EvalResult {
+        let [a, b] = args else {
+            return Err(EvalError::message(
+                "vector-dot requires exactly 2 arguments",
+            ));
+        };
+        let (a, b) = (
+            Self::numbers(a, "vector-dot")?,
+            Self::numbers(b, "vector-dot")?,
+        );
+        Self::same_length(&a, &b, "vector-dot")?;
+        match (a, b) {
+            (Numbers::Integers(a), Numbers::Integers(b)) => {
+                let exact = a
+                    .iter()
+                    .zip(&b)
+                    .try_fold(0i64, |total, (x, y)| total.checked_add(x.checked_mul(*y)?));
+                Ok(match exact {
+                    Some(total) => Expr::Integer(total),
+                    None => exact_total(a.iter().zip(&b).map(|(x, y)| {
+                        x.checked_mul(*y)
+                            .map(Expr::Integer)
+                            .unwrap_or_else(|| big_mul(*x, *y))
+                    })),
+                })
+            }
+            (a, b) => Ok(Expr::Float(
+                a.into_floats()
+                    .iter()
+                    .zip(b.into_floats())
+                    .map(|(x, y)| x * y)
+                    .sum(),
+            )),
+        }
+    }
+
+    // (vector-sum v): the sum of the elements, 0 for an empty vector
+    pub fn builtin_vector_sum(&mut self, args: &[Expr]) -> EvalResult {
+        let [vector] = args else {
+            return Err(EvalError::message("vector-sum requires exactly 1 argument"));
+        };
+        match Self::numbers(vector, "vector-sum")? {
+            Numbers::Integers(values) => Ok(
+                match values
+                    .iter()
+                    .try_fold(0i64, |total, n| total.checked_add(*n))
+                {
+                    Some(total) => Expr::Integer(total),
+                    None => exact_total(values.into_iter().map(Expr::Integer)),
+                },
+            ),
+            Numbers::Floats(values) => Ok(Expr::Float(values.iter().sum())),
+        }
+    }
+}
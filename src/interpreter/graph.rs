@@ -0,0 +1,138 @@
+use crate::interpreter::batch::json_string;
+use crate::interpreter::xref::XrefIndex;
+use std::collections::BTreeSet;
+
+// `zeus graph`: the call graph between top-level definitions, or the dependency graph
+// between files, as Graphviz DOT or JSON.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+impl GraphFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "dot" => Some(GraphFormat::Dot),
+            "json" => Some(GraphFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+// Functions clustered by the file that defines them, with an edge for each use
+pub fn function_graph(index: &XrefIndex, format: GraphFormat) -> String {
+    let edges = index.call_edges();
+    match format {
+        GraphFormat::Dot => {
+            let mut out = String::from("digraph zeus {\n  node [shape=box];\n");
+            for (cluster, file) in files(index).iter().enumerate() {
+                out.push_str(&format!(
+                    "  subgraph cluster_{} {{\n    label={};\n",
+                    cluster,
+                    dot_id(file)
+                ));
+                let names: BTreeSet<&str> = index
+                    .definitions
+                    .iter()
+                    .filter(|d| d.location.file == *file)
+                    .map(|d| d.name.as_str())
+                    .collect();
+                for name in names {
+                    out.push_str(&format!("    {};\n", dot_id(name)));
+                }
+                out.push_str("  }\n");
+            }
+            out.push_str(&dot_edges(&edges));
+            out.push_str("}\n");
+            out
+        }
+        GraphFormat::Json => {
+            let nodes: Vec<String> = index
+                .definitions
+                .iter()
+                .map(|d| {
+                    format!(
+                        "{{\"name\": {}, \"kind\": {}, \"file\": {}, \"line\": {}}}",
+                        json_string(&d.name),
+                        json_string(&d.kind),
+                        json_string(&d.location.file),
+                        d.location.line
+                    )
+                })
+                .collect();
+            json_graph(&nodes, &edges)
+        }
+    }
+}
+
+// One node per file, with an edge when a file uses something another defines
+pub fn module_graph(index: &XrefIndex, format: GraphFormat) -> String {
+    let edges = index.module_edges();
+    let files = files(index);
+    match format {
+        GraphFormat::Dot => {
+            let mut out = String::from("digraph zeus {\n  node [shape=folder];\n");
+            for file in &files {
+                out.push_str(&format!("  {};\n", dot_id(file)));
+            }
+            out.push_str(&dot_edges(&edges));
+            out.push_str("}\n");
+            out
+        }
+        GraphFormat::Json => {
+            let nodes: Vec<String> = files
+                .iter()
+                .map(|file| format!("{{\"file\": {}}}", json_string(file)))
+                .collect();
+            json_graph(&nodes, &edges)
+        }
+    }
+}
+
+fn files(index: &XrefIndex) -> BTreeSet<&str> {
+    index
+        .roots
+        .keys()
+        .map(String::as_str)
+        .chain(index.definitions.iter().map(|d| d.location.file.as_str()))
+        .collect()
+}
+
+fn dot_edges(edges: &BTreeSet<(&str, &str)>) -> String {
+    edges
+        .iter()
+        .map(|(from, to)| format!("  {} -> {};\n", dot_id(from), dot_id(to)))
+        .collect()
+}
+
+fn json_graph(nodes: &[String], edges: &BTreeSet<(&str, &str)>) -> String {
+    let edges: Vec<String> = edges
+        .iter()
+        .map(|(from, to)| {
+            format!(
+                "{{\"from\": {}, \"to\": {}}}",
+                json_string(from),
+                json_string(to)
+            )
+        })
+        .collect();
+    format!(
+        "{{\n  \"nodes\": [{}],\n  \"edges\": [{}]\n}}\n",
+        json_items(nodes),
+        json_items(&edges)
+    )
+}
+
+fn json_items(items: &[String]) -> String {
+    if items.is_empty() {
+        String::new()
+    } else {
+        format!("\n    {}\n  ", items.join(",\n    "))
+    }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,264 @@
+use crate::interpreter::environment::{Environment, BUILTINS};
+use crate::interpreter::evaluator::SPECIAL_FORMS;
+use crate::interpreter::optimizer::bound_names;
+use crate::interpreter::prelude;
+use crate::interpreter::pretty::pretty_print;
+use crate::interpreter::printer::{PrintMode, Printer};
+use crate::interpreter::types::{Expr, Symbol, SymbolData};
+use crate::interpreter::xref::{Definition, Location};
+use std::collections::HashSet;
+
+// What a front-end can say about a name without evaluating anything: what kind of thing
+// it is, a function's parameters and docstring, a preview of its value, and where it's
+// defined. A docstring is a string that starts a function body with more forms after it,
+// as in (defun f (x) "Doc." body).
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    SpecialForm,
+    Builtin,
+    Function,
+    Variable,
+    Keyword,
+    Unbound,
+}
+
+impl SymbolKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            SymbolKind::SpecialForm => "special form",
+            SymbolKind::Builtin => "builtin",
+            SymbolKind::Function => "function",
+            SymbolKind::Variable => "variable",
+            SymbolKind::Keyword => "keyword",
+            SymbolKind::Unbound => "unbound",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolInfo {
+    pub name: String,
+    pub kind: SymbolKind,
+    // (name param...) for functions
+    pub signature: Option<String>,
+    pub doc: Option<String>,
+    // The value as written, cut short past a few levels and items; functions have none
+    pub value: Option<String>,
+    pub definition: Option<Location>,
+}
+
+// `definitions` are searched for where `name` is defined, the last one winning as it
+// would when the forms are loaded in order
+pub fn describe_symbol(env: &Environment, name: &str, definitions: &[Definition]) -> SymbolInfo {
+    let definition = definitions
+        .iter()
+        .rev()
+        .find(|definition| definition.name == name)
+        .map(|definition| definition.location.clone());
+    let mut info = SymbolInfo {
+        name: name.to_string(),
+        kind: SymbolKind::Unbound,
+        signature: None,
+        doc: None,
+        value: None,
+        definition,
+    };
+
+    if name.starts_with(':') {
+        info.kind = SymbolKind::Keyword;
+        return info;
+    }
+    // Special forms are dispatched on the name before any binding is looked at
+    if SPECIAL_FORMS.contains(&name) {
+        info.kind = SymbolKind::SpecialForm;
+        return info;
+    }
+    let Ok(value) = env.get(name) else {
+        return info;
+    };
+
+    if BUILTINS.contains(&name)
+        && matches!(&value, Expr::Symbol(SymbolData::Interned(symbol)) if symbol == name)
+    {
+        info.kind = SymbolKind::Builtin;
+    } else if let Some((params, body)) = lambda_parts(&value) {
+        info.kind = SymbolKind::Function;
+        info.signature = Some(signature(name, params));
+        info.doc = docstring(body);
+    } else {
+        info.kind = SymbolKind::Variable;
+        info.value = Some(
+            Printer::new(PrintMode::Write)
+                .with_max_depth(3)
+                .with_max_length(8)
+                .print(&value),
+        );
+    }
+    info
+}
+
+// A function value taken apart for the inspector
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionInfo {
+    // (param...)
+    pub params: String,
+    // The body as source, pretty-printed to `width`
+    pub body: String,
+    // Each name the body uses that it doesn't bind itself, in order of first use, with the
+    // value it has now. Scoping is dynamic, so these are what a call made now would see.
+    pub free_variables: Vec<(String, Option<Expr>)>,
+}
+
+// None unless `value` is a function
+pub fn describe_function(env: &Environment, value: &Expr, width: usize) -> Option<FunctionInfo> {
+    let (params, body) = lambda_parts(value)?;
+    let mut bound = HashSet::new();
+    bound_names(value, &mut bound);
+    let mut used = Vec::new();
+    used_names(body, &mut used);
+
+    let free_variables = used
+        .into_iter()
+        .filter(|name| !bound.contains(name))
+        .map(|name| name.to_string())
+        .filter(|name| {
+            !SPECIAL_FORMS.contains(&name.as_str()) && !matches!(name.as_str(), "t" | "nil")
+        })
+        .filter_map(|name| match env.get(&name) {
+            // Builtins still bound to themselves aren't state worth showing
+            Ok(Expr::Symbol(SymbolData::Interned(symbol)))
+                if symbol.as_str() == name && BUILTINS.contains(&name.as_str()) =>
+            {
+                None
+            }
+            Ok(value) => Some((name, Some(value))),
+            Err(_) => Some((name, None)),
+        })
+        .collect();
+
+    Some(FunctionInfo {
+        params: Printer::new(PrintMode::Write).print(&Expr::new_list(params.to_vec())),
+        body: pretty_print(body, width),
+        free_variables,
+    })
+}
+
+// The symbols `expr` evaluates, outside quoted data, each once
+fn used_names(expr: &Expr, into: &mut Vec<Symbol>) {
+    match expr {
+        Expr::Symbol(SymbolData::Interned(name)) if !into.contains(name) => into.push(*name),
+        // Quoted data isn't evaluated
+        Expr::List(items) if matches!(items.first(), Some(Expr::Symbol(SymbolData::Interned(head))) if head == "quote") =>
+            {}
+        Expr::List(items) => {
+            for item in items.iter() {
+                used_names(item, into);
+            }
+        }
+        Expr::Vector(items) => {
+            for item in items.borrow().iter() {
+                used_names(item, into);
+            }
+        }
+        _ => {}
+    }
+}
+
+// The parameter list and body of (lambda params body)
+fn lambda_parts(value: &Expr) -> Option<(&[Expr], &Expr)> {
+    let Expr::List(items) = value else {
+        return None;
+    };
+    match items.as_slice() {
+        [Expr::Symbol(SymbolData::Interned(head)), Expr::List(params), body]
+            if head == "lambda" =>
+        {
+            Some((params, body))
+        }
+        _ => None,
+    }
+}
+
+fn signature(name: &str, params: &[Expr]) -> String {
+    let mut signature = format!("({}", name);
+    for param in params {
+        signature.push(' ');
+        signature.push_str(&Printer::new(PrintMode::Write).print(param));
+    }
+    signature.push(')');
+    signature
+}
+
+// defun wraps a body of several forms in progn, so a docstring is the first of those
+fn docstring(body: &Expr) -> Option<String> {
+    let Expr::List(items) = body else {
+        return None;
+    };
+    match items.as_slice() {
+        [Expr::Symbol(SymbolData::Interned(head)), Expr::String(doc), _, ..] if head == "progn" => {
+            Some(doc.clone())
+        }
+        _ => None,
+    }
+}
+
+// One scope in the chain the debugger shows, outermost (the globals) first
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeInfo {
+    pub label: &'static str,
+    pub bindings: Vec<BindingInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindingInfo {
+    pub name: String,
+    // The value as written, cut short like a variable's preview
+    pub value: String,
+    // Bound again in a scope further in, so lookups don't see this one
+    pub shadowed: bool,
+}
+
+// The globals defined so far (not t or the prelude's, which every environment has), then
+// each local scope from the outermost in. Globals are sorted by name; locals keep the order they were bound.
+pub fn scope_chain(env: &Environment) -> Vec<ScopeInfo> {
+    let mut globals: Vec<(Symbol, &Expr)> = env
+        .globals()
+        .filter(|(symbol, value)| {
+            symbol.as_str() != "t" && !prelude::is_unchanged(symbol.as_str(), value)
+        })
+        .collect();
+    globals.sort_by_key(|(symbol, _)| symbol.as_str());
+    let mut scopes = vec![("global", globals)];
+    for (kind, bindings) in env.scopes() {
+        scopes.push((
+            kind.label(),
+            bindings
+                .iter()
+                .map(|(symbol, value)| (*symbol, value))
+                .collect(),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    let mut chain: Vec<ScopeInfo> = scopes
+        .into_iter()
+        .rev()
+        .map(|(label, bindings)| {
+            let bindings = bindings
+                .into_iter()
+                .map(|(symbol, value)| BindingInfo {
+                    name: symbol.to_string(),
+                    value: Printer::new(PrintMode::Write)
+                        .with_max_depth(3)
+                        .with_max_length(8)
+                        .print(value),
+                    shadowed: !seen.insert(symbol),
+                })
+                .collect();
+            ScopeInfo { label, bindings }
+        })
+        .collect();
+    chain.reverse();
+    chain
+}
@@ -0,0 +1,118 @@
+use crate::interpreter::types::Expr;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// The state behind `Expr::Iterator`: where an iteration over a collection or range has got
+// to. `next` hands out one item at a time and the iterator is used up once `is_done`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cursor {
+    // Lists share their storage; strings and hash tables are copied out when `iterate`
+    // starts, so changing them afterwards doesn't affect the iteration
+    Items {
+        items: Rc<Vec<Expr>>,
+        index: usize,
+    },
+    // Read live, so items pushed onto the vector while iterating are reached too
+    Vector {
+        vector: Rc<RefCell<Vec<Expr>>>,
+        index: usize,
+    },
+    // From `next` toward `end` (exclusive) by `step`, which is never zero
+    IntRange {
+        next: i64,
+        end: i64,
+        step: i64,
+    },
+    // start + index * step, so long ranges don't accumulate rounding error
+    FloatRange {
+        start: f64,
+        step: f64,
+        index: u64,
+        count: u64,
+    },
+}
+
+impl Cursor {
+    pub fn int_range(start: i64, end: i64, step: i64) -> Self {
+        Cursor::IntRange {
+            next: start,
+            end,
+            step,
+        }
+    }
+
+    pub fn float_range(start: f64, end: f64, step: f64) -> Self {
+        let count = ((end - start) / step).ceil();
+        Cursor::FloatRange {
+            start,
+            step,
+            index: 0,
+            count: if count > 0.0 { count as u64 } else { 0 },
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        match self {
+            Cursor::Items { items, index } => *index >= items.len(),
+            Cursor::Vector { vector, index } => *index >= vector.borrow().len(),
+            Cursor::IntRange { next, end, step } => {
+                if *step > 0 {
+                    next >= end
+                } else {
+                    next <= end
+                }
+            }
+            Cursor::FloatRange { index, count, .. } => index >= count,
+        }
+    }
+
+    // The next item, or None once the iterator is used up
+    pub fn advance(&mut self) -> Option<Expr> {
+        if self.is_done() {
+            return None;
+        }
+        match self {
+            Cursor::Items { items, index } => {
+                *index += 1;
+                Some(items[*index - 1].clone())
+            }
+            Cursor::Vector { vector, index } => {
+                *index += 1;
+                Some(vector.borrow()[*index - 1].clone())
+            }
+            Cursor::IntRange { next, end, step } => {
+                let value = *next;
+                // Past i64::MAX or MIN the range can only be over
+                *next = next.checked_add(*step).unwrap_or(*end);
+                Some(Expr::Integer(value))
+            }
+            Cursor::FloatRange {
+                start, step, index, ..
+            } => {
+                *index += 1;
+                Some(Expr::Float(*start + (*index - 1) as f64 * *step))
+            }
+        }
+    }
+}
+
+// A source of items for the sequence builtins (mapcar, filter, reduce, dolist and the like),
+// so they walk lists, vectors, strings, hash tables and iterators the same way. See
+// `Evaluator::seq_iter`.
+pub trait SeqIter {
+    // The next item, or None at the end
+    fn next_item(&mut self) -> Option<Expr>;
+}
+
+impl SeqIter for Cursor {
+    fn next_item(&mut self) -> Option<Expr> {
+        self.advance()
+    }
+}
+
+// An `Expr::Iterator` is shared, so taking items from it moves every reference along
+impl SeqIter for Rc<RefCell<Cursor>> {
+    fn next_item(&mut self) -> Option<Expr> {
+        self.borrow_mut().advance()
+    }
+}
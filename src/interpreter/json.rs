@@ -0,0 +1,282 @@
+use crate::interpreter::batch::json_string;
+use std::fmt;
+
+// Just enough JSON for `zeus lsp`: a parser for the messages editors send and a compact
+// writer for the replies. Objects keep their keys in order.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = JsonParser {
+            chars: text.chars().collect(),
+            index: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.index < parser.chars.len() {
+            return Err(format!("unexpected text at offset {}", parser.index));
+        }
+        Ok(value)
+    }
+
+    pub fn object(pairs: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(text: impl Into<String>) -> Json {
+        Json::String(text.into())
+    }
+
+    // The value under `key`, or None if this isn't an object or has no such key
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(pairs) => pairs
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    // Follows a chain of object keys
+    pub fn path(&self, keys: &[&str]) -> Option<&Json> {
+        keys.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(text) => write!(f, "{}", json_string(text)),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(pairs) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in pairs.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", json_string(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl JsonParser {
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.index)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.index += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.chars.get(self.index) == Some(&expected) {
+            self.index += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at offset {}", expected, self.index))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        let end = self.index + word.chars().count();
+        if end <= self.chars.len() && self.chars[self.index..end].iter().copied().eq(word.chars()) {
+            self.index = end;
+            Ok(value)
+        } else {
+            Err(format!("unexpected text at offset {}", self.index))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.index) {
+            None => Err("unexpected end of JSON".to_string()),
+            Some('n') => self.literal("null", Json::Null),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.index += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.index) == Some(&']') {
+                    self.index += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.chars.get(self.index) {
+                        Some(',') => self.index += 1,
+                        Some(']') => {
+                            self.index += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(format!("expected ',' or ']' at offset {}", self.index)),
+                    }
+                }
+            }
+            Some('{') => {
+                self.index += 1;
+                let mut pairs = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.index) == Some(&'}') {
+                    self.index += 1;
+                    return Ok(Json::Object(pairs));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.chars.get(self.index) != Some(&'"') {
+                        return Err(format!("expected a key at offset {}", self.index));
+                    }
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    pairs.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.chars.get(self.index) {
+                        Some(',') => self.index += 1,
+                        Some('}') => {
+                            self.index += 1;
+                            return Ok(Json::Object(pairs));
+                        }
+                        _ => return Err(format!("expected ',' or '}}' at offset {}", self.index)),
+                    }
+                }
+            }
+            Some(_) => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.index;
+        while self
+            .chars
+            .get(self.index)
+            .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+        {
+            self.index += 1;
+        }
+        let text: String = self.chars[start..self.index].iter().collect();
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| format!("bad number at offset {}", start))
+    }
+
+    // A string starting at the opening quote
+    fn string(&mut self) -> Result<String, String> {
+        self.index += 1;
+        let mut text = String::new();
+        loop {
+            let Some(&ch) = self.chars.get(self.index) else {
+                return Err("unterminated string".to_string());
+            };
+            self.index += 1;
+            match ch {
+                '"' => return Ok(text),
+                '\\' => {
+                    let Some(&escape) = self.chars.get(self.index) else {
+                        return Err("unterminated string".to_string());
+                    };
+                    self.index += 1;
+                    match escape {
+                        'n' => text.push('\n'),
+                        't' => text.push('\t'),
+                        'r' => text.push('\r'),
+                        'b' => text.push('\u{8}'),
+                        'f' => text.push('\u{c}'),
+                        'u' => {
+                            let unit = self.hex4()?;
+                            // A surrogate pair spells one character outside the BMP
+                            let code = if (0xD800..0xDC00).contains(&unit)
+                                && self.chars.get(self.index) == Some(&'\\')
+                                && self.chars.get(self.index + 1) == Some(&'u')
+                            {
+                                self.index += 2;
+                                let low = self.hex4()?;
+                                0x10000
+                                    + ((unit - 0xD800) << 10)
+                                    + (low.wrapping_sub(0xDC00) & 0x3FF)
+                            } else {
+                                unit
+                            };
+                            text.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        }
+                        other => text.push(other),
+                    }
+                }
+                other => text.push(other),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.iter().skip(self.index).take(4).collect();
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("bad \\u escape at offset {}", self.index));
+        }
+        let value = u32::from_str_radix(&digits, 16).unwrap_or_default();
+        self.index += 4;
+        Ok(value)
+    }
+}
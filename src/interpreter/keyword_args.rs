@@ -0,0 +1,100 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::types::{EvalError, Expr, SymbolData};
+use std::ops::RangeInclusive;
+
+// Keyword arguments to builtins. A builtin takes its positional arguments first and then
+// :name value pairs, in any order, as in (make-hash-table :size 100) or
+// (sort people < :key age). Each builtin names the keywords it accepts; an unknown one, a
+// keyword with no value or a value of the wrong kind is an error naming the builtin. A
+// keyword given twice takes its first value.
+
+pub struct KeywordArgs<'a> {
+    name: &'a str,
+    pairs: Vec<(&'static str, &'a Expr)>,
+}
+
+// Splits `args` into the positional arguments of the builtin `name` and its keyword
+// arguments. Positional arguments past the first `positional.start()` are optional; they
+// end at the first of `keywords`.
+pub fn parse_keyword_args<'a>(
+    args: &'a [Expr],
+    name: &'a str,
+    positional: RangeInclusive<usize>,
+    keywords: &[&str],
+) -> Result<(&'a [Expr], KeywordArgs<'a>), EvalError> {
+    let (min, max) = positional.into_inner();
+    if args.len() < min {
+        return Err(EvalError::message(format!(
+            "{} requires at least {} arguments",
+            name, min
+        )));
+    }
+    let accepted = |arg: &Expr| match arg {
+        Expr::Symbol(SymbolData::Keyword(keyword)) => keywords.contains(&keyword.as_str()),
+        _ => false,
+    };
+    let mut count = min;
+    while count < max.min(args.len()) && !accepted(&args[count]) {
+        count += 1;
+    }
+
+    let (positional, rest) = args.split_at(count);
+    let mut pairs = Vec::new();
+    for pair in rest.chunks(2) {
+        let keyword = match &pair[0] {
+            Expr::Symbol(SymbolData::Keyword(keyword)) if accepted(&pair[0]) => keyword.as_str(),
+            other => {
+                let expected: Vec<String> =
+                    keywords.iter().map(|word| format!(":{}", word)).collect();
+                return Err(EvalError::message(format!(
+                    "{} expected {} but got {}",
+                    name,
+                    expected.join(" or "),
+                    write_string(other)
+                )));
+            }
+        };
+        let Some(value) = pair.get(1) else {
+            return Err(EvalError::message(format!(
+                "{} is missing a value for :{}",
+                name, keyword
+            )));
+        };
+        pairs.push((keyword, value));
+    }
+    Ok((positional, KeywordArgs { name, pairs }))
+}
+
+impl<'a> KeywordArgs<'a> {
+    pub fn get(&self, keyword: &str) -> Option<&'a Expr> {
+        self.pairs
+            .iter()
+            .find(|(name, _)| *name == keyword)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn function(&self, keyword: &str) -> Result<Option<&'a Expr>, EvalError> {
+        match self.get(keyword) {
+            Some(value) if !Evaluator::is_callable(value) => Err(EvalError::message(format!(
+                "{} requires a function for :{}",
+                self.name, keyword
+            ))),
+            value => Ok(value),
+        }
+    }
+
+    // A non-negative integer, such as a size or a count
+    pub fn count(&self, keyword: &str) -> Result<Option<usize>, EvalError> {
+        match self.get(keyword) {
+            None => Ok(None),
+            Some(Expr::Integer(n)) if *n >= 0 => Ok(Some(*n as usize)),
+            Some(other) => Err(EvalError::message(format!(
+                "{} requires a non-negative integer for :{}, got {}",
+                self.name,
+                keyword,
+                write_string(other)
+            ))),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::interpreter::completion::complete;
+use std::io::{self, BufRead, Read, Write};
+use std::process::{Command, Stdio};
+
+// Line input for the terminal REPL and the remote client. On a terminal, each line is read
+// with the terminal out of canonical mode (set with stty, and put back before the line is
+// evaluated) so Tab can complete; the line can be edited with Backspace, the arrow keys,
+// Home / End and Ctrl-A / Ctrl-E, and Up / Down step through the earlier lines. Anywhere
+// else lines are read as they come.
+
+// How many earlier lines a History keeps
+pub const MAX_HISTORY: usize = 500;
+
+// What reading a line ended with
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineEvent {
+    Line(String),
+    // Ctrl-D on an empty line, or the end of the input
+    Eof,
+    // Ctrl-C
+    Interrupt,
+}
+
+// The lines entered so far, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    lines: Vec<String>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Blank lines and repeats of the last line aren't kept
+    pub fn add(&mut self, line: &str) {
+        if line.trim().is_empty() || self.lines.last().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.lines.len() == MAX_HISTORY {
+            self.lines.remove(0);
+        }
+        self.lines.push(line.to_string());
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+// Reads a line from stdin, adding it to `history`
+pub fn read_line(prompt: &str, history: &mut History) -> io::Result<LineEvent> {
+    let mut stdout = io::stdout();
+    let event = match RawMode::enter() {
+        Some(_raw) => edit_line(&mut io::stdin().lock(), &mut stdout, prompt, history)?,
+        None => {
+            write!(stdout, "{}", prompt)?;
+            stdout.flush()?;
+            let mut line = String::new();
+            match io::stdin().lock().read_line(&mut line)? {
+                0 => LineEvent::Eof,
+                _ => LineEvent::Line(line.trim_end_matches(['\n', '\r']).to_string()),
+            }
+        }
+    };
+    if let LineEvent::Line(line) = &event {
+        history.add(line);
+    }
+    Ok(event)
+}
+
+// Puts the terminal back the way it was when dropped
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    // None when stdin isn't a terminal stty can change
+    fn enter() -> Option<RawMode> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1", "time", "0"])?;
+        Some(RawMode {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Reads keys from `input`, echoing the line being edited to `output`
+pub fn edit_line(
+    input: &mut impl Read,
+    output: &mut impl Write,
+    prompt: &str,
+    history: &History,
+) -> io::Result<LineEvent> {
+    let mut line: Vec<char> = Vec::new();
+    let mut cursor = 0;
+    // Which history line is shown (the length of the history for the new line), and the
+    // new line as it was before Up replaced it
+    let mut shown = history.lines.len();
+    let mut draft: Vec<char> = Vec::new();
+    redraw(output, prompt, &line, cursor)?;
+    loop {
+        let Some(byte) = read_byte(input)? else {
+            return Ok(if line.is_empty() {
+                LineEvent::Eof
+            } else {
+                LineEvent::Line(line.into_iter().collect())
+            });
+        };
+        match byte {
+            b'\r' | b'\n' => {
+                write!(output, "\r\n")?;
+                return Ok(LineEvent::Line(line.into_iter().collect()));
+            }
+            // Ctrl-C
+            3 => {
+                write!(output, "\r\n")?;
+                return Ok(LineEvent::Interrupt);
+            }
+            // Ctrl-D
+            4 if line.is_empty() => {
+                write!(output, "\r\n")?;
+                return Ok(LineEvent::Eof);
+            }
+            // Ctrl-A and Ctrl-E
+            1 => cursor = 0,
+            5 => cursor = line.len(),
+            b'\t' => complete_at(output, &mut line, &mut cursor)?,
+            127 | 8 if cursor > 0 => {
+                cursor -= 1;
+                line.remove(cursor);
+            }
+            27 => match escape_sequence(input)?.as_str() {
+                "[D" if cursor > 0 => cursor -= 1,
+                "[C" if cursor < line.len() => cursor += 1,
+                "[H" | "OH" | "[1~" => cursor = 0,
+                "[F" | "OF" | "[4~" => cursor = line.len(),
+                "[3~" if cursor < line.len() => {
+                    line.remove(cursor);
+                }
+                "[A" | "OA" if shown > 0 => {
+                    if shown == history.lines.len() {
+                        draft = std::mem::take(&mut line);
+                    }
+                    shown -= 1;
+                    line = history.lines[shown].chars().collect();
+                    cursor = line.len();
+                }
+                "[B" | "OB" if shown < history.lines.len() => {
+                    shown += 1;
+                    line = match history.lines.get(shown) {
+                        Some(earlier) => earlier.chars().collect(),
+                        None => std::mem::take(&mut draft),
+                    };
+                    cursor = line.len();
+                }
+                _ => {}
+            },
+            byte if byte >= 0x20 => {
+                if let Some(ch) = read_char(input, byte)? {
+                    line.insert(cursor, ch);
+                    cursor += 1;
+                }
+            }
+            _ => {}
+        }
+        redraw(output, prompt, &line, cursor)?;
+    }
+}
+
+// Tab: extend the text before the cursor as far as the candidates agree, otherwise list
+// them under the line
+fn complete_at(
+    output: &mut impl Write,
+    line: &mut Vec<char>,
+    cursor: &mut usize,
+) -> io::Result<()> {
+    let text: String = line.iter().collect();
+    let byte_cursor: usize = line[..*cursor].iter().map(|ch| ch.len_utf8()).sum();
+    let Some(completion) = complete(&text, byte_cursor) else {
+        return write!(output, "\x07");
+    };
+    if completion.candidates.is_empty() {
+        return write!(output, "\x07");
+    }
+    match completion.extend(&text, byte_cursor) {
+        Some((edited, edited_cursor)) => {
+            *cursor = edited[..edited_cursor].chars().count();
+            *line = edited.chars().collect();
+        }
+        None => {
+            let labels = completion.labels(&text, byte_cursor);
+            write!(output, "\r\n{}\r\n", labels.join("  "))?;
+        }
+    }
+    Ok(())
+}
+
+fn redraw(output: &mut impl Write, prompt: &str, line: &[char], cursor: usize) -> io::Result<()> {
+    let text: String = line.iter().collect();
+    write!(output, "\r{}{}\x1b[K", prompt, text)?;
+    if cursor < line.len() {
+        write!(output, "\x1b[{}D", line.len() - cursor)?;
+    }
+    output.flush()
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0u8];
+    match input.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+// The rest of a UTF-8 character that starts with `first`
+fn read_char(input: &mut impl Read, first: u8) -> io::Result<Option<char>> {
+    let length = match first {
+        0xf0.. => 4,
+        0xe0.. => 3,
+        0xc0.. => 2,
+        _ => 1,
+    };
+    let mut bytes = vec![first];
+    for _ in 1..length {
+        match read_byte(input)? {
+            Some(byte) => bytes.push(byte),
+            None => break,
+        }
+    }
+    Ok(std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|text| text.chars().next()))
+}
+
+// The bytes after an escape: '[' or 'O', any digits, then the final character
+fn escape_sequence(input: &mut impl Read) -> io::Result<String> {
+    let mut sequence = String::new();
+    while let Some(byte) = read_byte(input)? {
+        sequence.push(byte as char);
+        if sequence.len() > 1 && !byte.is_ascii_digit() && byte != b';' {
+            break;
+        }
+    }
+    Ok(sequence)
+}
@@ -0,0 +1,553 @@
+use crate::interpreter::batch::{split_forms, SourceForm};
+use crate::interpreter::check::{check_source, CheckConfig, Severity};
+use crate::interpreter::environment::BUILTINS;
+use crate::interpreter::evaluator::{Evaluator, SPECIAL_FORMS};
+use crate::interpreter::introspect::{describe_symbol, SymbolKind};
+use crate::interpreter::json::Json;
+use crate::interpreter::pretty::{pretty_print, DEFAULT_PRETTY_WIDTH};
+use crate::interpreter::xref::{advance, definition_head, name_position, XrefIndex};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+// `zeus lsp`: a Language Server Protocol server on stdin/stdout, so editors that speak LSP
+// get the `zeus check` findings as diagnostics, completion of builtins and the document's
+// definitions, hover, an outline and formatting. Documents are synced whole on each
+// change. Nothing in them runs except defun and defmacro forms, which are loaded into a
+// scratch evaluator so hover can show signatures and docstrings.
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// LSP enumerations
+const SYNC_FULL: f64 = 1.0;
+const COMPLETION_FUNCTION: f64 = 3.0;
+const COMPLETION_VARIABLE: f64 = 6.0;
+const COMPLETION_KEYWORD: f64 = 14.0;
+const SYMBOL_FUNCTION: f64 = 12.0;
+const SYMBOL_VARIABLE: f64 = 13.0;
+
+#[derive(Default)]
+pub struct LspServer {
+    // Open documents by URI
+    documents: HashMap<String, String>,
+    shutdown_requested: bool,
+    exit_code: Option<i32>,
+}
+
+impl LspServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Set once the client sends `exit`: 0 after a `shutdown` request, 1 without one
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    // Everything to send back for one message from the client: the response to a request,
+    // or the notifications a document change produces
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let params = message.get("params").unwrap_or(&Json::Null);
+        match (
+            message.get("method").and_then(Json::as_str),
+            message.get("id"),
+        ) {
+            (Some(method), Some(id)) => vec![self.request(method, params, id.clone())],
+            (Some(method), None) => self.notification(method, params),
+            // A response; this server never sends requests
+            (None, _) => Vec::new(),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Json, id: Json) -> Json {
+        let result = match method {
+            "initialize" => capabilities(),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Json::Null
+            }
+            "textDocument/completion" => self.completion(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/formatting" => self.formatting(params),
+            _ => {
+                return error_response(
+                    id,
+                    METHOD_NOT_FOUND,
+                    &format!("unsupported method {}", method),
+                )
+            }
+        };
+        Json::object(vec![
+            ("jsonrpc", Json::string("2.0")),
+            ("id", id),
+            ("result", result),
+        ])
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params
+            .path(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .unwrap_or_default()
+            .to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params
+                    .path(&["textDocument", "text"])
+                    .and_then(Json::as_str);
+                self.documents
+                    .insert(uri.clone(), text.unwrap_or_default().to_string());
+                vec![self.diagnostics(&uri)]
+            }
+            "textDocument/didChange" => {
+                // With full sync the last change holds the whole new text
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                match text {
+                    Some(text) => {
+                        self.documents.insert(uri.clone(), text.to_string());
+                        vec![self.diagnostics(&uri)]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                vec![publish_diagnostics(&uri, Vec::new())]
+            }
+            "exit" => {
+                self.exit_code = Some(if self.shutdown_requested { 0 } else { 1 });
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // The URI and text of the open document a request is about
+    fn document<'a>(&'a self, params: &Json) -> Option<(&'a str, &'a str)> {
+        let uri = params
+            .path(&["textDocument", "uri"])
+            .and_then(Json::as_str)?;
+        self.documents
+            .get_key_value(uri)
+            .map(|(uri, text)| (uri.as_str(), text.as_str()))
+    }
+
+    fn diagnostics(&self, uri: &str) -> Json {
+        let text = self.documents.get(uri).map(String::as_str).unwrap_or("");
+        let path = uri_to_path(uri);
+        let config = path
+            .as_ref()
+            .and_then(|path| path.parent())
+            .and_then(|dir| CheckConfig::load(dir).ok())
+            .unwrap_or_default();
+        let file = path
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| uri.to_string());
+        let diagnostics = check_source(&file, text, &config)
+            .into_iter()
+            .map(|diagnostic| {
+                Json::object(vec![
+                    (
+                        "range",
+                        range(
+                            text,
+                            (diagnostic.line, diagnostic.column),
+                            (diagnostic.end_line, diagnostic.end_column),
+                        ),
+                    ),
+                    (
+                        "severity",
+                        Json::Number(match diagnostic.severity {
+                            Severity::Error => 1.0,
+                            Severity::Warning => 2.0,
+                        }),
+                    ),
+                    ("code", Json::string(diagnostic.rule)),
+                    ("source", Json::string("zeus")),
+                    ("message", Json::string(diagnostic.message)),
+                ])
+            })
+            .collect();
+        publish_diagnostics(uri, diagnostics)
+    }
+
+    // Builtins, special forms and the document's own definitions that start with what has
+    // been typed of the symbol before the cursor
+    fn completion(&self, params: &Json) -> Json {
+        let Some((uri, text)) = self.document(params) else {
+            return Json::Array(Vec::new());
+        };
+        let prefix = match cursor_line(text, params) {
+            Some((_, line, offset)) => match symbol_around(line, offset) {
+                Some((start, _)) if start < offset => &line[start..offset],
+                _ => "",
+            },
+            None => "",
+        };
+
+        let index = XrefIndex::build(&[(uri.to_string(), text.to_string())]);
+        let definitions = index.definitions.iter().map(|definition| {
+            let kind = if definition.kind == "define" {
+                COMPLETION_VARIABLE
+            } else {
+                COMPLETION_FUNCTION
+            };
+            (definition.name.as_str(), kind, definition.kind.as_str())
+        });
+        let special_forms = SPECIAL_FORMS
+            .iter()
+            .map(|name| (*name, COMPLETION_KEYWORD, "special form"));
+        let builtins = BUILTINS
+            .iter()
+            .map(|name| (*name, COMPLETION_FUNCTION, "builtin"));
+
+        let mut seen = HashSet::new();
+        let items = definitions
+            .chain(special_forms)
+            .chain(builtins)
+            .filter(|(name, _, _)| name.starts_with(prefix) && seen.insert(*name))
+            .map(|(name, kind, detail)| {
+                Json::object(vec![
+                    ("label", Json::string(name)),
+                    ("kind", Json::Number(kind)),
+                    ("detail", Json::string(detail)),
+                ])
+            })
+            .collect();
+        Json::Array(items)
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let Some((uri, text)) = self.document(params) else {
+            return Json::Null;
+        };
+        let Some((line_index, line, offset)) = cursor_line(text, params) else {
+            return Json::Null;
+        };
+        let Some((start, end)) = symbol_around(line, offset) else {
+            return Json::Null;
+        };
+        let name = &line[start..end];
+
+        let evaluator = load_definitions(text);
+        let index = XrefIndex::build(&[(uri.to_string(), text.to_string())]);
+        let info = describe_symbol(evaluator.get_environment(), name, &index.definitions);
+        if info.kind == SymbolKind::Unbound && info.definition.is_none() {
+            return Json::Null;
+        }
+
+        let heading = info.signature.as_deref().unwrap_or(name);
+        let mut sections = vec![format!("```lisp\n{}\n```\n{}", heading, info.kind.label())];
+        if let Some(doc) = &info.doc {
+            sections.push(doc.clone());
+        }
+        if let Some(value) = &info.value {
+            sections.push(format!("```lisp\n{}\n```", value));
+        }
+        if let Some(location) = &info.definition {
+            sections.push(format!("Defined on line {}", location.line));
+        }
+        let column = |byte: usize| line[..byte].chars().count() + 1;
+        Json::object(vec![
+            (
+                "contents",
+                Json::object(vec![
+                    ("kind", Json::string("markdown")),
+                    ("value", Json::string(sections.join("\n\n"))),
+                ]),
+            ),
+            (
+                "range",
+                range(
+                    text,
+                    (line_index + 1, column(start)),
+                    (line_index + 1, column(end)),
+                ),
+            ),
+        ])
+    }
+
+    // The document's top-level definitions, for an editor's outline
+    fn document_symbols(&self, params: &Json) -> Json {
+        let Some((_, text)) = self.document(params) else {
+            return Json::Array(Vec::new());
+        };
+        let (forms, _) = split_forms(text);
+        let symbols = forms
+            .iter()
+            .filter_map(|form| {
+                let expr = Evaluator::parse(&form.source).ok()?;
+                let (kind, name) = definition_head(&expr)?;
+                let (line, column) = name_position(form, name);
+                Some(Json::object(vec![
+                    ("name", Json::string(name)),
+                    ("detail", Json::string(kind)),
+                    (
+                        "kind",
+                        Json::Number(if kind == "define" {
+                            SYMBOL_VARIABLE
+                        } else {
+                            SYMBOL_FUNCTION
+                        }),
+                    ),
+                    ("range", form_range(text, form)),
+                    (
+                        "selectionRange",
+                        range(text, (line, column), (line, column + name.chars().count())),
+                    ),
+                ]))
+            })
+            .collect();
+        Json::Array(symbols)
+    }
+
+    // Lays out each top-level form with the pretty printer. Forms with comments inside, or
+    // that don't start a line, are left alone since reprinting would lose or misplace text.
+    fn formatting(&self, params: &Json) -> Json {
+        let Some((_, text)) = self.document(params) else {
+            return Json::Array(Vec::new());
+        };
+        let (forms, _) = split_forms(text);
+        let edits = forms
+            .iter()
+            .filter(|form| form.column == 1 && !form.source.contains(';'))
+            .filter_map(|form| {
+                let expr = Evaluator::parse(&form.source).ok()?;
+                let formatted = pretty_print(&expr, DEFAULT_PRETTY_WIDTH);
+                // Only rewrite when the new text reads back as the same form
+                if formatted == form.source || Evaluator::parse(&formatted).ok()? != expr {
+                    return None;
+                }
+                Some(Json::object(vec![
+                    ("range", form_range(text, form)),
+                    ("newText", Json::string(formatted)),
+                ]))
+            })
+            .collect();
+        Json::Array(edits)
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("textDocumentSync", Json::Number(SYNC_FULL)),
+                ("completionProvider", Json::object(vec![])),
+                ("hoverProvider", Json::Bool(true)),
+                ("documentSymbolProvider", Json::Bool(true)),
+                ("documentFormattingProvider", Json::Bool(true)),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![
+                ("name", Json::string("zeus")),
+                ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+            ]),
+        ),
+    ])
+}
+
+fn error_response(id: Json, code: i64, message: &str) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("id", id),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::Number(code as f64)),
+                ("message", Json::string(message)),
+            ]),
+        ),
+    ])
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("method", Json::string("textDocument/publishDiagnostics")),
+        (
+            "params",
+            Json::object(vec![
+                ("uri", Json::string(uri)),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ),
+    ])
+}
+
+// A local path for file:// URIs, so zeus.toml can be found next to the document
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::new();
+    let mut rest = encoded.as_bytes();
+    while let [first, more @ ..] = rest {
+        let escaped = match more {
+            [high, low, ..] if *first == b'%' => std::str::from_utf8(&[*high, *low])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                bytes.push(byte);
+                rest = &more[2..];
+            }
+            None => {
+                bytes.push(*first);
+                rest = more;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+// LSP positions count lines from 0 and characters in UTF-16 code units; zeus counts both
+// from 1, with columns in characters
+fn position(text: &str, line: usize, column: usize) -> Json {
+    let character: usize = text
+        .lines()
+        .nth(line.saturating_sub(1))
+        .unwrap_or("")
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum();
+    Json::object(vec![
+        ("line", Json::Number(line.saturating_sub(1) as f64)),
+        ("character", Json::Number(character as f64)),
+    ])
+}
+
+fn range(text: &str, start: (usize, usize), end: (usize, usize)) -> Json {
+    Json::object(vec![
+        ("start", position(text, start.0, start.1)),
+        ("end", position(text, end.0, end.1)),
+    ])
+}
+
+fn form_range(text: &str, form: &SourceForm) -> Json {
+    let end = advance(form.line, form.column, &form.source);
+    range(text, (form.line, form.column), end)
+}
+
+// The 0-based line a request's `position` is on, that line's text and the byte offset of
+// the position within it
+fn cursor_line<'a>(text: &'a str, params: &Json) -> Option<(usize, &'a str, usize)> {
+    let position = params.get("position")?;
+    let line_index = position.get("line")?.as_usize()?;
+    let character = position.get("character")?.as_usize()?;
+    let line = text.lines().nth(line_index).unwrap_or("");
+    let mut units = 0;
+    let offset = line
+        .char_indices()
+        .find(|(_, ch)| {
+            let past = units >= character;
+            units += ch.len_utf16();
+            past
+        })
+        .map_or(line.len(), |(offset, _)| offset);
+    Some((line_index, line, offset))
+}
+
+fn is_symbol_char(ch: char) -> bool {
+    !ch.is_whitespace() && !"()[]\";".contains(ch)
+}
+
+// Byte range of the symbol at `offset` in `line`, or of the one ending there
+fn symbol_around(line: &str, offset: usize) -> Option<(usize, usize)> {
+    let start = line[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| is_symbol_char(*ch))
+        .last()
+        .map_or(offset, |(index, _)| index);
+    let end = line[offset..]
+        .char_indices()
+        .find(|(_, ch)| !is_symbol_char(*ch))
+        .map_or(line.len(), |(index, _)| offset + index);
+    (start < end).then_some((start, end))
+}
+
+// A fresh evaluator with the document's functions and macros defined. Nothing else is
+// evaluated, so opening a file never runs its top-level code.
+fn load_definitions(text: &str) -> Evaluator {
+    let mut evaluator = Evaluator::new();
+    evaluator.allow_processes = false;
+    evaluator.allow_network = false;
+    let (forms, _) = split_forms(text);
+    for form in &forms {
+        let is_function = Evaluator::parse(&form.source)
+            .is_ok_and(|expr| matches!(definition_head(&expr), Some(("defun" | "defmacro", _))));
+        if is_function {
+            let _ = evaluator.eval_str(&form.source);
+        }
+    }
+    evaluator
+}
+
+// One message body from a Content-Length framed stream, or None at end of input
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; length.unwrap_or(0)];
+    reader.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+// Serves one client on stdin/stdout until it sends `exit`; returns the process exit code
+pub fn run_stdio() -> i32 {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let mut stdout = io::stdout();
+    let mut server = LspServer::new();
+    while let Ok(Some(body)) = read_message(&mut reader) {
+        let replies = match Json::parse(&body) {
+            Ok(message) => server.handle(&message),
+            Err(error) => vec![error_response(Json::Null, PARSE_ERROR, &error)],
+        };
+        for reply in &replies {
+            if write_message(&mut stdout, reply).is_err() {
+                return 1;
+            }
+        }
+        if let Some(code) = server.exit_code() {
+            return code;
+        }
+    }
+    // The client closed the stream without sending exit
+    1
+}
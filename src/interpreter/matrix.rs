@@ -0,0 +1,83 @@
+// A dense two-dimensional matrix of floats, stored row after row in one buffer. Programs
+// hold it as `Expr::Matrix`, shared like a vector so matrix-set! updates it in place.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f64>,
+}
+
+impl Matrix {
+    pub fn new(rows: usize, cols: usize, fill: f64) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![fill; rows * cols],
+        }
+    }
+
+    // Rows of equal length; None when they differ
+    pub fn from_rows(rows: &[Vec<f64>]) -> Option<Self> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != cols) {
+            return None;
+        }
+        Some(Self {
+            rows: rows.len(),
+            cols,
+            data: rows.concat(),
+        })
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<f64> {
+        (row < self.rows && col < self.cols).then(|| self.data[row * self.cols + col])
+    }
+
+    // False when the position is out of range
+    pub fn set(&mut self, row: usize, col: usize, value: f64) -> bool {
+        if row < self.rows && col < self.cols {
+            self.data[row * self.cols + col] = value;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[f64] {
+        &self.data[row * self.cols..(row + 1) * self.cols]
+    }
+
+    // "2x3"
+    pub fn shape(&self) -> String {
+        format!("{}x{}", self.rows, self.cols)
+    }
+
+    // None unless self has as many columns as `other` has rows
+    pub fn multiply(&self, other: &Matrix) -> Option<Matrix> {
+        if self.cols != other.rows {
+            return None;
+        }
+        let mut product = Matrix::new(self.rows, other.cols, 0.0);
+        for row in 0..self.rows {
+            let out = &mut product.data[row * other.cols..(row + 1) * other.cols];
+            // Walking `other` a row at a time keeps the inner loop on contiguous memory
+            for (k, &scale) in self.row(row).iter().enumerate() {
+                for (cell, &value) in out.iter_mut().zip(other.row(k)) {
+                    *cell += scale * value;
+                }
+            }
+        }
+        Some(product)
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut result = Matrix::new(self.cols, self.rows, 0.0);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                result.data[col * self.rows + row] = self.data[row * self.cols + col];
+            }
+        }
+        result
+    }
+}
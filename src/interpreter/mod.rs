@@ -1,17 +1,67 @@
+pub mod allocations;
+pub mod batch;
+pub mod bench;
+pub mod bigint;
+pub mod check;
+pub mod completion;
+pub mod doctest;
 pub mod environment;
 pub mod evaluator;
 pub mod evaluator_builtins;
 pub mod evaluator_builtins_cont;
+pub mod evaluator_generics;
+pub mod evaluator_iterators;
+pub mod evaluator_lists;
+pub mod evaluator_matrix;
+pub mod evaluator_net;
+pub mod evaluator_os;
+pub mod evaluator_ports;
+pub mod evaluator_queues;
+pub mod evaluator_regex;
 pub mod evaluator_special_forms;
+pub mod evaluator_stats;
+pub mod evaluator_strings;
+pub mod evaluator_structs;
+pub mod evaluator_transduce;
+pub mod evaluator_vector_math;
+pub mod graph;
+pub mod introspect;
+pub mod iterator;
+pub mod json;
+pub mod keyword_args;
+pub mod line_editor;
+pub mod lsp;
+pub mod matrix;
+pub mod net;
+pub mod optimizer;
+pub mod ordered_map;
+pub mod packages;
 pub mod parser;
+pub mod path_completion;
+pub mod ports;
+pub mod prelude;
+pub mod pretty;
+pub mod printer;
+pub mod profile;
+pub mod recording;
+pub mod remote;
 pub mod repl;
+pub mod session;
+pub mod snapshot;
+pub mod source_map;
+pub mod symbol_map;
 pub mod tokenizer;
+pub mod typecheck;
 pub mod types;
+pub mod xref;
 
 // Re-export the main public types and structs
+pub use bigint::BigInt;
 pub use environment::Environment;
-pub use evaluator::Evaluator;
+pub use evaluator::{EvalBudget, Evaluator};
 pub use parser::Parser;
+pub use ports::{Direction, FilePort, InputPort, OutputPort, StringInputPort, StringPort};
 pub use repl::Repl;
+pub use session::SessionLog;
 pub use tokenizer::Tokenizer;
 pub use types::{EvalError, EvalResult, Expr, Token};
@@ -0,0 +1,302 @@
+use crate::interpreter::ports::{InputPort, OutputPort};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Sockets for the networking builtins. Programs hold them by number, the way a C program
+// holds file descriptors; the numbers index an `Evaluator::sockets` table shared by forks.
+
+// How long an HTTP request may take to connect, and may then wait without receiving
+// anything, before it fails
+pub const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+// The largest HTTP response read; a longer one fails rather than filling memory
+pub const MAX_HTTP_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+// Reads wake up this often to ask whether the evaluation waiting on them was interrupted
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn interrupted_error() -> io::Error {
+    io::Error::new(ErrorKind::Interrupted, "interrupted")
+}
+
+// A connected socket. It is an input port (lines) and an output port, so an embedder can
+// point an evaluator's I/O at a connection as well as using tcp-send and tcp-receive.
+pub struct TcpConnection {
+    reader: RefCell<BufReader<TcpStream>>,
+    writer: RefCell<TcpStream>,
+}
+
+impl TcpConnection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_read_timeout(Some(READ_POLL_INTERVAL))?;
+        Ok(Self {
+            reader: RefCell::new(BufReader::new(stream.try_clone()?)),
+            writer: RefCell::new(stream),
+        })
+    }
+
+    pub fn send(&self, text: &str) -> io::Result<()> {
+        let mut writer = self.writer.borrow_mut();
+        writer.write_all(text.as_bytes())?;
+        writer.flush()
+    }
+
+    // The next line including its newline, or None once the peer has closed. Waits as
+    // long as it takes, unless `stop` returns true, which fails with ErrorKind::Interrupted.
+    pub fn receive_line(&self, stop: &dyn Fn() -> bool) -> io::Result<Option<String>> {
+        let mut line = Vec::new();
+        let mut reader = self.reader.borrow_mut();
+        loop {
+            match reader.read_until(b'\n', &mut line) {
+                Ok(_) => break,
+                Err(e) if is_timeout(&e) => {
+                    if stop() {
+                        return Err(interrupted_error());
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if line.is_empty() {
+            return Ok(None);
+        }
+        String::from_utf8(line)
+            .map(Some)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+
+    // Everything until the peer closes, failing past `max_bytes`, after `idle` without
+    // receiving anything, or when `stop` returns true
+    pub fn receive_all(
+        &self,
+        max_bytes: usize,
+        idle: Duration,
+        stop: &dyn Fn() -> bool,
+    ) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let mut buffer = [0u8; 8192];
+        let mut last_received = Instant::now();
+        let mut reader = self.reader.borrow_mut();
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(bytes),
+                Ok(n) => {
+                    if bytes.len() + n > max_bytes {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("response is over the limit of {} bytes", max_bytes),
+                        ));
+                    }
+                    bytes.extend_from_slice(&buffer[..n]);
+                    last_received = Instant::now();
+                }
+                Err(e) if is_timeout(&e) => {
+                    if stop() {
+                        return Err(interrupted_error());
+                    }
+                    if last_received.elapsed() >= idle {
+                        return Err(io::Error::new(ErrorKind::TimedOut, "timed out"));
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Later sends and receives fail, even through other references to the connection
+    pub fn close(&self) {
+        let _ = self.writer.borrow().shutdown(Shutdown::Both);
+    }
+
+    pub fn local_port(&self) -> io::Result<u16> {
+        Ok(self.writer.borrow().local_addr()?.port())
+    }
+}
+
+impl OutputPort for TcpConnection {
+    fn write(&self, text: &str) {
+        let _ = self.send(text);
+    }
+}
+
+impl InputPort for TcpConnection {
+    fn read_line(&self) -> Option<String> {
+        self.receive_line(&|| false).ok().flatten()
+    }
+}
+
+// A read timeout shows up as WouldBlock on Unix and TimedOut on Windows
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+pub enum Socket {
+    Connection(Rc<TcpConnection>),
+    Listener(TcpListener),
+}
+
+#[derive(Default)]
+pub struct SocketTable {
+    sockets: HashMap<i64, Socket>,
+    next_id: i64,
+}
+
+impl SocketTable {
+    pub fn add(&mut self, socket: Socket) -> i64 {
+        self.next_id += 1;
+        self.sockets.insert(self.next_id, socket);
+        self.next_id
+    }
+
+    pub fn get(&self, id: i64) -> Option<&Socket> {
+        self.sockets.get(&id)
+    }
+
+    pub fn remove(&mut self, id: i64) -> Option<Socket> {
+        self.sockets.remove(&id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    // Names lowercased, in the order the server sent them
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+// Splits "http://host[:port][/path]" into (host, port, path)
+pub fn parse_http_url(url: &str) -> Result<(String, u16, String), String> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        return Err(if url.starts_with("https://") {
+            "https is not supported".to_string()
+        } else {
+            format!("not an http:// URL: {}", url)
+        });
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("bad port in URL: {}", url))?,
+        ),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(format!("no host in URL: {}", url));
+    }
+    // They go into the request line and Host header, where a line break would start a
+    // header of the URL's choosing
+    if rest.chars().any(|c| c.is_control() || c == ' ') {
+        return Err(format!(
+            "URL contains a space or control character: {:?}",
+            url
+        ));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+// An HTTP/1.0 request, so the server answers with a plain body and closes the connection
+pub fn http_request_text(
+    method: &str,
+    host: &str,
+    path: &str,
+    body: Option<(&str, &str)>,
+) -> String {
+    let mut request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: zeus\r\nConnection: close\r\n",
+        method, path, host
+    );
+    if let Some((content_type, body)) = body {
+        request.push_str(&format!(
+            "Content-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+            content_type,
+            body.len(),
+            body
+        ));
+    } else {
+        request.push_str("\r\n");
+    }
+    request
+}
+
+pub fn parse_http_response(bytes: &[u8]) -> Result<HttpResponse, String> {
+    let text = String::from_utf8_lossy(bytes);
+    let (head, body) = text
+        .split_once("\r\n\r\n")
+        .or_else(|| text.split_once("\n\n"))
+        .unwrap_or((&text, ""));
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .filter(|_| status_line.starts_with("HTTP/"))
+        .ok_or_else(|| format!("bad HTTP status line: {}", status_line))?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    Ok(HttpResponse {
+        status,
+        headers,
+        body: body.to_string(),
+    })
+}
+
+// Connects with HTTP_TIMEOUT to each address `host` resolves to until one answers
+fn connect_with_timeout(host: &str, port: u16) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(ErrorKind::NotFound, "no addresses for host");
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, HTTP_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error)
+}
+
+// `stop` is asked while waiting for the server; returning true abandons the request
+pub fn http_fetch(
+    method: &str,
+    url: &str,
+    body: Option<(&str, &str)>,
+    stop: &dyn Fn() -> bool,
+) -> Result<HttpResponse, String> {
+    let (host, port, path) = parse_http_url(url)?;
+    if let Some((content_type, _)) = body {
+        if content_type.chars().any(char::is_control) {
+            return Err(format!(
+                "content type contains a control character: {:?}",
+                content_type
+            ));
+        }
+    }
+    let stream = connect_with_timeout(&host, port)
+        .map_err(|err| format!("cannot connect to {}:{}: {}", host, port, err))?;
+    stream
+        .set_write_timeout(Some(HTTP_TIMEOUT))
+        .map_err(|err| err.to_string())?;
+    let connection = TcpConnection::new(stream).map_err(|err| err.to_string())?;
+    let host_header = match port {
+        80 => host.clone(),
+        _ => format!("{}:{}", host, port),
+    };
+    connection
+        .send(&http_request_text(method, &host_header, &path, body))
+        .map_err(|err| err.to_string())?;
+    let bytes = connection
+        .receive_all(MAX_HTTP_RESPONSE_BYTES, HTTP_TIMEOUT, stop)
+        .map_err(|err| err.to_string())?;
+    parse_http_response(&bytes)
+}
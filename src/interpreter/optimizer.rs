@@ -0,0 +1,359 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{Expr, Symbol, SymbolData};
+use std::collections::HashSet;
+
+// Optional rewrite pass over each top-level form before it's evaluated (Evaluator::optimize):
+// folds arithmetic on literals, collapses (progn x) to x, resolves quoted literals, drops
+// cond clauses that can never be reached and fuses chains of mapcar, filter, remove and
+// reduce into one transduce pass. Only builtins that are still the global builtin, and
+// aren't rebound anywhere in the form, are folded or fused. A fused chain calls its
+// functions element by element instead of stage by stage.
+
+const FOLDABLE: &[&str] = &["+", "-", "*", "/", "expt"];
+
+// The list builtins a transduce step can stand in for, with the step's keyword
+const FUSIBLE: &[(&str, &str)] = &[
+    ("mapcar", "map"),
+    ("filter", "filter"),
+    ("remove", "remove"),
+];
+
+impl Evaluator {
+    pub fn optimize_expr(&mut self, expr: &Expr) -> Expr {
+        let mut rebound = HashSet::new();
+        bound_names(expr, &mut rebound);
+        self.optimize_form(expr, &rebound)
+    }
+
+    fn optimize_form(&mut self, expr: &Expr, rebound: &HashSet<Symbol>) -> Expr {
+        let Expr::List(items) = expr else {
+            return expr.clone();
+        };
+        let Some(Expr::Symbol(SymbolData::Interned(head))) = items.first() else {
+            return self.optimize_from(items, 0, rebound);
+        };
+
+        match head.as_str() {
+            "quote" => match items.get(1) {
+                Some(datum) if items.len() == 2 && is_literal(datum) => datum.clone(),
+                _ => expr.clone(),
+            },
+            "progn" if items.len() == 2 => self.optimize_form(&items[1], rebound),
+            "cond" if items.len() > 1 => self.optimize_cond(items, rebound),
+            "define" | "lambda" | "block" | "return-from" => self.optimize_from(items, 2, rebound),
+            "defun" => self.optimize_from(items, 3, rebound),
+            "go" | "undefine" => expr.clone(),
+            // Restart names and parameter lists stay as written
+            "restart-case" => {
+                let mut optimized = vec![items[0].clone()];
+                optimized.extend(items.get(1).map(|form| self.optimize_form(form, rebound)));
+                for clause in items.iter().skip(2) {
+                    optimized.push(match clause {
+                        Expr::List(parts) => self.optimize_from(parts, 2, rebound),
+                        other => other.clone(),
+                    });
+                }
+                Expr::new_list(optimized)
+            }
+            // Binding names and case datums stay as written; everything else is code
+            "let" | "let*" | "letrec" | "do" | "case" => {
+                let mut optimized = items.to_vec();
+                match optimized.get_mut(1) {
+                    Some(Expr::List(bindings)) if head.as_str() != "case" => {
+                        *bindings = std::rc::Rc::new(
+                            bindings
+                                .iter()
+                                .map(|binding| match binding {
+                                    Expr::List(parts) => self.optimize_from(parts, 1, rebound),
+                                    other => other.clone(),
+                                })
+                                .collect(),
+                        );
+                    }
+                    Some(key) => *key = self.optimize_form(key, rebound),
+                    None => {}
+                }
+                for (index, item) in optimized.iter_mut().enumerate().skip(2) {
+                    *item = match item {
+                        // (datum...) body...
+                        Expr::List(parts) if head.as_str() == "case" => {
+                            self.optimize_from(parts, 1, rebound)
+                        }
+                        // (test result...)
+                        Expr::List(parts) if head.as_str() == "do" && index == 2 => {
+                            self.optimize_from(parts, 0, rebound)
+                        }
+                        _ => self.optimize_form(item, rebound),
+                    };
+                }
+                Expr::new_list(optimized)
+            }
+            name => {
+                let optimized = self.optimize_from(items, 1, rebound);
+                if FOLDABLE.contains(&name) && !rebound.contains(head) {
+                    if let Some(value) = self.fold(*head, &optimized) {
+                        return value;
+                    }
+                }
+                if name == "reduce" || FUSIBLE.iter().any(|(builtin, _)| *builtin == name) {
+                    if let Some(fused) = self.fuse(&optimized, rebound) {
+                        return fused;
+                    }
+                }
+                optimized
+            }
+        }
+    }
+
+    // `items` with everything from `start` on optimized
+    fn optimize_from(&mut self, items: &[Expr], start: usize, rebound: &HashSet<Symbol>) -> Expr {
+        let mut optimized = items[..start.min(items.len())].to_vec();
+        for item in items.iter().skip(start) {
+            optimized.push(self.optimize_form(item, rebound));
+        }
+        Expr::new_list(optimized)
+    }
+
+    // The value of (op literal...) when op is the global builtin and the call succeeds;
+    // a call that fails, like division by zero, is left to fail at run time
+    fn fold(&mut self, op: Symbol, call: &Expr) -> Option<Expr> {
+        let Expr::List(items) = call else {
+            return None;
+        };
+        let builtin = Expr::Symbol(SymbolData::Interned(op));
+        if self.environment.get_symbol(op).ok()? != builtin {
+            return None;
+        }
+        if !items[1..].iter().all(is_number) {
+            return None;
+        }
+        self.apply_builtin(op.as_str(), &items[1..])
+            .ok()
+            .filter(is_number)
+    }
+
+    fn is_global_builtin(&self, name: Symbol, rebound: &HashSet<Symbol>) -> bool {
+        !rebound.contains(&name)
+            && self.environment.get_symbol(name).ok()
+                == Some(Expr::Symbol(SymbolData::Interned(name)))
+    }
+
+    // (reduce g (mapcar f (filter p xs)) init), or any chain of two or more of these
+    // calls, becomes (transduce xs :filter p :map f :reduce g init)
+    fn fuse(&mut self, call: &Expr, rebound: &HashSet<Symbol>) -> Option<Expr> {
+        let keyword = |name: &str| Expr::Symbol(SymbolData::Keyword(Symbol::intern(name)));
+        let transduce = Symbol::intern("transduce");
+        if !self.is_global_builtin(transduce, rebound) {
+            return None;
+        }
+
+        let mut finish = Vec::new();
+        let mut current = call.clone();
+        if let Expr::List(items) = call {
+            if items[0] == Expr::Symbol(SymbolData::Interned(Symbol::intern("reduce")))
+                && (items.len() == 3 || items.len() == 4)
+            {
+                finish.push(keyword("reduce"));
+                finish.push(items[1].clone());
+                finish.extend(items.get(3).cloned());
+                current = items[2].clone();
+            }
+        }
+
+        // Outermost first; reversed below into the order they apply
+        let mut steps = Vec::new();
+        while let Expr::List(items) = &current {
+            let Some(Expr::Symbol(SymbolData::Interned(head))) = items.first() else {
+                break;
+            };
+            // Inner chains are fused first, so the rest of this one may already be a
+            // transduce that just collects
+            if *head == transduce && items.len() % 2 == 0 {
+                let pairs: Vec<&[Expr]> = items[2..].chunks(2).collect();
+                if pairs.iter().all(|pair| pair[0] != keyword("reduce")) {
+                    for pair in pairs.into_iter().rev() {
+                        steps.push((pair[0].clone(), pair[1].clone()));
+                    }
+                    current = items[1].clone();
+                }
+                break;
+            }
+            let Some((_, step)) = FUSIBLE.iter().find(|(builtin, _)| head == *builtin) else {
+                break;
+            };
+            if items.len() != 3 || !self.is_global_builtin(*head, rebound) {
+                break;
+            }
+            steps.push((keyword(step), items[1].clone()));
+            current = items[2].clone();
+        }
+        if steps.len() + usize::from(!finish.is_empty()) < 2 {
+            return None;
+        }
+
+        let mut fused = vec![Expr::Symbol(SymbolData::Interned(transduce)), current];
+        for (step, function) in steps.into_iter().rev() {
+            fused.push(step);
+            fused.push(function);
+        }
+        fused.extend(finish);
+        Some(Expr::new_list(fused))
+    }
+
+    fn optimize_cond(&mut self, items: &[Expr], rebound: &HashSet<Symbol>) -> Expr {
+        let mut clauses = Vec::new();
+        for clause in &items[1..] {
+            // A malformed clause is kept so it still fails at run time
+            let Expr::List(parts) = clause else {
+                clauses.push(clause.clone());
+                continue;
+            };
+            let parts: Vec<Expr> = parts
+                .iter()
+                .map(|part| self.optimize_form(part, rebound))
+                .collect();
+            match parts.first().and_then(constant_test) {
+                Some(false) => continue,
+                Some(true) => {
+                    // Nothing before it can be taken, so the cond is just this body
+                    if clauses.is_empty() && parts.len() > 1 {
+                        return progn(parts[1..].to_vec());
+                    }
+                    clauses.push(Expr::new_list(parts));
+                    break;
+                }
+                None => clauses.push(Expr::new_list(parts)),
+            }
+        }
+        if clauses.is_empty() {
+            return Expr::new_list(vec![]);
+        }
+        let mut cond = vec![items[0].clone()];
+        cond.extend(clauses);
+        Expr::new_list(cond)
+    }
+}
+
+fn progn(mut body: Vec<Expr>) -> Expr {
+    if body.len() == 1 {
+        return body.remove(0);
+    }
+    body.insert(
+        0,
+        Expr::Symbol(SymbolData::Interned(Symbol::intern("progn"))),
+    );
+    Expr::new_list(body)
+}
+
+fn is_number(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Integer(_) | Expr::BigInt(_) | Expr::Float(_) | Expr::Rational { .. }
+    )
+}
+
+// Data that evaluates to itself, so (quote x) can be replaced by x
+fn is_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::String(_) | Expr::Character(_) | Expr::Symbol(SymbolData::Keyword(_)) => true,
+        Expr::List(items) => items.is_empty(),
+        _ => is_number(expr),
+    }
+}
+
+// Whether a cond test always or never succeeds, when that's known without running it
+fn constant_test(test: &Expr) -> Option<bool> {
+    match test {
+        Expr::List(items) if items.is_empty() => Some(false),
+        Expr::List(items) => match items.first() {
+            Some(Expr::Symbol(SymbolData::Interned(head))) if head == "quote" => {
+                items.get(1).map(|datum| *datum != Expr::new_list(vec![]))
+            }
+            _ => None,
+        },
+        Expr::Symbol(SymbolData::Interned(name)) if name == "else" => Some(true),
+        Expr::Symbol(SymbolData::Keyword(_)) => Some(true),
+        _ if is_literal(test) => Some(true),
+        _ => None,
+    }
+}
+
+// Names bound anywhere in `expr` by define, defun, defgeneric, defmethod, lambda, let, let*,
+// letrec, do or for
+pub(crate) fn bound_names(expr: &Expr, into: &mut HashSet<Symbol>) {
+    let Expr::List(items) = expr else {
+        return;
+    };
+    let mut bind = |expr: Option<&Expr>| {
+        if let Some(Expr::Symbol(SymbolData::Interned(name))) = expr {
+            into.insert(*name);
+        }
+    };
+    if let Some(Expr::Symbol(SymbolData::Interned(head))) = items.first() {
+        match head.as_str() {
+            "quote" => return,
+            "define" => bind(items.get(1)),
+            "defun" | "lambda" => {
+                let params = if head == "defun" {
+                    bind(items.get(1));
+                    items.get(2)
+                } else {
+                    items.get(1)
+                };
+                if let Some(Expr::List(params)) = params {
+                    params.iter().for_each(|param| bind(Some(param)));
+                }
+            }
+            "defgeneric" => bind(items.get(1)),
+            // A specialized parameter is written (name type)
+            "defmethod" => {
+                bind(items.get(1));
+                if let Some(Expr::List(params)) = items.get(2) {
+                    for param in params.iter() {
+                        match param {
+                            Expr::List(parts) => bind(parts.first()),
+                            other => bind(Some(other)),
+                        }
+                    }
+                }
+            }
+            "restart-case" => {
+                for clause in items.iter().skip(2) {
+                    if let Expr::List(parts) = clause {
+                        if let Some(Expr::List(params)) = parts.get(1) {
+                            params.iter().for_each(|param| bind(Some(param)));
+                        }
+                    }
+                }
+            }
+            "dolist" | "with-open-file" => {
+                if let Some(Expr::List(spec)) = items.get(1) {
+                    bind(spec.first());
+                }
+            }
+            "for" => {
+                if let Some(Expr::List(clauses)) = items.get(1) {
+                    for clause in clauses.iter() {
+                        if let Expr::List(parts) = clause {
+                            bind(parts.first());
+                        }
+                    }
+                }
+            }
+            "let" | "let*" | "letrec" | "do" => {
+                if let Some(Expr::List(bindings)) = items.get(1) {
+                    for binding in bindings.iter() {
+                        match binding {
+                            Expr::List(parts) => bind(parts.first()),
+                            other => bind(Some(other)),
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for item in items.iter() {
+        bound_names(item, into);
+    }
+}
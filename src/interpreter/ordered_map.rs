@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+// Hash map that iterates in insertion order, backing hash tables so hash-keys, printing
+// and snapshots come out the same on every run. Entries live in a vector and the index
+// maps each key to its slot. Replacing a value keeps the key where it was; removing one
+// leaves a hole that's skipped, and the holes are compacted away once they outnumber
+// the live entries, so removal stays O(1) amortized.
+
+#[derive(Clone)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<Option<(K, V)>>,
+    index: HashMap<K, usize>,
+}
+
+impl<K, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        OrderedMap {
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> OrderedMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        OrderedMap {
+            entries: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let slot = *self.index.get(key)?;
+        self.entries[slot].as_ref().map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.index.contains_key(key)
+    }
+
+    // Returns the value `key` had before, if any; a new key goes at the end
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&slot) = self.index.get(&key) {
+            let (_, existing) = self.entries[slot].as_mut()?;
+            return Some(std::mem::replace(existing, value));
+        }
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push(Some((key, value)));
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let slot = self.index.remove(key)?;
+        let (_, value) = self.entries[slot].take()?;
+        if self.entries.len() > 2 * self.index.len() + 8 {
+            self.compact();
+        }
+        Some(value)
+    }
+
+    fn compact(&mut self) {
+        self.entries.retain(Option::is_some);
+        for (slot, entry) in self.entries.iter().enumerate() {
+            if let Some((key, _)) = entry {
+                self.index.insert(key.clone(), slot);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+// Later duplicates replace the value but keep the first position, as insert does
+impl<K: Hash + Eq + Clone, V> FromIterator<(K, V)> for OrderedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(pairs: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in pairs {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Debug, V: fmt::Debug> fmt::Debug for OrderedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
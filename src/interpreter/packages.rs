@@ -0,0 +1,483 @@
+use crate::interpreter::batch::split_forms;
+use crate::interpreter::check::CONFIG_FILE;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::source_map::Position;
+use crate::interpreter::types::{EvalError, EvalResult, Expr};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Source packages. A project's zeus.toml lists what it depends on in a [dependencies] table,
+// each a local directory or a git repository:
+//   [dependencies]
+//   json = { path = "../zeus-json" }
+//   http = { git = "https://example.com/zeus-http.git", rev = "v1.2" }
+// `zeus pkg install` copies each one's .lisp files into zeus_packages/<name> beside the
+// manifest (a git dependency is cloned first, at `rev` if given), and `zeus pkg add` records
+// a dependency and installs it. Nothing is built or cached anywhere else: a package is just
+// its sources.
+//
+// (require "name") loads name.lisp from the directory of the file doing the requiring (the
+// current directory at the top level), or else the package `name` from the nearest
+// zeus_packages: its <name>.lisp or main.lisp, or for "name/module" its module.lisp. Each
+// file is loaded once per evaluator.
+
+pub const PACKAGES_DIR: &str = "zeus_packages";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackageSource {
+    // Relative to the manifest's directory
+    Path(PathBuf),
+    Git { url: String, rev: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub source: PackageSource,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    pub dependencies: Vec<Dependency>,
+}
+
+// What require has loaded, and the directories of the files being required, innermost last
+#[derive(Debug, Clone, Default)]
+pub struct RequireState {
+    loaded: Vec<PathBuf>,
+    dirs: Vec<PathBuf>,
+}
+
+impl Manifest {
+    // Reads the [dependencies] table; other tables belong to other tools
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut manifest = Self::default();
+        let mut in_dependencies = false;
+        for (index, raw) in text.lines().enumerate() {
+            let line = strip_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_dependencies = line == "[dependencies]";
+                continue;
+            }
+            if in_dependencies {
+                let dependency =
+                    parse_dependency(line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+                manifest.dependencies.push(dependency);
+            }
+        }
+        Ok(manifest)
+    }
+
+    // The nearest zeus.toml in `dir` or one of its parents
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|ancestor| ancestor.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl Dependency {
+    // As it's written in the manifest
+    pub fn to_line(&self) -> String {
+        match &self.source {
+            PackageSource::Path(path) => {
+                format!("{} = {{ path = \"{}\" }}", self.name, path.display())
+            }
+            PackageSource::Git { url, rev: None } => {
+                format!("{} = {{ git = \"{}\" }}", self.name, url)
+            }
+            PackageSource::Git {
+                url,
+                rev: Some(rev),
+            } => format!("{} = {{ git = \"{}\", rev = \"{}\" }}", self.name, url, rev),
+        }
+    }
+}
+
+// Drops a `#` comment, unless the `#` is inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, ch) in line.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+// name = { path = "..." } or name = { git = "...", rev = "..." }
+fn parse_dependency(line: &str) -> Result<Dependency, String> {
+    let Some((name, table)) = line.split_once('=') else {
+        return Err("expected `name = { path = \"...\" }` or `name = { git = \"...\" }`".into());
+    };
+    let name = name.trim().trim_matches('"');
+    if !is_package_name(name) {
+        return Err(format!(
+            "`{}` isn't a package name (letters, digits, - and _)",
+            name
+        ));
+    }
+    let Some(table) = table
+        .trim()
+        .strip_prefix('{')
+        .and_then(|table| table.strip_suffix('}'))
+    else {
+        return Err(format!("expected an inline table for `{}`", name));
+    };
+
+    let (mut path, mut git, mut rev) = (None, None, None);
+    for entry in table.split(',').filter(|entry| !entry.trim().is_empty()) {
+        let Some((key, value)) = entry.split_once('=') else {
+            return Err(format!("expected `key = \"value\"` for `{}`", name));
+        };
+        let value = value.trim();
+        let Some(value) = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+        else {
+            return Err(format!("expected a quoted string, got `{}`", value));
+        };
+        // URLs and revisions are passed to git, which would read these as options
+        if value.starts_with('-') {
+            return Err(format!("`{}` can't start with `-` for `{}`", value, name));
+        }
+        let slot = match key.trim() {
+            "path" => &mut path,
+            "git" => &mut git,
+            "rev" => &mut rev,
+            other => return Err(format!("unknown key `{}` for `{}`", other, name)),
+        };
+        *slot = Some(value.to_string());
+    }
+
+    let source = match (path, git, rev) {
+        (Some(path), None, None) => PackageSource::Path(PathBuf::from(path)),
+        (None, Some(url), rev) => PackageSource::Git { url, rev },
+        (Some(_), None, Some(_)) => return Err(format!("`{}`: rev needs git", name)),
+        (Some(_), Some(_), _) => return Err(format!("`{}` has both path and git", name)),
+        (None, None, _) => return Err(format!("`{}` needs a path or git", name)),
+    };
+    Ok(Dependency {
+        name: name.to_string(),
+        source,
+    })
+}
+
+pub fn is_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// Writes `dependency` into the manifest at `path`, created if need be, replacing any earlier
+// entry of the same name
+pub fn add_dependency(path: &Path, dependency: &Dependency) -> Result<(), String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    Manifest::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_dependency(&dependency.to_line())?;
+
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    // The last line of the [dependencies] table that isn't blank
+    let mut section_end = None;
+    let mut existing = None;
+    let mut in_dependencies = false;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = strip_comment(line).trim();
+        if trimmed.starts_with('[') {
+            in_dependencies = trimmed == "[dependencies]";
+            if in_dependencies {
+                section_end = Some(index);
+            }
+        } else if in_dependencies && !trimmed.is_empty() {
+            section_end = Some(index);
+            let name = trimmed.split('=').next().unwrap_or("").trim();
+            if name.trim_matches('"') == dependency.name {
+                existing = Some(index);
+            }
+        }
+    }
+    match (existing, section_end) {
+        (Some(index), _) => lines[index] = dependency.to_line(),
+        (None, Some(index)) => lines.insert(index + 1, dependency.to_line()),
+        (None, None) => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("[dependencies]".to_string());
+            lines.push(dependency.to_line());
+        }
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Vendors every dependency of the manifest at `manifest_path` under zeus_packages beside it,
+// replacing what was there. Returns a line per package saying what was installed.
+pub fn install(manifest_path: &Path) -> Result<Vec<String>, String> {
+    let manifest = Manifest::load(manifest_path)?;
+    let project = manifest_path.parent().unwrap_or(Path::new("."));
+    manifest
+        .dependencies
+        .iter()
+        .map(|dependency| install_dependency(project, dependency))
+        .collect()
+}
+
+// `zeus pkg add`: installs the dependency into `project`, then records it in the manifest
+// at `manifest_path`, so one that can't be installed never gets into the manifest
+pub fn add_package(
+    manifest_path: &Path,
+    project: &Path,
+    dependency: &Dependency,
+) -> Result<String, String> {
+    parse_dependency(&dependency.to_line())?;
+    let installed = install_dependency(project, dependency)?;
+    add_dependency(manifest_path, dependency)?;
+    Ok(installed)
+}
+
+pub fn install_dependency(project: &Path, dependency: &Dependency) -> Result<String, String> {
+    let packages = project.join(PACKAGES_DIR);
+    let target = packages.join(&dependency.name);
+    let (from, files) = match &dependency.source {
+        PackageSource::Path(path) => {
+            let source = project.join(path);
+            if !source.is_dir() {
+                return Err(format!(
+                    "{}: {} is not a directory",
+                    dependency.name,
+                    source.display()
+                ));
+            }
+            replace_dir(&target)?;
+            (path.display().to_string(), copy_sources(&source, &target)?)
+        }
+        PackageSource::Git { url, rev } => {
+            let clone = packages.join(format!(".{}.clone", dependency.name));
+            replace_dir(&clone)?;
+            let result = git_clone(url, rev.as_deref(), &clone).and_then(|()| {
+                replace_dir(&target)?;
+                copy_sources(&clone, &target)
+            });
+            let _ = fs::remove_dir_all(&clone);
+            let from = match rev {
+                Some(rev) => format!("{} at {}", url, rev),
+                None => url.clone(),
+            };
+            (from, result?)
+        }
+    };
+    if files == 0 {
+        return Err(format!("{}: no .lisp files in {}", dependency.name, from));
+    }
+    Ok(format!(
+        "{}: {} file{} from {}",
+        dependency.name,
+        files,
+        if files == 1 { "" } else { "s" },
+        from
+    ))
+}
+
+// Empties `dir`, creating it if need be
+fn replace_dir(dir: &Path) -> Result<(), String> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)
+            .map_err(|e| format!("Failed to remove {}: {}", dir.display(), e))?;
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))
+}
+
+// Options end before the URL and the rev, so neither can be read as one. checkout would
+// take anything after `--` as a path, so the rev is resolved to a commit id first.
+fn git_clone(url: &str, rev: Option<&str>, into: &Path) -> Result<(), String> {
+    let mut clone = Command::new("git");
+    clone.args(["clone", "--quiet", "--", url]).arg(into);
+    run_git(clone, "clone", url)?;
+    if let Some(rev) = rev {
+        let mut resolve = Command::new("git");
+        resolve.arg("-C").arg(into).args([
+            "rev-parse",
+            "--verify",
+            "--end-of-options",
+            &format!("{}^{{commit}}", rev),
+        ]);
+        let commit = run_git(resolve, "rev-parse", rev)?;
+        let mut checkout = Command::new("git");
+        checkout
+            .arg("-C")
+            .arg(into)
+            .args(["checkout", "--quiet", "--detach", commit.trim(), "--"]);
+        run_git(checkout, "checkout", rev)?;
+    }
+    Ok(())
+}
+
+// Runs git and returns what it printed
+fn run_git(mut command: Command, what: &str, target: &str) -> Result<String, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "git {} {} failed: {}",
+            what,
+            target,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// Copies the .lisp files under `from` to the same places under `to`, skipping hidden
+// directories and installed packages, and returns how many there were
+fn copy_sources(from: &Path, to: &Path) -> Result<usize, String> {
+    let entries =
+        fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    let mut copied = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if name.starts_with('.') || name == PACKAGES_DIR {
+                continue;
+            }
+            let files = copy_sources(&path, &to.join(&name))?;
+            copied += files;
+        } else if path.extension().is_some_and(|ext| ext == "lisp") {
+            fs::create_dir_all(to)
+                .map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+            fs::copy(&path, to.join(&name))
+                .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+// The file (require name) loads when required from `dir`
+pub fn resolve_require(name: &str, dir: &Path) -> Option<PathBuf> {
+    let file = |base: &Path, relative: &str| {
+        let path = if relative.ends_with(".lisp") {
+            base.join(relative)
+        } else {
+            base.join(format!("{}.lisp", relative))
+        };
+        path.is_file().then_some(path)
+    };
+    if let Some(path) = file(dir, name) {
+        return Some(path);
+    }
+    let (package, module) = match name.split_once('/') {
+        Some((package, module)) => (package, Some(module)),
+        None => (name, None),
+    };
+    if !is_package_name(package) {
+        return None;
+    }
+    let installed = dir
+        .ancestors()
+        .map(|ancestor| ancestor.join(PACKAGES_DIR).join(package))
+        .find(|path| path.is_dir())?;
+    match module {
+        Some(module) => file(&installed, module),
+        None => file(&installed, package).or_else(|| file(&installed, "main")),
+    }
+}
+
+impl Evaluator {
+    // Where (require ...) at the top level looks first, instead of the current directory:
+    // front-ends running a file set it to the file's directory
+    pub fn set_require_dir(&mut self, dir: &Path) {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        self.requires.dirs = vec![dir.to_path_buf()];
+    }
+
+    // (require "name"): loads the file or package `name` (see above) unless it has been
+    // already, returning t if it was loaded now and nil if it had been
+    pub fn builtin_require(&mut self, args: &[Expr]) -> EvalResult {
+        let [Expr::String(name)] = args else {
+            return Err(EvalError::message("require requires a name as a string"));
+        };
+        let dir = match self.requires.dirs.last() {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        };
+        let Some(path) = resolve_require(name, &dir) else {
+            return Err(EvalError::message(format!(
+                "require: no file {}.lisp in {} and no installed package {}",
+                name,
+                dir.display(),
+                name
+            )));
+        };
+        let path = path.canonicalize().unwrap_or(path);
+        if self.requires.loaded.contains(&path) {
+            return Ok(Expr::new_list(vec![]));
+        }
+        let source = fs::read_to_string(&path).map_err(|e| {
+            EvalError::message(format!("require: failed to read {}: {}", path.display(), e))
+        })?;
+
+        // Counted as loaded from the start, so files that require each other stop there
+        self.requires.loaded.push(path.clone());
+        self.requires
+            .dirs
+            .push(path.parent().map(Path::to_path_buf).unwrap_or(dir));
+        let result = self.load_required(&path, &source);
+        self.requires.dirs.pop();
+        if result.is_err() {
+            self.requires.loaded.retain(|loaded| *loaded != path);
+        }
+        result.map(|()| Self::bool_to_expr(true))
+    }
+
+    fn load_required(&mut self, path: &Path, source: &str) -> Result<(), EvalError> {
+        let (forms, unterminated) = split_forms(source);
+        for form in forms {
+            let expr = self
+                .read_at(&form.source, Position::new(form.line, form.column))
+                .map_err(|error| {
+                    EvalError::message(format!(
+                        "{}:{}:{}: {}",
+                        path.display(),
+                        error.position.line,
+                        error.position.column,
+                        error.message
+                    ))
+                })?;
+            self.eval(&expr)?;
+        }
+        match unterminated {
+            Some(form) => Err(EvalError::message(format!(
+                "{}:{}: unbalanced parentheses or unterminated string",
+                path.display(),
+                form.line
+            ))),
+            None => Ok(()),
+        }
+    }
+}
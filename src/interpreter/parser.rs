@@ -1,8 +1,14 @@
-use crate::interpreter::types::{Expr, SymbolData, Token};
+use crate::interpreter::source_map::{Position, ReadError, SourceMap, Span};
+use crate::interpreter::types::{Expr, Symbol, SymbolData, Token};
 
 pub struct Parser {
     tokens: Vec<Token>,
     position: usize,
+    // One per token when parsing with spans, otherwise empty
+    spans: Vec<Span>,
+    source_map: Option<SourceMap>,
+    // The closing bracket that had nothing to close, when that is what stopped the parse
+    stray_close: Option<usize>,
 }
 
 impl Parser {
@@ -10,6 +16,37 @@ impl Parser {
         Parser {
             tokens,
             position: 0,
+            spans: Vec::new(),
+            source_map: None,
+            stray_close: None,
+        }
+    }
+
+    // A parser that records the span of every list it builds in `source_map`
+    pub fn with_spans(tokens: Vec<Token>, spans: Vec<Span>, source_map: SourceMap) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+            spans,
+            source_map: Some(source_map),
+            stray_close: None,
+        }
+    }
+
+    // Where the token at `index` starts, or the end of the input past the last one
+    fn position_of(&self, index: usize) -> Position {
+        match self.spans.get(index) {
+            Some(span) => span.start,
+            None => self.spans.last().map_or(Position::START, |span| span.end),
+        }
+    }
+
+    fn record_list(&self, list: &Expr, first_token: usize) {
+        if let (Some(source_map), Expr::List(items)) = (&self.source_map, list) {
+            let end = self.spans.get(self.position - 1).map(|span| span.end);
+            if let Some(end) = end {
+                source_map.record(items, Span::new(self.position_of(first_token), end));
+            }
         }
     }
 
@@ -28,12 +65,21 @@ impl Parser {
     }
 
     fn parse_expr(&mut self) -> Result<Expr, String> {
+        let first_token = self.position;
         match self.advance() {
             Some(Token::Integer(n)) => Ok(Expr::Integer(n)),
+            Some(Token::BigInt(n)) => Ok(Expr::BigInt(n)),
             Some(Token::Float(n)) => Ok(Expr::Float(n)),
             Some(Token::Character(ch)) => Ok(Expr::Character(ch)),
-            Some(Token::Symbol(s)) => Ok(Expr::Symbol(SymbolData::Interned(s))),
-            Some(Token::Keyword(s)) => Ok(Expr::Symbol(SymbolData::Keyword(s))),
+            Some(Token::Boolean(true)) => {
+                Ok(Expr::Symbol(SymbolData::Interned(Symbol::intern("t"))))
+            }
+            // nil and #f are read as the empty list itself, the one false value, so 'nil
+            // is false too and nil can't be rebound
+            Some(Token::Boolean(false)) => Ok(Expr::new_list(vec![])),
+            Some(Token::Symbol(s)) if s == "nil" => Ok(Expr::new_list(vec![])),
+            Some(Token::Symbol(s)) => Ok(Expr::Symbol(SymbolData::Interned(Symbol::intern(&s)))),
+            Some(Token::Keyword(s)) => Ok(Expr::Symbol(SymbolData::Keyword(Symbol::keyword(&s)))),
             Some(Token::String(s)) => Ok(Expr::String(s)),
             Some(Token::LeftParen) => {
                 let mut list = Vec::new();
@@ -42,7 +88,9 @@ impl Parser {
                     match self.peek() {
                         Some(Token::RightParen) => {
                             self.advance();
-                            return Ok(Expr::List(list));
+                            let list = Expr::new_list(list);
+                            self.record_list(&list, first_token);
+                            return Ok(list);
                         }
                         None => return Err("Unexpected end of input".to_string()),
                         _ => {
@@ -58,7 +106,7 @@ impl Parser {
                     match self.peek() {
                         Some(Token::RightBracket) => {
                             self.advance();
-                            return Ok(Expr::Vector(vector));
+                            return Ok(Expr::new_vector(vector));
                         }
                         None => return Err("Unexpected end of input in vector".to_string()),
                         _ => {
@@ -67,8 +115,14 @@ impl Parser {
                     }
                 }
             }
-            Some(Token::RightParen) => Err("Unexpected )".to_string()),
-            Some(Token::RightBracket) => Err("Unexpected ]".to_string()),
+            Some(Token::RightParen) => {
+                self.stray_close = Some(first_token);
+                Err("Unexpected )".to_string())
+            }
+            Some(Token::RightBracket) => {
+                self.stray_close = Some(first_token);
+                Err("Unexpected ]".to_string())
+            }
             None => Err("Unexpected end of input".to_string()),
         }
     }
@@ -82,4 +136,25 @@ impl Parser {
             Ok(expr)
         }
     }
+
+    // parse, with errors placed where they happened: at a stray closing bracket, the first
+    // extra token, or the end of the input
+    pub fn parse_spanned(&mut self) -> Result<Expr, ReadError> {
+        let error = |parser: &Self, message: String, index: usize| ReadError {
+            message,
+            position: parser.position_of(index),
+        };
+        let expr = self.parse_expr().map_err(|message| {
+            let index = self.stray_close.unwrap_or(self.tokens.len());
+            error(self, message, index)
+        })?;
+        if self.position < self.tokens.len() {
+            return Err(error(
+                self,
+                "Extra tokens after expression".to_string(),
+                self.position,
+            ));
+        }
+        Ok(expr)
+    }
 }
@@ -0,0 +1,108 @@
+use std::fs;
+use std::path::PathBuf;
+
+// Filesystem entries that complete `input`, returned as full replacement strings.
+// Directories carry a trailing '/' so accepting one lets the user keep descending.
+pub fn complete_path(input: &str) -> Vec<String> {
+    let (dir_part, name_prefix) = match input.rfind('/') {
+        Some(index) => input.split_at(index + 1),
+        None => ("", input),
+    };
+
+    let Ok(entries) = fs::read_dir(resolve_dir(dir_part)) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            // Hidden entries only show up once the user starts typing a dot
+            if !name.starts_with(name_prefix)
+                || (name.starts_with('.') && !name_prefix.starts_with('.'))
+            {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            Some(format!(
+                "{}{}{}",
+                dir_part,
+                name,
+                if is_dir { "/" } else { "" }
+            ))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+// Longest string every candidate starts with
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut prefix_len = first.len();
+    for candidate in &candidates[1..] {
+        prefix_len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(prefix_len);
+    }
+    first[..prefix_len].to_string()
+}
+
+fn resolve_dir(dir_part: &str) -> PathBuf {
+    if dir_part.is_empty() {
+        return PathBuf::from(".");
+    }
+    if let Some(rest) = dir_part.strip_prefix("~/") {
+        if let Some(home) = std::env::var_os("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    PathBuf::from(dir_part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{common_prefix, complete_path};
+    use std::fs;
+
+    #[test]
+    fn test_common_prefix() {
+        let candidates = vec![
+            "src/interpreter/".to_string(),
+            "src/ide/".to_string(),
+            "src/main.rs".to_string(),
+        ];
+        assert_eq!(common_prefix(&candidates), "src/");
+        assert_eq!(common_prefix(&candidates[..2]), "src/i");
+        assert_eq!(common_prefix(&[]), "");
+    }
+
+    #[test]
+    fn test_complete_path() {
+        let dir = std::env::temp_dir().join(format!("zeus_path_completion_{}", std::process::id()));
+        fs::create_dir_all(dir.join("scripts")).unwrap();
+        fs::write(dir.join("sample.lisp"), "").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+
+        let base = format!("{}/", dir.display());
+        assert_eq!(
+            complete_path(&format!("{}s", base)),
+            vec![format!("{}sample.lisp", base), format!("{}scripts/", base)]
+        );
+        assert_eq!(
+            complete_path(&format!("{}.h", base)),
+            vec![format!("{}.hidden", base)]
+        );
+        assert_eq!(complete_path(&base).len(), 2);
+        assert!(complete_path(&format!("{}missing/", base)).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
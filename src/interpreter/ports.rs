@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::rc::Rc;
+
+// Where print, println, format and pprint send text (`Evaluator::output`), and eprint and
+// eprintln (`Evaluator::error_output`). The CLI writes to stdout and stderr; embedders,
+// tests and the IDE install their own.
+pub trait OutputPort {
+    fn write(&self, text: &str);
+}
+
+// Where input builtins read from (`Evaluator::input`)
+pub trait InputPort {
+    // The next line including its newline, or None at the end of input
+    fn read_line(&self) -> Option<String>;
+}
+
+pub struct StdoutPort;
+
+impl OutputPort for StdoutPort {
+    fn write(&self, text: &str) {
+        print!("{}", text);
+        io::stdout().flush().unwrap();
+    }
+}
+
+pub struct StderrPort;
+
+impl OutputPort for StderrPort {
+    fn write(&self, text: &str) {
+        eprint!("{}", text);
+    }
+}
+
+pub struct StdinPort;
+
+impl InputPort for StdinPort {
+    fn read_line(&self) -> Option<String> {
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line),
+        }
+    }
+}
+
+// Collects output until the owner takes it. Clones share one buffer, so a front-end can
+// hand a clone to each evaluator and drain them all from one place.
+#[derive(Clone, Default)]
+pub struct StringPort {
+    buffer: Rc<RefCell<String>>,
+}
+
+impl StringPort {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn take(&self) -> String {
+        std::mem::take(&mut *self.buffer.borrow_mut())
+    }
+}
+
+impl OutputPort for StringPort {
+    fn write(&self, text: &str) {
+        self.buffer.borrow_mut().push_str(text);
+    }
+}
+
+// Canned input, e.g. for tests. Clones share the unread text.
+#[derive(Clone, Default)]
+pub struct StringInputPort {
+    remaining: Rc<RefCell<String>>,
+}
+
+impl StringInputPort {
+    pub fn new(text: &str) -> Self {
+        Self {
+            remaining: Rc::new(RefCell::new(text.to_string())),
+        }
+    }
+}
+
+impl InputPort for StringInputPort {
+    fn read_line(&self) -> Option<String> {
+        let mut remaining = self.remaining.borrow_mut();
+        if remaining.is_empty() {
+            return None;
+        }
+        let end = remaining
+            .find('\n')
+            .map_or(remaining.len(), |index| index + 1);
+        Some(remaining.drain(..end).collect())
+    }
+}
+
+// A file opened by with-open-file, as the value its variable holds (`Expr::Port`). Clones
+// of the value share it, so once with-open-file closes it every reference sees it closed.
+pub struct FilePort {
+    pub path: String,
+    state: RefCell<FileState>,
+}
+
+enum FileState {
+    Input(BufReader<File>),
+    Output(BufWriter<File>),
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Input,
+    // Replacing the file, or adding to its end
+    Output { append: bool },
+}
+
+impl FilePort {
+    pub fn open(path: &str, direction: Direction) -> io::Result<Self> {
+        let state = match direction {
+            Direction::Input => FileState::Input(BufReader::new(File::open(path)?)),
+            Direction::Output { append } => FileState::Output(BufWriter::new(
+                OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .append(append)
+                    .truncate(!append)
+                    .open(path)?,
+            )),
+        };
+        Ok(Self {
+            path: path.to_string(),
+            state: RefCell::new(state),
+        })
+    }
+
+    // "input", "output" or "closed"
+    pub fn status(&self) -> &'static str {
+        self.state.borrow().name()
+    }
+
+    // The next line including its newline, or None at the end of the file
+    pub fn read_line(&self) -> io::Result<Option<String>> {
+        match &mut *self.state.borrow_mut() {
+            FileState::Input(reader) => {
+                let mut line = String::new();
+                Ok((reader.read_line(&mut line)? > 0).then_some(line))
+            }
+            other => Err(self.wrong_direction(other, "input")),
+        }
+    }
+
+    pub fn write_text(&self, text: &str) -> io::Result<()> {
+        match &mut *self.state.borrow_mut() {
+            FileState::Output(writer) => writer.write_all(text.as_bytes()),
+            other => Err(self.wrong_direction(other, "output")),
+        }
+    }
+
+    // Flushes what's been written; closing twice does nothing
+    pub fn close(&self) -> io::Result<()> {
+        match std::mem::replace(&mut *self.state.borrow_mut(), FileState::Closed) {
+            FileState::Output(mut writer) => writer.flush(),
+            FileState::Input(_) | FileState::Closed => Ok(()),
+        }
+    }
+
+    fn wrong_direction(&self, state: &FileState, wanted: &str) -> io::Error {
+        io::Error::other(match state {
+            FileState::Closed => format!("{} is closed", self.path),
+            _ => format!("{} is open for {}, not {}", self.path, state.name(), wanted),
+        })
+    }
+}
+
+impl FileState {
+    fn name(&self) -> &'static str {
+        match self {
+            FileState::Input(_) => "input",
+            FileState::Output(_) => "output",
+            FileState::Closed => "closed",
+        }
+    }
+}
+
+impl fmt::Debug for FilePort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FilePort({:?}, {})", self.path, self.status())
+    }
+}
+
+// So an embedder can also send a program's output to a file, or read its input from one
+impl OutputPort for FilePort {
+    fn write(&self, text: &str) {
+        let _ = self.write_text(text);
+    }
+}
+
+impl InputPort for FilePort {
+    fn read_line(&self) -> Option<String> {
+        FilePort::read_line(self).ok().flatten()
+    }
+}
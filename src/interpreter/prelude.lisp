@@ -0,0 +1,76 @@
+;; The Zeus prelude: functions written in Zeus itself, loaded into every new evaluator
+;; unless zeus starts with --no-prelude. Lambdas don't capture the variables around them,
+;; since scoping is dynamic, so a function that returns a function builds the lambda as a
+;; list with the values it needs quoted into it. For the same reason, parameters and
+;; locals of functions that call a function they're given are prefixed with the function's
+;; name, so they don't hide the caller's variables from it.
+
+;; Positions in a list
+
+(defun first (items)
+  "The first element of items, or nil if it's empty"
+  (if items (car items) nil))
+
+(defun rest (items)
+  "Everything in items after the first element"
+  (if items (cdr items) nil))
+
+(defun second (items)
+  "The second element of items, or nil if it has fewer"
+  (first (nthcdr 1 items)))
+
+(defun third (items)
+  "The third element of items, or nil if it has fewer"
+  (first (nthcdr 2 items)))
+
+(defun fourth (items)
+  "The fourth element of items, or nil if it has fewer"
+  (first (nthcdr 3 items)))
+
+;; Association lists
+
+(defun assoc-default (key alist default)
+  "The value paired with key in alist, or default if key isn't there"
+  (let ((pair (assoc key alist)))
+    (if pair (cdr pair) default)))
+
+;; Searching with a predicate
+
+(defun count-if (count-if-pred count-if-items)
+  "How many elements of items satisfy pred"
+  (length (filter count-if-pred count-if-items)))
+
+(defun some (some-pred some-items)
+  "The first true result of pred on an element of items, or nil if there's none"
+  (let ((some-found nil))
+    (while (and some-items (if some-found nil t))
+      (define some-found (funcall some-pred (car some-items)))
+      (define some-items (cdr some-items)))
+    some-found))
+
+(defun every (every-pred every-items)
+  "t if pred holds for every element of items, including when there are none"
+  (let ((every-holds t))
+    (while (and every-items every-holds)
+      (define every-holds (funcall every-pred (car every-items)))
+      (define every-items (cdr every-items)))
+    (if every-holds t nil)))
+
+;; Building functions
+
+(defun curry (f)
+  "A one-argument version of the two-argument function f: ((curry f) a) is (partial f a)"
+  (list (quote lambda) (quote (curried-arg))
+        (list (quote partial) (list (quote quote) f) (quote curried-arg))))
+
+(defun complement (pred)
+  "A predicate that's true exactly where pred is false"
+  (list (quote lambda) (quote (&rest complement-args))
+        (list (quote if) (list (quote apply) (list (quote quote) pred) (quote complement-args))
+              nil t)))
+
+(defun flip (f)
+  "f with its first two arguments swapped"
+  (list (quote lambda) (quote (flip-a flip-b &rest flip-rest))
+        (list (quote apply) (list (quote quote) f)
+              (quote (cons flip-b (cons flip-a flip-rest))))))
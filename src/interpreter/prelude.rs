@@ -0,0 +1,59 @@
+use crate::interpreter::batch::split_forms;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::Expr;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Derived functions written in Zeus (second, assoc-default, curry and so on) that every
+// Evaluator::new starts with. The prelude is evaluated once per thread and each new
+// evaluator gets an O(1) fork of the result, so it costs nothing per evaluator.
+// Evaluator::without_prelude, or --no-prelude for the whole process, leaves it out.
+
+pub const SOURCE: &str = include_str!("prelude.lisp");
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static LOADED: RefCell<Option<Environment>> = const { RefCell::new(None) };
+}
+
+// For --no-prelude: evaluators created from now on, on any thread, start without it
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed)
+}
+
+// The builtins with the prelude defined on top
+pub(crate) fn environment() -> Environment {
+    with_loaded(Environment::fork)
+}
+
+// Whether `name` is bound to `value` by the prelude, rather than by a program's own
+// definition, so views of what a program defined can leave it out
+pub fn is_unchanged(name: &str, value: &Expr) -> bool {
+    is_enabled() && with_loaded(|prelude| prelude.get(name).is_ok_and(|own| own == *value))
+}
+
+fn with_loaded<T>(f: impl FnOnce(&Environment) -> T) -> T {
+    LOADED.with(|loaded| {
+        f(loaded.borrow_mut().get_or_insert_with(|| {
+            let mut evaluator = Evaluator::without_prelude();
+            let (forms, unterminated) = split_forms(SOURCE);
+            assert!(
+                unterminated.is_none(),
+                "the prelude has an unterminated form"
+            );
+            for form in forms {
+                if let Err(error) = evaluator.eval_source(&form.source) {
+                    panic!("the prelude failed on line {}: {}", form.line, error);
+                }
+            }
+            evaluator.environment.take_changed_globals();
+            evaluator.environment
+        }))
+    })
+}
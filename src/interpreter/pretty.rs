@@ -0,0 +1,144 @@
+use crate::interpreter::printer::{PrintMode, Printer, MAX_PRINT_DEPTH};
+use crate::interpreter::types::{Expr, SymbolData};
+
+pub const DEFAULT_PRETTY_WIDTH: usize = 80;
+
+// Forms whose first N arguments stay on the opening line, with the body indented by two
+// under them: (defun name (args)\n  body)
+const BODY_FORMS: &[(&str, usize)] = &[
+    ("defun", 2),
+    ("defmethod", 2),
+    ("lambda", 1),
+    ("define", 1),
+    ("let", 1),
+    ("let*", 1),
+    ("letrec", 1),
+    ("when", 1),
+    ("unless", 1),
+    ("do", 2),
+    ("while", 1),
+    ("until", 1),
+    ("block", 1),
+    ("catch", 1),
+    ("progn", 0),
+    ("begin", 0),
+    ("unwind-protect", 1),
+    ("with-output-to-string", 0),
+    ("restart-case", 1),
+    ("for", 1),
+    ("dolist", 1),
+    ("with-open-file", 1),
+];
+
+// Readable (write-mode) layout of `expr` that keeps lines within `width` columns where it
+// can. Anything that fits stays on one line; longer forms break between elements, and
+// longer data lists and vectors fill each line before starting the next.
+pub fn pretty_print(expr: &Expr, width: usize) -> String {
+    let mut out = String::new();
+    layout(expr, 0, 0, width, &mut out);
+    out
+}
+
+// Nesting already laid out counts against the printer's limit
+fn flat(expr: &Expr, depth: usize) -> String {
+    Printer::new(PrintMode::Write)
+        .with_max_depth(MAX_PRINT_DEPTH.saturating_sub(depth))
+        .print(expr)
+}
+
+fn layout(expr: &Expr, depth: usize, column: usize, width: usize, out: &mut String) {
+    let flat = flat(expr, depth);
+    if column + flat.chars().count() <= width || depth >= MAX_PRINT_DEPTH {
+        out.push_str(&flat);
+        return;
+    }
+
+    match expr {
+        Expr::List(items) if !items.is_empty() => layout_list(items, depth, column, width, out),
+        Expr::Vector(items) if !items.borrow().is_empty() => {
+            out.push('[');
+            layout_filled(&items.borrow(), depth, column + 1, width, out);
+            out.push(']');
+        }
+        _ => out.push_str(&flat),
+    }
+}
+
+fn layout_list(items: &[Expr], depth: usize, column: usize, width: usize, out: &mut String) {
+    out.push('(');
+
+    let head = match &items[0] {
+        Expr::Symbol(SymbolData::Interned(name)) => Some(name.as_str()),
+        _ => None,
+    };
+    let Some(head) = head else {
+        layout_filled(items, depth, column + 1, width, out);
+        out.push(')');
+        return;
+    };
+
+    out.push_str(head);
+    let mut current = column + 1 + head.chars().count();
+    let rest = &items[1..];
+
+    if let Some(&(_, header)) = BODY_FORMS.iter().find(|(name, _)| *name == head) {
+        let header = header.min(rest.len());
+        for item in &rest[..header] {
+            out.push(' ');
+            layout(item, depth + 1, current + 1, width, out);
+            current = current_column(out);
+        }
+        for item in &rest[header..] {
+            newline(out, column + 2);
+            layout(item, depth + 1, column + 2, width, out);
+        }
+    } else if !rest.is_empty() {
+        // Calls: first argument after the operator, the rest lined up beneath it
+        out.push(' ');
+        layout_aligned(rest, depth, current + 1, width, out);
+    }
+
+    out.push(')');
+}
+
+fn layout_aligned(items: &[Expr], depth: usize, column: usize, width: usize, out: &mut String) {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            newline(out, column);
+        }
+        layout(item, depth + 1, column, width, out);
+    }
+}
+
+// Data: as many elements per line as fit, continuing lines aligned after the opening
+// bracket. An element too long for what's left of the line starts a new one, and one
+// that had to be broken over several lines gets them to itself.
+fn layout_filled(items: &[Expr], depth: usize, column: usize, width: usize, out: &mut String) {
+    let mut after_broken = false;
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            let fits = current_column(out) + 1 + flat(item, depth + 1).chars().count() <= width;
+            if fits && !after_broken {
+                out.push(' ');
+            } else {
+                newline(out, column);
+            }
+        }
+        let start = out.len();
+        layout(item, depth + 1, current_column(out), width, out);
+        after_broken = out[start..].contains('\n');
+    }
+}
+
+fn newline(out: &mut String, indent: usize) {
+    out.push('\n');
+    out.push_str(&" ".repeat(indent));
+}
+
+// Output always starts at column 0, so the current column is the last line's length
+fn current_column(out: &str) -> usize {
+    match out.rfind('\n') {
+        Some(index) => out[index + 1..].chars().count(),
+        None => out.chars().count(),
+    }
+}
@@ -0,0 +1,341 @@
+use crate::interpreter::matrix::Matrix;
+use crate::interpreter::ordered_map::OrderedMap;
+use crate::interpreter::types::{Expr, HashKey, SymbolData, HASH_PRINT_LIMIT};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// The one printer behind every front-end: the terminal REPL, the raylib UI, the IDE panes
+// and the print/format builtins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrintMode {
+    // What print/println and format's ~a show: strings and characters raw, keywords
+    // without their colon
+    Display,
+    // Readable form used for REPL results and format's ~s: strings quoted, characters as
+    // #\c (named for whitespace), keywords with their colon, gensyms as #:name#id
+    Write,
+}
+
+// Nesting printed before giving up when no `max_depth` is set, so structures built
+// deeper than the stack allows still print instead of overflowing
+pub const MAX_PRINT_DEPTH: usize = 1000;
+
+// Nested containers deeper than `max_depth` (at most MAX_PRINT_DEPTH) print as `#`, and
+// lists or vectors longer than `max_length` end in `...`. Hash tables never show more than
+// HASH_PRINT_LIMIT entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Printer {
+    pub mode: PrintMode,
+    pub max_depth: Option<usize>,
+    pub max_length: Option<usize>,
+}
+
+pub fn display_string(expr: &Expr) -> String {
+    Printer::new(PrintMode::Display).print(expr)
+}
+
+pub fn write_string(expr: &Expr) -> String {
+    Printer::new(PrintMode::Write).print(expr)
+}
+
+impl Printer {
+    pub fn new(mode: PrintMode) -> Self {
+        Self {
+            mode,
+            max_depth: None,
+            max_length: None,
+        }
+    }
+
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    pub fn with_max_length(mut self, length: usize) -> Self {
+        self.max_length = Some(length);
+        self
+    }
+
+    pub fn print(&self, expr: &Expr) -> String {
+        let mut out = String::new();
+        let mut open = Vec::new();
+        self.print_into(expr, 0, &mut open, &mut out);
+        out
+    }
+
+    // `open` holds the vectors and hash tables currently being printed; meeting one of
+    // them again means the structure contains itself
+    fn print_into(&self, expr: &Expr, depth: usize, open: &mut Vec<*const ()>, out: &mut String) {
+        let is_container = match expr {
+            Expr::List(items) => !items.is_empty(),
+            Expr::Cons(_, _)
+            | Expr::Vector(_)
+            | Expr::HashTable(_)
+            | Expr::Matrix(_)
+            | Expr::Queue(_)
+            | Expr::Stack(_)
+            | Expr::Struct(_) => true,
+            _ => false,
+        };
+        if is_container && depth >= self.max_depth.unwrap_or(MAX_PRINT_DEPTH) {
+            out.push('#');
+            return;
+        }
+
+        match expr {
+            Expr::Integer(n) => out.push_str(&n.to_string()),
+            Expr::BigInt(n) => out.push_str(&n.to_string()),
+            Expr::Float(f) => out.push_str(&format_float(*f)),
+            Expr::Rational {
+                numerator,
+                denominator,
+            } => out.push_str(&format!("{}/{}", numerator, denominator)),
+            Expr::Character(ch) => match self.mode {
+                PrintMode::Display => out.push(*ch),
+                PrintMode::Write => out.push_str(&character_name(*ch)),
+            },
+            Expr::String(s) => match self.mode {
+                PrintMode::Display => out.push_str(s),
+                PrintMode::Write => out.push_str(&quote_string(s)),
+            },
+            Expr::Symbol(sym_data) => match (self.mode, sym_data) {
+                (PrintMode::Write, SymbolData::Keyword(name)) => {
+                    out.push_str(&format!(":{}", name))
+                }
+                (PrintMode::Write, SymbolData::Uninterned(name, id)) => {
+                    out.push_str(&format!("#:{}#{}", name, id))
+                }
+                _ => out.push_str(sym_data.name()),
+            },
+            Expr::List(items) => {
+                out.push('(');
+                self.print_items(items.iter(), depth, open, out);
+                out.push(')');
+            }
+            Expr::Cons(car, cdr) => {
+                out.push('(');
+                let mut items = vec![car.as_ref()];
+                let mut tail = cdr.as_ref();
+                while let Expr::Cons(next_car, next_cdr) = tail {
+                    items.push(next_car.as_ref());
+                    tail = next_cdr.as_ref();
+                }
+                if let Expr::List(rest) = tail {
+                    items.extend(rest.iter());
+                }
+                let truncated = self.print_items(items.into_iter(), depth, open, out);
+                if !truncated && !matches!(tail, Expr::List(_)) {
+                    out.push_str(" . ");
+                    self.print_into(tail, depth + 1, open, out);
+                }
+                out.push(')');
+            }
+            Expr::Vector(vec) => {
+                let id = Rc::as_ptr(vec) as *const ();
+                if open.contains(&id) {
+                    out.push_str("#<circular>");
+                    return;
+                }
+                open.push(id);
+                out.push('[');
+                self.print_items(vec.borrow().iter(), depth, open, out);
+                out.push(']');
+                open.pop();
+            }
+            Expr::HashTable(table) => {
+                let id = Rc::as_ptr(table) as *const ();
+                if open.contains(&id) {
+                    out.push_str("#<circular>");
+                    return;
+                }
+                open.push(id);
+                self.print_hash_table(table, depth, open, out);
+                open.pop();
+            }
+            Expr::Matrix(matrix) => self.print_matrix(&matrix.borrow(), out),
+            // #<queue 1 2 3> from the front, #<stack 3 2 1> from the top
+            Expr::Queue(items) | Expr::Stack(items) => {
+                let id = Rc::as_ptr(items) as *const ();
+                if open.contains(&id) {
+                    out.push_str("#<circular>");
+                    return;
+                }
+                open.push(id);
+                let items = items.borrow();
+                if matches!(expr, Expr::Queue(_)) {
+                    out.push_str("#<queue");
+                    self.print_tagged_items(items.iter(), depth, open, out);
+                } else {
+                    out.push_str("#<stack");
+                    self.print_tagged_items(items.iter().rev(), depth, open, out);
+                }
+                out.push('>');
+                open.pop();
+            }
+            // Only the size: the storage order isn't the order items come out in
+            Expr::Heap(heap) => out.push_str(&format!("#<heap {}>", heap.borrow().items.len())),
+            Expr::Iterator(cursor) => out.push_str(if cursor.borrow().is_done() {
+                "#<iterator done>"
+            } else {
+                "#<iterator>"
+            }),
+            Expr::Port(port) => out.push_str(&format!(
+                "#<port {} {}>",
+                quote_string(&port.path),
+                port.status()
+            )),
+            // #<point x: 1 y: 2>, fields in the order defstruct named them
+            Expr::Struct(instance) => {
+                let id = Rc::as_ptr(instance) as *const ();
+                if open.contains(&id) {
+                    out.push_str("#<circular>");
+                    return;
+                }
+                open.push(id);
+                let instance = instance.borrow();
+                out.push_str(&format!("#<{}", instance.type_name));
+                for (field, value) in instance.fields.iter().zip(&instance.values) {
+                    out.push_str(&format!(" {}: ", field));
+                    self.print_into(value, depth + 1, open, out);
+                }
+                out.push('>');
+                open.pop();
+            }
+        }
+    }
+
+    // Prints space-separated elements one level down, returning whether the
+    // length limit cut them short
+    fn print_items<'a>(
+        &self,
+        items: impl Iterator<Item = &'a Expr>,
+        depth: usize,
+        open: &mut Vec<*const ()>,
+        out: &mut String,
+    ) -> bool {
+        for (index, item) in items.enumerate() {
+            if index > 0 {
+                out.push(' ');
+            }
+            if self.max_length.is_some_and(|max| index >= max) {
+                out.push_str("...");
+                return true;
+            }
+            self.print_into(item, depth + 1, open, out);
+        }
+        false
+    }
+
+    // A space before the elements unless there are none, as in "#<queue>"
+    fn print_tagged_items<'a>(
+        &self,
+        items: impl Iterator<Item = &'a Expr>,
+        depth: usize,
+        open: &mut Vec<*const ()>,
+        out: &mut String,
+    ) {
+        let mut items = items.peekable();
+        if items.peek().is_some() {
+            out.push(' ');
+            self.print_items(items, depth, open, out);
+        }
+    }
+
+    // #<hash-table :a 1 :b 2 ...3 more>, entries in the order they were added
+    fn print_hash_table(
+        &self,
+        table: &Rc<RefCell<OrderedMap<HashKey, Expr>>>,
+        depth: usize,
+        open: &mut Vec<*const ()>,
+        out: &mut String,
+    ) {
+        let table = table.borrow();
+        let limit = self
+            .max_length
+            .map_or(HASH_PRINT_LIMIT, |max| max.min(HASH_PRINT_LIMIT));
+        out.push_str("#<hash-table");
+        for (key, value) in table.iter().take(limit) {
+            out.push(' ');
+            self.print_into(&key.to_expr(), depth + 1, open, out);
+            out.push(' ');
+            self.print_into(value, depth + 1, open, out);
+        }
+        if table.len() > limit {
+            out.push_str(&format!(" ...{} more", table.len() - limit));
+        }
+        out.push('>');
+    }
+
+    // #<matrix 2x3 (1.0 2.0 3.0) (4.0 5.0 6.0)>, one list per row
+    fn print_matrix(&self, matrix: &Matrix, out: &mut String) {
+        out.push_str(&format!("#<matrix {}", matrix.shape()));
+        let limit = self.max_length.unwrap_or(usize::MAX);
+        for row in 0..matrix.rows.min(limit) {
+            out.push_str(" (");
+            let values = matrix.row(row);
+            for (index, value) in values.iter().take(limit).enumerate() {
+                if index > 0 {
+                    out.push(' ');
+                }
+                out.push_str(&format_float(*value));
+            }
+            if values.len() > limit {
+                out.push_str(" ...");
+            }
+            out.push(')');
+        }
+        if matrix.rows > limit {
+            out.push_str(" ...");
+        }
+        out.push('>');
+    }
+}
+
+// The shortest text that reads back as exactly `f`, always recognizably a float: 1.0 not
+// 1, exponents outside 1e-7..1e21, and +inf.0, -inf.0 and +nan.0 for the special values
+pub fn format_float(f: f64) -> String {
+    if f.is_nan() {
+        return "+nan.0".to_string();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "+inf.0" } else { "-inf.0" }.to_string();
+    }
+    let magnitude = f.abs();
+    if magnitude != 0.0 && !(1e-7..1e21).contains(&magnitude) {
+        return format!("{:e}", f);
+    }
+    let text = f.to_string();
+    if text.contains('.') {
+        text
+    } else {
+        format!("{}.0", text)
+    }
+}
+
+// A string literal the reader turns back into `s`, using only the escapes it knows
+pub fn quote_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn character_name(ch: char) -> String {
+    match ch {
+        ' ' => "#\\space".to_string(),
+        '\n' => "#\\newline".to_string(),
+        '\t' => "#\\tab".to_string(),
+        '\r' => "#\\return".to_string(),
+        c => format!("#\\{}", c),
+    }
+}
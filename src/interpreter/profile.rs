@@ -0,0 +1,82 @@
+use crate::interpreter::batch::split_forms;
+use crate::interpreter::evaluator::Evaluator;
+use std::time::{Duration, Instant};
+
+// Profiling a buffer for the editor's heatmap: each top-level form is evaluated in turn,
+// timed and its evaluation steps counted, stopping at the first error. A form's heat is
+// its share of the time relative to the hottest form's, so the hottest is always 1.0.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormTiming {
+    // First and last line of the form, from 1
+    pub line: usize,
+    pub end_line: usize,
+    pub steps: usize,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub forms: Vec<FormTiming>,
+    // The line and message of the form that failed, which is the last one timed
+    pub error: Option<(usize, String)>,
+}
+
+pub fn profile_program(evaluator: &mut Evaluator, source: &str) -> Profile {
+    let mut profile = Profile::default();
+    let (forms, _) = split_forms(source);
+    for form in forms {
+        let steps_before = evaluator.step_count();
+        let started = Instant::now();
+        let result = evaluator.eval_source(&form.source);
+        profile.forms.push(FormTiming {
+            line: form.line,
+            end_line: form.line + form.source.matches('\n').count(),
+            steps: evaluator.step_count().wrapping_sub(steps_before),
+            duration: started.elapsed(),
+        });
+        if let Err(error) = result {
+            profile.error = Some((form.line, error.to_string()));
+            break;
+        }
+    }
+    profile
+}
+
+impl Profile {
+    pub fn total(&self) -> Duration {
+        self.forms.iter().map(|form| form.duration).sum()
+    }
+
+    // The form that took longest
+    pub fn hottest(&self) -> Option<&FormTiming> {
+        self.forms.iter().max_by_key(|form| form.duration)
+    }
+
+    // What share of the total time `form` took, from 0.0 to 1.0
+    pub fn share(&self, form: &FormTiming) -> f64 {
+        let total = self.total().as_secs_f64();
+        if total == 0.0 {
+            return 0.0;
+        }
+        form.duration.as_secs_f64() / total
+    }
+
+    // Each line a form covers with that form's heat, for shading
+    pub fn line_heat(&self) -> Vec<(usize, f64)> {
+        let Some(hottest) = self.hottest() else {
+            return Vec::new();
+        };
+        let peak = hottest.duration.as_secs_f64();
+        let mut lines = Vec::new();
+        for form in &self.forms {
+            let heat = if peak == 0.0 {
+                0.0
+            } else {
+                form.duration.as_secs_f64() / peak
+            };
+            lines.extend((form.line..=form.end_line).map(|line| (line, heat)));
+        }
+        lines
+    }
+}
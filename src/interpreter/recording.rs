@@ -0,0 +1,229 @@
+use crate::interpreter::environment::Environment;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::ports::{OutputPort, StringPort};
+use crate::interpreter::prelude;
+use crate::interpreter::symbol_map::SymbolMap;
+use crate::interpreter::types::{Expr, Symbol, SymbolData};
+use std::rc::Rc;
+
+// Time-travel for a live session. While recording is on, every top-level form evaluated is
+// logged with the globals it added or changed, and (rewind n) goes back to how things were
+// before form n by replaying the forms before it into a fresh environment. Replaying runs
+// their side effects again, such as run-process, with their output thrown away. Session
+// commands, these included, are neither recorded nor replayed. Changes are to bindings: a
+// hash table filled in place is still the same global, though replaying fills it again.
+//
+// The recording keeps the globals as they stood after each form too, which costs little
+// since snapshots share everything that didn't change, so the history can be asked what a
+// global held at any point or what changed between any two forms without replaying.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalChange {
+    pub name: String,
+    // None where the global wasn't defined
+    pub before: Option<Expr>,
+    pub after: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedForm {
+    pub source: String,
+    pub succeeded: bool,
+    // By name
+    pub changes: Vec<GlobalChange>,
+}
+
+#[derive(Clone)]
+pub struct Recording {
+    forms: Vec<RecordedForm>,
+    // The globals when recording started, then after each form
+    snapshots: Vec<SymbolMap<Expr>>,
+    // Goes up with every form recorded, and carries on past a rewind, so a view of the
+    // recording can tell when it's out of date
+    revision: u64,
+}
+
+impl Recording {
+    pub fn new(environment: &Environment) -> Self {
+        Recording {
+            forms: Vec::new(),
+            snapshots: vec![environment.globals_snapshot()],
+            revision: 0,
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn forms(&self) -> &[RecordedForm] {
+        &self.forms
+    }
+
+    // Logs `source`, which took the globals from `before` to those of `environment`
+    pub fn record(
+        &mut self,
+        source: &str,
+        succeeded: bool,
+        before: &SymbolMap<Expr>,
+        environment: &Environment,
+    ) {
+        let source = source.trim();
+        if source.is_empty() {
+            return;
+        }
+        let after = environment.globals_snapshot();
+        self.forms.push(RecordedForm {
+            source: source.to_string(),
+            succeeded,
+            changes: global_changes(before, &after),
+        });
+        self.snapshots.push(after);
+        self.revision += 1;
+    }
+
+    // The globals after form `form`, counting from 1, or when recording started for 0
+    pub fn globals_after(&self, form: usize) -> Option<&SymbolMap<Expr>> {
+        self.snapshots.get(form)
+    }
+
+    // What changed between the globals after form `from` and after form `to`, numbered as
+    // for globals_after; None if either is out of range
+    pub fn changes_between(&self, from: usize, to: usize) -> Option<Vec<GlobalChange>> {
+        Some(global_changes(
+            self.globals_after(from)?,
+            self.globals_after(to)?,
+        ))
+    }
+
+    // Every recorded change to the global `name`, with the number of the form that made it
+    pub fn history_of(&self, name: &str) -> Vec<(usize, &GlobalChange)> {
+        self.forms
+            .iter()
+            .enumerate()
+            .flat_map(|(index, form)| {
+                form.changes
+                    .iter()
+                    .filter(|change| change.name == name)
+                    .map(move |change| (index + 1, change))
+            })
+            .collect()
+    }
+
+    // As (recorded-forms) shows it: a list per form of its number, source, :ok or :error,
+    // and a list of changes, each (name :added value), (name :changed old new) or
+    // (name :removed old)
+    pub fn to_expr(&self) -> Expr {
+        let forms = self.forms.iter().enumerate().map(|(index, form)| {
+            let changes = form.changes.iter().map(|change| {
+                let name = Expr::Symbol(SymbolData::Interned(Symbol::intern(&change.name)));
+                change.to_expr(name)
+            });
+            Expr::new_list(vec![
+                Expr::Integer(index as i64 + 1),
+                Expr::String(form.source.clone()),
+                keyword(if form.succeeded { "ok" } else { "error" }),
+                Expr::new_list(changes.collect()),
+            ])
+        });
+        Expr::new_list(forms.collect())
+    }
+}
+
+impl GlobalChange {
+    // (label :added value), (label :changed old new) or (label :removed old)
+    pub fn to_expr(&self, label: Expr) -> Expr {
+        let mut items = vec![label];
+        match (&self.before, &self.after) {
+            (None, Some(after)) => items.extend([keyword("added"), after.clone()]),
+            (Some(before), Some(after)) => {
+                items.extend([keyword("changed"), before.clone(), after.clone()])
+            }
+            (Some(before), None) => items.extend([keyword("removed"), before.clone()]),
+            (None, None) => {}
+        }
+        Expr::new_list(items)
+    }
+}
+
+// By name; a global defined again with an equal value hasn't changed
+fn global_changes(before: &SymbolMap<Expr>, after: &SymbolMap<Expr>) -> Vec<GlobalChange> {
+    let mut changes: Vec<GlobalChange> = after
+        .diff(before)
+        .into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(name, old, new)| GlobalChange {
+            name: name.to_string(),
+            before: old.cloned(),
+            after: new.cloned(),
+        })
+        .collect();
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
+
+fn keyword(name: &str) -> Expr {
+    Expr::Symbol(SymbolData::Keyword(Symbol::keyword(name)))
+}
+
+impl Evaluator {
+    // Starts a new recording, dropping any earlier one
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::new(&self.environment));
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    // Checks that recorded form `form`, counting from 1, can be rewound to
+    pub(crate) fn check_rewind(&self, form: usize) -> Result<(), String> {
+        let Some(recording) = &self.recording else {
+            return Err("rewind requires recording to be on; see start-recording".to_string());
+        };
+        let count = recording.forms.len();
+        if form == 0 || form > count {
+            return Err(format!(
+                "rewind: no form {} among the {} recorded",
+                form, count
+            ));
+        }
+        Ok(())
+    }
+
+    // Puts the globals back as they were before recorded form `form` (counting from 1):
+    // the forms before it are evaluated again, in order, in a fresh environment, and make
+    // up the recording from then on. Returns how many forms were replayed.
+    pub fn rewind(&mut self, form: usize) -> Result<usize, String> {
+        self.check_rewind(form)?;
+        let Some(recording) = self.recording.take() else {
+            return Ok(0);
+        };
+        self.environment = if prelude::is_enabled() {
+            prelude::environment()
+        } else {
+            let mut environment = Environment::new();
+            environment.define_builtins();
+            environment
+        };
+        let mut fresh = Recording::new(&self.environment);
+        fresh.revision = recording.revision + 1;
+        self.recording = Some(fresh);
+
+        let quiet: Rc<dyn OutputPort> = Rc::new(StringPort::new());
+        let output = std::mem::replace(&mut self.output, Rc::clone(&quiet));
+        let error_output = std::mem::replace(&mut self.error_output, quiet);
+        let restart_chooser = self.restart_chooser.take();
+        let session = self.session.clone();
+        let replayed = &recording.forms[..form - 1];
+        for entry in replayed {
+            // Forms that failed the first time may still have defined something first
+            let _ = self.eval_source(&entry.source);
+        }
+        self.output = output;
+        self.error_output = error_output;
+        self.restart_chooser = restart_chooser;
+        self.session = session;
+        Ok(replayed.len())
+    }
+}
@@ -0,0 +1,425 @@
+use crate::interpreter::line_editor::{self, History, LineEvent};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+// Remote REPL protocol over TCP. Every message is a frame: a 4-byte big-endian payload
+// length followed by that many bytes of UTF-8. A client sends one form per frame; the
+// server answers each with one frame, "ok <printed value>" or "err <message>". The server
+// may also push "def <origin> <name>" whenever another front-end (re)defines a global, at
+// any point between replies.
+pub const DEFAULT_REMOTE_ADDR: &str = "127.0.0.1:7888";
+
+// Frames announcing a larger payload are refused and the connection closed, so a client
+// can't make the server buffer unbounded input
+pub const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+// Requests each connection may make per second, with bursts up to the same number.
+// Requests over the limit are answered with an error instead of being evaluated.
+pub const MAX_REQUESTS_PER_SECOND: u32 = 50;
+
+// Clients connected at once; further connections are closed as soon as they're accepted
+pub const MAX_CONNECTIONS: usize = 16;
+
+// Replies and notices queued for a client that isn't reading them. A client whose queue
+// would grow past this is disconnected, so a stalled reader can't hold the server up or
+// make it buffer without bound.
+pub const MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+const FRAME_HEADER_BYTES: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    Ok(String),
+    Err(String),
+}
+
+impl Response {
+    pub fn to_payload(&self) -> String {
+        match self {
+            Response::Ok(text) => format!("ok {}", text),
+            Response::Err(text) => format!("err {}", text),
+        }
+    }
+
+    pub fn parse(payload: &str) -> Option<Self> {
+        if let Some(text) = payload.strip_prefix("ok ") {
+            Some(Response::Ok(text.to_string()))
+        } else {
+            payload
+                .strip_prefix("err ")
+                .map(|text| Response::Err(text.to_string()))
+        }
+    }
+}
+
+pub fn definition_notice(origin: &str, name: &str) -> String {
+    format!("def {} {}", origin, name)
+}
+
+// Splits a "def <origin> <name>" push into (origin, name)
+pub fn parse_definition_notice(payload: &str) -> Option<(&str, &str)> {
+    payload.strip_prefix("def ")?.split_once(' ')
+}
+
+pub fn encode_frame(payload: &str) -> Vec<u8> {
+    let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+    frame.extend_from_slice(payload.as_bytes());
+    frame
+}
+
+// Removes the first complete frame from `buffer`. Ok(None) means more bytes are needed;
+// an error means the next frame is over `max_bytes` and the stream can't be trusted.
+pub fn take_frame(buffer: &mut Vec<u8>, max_bytes: usize) -> Result<Option<String>, String> {
+    if buffer.len() < FRAME_HEADER_BYTES {
+        return Ok(None);
+    }
+    let mut header = [0u8; FRAME_HEADER_BYTES];
+    header.copy_from_slice(&buffer[..FRAME_HEADER_BYTES]);
+    let length = u32::from_be_bytes(header) as usize;
+    if length > max_bytes {
+        return Err(format!(
+            "message too large ({} bytes, limit {})",
+            length, max_bytes
+        ));
+    }
+    if buffer.len() < FRAME_HEADER_BYTES + length {
+        return Ok(None);
+    }
+
+    let frame: Vec<u8> = buffer.drain(..FRAME_HEADER_BYTES + length).collect();
+    Ok(Some(
+        String::from_utf8_lossy(&frame[FRAME_HEADER_BYTES..]).into_owned(),
+    ))
+}
+
+// Blocking read of one frame; Ok(None) when the peer closed the connection
+pub fn read_frame(reader: &mut impl Read, max_bytes: usize) -> io::Result<Option<String>> {
+    let mut header = [0u8; FRAME_HEADER_BYTES];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let length = u32::from_be_bytes(header) as usize;
+    if length > max_bytes {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("message too large ({} bytes, limit {})", length, max_bytes),
+        ));
+    }
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(String::from_utf8_lossy(&payload).into_owned()))
+}
+
+// Token bucket: refills at `per_second` tokens a second and holds at most that many
+struct RateLimiter {
+    per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    fn new(per_second: u32) -> Self {
+        Self {
+            per_second: per_second as f64,
+            tokens: per_second as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn allow(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.per_second);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct RemoteConnection {
+    stream: TcpStream,
+    peer: String,
+    pending: Vec<u8>,
+    // Frames written to the socket only in part so far
+    output: Vec<u8>,
+    limiter: RateLimiter,
+}
+
+impl RemoteConnection {
+    // Queues a frame and writes what the socket takes without blocking; false when the
+    // client has to be dropped
+    fn send(&mut self, payload: &str, max_output_bytes: usize) -> bool {
+        if self.output.len() + FRAME_HEADER_BYTES + payload.len() > max_output_bytes {
+            return false;
+        }
+        self.output.extend(encode_frame(payload));
+        self.flush()
+    }
+
+    fn flush(&mut self) -> bool {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => return false,
+                Ok(n) => {
+                    self.output.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+}
+
+// Non-blocking server polled from the owner's main loop, so evaluation happens on the
+// thread that owns the evaluator
+pub struct RemoteServer {
+    listener: TcpListener,
+    connections: Vec<RemoteConnection>,
+    max_message_bytes: usize,
+    requests_per_second: u32,
+    max_connections: usize,
+    max_output_bytes: usize,
+}
+
+impl RemoteServer {
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            connections: Vec::new(),
+            max_message_bytes: MAX_MESSAGE_BYTES,
+            requests_per_second: MAX_REQUESTS_PER_SECOND,
+            max_connections: MAX_CONNECTIONS,
+            max_output_bytes: MAX_OUTPUT_BYTES,
+        })
+    }
+
+    // Overrides the per-message size and per-connection request rate limits
+    pub fn with_limits(mut self, max_message_bytes: usize, requests_per_second: u32) -> Self {
+        self.max_message_bytes = max_message_bytes;
+        self.requests_per_second = requests_per_second;
+        self
+    }
+
+    // Overrides how many clients may connect at once and how much output each may leave
+    // unread
+    pub fn with_connection_limits(
+        mut self,
+        max_connections: usize,
+        max_output_bytes: usize,
+    ) -> Self {
+        self.max_connections = max_connections;
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    // Sends `payload` to every client except the one at `except`
+    pub fn broadcast(&mut self, payload: &str, except: Option<&str>) {
+        let max_output_bytes = self.max_output_bytes;
+        self.connections.retain_mut(|connection| {
+            Some(connection.peer.as_str()) == except || connection.send(payload, max_output_bytes)
+        });
+    }
+
+    // Accepts new clients and answers every complete request received since the last
+    // poll. `eval` gets the peer address and the source of one form.
+    pub fn poll(&mut self, eval: &mut dyn FnMut(&str, &str) -> Response) {
+        while let Ok((stream, addr)) = self.listener.accept() {
+            // Dropping the stream closes connections over the limit
+            if self.connections.len() < self.max_connections && stream.set_nonblocking(true).is_ok()
+            {
+                self.connections.push(RemoteConnection {
+                    stream,
+                    peer: addr.to_string(),
+                    pending: Vec::new(),
+                    output: Vec::new(),
+                    limiter: RateLimiter::new(self.requests_per_second),
+                });
+            }
+        }
+
+        let max_bytes = self.max_message_bytes;
+        let max_output_bytes = self.max_output_bytes;
+        self.connections.retain_mut(|connection| {
+            if !connection.flush() {
+                return false;
+            }
+            // Never buffer more than one maximal frame plus a read's worth; the rest
+            // waits in the socket until those requests are answered
+            let mut buffer = [0u8; 4096];
+            while connection.pending.len() <= FRAME_HEADER_BYTES + max_bytes {
+                match connection.stream.read(&mut buffer) {
+                    Ok(0) => return false,
+                    Ok(n) => connection.pending.extend_from_slice(&buffer[..n]),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(_) => return false,
+                }
+            }
+
+            loop {
+                let source = match take_frame(&mut connection.pending, max_bytes) {
+                    Ok(Some(source)) => source,
+                    Ok(None) => return true,
+                    Err(message) => {
+                        connection.send(&Response::Err(message).to_payload(), max_output_bytes);
+                        return false;
+                    }
+                };
+                if source.trim().is_empty() {
+                    continue;
+                }
+                let response = if connection.limiter.allow() {
+                    eval(&connection.peer, &source)
+                } else {
+                    Response::Err("rate limit exceeded, request dropped".to_string())
+                };
+                if !connection.send(&response.to_payload(), max_output_bytes) {
+                    return false;
+                }
+            }
+        });
+    }
+}
+
+// `zeus repl --connect`: reads forms locally (continuing across lines until the parens
+// balance), sends each to the server and prints the reply
+pub fn run_client(addr: &str) -> Result<(), String> {
+    let stream =
+        TcpStream::connect(addr).map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+    let mut writer = stream
+        .try_clone()
+        .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+
+    // Replies arrive on a reader thread so the connection is always drained
+    let (sender, responses) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stream = stream;
+        while let Ok(Some(payload)) = read_frame(&mut stream, MAX_MESSAGE_BYTES) {
+            if let Some((origin, name)) = parse_definition_notice(&payload) {
+                println!("\n;; {} defined by {}", name, origin);
+                continue;
+            }
+            match Response::parse(&payload) {
+                Some(response) => {
+                    if sender.send(response).is_err() {
+                        break;
+                    }
+                }
+                None => println!("{}", payload),
+            }
+        }
+    });
+
+    println!("Connected to Zeus at {}", addr);
+    println!("Type 'exit' or press Ctrl+D to disconnect\n");
+
+    let mut history = History::new();
+    let mut form = String::new();
+    loop {
+        let prompt = if form.is_empty() { "zeus> " } else { "  ... " };
+        let line = match line_editor::read_line(prompt, &mut history) {
+            Ok(LineEvent::Line(line)) => line,
+            Ok(LineEvent::Eof) => break,
+            // Drops the form being typed, as at a shell prompt
+            Ok(LineEvent::Interrupt) => {
+                form.clear();
+                continue;
+            }
+            Err(e) => return Err(format!("Error reading input: {}", e)),
+        };
+        if form.is_empty() && line.trim() == "exit" {
+            break;
+        }
+
+        form.push_str(&line);
+        form.push('\n');
+        if form.trim().is_empty() {
+            form.clear();
+            continue;
+        }
+        if paren_depth(&form) > 0 {
+            continue;
+        }
+
+        let source = form.trim().to_string();
+        form.clear();
+        if source.len() > MAX_MESSAGE_BYTES {
+            println!(
+                "Error: form is {} bytes, the server accepts at most {}",
+                source.len(),
+                MAX_MESSAGE_BYTES
+            );
+            continue;
+        }
+        writer
+            .write_all(&encode_frame(&source))
+            .map_err(|e| format!("Connection lost: {}", e))?;
+
+        match responses.recv() {
+            Ok(Response::Ok(text)) => println!("{}", text),
+            Ok(Response::Err(text)) => println!("Error: {}", text),
+            Err(_) => return Err("Connection closed by server".to_string()),
+        }
+    }
+
+    println!("Goodbye!");
+    Ok(())
+}
+
+// Open parens not yet closed, ignoring those inside strings, comments and #\( literals
+pub fn paren_depth(source: &str) -> i32 {
+    let mut depth = 0;
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+    while let Some(ch) = chars.next() {
+        if in_string {
+            match ch {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            ';' => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '#' if chars.peek() == Some(&'\\') => {
+                chars.next();
+                chars.next();
+            }
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth
+}
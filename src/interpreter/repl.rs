@@ -1,33 +1,64 @@
 use crate::interpreter::{
-    evaluator::Evaluator,
-    types::{Expr, SymbolData},
+    evaluator::{Evaluator, Restart},
+    introspect::{scope_chain, ScopeInfo},
+    line_editor::{self, History, LineEvent},
+    pretty::{pretty_print, DEFAULT_PRETTY_WIDTH},
+    printer::write_string,
+    types::{EvalError, Expr},
 };
+use std::cell::Cell;
 use std::io::{self, Write};
+use std::rc::Rc;
+
+// Typed at the debugger prompt to list the bindings in each scope
+const SCOPES_COMMAND: &str = ":scopes";
 
 pub struct Repl {
     evaluator: Evaluator,
+    // Set when the restart menu chose to retry or abort rather than take a restart
+    menu_exit: Rc<Cell<Option<MenuChoice>>>,
+}
+
+// What the restart menu chose
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuChoice {
+    Restart(usize, Vec<Expr>),
+    // Evaluate the whole top-level form again
+    Retry,
+    Abort,
 }
 
 impl Repl {
     pub fn new() -> Self {
         Repl {
             evaluator: Evaluator::new(),
+            menu_exit: Rc::new(Cell::new(None)),
         }
     }
 
     pub fn run(&mut self) {
+        sigint::install(self.evaluator.interrupt_handle());
+        let menu_exit = Rc::clone(&self.menu_exit);
+        self.evaluator.restart_chooser =
+            Some(Rc::new(move |evaluator, err, restarts| match restart_menu(
+                evaluator, err, restarts,
+            ) {
+                MenuChoice::Restart(id, args) => Some((id, args)),
+                exit => {
+                    menu_exit.set(Some(exit));
+                    None
+                }
+            }));
+        let mut history = History::new();
         loop {
-            print!("zeus> ");
-            io::stdout().flush().unwrap();
-
-            let mut input = String::new();
-            match io::stdin().read_line(&mut input) {
-                Ok(0) => {
-                    // EOF reached
+            match line_editor::read_line("zeus> ", &mut history) {
+                Ok(LineEvent::Eof) => {
                     println!("\nGoodbye!");
                     break;
                 }
-                Ok(_) => {
+                // As SIGINT does at the prompt; the terminal has been put back by now
+                Ok(LineEvent::Interrupt) => std::process::exit(130),
+                Ok(LineEvent::Line(input)) => {
                     let input = input.trim();
 
                     if input == "exit" {
@@ -39,8 +70,25 @@ impl Repl {
                         continue;
                     }
 
-                    match self.evaluate(input) {
-                        Ok(result) => println!("{}", self.format_expr(&result)),
+                    sigint::set_evaluating(true);
+                    let mut result = self.evaluate(input);
+                    let mut menu_exit = self.menu_exit.take();
+                    while result.is_err() && menu_exit == Some(MenuChoice::Retry) {
+                        result = self.evaluate(input);
+                        menu_exit = self.menu_exit.take();
+                    }
+                    sigint::set_evaluating(false);
+                    match result {
+                        Ok(result) => {
+                            let formatted = self.format_expr(&result);
+                            if formatted.chars().count() > DEFAULT_PRETTY_WIDTH {
+                                println!("{}", pretty_print(&result, DEFAULT_PRETTY_WIDTH));
+                            } else {
+                                println!("{}", formatted);
+                            }
+                        }
+                        // The menu already showed the error
+                        Err(_) if menu_exit == Some(MenuChoice::Abort) => {}
                         Err(e) => println!("Error: {}", e),
                     }
                 }
@@ -52,76 +100,198 @@ impl Repl {
         }
     }
 
+    // This is the terminal REPL, so (exit code) ends the process
     pub fn evaluate(&mut self, input: &str) -> Result<Expr, String> {
-        self.evaluator.eval_str(input)
+        match self.evaluator.eval_source(input) {
+            Err(EvalError::Exit(code)) => {
+                io::stdout().flush().ok();
+                std::process::exit(code)
+            }
+            result => result.map_err(|e| e.to_string()),
+        }
     }
 
     pub fn format_expr(&self, expr: &Expr) -> String {
-        match expr {
-            Expr::Integer(n) => format!("{}", n),
-            Expr::Float(f) => format!("{}", f),
-            Expr::Rational {
-                numerator,
-                denominator,
-            } => format!("{}/{}", numerator, denominator),
-            Expr::Character(ch) => format!(
-                "#\\{}",
-                match *ch {
-                    ' ' => "space".to_string(),
-                    '\n' => "newline".to_string(),
-                    '\t' => "tab".to_string(),
-                    '\r' => "return".to_string(),
-                    c => c.to_string(),
+        write_string(expr)
+    }
+}
+
+// Shown by the terminal REPL when an error leaves a restart-case: the error, then the
+// active restarts and the REPL's own choices, numbered. Answers are read from the
+// evaluator's input port. A restart's parameters are filled in by evaluating an expression
+// for each. The debugger evaluates expressions where the error happened until a blank line
+// brings the menu back; :scopes there lists the bindings in each scope, from the globals in.
+// End of input aborts.
+pub fn restart_menu(
+    evaluator: &mut Evaluator,
+    err: &EvalError,
+    restarts: &[Restart],
+) -> MenuChoice {
+    evaluator.write_output(&format!("Error: {}\n", err));
+    let count = restarts.len();
+    loop {
+        evaluator.write_output("Restarts:\n");
+        for (index, restart) in restarts.iter().enumerate() {
+            evaluator.write_output(&format!(
+                "  {}: {} ({})\n",
+                index + 1,
+                restart.name,
+                restart.params.join(" ")
+            ));
+        }
+        evaluator.write_output(&format!(
+            "  {}: Retry the top-level form\n  {}: Abort to the top level\n  {}: Enter the debugger\nChoose a restart: ",
+            count + 1,
+            count + 2,
+            count + 3
+        ));
+        let Some(line) = evaluator.input.read_line() else {
+            return MenuChoice::Abort;
+        };
+        let choice = line.trim().parse::<usize>().unwrap_or(0);
+        if (1..=count).contains(&choice) {
+            let restart = &restarts[choice - 1];
+            let mut args = Vec::new();
+            for param in &restart.params {
+                match read_entry(evaluator, &format!("Value for {}: ", param)) {
+                    Entry::Value(value) => args.push(value),
+                    Entry::Failed(err) => {
+                        evaluator.write_output(&format!("Error: {}\n", err));
+                        break;
+                    }
+                    Entry::Blank => break,
+                    Entry::End => return MenuChoice::Abort,
                 }
-            ),
-            Expr::Symbol(sym_data) => match sym_data {
-                SymbolData::Keyword(name) => format!(":{}", name),
-                SymbolData::Uninterned(name, id) => format!("#:{}#{}", name, id),
-                SymbolData::Interned(name) => name.clone(),
-            },
-            Expr::String(s) => format!("\"{}\"", s),
-            Expr::List(list) => {
-                let items: Vec<String> = list.iter().map(|e| self.format_expr(e)).collect();
-                format!("({})", items.join(" "))
             }
-            Expr::Cons(car, cdr) => {
-                let mut repr = String::from("(");
-                repr.push_str(&self.format_expr(car));
-
-                let mut tail = cdr.as_ref();
-                loop {
-                    match tail {
-                        Expr::Cons(next_car, next_cdr) => {
-                            repr.push(' ');
-                            repr.push_str(&self.format_expr(next_car));
-                            tail = next_cdr.as_ref();
-                        }
-                        Expr::List(list) => {
-                            for item in list {
-                                repr.push(' ');
-                                repr.push_str(&self.format_expr(item));
-                            }
-                            repr.push(')');
-                            break;
-                        }
-                        other => {
-                            repr.push_str(" . ");
-                            repr.push_str(&self.format_expr(other));
-                            repr.push(')');
-                            break;
-                        }
+            if args.len() == restart.params.len() {
+                return MenuChoice::Restart(restart.id, args);
+            }
+        } else if choice == count + 1 {
+            return MenuChoice::Retry;
+        } else if choice == count + 2 {
+            return MenuChoice::Abort;
+        } else if choice == count + 3 {
+            evaluator.write_output(&format!(
+                "Evaluating at the error; {} lists the bindings in scope, a blank line returns to the menu\n",
+                SCOPES_COMMAND
+            ));
+            loop {
+                evaluator.write_output("debug> ");
+                let Some(line) = evaluator.input.read_line() else {
+                    return MenuChoice::Abort;
+                };
+                if line.trim() == SCOPES_COMMAND {
+                    let scopes = format_scopes(&scope_chain(evaluator.get_environment()));
+                    evaluator.write_output(&scopes);
+                    continue;
+                }
+                match eval_entry(evaluator, &line) {
+                    Entry::Value(value) => {
+                        evaluator.write_output(&format!("{}\n", write_string(&value)))
                     }
+                    Entry::Failed(err) => evaluator.write_output(&format!("Error: {}\n", err)),
+                    Entry::Blank => break,
+                    Entry::End => return MenuChoice::Abort,
                 }
-
-                repr
             }
-            Expr::Vector(vec) => {
-                let items: Vec<String> = vec.iter().map(|e| self.format_expr(e)).collect();
-                format!("[{}]", items.join(" "))
-            }
-            Expr::HashTable(h) => {
-                format!("#<hash-table:{}>", h.len())
+        } else {
+            evaluator.write_output(&format!("Choose a number from 1 to {}\n", count + 3));
+        }
+    }
+}
+
+// One expression typed at a restart menu prompt, evaluated
+enum Entry {
+    Value(Expr),
+    Failed(String),
+    Blank,
+    End,
+}
+
+fn read_entry(evaluator: &mut Evaluator, prompt: &str) -> Entry {
+    evaluator.write_output(prompt);
+    match evaluator.input.read_line() {
+        Some(line) => eval_entry(evaluator, &line),
+        None => Entry::End,
+    }
+}
+
+fn eval_entry(evaluator: &mut Evaluator, line: &str) -> Entry {
+    if line.trim().is_empty() {
+        return Entry::Blank;
+    }
+    match Evaluator::parse(line)
+        .and_then(|expr| evaluator.eval(&expr).map_err(|err| err.to_string()))
+    {
+        Ok(value) => Entry::Value(value),
+        Err(err) => Entry::Failed(err),
+    }
+}
+
+// Each scope as a section indented under the one enclosing it, with shadowed bindings
+// marked
+fn format_scopes(scopes: &[ScopeInfo]) -> String {
+    let mut text = String::new();
+    for (depth, scope) in scopes.iter().enumerate() {
+        let indent = "  ".repeat(depth);
+        text.push_str(&format!("{}{}\n", indent, scope.label));
+        for binding in &scope.bindings {
+            text.push_str(&format!(
+                "{}  {} = {}{}\n",
+                indent,
+                binding.name,
+                binding.value,
+                if binding.shadowed { "  (shadowed)" } else { "" }
+            ));
+        }
+    }
+    text
+}
+
+// Ctrl+C interrupts a running evaluation and returns to the prompt; at the prompt it
+// still quits
+#[cfg(unix)]
+mod sigint {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, OnceLock};
+
+    const SIGINT: i32 = 2;
+
+    static INTERRUPT: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+    static EVALUATING: AtomicBool = AtomicBool::new(false);
+
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+        fn _exit(status: i32) -> !;
+    }
+
+    // Only async-signal-safe work here: atomic stores and _exit
+    extern "C" fn on_sigint(_: i32) {
+        match INTERRUPT.get() {
+            Some(flag) if EVALUATING.load(Ordering::SeqCst) => flag.store(true, Ordering::SeqCst),
+            _ => unsafe { _exit(130) },
+        }
+    }
+
+    pub fn install(flag: Arc<AtomicBool>) {
+        if INTERRUPT.set(flag).is_ok() {
+            unsafe {
+                signal(SIGINT, on_sigint);
             }
         }
     }
+
+    pub fn set_evaluating(evaluating: bool) {
+        EVALUATING.store(evaluating, Ordering::SeqCst);
+    }
+}
+
+#[cfg(not(unix))]
+mod sigint {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    pub fn install(_flag: Arc<AtomicBool>) {}
+
+    pub fn set_evaluating(_evaluating: bool) {}
 }
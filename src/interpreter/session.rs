@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+// How many forms the log keeps; older ones are dropped, and with them from exported scripts
+pub const MAX_SESSION_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionEntry {
+    pub source: String,
+    pub succeeded: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionLog {
+    entries: VecDeque<SessionEntry>,
+}
+
+impl SessionLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, source: &str, succeeded: bool) {
+        let source = source.trim();
+        if source.is_empty() {
+            return;
+        }
+        if self.entries.len() == MAX_SESSION_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(SessionEntry {
+            source: source.to_string(),
+            succeeded,
+        });
+    }
+
+    pub fn entries(&self) -> &VecDeque<SessionEntry> {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // Successful forms in evaluation order
+    pub fn exportable_forms(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.succeeded)
+            .map(|entry| entry.source.as_str())
+            .collect()
+    }
+
+    pub fn to_script(&self) -> String {
+        let mut script = String::from(";; Exported Zeus session\n");
+        for form in self.exportable_forms() {
+            script.push('\n');
+            script.push_str(form);
+            script.push('\n');
+        }
+        script
+    }
+
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> Result<usize, String> {
+        let path = path.as_ref();
+        fs::write(path, self.to_script())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(self.exportable_forms().len())
+    }
+}
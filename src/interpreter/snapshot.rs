@@ -0,0 +1,185 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::printer::{format_float, quote_string};
+use crate::interpreter::types::{Expr, SymbolData};
+use std::fs;
+use std::path::Path;
+
+// Global bindings written back out as one `(define name value)` form per line, so a later
+// session can rebuild them. Lambdas are plain lists, so functions survive as their source.
+// Builtins and anything without a readable form (gensyms) are left out.
+pub fn snapshot_script(evaluator: &Evaluator) -> String {
+    let defaults = Evaluator::new().get_environment().get_all_bindings();
+    let bindings = evaluator.get_environment().get_all_bindings();
+
+    let mut names: Vec<&String> = bindings
+        .iter()
+        .filter(|(name, value)| defaults.get(*name) != Some(*value))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+
+    let mut script = String::from(";; Zeus environment snapshot\n");
+    for name in names {
+        if let Some(source) = value_source(&bindings[name]) {
+            script.push_str(&format!("(define {} {})\n", name, source));
+        }
+    }
+    script
+}
+
+pub fn save_snapshot<P: AsRef<Path>>(evaluator: &Evaluator, path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    fs::write(path, snapshot_script(evaluator))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Evaluates each form of a snapshot, returning how many were restored and the errors of
+// any that failed. A bad line never stops the rest from loading.
+pub fn restore_snapshot(evaluator: &mut Evaluator, script: &str) -> (usize, Vec<String>) {
+    let mut restored = 0;
+    let mut errors = Vec::new();
+    for line in script.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        // Evaluated directly so restored bindings don't show up in the session log
+        match Evaluator::parse(line)
+            .and_then(|expr| evaluator.eval(&expr).map_err(|e| e.to_string()))
+        {
+            Ok(_) => restored += 1,
+            Err(err) => errors.push(err),
+        }
+    }
+    (restored, errors)
+}
+
+// Source that evaluates back to `expr`: self-evaluating atoms as-is, data as a quoted
+// literal, and constructor calls for values the reader has no syntax for
+pub fn value_source(expr: &Expr) -> Option<String> {
+    if matches!(
+        expr,
+        Expr::List(_) | Expr::Vector(_) | Expr::Symbol(SymbolData::Interned(_))
+    ) {
+        if let Some(datum) = datum_source(expr) {
+            return Some(format!("(quote {})", datum));
+        }
+    }
+
+    match expr {
+        Expr::Integer(_)
+        | Expr::BigInt(_)
+        | Expr::Float(_)
+        | Expr::String(_)
+        | Expr::Character(_)
+        | Expr::Symbol(SymbolData::Keyword(_)) => datum_source(expr),
+        Expr::Rational {
+            numerator,
+            denominator,
+        } => Some(format!(
+            "(string->number \"{}/{}\")",
+            numerator, denominator
+        )),
+        Expr::Cons(car, cdr) => Some(format!(
+            "(cons {} {})",
+            value_source(car)?,
+            value_source(cdr)?
+        )),
+        Expr::List(items) => Some(format!("(list{})", sources_of(items)?)),
+        Expr::Vector(items) => Some(format!("(vector{})", sources_of(&items.borrow())?)),
+        Expr::HashTable(table) => {
+            let mut entries = Vec::new();
+            for (key, value) in table.borrow().iter() {
+                entries.push(format!(
+                    "(cons {} {})",
+                    value_source(&key.to_expr())?,
+                    value_source(value)?
+                ));
+            }
+            Some(format!("(alist->hash (list {}))", entries.join(" ")))
+        }
+        Expr::Matrix(matrix) => {
+            let rows: Vec<String> = (0..matrix.borrow().rows)
+                .map(|row| {
+                    let values: Vec<String> = matrix
+                        .borrow()
+                        .row(row)
+                        .iter()
+                        .map(|v| format_float(*v))
+                        .collect();
+                    format!("({})", values.join(" "))
+                })
+                .collect();
+            Some(format!("(list->matrix (quote ({})))", rows.join(" ")))
+        }
+        // Front to back for a queue and bottom to top for a stack, the order the
+        // constructors add them in
+        Expr::Queue(items) | Expr::Stack(items) => {
+            let constructor = match expr {
+                Expr::Queue(_) => "make-queue",
+                _ => "make-stack",
+            };
+            let items = items.borrow();
+            let (front, back) = items.as_slices();
+            Some(format!(
+                "({}{}{})",
+                constructor,
+                sources_of(front)?,
+                sources_of(back)?
+            ))
+        }
+        Expr::Heap(heap) => {
+            let heap = heap.borrow();
+            Some(format!(
+                "(make-heap {}{})",
+                value_source(&heap.compare)?,
+                sources_of(&heap.items)?
+            ))
+        }
+        Expr::Struct(instance) => {
+            let instance = instance.borrow();
+            let fields: Vec<String> = instance.fields.iter().map(|f| f.to_string()).collect();
+            Some(format!(
+                "(make-struct (quote {}) (quote ({})){})",
+                instance.type_name,
+                fields.join(" "),
+                sources_of(&instance.values)?
+            ))
+        }
+        Expr::Symbol(_) | Expr::Iterator(_) | Expr::Port(_) => None,
+    }
+}
+
+fn sources_of(items: &[Expr]) -> Option<String> {
+    items
+        .iter()
+        .map(|item| value_source(item).map(|source| format!(" {}", source)))
+        .collect()
+}
+
+// Reader syntax for `expr`, if it has one
+fn datum_source(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Integer(n) => Some(n.to_string()),
+        Expr::BigInt(n) => Some(n.to_string()),
+        Expr::Float(f) => Some(format_float(*f)),
+        Expr::String(s) => Some(quote_string(s)),
+        Expr::Character(c) if c.is_alphanumeric() => Some(format!("#\\{}", c)),
+        Expr::Character(c) => Some(format!("#\\x{:x}", *c as u32)),
+        Expr::Symbol(SymbolData::Interned(name)) => Some(name.to_string()),
+        Expr::Symbol(SymbolData::Keyword(name)) => Some(format!(":{}", name)),
+        Expr::List(items) => {
+            let items: Option<Vec<String>> = items.iter().map(datum_source).collect();
+            Some(format!("({})", items?.join(" ")))
+        }
+        Expr::Vector(items) => {
+            let items: Option<Vec<String>> = items.borrow().iter().map(datum_source).collect();
+            Some(format!("[{}]", items?.join(" ")))
+        }
+        _ => None,
+    }
+}
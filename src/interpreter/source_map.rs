@@ -0,0 +1,104 @@
+use crate::interpreter::types::Expr;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+// Where parsed forms came from. Exprs carry no positions, so the reader records the span of
+// every list it builds in a side table keyed by the list's allocation; lists made at run
+// time simply have no entry.
+
+// A 1-based line and column, counted in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub const START: Position = Position { line: 1, column: 1 };
+
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+}
+
+// From the first character of a form to just past its last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+}
+
+// A reader error and where in the source it happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadError {
+    pub message: String,
+    pub position: Position,
+}
+
+// Entries hold a Weak to their list, which keeps the address from being reused while the
+// entry exists; entries whose list has been dropped are pruned as the table grows. Shared
+// by forked evaluators, like the ports.
+#[derive(Clone, Default)]
+pub struct SourceMap {
+    table: Rc<RefCell<SpanTable>>,
+}
+
+#[derive(Default)]
+struct SpanTable {
+    spans: HashMap<*const Vec<Expr>, (Weak<Vec<Expr>>, Span)>,
+    prune_at: usize,
+}
+
+const MIN_PRUNE_AT: usize = 1024;
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, list: &Rc<Vec<Expr>>, span: Span) {
+        let mut table = self.table.borrow_mut();
+        if table.spans.len() >= table.prune_at.max(MIN_PRUNE_AT) {
+            table.spans.retain(|_, (list, _)| list.strong_count() > 0);
+            table.prune_at = table.spans.len() * 2;
+        }
+        table
+            .spans
+            .insert(Rc::as_ptr(list), (Rc::downgrade(list), span));
+    }
+
+    // Where `expr` was read from, if it is a list the reader built
+    pub fn span_of(&self, expr: &Expr) -> Option<Span> {
+        let Expr::List(list) = expr else {
+            return None;
+        };
+        let table = self.table.borrow();
+        let (weak, span) = table.spans.get(&Rc::as_ptr(list))?;
+        (weak.strong_count() > 0).then_some(*span)
+    }
+
+    // Gives `rewritten` the span of `original` unless it already has one, so forms rewritten
+    // before evaluation still point at the source they came from
+    pub fn inherit(&self, rewritten: &Expr, original: &Expr) {
+        if let (Expr::List(list), Some(span)) = (rewritten, self.span_of(original)) {
+            if self.span_of(rewritten).is_none() {
+                self.record(list, span);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.borrow().spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
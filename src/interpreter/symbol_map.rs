@@ -0,0 +1,283 @@
+use crate::interpreter::types::Symbol;
+use std::rc::Rc;
+
+// Persistent map from symbols to values: a hash array mapped trie indexed by the bits of
+// the symbol id, five at a time. Cloning shares the whole trie, so it's O(1) however many
+// entries there are; an insert copies only the nodes on the path to its key that are still
+// shared, and updates unshared nodes in place.
+
+const BITS: u32 = 5;
+
+#[derive(Clone)]
+pub struct SymbolMap<V> {
+    root: Option<Rc<Node<V>>>,
+    len: usize,
+}
+
+#[derive(Clone)]
+enum Node<V> {
+    Leaf(Symbol, V),
+    // `bitmap` has a bit set for each of the 32 slots at this level that holds a child;
+    // `children` stores just those, in slot order
+    Branch {
+        bitmap: u32,
+        children: Vec<Rc<Node<V>>>,
+    },
+}
+
+impl<V> Default for SymbolMap<V> {
+    fn default() -> Self {
+        SymbolMap { root: None, len: 0 }
+    }
+}
+
+impl<V: Clone> SymbolMap<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, key: Symbol) -> Option<&V> {
+        let mut node = self.root.as_ref()?;
+        let mut shift = 0;
+        loop {
+            match &**node {
+                Node::Leaf(existing, value) => return (*existing == key).then_some(value),
+                Node::Branch { bitmap, children } => {
+                    let bit = slot_bit(key, shift);
+                    if bitmap & bit == 0 {
+                        return None;
+                    }
+                    node = &children[child_index(*bitmap, bit)];
+                    shift += BITS;
+                }
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: Symbol) -> bool {
+        self.get(key).is_some()
+    }
+
+    // Returns true if `key` wasn't in the map before
+    pub fn insert(&mut self, key: Symbol, value: V) -> bool {
+        let added = match &mut self.root {
+            Some(root) => insert(root, key, value, 0),
+            None => {
+                self.root = Some(Rc::new(Node::Leaf(key, value)));
+                true
+            }
+        };
+        if added {
+            self.len += 1;
+        }
+        added
+    }
+
+    // Returns the value `key` had, if it was in the map. A branch left holding a single
+    // leaf is replaced by the leaf, so paths stay as short as inserts alone would make them.
+    pub fn remove(&mut self, key: Symbol) -> Option<V> {
+        let value = self.get(key)?.clone();
+        let root = self.root.as_mut()?;
+        if remove(root, key, 0) {
+            self.root = None;
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    // Whether `other` is an unmodified clone of this map (or vice versa)
+    pub fn shares_root_with(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    // Every entry, in trie order
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &V)> {
+        let mut pending: Vec<&Node<V>> = self.root.iter().map(|root| &**root).collect();
+        std::iter::from_fn(move || {
+            while let Some(node) = pending.pop() {
+                match node {
+                    Node::Leaf(key, value) => return Some((*key, value)),
+                    Node::Branch { children, .. } => {
+                        pending.extend(children.iter().rev().map(|child| &**child))
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    // The entries that differ between `before` and this map, as (key, value before, value
+    // now), in no particular order. Subtrees the two still share are skipped, so comparing
+    // a map with an earlier clone of itself takes time in proportion to what changed. A
+    // key that was inserted again counts as changed, even if its value is equal.
+    pub fn diff<'a>(&'a self, before: &'a Self) -> Vec<MapChange<'a, V>> {
+        let mut changes = Vec::new();
+        diff(before.root.as_ref(), self.root.as_ref(), &mut changes);
+        changes
+    }
+}
+
+pub type MapChange<'a, V> = (Symbol, Option<&'a V>, Option<&'a V>);
+
+fn diff<'a, V>(
+    before: Option<&'a Rc<Node<V>>>,
+    after: Option<&'a Rc<Node<V>>>,
+    changes: &mut Vec<MapChange<'a, V>>,
+) {
+    match (before, after) {
+        (Some(before), Some(after)) if Rc::ptr_eq(before, after) => {}
+        (Some(before), Some(after)) => match (&**before, &**after) {
+            (
+                Node::Branch {
+                    bitmap: before_bitmap,
+                    children: before_children,
+                },
+                Node::Branch {
+                    bitmap: after_bitmap,
+                    children: after_children,
+                },
+            ) => {
+                for slot in 0..32 {
+                    let bit = 1 << slot;
+                    let child = |bitmap: u32, children: &'a [Rc<Node<V>>]| {
+                        (bitmap & bit != 0).then(|| &children[child_index(bitmap, bit)])
+                    };
+                    diff(
+                        child(*before_bitmap, before_children),
+                        child(*after_bitmap, after_children),
+                        changes,
+                    );
+                }
+            }
+            // A leaf on one side and a branch on the other: match up their leaves by key
+            _ => {
+                let mut before_leaves = Vec::new();
+                leaves(before, &mut before_leaves);
+                let mut after_leaves = Vec::new();
+                leaves(after, &mut after_leaves);
+                for &(key, value, leaf) in &after_leaves {
+                    match before_leaves
+                        .iter()
+                        .find(|(existing, _, _)| *existing == key)
+                    {
+                        Some((_, _, old)) if Rc::ptr_eq(old, leaf) => {}
+                        old => changes.push((key, old.map(|(_, old, _)| *old), Some(value))),
+                    }
+                }
+                for &(key, value, _) in &before_leaves {
+                    if !after_leaves.iter().any(|(existing, _, _)| *existing == key) {
+                        changes.push((key, Some(value), None));
+                    }
+                }
+            }
+        },
+        (Some(before), None) => {
+            let mut removed = Vec::new();
+            leaves(before, &mut removed);
+            changes.extend(
+                removed
+                    .into_iter()
+                    .map(|(key, value, _)| (key, Some(value), None)),
+            );
+        }
+        (None, Some(after)) => {
+            let mut added = Vec::new();
+            leaves(after, &mut added);
+            changes.extend(
+                added
+                    .into_iter()
+                    .map(|(key, value, _)| (key, None, Some(value))),
+            );
+        }
+        (None, None) => {}
+    }
+}
+
+// Every leaf under `node`, with its key and value
+type LeafEntry<'a, V> = (Symbol, &'a V, &'a Rc<Node<V>>);
+
+fn leaves<'a, V>(node: &'a Rc<Node<V>>, found: &mut Vec<LeafEntry<'a, V>>) {
+    match &**node {
+        Node::Leaf(key, value) => found.push((*key, value, node)),
+        Node::Branch { children, .. } => {
+            for child in children {
+                leaves(child, found);
+            }
+        }
+    }
+}
+
+fn insert<V: Clone>(node: &mut Rc<Node<V>>, key: Symbol, value: V, shift: u32) -> bool {
+    // A leaf for another key becomes a branch holding it, and the new key goes in below
+    if let Node::Leaf(existing, _) = &**node {
+        if *existing != key {
+            let bitmap = slot_bit(*existing, shift);
+            let leaf = Rc::clone(node);
+            *node = Rc::new(Node::Branch {
+                bitmap,
+                children: vec![leaf],
+            });
+        }
+    }
+
+    match Rc::make_mut(node) {
+        Node::Leaf(_, slot) => {
+            *slot = value;
+            false
+        }
+        Node::Branch { bitmap, children } => {
+            let bit = slot_bit(key, shift);
+            let index = child_index(*bitmap, bit);
+            if *bitmap & bit == 0 {
+                *bitmap |= bit;
+                children.insert(index, Rc::new(Node::Leaf(key, value)));
+                true
+            } else {
+                insert(&mut children[index], key, value, shift + BITS)
+            }
+        }
+    }
+}
+
+// Takes `key`, which must be under `node`, out of it; true if nothing is left
+fn remove<V: Clone>(node: &mut Rc<Node<V>>, key: Symbol, shift: u32) -> bool {
+    let collapsed = match Rc::make_mut(node) {
+        Node::Leaf(_, _) => return true,
+        Node::Branch { bitmap, children } => {
+            let bit = slot_bit(key, shift);
+            let index = child_index(*bitmap, bit);
+            if remove(&mut children[index], key, shift + BITS) {
+                *bitmap &= !bit;
+                children.remove(index);
+            }
+            match children.as_slice() {
+                [] => return true,
+                [only] if matches!(**only, Node::Leaf(_, _)) => Rc::clone(only),
+                _ => return false,
+            }
+        }
+    };
+    *node = collapsed;
+    false
+}
+
+// Distinct ids differ in some bit below 32, so their paths part before the shift runs out
+fn slot_bit(key: Symbol, shift: u32) -> u32 {
+    1 << (key.id().checked_shr(shift).unwrap_or(0) & 31)
+}
+
+fn child_index(bitmap: u32, bit: u32) -> usize {
+    (bitmap & (bit - 1)).count_ones() as usize
+}
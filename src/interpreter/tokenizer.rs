@@ -1,15 +1,49 @@
+use crate::interpreter::bigint::BigInt;
+use crate::interpreter::source_map::{Position, ReadError, Span};
 use crate::interpreter::types::Token;
 
 pub struct Tokenizer {
     input: Vec<char>,
     position: usize,
+    // Index of the first character of each line, for turning positions into lines and columns
+    line_starts: Vec<usize>,
+    // Where the input starts in its file
+    origin: Position,
 }
 
 impl Tokenizer {
     pub fn new(input: &str) -> Self {
+        Self::with_origin(input, Position::START)
+    }
+
+    // A tokenizer for `input` found at `origin` in a larger source, so spans are reported
+    // in that source's lines and columns
+    pub fn with_origin(input: &str, origin: Position) -> Self {
+        let input: Vec<char> = input.chars().collect();
+        let line_starts = std::iter::once(0)
+            .chain(
+                input
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, ch)| **ch == '\n')
+                    .map(|(index, _)| index + 1),
+            )
+            .collect();
         Tokenizer {
-            input: input.chars().collect(),
+            input,
             position: 0,
+            line_starts,
+            origin,
+        }
+    }
+
+    fn position_at(&self, index: usize) -> Position {
+        let line = self.line_starts.partition_point(|start| *start <= index) - 1;
+        let column = index - self.line_starts[line] + 1;
+        if line == 0 {
+            Position::new(self.origin.line, self.origin.column + column - 1)
+        } else {
+            Position::new(self.origin.line + line, column)
         }
     }
 
@@ -77,7 +111,7 @@ impl Tokenizer {
         let mut result = String::new();
 
         while let Some(ch) = self.peek() {
-            if ch.is_alphanumeric() || "+-*/<>=!?_".contains(ch) {
+            if ch.is_alphanumeric() || "+-*/<>=!?_&".contains(ch) {
                 result.push(ch);
                 self.advance();
             } else {
@@ -92,6 +126,7 @@ impl Tokenizer {
         let mut result = String::new();
         let mut has_dot = false;
         let mut has_slash = false; // For rational numbers
+        let mut has_exponent = false;
 
         if self.peek() == Some('-') {
             result.push('-');
@@ -136,22 +171,70 @@ impl Tokenizer {
 
                 // Return as a special rational token - we'll handle this in parser
                 return Ok(Token::Integer(num)); // Temporarily use Integer, will fix in parser
+            } else if (ch == 'e' || ch == 'E')
+                && !has_slash
+                && !has_exponent
+                && self.exponent_follows()
+            {
+                // 1e10, 2.5E-3
+                has_exponent = true;
+                result.push(ch);
+                self.advance();
+                if let Some(sign) = self.peek().filter(|c| *c == '-' || *c == '+') {
+                    result.push(sign);
+                    self.advance();
+                }
             } else {
                 break;
             }
         }
 
-        if has_dot {
+        if has_dot || has_exponent {
             result
                 .parse::<f64>()
                 .map(Token::Float)
                 .map_err(|_| "Invalid float".to_string())
         } else {
-            result
-                .parse::<i64>()
-                .map(Token::Integer)
-                .map_err(|_| "Invalid integer".to_string())
+            match result.parse::<i64>() {
+                Ok(n) => Ok(Token::Integer(n)),
+                Err(_) => BigInt::parse(&result)
+                    .map(Token::BigInt)
+                    .ok_or_else(|| "Invalid integer".to_string()),
+            }
+        }
+    }
+
+    // Whether the 'e' at the current position starts an exponent: digits, optionally signed
+    fn exponent_follows(&self) -> bool {
+        let mut next = self.position + 1;
+        if matches!(self.input.get(next), Some('-') | Some('+')) {
+            next += 1;
         }
+        self.input.get(next).is_some_and(|c| c.is_ascii_digit())
+    }
+
+    // +inf.0, -inf.0 and +nan.0, the printed forms of the special floats
+    fn read_special_float(&mut self) -> Option<Token> {
+        for (text, value) in [
+            ("+inf.0", f64::INFINITY),
+            ("-inf.0", f64::NEG_INFINITY),
+            ("+nan.0", f64::NAN),
+        ] {
+            let end = self.position + text.len();
+            let matches = self
+                .input
+                .get(self.position..end)
+                .is_some_and(|chars| chars.iter().copied().eq(text.chars()));
+            let delimited = self
+                .input
+                .get(end)
+                .is_none_or(|c| !c.is_alphanumeric() && !"+-*/<>=!?_&.".contains(*c));
+            if matches && delimited {
+                self.position = end;
+                return Some(Token::Float(value));
+            }
+        }
+        None
     }
 
     fn read_character(&mut self) -> Result<char, String> {
@@ -163,10 +246,10 @@ impl Tokenizer {
 
         // Check for special character names or single character
         if let Some(ch) = self.peek() {
-            if ch.is_alphabetic() {
+            if ch.is_alphanumeric() {
                 let mut name = String::new();
                 while let Some(ch) = self.peek() {
-                    if ch.is_alphabetic() {
+                    if ch.is_alphanumeric() {
                         name.push(ch);
                         self.advance();
                     } else {
@@ -174,12 +257,28 @@ impl Tokenizer {
                     }
                 }
 
-                match name.as_str() {
+                if name.chars().count() == 1 {
+                    return Ok(name.chars().next().unwrap());
+                }
+
+                match name.to_lowercase().as_str() {
                     "space" => Ok(' '),
-                    "newline" => Ok('\n'),
+                    "newline" | "linefeed" => Ok('\n'),
                     "tab" => Ok('\t'),
                     "return" => Ok('\r'),
-                    _ if name.len() == 1 => Ok(name.chars().next().unwrap()),
+                    "nul" | "null" => Ok('\0'),
+                    "backspace" => Ok('\u{8}'),
+                    "escape" | "altmode" => Ok('\u{1b}'),
+                    "delete" | "rubout" => Ok('\u{7f}'),
+                    "page" => Ok('\u{c}'),
+                    "alarm" | "bell" => Ok('\u{7}'),
+                    lower if lower.starts_with('x') || lower.starts_with('u') => {
+                        // Hex code point: #\x41 or #\u3bb
+                        u32::from_str_radix(&name[1..], 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("Invalid character code: {}", name))
+                    }
                     _ => Err(format!("Unknown character name: {}", name)),
                 }
             } else {
@@ -193,8 +292,65 @@ impl Tokenizer {
         }
     }
 
+    fn read_radix_integer(&mut self, radix: u32) -> Result<Token, String> {
+        let mut digits = String::new();
+
+        if let Some(sign) = self.peek().filter(|c| *c == '-' || *c == '+') {
+            digits.push(sign);
+            self.advance();
+        }
+
+        while let Some(ch) = self.peek() {
+            if ch.is_alphanumeric() {
+                digits.push(ch);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        match i64::from_str_radix(&digits, radix) {
+            Ok(n) => Ok(Token::Integer(n)),
+            Err(_) => BigInt::parse_radix(&digits, radix)
+                .map(Token::BigInt)
+                .ok_or_else(|| format!("Invalid base-{} integer: {}", radix, digits)),
+        }
+    }
+
+    // Reader dispatch for everything that starts with '#'
+    fn read_dispatch(&mut self) -> Result<Token, String> {
+        match self.peek() {
+            Some('\\') => self.read_character().map(Token::Character),
+            Some('x') | Some('X') => {
+                self.advance();
+                self.read_radix_integer(16)
+            }
+            Some('o') | Some('O') => {
+                self.advance();
+                self.read_radix_integer(8)
+            }
+            Some('b') | Some('B') => {
+                self.advance();
+                self.read_radix_integer(2)
+            }
+            Some('t') | Some('f') => {
+                let name = self.read_symbol();
+                match name.as_str() {
+                    "t" | "true" => Ok(Token::Boolean(true)),
+                    "f" | "false" => Ok(Token::Boolean(false)),
+                    _ => Err(format!("Unknown reader syntax: #{}", name)),
+                }
+            }
+            Some(ch) => Err(format!("Unknown reader syntax: #{}", ch)),
+            None => Err("Unexpected end of input after '#'".to_string()),
+        }
+    }
+
     fn next_token(&mut self) -> Result<Option<Token>, String> {
         self.skip_whitespace();
+        if let Some(token) = self.read_special_float() {
+            return Ok(Some(token));
+        }
 
         match self.peek() {
             None => Ok(None),
@@ -220,8 +376,8 @@ impl Tokenizer {
             }
             Some('#') => {
                 self.advance(); // consume #
-                let ch = self.read_character()?;
-                Ok(Some(Token::Character(ch)))
+                let token = self.read_dispatch()?;
+                Ok(Some(token))
             }
             Some(ch) if ch == '-' || ch.is_ascii_digit() => {
                 let start_pos = self.position;
@@ -276,4 +432,28 @@ impl Tokenizer {
 
         Ok(tokens)
     }
+
+    // tokenize, plus the span of each token; an error says where reading stopped
+    pub fn tokenize_spanned(&mut self) -> Result<(Vec<Token>, Vec<Span>), ReadError> {
+        let mut tokens = Vec::new();
+        let mut spans = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            let start = self.position_at(self.position);
+            match self.next_token() {
+                Ok(Some(token)) => {
+                    tokens.push(token);
+                    spans.push(Span::new(start, self.position_at(self.position)));
+                }
+                Ok(None) => return Ok((tokens, spans)),
+                Err(message) => {
+                    return Err(ReadError {
+                        message,
+                        position: start,
+                    })
+                }
+            }
+        }
+    }
 }
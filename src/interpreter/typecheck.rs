@@ -0,0 +1,428 @@
+use crate::interpreter::evaluator_generics::{supertypes, TYPE_NAMES};
+use crate::interpreter::printer::{PrintMode, Printer};
+use crate::interpreter::source_map::{SourceMap, Span};
+use crate::interpreter::types::{Expr, SymbolData};
+use std::collections::{HashMap, HashSet};
+
+// Gradual type checking for `zeus check`. Only what's annotated is checked: variables get
+// types from (declare (type integer x)) at the start of a function or let body, and
+// expressions from (the integer e), literals and a few builtins whose result type is fixed.
+// Everything else has no static type and is never reported. A finding is a call or `the`
+// whose argument can't have the type it needs: neither type is one of the other's
+// supertypes, as with a string where a number is needed (an expression of type number is
+// fine where an integer is needed, since it might be one). A type name in `the` or a
+// declaration that is neither a builtin type nor a struct the checked forms define is
+// reported as unknown.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeFinding {
+    // The list the mismatch is in, when the reader recorded one
+    pub span: Option<Span>,
+    // type-mismatch or unknown-type
+    pub rule: &'static str,
+    pub message: String,
+}
+
+// Builtins that need a type: (name, type, which argument, or None for all of them)
+const ARGUMENT_TYPES: &[(&str, &str, Option<usize>)] = &[
+    ("+", "number", None),
+    ("-", "number", None),
+    ("*", "number", None),
+    ("/", "number", None),
+    ("expt", "number", None),
+    ("=", "number", None),
+    ("/=", "number", None),
+    ("<", "number", None),
+    ("<=", "number", None),
+    (">", "number", None),
+    (">=", "number", None),
+    ("string=", "string", None),
+    ("string<", "string", None),
+    ("string>", "string", None),
+    ("string<=", "string", None),
+    ("string>=", "string", None),
+    ("string-ci=", "string", None),
+    ("string-split", "string", Some(0)),
+    ("char=", "character", None),
+    ("char<", "character", None),
+    ("char>", "character", None),
+    ("char<=", "character", None),
+    ("char>=", "character", None),
+    ("char->integer", "character", Some(0)),
+    ("integer->char", "integer", Some(0)),
+    ("alpha?", "character", Some(0)),
+    ("digit?", "character", Some(0)),
+    ("whitespace?", "character", Some(0)),
+    ("string->number", "string", Some(0)),
+    ("number->string", "number", Some(0)),
+    ("car", "list", Some(0)),
+    ("cdr", "list", Some(0)),
+    ("vector-ref", "vector", Some(0)),
+    ("vector-set!", "vector", Some(0)),
+    ("vector-length", "vector", Some(0)),
+    ("hash-ref", "hash-table", Some(0)),
+    ("hash-set!", "hash-table", Some(0)),
+    ("hash-count", "hash-table", Some(0)),
+];
+
+// Builtins whose result always has one type
+const RESULT_TYPES: &[(&str, &str)] = &[
+    ("+", "number"),
+    ("-", "number"),
+    ("*", "number"),
+    ("/", "number"),
+    ("length", "integer"),
+    ("char->integer", "integer"),
+    ("integer->char", "character"),
+    ("number->string", "string"),
+    ("list", "list"),
+    ("cons", "cons"),
+    ("vector", "vector"),
+    ("make-vector", "vector"),
+    ("make-hash-table", "hash-table"),
+    ("string-split", "list"),
+];
+
+// What a function's declarations say about it
+#[derive(Debug, Clone, Default)]
+struct Signature {
+    // The declared type of each required parameter, if any
+    params: Vec<Option<String>>,
+    // From a body whose last form is (the type ...)
+    result: Option<String>,
+}
+
+pub fn check_types(forms: &[Expr], source_map: &SourceMap) -> Vec<TypeFinding> {
+    let mut checker = Checker {
+        signatures: HashMap::new(),
+        structs: HashSet::new(),
+        source_map,
+        findings: Vec::new(),
+    };
+    for form in forms {
+        if let Some((name, signature)) = defun_signature(form) {
+            checker.signatures.insert(name, signature);
+        }
+        struct_names(form, &mut checker.structs);
+    }
+    for form in forms {
+        checker.visit(form, &mut Vec::new());
+    }
+    checker.findings
+}
+
+struct Checker<'a> {
+    signatures: HashMap<String, Signature>,
+    // Struct types defined anywhere in the checked forms
+    structs: HashSet<String>,
+    source_map: &'a SourceMap,
+    findings: Vec<TypeFinding>,
+}
+
+// Declared variable types, innermost last; None where a binding hides an outer declaration
+type Scope = Vec<(String, Option<String>)>;
+
+impl Checker<'_> {
+    fn visit(&mut self, expr: &Expr, scope: &mut Scope) {
+        let Expr::List(items) = expr else {
+            return;
+        };
+        let head = match items.first() {
+            Some(Expr::Symbol(SymbolData::Interned(head))) => head.as_str(),
+            _ => "",
+        };
+        match head {
+            "quote" => return,
+            "declare" => {
+                for declaration in items[1..].iter() {
+                    if let Expr::List(parts) = declaration {
+                        if let [Expr::Symbol(SymbolData::Interned(kind)), Expr::Symbol(SymbolData::Interned(type_name)), ..] =
+                            parts.as_slice()
+                        {
+                            if kind == "type" {
+                                self.check_type_name(type_name.as_str(), expr);
+                            }
+                        }
+                    }
+                }
+                return;
+            }
+            "the" => {
+                if let [_, Expr::Symbol(SymbolData::Interned(wanted)), value] = items.as_slice() {
+                    self.check_type_name(wanted.as_str(), expr);
+                    self.expect(value, wanted.as_str(), scope, expr, || "the".to_string());
+                    self.visit(value, scope);
+                }
+                return;
+            }
+            "defun" | "lambda" => {
+                let (params, body) = match (head, items.as_slice()) {
+                    ("defun", [_, _, Expr::List(params), body @ ..]) => (params, body),
+                    ("lambda", [_, Expr::List(params), body @ ..]) => (params, body),
+                    _ => return,
+                };
+                let declared = declarations(body);
+                let depth = scope.len();
+                for param in params.iter() {
+                    if let Expr::Symbol(SymbolData::Interned(name)) = param {
+                        let name = name.to_string();
+                        let declared_type = declared.get(&name).cloned();
+                        scope.push((name, declared_type));
+                    }
+                }
+                for form in body {
+                    self.visit(form, scope);
+                }
+                scope.truncate(depth);
+                return;
+            }
+            "let" | "let*" | "letrec" => {
+                let [_, Expr::List(bindings), body @ ..] = items.as_slice() else {
+                    return;
+                };
+                let declared = declarations(body);
+                let depth = scope.len();
+                let mut bound = Vec::new();
+                for binding in bindings.iter() {
+                    let (name, init) = match binding {
+                        Expr::List(parts) => (parts.first(), parts.get(1)),
+                        other => (Some(other), None),
+                    };
+                    if let Some(init) = init {
+                        self.visit(init, scope);
+                    }
+                    if let Some(Expr::Symbol(SymbolData::Interned(name))) = name {
+                        let name = name.to_string();
+                        let declared_type = declared.get(&name).cloned();
+                        if let (Some(wanted), Some(init)) = (&declared_type, init) {
+                            self.expect(init, wanted, scope, expr, || format!("let {}", name));
+                        }
+                        bound.push((name, declared_type));
+                    }
+                }
+                scope.extend(bound);
+                for form in body {
+                    self.visit(form, scope);
+                }
+                scope.truncate(depth);
+                return;
+            }
+            _ => {}
+        }
+
+        let args = items.get(1..).unwrap_or(&[]);
+        if let Some(signature) = self.signatures.get(head).cloned() {
+            for (index, (arg, wanted)) in args.iter().zip(&signature.params).enumerate() {
+                if let Some(wanted) = wanted {
+                    self.expect(arg, wanted, scope, expr, || {
+                        format!("argument {} of {}", index + 1, head)
+                    });
+                }
+            }
+        } else if let Some((_, wanted, position)) =
+            ARGUMENT_TYPES.iter().find(|(name, _, _)| *name == head)
+        {
+            for (index, arg) in args.iter().enumerate() {
+                if position.is_none_or(|position| position == index) {
+                    self.expect(arg, wanted, scope, expr, || {
+                        format!("argument {} of {}", index + 1, head)
+                    });
+                }
+            }
+        }
+        for item in items.iter() {
+            self.visit(item, scope);
+        }
+    }
+
+    // Reports `type_name`, named in `within`, if it's neither a builtin type nor a struct
+    fn check_type_name(&mut self, type_name: &str, within: &Expr) {
+        if self.is_known_type(type_name) {
+            return;
+        }
+        self.findings.push(TypeFinding {
+            span: self.source_map.span_of(within),
+            rule: "unknown-type",
+            message: format!("unknown type {}", type_name),
+        });
+    }
+
+    fn is_known_type(&self, type_name: &str) -> bool {
+        TYPE_NAMES.contains(&type_name) || self.structs.contains(type_name)
+    }
+
+    // Reports `value` if its static type can't be `wanted`. Unknown types were reported
+    // where they were named, and match anything.
+    fn expect(
+        &mut self,
+        value: &Expr,
+        wanted: &str,
+        scope: &Scope,
+        within: &Expr,
+        what: impl Fn() -> String,
+    ) {
+        let Some(actual) = self.static_type(value, scope) else {
+            return;
+        };
+        if !self.is_known_type(wanted) || !self.is_known_type(&actual) {
+            return;
+        }
+        if supertypes(&actual).contains(&wanted) || supertypes(wanted).contains(&actual.as_str()) {
+            return;
+        }
+        self.findings.push(TypeFinding {
+            span: self.source_map.span_of(within),
+            rule: "type-mismatch",
+            message: format!(
+                "{} should be of type {}, but {} is of type {}",
+                what(),
+                wanted,
+                source_text(value),
+                actual
+            ),
+        });
+    }
+
+    fn static_type(&self, expr: &Expr, scope: &Scope) -> Option<String> {
+        let literal = match expr {
+            Expr::Integer(_) | Expr::BigInt(_) => "integer",
+            Expr::Float(_) => "float",
+            Expr::Rational { .. } => "rational",
+            Expr::String(_) => "string",
+            Expr::Character(_) => "character",
+            Expr::Symbol(SymbolData::Keyword(_)) => "keyword",
+            Expr::Vector(_) => "vector",
+            Expr::Symbol(SymbolData::Interned(name)) => {
+                return scope
+                    .iter()
+                    .rev()
+                    .find(|(bound, _)| bound == name.as_str())
+                    .and_then(|(_, declared)| declared.clone());
+            }
+            Expr::List(items) if items.is_empty() => "null",
+            Expr::List(items) => return self.form_type(items, scope),
+            _ => return None,
+        };
+        Some(literal.to_string())
+    }
+
+    fn form_type(&self, items: &[Expr], scope: &Scope) -> Option<String> {
+        let Some(Expr::Symbol(SymbolData::Interned(head))) = items.first() else {
+            return None;
+        };
+        match (head.as_str(), items) {
+            ("the", [_, Expr::Symbol(SymbolData::Interned(wanted)), _]) => Some(wanted.to_string()),
+            ("quote", [_, quoted]) => match quoted {
+                Expr::Symbol(SymbolData::Interned(_)) => Some("symbol".to_string()),
+                Expr::List(items) if items.is_empty() => Some("null".to_string()),
+                Expr::List(_) | Expr::Cons(_, _) => Some("cons".to_string()),
+                literal => self.static_type(literal, scope),
+            },
+            (name, _) => match self.signatures.get(name) {
+                Some(signature) => signature.result.clone(),
+                None => RESULT_TYPES
+                    .iter()
+                    .find(|(builtin, _)| *builtin == name)
+                    .map(|(_, result)| result.to_string()),
+            },
+        }
+    }
+}
+
+// The variable types declared by (declare (type ...)) forms at the start of `body`, after
+// any docstring
+fn declarations(body: &[Expr]) -> HashMap<String, String> {
+    let mut declared = HashMap::new();
+    let body = match body {
+        [Expr::String(_), rest @ ..] if !rest.is_empty() => rest,
+        _ => body,
+    };
+    for form in body {
+        let Expr::List(items) = form else {
+            break;
+        };
+        match items.first() {
+            Some(Expr::Symbol(SymbolData::Interned(head))) if head == "declare" => {}
+            _ => break,
+        }
+        for declaration in items[1..].iter() {
+            let Expr::List(parts) = declaration else {
+                continue;
+            };
+            if let [Expr::Symbol(SymbolData::Interned(kind)), Expr::Symbol(SymbolData::Interned(type_name)), names @ ..] =
+                parts.as_slice()
+            {
+                if kind == "type" {
+                    for name in names {
+                        if let Expr::Symbol(SymbolData::Interned(name)) = name {
+                            declared.insert(name.to_string(), type_name.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    declared
+}
+
+// Adds the name of every (defstruct name ...) in `expr` to `names`
+fn struct_names(expr: &Expr, names: &mut HashSet<String>) {
+    let Expr::List(items) = expr else {
+        return;
+    };
+    match items.as_slice() {
+        [Expr::Symbol(SymbolData::Interned(head)), Expr::Symbol(SymbolData::Interned(name)), ..]
+            if head == "defstruct" =>
+        {
+            names.insert(name.to_string());
+        }
+        [Expr::Symbol(SymbolData::Interned(head)), ..] if head == "quote" => {}
+        _ => {
+            for item in items.iter() {
+                struct_names(item, names);
+            }
+        }
+    }
+}
+
+fn defun_signature(form: &Expr) -> Option<(String, Signature)> {
+    let Expr::List(items) = form else {
+        return None;
+    };
+    let [Expr::Symbol(SymbolData::Interned(head)), Expr::Symbol(SymbolData::Interned(name)), Expr::List(params), body @ ..] =
+        items.as_slice()
+    else {
+        return None;
+    };
+    if head != "defun" {
+        return None;
+    }
+    let declared = declarations(body);
+    let params = params
+        .iter()
+        .take_while(|param| !matches!(param, Expr::Symbol(SymbolData::Interned(p)) if p.as_str().starts_with('&')))
+        .map(|param| match param {
+            Expr::Symbol(SymbolData::Interned(param)) => declared.get(param.as_str()).cloned(),
+            _ => None,
+        })
+        .collect();
+    let result = match body.last() {
+        Some(Expr::List(last)) => match last.as_slice() {
+            [Expr::Symbol(SymbolData::Interned(the)), Expr::Symbol(SymbolData::Interned(result)), _]
+                if the == "the" =>
+            {
+                Some(result.to_string())
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    Some((name.to_string(), Signature { params, result }))
+}
+
+fn source_text(expr: &Expr) -> String {
+    Printer::new(PrintMode::Write)
+        .with_max_depth(2)
+        .with_max_length(4)
+        .print(expr)
+}
@@ -1,5 +1,17 @@
-use std::collections::HashMap;
+use crate::interpreter::bigint::BigInt;
+use crate::interpreter::environment::BUILTINS;
+use crate::interpreter::evaluator::INTERRUPTED;
+use crate::interpreter::iterator::Cursor;
+use crate::interpreter::matrix::Matrix;
+use crate::interpreter::ordered_map::OrderedMap;
+use crate::interpreter::ports::FilePort;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::{Mutex, MutexGuard, OnceLock};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -10,24 +22,178 @@ pub enum Token {
     Symbol(String),
     Keyword(String), // Self-evaluating keyword symbols (e.g., :keyword)
     Integer(i64),
+    BigInt(BigInt), // Integer literals too large for i64
     Float(f64),
     String(String),
     Character(char),
+    Boolean(bool), // #t / #f reader literals
+}
+
+// An interned name: its SymbolId in the global symbol table, plus the table's copy of
+// the string so reading the name takes no lock. Equal names always intern to the same id,
+// so comparing, hashing and cloning symbols never touches the string.
+#[derive(Clone, Copy)]
+pub struct Symbol {
+    id: SymbolId,
+    name: &'static str,
+}
+
+pub type SymbolId = u32;
+
+// Names are leaked into the table and live as long as the program; the set of distinct
+// symbol names is bounded by the source code that mentions them
+#[derive(Default)]
+struct SymbolTable {
+    ids: HashMap<&'static str, SymbolId>,
+    names: Vec<&'static str>,
+    // The symbols the reader has seen written as keywords, for completion
+    keywords: BTreeSet<SymbolId>,
+}
+
+impl SymbolTable {
+    // Builtin names are interned first, so their ids are exactly 0..BUILTINS.len()
+    fn with_builtins() -> Self {
+        let mut table = Self::default();
+        for name in BUILTINS {
+            table.insert(name);
+        }
+        debug_assert_eq!(table.names.len(), BUILTINS.len(), "duplicate builtin name");
+        table
+    }
+
+    fn insert(&mut self, name: &str) -> Symbol {
+        if let Some(&id) = self.ids.get(name) {
+            return Symbol {
+                id,
+                name: self.names[id as usize],
+            };
+        }
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        let id = self.names.len() as SymbolId;
+        self.names.push(name);
+        self.ids.insert(name, id);
+        Symbol { id, name }
+    }
+}
+
+static SYMBOL_TABLE: OnceLock<Mutex<SymbolTable>> = OnceLock::new();
+
+fn symbol_table() -> MutexGuard<'static, SymbolTable> {
+    SYMBOL_TABLE
+        .get_or_init(|| Mutex::new(SymbolTable::with_builtins()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Symbol {
+        symbol_table().insert(name)
+    }
+
+    // Interns `name` as a keyword the reader has seen
+    pub fn keyword(name: &str) -> Symbol {
+        let mut table = symbol_table();
+        let symbol = table.insert(name);
+        table.keywords.insert(symbol.id);
+        symbol
+    }
+
+    // Every name read as a keyword so far, in no particular order
+    pub fn keywords() -> Vec<Symbol> {
+        let table = symbol_table();
+        table
+            .keywords
+            .iter()
+            .map(|&id| Symbol {
+                id,
+                name: table.names[id as usize],
+            })
+            .collect()
+    }
+
+    // The symbol for `name` if it has been interned, without adding it
+    pub fn lookup(name: &str) -> Option<Symbol> {
+        let table = symbol_table();
+        table.ids.get(name).map(|&id| Symbol {
+            id,
+            name: table.names[id as usize],
+        })
+    }
+
+    // Whether this names a builtin function, which evaluates to itself unless redefined
+    pub fn is_builtin(&self) -> bool {
+        (self.id as usize) < BUILTINS.len()
+    }
+
+    pub fn id(&self) -> SymbolId {
+        self.id
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.name == other
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.name
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+// Debug output shows the name as a quoted string, as it did when symbols held Strings
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.name)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SymbolData {
-    Interned(String),        // Normal symbols that are interned
+    Interned(Symbol),        // Normal symbols that are interned
     Uninterned(String, u64), // Uninterned symbols from gensym with unique ID
-    Keyword(String),         // Self-evaluating keyword symbols
+    Keyword(Symbol),         // Self-evaluating keyword symbols
 }
 
 impl SymbolData {
     pub fn name(&self) -> &str {
         match self {
-            SymbolData::Interned(name) => name,
+            SymbolData::Interned(name) => name.as_str(),
             SymbolData::Uninterned(name, _) => name,
-            SymbolData::Keyword(name) => name,
+            SymbolData::Keyword(name) => name.as_str(),
         }
     }
 
@@ -35,6 +201,14 @@ impl SymbolData {
         self.name()
     }
 
+    // The interned symbol naming this variable; a gensym is interned by its printed name
+    pub fn symbol(&self) -> Symbol {
+        match self {
+            SymbolData::Interned(symbol) | SymbolData::Keyword(symbol) => *symbol,
+            SymbolData::Uninterned(name, _) => Symbol::intern(name),
+        }
+    }
+
     pub fn is_keyword(&self) -> bool {
         matches!(self, SymbolData::Keyword(_))
     }
@@ -47,15 +221,58 @@ impl SymbolData {
 #[derive(Debug, Clone)]
 pub enum Expr {
     Integer(i64),
+    BigInt(BigInt), // Only used for values outside the i64 range
     Float(f64),
     Rational { numerator: i64, denominator: i64 },
     Symbol(SymbolData),
     String(String),
     Character(char),
     Cons(Box<Expr>, Box<Expr>),
-    List(Vec<Expr>),
-    Vector(Vec<Expr>),
-    HashTable(Rc<HashMap<HashKey, Expr>>),
+    // Shared and immutable: cloning a list (a quoted datum, a lambda body, an argument
+    // passed along) bumps a count instead of copying; Rc::make_mut copies on write
+    List(Rc<Vec<Expr>>),
+    // Shared like hash tables, so vector-set!, vector-push and friends update in place
+    Vector(Rc<RefCell<Vec<Expr>>>),
+    // Shared and mutable: hash-set! updates every reference to the table in place
+    HashTable(Rc<RefCell<OrderedMap<HashKey, Expr>>>),
+    // Shared like vectors, so matrix-set! updates every reference in place
+    Matrix(Rc<RefCell<Matrix>>),
+    // Shared like vectors, so enqueue and dequeue update every reference in place; the
+    // front of the queue is the front of the deque
+    Queue(Rc<RefCell<VecDeque<Expr>>>),
+    // Shared the same way; the top of the stack is the back of the deque
+    Stack(Rc<RefCell<VecDeque<Expr>>>),
+    // A priority queue, shared the same way
+    Heap(Rc<RefCell<Heap>>),
+    // Shared and stateful: `next` advances every reference to the iterator
+    Iterator(Rc<RefCell<Cursor>>),
+    // A defstruct instance, shared like vectors so setters update every reference in place
+    Struct(Rc<RefCell<Struct>>),
+    // A file opened by with-open-file; see ports.rs
+    Port(Rc<FilePort>),
+}
+
+// A binary heap ordered by a Lisp function: `items[0]` is the item that `compare` puts
+// before every other, and each item comes before its children at 2i+1 and 2i+2
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heap {
+    pub compare: Expr,
+    pub items: Vec<Expr>,
+}
+
+// An instance of a record type: the type's name, its field names (shared by every instance
+// made by the same constructor) and a value per field, in the same order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    pub type_name: Symbol,
+    pub fields: Rc<Vec<Symbol>>,
+    pub values: Vec<Expr>,
+}
+
+impl Struct {
+    pub fn index_of(&self, field: Symbol) -> Option<usize> {
+        self.fields.iter().position(|name| *name == field)
+    }
 }
 
 // Custom PartialEq implementation for Expr to handle HashTable comparison
@@ -63,6 +280,7 @@ impl PartialEq for Expr {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Expr::Integer(a), Expr::Integer(b)) => a == b,
+            (Expr::BigInt(a), Expr::BigInt(b)) => a == b,
             (Expr::Float(a), Expr::Float(b)) => a == b,
             (
                 Expr::Rational {
@@ -81,24 +299,104 @@ impl PartialEq for Expr {
             (Expr::Cons(a_car, a_cdr), Expr::Cons(b_car, b_cdr)) => {
                 a_car == b_car && a_cdr == b_cdr
             }
-            (Expr::Vector(a), Expr::Vector(b)) => a == b,
+            (Expr::Vector(a), Expr::Vector(b)) => *a.borrow() == *b.borrow(),
             (Expr::HashTable(a), Expr::HashTable(b)) => {
                 // HashTables are equal if they have the same keys and values
+                let (a, b) = (a.borrow(), b.borrow());
                 a.len() == b.len() && a.iter().all(|(k, v)| b.get(k).map_or(false, |v2| v == v2))
             }
+            (Expr::Matrix(a), Expr::Matrix(b)) => *a.borrow() == *b.borrow(),
+            (Expr::Queue(a), Expr::Queue(b)) | (Expr::Stack(a), Expr::Stack(b)) => {
+                *a.borrow() == *b.borrow()
+            }
+            (Expr::Heap(a), Expr::Heap(b)) => *a.borrow() == *b.borrow(),
+            (Expr::Iterator(a), Expr::Iterator(b)) => *a.borrow() == *b.borrow(),
+            (Expr::Struct(a), Expr::Struct(b)) => *a.borrow() == *b.borrow(),
+            (Expr::Port(a), Expr::Port(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
 }
 
+impl Expr {
+    // Integer results go through here so values that fit stay as Expr::Integer
+    pub fn from_bigint(value: BigInt) -> Expr {
+        match value.to_i64() {
+            Some(n) => Expr::Integer(n),
+            None => Expr::BigInt(value),
+        }
+    }
+
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Expr::Integer(n) => Some(BigInt::from(*n)),
+            Expr::BigInt(b) => Some(b.clone()),
+            _ => None,
+        }
+    }
+}
+
 // A hashable key type for hash tables
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum HashKey {
     Integer(i64),
-    Symbol(String),
+    Symbol(Symbol),
     String(String),
     Character(char),
-    Keyword(String),
+    Keyword(Symbol),
+}
+
+// Entries shown when a hash table is printed before the rest are elided
+pub const HASH_PRINT_LIMIT: usize = 10;
+
+impl HashKey {
+    pub fn to_expr(&self) -> Expr {
+        match self {
+            HashKey::Integer(n) => Expr::Integer(*n),
+            HashKey::Symbol(s) => Expr::Symbol(SymbolData::Interned(*s)),
+            HashKey::String(s) => Expr::String(s.clone()),
+            HashKey::Character(c) => Expr::Character(*c),
+            HashKey::Keyword(s) => Expr::Symbol(SymbolData::Keyword(*s)),
+        }
+    }
+}
+
+impl Expr {
+    pub fn new_list(items: Vec<Expr>) -> Expr {
+        Expr::List(Rc::new(items))
+    }
+
+    pub fn new_vector(items: Vec<Expr>) -> Expr {
+        Expr::Vector(Rc::new(RefCell::new(items)))
+    }
+
+    pub fn new_matrix(matrix: Matrix) -> Expr {
+        Expr::Matrix(Rc::new(RefCell::new(matrix)))
+    }
+
+    pub fn new_queue(items: VecDeque<Expr>) -> Expr {
+        Expr::Queue(Rc::new(RefCell::new(items)))
+    }
+
+    pub fn new_stack(items: VecDeque<Expr>) -> Expr {
+        Expr::Stack(Rc::new(RefCell::new(items)))
+    }
+
+    pub fn new_heap(heap: Heap) -> Expr {
+        Expr::Heap(Rc::new(RefCell::new(heap)))
+    }
+
+    pub fn new_iterator(cursor: Cursor) -> Expr {
+        Expr::Iterator(Rc::new(RefCell::new(cursor)))
+    }
+
+    pub fn new_struct(instance: Struct) -> Expr {
+        Expr::Struct(Rc::new(RefCell::new(instance)))
+    }
+
+    pub fn new_hash_table(table: OrderedMap<HashKey, Expr>) -> Expr {
+        Expr::HashTable(Rc::new(RefCell::new(table)))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -107,6 +405,18 @@ pub enum EvalError {
     Throw { tag: Expr, value: Expr },
     ReturnFrom { name: String, value: Expr },
     Go { label: String },
+    // Leave the innermost while/until/loop, optionally with its value
+    Break { value: Expr },
+    // Skip to the next iteration of the innermost while/until/loop
+    Continue,
+    // The evaluation used up the step or time budget set in `Evaluator::budget`
+    Timeout(String),
+    // Unwind to the restart-case that established restart `id` and run it with `args`
+    InvokeRestart { id: usize, args: Vec<Expr> },
+    // (exit code) unwinding to the front-end, which ends the program if it owns the process
+    Exit(i32),
+    // The interrupt flag was set (Ctrl-C, the IDE's stop key) while evaluating
+    Interrupted,
 }
 
 pub type EvalResult = Result<Expr, EvalError>;
@@ -126,6 +436,14 @@ impl std::fmt::Display for EvalError {
                 write!(f, "Unhandled return-from for block {}", name)
             }
             EvalError::Go { label } => write!(f, "Unhandled go to label {}", label),
+            EvalError::Break { .. } => write!(f, "break outside of a loop"),
+            EvalError::Continue => write!(f, "continue outside of a loop"),
+            EvalError::Timeout(reason) => write!(f, "Timeout: {}", reason),
+            EvalError::InvokeRestart { .. } => {
+                write!(f, "restart invoked outside its restart-case")
+            }
+            EvalError::Exit(code) => write!(f, "exit with code {}", code),
+            EvalError::Interrupted => write!(f, "{}", INTERRUPTED),
         }
     }
 }
@@ -0,0 +1,307 @@
+use crate::interpreter::batch::{split_forms, SourceForm};
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{Expr, SymbolData};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// Cross-reference index over a set of source files: every top-level definition, the
+// symbols its body mentions, and the symbols mentioned by code that runs when the files
+// are loaded. Built from the source text alone, without evaluating anything.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    // The defining form's head: "defun", "define" or "defmacro"
+    pub kind: String,
+    pub location: Location,
+    pub uses: HashSet<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct XrefIndex {
+    pub definitions: Vec<Definition>,
+    // Entry points by file: symbols used by top-level expressions and by define values
+    // that are computed at load time
+    pub roots: HashMap<String, HashSet<String>>,
+}
+
+impl XrefIndex {
+    // `files` pairs each file name with its source; forms that don't parse are skipped
+    pub fn build(files: &[(String, String)]) -> Self {
+        let mut index = Self::default();
+        for (file, source) in files {
+            let (forms, _) = split_forms(source);
+            for form in &forms {
+                let Ok(expr) = Evaluator::parse(&form.source) else {
+                    continue;
+                };
+                index.add_form(file, form, &expr);
+            }
+        }
+        index
+    }
+
+    fn add_form(&mut self, file: &str, form: &SourceForm, expr: &Expr) {
+        let roots = self.roots.entry(file.to_string()).or_default();
+        let Some((kind, name)) = definition_head(expr) else {
+            collect_symbols(expr, roots);
+            return;
+        };
+        let Expr::List(items) = expr else {
+            return;
+        };
+
+        let mut uses = HashSet::new();
+        for item in &items[2..] {
+            collect_symbols(item, &mut uses);
+        }
+        // (define x (compute)) runs its value now; (define f (lambda ...)) doesn't
+        if kind == "define" && !items.get(2).is_some_and(is_lambda) {
+            roots.extend(uses.iter().cloned());
+        }
+
+        let (line, column) = name_position(form, name);
+        self.definitions.push(Definition {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            location: Location {
+                file: file.to_string(),
+                line,
+                column,
+            },
+            uses,
+        });
+    }
+
+    // Definitions no entry point reaches, directly or through other definitions
+    pub fn unreachable(&self) -> Vec<&Definition> {
+        let mut reached: HashSet<&str> = HashSet::new();
+        let mut pending: Vec<&str> = self
+            .roots
+            .values()
+            .flat_map(|uses| uses.iter().map(String::as_str))
+            .collect();
+        while let Some(name) = pending.pop() {
+            if !reached.insert(name) {
+                continue;
+            }
+            for definition in self.definitions.iter().filter(|d| d.name == name) {
+                pending.extend(definition.uses.iter().map(String::as_str));
+            }
+        }
+        self.definitions
+            .iter()
+            .filter(|definition| !reached.contains(definition.name.as_str()))
+            .collect()
+    }
+
+    // Caller and callee names for every definition that uses another definition, sorted
+    pub fn call_edges(&self) -> BTreeSet<(&str, &str)> {
+        let defined: HashSet<&str> = self.definitions.iter().map(|d| d.name.as_str()).collect();
+        self.definitions
+            .iter()
+            .flat_map(|definition| {
+                definition
+                    .uses
+                    .iter()
+                    .filter(|name| defined.contains(name.as_str()))
+                    .map(|name| (definition.name.as_str(), name.as_str()))
+            })
+            .collect()
+    }
+
+    // Files treated as modules: one depends on another when code in it, definitions or
+    // top-level forms, uses something defined there
+    pub fn module_edges(&self) -> BTreeSet<(&str, &str)> {
+        let mut defined_in: HashMap<&str, Vec<&str>> = HashMap::new();
+        for definition in &self.definitions {
+            defined_in
+                .entry(definition.name.as_str())
+                .or_default()
+                .push(definition.location.file.as_str());
+        }
+
+        let definition_uses = self
+            .definitions
+            .iter()
+            .map(|d| (d.location.file.as_str(), &d.uses));
+        let root_uses = self.roots.iter().map(|(file, uses)| (file.as_str(), uses));
+        let mut edges = BTreeSet::new();
+        for (file, uses) in definition_uses.chain(root_uses) {
+            for name in uses {
+                for target in defined_in.get(name.as_str()).into_iter().flatten() {
+                    if *target != file {
+                        edges.insert((file, *target));
+                    }
+                }
+            }
+        }
+        edges
+    }
+}
+
+// Every place `name` appears as a symbol in `source`, in order. Strings, comments and
+// character literals are skipped, so only code that names the symbol counts
+pub fn find_references(file: &str, source: &str, name: &str) -> Vec<Location> {
+    let mut references = Vec::new();
+    let mut chars = source.chars().peekable();
+    let (mut line, mut column) = (1, 1);
+    let mut token = String::new();
+    let mut token_start = (1, 1);
+    let mut finish = |token: &mut String, start: (usize, usize)| {
+        if token == name {
+            references.push(Location {
+                file: file.to_string(),
+                line: start.0,
+                column: start.1,
+            });
+        }
+        token.clear();
+    };
+
+    while let Some(ch) = chars.next() {
+        let (ch_line, ch_column) = (line, column);
+        if ch == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+
+        if ch.is_whitespace() || matches!(ch, '(' | ')' | '[' | ']' | '\'' | '"' | ';') {
+            finish(&mut token, token_start);
+        } else {
+            if token.is_empty() {
+                token_start = (ch_line, ch_column);
+                // #\x reads one character whatever it is, so #\( isn't a delimiter
+                if ch == '#' && chars.peek() == Some(&'\\') {
+                    token.push(ch);
+                    token.extend(chars.next());
+                    column += 1;
+                    if let Some(next) = chars.next() {
+                        token.push(next);
+                        if next == '\n' {
+                            line += 1;
+                            column = 1;
+                        } else {
+                            column += 1;
+                        }
+                    }
+                    continue;
+                }
+            }
+            token.push(ch);
+            continue;
+        }
+
+        match ch {
+            ';' => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        line += 1;
+                        column = 1;
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        line += 1;
+                        column = 1;
+                    } else {
+                        column += 1;
+                    }
+                    if escaped {
+                        escaped = false;
+                    } else if next == '\\' {
+                        escaped = true;
+                    } else if next == '"' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    finish(&mut token, token_start);
+    references
+}
+
+// ("defun", name) for (defun name ...), (define name ...) and (defmacro name ...)
+pub(crate) fn definition_head(expr: &Expr) -> Option<(&str, &str)> {
+    let Expr::List(items) = expr else {
+        return None;
+    };
+    match (items.first(), items.get(1)) {
+        (
+            Some(Expr::Symbol(SymbolData::Interned(head))),
+            Some(Expr::Symbol(SymbolData::Interned(name))),
+        ) if matches!(head.as_str(), "defun" | "define" | "defmacro") => Some((head, name)),
+        _ => None,
+    }
+}
+
+// Where `name` appears after the defining form's head, falling back to the form's start
+pub(crate) fn name_position(form: &SourceForm, name: &str) -> (usize, usize) {
+    let after_head = form
+        .source
+        .find(char::is_whitespace)
+        .unwrap_or(form.source.len());
+    match form.source[after_head..].find(name) {
+        Some(offset) => advance(form.line, form.column, &form.source[..after_head + offset]),
+        None => (form.line, form.column),
+    }
+}
+
+// The position just past `text` when it starts at line:column
+pub(crate) fn advance(line: usize, column: usize, text: &str) -> (usize, usize) {
+    match text.rfind('\n') {
+        Some(index) => (
+            line + text.matches('\n').count(),
+            text[index + 1..].chars().count() + 1,
+        ),
+        None => (line, column + text.chars().count()),
+    }
+}
+
+fn is_lambda(expr: &Expr) -> bool {
+    match expr {
+        Expr::List(items) => {
+            matches!(items.first(), Some(Expr::Symbol(SymbolData::Interned(head))) if head == "lambda")
+        }
+        _ => false,
+    }
+}
+
+// Every interned symbol in `expr`, quoted data included, so a name passed around as
+// (quote f) still counts as used
+fn collect_symbols(expr: &Expr, into: &mut HashSet<String>) {
+    match expr {
+        Expr::Symbol(SymbolData::Interned(name)) => {
+            into.insert(name.to_string());
+        }
+        Expr::List(items) => {
+            for item in items.iter() {
+                collect_symbols(item, into);
+            }
+        }
+        Expr::Cons(car, cdr) => {
+            collect_symbols(car, into);
+            collect_symbols(cdr, into);
+        }
+        Expr::Vector(items) => {
+            for item in items.borrow().iter() {
+                collect_symbols(item, into);
+            }
+        }
+        _ => {}
+    }
+}
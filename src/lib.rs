@@ -0,0 +1,152 @@
+//! Zeus, a small Lisp, as a library. `eval_str` runs a program in a fresh interpreter and
+//! `Session` keeps one alive across calls; `Evaluator`, `Expr` and `Environment` are the
+//! interpreter itself, re-exported for embedders that need more than the facade.
+//!
+//! ```
+//! let value = zeus::eval_str("(defun square (x) (* x x)) (square 12)").unwrap();
+//! assert_eq!(zeus::write_string(&value), "144");
+//! ```
+//!
+//! Failures come back as a [`ZeusError`], which says whether the source didn't read, an
+//! evaluation failed (and in which form), a file couldn't be read or the run was interrupted.
+//!
+//! The raylib IDE (`ide`, `ui`, `fonts`) is only built with the `gui` feature.
+
+pub mod error;
+#[cfg(feature = "gui")]
+pub mod fonts;
+#[cfg(feature = "gui")]
+pub mod ide;
+pub mod interpreter;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "gui")]
+pub mod ui;
+
+pub use error::{TraceFrame, ZeusError};
+pub use interpreter::environment::Environment;
+pub use interpreter::evaluator::Evaluator;
+pub use interpreter::printer::{display_string, write_string};
+pub use interpreter::types::{EvalError, Expr};
+
+use interpreter::batch::split_forms;
+use interpreter::ports::StringPort;
+use interpreter::source_map::Position;
+use std::fs;
+use std::path::Path;
+
+/// Evaluates every top-level form of `source` in a new interpreter and returns the value of
+/// the last one (nil if there are none). Output from `print` and friends goes to stdout.
+///
+/// ```
+/// let value = zeus::eval_str("(list 1 2)").unwrap();
+/// assert_eq!(zeus::write_string(&value), "(1 2)");
+/// assert!(zeus::eval_str("(car 5)").is_err());
+/// ```
+pub fn eval_str(source: &str) -> Result<Expr, ZeusError> {
+    eval_in(&mut Evaluator::new(), source)
+}
+
+/// Evaluates every top-level form of `source` in `evaluator`, stopping at the first error,
+/// and returns the value of the last one. Forms before an unreadable one still run.
+pub fn eval_in(evaluator: &mut Evaluator, source: &str) -> Result<Expr, ZeusError> {
+    let (forms, read_error) = split_forms(source);
+    let mut value = Expr::new_list(vec![]);
+    for form in forms {
+        let origin = Position::new(form.line, form.column);
+        let expr = evaluator
+            .read_at(&form.source, origin)
+            .map_err(|error| ZeusError::Parse {
+                line: error.position.line,
+                column: error.position.column,
+                message: error.message,
+            })?;
+        value = evaluator
+            .eval_read(&form.source, Ok(expr))
+            .map_err(|error| ZeusError::from(error).within(&form, evaluator.error_span()))?;
+    }
+    match read_error {
+        Some(form) => Err(ZeusError::Parse {
+            line: form.line,
+            column: form.column,
+            message: "unbalanced parentheses or unterminated string".to_string(),
+        }),
+        None => Ok(value),
+    }
+}
+
+/// Reads the file at `path` and evaluates it with [`eval_in`]; `(require ...)` in it looks
+/// in the file's directory
+pub fn load_file(evaluator: &mut Evaluator, path: &Path) -> Result<Expr, ZeusError> {
+    let source = fs::read_to_string(path).map_err(|error| ZeusError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    if let Some(dir) = path.parent() {
+        evaluator.set_require_dir(dir);
+    }
+    eval_in(evaluator, &source)
+}
+
+/// A long-lived interpreter whose definitions carry over from one `eval` to the next, with
+/// program output collected for the host instead of printed.
+///
+/// ```
+/// use zeus::{Expr, Session};
+///
+/// let mut session = Session::new();
+/// session.define("limit", Expr::Integer(3));
+/// session.eval("(defun under (n) (< n limit))").unwrap();
+/// session.eval("(println (under 2))").unwrap();
+/// assert_eq!(session.take_output(), "t\n");
+/// assert_eq!(session.get("limit"), Some(Expr::Integer(3)));
+/// ```
+pub struct Session {
+    evaluator: Evaluator,
+    output: StringPort,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        let mut evaluator = Evaluator::new();
+        let output = evaluator.capture_output();
+        Self { evaluator, output }
+    }
+
+    /// Evaluates every top-level form of `source`, stopping at the first error, and returns
+    /// the last value
+    pub fn eval(&mut self, source: &str) -> Result<Expr, ZeusError> {
+        eval_in(&mut self.evaluator, source)
+    }
+
+    /// Evaluates the file at `path`, as `eval` would its contents
+    pub fn load(&mut self, path: &Path) -> Result<Expr, ZeusError> {
+        load_file(&mut self.evaluator, path)
+    }
+
+    /// Everything printed since the last call
+    pub fn take_output(&mut self) -> String {
+        self.output.take()
+    }
+
+    /// Binds a global, as `(define name value)` would
+    pub fn define(&mut self, name: &str, value: Expr) {
+        self.evaluator.environment.define(name.to_string(), value);
+    }
+
+    /// The global named `name`, if it is bound
+    pub fn get(&self, name: &str) -> Option<Expr> {
+        self.evaluator.environment.get(name).ok()
+    }
+
+    /// The interpreter underneath, for budgets, ports and everything else the facade leaves out
+    pub fn evaluator(&mut self) -> &mut Evaluator {
+        &mut self.evaluator
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,103 +1,470 @@
-pub mod fonts;
-pub mod ide;
-pub mod interpreter;
-pub mod tests;
-pub mod ui;
-
-use crate::interpreter::repl::Repl;
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use zeus::interpreter::allocations::CountingAllocator;
+use zeus::interpreter::batch::{self, FormOutcome};
+use zeus::interpreter::bench;
+use zeus::interpreter::check::{self, CheckConfig, OutputFormat};
+use zeus::interpreter::doctest;
+use zeus::interpreter::evaluator::with_eval_stack;
+use zeus::interpreter::graph::{self, GraphFormat};
+use zeus::interpreter::lsp;
+use zeus::interpreter::packages::{self, Dependency, Manifest, PackageSource};
+use zeus::interpreter::prelude;
+use zeus::interpreter::remote::{self, DEFAULT_REMOTE_ADDR};
+use zeus::interpreter::repl::Repl;
+use zeus::interpreter::xref::XrefIndex;
+use zeus::{EvalError, Evaluator, ZeusError};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    // Arguments after `--` belong to the program, which reads them with (command-line)
+    let mut args: Vec<String> = env::args().take_while(|arg| arg != "--").collect();
+    // `--no-prelude` anywhere before `--` starts every evaluator with only the builtins
+    if let Some(index) = args.iter().position(|arg| arg == "--no-prelude") {
+        args.remove(index);
+        prelude::disable();
+    }
+
+    // The IDE stays on the main thread, where the window system wants it; everything else
+    // runs with room for deep recursion
+    if matches!(args.get(1).map(String::as_str), Some("-ui" | "-ui-old")) {
+        run_command(args);
+    } else {
+        with_eval_stack(move || run_command(args));
+    }
+}
 
+fn run_command(args: Vec<String>) {
     if args.len() > 1 {
         match args[1].as_str() {
+            #[cfg(feature = "gui")]
             "-ui" => {
-                // Launch IDE mode (Phase 1)
-                let mut app = ide::IdeApp::new();
+                // Launch IDE mode (Phase 1), optionally serving remote REPL clients
+                let mut app = zeus::ide::IdeApp::new();
+                if let Some(index) = args.iter().position(|arg| arg == "--serve") {
+                    let addr = args
+                        .get(index + 1)
+                        .map(String::as_str)
+                        .unwrap_or(DEFAULT_REMOTE_ADDR);
+                    if let Err(e) = app.serve_remote(addr) {
+                        eprintln!("Error: {}", e);
+                    }
+                }
                 app.run();
             }
+            #[cfg(feature = "gui")]
             "-ui-old" => {
                 // Launch old UI mode
-                ui::run_ui();
+                zeus::ui::run_ui();
             }
-            "--load" => {
-                if args.len() < 3 {
-                    eprintln!("Error: --load requires a filename");
-                    eprintln!("Usage: {} --load <filename.lisp>", args[0]);
-                    std::process::exit(1);
+            #[cfg(not(feature = "gui"))]
+            "-ui" | "-ui-old" => {
+                eprintln!("Error: this zeus was built without the `gui` feature; use `repl`, `run` or `lsp`, or rebuild with default features");
+                std::process::exit(1);
+            }
+            "repl" => {
+                // `repl --connect [addr]` attaches to an IDE started with --serve
+                match args.get(2).map(String::as_str) {
+                    Some("--connect") => {
+                        let addr = args
+                            .get(3)
+                            .map(String::as_str)
+                            .unwrap_or(DEFAULT_REMOTE_ADDR);
+                        if let Err(e) = remote::run_client(addr) {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                    Some(other) => {
+                        eprintln!("Error: unknown repl option '{}'", other);
+                        eprintln!("Usage: {} repl [--connect <host:port>]", args[0]);
+                        std::process::exit(1);
+                    }
+                    None => {
+                        println!("Zeus LISP v0.1.0");
+                        println!("Type 'exit' or press Ctrl+C to quit\n");
+
+                        let mut repl = Repl::new();
+                        repl.run();
+                    }
                 }
+            }
+            "run" => {
+                // `run [--json] file.lisp`: headless batch run for CI and tooling
+                let json = args.get(2).map(String::as_str) == Some("--json");
+                let Some(filename) = args.get(if json { 3 } else { 2 }) else {
+                    eprintln!(
+                        "Usage: {} run [--json] <filename.lisp> [-- <program arguments>...]",
+                        args[0]
+                    );
+                    std::process::exit(1);
+                };
+                let contents = match fs::read_to_string(filename) {
+                    Ok(contents) => contents,
+                    Err(e) => {
+                        eprintln!("Error reading file '{}': {}", filename, e);
+                        std::process::exit(1);
+                    }
+                };
 
-                let filename = &args[2];
-                if !Path::new(filename).exists() {
-                    eprintln!("Error: File '{}' not found", filename);
+                let report = batch::run_program(filename, &contents);
+                if json {
+                    print!("{}", report.to_json());
+                } else {
+                    for form in &report.forms {
+                        print!("{}", form.output);
+                        if let FormOutcome::Error(error) = &form.outcome {
+                            eprintln!("{}:{}: Error: {}", filename, form.line, error);
+                        }
+                    }
+                    for warning in &report.warnings {
+                        eprintln!(
+                            "{}:{}: warning: {}",
+                            filename, warning.line, warning.message
+                        );
+                    }
+                }
+                std::process::exit(report.exit_code());
+            }
+            "bench" => {
+                // `bench [--runs N] files...`: times each program over N fresh runs
+                let usage = format!("Usage: {} bench [--runs N] <filename.lisp>...", args[0]);
+                let mut runs = 10;
+                let mut files = Vec::new();
+                let mut rest = args[2..].iter();
+                while let Some(arg) = rest.next() {
+                    if arg == "--runs" {
+                        match rest.next().and_then(|count| count.parse().ok()) {
+                            Some(count) if count > 0 => runs = count,
+                            _ => {
+                                eprintln!("Error: --runs expects a positive number");
+                                eprintln!("{}", usage);
+                                std::process::exit(1);
+                            }
+                        }
+                    } else {
+                        files.push(arg);
+                    }
+                }
+                if files.is_empty() {
+                    eprintln!("{}", usage);
                     std::process::exit(1);
                 }
 
-                // Read the file
-                match fs::read_to_string(filename) {
-                    Ok(contents) => {
-                        // Create a REPL instance and evaluate the file
-                        let mut repl = Repl::new();
+                for filename in files {
+                    let contents = match fs::read_to_string(filename) {
+                        Ok(contents) => contents,
+                        Err(e) => {
+                            eprintln!("Error reading file '{}': {}", filename, e);
+                            std::process::exit(1);
+                        }
+                    };
+                    match bench::bench_program(filename, &contents, runs) {
+                        Ok(report) => print!("{}", report.summary()),
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            "check" => {
+                // `check [--format text|json|github] [--dead-code] [--strict-types] files...`:
+                // static checks without evaluating; exits non-zero when any finding is an
+                // error. Rules are configured by the zeus.toml nearest each file.
+                let usage = format!(
+                    "Usage: {} check [--format text|json|github] [--dead-code] [--strict-types] <filename.lisp>...",
+                    args[0]
+                );
+                let mut format = OutputFormat::Text;
+                let mut dead_code = false;
+                let mut strict_types = false;
+                let mut files = Vec::new();
+                let mut rest = args[2..].iter();
+                while let Some(arg) = rest.next() {
+                    if arg == "--dead-code" {
+                        dead_code = true;
+                    } else if arg == "--strict-types" {
+                        strict_types = true;
+                    } else if arg == "--format" {
+                        match rest.next().and_then(|name| OutputFormat::parse(name)) {
+                            Some(parsed) => format = parsed,
+                            None => {
+                                eprintln!("Error: --format expects text, json or github");
+                                eprintln!("{}", usage);
+                                std::process::exit(1);
+                            }
+                        }
+                    } else {
+                        files.push(arg);
+                    }
+                }
+                if files.is_empty() {
+                    eprintln!("{}", usage);
+                    std::process::exit(1);
+                }
 
-                        // Remove comments and join lines, then process complete expressions
-                        let mut buffer = String::new();
-                        let mut paren_count = 0;
+                let mut sources = Vec::new();
+                let mut configs = Vec::new();
+                for filename in files {
+                    let dir = fs::canonicalize(filename)
+                        .ok()
+                        .and_then(|path| path.parent().map(Path::to_path_buf))
+                        .unwrap_or_default();
+                    let mut config = match CheckConfig::load(&dir) {
+                        Ok(config) => config,
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                    match fs::read_to_string(filename) {
+                        Ok(contents) => sources.push((filename.clone(), contents)),
+                        Err(e) => {
+                            eprintln!("Error reading file '{}': {}", filename, e);
+                            std::process::exit(1);
+                        }
+                    }
+                    if strict_types {
+                        config.set_strict_types(true);
+                    }
+                    configs.push(config);
+                }
 
-                        for line in contents.lines() {
-                            // Skip comment lines
-                            if line.trim().starts_with(';') {
-                                continue;
+                // Dead code is judged across all the files together
+                let index = XrefIndex::build(&sources);
+                let mut diagnostics = Vec::new();
+                for ((filename, contents), config) in sources.iter().zip(&configs) {
+                    diagnostics.extend(check::check_source(filename, contents, config));
+                    if dead_code {
+                        diagnostics
+                            .extend(check::check_dead_code(&index, filename, contents, config));
+                    }
+                }
+                print!("{}", check::format_diagnostics(&diagnostics, format));
+                std::process::exit(if check::has_errors(&diagnostics) {
+                    1
+                } else {
+                    0
+                });
+            }
+            "graph" => {
+                // `graph [--modules] [--format dot|json] files...`: who calls whom among
+                // the files' definitions, or with --modules which files depend on which
+                let usage = format!(
+                    "Usage: {} graph [--modules] [--format dot|json] <filename.lisp>...",
+                    args[0]
+                );
+                let mut format = GraphFormat::Dot;
+                let mut modules = false;
+                let mut sources = Vec::new();
+                let mut rest = args[2..].iter();
+                while let Some(arg) = rest.next() {
+                    if arg == "--modules" {
+                        modules = true;
+                    } else if arg == "--format" {
+                        match rest.next().and_then(|name| GraphFormat::parse(name)) {
+                            Some(parsed) => format = parsed,
+                            None => {
+                                eprintln!("Error: --format expects dot or json");
+                                eprintln!("{}", usage);
+                                std::process::exit(1);
+                            }
+                        }
+                    } else {
+                        match fs::read_to_string(arg) {
+                            Ok(contents) => sources.push((arg.clone(), contents)),
+                            Err(e) => {
+                                eprintln!("Error reading file '{}': {}", arg, e);
+                                std::process::exit(1);
                             }
+                        }
+                    }
+                }
+                if sources.is_empty() {
+                    eprintln!("{}", usage);
+                    std::process::exit(1);
+                }
 
-                            // Add the line to buffer
-                            buffer.push_str(line);
-                            buffer.push(' '); // Add space to separate lines
+                let index = XrefIndex::build(&sources);
+                if modules {
+                    print!("{}", graph::module_graph(&index, format));
+                } else {
+                    print!("{}", graph::function_graph(&index, format));
+                }
+            }
+            "test" => {
+                // `test --doc files...`: runs the `(form) => value` examples in each file's
+                // docstrings; exits non-zero when any fails
+                let usage = format!("Usage: {} test --doc <filename.lisp>...", args[0]);
+                if args.get(2).map(String::as_str) != Some("--doc") || args.len() < 4 {
+                    eprintln!("{}", usage);
+                    std::process::exit(1);
+                }
 
-                            // Count parentheses to detect complete expressions
-                            for ch in line.chars() {
-                                match ch {
-                                    '(' => paren_count += 1,
-                                    ')' => paren_count -= 1,
-                                    _ => {}
-                                }
+                let mut succeeded = true;
+                for filename in &args[3..] {
+                    let contents = match fs::read_to_string(filename) {
+                        Ok(contents) => contents,
+                        Err(e) => {
+                            eprintln!("Error reading file '{}': {}", filename, e);
+                            std::process::exit(1);
+                        }
+                    };
+                    let report = doctest::run_doctests(filename, &contents);
+                    print!("{}", report.summary());
+                    succeeded &= report.succeeded();
+                }
+                std::process::exit(if succeeded { 0 } else { 1 });
+            }
+            "pkg" => {
+                // `pkg add <name> --path <dir> | --git <url> [--rev <rev>]` records a
+                // dependency in the nearest zeus.toml (or a new one here) and vendors it;
+                // `pkg install` vendors every dependency the manifest lists
+                let usage = format!(
+                    "Usage: {} pkg add <name> (--path <dir> | --git <url> [--rev <rev>]) | pkg install",
+                    args[0]
+                );
+                let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+                let manifest = Manifest::find(&cwd);
+                match args.get(2).map(String::as_str) {
+                    Some("install") if args.len() == 3 => {
+                        let Some(manifest) = manifest else {
+                            eprintln!(
+                                "Error: no {} in this directory or its parents",
+                                check::CONFIG_FILE
+                            );
+                            std::process::exit(1);
+                        };
+                        match packages::install(&manifest) {
+                            Ok(installed) if installed.is_empty() => {
+                                println!("No dependencies in {}", manifest.display())
                             }
-
-                            // If we have a complete expression (balanced parens), evaluate it
-                            if paren_count == 0 && !buffer.trim().is_empty() {
-                                match repl.evaluate(buffer.trim()) {
-                                    Ok(_result) => {
-                                        // Don't print the result - let print/println handle output
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Error: {}", e);
-                                        eprintln!("In expression: {}", buffer.trim());
-                                        std::process::exit(1);
-                                    }
+                            Ok(installed) => {
+                                for line in installed {
+                                    println!("Installed {}", line);
                                 }
-                                buffer.clear();
+                            }
+                            Err(e) => {
+                                eprintln!("Error: {}", e);
+                                std::process::exit(1);
                             }
                         }
-
-                        // Evaluate any remaining buffer
-                        if !buffer.trim().is_empty() {
-                            if paren_count != 0 {
-                                eprintln!("Error: Unbalanced parentheses in file");
+                    }
+                    Some("add") if args.len() >= 5 => {
+                        let name = &args[3];
+                        let option = |flag: &str| {
+                            args.iter()
+                                .position(|arg| arg == flag)
+                                .and_then(|index| args.get(index + 1))
+                        };
+                        let manifest = manifest.unwrap_or_else(|| cwd.join(check::CONFIG_FILE));
+                        let project = manifest.parent().unwrap_or(&cwd).to_path_buf();
+                        let source = match (option("--path"), option("--git")) {
+                            // Paths in the manifest are relative to it
+                            (Some(path), None) if project == cwd => {
+                                PackageSource::Path(PathBuf::from(path))
+                            }
+                            (Some(path), None) => PackageSource::Path(cwd.join(path)),
+                            (None, Some(url)) => PackageSource::Git {
+                                url: url.clone(),
+                                rev: option("--rev").cloned(),
+                            },
+                            _ => {
+                                eprintln!("{}", usage);
                                 std::process::exit(1);
                             }
-                            match repl.evaluate(buffer.trim()) {
-                                Ok(_result) => {}
-                                Err(e) => {
-                                    eprintln!("Error: {}", e);
-                                    std::process::exit(1);
+                        };
+                        if !packages::is_package_name(name) {
+                            eprintln!(
+                                "Error: `{}` isn't a package name (letters, digits, - and _)",
+                                name
+                            );
+                            std::process::exit(1);
+                        }
+                        let dependency = Dependency {
+                            name: name.clone(),
+                            source,
+                        };
+                        match packages::add_package(&manifest, &project, &dependency) {
+                            Ok(line) => println!("Added {} to {}", line, manifest.display()),
+                            Err(e) => {
+                                eprintln!("Error: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    _ => {
+                        eprintln!("{}", usage);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "lsp" => {
+                // `lsp`: a language server for editors, speaking LSP on stdin/stdout
+                std::process::exit(lsp::run_stdio());
+            }
+            "--load" => {
+                if args.len() < 3 {
+                    eprintln!("Error: --load requires a filename");
+                    eprintln!("Usage: {} --load <filename.lisp>", args[0]);
+                    std::process::exit(1);
+                }
+
+                let filename = &args[2];
+                if !Path::new(filename).exists() {
+                    eprintln!("Error: File '{}' not found", filename);
+                    std::process::exit(1);
+                }
+
+                // Same reader as `run`, so comments, strings and character literals holding
+                // parens don't cut a form short; output goes straight to stdout
+                match zeus::load_file(&mut Evaluator::new(), Path::new(filename)) {
+                    Ok(_) => {}
+                    Err(ZeusError::Eval {
+                        error: EvalError::Exit(code),
+                        ..
+                    }) => {
+                        io::stdout().flush().ok();
+                        std::process::exit(code);
+                    }
+                    Err(ZeusError::Eval { error, trace }) => {
+                        match trace.first() {
+                            Some(frame) => {
+                                match frame.span {
+                                    Some(span) => eprintln!(
+                                        "{}:{}:{}: Error: {}",
+                                        filename, span.start.line, span.start.column, error
+                                    ),
+                                    None => eprintln!(
+                                        "{}:{}: Error: {}",
+                                        filename, frame.form.line, error
+                                    ),
                                 }
+                                eprintln!("In expression: {}", frame.form.source);
                             }
+                            None => eprintln!("{}: Error: {}", filename, error),
                         }
+                        std::process::exit(1);
+                    }
+                    Err(ZeusError::Parse {
+                        line,
+                        column,
+                        message,
+                    }) => {
+                        eprintln!("{}:{}:{}: Error: {}", filename, line, column, message);
+                        std::process::exit(1);
+                    }
+                    Err(e @ ZeusError::Io { .. }) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
                     }
                     Err(e) => {
-                        eprintln!("Error reading file '{}': {}", filename, e);
+                        eprintln!("{}: Error: {}", filename, e);
                         std::process::exit(1);
                     }
                 }
@@ -105,7 +472,10 @@ fn main() {
             _ => {
                 // Run traditional REPL with unknown arguments
                 println!("Zeus LISP v0.1.0");
-                println!("Usage: {} [-ui | --load <filename.lisp>]", args[0]);
+                println!(
+                    "Usage: {} [--no-prelude] [-ui [--serve <host:port>] | --load <filename.lisp> | run [--json] <filename.lisp> | bench [--runs N] <filename.lisp>... | check [--format text|json|github] [--dead-code] [--strict-types] <filename.lisp>... | graph [--modules] [--format dot|json] <filename.lisp>... | test --doc <filename.lisp>... | pkg add <name> (--path <dir> | --git <url> [--rev <rev>]) | pkg install | lsp | repl [--connect <host:port>]] [-- <program arguments>...]",
+                    args[0]
+                );
                 println!("Type 'exit' or press Ctrl+C to quit\n");
 
                 let mut repl = Repl::new();
@@ -98,3 +98,145 @@ fn test_comparison_supports_rationals() {
         .unwrap();
     assert_eq!(ge_result, Evaluator::bool_to_expr(true));
 }
+
+#[test]
+fn test_integer_overflow_promotes_to_bignum() {
+    let mut evaluator = Evaluator::new();
+    let sum = evaluator.eval_str("(+ 9223372036854775807 1)").unwrap();
+    assert_eq!(
+        sum,
+        Expr::BigInt(BigInt::parse("9223372036854775808").unwrap())
+    );
+
+    let product = evaluator.eval_str("(* 4294967296 4294967296 -1)").unwrap();
+    assert_eq!(Repl::new().format_expr(&product), "-18446744073709551616");
+
+    // Results that fit back into i64 are narrowed again
+    assert_eq!(
+        evaluator.eval_str("(- 9223372036854775808 1)").unwrap(),
+        Expr::Integer(i64::MAX)
+    );
+    assert_eq!(
+        evaluator.eval_str("(- -9223372036854775808)").unwrap(),
+        Expr::BigInt(BigInt::parse("9223372036854775808").unwrap())
+    );
+}
+
+#[test]
+fn test_bignum_factorial_is_exact() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str("(defun fact (n) (if (= n 0) 1 (* n (fact (- n 1)))))")
+        .unwrap();
+    let result = evaluator.eval_str("(fact 25)").unwrap();
+    assert_eq!(
+        Repl::new().format_expr(&result),
+        "15511210043330985984000000"
+    );
+    assert_eq!(
+        evaluator.eval_str("(integerp (fact 30))").unwrap(),
+        Evaluator::bool_to_expr(true)
+    );
+}
+
+#[test]
+fn test_expt() {
+    let mut evaluator = Evaluator::new();
+    assert_eq!(
+        evaluator.eval_str("(expt 2 10)").unwrap(),
+        Expr::Integer(1024)
+    );
+    assert_eq!(
+        Repl::new().format_expr(&evaluator.eval_str("(expt 2 100)").unwrap()),
+        "1267650600228229401496703205376"
+    );
+    assert_eq!(evaluator.eval_str("(expt 2 -1)").unwrap(), Expr::Float(0.5));
+    assert_eq!(
+        evaluator.eval_str("(expt 4.0 0.5)").unwrap(),
+        Expr::Float(2.0)
+    );
+    assert!(evaluator.eval_str("(expt 2)").is_err());
+
+    // Results too big to compute in reasonable time are errors, except for bases whose
+    // powers stay small
+    assert_eq!(
+        evaluator
+            .eval_str("(expt 3 4000000000)")
+            .unwrap_err()
+            .to_string(),
+        "expt: the result would have about 8000000000 bits, over the limit of 1048576"
+    );
+    assert!(evaluator.eval_str("(expt 100000000000 100000)").is_err());
+    assert_eq!(
+        evaluator.eval_str("(expt -1 4000000001)").unwrap(),
+        Expr::Integer(-1)
+    );
+    assert_eq!(
+        evaluator.eval_str("(expt 1 9223372036854775807)").unwrap(),
+        Expr::Integer(1)
+    );
+    assert_eq!(
+        evaluator.eval_str("(expt 0 4000000000)").unwrap(),
+        Expr::Integer(0)
+    );
+    assert_eq!(evaluator.eval_str("(expt 0 0)").unwrap(), Expr::Integer(1));
+}
+
+#[test]
+fn test_bignum_comparisons_are_exact() {
+    let mut evaluator = Evaluator::new();
+    let t = Evaluator::bool_to_expr(true);
+    let nil = Evaluator::bool_to_expr(false);
+    assert_eq!(
+        evaluator
+            .eval_str("(< 100000000000000000000 100000000000000000001)")
+            .unwrap(),
+        t
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(= 100000000000000000000 100000000000000000001)")
+            .unwrap(),
+        nil
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(> (expt 2 64) 9223372036854775807 -5)")
+            .unwrap(),
+        t
+    );
+}
+
+#[test]
+fn test_statistics() {
+    assert_eq!(eval_to_number("(mean (list 1 2 3 4))"), 2.5);
+    assert_eq!(eval_to_number("(mean [2.5])"), 2.5);
+    assert_eq!(eval_to_number("(median (list 3 1 2))"), 2.0);
+    assert_eq!(eval_to_number("(median [4 1 3 2])"), 2.5);
+    assert_eq!(eval_to_number("(variance (list 2 4 4 4 5 5 7 9))"), 4.0);
+    assert_eq!(eval_to_number("(stddev (list 2 4 4 4 5 5 7 9))"), 2.0);
+    assert_eq!(eval_to_number("(percentile (list 15 20 35 40 50) 0)"), 15.0);
+    assert_eq!(
+        eval_to_number("(percentile (list 15 20 35 40 50) 100)"),
+        50.0
+    );
+    assert_eq!(
+        eval_to_number("(percentile (list 50 40 35 20 15) 25)"),
+        20.0
+    );
+    assert_eq!(eval_to_number("(percentile [1 2] 40)"), 1.4);
+
+    let mut evaluator = Evaluator::new();
+    assert_eq!(
+        evaluator.eval_str("(mean (list))").unwrap_err(),
+        "mean: no values"
+    );
+    for bad in [
+        "(median (list 1 \"two\"))",
+        "(variance 5)",
+        "(percentile (list 1 2) 101)",
+        "(percentile (list 1 2))",
+    ] {
+        assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+    }
+}
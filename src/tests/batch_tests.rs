@@ -0,0 +1,63 @@
+use crate::interpreter::batch::{run_program, split_forms, FormOutcome};
+
+#[test]
+fn test_split_forms_tracks_lines() {
+    let source = "; header\n(define x 1)\n\n(list \"(\" #\\) ; )\n  2) 42 \"s\"\n(open";
+    let (forms, error) = split_forms(source);
+    let summary: Vec<(usize, &str)> = forms
+        .iter()
+        .map(|form| (form.line, form.source.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (2, "(define x 1)"),
+            (4, "(list \"(\" #\\) ; )\n  2)"),
+            (5, "42"),
+            (5, "\"s\""),
+        ]
+    );
+    assert_eq!(error.map(|form| form.line), Some(6));
+}
+
+#[test]
+fn test_run_program_reports_each_form() {
+    let report = run_program(
+        "demo.lisp",
+        "(define n 2)\n(println \"n is\" n)\n(defun list (x) x)\n(car 5)\n(println \"never\")",
+    );
+    assert!(!report.succeeded());
+    assert_eq!(report.exit_code(), 1);
+    assert_eq!(report.forms.len(), 4);
+    assert_eq!(report.forms[0].outcome, FormOutcome::Value("2".to_string()));
+    assert_eq!(report.forms[1].output, "n is\n2\n");
+    assert!(matches!(report.forms[3].outcome, FormOutcome::Error(_)));
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].line, 3);
+
+    let json = report.to_json();
+    assert!(json.contains("\"status\": \"error\""));
+    assert!(json.contains("\"output\": \"n is\\n2\\n\""));
+    assert!(json.contains("\"message\": \"redefines builtin `list`\""));
+
+    let report = run_program("ok.lisp", "(+ 1 2)");
+    assert!(report.succeeded());
+    assert!(report.to_json().contains("\"exit_code\": 0"));
+}
+
+#[test]
+fn test_exit_ends_the_run_with_its_code() {
+    let report = run_program(
+        "exit.lisp",
+        "(println \"before\")\n(unwind-protect (exit 3) (println \"cleanup\"))\n(println \"after\")\n(open",
+    );
+    assert_eq!(report.forms.len(), 2);
+    assert_eq!(report.forms[1].outcome, FormOutcome::Exit(3));
+    assert_eq!(report.forms[1].output, "cleanup\n");
+    assert_eq!(report.exit_code(), 3);
+    assert!(report
+        .to_json()
+        .contains("\"status\": \"exit\", \"code\": 3"));
+
+    assert!(run_program("done.lisp", "(exit)\n(car 5)").succeeded());
+}
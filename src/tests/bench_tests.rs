@@ -0,0 +1,49 @@
+use crate::interpreter::allocations::AllocationCount;
+use crate::interpreter::bench::{bench_program, BenchReport};
+use std::time::Duration;
+
+#[test]
+fn test_bench_program_runs() {
+    let report = bench_program(
+        "sum.lisp",
+        "(define xs (list 1 2 3))\n(println (length xs))\n",
+        3,
+    )
+    .unwrap();
+    assert_eq!(report.durations.len(), 3);
+    assert_eq!(report.allocations.len(), 3);
+    assert!(report.summary().starts_with("sum.lisp: 3 runs\n"));
+
+    let error = bench_program("bad.lisp", "(define x 1)\n(car 5)\n", 3).unwrap_err();
+    assert!(error.starts_with("bad.lisp:2: "), "{}", error);
+}
+
+#[test]
+fn test_bench_statistics() {
+    let report = BenchReport {
+        file: "f.lisp".to_string(),
+        durations: [4, 1, 3, 2].map(Duration::from_millis).to_vec(),
+        allocations: vec![
+            AllocationCount {
+                allocations: 10,
+                bytes: 100,
+            },
+            AllocationCount {
+                allocations: 20,
+                bytes: 300,
+            },
+        ],
+    };
+    assert_eq!(report.min(), Duration::from_millis(1));
+    assert_eq!(report.max(), Duration::from_millis(4));
+    assert_eq!(report.mean(), Duration::from_micros(2500));
+    assert_eq!(report.median(), Duration::from_micros(2500));
+    assert!((report.std_dev().as_secs_f64() * 1000.0 - 1.291).abs() < 0.001);
+    assert_eq!(
+        report.mean_allocations(),
+        AllocationCount {
+            allocations: 15,
+            bytes: 200
+        }
+    );
+}
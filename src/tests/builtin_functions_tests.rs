@@ -78,6 +78,25 @@ fn test_apply() {
         eval_to_number("(apply (lambda (x y) (* x y)) (list 3 4))"),
         12.0
     );
+
+    // Arguments before the last list are passed first, as in Common Lisp
+    assert_eq!(eval_to_number("(apply + 1 2 (list 3 4))"), 10.0);
+    assert_eq!(eval_to_number("(apply + 1 2 (list))"), 3.0);
+    assert_eq!(
+        eval_to_number("(apply (lambda (a b c) (- a (+ b c))) 10 (list 2 3))"),
+        5.0
+    );
+
+    let error = Evaluator::eval_once("(apply + 1 2)").unwrap_err();
+    assert!(
+        error.contains("last argument must be a list, got 2"),
+        "{}",
+        error
+    );
+    let error = Evaluator::eval_once("(apply +)").unwrap_err();
+    assert!(error.contains("at least 2 arguments"), "{}", error);
+    let error = Evaluator::eval_once("(apply 5 (list 1))").unwrap_err();
+    assert!(error.contains("5 is not a function"), "{}", error);
 }
 
 #[test]
@@ -87,6 +106,48 @@ fn test_funcall() {
 
     // With lambda
     assert_eq!(eval_to_number("(funcall (lambda (x y) (* x y)) 3 4)"), 12.0);
+
+    // Variables holding a function, or another function's symbol
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define first car)").unwrap();
+    evaluator
+        .eval_str("(define twice (lambda (x) (* 2 x)))")
+        .unwrap();
+    evaluator.eval_str("(define double (quote twice))").unwrap();
+    assert_eq!(
+        evaluator
+            .eval_str("(funcall (quote first) (list 7 8))")
+            .unwrap(),
+        Expr::Integer(7)
+    );
+    assert_eq!(
+        evaluator.eval_str("(funcall double 21)").unwrap(),
+        Expr::Integer(42)
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(apply (quote double) (list 4))")
+            .unwrap(),
+        Expr::Integer(8)
+    );
+
+    evaluator.eval_str("(define color (quote red))").unwrap();
+    let error = evaluator.eval_str("(funcall (quote color))").unwrap_err();
+    assert!(
+        error.contains("color is not a function (it is bound to red)"),
+        "{}",
+        error
+    );
+    evaluator.eval_str("(define ping (quote pong))").unwrap();
+    evaluator.eval_str("(define pong (quote ping))").unwrap();
+    assert!(evaluator.eval_str("(funcall (quote ping))").is_err());
+    // Keywords are callable now, but only on records
+    let error = evaluator.eval_str("(funcall :key 1)").unwrap_err();
+    assert!(
+        error.contains(":key requires a hash table or property list, got 1"),
+        "{}",
+        error
+    );
 }
 
 #[test]
@@ -105,7 +166,47 @@ fn test_print_println() {
     assert_eq!(result, Expr::String("hello".to_string()));
 
     let result = evaluator.eval_str("(print)").unwrap();
-    assert_eq!(result, Expr::List(vec![]));
+    assert_eq!(result, Expr::new_list(vec![]));
+}
+
+#[test]
+fn test_format_directives() {
+    let mut evaluator = Evaluator::new();
+    let format = |evaluator: &mut Evaluator, src: &str| match evaluator.eval_str(src).unwrap() {
+        Expr::String(s) => s,
+        other => panic!("expected string, got {:?}", other),
+    };
+
+    assert_eq!(
+        format(
+            &mut evaluator,
+            r#"(format nil "~a and ~s: ~d~%" "plain" "quoted" 42)"#
+        ),
+        "plain and \"quoted\": 42\n"
+    );
+    assert_eq!(
+        format(&mut evaluator, r#"(format nil "~a ~s ~s" :k :k #\x)"#),
+        "k :k #\\x"
+    );
+    assert_eq!(
+        format(&mut evaluator, r#"(format nil "~f ~,2f ~~" 3 3.14159)"#),
+        "3.0 3.14 ~"
+    );
+    assert_eq!(
+        format(&mut evaluator, r#"(format nil "~a" (list 1 "two" [3]))"#),
+        "(1 two [3])"
+    );
+
+    // t prints and returns nil
+    assert_eq!(
+        evaluator.eval_str(r#"(format t "")"#).unwrap(),
+        Expr::new_list(vec![])
+    );
+
+    assert!(evaluator.eval_str(r#"(format nil "~a ~a" 1)"#).is_err());
+    assert!(evaluator.eval_str(r#"(format nil "~d" 1.5)"#).is_err());
+    assert!(evaluator.eval_str(r#"(format nil "~q" 1)"#).is_err());
+    assert!(evaluator.eval_str(r#"(format 5 "x")"#).is_err());
 }
 
 #[test]
@@ -119,7 +220,7 @@ fn test_complex_mapcar() {
         .unwrap();
     assert_eq!(
         result,
-        Expr::List(vec![
+        Expr::new_list(vec![
             Expr::Integer(1),
             Expr::Integer(4),
             Expr::Integer(9),
@@ -151,3 +252,287 @@ fn test_apply_with_funcall() {
     assert_eq!(eval_to_number("(funcall apply + (list 1 2 3))"), 6.0);
     assert_eq!(eval_to_number("(apply funcall (list + 1 2 3))"), 6.0);
 }
+
+#[test]
+fn test_equality_predicates() {
+    // equal? compares structure, including vectors and characters
+    assert!(eval_to_bool(
+        "(equal? (list 1 (list 2 3)) (list 1 (list 2 3)))"
+    ));
+    assert!(eval_to_bool("(equal? (vector 1 \"a\") (vector 1 \"a\"))"));
+    assert!(eval_to_bool("(equal? #\\a #\\a)"));
+    assert!(!eval_to_bool("(equal? (vector 1 2) (vector 1 3))"));
+    assert!(!eval_to_bool("(equal? (list 1) (vector 1))"));
+
+    // eq? is identity: atoms with one representation, never fresh structure
+    assert!(eval_to_bool("(eq? :key :key)"));
+    assert!(eval_to_bool("(eq? 5 5)"));
+    assert!(eval_to_bool("(eq? (list) (list))"));
+    assert!(!eval_to_bool("(eq? (list 1) (list 1))"));
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define l (list 1 2))").unwrap();
+    evaluator.eval_str("(define m (list 1 2))").unwrap();
+    assert!(Evaluator::is_truthy(
+        &evaluator.eval_str("(eq? l l)").unwrap()
+    ));
+    assert!(!Evaluator::is_truthy(
+        &evaluator.eval_str("(eq? l m)").unwrap()
+    ));
+    assert!(!eval_to_bool("(eq? \"a\" \"a\")"));
+    assert!(!eval_to_bool("(eq? 1.5 1.5)"));
+
+    // eqv? adds numbers of the same exactness
+    assert!(eval_to_bool("(eqv? 1.5 1.5)"));
+    assert!(eval_to_bool(
+        "(eqv? 100000000000000000000 100000000000000000000)"
+    ));
+    assert!(!eval_to_bool("(eqv? 2 2.0)"));
+    assert!(!eval_to_bool("(eqv? (vector) (vector))"));
+
+    assert!(Evaluator::eval_once("(eq? 1)").is_err());
+}
+
+#[test]
+fn test_hash_table_equality() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str("(define a (hash-set! (make-hash-table) :x (list 1 2)))")
+        .unwrap();
+    evaluator
+        .eval_str("(define b (hash-set! (make-hash-table) :x (list 1 2)))")
+        .unwrap();
+    evaluator.eval_str("(define same a)").unwrap();
+
+    assert!(Evaluator::is_truthy(
+        &evaluator.eval_str("(equal? a b)").unwrap()
+    ));
+    assert!(Evaluator::is_truthy(
+        &evaluator.eval_str("(eq? a same)").unwrap()
+    ));
+    assert!(!Evaluator::is_truthy(
+        &evaluator.eval_str("(eq? a b)").unwrap()
+    ));
+}
+
+#[test]
+fn test_partial_compose_constantly() {
+    let mut evaluator = Evaluator::new();
+    assert_eq!(
+        evaluator
+            .eval_str("(mapcar (partial * 10) (list 1 2 3))")
+            .unwrap(),
+        Evaluator::parse("(10 20 30)").unwrap()
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(funcall (partial (quote list) (quote (a b))) 3)")
+            .unwrap(),
+        Evaluator::parse("((a b) 3)").unwrap()
+    );
+    evaluator
+        .eval_str("(define inc-then-double (compose (partial * 2) (partial + 1)))")
+        .unwrap();
+    assert_eq!(
+        evaluator.eval_str("(inc-then-double 4)").unwrap(),
+        Expr::Integer(10)
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(filter (compose (partial < 1) length) (list (list 1) (list 1 2)))")
+            .unwrap(),
+        Evaluator::parse("((1 2))").unwrap()
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(mapcar (constantly :x) (list 1 2))")
+            .unwrap(),
+        Evaluator::parse("(:x :x)").unwrap()
+    );
+    assert_eq!(
+        evaluator.eval_str("(identity (list 1))").unwrap(),
+        Evaluator::parse("(1)").unwrap()
+    );
+    assert_eq!(
+        evaluator.eval_str("(funcall (compose) 5)").unwrap(),
+        Expr::Integer(5)
+    );
+
+    // The results are plain lambdas, so they print in a form that reads back
+    let printed =
+        crate::interpreter::printer::write_string(&evaluator.eval_str("(partial + 1)").unwrap());
+    assert_eq!(
+        printed,
+        "(lambda (&rest partial-args) (apply (quote +) (quote 1) partial-args))"
+    );
+    evaluator
+        .eval_str(&format!("(define add-one {})", printed))
+        .unwrap();
+    assert_eq!(evaluator.eval_str("(add-one 2)").unwrap(), Expr::Integer(3));
+
+    assert!(evaluator.eval_str("(partial 5 1)").is_err());
+    assert!(evaluator.eval_str("(compose car 5)").is_err());
+    assert!(evaluator.eval_str("(constantly)").is_err());
+    assert!(evaluator.eval_str("(identity 1 2)").is_err());
+}
+
+#[test]
+fn test_keyword_arguments() {
+    let check = |expr: &str, expected: &str| {
+        let value = Evaluator::eval_once(expr).unwrap_or_else(|e| panic!("{}: {}", expr, e));
+        assert_eq!(
+            crate::interpreter::printer::write_string(&value),
+            expected,
+            "{}",
+            expr
+        );
+    };
+    let error = |expr: &str| Evaluator::eval_once(expr).unwrap_err();
+
+    check(
+        "(hash-count (hash-set! (make-hash-table :size 100 :test equal?) \"k\" 1))",
+        "1",
+    );
+    check("(hash-count (make-hash-table :test (quote eq?)))", "0");
+    assert_eq!(
+        error("(make-hash-table :test string=)"),
+        "make-hash-table: :test must be eq?, eqv? or equal?, got string="
+    );
+    assert_eq!(
+        error("(make-hash-table :size -1)"),
+        "make-hash-table requires a non-negative integer for :size, got -1"
+    );
+    assert_eq!(
+        error("(make-hash-table :weak t)"),
+        "make-hash-table expected :size or :test but got :weak"
+    );
+    assert_eq!(
+        error("(make-hash-table :size)"),
+        "make-hash-table is missing a value for :size"
+    );
+
+    check("(string-split \"  a b\\t c \")", "(\"a\" \"b\" \"c\")");
+    check("(string-split \"a,,b\" \",\")", "(\"a\" \"\" \"b\")");
+    check("(string-split \"a,b,c\" \",\" :limit 2)", "(\"a\" \"b,c\")");
+    check("(string-split \" a b  c \" :limit 2)", "(\"a\" \"b  c \")");
+    check("(string-split \"\")", "()");
+    assert!(Evaluator::eval_once("(string-split \"a\" :limit 0)").is_err());
+    assert!(Evaluator::eval_once("(string-split \"a\" \"\")").is_err());
+    assert!(Evaluator::eval_once("(string-split 5)").is_err());
+    assert!(Evaluator::eval_once("(string-split \"a\" \",\" \";\")").is_err());
+
+    check(
+        "(sort (list (list 2 :b) (list 1 :a)) < :key car)",
+        "((1 :a) (2 :b))",
+    );
+    // A keyword given twice takes its first value
+    check(
+        "(sort (list 3 1 2) < :key identity :key (lambda (x) (- x)))",
+        "(1 2 3)",
+    );
+}
+
+#[test]
+fn test_character_classes() {
+    assert!(eval_to_bool("(alpha? #\\a)"));
+    assert!(eval_to_bool("(alpha? #\\é)"));
+    assert!(!eval_to_bool("(alpha? #\\1)"));
+    assert!(eval_to_bool("(digit? #\\7)"));
+    assert!(!eval_to_bool("(digit? #\\f)"));
+    assert!(eval_to_bool("(digit? #\\f 16)"));
+    assert!(!eval_to_bool("(digit? #\\2 2)"));
+    assert!(eval_to_bool("(whitespace? #\\space)"));
+    assert!(eval_to_bool("(whitespace? #\\newline)"));
+    assert!(!eval_to_bool("(whitespace? #\\x)"));
+
+    for bad in [
+        "(alpha? \"a\")",
+        "(alpha? #\\a 10)",
+        "(digit? #\\1 37)",
+        "(whitespace?)",
+    ] {
+        assert!(Evaluator::eval_once(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_number_string_conversion() {
+    let check = |expr: &str, expected: &str| {
+        let value = Evaluator::eval_once(expr).unwrap_or_else(|e| panic!("{}: {}", expr, e));
+        assert_eq!(
+            crate::interpreter::printer::write_string(&value),
+            expected,
+            "{}",
+            expr
+        );
+    };
+
+    check("(string->number \"42\")", "42");
+    check("(string->number \"-17\")", "-17");
+    check("(string->number \"+5\")", "5");
+    check("(string->number \"2.5\")", "2.5");
+    check("(string->number \"1e3\")", "1000.0");
+    check("(string->number \"-inf.0\")", "-inf.0");
+    check(
+        "(string->number \"123456789012345678901234567890\")",
+        "123456789012345678901234567890",
+    );
+    // Rationals come back in lowest terms
+    check("(string->number \"6/4\")", "3/2");
+    check("(string->number \"-4/2\")", "-2");
+    check("(string->number \"ff\" 16)", "255");
+    check("(string->number \"#b101\")", "5");
+    check("(string->number \"#xFF\" 2)", "255");
+    check("(string->number \"-11\" 2)", "-3");
+    // Integers past i64 become bignums in any radix
+    check(
+        "(string->number \"ffffffffffffffffffff\" 16)",
+        "1208925819614629174706175",
+    );
+    check(
+        "(string->number \"#xFFFFFFFFFFFFFFFFFFFF\")",
+        "1208925819614629174706175",
+    );
+    for not_a_number in ["", "abc", "1/0", "1/-2", "1.5.2", "inf", "NaN", "1 2"] {
+        check(&format!("(string->number \"{}\")", not_a_number), "()");
+    }
+    // Floats are only read in radix 10, and digits must belong to the radix
+    check("(string->number \"1.5\" 16)", "()");
+    check("(string->number \"2\" 2)", "()");
+
+    check("(number->string 42)", "\"42\"");
+    check("(number->string 255 16)", "\"ff\"");
+    check("(number->string -5 2)", "\"-101\"");
+    check("(number->string 2.0)", "\"2.0\"");
+    check("(number->string (string->number \"10/4\") 2)", "\"101/10\"");
+    check(
+        "(number->string -9223372036854775808 16)",
+        "\"-8000000000000000\"",
+    );
+    check(
+        "(number->string (expt 2 100) 16)",
+        "\"10000000000000000000000000\"",
+    );
+    check(
+        "(number->string (- 1 (expt 2 64)) 2)",
+        &format!("\"-{}\"", "1".repeat(64)),
+    );
+    check("(number->string (expt 3 50) 36)", "\"38wj0cnk73u15rk9\"");
+    check(
+        "(number->string (expt 10 30) 7)",
+        "\"243230604464041356413054436032064451\"",
+    );
+    check(
+        "(= (string->number (number->string (expt 7 80) 36) 36) (expt 7 80))",
+        "t",
+    );
+
+    for bad in [
+        "(string->number 5)",
+        "(string->number \"1\" 1)",
+        "(number->string \"1\")",
+        "(number->string 1.5 2)",
+        "(number->string 1 2 3)",
+    ] {
+        assert!(Evaluator::eval_once(bad).is_err(), "{}", bad);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::interpreter::check::{
+    check_dead_code, check_source, format_diagnostics, has_errors, CheckConfig, OutputFormat,
+    Severity,
+};
+use crate::interpreter::xref::XrefIndex;
+
+#[test]
+fn test_check_reports_rules_with_spans() {
+    let source = "(defun twice (x) (* 2 x))\n(define  twice 3)\n(defun car (x) x)   \n  (list 1 2";
+    let diagnostics = check_source("demo.lisp", source, &CheckConfig::default());
+    let summary: Vec<(&str, usize, usize, usize)> = diagnostics
+        .iter()
+        .map(|d| (d.rule, d.line, d.column, d.end_column))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("duplicate-definition", 2, 10, 15),
+            ("redefined-builtin", 3, 8, 11),
+            ("trailing-whitespace", 3, 18, 21),
+            ("unterminated-form", 4, 3, 12),
+        ]
+    );
+    assert_eq!(
+        diagnostics[0].message,
+        "`twice` was already defined on line 1"
+    );
+    assert_eq!(diagnostics[3].severity, Severity::Error);
+    assert!(has_errors(&diagnostics));
+
+    let clean = check_source(
+        "ok.lisp",
+        "(define x 1)\n(println x)\n",
+        &CheckConfig::default(),
+    );
+    assert!(clean.is_empty());
+    assert!(!has_errors(&clean));
+}
+
+#[test]
+fn test_check_output_formats() {
+    let diagnostics = check_source("a,b.lisp", "(define list 1)\n", &CheckConfig::default());
+
+    assert_eq!(
+        format_diagnostics(&diagnostics, OutputFormat::Text),
+        "a,b.lisp:1:9: warning[redefined-builtin]: redefines builtin `list`\n"
+    );
+    assert_eq!(
+        format_diagnostics(&diagnostics, OutputFormat::Json),
+        "[\n  {\"file\": \"a,b.lisp\", \"line\": 1, \"column\": 9, \"end_line\": 1, \"end_column\": 13, \"severity\": \"warning\", \"rule\": \"redefined-builtin\", \"message\": \"redefines builtin `list`\"}\n]\n"
+    );
+    assert_eq!(
+        format_diagnostics(&diagnostics, OutputFormat::Github),
+        "::warning file=a%2Cb.lisp,line=1,col=9,endLine=1,endColumn=13,title=redefined-builtin::redefines builtin `list`\n"
+    );
+    assert_eq!(format_diagnostics(&[], OutputFormat::Json), "[]\n");
+    assert_eq!(OutputFormat::parse("github"), Some(OutputFormat::Github));
+    assert_eq!(OutputFormat::parse("xml"), None);
+}
+
+#[test]
+fn test_check_rule_config_and_suppression() {
+    let config = CheckConfig::parse(
+        "[package]\nname = \"demo # not a comment\"\n\n[check.rules]\ntrailing-whitespace = false # legacy files\nredefined-builtin = true\n",
+    )
+    .unwrap();
+    assert!(!config.is_enabled("trailing-whitespace"));
+    assert!(config.is_enabled("redefined-builtin"));
+    assert!(config.is_enabled("duplicate-definition"));
+
+    let source = "(define car 1)  \n;; zeus-check: ignore redefined-builtin\n(define cdr 2)\n(define cons 3) ; zeus-check: ignore\n(define list 4)\n";
+    let rules: Vec<(&str, usize)> = check_source("demo.lisp", source, &config)
+        .iter()
+        .map(|d| (d.rule, d.line))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![("redefined-builtin", 1), ("redefined-builtin", 5)]
+    );
+
+    assert_eq!(
+        CheckConfig::parse("[check.rules]\nno-such-rule = false\n"),
+        Err("line 2: unknown rule `no-such-rule`".to_string())
+    );
+    assert!(CheckConfig::parse("[check.rules]\ntrailing-whitespace = off\n").is_err());
+}
+
+#[test]
+fn test_dead_code_across_files() {
+    let files = vec![
+        (
+            "lib.lisp".to_string(),
+            "(defun helper (x) (* x 2))\n(defun used (x) (helper x))\n(defun orphan () (orphan))\n(define table (list (quote used)))\n(define unused-fn (lambda () 1))\n;; zeus-check: ignore unused-definition\n(defun kept-for-later () 0)\n".to_string(),
+        ),
+        (
+            "main.lisp".to_string(),
+            "(define scale 3)\n(println (used scale))\n".to_string(),
+        ),
+    ];
+    let index = XrefIndex::build(&files);
+    let config = CheckConfig::default();
+
+    let lib: Vec<(usize, usize, String)> =
+        check_dead_code(&index, &files[0].0, &files[0].1, &config)
+            .into_iter()
+            .map(|d| (d.line, d.column, d.message))
+            .collect();
+    assert_eq!(
+        lib,
+        vec![
+            (
+                3,
+                8,
+                "`orphan` is never used from any entry point".to_string()
+            ),
+            (
+                4,
+                9,
+                "`table` is never used from any entry point".to_string()
+            ),
+            (
+                5,
+                9,
+                "`unused-fn` is never used from any entry point".to_string()
+            ),
+        ]
+    );
+    assert!(check_dead_code(&index, &files[1].0, &files[1].1, &config).is_empty());
+
+    let mut quiet = CheckConfig::default();
+    quiet.set_enabled("unused-definition", false);
+    assert!(check_dead_code(&index, &files[0].0, &files[0].1, &quiet).is_empty());
+}
+
+#[test]
+fn test_check_type_mismatches() {
+    let source = concat!(
+        "(defun area (w h)\n",
+        "  (declare (type number w h))\n",
+        "  (the number (* w h)))\n",
+        "(area 2 \"3\")\n",
+        "(defun label (n) (string-split (area n 1)))\n",
+        "(let ((name 5)) (declare (type string name)) (+ name 1))\n",
+        "(the integer (quote sym))\n",
+        "(+ 1 (the number x) (length y) unknown)\n",
+    );
+    let diagnostics = check_source("types.lisp", source, &CheckConfig::default());
+    let found: Vec<(usize, usize, &str)> = diagnostics
+        .iter()
+        .map(|d| (d.line, d.column, d.message.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                4,
+                1,
+                "argument 2 of area should be of type number, but \"3\" is of type string"
+            ),
+            (
+                5,
+                18,
+                "argument 1 of string-split should be of type string, but (area n 1) is of type number"
+            ),
+            (
+                6,
+                1,
+                "let name should be of type string, but 5 is of type integer"
+            ),
+            (
+                6,
+                46,
+                "argument 1 of + should be of type number, but name is of type string"
+            ),
+            (
+                7,
+                1,
+                "the should be of type integer, but (quote sym) is of type symbol"
+            ),
+        ]
+    );
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.rule == "type-mismatch" && d.severity == Severity::Warning));
+    assert_eq!(
+        (diagnostics[0].end_line, diagnostics[0].end_column),
+        (4, 13)
+    );
+
+    let strict = CheckConfig::parse("[check]\nstrict-types = true\n").unwrap();
+    let diagnostics = check_source("types.lisp", "(+ 1 \"2\")\n", &strict);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert!(has_errors(&diagnostics));
+    assert!(CheckConfig::parse("[check]\nstrict = true\n").is_err());
+}
+
+#[test]
+fn test_check_unknown_type_names() {
+    let source = concat!(
+        "(defstruct point x y)\n",
+        "(the foo 1)\n",
+        "(defun norm (p) (declare (type point p) (type vec3 q)) (the number (point-x p)))\n",
+        "(the point (make-point 1 2))\n",
+    );
+    let diagnostics = check_source("types.lisp", source, &CheckConfig::default());
+    let found: Vec<(usize, usize, &str, &str)> = diagnostics
+        .iter()
+        .map(|d| (d.line, d.column, d.rule, d.message.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (2, 1, "unknown-type", "unknown type foo"),
+            (3, 17, "unknown-type", "unknown type vec3"),
+        ]
+    );
+}
@@ -0,0 +1,139 @@
+use crate::interpreter::completion::{complete, Completion};
+use crate::interpreter::line_editor::{edit_line, History, LineEvent, MAX_HISTORY};
+use crate::interpreter::*;
+use std::fs;
+
+fn candidates(line: &str) -> Option<Vec<String>> {
+    complete(line, line.len()).map(|completion| completion.candidates)
+}
+
+#[test]
+fn test_keywords_complete_from_those_read() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str("(list :completion-alpha :completion-beta :other)")
+        .unwrap();
+
+    assert_eq!(
+        candidates("(list 1 :completion-"),
+        Some(vec![
+            ":completion-alpha".to_string(),
+            ":completion-beta".to_string()
+        ])
+    );
+    assert_eq!(
+        complete("(f :completion-a)", 16),
+        Some(Completion {
+            start: 3,
+            candidates: vec![":completion-alpha".to_string()]
+        })
+    );
+    // Symbols, comments and keywords inside strings don't complete
+    assert_eq!(candidates("(list completion-"), None);
+    assert_eq!(candidates("(list 1) ; :completion-"), None);
+    assert_eq!(candidates("(print \":completion-"), None);
+}
+
+#[test]
+fn test_paths_complete_inside_strings() {
+    let dir = std::env::temp_dir().join(format!("zeus_completion_{}", std::process::id()));
+    fs::create_dir_all(dir.join("scripts")).unwrap();
+    fs::write(dir.join("sample.lisp"), "").unwrap();
+    let base = format!("{}/", dir.display());
+
+    // Any string that starts like a path
+    let line = format!("(define f \"{}sa", base);
+    let completion = complete(&line, line.len()).unwrap();
+    assert_eq!(completion.start, "(define f \"".len());
+    assert_eq!(completion.candidates, vec![format!("{}sample.lisp", base)]);
+    let (edited, cursor) = completion.extend(&line, line.len()).unwrap();
+    assert_eq!(edited, format!("(define f \"{}sample.lisp", base));
+    assert_eq!(cursor, edited.len());
+
+    // Two candidates sharing nothing more are listed by name
+    let line = format!("(run-process \"{}s", base);
+    let completion = complete(&line, line.len()).unwrap();
+    assert_eq!(completion.extend(&line, line.len()), None);
+    assert_eq!(
+        completion.labels(&line, line.len()),
+        vec!["sample.lisp".to_string(), "scripts/".to_string()]
+    );
+
+    // Other strings only complete as arguments to functions that take paths
+    assert_eq!(candidates("(print \"sa"), None);
+    assert!(candidates("(run-process \"sa").is_some());
+    assert!(candidates("(with-open-file (f \"sa").is_some());
+    assert_eq!(candidates("(with-open-file (f \"x\") (print \"sa"), None);
+    // A closed string or a character literal doesn't leave the cursor in a string
+    assert_eq!(candidates(&format!("(list \"{}\" ", base)), None);
+    assert_eq!(candidates("(list #\\\" :zz-no-such"), Some(vec![]));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_line_editor_keys() {
+    let read = |keys: &[u8]| {
+        let mut output = Vec::new();
+        let event = edit_line(&mut &keys[..], &mut output, "> ", &History::new()).unwrap();
+        (event, String::from_utf8(output).unwrap())
+    };
+
+    assert_eq!(read(b"(+ 1 2)\r").0, LineEvent::Line("(+ 1 2)".to_string()));
+    // Backspace, then moving left to insert before the cursor
+    assert_eq!(
+        read(b"(+ 1 3\x7f2)\x1b[D\x1b[D0 \r").0,
+        LineEvent::Line("(+ 1 0 2)".to_string())
+    );
+    // Home, then Delete
+    assert_eq!(
+        read(b"xlist\x1b[H\x1b[3~\n").0,
+        LineEvent::Line("list".to_string())
+    );
+    assert_eq!(
+        read("(λ)\r".as_bytes()).0,
+        LineEvent::Line("(λ)".to_string())
+    );
+    assert_eq!(read(b"\x04").0, LineEvent::Eof);
+    assert_eq!(read(b"").0, LineEvent::Eof);
+    assert_eq!(read(b"(list\x03").0, LineEvent::Interrupt);
+
+    // Tab completes in place
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str(":line-editor-keyword").unwrap();
+    assert_eq!(
+        read(b"(list :line-editor-k\t)\r").0,
+        LineEvent::Line("(list :line-editor-keyword)".to_string())
+    );
+    let (event, output) = read(b"(list :no-such-keyword-here\t\r");
+    assert_eq!(
+        event,
+        LineEvent::Line("(list :no-such-keyword-here".to_string())
+    );
+    assert!(output.contains('\x07'));
+}
+
+#[test]
+fn test_line_editor_history() {
+    let mut history = History::new();
+    for line in ["(define a 1)", "", "(+ a 1)", "(+ a 1)", "(list a)"] {
+        history.add(line);
+    }
+    assert_eq!(history.lines(), ["(define a 1)", "(+ a 1)", "(list a)"]);
+
+    let read = |keys: &[u8]| edit_line(&mut &keys[..], &mut Vec::new(), "> ", &history).unwrap();
+    let line = |text: &str| LineEvent::Line(text.to_string());
+    assert_eq!(read(b"\x1b[A\r"), line("(list a)"));
+    assert_eq!(read(b"\x1b[A\x1b[A\x1b[A\x1b[A\r"), line("(define a 1)"));
+    // Down past the newest line brings back what was being typed
+    assert_eq!(read(b"(car\x1b[A\x1b[A\x1b[B\x1b[B\r"), line("(car"));
+    // A recalled line can be edited before it's entered
+    assert_eq!(read(b"\x1b[A\x7f b)\r"), line("(list a b)"));
+
+    let mut long = History::new();
+    for i in 0..MAX_HISTORY + 3 {
+        long.add(&i.to_string());
+    }
+    assert_eq!(long.lines().len(), MAX_HISTORY);
+    assert_eq!(long.lines()[0], "3");
+}
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod data_types_tests {
     use crate::interpreter::evaluator::Evaluator;
+    use crate::interpreter::printer::display_string;
 
     #[test]
     fn test_integers() {
@@ -116,6 +117,48 @@ mod data_types_tests {
         );
     }
 
+    #[test]
+    fn test_comparison_chains() {
+        let mut evaluator = Evaluator::new();
+        let cases = [
+            ("(char< #\\a #\\b #\\c)", true),
+            ("(char< #\\a #\\c #\\b)", false),
+            ("(char= #\\x #\\x #\\x)", true),
+            ("(char> #\\c #\\b #\\b)", false),
+            ("(char>= #\\c #\\b #\\b)", true),
+            ("(char<= #\\a #\\a #\\b)", true),
+            ("(string< \"apple\" \"banana\" \"cherry\")", true),
+            ("(string< \"ab\" \"abc\")", true),
+            ("(string< \"abc\" \"abc\")", false),
+            ("(string<= \"abc\" \"abc\" \"abd\")", true),
+            ("(string> \"b\" \"a\" \"B\")", true),
+            ("(string>= \"a\" \"b\")", false),
+            ("(string= \"x\" \"x\" \"y\")", false),
+            ("(string-ci= \"Hello\" \"HELLO\" \"hello\")", true),
+            ("(string-ci= \"Hello\" \"help\")", false),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(
+                evaluator.eval_str(source).unwrap(),
+                Evaluator::bool_to_expr(expected),
+                "{}",
+                source
+            );
+        }
+
+        assert_eq!(
+            evaluator.eval_str("(char< #\\a)").unwrap_err().to_string(),
+            "char< requires at least 2 arguments"
+        );
+        assert_eq!(
+            evaluator
+                .eval_str("(string< \"a\" #\\b)")
+                .unwrap_err()
+                .to_string(),
+            "string< requires string arguments"
+        );
+    }
+
     #[test]
     fn test_vectors() {
         let mut evaluator = Evaluator::new();
@@ -128,6 +171,10 @@ mod data_types_tests {
         assert!(evaluator.eval_str("(vector 1 2 3)").is_ok());
         assert!(evaluator.eval_str("(make-vector 5)").is_ok());
         assert!(evaluator.eval_str("(make-vector 3 \"hello\")").is_ok());
+        assert!(evaluator
+            .eval_str("(make-vector 9223372036854775807)")
+            .unwrap_err()
+            .contains("over the limit"));
 
         // Vector operations
         let setup = "(define v [10 20 30])";
@@ -164,6 +211,546 @@ mod data_types_tests {
         );
     }
 
+    #[test]
+    fn test_vector_library() {
+        let mut evaluator = Evaluator::new();
+
+        evaluator.eval_str("(define v (vector 3 1 2))").unwrap();
+        evaluator.eval_str("(define alias v)").unwrap();
+
+        // Mutating operations update every reference to the vector
+        evaluator.eval_str("(vector-push v 0)").unwrap();
+        assert_eq!(
+            evaluator.eval_str("(vector-length alias)").unwrap(),
+            evaluator.eval_str("4").unwrap()
+        );
+        assert_eq!(
+            evaluator.eval_str("(vector-pop v)").unwrap(),
+            evaluator.eval_str("0").unwrap()
+        );
+        assert!(evaluator.eval_str("(vector-pop (vector))").is_err());
+
+        evaluator.eval_str("(vector-sort! v <)").unwrap();
+        assert_eq!(
+            evaluator.eval_str("(vector->list alias)").unwrap(),
+            evaluator.eval_str("(list 1 2 3)").unwrap()
+        );
+        assert_eq!(
+            evaluator.eval_str("(eq? v alias)").unwrap(),
+            Evaluator::bool_to_expr(true)
+        );
+
+        assert_eq!(
+            evaluator
+                .eval_str("(vector-map (lambda (x y) (* x y)) v [10 20])")
+                .unwrap(),
+            evaluator.eval_str("[10 40]").unwrap()
+        );
+        assert_eq!(
+            evaluator.eval_str("(subvector v 1)").unwrap(),
+            evaluator.eval_str("[2 3]").unwrap()
+        );
+        assert_eq!(
+            evaluator.eval_str("(subvector v 0 1)").unwrap(),
+            evaluator.eval_str("[1]").unwrap()
+        );
+        assert!(evaluator.eval_str("(subvector v 2 5)").is_err());
+        assert_eq!(
+            evaluator.eval_str("(list->vector (list 1 2))").unwrap(),
+            evaluator.eval_str("[1 2]").unwrap()
+        );
+
+        evaluator.eval_str("(vector-fill! v 7)").unwrap();
+        assert_eq!(
+            evaluator.eval_str("alias").unwrap(),
+            evaluator.eval_str("[7 7 7]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_vector_math() {
+        let mut evaluator = Evaluator::new();
+
+        let cases = [
+            (
+                "(vector-add [1 2 3] [10 20 30] [100 200 300])",
+                "[111 222 333]",
+            ),
+            ("(vector-add [1 2] [0.5 0.5])", "[1.5 2.5]"),
+            ("(vector-scale [1 2 3] 2)", "[2 4 6]"),
+            ("(vector-scale [1 2] 0.5)", "[0.5 1.0]"),
+            ("(vector-dot [1 2 3] [4 5 6])", "32"),
+            ("(vector-dot [1.5 2] [2 2])", "7.0"),
+            ("(vector-sum [1 2 3 4])", "10"),
+            ("(vector-sum [])", "0"),
+            ("(vector-sum (vector 1 (/ 1 2)))", "1.5"),
+            // Integer results that overflow i64 stay exact
+            (
+                "(vector-add [9223372036854775807 1] [1 1] [1 1])",
+                "(vector (+ 9223372036854775807 2) 3)",
+            ),
+            (
+                "(vector-sum [9223372036854775807 9223372036854775807])",
+                "(* 2 9223372036854775807)",
+            ),
+            (
+                "(vector-dot [4294967296 1] [4294967296 1])",
+                "(+ (* 4294967296 4294967296) 1)",
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                evaluator.eval_str(expr).unwrap(),
+                evaluator.eval_str(expected).unwrap(),
+                "{}",
+                expr
+            );
+        }
+
+        // The result is a new vector
+        evaluator.eval_str("(define v [1 2])").unwrap();
+        assert_eq!(
+            evaluator.eval_str("(eq? v (vector-add v))").unwrap(),
+            Evaluator::bool_to_expr(false)
+        );
+
+        for bad in [
+            "(vector-add [1 2] [1])",
+            "(vector-dot [1] [1 2])",
+            "(vector-sum [1 \"two\"])",
+            "(vector-sum (list 1 2))",
+            "(vector-scale [1] \"x\")",
+            "(vector-add)",
+        ] {
+            assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_matrices() {
+        let mut evaluator = Evaluator::new();
+
+        evaluator
+            .eval_str("(define a (list->matrix (list (list 1 2 3) (list 4 5 6))))")
+            .unwrap();
+        evaluator.eval_str("(define alias a)").unwrap();
+        assert_eq!(
+            display_string(&evaluator.eval_str("a").unwrap()),
+            "#<matrix 2x3 (1.0 2.0 3.0) (4.0 5.0 6.0)>"
+        );
+        assert_eq!(
+            evaluator
+                .eval_str("(list (matrix-rows a) (matrix-cols a))")
+                .unwrap(),
+            evaluator.eval_str("(list 2 3)").unwrap()
+        );
+
+        // matrix-set! updates every reference in place
+        evaluator.eval_str("(matrix-set! a 1 2 60)").unwrap();
+        assert_eq!(
+            evaluator.eval_str("(matrix-ref alias 1 2)").unwrap(),
+            evaluator.eval_str("60.0").unwrap()
+        );
+
+        let cases = [
+            (
+                "(matrix->list (matrix-transpose a))",
+                "(list (list 1.0 4.0) (list 2.0 5.0) (list 3.0 60.0))",
+            ),
+            (
+                "(matrix->list (matrix-multiply a (matrix-transpose a)))",
+                "(list (list 14.0 194.0) (list 194.0 3641.0))",
+            ),
+            (
+                "(matrix->list (make-matrix 2 2 7))",
+                "(list (list 7.0 7.0) (list 7.0 7.0))",
+            ),
+            ("(matrix->list (make-matrix 0 3))", "(list)"),
+            ("(equal? a (list->matrix (matrix->list a)))", "t"),
+            ("(eq? a (list->matrix (matrix->list a)))", "()"),
+            ("(eq? a alias)", "t"),
+            ("(matrixp a)", "t"),
+            ("(matrixp [1 2])", "()"),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                evaluator.eval_str(expr).unwrap(),
+                evaluator.eval_str(expected).unwrap(),
+                "{}",
+                expr
+            );
+        }
+
+        assert_eq!(
+            evaluator.eval_str("(matrix-multiply a a)").unwrap_err(),
+            "matrix-multiply: cannot multiply a 2x3 matrix by a 2x3 matrix"
+        );
+        for bad in [
+            "(matrix-ref a 2 0)",
+            "(matrix-set! a 0 3 1)",
+            "(matrix-set! a 0 0 \"x\")",
+            "(list->matrix (list (list 1 2) (list 3)))",
+            "(make-matrix -1 2)",
+            "(make-matrix 100000 100000)",
+            "(matrix-rows [1 2])",
+        ] {
+            assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_queues_and_stacks() {
+        let mut evaluator = Evaluator::new();
+
+        evaluator.eval_str("(define q (make-queue 1 2))").unwrap();
+        evaluator.eval_str("(define s (make-stack 1 2))").unwrap();
+        evaluator.eval_str("(define alias q)").unwrap();
+        evaluator.eval_str("(enqueue (enqueue q 3) 4)").unwrap();
+        evaluator.eval_str("(push! s 3)").unwrap();
+        assert_eq!(
+            display_string(&evaluator.eval_str("q").unwrap()),
+            "#<queue 1 2 3 4>"
+        );
+        // A stack prints from the top down
+        assert_eq!(
+            display_string(&evaluator.eval_str("s").unwrap()),
+            "#<stack 3 2 1>"
+        );
+        assert_eq!(
+            display_string(&evaluator.eval_str("(make-queue)").unwrap()),
+            "#<queue>"
+        );
+
+        let cases = [
+            (
+                "(list (dequeue q) (dequeue alias) (queue-front q))",
+                "(list 1 2 3)",
+            ),
+            ("(list (pop! s) (stack-top s) (length s))", "(list 3 2 2)"),
+            ("(length q)", "2"),
+            ("(equal? q (make-queue 3 4))", "t"),
+            ("(equal? q (make-stack 3 4))", "()"),
+            ("(eq? q (make-queue 3 4))", "()"),
+            ("(eq? q alias)", "t"),
+            (
+                "(list (queuep q) (queuep s) (stackp s) (stackp (list)))",
+                "(list t () t ())",
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                evaluator.eval_str(expr).unwrap(),
+                evaluator.eval_str(expected).unwrap(),
+                "{}",
+                expr
+            );
+        }
+
+        // FIFO and LIFO order through a longer run
+        assert_eq!(
+            evaluator
+                .eval_str(
+                    "(progn
+                       (define fifo (make-queue))
+                       (define lifo (make-stack))
+                       (define i 0)
+                       (define mismatches 0)
+                       (while (< i 1000) (enqueue fifo i) (push! lifo i) (define i (+ i 1)))
+                       (while (> i 0)
+                         (define i (- i 1))
+                         (unless (= (dequeue fifo) (- 999 (pop! lifo)))
+                           (define mismatches (+ mismatches 1))))
+                       (list mismatches (length fifo) (length lifo)))"
+                )
+                .unwrap(),
+            evaluator.eval_str("(list 0 0 0)").unwrap()
+        );
+
+        assert_eq!(
+            evaluator.eval_str("(dequeue (make-queue))").unwrap_err(),
+            "dequeue: queue is empty"
+        );
+        assert_eq!(
+            evaluator.eval_str("(pop! (make-stack))").unwrap_err(),
+            "pop!: stack is empty"
+        );
+        for bad in [
+            "(enqueue s 1)",
+            "(push! q 1)",
+            "(queue-front (make-queue))",
+            "(stack-top (make-stack))",
+        ] {
+            assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_heaps() {
+        let mut evaluator = Evaluator::new();
+
+        evaluator
+            .eval_str("(define h (make-heap < 5 3 8 1))")
+            .unwrap();
+        evaluator
+            .eval_str("(heap-push! (heap-push! h 0) 7)")
+            .unwrap();
+        assert_eq!(
+            display_string(&evaluator.eval_str("h").unwrap()),
+            "#<heap 6>"
+        );
+        let cases = [
+            ("(heap-peek h)", "0"),
+            ("(length h)", "6"),
+            (
+                "(list (heap-pop! h) (heap-pop! h) (heap-pop! h) (heap-pop! h) (heap-pop! h) (heap-pop! h))",
+                "(list 0 1 3 5 7 8)",
+            ),
+            ("(length h)", "0"),
+            ("(list (heapp h) (heapp (make-queue)))", "(list t ())"),
+            ("(equal? h (make-heap <))", "()"),
+            ("(eq? h h)", "t"),
+            // Any two-argument function orders the heap, here the highest priority first
+            (
+                "(let ((tasks (make-heap (lambda (a b) (> (car a) (car b))))))
+                   (heap-push! tasks (cons 1 :low))
+                   (heap-push! tasks (cons 9 :urgent))
+                   (heap-push! tasks (cons 5 :normal))
+                   (list (cdr (heap-pop! tasks)) (cdr (heap-pop! tasks))))",
+                "(list :urgent :normal)",
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                evaluator.eval_str(expr).unwrap(),
+                evaluator.eval_str(expected).unwrap(),
+                "{}",
+                expr
+            );
+        }
+
+        // Draining a heap sorts, including duplicates
+        evaluator
+            .eval_str(
+                "(progn
+                   (define sorted (make-heap <))
+                   (define n 0)
+                   (define inputs (list))
+                   (while (< n 300)
+                     (define value (* (- n 150) (- n 97)))
+                     (define inputs (cons value inputs))
+                     (heap-push! sorted value)
+                     (define n (+ n 1)))
+                   (define drained (list))
+                   (while (> (length sorted) 0)
+                     (define drained (cons (heap-pop! sorted) drained))))",
+            )
+            .unwrap();
+        assert_eq!(
+            evaluator.eval_str("(reverse drained)").unwrap(),
+            evaluator.eval_str("(sort inputs <)").unwrap()
+        );
+
+        // A comparison that fails leaves the heap as it was
+        evaluator
+            .eval_str("(define strict (make-heap < 2 1))")
+            .unwrap();
+        assert!(evaluator.eval_str("(heap-push! strict \"x\")").is_err());
+        assert_eq!(
+            evaluator
+                .eval_str("(list (length strict) (heap-pop! strict) (heap-pop! strict))")
+                .unwrap(),
+            evaluator.eval_str("(list 2 1 2)").unwrap()
+        );
+
+        assert_eq!(
+            evaluator.eval_str("(heap-pop! (make-heap <))").unwrap_err(),
+            "heap-pop!: heap is empty"
+        );
+        for bad in [
+            "(make-heap)",
+            "(make-heap 5)",
+            "(heap-peek (make-heap >))",
+            "(heap-push! (make-stack) 1)",
+        ] {
+            assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut evaluator = Evaluator::new();
+
+        evaluator
+            .eval_str("(define it (iterate (list 1 2 3)))")
+            .unwrap();
+        assert_eq!(
+            evaluator
+                .eval_str("(list (next it) (iter-done? it) (next it) (next it) (iter-done? it))")
+                .unwrap(),
+            evaluator.eval_str("(list 1 () 2 3 t)").unwrap()
+        );
+        assert_eq!(
+            evaluator.eval_str("(next it)").unwrap_err(),
+            "next: iterator is exhausted"
+        );
+        assert_eq!(
+            display_string(&evaluator.eval_str("it").unwrap()),
+            "#<iterator done>"
+        );
+
+        let cases = [
+            ("(iter->list (range 5))", "(list 0 1 2 3 4)"),
+            ("(iter->list (range 0 10 3))", "(list 0 3 6 9)"),
+            ("(iter->list (range 5 0 -2))", "(list 5 3 1)"),
+            ("(iter->list (range 3 3))", "(list)"),
+            ("(iter->list (range 0 1 0.25))", "(list 0.0 0.25 0.5 0.75)"),
+            ("(iter->list \"abc\")", "(list #\\a #\\b #\\c)"),
+            ("(iter->list [1 2])", "(list 1 2)"),
+            (
+                "(iter->list (hash-set! (hash-set! (make-hash-table) :a 1) :b 2))",
+                "(list (cons :a 1) (cons :b 2))",
+            ),
+            // An iterator iterates over itself, from where it has got to
+            (
+                "(let ((r (range 4))) (next r) (list (eq? (iterate r) r) (iter->list r)))",
+                "(list t (list 1 2 3))",
+            ),
+            (
+                "(list (iteratorp (range 1)) (iteratorp (list)))",
+                "(list t ())",
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                evaluator.eval_str(expr).unwrap(),
+                evaluator.eval_str(expected).unwrap(),
+                "{}",
+                expr
+            );
+        }
+
+        // Ranges are lazy, so a huge one costs nothing until it is consumed
+        assert_eq!(
+            evaluator
+                .eval_str("(let ((r (range 0 1000000000000))) (next r) (next r))")
+                .unwrap(),
+            evaluator.eval_str("1").unwrap()
+        );
+
+        // A vector is read live, so items pushed while iterating are reached
+        evaluator
+            .eval_str(
+                "(progn
+                   (define v [1])
+                   (define vi (iterate v))
+                   (define seen (list))
+                   (while (if (iter-done? vi) () t)
+                     (define x (next vi))
+                     (define seen (cons x seen))
+                     (when (< x 3) (vector-push v (+ x 1)))))",
+            )
+            .unwrap();
+        assert_eq!(
+            evaluator.eval_str("(reverse seen)").unwrap(),
+            evaluator.eval_str("(list 1 2 3)").unwrap()
+        );
+
+        for bad in [
+            "(range 0 5 0)",
+            "(range)",
+            "(range \"a\")",
+            "(iterate 5)",
+            "(next (list 1))",
+            "(iter-done? 1)",
+        ] {
+            assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_sequence_functions_take_any_collection() {
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .eval_str("(define table (hash-set! (hash-set! (make-hash-table) :a 1) :b 2))")
+            .unwrap();
+
+        let cases = [
+            ("(mapcar (lambda (x) (* x 2)) [1 2 3])", "(list 2 4 6)"),
+            ("(mapcar char->integer \"ab\")", "(list 97 98)"),
+            ("(mapcar cdr table)", "(list 1 2)"),
+            // Sequences of different kinds walk in step, up to the shortest
+            (
+                "(mapcar list (range 10) \"ab\")",
+                "(list (list 0 #\\a) (list 1 #\\b))",
+            ),
+            ("(filter (lambda (x) (> x 1)) [1 2 3])", "(list 2 3)"),
+            ("(remove (lambda (x) (> x 1)) (range 4))", "(list 0 1)"),
+            ("(reduce + [1 2 3])", "6"),
+            ("(reduce + (range 5) 10)", "20"),
+            ("(reduce + \"\" 0)", "0"),
+            ("(find-if (lambda (p) (> (cdr p) 1)) table)", "(cons :b 2)"),
+            ("(member-if (lambda (x) (> x 1)) [1 2 3])", "(list 2 3)"),
+            ("(member #\\b \"abc\")", "(list #\\b #\\c)"),
+            ("(foldl + 0 [1 2 3])", "6"),
+            ("(take 2 (range 1000000000000))", "(list 0 1)"),
+            (
+                "(transduce [1 2 3] :map (lambda (x) (* x x)) :reduce +)",
+                "14",
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                evaluator.eval_str(expr).unwrap(),
+                evaluator.eval_str(expected).unwrap(),
+                "{}",
+                expr
+            );
+        }
+
+        // find-if stops taking items from an iterator at the one it returns
+        evaluator.eval_str("(define it (range 10))").unwrap();
+        evaluator
+            .eval_str("(find-if (lambda (x) (> x 2)) it)")
+            .unwrap();
+        assert_eq!(
+            evaluator.eval_str("(next it)").unwrap(),
+            evaluator.eval_str("4").unwrap()
+        );
+
+        // The function can change the vector it is mapping over without affecting the walk
+        evaluator.eval_str("(define v [1 2])").unwrap();
+        assert_eq!(
+            evaluator
+                .eval_str("(mapcar (lambda (x) (progn (vector-push v x) x)) v)")
+                .unwrap(),
+            evaluator.eval_str("(list 1 2)").unwrap()
+        );
+
+        assert_eq!(
+            evaluator.eval_str("(mapcar car 5)").unwrap_err(),
+            "mapcar requires list, vector, string, hash table or iterator arguments"
+        );
+        assert_eq!(
+            evaluator.eval_str("(filter car 5)").unwrap_err(),
+            "filter requires a list, vector, string, hash table or iterator as second argument"
+        );
+    }
+
+    #[test]
+    fn test_vector_literals_are_fresh() {
+        let mut evaluator = Evaluator::new();
+
+        // A literal in a function body must not accumulate pushes across calls
+        evaluator
+            .eval_str("(defun collect (x) (vector-push [] x))")
+            .unwrap();
+        evaluator.eval_str("(collect 1)").unwrap();
+        assert_eq!(
+            evaluator.eval_str("(collect 2)").unwrap(),
+            evaluator.eval_str("[2]").unwrap()
+        );
+    }
+
     #[test]
     fn test_hash_tables() {
         let mut evaluator = Evaluator::new();
@@ -320,6 +907,159 @@ mod data_types_tests {
         assert!(evaluator.eval_str("(car (hash-ref h2 \"list\"))").is_ok());
     }
 
+    #[test]
+    fn test_hash_table_mutation_and_iteration() {
+        let mut evaluator = Evaluator::new();
+
+        // hash-set! updates the table in place, so aliases see the change
+        evaluator.eval_str("(define h (make-hash-table))").unwrap();
+        evaluator.eval_str("(define alias h)").unwrap();
+        evaluator.eval_str("(hash-set! h :a 1)").unwrap();
+        evaluator.eval_str("(hash-set! h :b 2)").unwrap();
+        assert_eq!(
+            evaluator.eval_str("(hash-ref alias :b)").unwrap(),
+            evaluator.eval_str("2").unwrap()
+        );
+        assert_eq!(
+            evaluator.eval_str("(hash-count h)").unwrap(),
+            evaluator.eval_str("2").unwrap()
+        );
+        assert_eq!(
+            evaluator.eval_str("(apply + (hash-values h))").unwrap(),
+            evaluator.eval_str("3").unwrap()
+        );
+
+        // hash-update! applies f to the current value, or to the default
+        assert_eq!(
+            evaluator
+                .eval_str("(hash-update! h :a (lambda (n) (+ n 10)))")
+                .unwrap(),
+            evaluator.eval_str("11").unwrap()
+        );
+        evaluator
+            .eval_str("(hash-update! h :c (lambda (n) (+ n 1)) 0)")
+            .unwrap();
+        assert_eq!(
+            evaluator.eval_str("(hash-ref alias :c)").unwrap(),
+            evaluator.eval_str("1").unwrap()
+        );
+        assert!(evaluator
+            .eval_str("(hash-update! h :missing (lambda (n) n))")
+            .is_err());
+
+        // hash-map and hash-for-each receive the key and the value
+        assert_eq!(
+            evaluator
+                .eval_str("(sort (hash-map h (lambda (k v) v)) <)")
+                .unwrap(),
+            evaluator.eval_str("(list 1 2 11)").unwrap()
+        );
+        evaluator
+            .eval_str("(define copy (make-hash-table))")
+            .unwrap();
+        assert_eq!(
+            evaluator
+                .eval_str("(hash-for-each h (lambda (k v) (hash-set! copy k v)))")
+                .unwrap(),
+            evaluator.eval_str("nil").unwrap()
+        );
+        assert_eq!(
+            evaluator.eval_str("(equal? copy h)").unwrap(),
+            evaluator.eval_str("t").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_table_printing() {
+        let mut evaluator = Evaluator::new();
+
+        let small = evaluator
+            .eval_str("(alist->hash (list (cons 2 20) (cons 1 10)))")
+            .unwrap();
+        assert_eq!(display_string(&small), "#<hash-table 2 20 1 10>");
+
+        evaluator
+            .eval_str("(define big (make-hash-table))")
+            .unwrap();
+        for i in 0..15 {
+            evaluator
+                .eval_str(&format!("(hash-set! big {} {})", i + 10, i))
+                .unwrap();
+        }
+        let big = evaluator.eval_str("big").unwrap();
+        let printed = display_string(&big);
+        assert!(printed.starts_with("#<hash-table 10 0 11 1 "));
+        assert!(printed.ends_with(" ...5 more>"));
+    }
+
+    #[test]
+    fn test_structs() {
+        let mut evaluator = Evaluator::new();
+
+        assert_eq!(
+            evaluator.eval_str("(defstruct point x y)").unwrap(),
+            evaluator.eval_str("(quote point)").unwrap()
+        );
+        evaluator.eval_str("(define p (make-point 1 2))").unwrap();
+        assert_eq!(
+            display_string(&evaluator.eval_str("p").unwrap()),
+            "#<point x: 1 y: 2>"
+        );
+
+        let cases = [
+            ("(list (point-x p) (point-y p))", "(list 1 2)"),
+            (
+                "(list (point-p p) (point-p 5) (structp p))",
+                "(list t () t)",
+            ),
+            ("(struct-type p)", "(quote point)"),
+            ("(struct-type [1])", "()"),
+            ("(struct-fields p)", "(quote (x y))"),
+            ("(struct-ref p (quote y))", "2"),
+            // Setters update every reference in place and return the new value
+            (
+                "(let ((q p)) (list (set-point-x! q 10) (point-x p)))",
+                "(list 10 10)",
+            ),
+            ("(equal? (make-point 10 2) p)", "t"),
+            ("(eq? (make-point 10 2) p)", "()"),
+            // Fields are compared by name, so a different type with the same shape isn't equal
+            (
+                "(equal? (make-struct (quote pair) (quote (x y)) 10 2) p)",
+                "()",
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                evaluator.eval_str(expr).unwrap(),
+                evaluator.eval_str(expected).unwrap(),
+                "{}",
+                expr
+            );
+        }
+
+        // Accessors check the type they were defined for
+        evaluator.eval_str("(defstruct circle radius)").unwrap();
+        assert_eq!(
+            evaluator.eval_str("(point-x (make-circle 1))").unwrap_err(),
+            "struct-ref: expected a point, got a circle"
+        );
+        assert_eq!(
+            evaluator.eval_str("(point-x 5)").unwrap_err(),
+            "struct-ref requires a struct, got 5"
+        );
+
+        for bad in [
+            "(make-point 1)",
+            "(struct-ref p (quote z))",
+            "(defstruct bad x x)",
+            "(defstruct \"bad\" x)",
+            "(make-struct (quote bad) (quote (x)))",
+        ] {
+            assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+        }
+    }
+
     #[test]
     fn test_type_conversion() {
         let mut evaluator = Evaluator::new();
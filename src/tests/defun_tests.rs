@@ -1,4 +1,4 @@
-use crate::interpreter::types::SymbolData;
+use crate::interpreter::types::{Symbol, SymbolData};
 use crate::interpreter::*;
 
 #[test]
@@ -7,7 +7,7 @@ fn test_defun_basic() {
     let result = evaluator.eval_str("(defun square (x) (* x x))").unwrap();
     assert_eq!(
         result,
-        Expr::Symbol(SymbolData::Interned("square".to_string()))
+        Expr::Symbol(SymbolData::Interned(Symbol::intern("square")))
     );
 
     let result = evaluator.eval_str("(square 5)").unwrap();
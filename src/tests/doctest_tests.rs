@@ -0,0 +1,98 @@
+use crate::interpreter::doctest::{extract_examples, run_doctests, DocOutcome};
+
+const SOURCE: &str = r#"; Helpers
+(defun square (x)
+  "The square of x.
+   (square 3) => 9
+   (square -2) => 4"
+  (* x x))
+
+(defun pair (a b)
+  "Both, in a list: (pair 1 2) => (1 2)"
+  (list a b))
+
+(defun shout (s)
+  "Not an example: => on its own, or x => y"
+  (println s)
+  s)
+
+(defun bare (x) "Only a body, so no docstring: (bare 1) => 1")
+"#;
+
+#[test]
+fn test_extracts_examples_with_their_lines() {
+    let examples = extract_examples(SOURCE);
+    let found: Vec<(&str, usize, &str, &str)> = examples
+        .iter()
+        .map(|example| {
+            (
+                example.function.as_str(),
+                example.line,
+                example.source.as_str(),
+                example.expected.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("square", 4, "(square 3)", "9"),
+            ("square", 5, "(square -2)", "4"),
+        ]
+    );
+}
+
+#[test]
+fn test_runs_examples_against_the_loaded_file() {
+    let source = format!(
+        "{}\n{}",
+        SOURCE,
+        r#"(defun wrong (x)
+  "(wrong 1) => 2
+   (wrong 2) => 2
+   (wrong (car 5)) => 1
+   (wrong 1) => (1"
+  (println "loud")
+  x)"#
+    );
+    let report = run_doctests("lib.lisp", &source);
+    assert_eq!(report.load_error, None);
+    let outcomes: Vec<&DocOutcome> = report.results.iter().map(|(_, outcome)| outcome).collect();
+    assert_eq!(
+        outcomes[..3],
+        [
+            &DocOutcome::Passed,
+            &DocOutcome::Passed,
+            &DocOutcome::Failed("1".to_string())
+        ]
+    );
+    assert_eq!(outcomes[3], &DocOutcome::Passed);
+    assert!(matches!(outcomes[4], DocOutcome::Error(_)));
+    assert!(matches!(outcomes[5], DocOutcome::Error(_)));
+    assert!(!report.succeeded());
+    assert_eq!(report.failures(), 3);
+
+    let summary = report.summary();
+    assert!(
+        summary.contains("lib.lisp:20: (wrong 1) => 2 (wrong): got 1\n"),
+        "{}",
+        summary
+    );
+    assert!(
+        summary.ends_with("lib.lisp: 6 examples, 3 passed, 3 failed\n"),
+        "{}",
+        summary
+    );
+}
+
+#[test]
+fn test_a_file_that_fails_to_load_runs_no_examples() {
+    let report = run_doctests("bad.lisp", "(defun f (x) \"(f 1) => 1\" x)\n(car 5)\n");
+    assert!(report.results.is_empty());
+    assert!(!report.succeeded());
+    assert!(
+        report.summary().starts_with("bad.lisp: line 2: "),
+        "{}",
+        report.summary()
+    );
+}
@@ -0,0 +1,184 @@
+use crate::interpreter::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+// An evaluator with a global `trail` vector that the programs below push onto, so the
+// order cleanups ran in can be checked from outside
+fn tracing_evaluator() -> Evaluator {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define trail (vector))").unwrap();
+    evaluator
+}
+
+fn trail(evaluator: &mut Evaluator) -> String {
+    match evaluator.eval_str("(format nil \"~a\" trail)").unwrap() {
+        Expr::String(text) => text,
+        other => panic!("Expected string, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_nested_cleanups_run_innermost_first_on_throw() {
+    let mut evaluator = tracing_evaluator();
+    let result = evaluator
+        .eval_str(
+            "(catch (quote out)
+               (unwind-protect
+                 (let ((x 1))
+                   (unwind-protect
+                     (let ((y 2))
+                       (unwind-protect
+                         (throw (quote out) (+ x y))
+                         (vector-push trail 3)))
+                     (vector-push trail 2)))
+                 (vector-push trail 1)))",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::Integer(3));
+    assert_eq!(trail(&mut evaluator), "[3 2 1]");
+}
+
+#[test]
+fn test_dynamic_wind_pairs_before_and_after_across_return_from() {
+    let mut evaluator = tracing_evaluator();
+    evaluator
+        .eval_str(
+            "(defun wind (name thunk)
+               (dynamic-wind
+                 (lambda () (vector-push trail (list (quote in) name)))
+                 thunk
+                 (lambda () (vector-push trail (list (quote out) name)))))",
+        )
+        .unwrap();
+    let result = evaluator
+        .eval_str(
+            "(block done
+               (wind 1 (lambda ()
+                 (wind 2 (lambda ()
+                   (progn (return-from done 42) (vector-push trail (quote unreached))))))))",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::Integer(42));
+    assert_eq!(trail(&mut evaluator), "[(in 1) (in 2) (out 2) (out 1)]");
+
+    // A normal exit returns the thunk's value
+    assert_eq!(
+        evaluator
+            .eval_str("(dynamic-wind (lambda () 0) (lambda () 7) (lambda () 8))")
+            .unwrap(),
+        Expr::Integer(7)
+    );
+}
+
+#[test]
+fn test_failing_before_skips_thunk_and_after() {
+    let mut evaluator = tracing_evaluator();
+    let err = evaluator
+        .eval_str(
+            "(dynamic-wind
+               (lambda () (car 1))
+               (lambda () (vector-push trail (quote thunk)))
+               (lambda () (vector-push trail (quote after))))",
+        )
+        .unwrap_err();
+    assert!(!err.is_empty());
+    assert_eq!(trail(&mut evaluator), "[]");
+
+    let err = Evaluator::eval_once("(dynamic-wind (lambda () 1) 2 (lambda () 3))").unwrap_err();
+    assert!(err.contains("2 is not a function"), "{}", err);
+    let err = Evaluator::eval_once("(dynamic-wind (lambda () 1))").unwrap_err();
+    assert!(err.contains("exactly 3 arguments"), "{}", err);
+}
+
+#[test]
+fn test_error_in_cleanup_replaces_throw() {
+    let mut evaluator = tracing_evaluator();
+    let err = evaluator
+        .eval_str(
+            "(catch (quote out)
+               (unwind-protect
+                 (unwind-protect
+                   (throw (quote out) 1)
+                   (undefined-function-in-cleanup))
+                 (vector-push trail (quote outer))))",
+        )
+        .unwrap_err();
+    assert!(err.contains("undefined-function-in-cleanup"), "{}", err);
+    // The outer cleanup still ran on the way out of the new error
+    assert_eq!(trail(&mut evaluator), "[outer]");
+}
+
+#[test]
+fn test_exits_close_the_scopes_they_leave() {
+    let mut evaluator = Evaluator::new();
+    // Throwing out of a let* binding used to leave `a` bound after the catch
+    evaluator
+        .eval_str("(catch (quote out) (let* ((a 1) (b (throw (quote out) 0))) b))")
+        .unwrap();
+    assert!(evaluator.eval_str("a").is_err());
+    evaluator
+        .eval_str("(block done (let ((c 1) (d (return-from done 0))) d))")
+        .unwrap();
+    assert!(evaluator.eval_str("c").is_err());
+
+    // An error escaping to the top level leaves no scopes behind either, so a later
+    // define is global again
+    assert!(evaluator.eval_str("(let* ((e 1) (f (car 1))) f)").is_err());
+    assert!(evaluator.eval_str("e").is_err());
+    evaluator.eval_str("(define g 5)").unwrap();
+    assert_eq!(
+        evaluator.environment.take_changed_globals(),
+        vec!["g".to_string()]
+    );
+
+    // Each go back to the label closes the let* it jumped out of
+    let result = evaluator
+        .eval_str(
+            "(block done
+               (let ((count (vector 0)))
+                 (tagbody
+                  top
+                   (let* ((n (+ (vector-ref count 0) 1)))
+                     (vector-set! count 0 n)
+                     (if (< n 3) (go top) (return-from done n))))))",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::Integer(3));
+    assert_eq!(evaluator.environment.scope_depth(), 0);
+}
+
+#[test]
+fn test_cleanup_runs_when_interrupted() {
+    let mut evaluator = tracing_evaluator();
+    // Asks to stop once, the first time it's polled
+    let asked = Rc::new(Cell::new(false));
+    let poll_asked = Rc::clone(&asked);
+    evaluator.interrupt_poll = Some(Rc::new(move || !poll_asked.replace(true)));
+    let err = evaluator
+        .eval_str("(unwind-protect (while 1 1) (vector-push trail (quote cleaned)))")
+        .unwrap_err();
+    assert!(asked.get());
+    assert_eq!(err, "interrupted");
+    assert_eq!(trail(&mut evaluator), "[cleaned]");
+}
+
+#[test]
+fn test_cleanup_runs_when_out_of_budget() {
+    let mut evaluator = tracing_evaluator();
+    evaluator.budget.max_steps = Some(5_000);
+    let err = evaluator
+        .eval_source("(unwind-protect (while 1 1) (vector-push trail (quote cleaned)))")
+        .unwrap_err();
+    assert!(matches!(err, EvalError::Timeout(_)), "{}", err);
+    evaluator.budget.max_steps = None;
+    assert_eq!(trail(&mut evaluator), "[cleaned]");
+
+    evaluator.budget.timeout = Some(Duration::from_millis(20));
+    let err = evaluator
+        .eval_source("(unwind-protect (while 1 1) (vector-push trail (quote again)))")
+        .unwrap_err();
+    assert!(matches!(err, EvalError::Timeout(_)), "{}", err);
+    evaluator.budget.timeout = None;
+    assert_eq!(trail(&mut evaluator), "[cleaned again]");
+}
@@ -0,0 +1,95 @@
+use crate::interpreter::check::CheckConfig;
+use crate::interpreter::source_map::Position;
+use crate::interpreter::*;
+use crate::{eval_in, eval_str, Session, ZeusError};
+use std::fs;
+
+#[test]
+fn test_parse_errors_point_at_the_form() {
+    match eval_str("(define x 1)\n  (list 1 2") {
+        Err(ZeusError::Parse { line, column, .. }) => assert_eq!((line, column), (2, 3)),
+        other => panic!("Expected a parse error, got {:?}", other),
+    }
+
+    // A malformed token inside an otherwise balanced form, after earlier forms have run
+    let mut evaluator = Evaluator::new();
+    let err = eval_in(&mut evaluator, "(define ran 1)\n(list 1 #<oops>)").unwrap_err();
+    assert!(matches!(err, ZeusError::Parse { line: 2, .. }), "{:?}", err);
+    assert_eq!(evaluator.eval_str("ran").unwrap(), Expr::Integer(1));
+}
+
+#[test]
+fn test_eval_errors_keep_the_evaluation_error_and_form() {
+    let err = eval_str("(defun f (x) (car x))\n\n(f 5)").unwrap_err();
+    let ZeusError::Eval { error, trace } = &err else {
+        panic!("Expected an evaluation error, got {:?}", err);
+    };
+    assert!(matches!(error, EvalError::Message(_)));
+    assert_eq!(trace.len(), 1);
+    let form = &trace[0].form;
+    assert_eq!((form.line, form.source.as_str()), (3, "(f 5)"));
+    // The span is the innermost form that failed, here inside the function's body
+    assert_eq!(
+        trace[0].span.map(|span| (span.start, span.end)),
+        Some((Position::new(1, 14), Position::new(1, 21)))
+    );
+    assert_eq!(err.eval_error(), Some(error));
+    assert_eq!(err.to_string(), error.to_string());
+
+    // Control transfers that escape the program stay distinguishable
+    assert!(matches!(
+        eval_str("(exit 3)"),
+        Err(ZeusError::Eval {
+            error: EvalError::Exit(3),
+            ..
+        })
+    ));
+    let mut session = Session::new();
+    session.evaluator().budget.max_steps = Some(1000);
+    assert!(matches!(
+        session.eval("(loop (+ 1 2))"),
+        Err(ZeusError::Eval {
+            error: EvalError::Timeout(_),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_interrupts_are_their_own_kind() {
+    let mut session = Session::new();
+    session.evaluator().interrupt_poll = Some(std::rc::Rc::new(|| true));
+    let err = session.eval("(loop (+ 1 2))").unwrap_err();
+    assert!(matches!(err, ZeusError::Interrupt), "{:?}", err);
+    assert_eq!(err.to_string(), "interrupted");
+
+    // An error that merely says "interrupted" is still an ordinary evaluation error
+    let err = eval_str("(error \"interrupted\")").unwrap_err();
+    assert!(matches!(err, ZeusError::Eval { .. }), "{:?}", err);
+}
+
+#[test]
+fn test_io_and_config_errors_name_the_file() {
+    let missing = std::env::temp_dir().join("zeus_error_tests_missing.lisp");
+    match Session::new().load(&missing) {
+        Err(ZeusError::Io { path, .. }) => assert_eq!(path, missing),
+        other => panic!("Expected an I/O error, got {:?}", other),
+    }
+
+    let dir = std::env::temp_dir().join(format!("zeus_error_tests_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("zeus.toml"),
+        "[check.rules]\nno-such-rule = false\n",
+    )
+    .unwrap();
+    let result = CheckConfig::load(&dir);
+    fs::remove_dir_all(&dir).ok();
+    match result {
+        Err(ZeusError::Config { path, message }) => {
+            assert_eq!(path, dir.join("zeus.toml"));
+            assert!(message.contains("no-such-rule"), "{}", message);
+        }
+        other => panic!("Expected a config error, got {:?}", other),
+    }
+}
@@ -1,5 +1,6 @@
 use super::helpers::*;
-use crate::interpreter::types::SymbolData;
+use crate::interpreter::evaluator::with_eval_stack;
+use crate::interpreter::types::{Symbol, SymbolData};
 use crate::interpreter::*;
 
 #[test]
@@ -14,6 +15,24 @@ fn test_eval_string() {
     assert_eq!(result, Expr::String("hello".to_string()));
 }
 
+#[test]
+fn test_changed_globals_tracks_top_level_definitions() {
+    let mut evaluator = Evaluator::new();
+    assert!(evaluator.environment.take_changed_globals().is_empty());
+
+    evaluator.eval_str("(define y 1)").unwrap();
+    evaluator
+        .eval_str("(defun f (x) (define inner x) x)")
+        .unwrap();
+    evaluator.eval_str("(define y 2)").unwrap();
+    evaluator.eval_str("(f 3)").unwrap();
+    assert_eq!(
+        evaluator.environment.take_changed_globals(),
+        vec!["f".to_string(), "y".to_string()]
+    );
+    assert!(evaluator.environment.take_changed_globals().is_empty());
+}
+
 #[test]
 fn test_eval_define_and_use() {
     let mut evaluator = Evaluator::new();
@@ -37,8 +56,8 @@ fn test_eval_quote() {
     let result = Evaluator::eval_once("(quote (+ 1 2))").unwrap();
     assert_eq!(
         result,
-        Expr::List(vec![
-            Expr::Symbol(SymbolData::Interned("+".to_string())),
+        Expr::new_list(vec![
+            Expr::Symbol(SymbolData::Interned(Symbol::intern("+"))),
             Expr::Integer(1),
             Expr::Integer(2),
         ])
@@ -46,7 +65,10 @@ fn test_eval_quote() {
 
     // Test single-quote syntax
     let result = Evaluator::eval_once("(quote x)").unwrap();
-    assert_eq!(result, Expr::Symbol(SymbolData::Interned("x".to_string())));
+    assert_eq!(
+        result,
+        Expr::Symbol(SymbolData::Interned(Symbol::intern("x")))
+    );
 }
 
 #[test]
@@ -84,3 +106,276 @@ fn test_complex_expression() {
     let result = evaluator.eval_str("(+ (* x 2) y)").unwrap();
     assert_eq!(result, Expr::Integer(20));
 }
+
+#[test]
+fn test_runaway_recursion_reports_nesting_too_deep() {
+    with_main_thread_stack(|| {
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .eval_str("(defun down (n) (+ 1 (down n)))")
+            .unwrap();
+        let error = evaluator.eval_str("(down 0)").unwrap_err();
+        assert!(error.contains("nesting too deep"), "{}", error);
+
+        // The depth count unwinds with the error
+        evaluator.max_depth = 20;
+        assert!(evaluator.eval_str("(+ 1 (+ 2 (+ 3 4)))").is_ok());
+        evaluator
+            .eval_str("(defun count (n) (if (= n 0) 0 (+ 1 (count (- n 1)))))")
+            .unwrap();
+        assert!(evaluator.eval_str("(count 3)").is_ok());
+        assert!(evaluator.eval_str("(count 50)").is_err());
+    });
+}
+
+#[test]
+fn test_deep_recursion_on_the_eval_stack() {
+    with_eval_stack(|| {
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .eval_str("(defun count (n) (if (= n 0) 0 (+ 1 (count (- n 1)))))")
+            .unwrap();
+        assert_eq!(
+            evaluator.eval_str("(count 1000)").unwrap(),
+            Expr::Integer(1000)
+        );
+        assert_eq!(
+            evaluator.eval_str("(count 3000)").unwrap(),
+            Expr::Integer(3000)
+        );
+        evaluator
+            .eval_str("(defun fact (n) (if (= n 0) 1 (* n (fact (- n 1)))))")
+            .unwrap();
+        assert!(evaluator.eval_str("(fact 400)").is_ok());
+
+        // Past the depth limit it's still an error rather than an overflow
+        let error = evaluator.eval_str("(count 100000)").unwrap_err();
+        assert!(error.contains("nesting too deep (limit"), "{}", error);
+    });
+}
+
+#[test]
+fn test_equal_on_deep_and_cyclic_structures() {
+    with_main_thread_stack(|| {
+        let evaluator = Evaluator::new();
+        let mut deep = Expr::new_list(vec![]);
+        for _ in 0..12000 {
+            deep = Expr::new_list(vec![deep]);
+        }
+        let error = evaluator.expr_equal(&deep, &deep).unwrap_err();
+        assert!(error.to_string().contains("nesting too deep"));
+    });
+
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define v (vector 1 2))").unwrap();
+    evaluator.eval_str("(vector-set! v 1 v)").unwrap();
+    assert!(Evaluator::is_truthy(
+        &evaluator.eval_str("(equal? v v)").unwrap()
+    ));
+    assert!(Evaluator::is_truthy(
+        &evaluator.eval_str("(equal? v (vector 1 v))").unwrap()
+    ));
+}
+
+#[test]
+fn test_interrupting_a_runaway_loop() {
+    let mut evaluator = Evaluator::new();
+
+    // From another thread, as the terminal REPL's Ctrl+C handler does
+    let flag = evaluator.interrupt_handle();
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        flag.store(true, std::sync::atomic::Ordering::SeqCst);
+    });
+    assert_eq!(
+        evaluator.eval_str("(loop (+ 1 2))"),
+        Err("interrupted".to_string())
+    );
+    stopper.join().unwrap();
+    assert_eq!(evaluator.eval_str("(+ 1 2)").unwrap(), Expr::Integer(3));
+
+    // From a poll hook, as the IDE's Esc key does
+    let polls = std::rc::Rc::new(std::cell::Cell::new(0));
+    let counter = std::rc::Rc::clone(&polls);
+    evaluator.interrupt_poll = Some(std::rc::Rc::new(move || {
+        counter.set(counter.get() + 1);
+        counter.get() == 3
+    }));
+    assert_eq!(
+        evaluator.eval_str("(loop (+ 1 2))"),
+        Err("interrupted".to_string())
+    );
+    assert_eq!(polls.get(), 3);
+    assert_eq!(evaluator.eval_str("(+ 1 2)").unwrap(), Expr::Integer(3));
+}
+
+#[test]
+fn test_step_budget() {
+    let mut evaluator = Evaluator::new();
+    evaluator.budget = EvalBudget {
+        max_steps: Some(10_000),
+        timeout: None,
+    };
+
+    let result = evaluator.eval_source("(loop (+ 1 2))");
+    assert!(matches!(result, Err(EvalError::Timeout(_))), "{:?}", result);
+    let result = evaluator.eval_source("(while t)");
+    assert!(matches!(result, Err(EvalError::Timeout(_))), "{:?}", result);
+    assert_eq!(
+        evaluator.eval_str("(loop (+ 1 2))"),
+        Err("Timeout: exceeded 10000 evaluation steps".to_string())
+    );
+
+    // Each top-level form starts with a fresh budget
+    for _ in 0..5 {
+        assert_eq!(
+            evaluator
+                .eval_str("(do ((i 0 (+ i 1)) (sum 0 (+ sum i))) ((= i 100) sum))")
+                .unwrap(),
+            Expr::Integer(4950)
+        );
+    }
+}
+
+#[test]
+fn test_time_budget() {
+    let mut evaluator = Evaluator::new();
+    evaluator.budget.timeout = Some(std::time::Duration::from_millis(50));
+
+    let started = std::time::Instant::now();
+    assert_eq!(
+        evaluator.eval_str("(loop (+ 1 2))"),
+        Err("Timeout: exceeded 50ms".to_string())
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!(evaluator.eval_str("(+ 1 2)").unwrap(), Expr::Integer(3));
+
+    // Ordinary errors stay distinguishable from running out of budget
+    let result = evaluator.eval_source("(car 5)");
+    assert!(matches!(result, Err(EvalError::Message(_))), "{:?}", result);
+}
+
+#[test]
+fn test_environment_lookup_by_symbol() {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define x 1)").unwrap();
+    evaluator.eval_str("(defun show-x () x)").unwrap();
+
+    // Inner frames shadow outer ones, and a called function sees its caller's bindings
+    assert_eq!(
+        evaluator
+            .eval_str("(let ((x 2)) (let ((x 3)) (list x (show-x))))")
+            .unwrap(),
+        evaluator.eval_str("(list 3 3)").unwrap()
+    );
+    assert_eq!(evaluator.eval_str("(show-x)").unwrap(), Expr::Integer(1));
+
+    let environment = evaluator.get_environment();
+    assert_eq!(
+        environment.get_symbol(Symbol::intern("x")),
+        Ok(Expr::Integer(1))
+    );
+    assert_eq!(environment.get("x"), Ok(Expr::Integer(1)));
+    // Looking up a name nobody has mentioned doesn't intern it
+    assert!(environment.get("env-lookup-never-interned").is_err());
+    assert!(Symbol::lookup("env-lookup-never-interned").is_none());
+}
+
+#[test]
+fn test_builtins_resolve_without_bindings() {
+    use crate::interpreter::environment::BUILTINS;
+    use std::collections::HashSet;
+
+    let unique: HashSet<&str> = BUILTINS.iter().copied().collect();
+    assert_eq!(unique.len(), BUILTINS.len());
+    assert!(Symbol::intern("mapcar").is_builtin());
+    assert!(!Symbol::intern("not-a-builtin-name").is_builtin());
+
+    let mut evaluator = Evaluator::new();
+    assert_eq!(
+        evaluator.eval_str("car").unwrap(),
+        Expr::Symbol(SymbolData::Interned(Symbol::intern("car")))
+    );
+    assert!(evaluator
+        .get_environment()
+        .get_all_bindings()
+        .contains_key("hash-table-p"));
+
+    // Redefining a builtin shadows it for this evaluator only
+    evaluator.eval_str("(defun car (x) 42)").unwrap();
+    assert_eq!(
+        evaluator.eval_str("(car (list 1 2))").unwrap(),
+        Expr::Integer(42)
+    );
+    assert_eq!(
+        Evaluator::new().eval_str("(car (list 1 2))").unwrap(),
+        Expr::Integer(1)
+    );
+}
+
+#[test]
+fn test_fork_is_an_independent_sandbox() {
+    let mut evaluator = Evaluator::new();
+    for i in 0..10_000 {
+        evaluator
+            .environment
+            .set(format!("fork-binding-{}", i), Expr::Integer(i));
+    }
+    evaluator.eval_str("(define shared 1)").unwrap();
+    evaluator.environment.take_changed_globals();
+
+    let mut sandbox = evaluator.fork();
+    assert_eq!(
+        sandbox.eval_str("fork-binding-9999").unwrap(),
+        Expr::Integer(9999)
+    );
+    sandbox.eval_str("(define shared 2)").unwrap();
+    sandbox.eval_str("(define only-in-sandbox 3)").unwrap();
+    assert_eq!(
+        sandbox.environment.take_changed_globals(),
+        vec!["only-in-sandbox".to_string(), "shared".to_string()]
+    );
+
+    assert_eq!(evaluator.eval_str("shared").unwrap(), Expr::Integer(1));
+    assert!(evaluator.eval_str("only-in-sandbox").is_err());
+    assert!(evaluator.environment.take_changed_globals().is_empty());
+
+    evaluator.eval_str("(define shared 4)").unwrap();
+    assert_eq!(sandbox.eval_str("shared").unwrap(), Expr::Integer(2));
+}
+
+#[test]
+fn test_only_the_empty_list_is_false() {
+    for falsy in [
+        "()",
+        "nil",
+        "(quote nil)",
+        "(quote ())",
+        "#f",
+        "(list)",
+        "(cdr (quote (1)))",
+    ] {
+        assert!(!eval_to_bool(falsy), "{} should be false", falsy);
+    }
+    for truthy in [
+        "0",
+        "0.0",
+        "\"\"",
+        "[]",
+        "(quote (nil))",
+        "t",
+        "(quote t)",
+        ":key",
+        "(quote x)",
+    ] {
+        assert!(eval_to_bool(truthy), "{} should be true", truthy);
+    }
+
+    // nil is the empty list itself, not a symbol bound to it
+    assert_eq!(
+        Evaluator::eval_once("(list (equal? (quote nil) ()) (eq? (car (quote (nil))) nil))")
+            .unwrap(),
+        Evaluator::parse("(t t)").unwrap()
+    );
+    assert!(Evaluator::eval_once("(define nil 5)").is_err());
+}
@@ -0,0 +1,306 @@
+use super::helpers::with_main_thread_stack;
+use crate::interpreter::bigint::BigInt;
+use crate::interpreter::environment::BUILTINS;
+use crate::interpreter::evaluator::{EvalBudget, Evaluator, SPECIAL_FORMS};
+use crate::interpreter::ports::StringInputPort;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::tokenizer::Tokenizer;
+use crate::interpreter::types::{Expr, Symbol, SymbolData};
+use std::panic::{self, AssertUnwindSafe};
+
+// Randomized checks of the reader, printer and evaluator. Each test runs a fixed number of
+// cases from a fixed seed so failures reproduce; set ZEUS_FUZZ_SEED and ZEUS_FUZZ_CASES to
+// explore further, e.g. ZEUS_FUZZ_CASES=100000 cargo test fuzz
+
+const DEFAULT_SEED: u64 = 0x5eed_2e05;
+const DEFAULT_CASES: usize = 500;
+
+// Fragments the token-stream generator strings together: every kind of token, plus the
+// malformed and unterminated pieces the reader has to reject cleanly
+const FRAGMENTS: &[&str] = &[
+    "(",
+    ")",
+    "[",
+    "]",
+    "'",
+    "\"",
+    "\\",
+    "#",
+    "#\\",
+    "#\\x",
+    "#\\u",
+    "#\\space",
+    "#t",
+    "#f",
+    "#:",
+    ":",
+    ":key",
+    "1",
+    "-",
+    "-7",
+    "3.5",
+    "1e",
+    "1e5",
+    "2.5e-3",
+    ".",
+    "1/2",
+    "1/0",
+    "1/",
+    "-/",
+    "+inf.0",
+    "-inf.0",
+    "+nan.0",
+    "99999999999999999999999",
+    "abc",
+    "x",
+    "λ",
+    "é",
+    ";",
+    "; comment\n",
+    " ",
+    "\n",
+    "\t",
+    "\"a\\nb\"",
+    "\"\\q\"",
+    "\0",
+];
+
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    // xorshift64*
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+fn settings() -> (u64, usize) {
+    let read = |name: &str| std::env::var(name).ok().and_then(|v| v.parse().ok());
+    (
+        read("ZEUS_FUZZ_SEED").unwrap_or(DEFAULT_SEED),
+        read("ZEUS_FUZZ_CASES").map_or(DEFAULT_CASES, |cases| cases as usize),
+    )
+}
+
+fn symbol(name: &str) -> Expr {
+    Expr::Symbol(SymbolData::Interned(Symbol::intern(name)))
+}
+
+fn random_token_stream(rng: &mut Rng) -> String {
+    let mut source = String::new();
+    for _ in 0..rng.below(24) {
+        if rng.chance(10) {
+            source.push(char::from_u32(rng.below(0x3000) as u32).unwrap_or('?'));
+        } else {
+            source.push_str(rng.pick::<&str>(FRAGMENTS));
+        }
+    }
+    source
+}
+
+fn random_name(rng: &mut Rng) -> String {
+    const FIRST: &[char] = &['a', 'b', 'f', 'x', 'z', 'λ', '*', '<', '?'];
+    const REST: &[char] = &['a', 'e', 'q', '1', '0', '-', '?', '!', '*', '>', '=', '_'];
+    let mut name = rng.pick(FIRST).to_string();
+    for _ in 0..rng.below(6) {
+        name.push(*rng.pick(REST));
+    }
+    name
+}
+
+fn random_float(rng: &mut Rng) -> f64 {
+    match rng.below(6) {
+        0 => f64::INFINITY,
+        1 => f64::NEG_INFINITY,
+        2 => (rng.below(2000) as f64 - 1000.0) / 8.0,
+        // Arbitrary bit patterns cover subnormals and huge exponents; NaN never compares
+        // equal to itself, so it's covered by the printer tests instead
+        _ => Some(f64::from_bits(rng.next()))
+            .filter(|f| !f.is_nan())
+            .unwrap_or(0.5),
+    }
+}
+
+fn random_string(rng: &mut Rng) -> String {
+    const CHARS: &[char] = &[
+        'a', 'Z', ' ', '"', '\\', '\n', '\t', '\r', 'λ', '(', ';', '#',
+    ];
+    (0..rng.below(8)).map(|_| *rng.pick(CHARS)).collect()
+}
+
+// A readable datum: everything the printer writes in a form the reader accepts
+fn random_datum(rng: &mut Rng, depth: usize) -> Expr {
+    let kinds = if depth == 0 { 8 } else { 10 };
+    match rng.below(kinds) {
+        0 => Expr::Integer(match rng.below(4) {
+            0 => i64::MIN,
+            1 => i64::MAX,
+            _ => rng.below(2000) as i64 - 1000,
+        }),
+        1 => {
+            // Only values past i64 stay BigInts when read back
+            let digits: String = (0..20 + rng.below(20))
+                .map(|_| char::from(b'1' + rng.below(9) as u8))
+                .collect();
+            let sign = if rng.chance(50) { "-" } else { "" };
+            Expr::BigInt(BigInt::parse(&format!("{}{}", sign, digits)).unwrap())
+        }
+        2 => Expr::Float(random_float(rng)),
+        3 => Expr::String(random_string(rng)),
+        4 => Expr::Character(*rng.pick(&[
+            'a', 'x', 'u', '7', ' ', '\n', '\t', '\r', '(', ']', '"', '\\', ';', 'λ', '\0',
+        ])),
+        5 => symbol(&random_name(rng)),
+        6 => Expr::Symbol(SymbolData::Keyword(Symbol::intern(&random_name(rng)))),
+        7 => Expr::new_list(vec![]),
+        8 => Expr::new_list(
+            (0..1 + rng.below(4))
+                .map(|_| random_datum(rng, depth - 1))
+                .collect(),
+        ),
+        _ => Expr::new_vector(
+            (0..rng.below(4))
+                .map(|_| random_datum(rng, depth - 1))
+                .collect(),
+        ),
+    }
+}
+
+// Builtins and special forms random forms leave out: they write files into the working
+// directory or change the process environment, which other test threads share
+const SIDE_EFFECT_HEADS: &[&str] = &["export-session", "setenv", "with-open-file"];
+
+// A form that mostly calls builtins and special forms on small values, so evaluation
+// reaches deep into the interpreter instead of stopping at the first unbound name
+fn random_form(rng: &mut Rng, depth: usize) -> Expr {
+    if depth == 0 || rng.chance(30) {
+        return match rng.below(8) {
+            0 => symbol(rng.pick::<&str>(&["x", "y", "f", "t", "nil", "else"])),
+            1 => Expr::Float(random_float(rng)),
+            2 => Expr::String(random_string(rng)),
+            3 => Expr::Character(*rng.pick(&['a', ' ', '0'])),
+            4 => Expr::Symbol(SymbolData::Keyword(Symbol::intern("k"))),
+            5 => Expr::new_list(vec![symbol("quote"), random_datum(rng, 2)]),
+            6 => Expr::Integer(*rng.pick(&[i64::MIN, -1, i64::MAX, 1 << 40])),
+            _ => Expr::Integer(rng.below(12) as i64 - 3),
+        };
+    }
+    let head = loop {
+        let head = if rng.chance(65) {
+            rng.pick(BUILTINS)
+        } else {
+            rng.pick(SPECIAL_FORMS)
+        };
+        if !SIDE_EFFECT_HEADS.contains(head) {
+            break head;
+        }
+    };
+    let mut items = vec![symbol(head)];
+    for _ in 0..rng.below(5) {
+        items.push(random_form(rng, depth - 1));
+    }
+    Expr::new_list(items)
+}
+
+#[test]
+fn test_random_token_streams_never_panic() {
+    let (seed, cases) = settings();
+    let mut rng = Rng::new(seed);
+    for case in 0..cases {
+        let source = random_token_stream(&mut rng);
+        let outcome = panic::catch_unwind(|| {
+            let _ = Tokenizer::new(&source).tokenize();
+            let _ = Evaluator::parse(&source);
+        });
+        assert!(
+            outcome.is_ok(),
+            "reader panicked on case {} (seed {}): {:?}",
+            case,
+            seed,
+            source
+        );
+    }
+}
+
+#[test]
+fn test_random_data_round_trip_through_the_printer() {
+    let (seed, cases) = settings();
+    let mut rng = Rng::new(seed);
+    for case in 0..cases {
+        let datum = random_datum(&mut rng, 3);
+        let written = write_string(&datum);
+        let read = Evaluator::parse(&written);
+        assert_eq!(
+            read.as_ref(),
+            Ok(&datum),
+            "case {} (seed {}) didn't read back: {}",
+            case,
+            seed,
+            written
+        );
+    }
+}
+
+#[test]
+fn test_random_forms_never_panic_the_evaluator() {
+    with_main_thread_stack(|| {
+        let (seed, cases) = settings();
+        let mut rng = Rng::new(seed);
+        let mut base = Evaluator::new();
+        base.capture_output();
+        // Input builtins find nothing to read rather than waiting on stdin, and random
+        // forms never start other programs or touch the network
+        base.input = std::rc::Rc::new(StringInputPort::default());
+        base.allow_processes = false;
+        base.allow_network = false;
+        base.budget = EvalBudget {
+            max_steps: Some(20_000),
+            timeout: None,
+        };
+        base.max_depth = 300;
+        for definition in [
+            "(define x 2)",
+            "(define y (list 1 2.5 \"s\"))",
+            "(define f (lambda (a) a))",
+        ] {
+            base.eval_str(definition).unwrap();
+        }
+
+        for case in 0..cases {
+            let form = random_form(&mut rng, 4);
+            let source = write_string(&form);
+            let mut eval = base.fork();
+            eval.capture_output();
+            eval.optimize = rng.chance(50);
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                let _ = eval.eval_source(&source);
+            }));
+            assert!(
+                outcome.is_ok(),
+                "eval panicked on case {} (seed {}): {}",
+                case,
+                seed,
+                source
+            );
+        }
+    });
+}
@@ -0,0 +1,194 @@
+use crate::interpreter::printer::write_string;
+use crate::interpreter::*;
+
+fn check(evaluator: &mut Evaluator, cases: &[(&str, &str)]) {
+    for (expr, expected) in cases {
+        let value = evaluator
+            .eval_str(expr)
+            .unwrap_or_else(|e| panic!("{}: {}", expr, e));
+        assert_eq!(write_string(&value), *expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_methods_dispatch_on_the_first_argument() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str(
+            "(progn
+               (defstruct circle radius)
+               (defstruct square side)
+               (defgeneric area (shape))
+               (defmethod area ((c circle)) (* 3 (circle-radius c) (circle-radius c)))
+               (defmethod area ((s square)) (* (square-side s) (square-side s)))
+               (defgeneric describe (x) \"What x is.\")
+               (defmethod describe ((x string)) \"a string\")
+               (defmethod describe ((x list)) \"a list\")
+               (defmethod describe ((x null)) \"nothing\")
+               (defmethod describe ((x vector)) \"a vector\")
+               (defmethod describe ((x float)) \"a float\")
+               (defmethod describe (x) \"something\"))",
+        )
+        .unwrap();
+
+    check(
+        &mut evaluator,
+        &[
+            ("(area (make-circle 2))", "12"),
+            ("(area (make-square 3))", "9"),
+            (
+                "(mapcar area (list (make-square 1) (make-circle 1)))",
+                "(1 3)",
+            ),
+            ("(describe \"s\")", "\"a string\""),
+            ("(describe (list 1))", "\"a list\""),
+            ("(describe ())", "\"nothing\""),
+            ("(describe [1])", "\"a vector\""),
+            ("(describe 1.5)", "\"a float\""),
+            ("(describe 1)", "\"something\""),
+            ("(describe (make-circle 1))", "\"something\""),
+        ],
+    );
+
+    // Redefining a method replaces it; the others stay
+    evaluator
+        .eval_str("(defmethod area ((c circle)) (circle-radius c))")
+        .unwrap();
+    check(
+        &mut evaluator,
+        &[
+            ("(area (make-circle 2))", "2"),
+            ("(area (make-square 3))", "9"),
+        ],
+    );
+
+    assert_eq!(
+        evaluator.eval_str("(area 5)").unwrap_err(),
+        "area: no method for 5 of type integer"
+    );
+    assert!(evaluator.eval_str("(area)").is_err());
+}
+
+#[test]
+fn test_call_next_method_runs_the_less_specific_methods() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str(
+            "(progn
+               (defstruct dog name)
+               (defmethod greet (x) (list :hello))
+               (defmethod greet ((x struct)) (cons :struct (call-next-method)))
+               (defmethod greet ((x dog)) (cons (dog-name x) (call-next-method)))
+               (defmethod greet ((x number)) (cons :number (call-next-method)))
+               (defmethod greet ((x integer))
+                 (if (next-method-p) (cons :integer (call-next-method (* x 2))) ()))
+               (defmethod show ((x integer)) (call-next-method)))",
+        )
+        .unwrap();
+
+    check(
+        &mut evaluator,
+        &[
+            ("(greet (make-dog \"rex\"))", "(\"rex\" :struct :hello)"),
+            ("(greet 2)", "(:integer :number :hello)"),
+            ("(greet 2.5)", "(:number :hello)"),
+            ("(greet \"s\")", "(:hello)"),
+            ("(next-method-p)", "()"),
+        ],
+    );
+
+    assert_eq!(
+        evaluator.eval_str("(show 1)").unwrap_err(),
+        "call-next-method: no next method for show"
+    );
+    assert!(evaluator.eval_str("(call-next-method)").is_err());
+    // A failed method doesn't leave its frame behind
+    assert_eq!(
+        evaluator.eval_str("(next-method-p)").unwrap(),
+        Expr::new_list(vec![])
+    );
+}
+
+#[test]
+fn test_type_of() {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(defstruct point x y)").unwrap();
+    check(
+        &mut evaluator,
+        &[
+            (
+                "(mapcar type-of (list 1 1.5 \"s\" #\\c (quote a) :k () (list 1)))",
+                "(integer float string character symbol keyword null cons)",
+            ),
+            ("(type-of (make-point 1 2))", "point"),
+            ("(type-of (make-hash-table))", "hash-table"),
+        ],
+    );
+}
+
+#[test]
+fn test_bad_generic_definitions() {
+    let mut evaluator = Evaluator::new();
+    for bad in [
+        "(defgeneric)",
+        "(defgeneric f ())",
+        "(defgeneric \"f\" (x))",
+        "(defmethod f () 1)",
+        "(defmethod f ((x)) 1)",
+        "(defmethod f ((x 5)) 1)",
+        "(defmethod f (x))",
+    ] {
+        assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_the_and_declare_check_types_at_run_time() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str(
+            "(progn
+               (defstruct point x y)
+               (defun scale (p k)
+                 \"Scales a point.\"
+                 (declare (type point p) (type number k) (ignorable k))
+                 (make-point (* k (point-x p)) (the integer (* k (point-y p))))))",
+        )
+        .unwrap();
+
+    check(
+        &mut evaluator,
+        &[
+            ("(the integer (+ 1 2))", "3"),
+            ("(the number 1.5)", "1.5"),
+            ("(the list ())", "()"),
+            ("(the t \"s\")", "\"s\""),
+            ("(scale (make-point 1 2) 3)", "#<point x: 3 y: 6>"),
+            ("(let ((n 1)) (declare (type integer n)) n)", "1"),
+        ],
+    );
+
+    assert_eq!(
+        evaluator.eval_str("(the integer \"a\")").unwrap_err(),
+        "the: expected integer, got \"a\" of type string"
+    );
+    assert_eq!(
+        evaluator.eval_str("(scale 5 1)").unwrap_err(),
+        "declare: p should be of type point, got 5 of type integer"
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(scale (make-point 1 2) 1.5)")
+            .unwrap_err(),
+        "the: expected integer, got 3.0 of type float"
+    );
+    for bad in [
+        "(the integer)",
+        "(the \"integer\" 1)",
+        "(declare (frobnicate x))",
+        "(declare 5)",
+        "(declare (type integer 5))",
+    ] {
+        assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+    }
+}
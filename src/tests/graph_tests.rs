@@ -0,0 +1,71 @@
+use crate::interpreter::graph::{function_graph, module_graph, GraphFormat};
+use crate::interpreter::xref::XrefIndex;
+
+fn sample_index() -> XrefIndex {
+    XrefIndex::build(&[
+        (
+            "lib.lisp".to_string(),
+            "(defun square (x) (* x x))\n(defun sum-squares (a b) (+ (square a) (square b)))\n"
+                .to_string(),
+        ),
+        (
+            "main.lisp".to_string(),
+            "(defun main () (println (sum-squares 3 4)))\n(main)\n".to_string(),
+        ),
+    ])
+}
+
+#[test]
+fn test_function_graph() {
+    let index = sample_index();
+    let edges: Vec<(&str, &str)> = index.call_edges().into_iter().collect();
+    assert_eq!(
+        edges,
+        vec![("main", "sum-squares"), ("sum-squares", "square")]
+    );
+
+    assert_eq!(
+        function_graph(&index, GraphFormat::Dot),
+        "digraph zeus {
+  node [shape=box];
+  subgraph cluster_0 {
+    label=\"lib.lisp\";
+    \"square\";
+    \"sum-squares\";
+  }
+  subgraph cluster_1 {
+    label=\"main.lisp\";
+    \"main\";
+  }
+  \"main\" -> \"sum-squares\";
+  \"sum-squares\" -> \"square\";
+}
+"
+    );
+
+    let json = function_graph(&index, GraphFormat::Json);
+    assert!(json.contains(
+        "{\"name\": \"sum-squares\", \"kind\": \"defun\", \"file\": \"lib.lisp\", \"line\": 2}"
+    ));
+    assert!(json.contains("{\"from\": \"main\", \"to\": \"sum-squares\"}"));
+}
+
+#[test]
+fn test_module_graph() {
+    let index = sample_index();
+    assert_eq!(
+        module_graph(&index, GraphFormat::Json),
+        "{
+  \"nodes\": [
+    {\"file\": \"lib.lisp\"},
+    {\"file\": \"main.lisp\"}
+  ],
+  \"edges\": [
+    {\"from\": \"main.lisp\", \"to\": \"lib.lisp\"}
+  ]
+}
+"
+    );
+    assert!(module_graph(&index, GraphFormat::Dot).contains("\"main.lisp\" -> \"lib.lisp\";"));
+    assert_eq!(GraphFormat::parse("svg"), None);
+}
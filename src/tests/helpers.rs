@@ -1,26 +1,37 @@
 use crate::interpreter::evaluator::Evaluator as Eval;
 use crate::interpreter::*;
 
-pub fn eval_to_number(input: &str) -> f64 {
+pub(crate) fn eval_to_number(input: &str) -> f64 {
     let result = Evaluator::eval_once(input).unwrap();
     Eval::to_number(&result).expect("Expected a number")
 }
 
-pub fn eval_to_string(input: &str) -> String {
+pub(crate) fn eval_to_string(input: &str) -> String {
     match Evaluator::eval_once(input).unwrap() {
         Expr::String(s) => s,
         other => panic!("Expected string, got {:?}", other),
     }
 }
 
-pub fn eval_to_list(input: &str) -> Vec<Expr> {
+pub(crate) fn eval_to_list(input: &str) -> Vec<Expr> {
     match Evaluator::eval_once(input).unwrap() {
-        Expr::List(l) => l,
+        Expr::List(l) => l.to_vec(),
         other => panic!("Expected list, got {:?}", other),
     }
 }
 
-pub fn eval_to_bool(input: &str) -> bool {
+pub(crate) fn eval_to_bool(input: &str) -> bool {
     let result = Evaluator::eval_once(input).unwrap();
     Evaluator::is_truthy(&result)
 }
+
+// Runs `test` with the main thread's 8MB stack instead of the 2MB test threads get, for
+// tests that deliberately recurse up to the nesting limits
+pub(crate) fn with_main_thread_stack<F: FnOnce() + Send + 'static>(test: F) {
+    std::thread::Builder::new()
+        .stack_size(8 * 1024 * 1024)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -1,4 +1,4 @@
-use crate::interpreter::types::SymbolData;
+use crate::interpreter::types::{Symbol, SymbolData};
 use crate::interpreter::*;
 
 #[test]
@@ -38,13 +38,13 @@ fn test_format_expr() {
 
     // Test symbol formatting
     assert_eq!(
-        repl.format_expr(&Expr::Symbol(SymbolData::Interned("x".to_string()))),
+        repl.format_expr(&Expr::Symbol(SymbolData::Interned(Symbol::intern("x")))),
         "x"
     );
 
     // Test list formatting
-    let list = Expr::List(vec![
-        Expr::Symbol(SymbolData::Interned("+".to_string())),
+    let list = Expr::new_list(vec![
+        Expr::Symbol(SymbolData::Interned(Symbol::intern("+"))),
         Expr::Integer(1),
         Expr::Integer(2),
     ]);
@@ -0,0 +1,89 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::introspect::{describe_function, describe_symbol, SymbolKind};
+use crate::interpreter::types::Expr;
+use crate::interpreter::xref::XrefIndex;
+
+#[test]
+fn test_describe_symbol_kinds() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(defun square (x) \"Multiplies x by itself.\" (* x x))")
+        .unwrap();
+    eval.eval_str("(defun answer () \"not a docstring\")")
+        .unwrap();
+    eval.eval_str("(define limit (list 1 2 3))").unwrap();
+    eval.eval_str("(define car 5)").unwrap();
+    let env = &eval.environment;
+
+    let square = describe_symbol(env, "square", &[]);
+    assert_eq!(square.kind, SymbolKind::Function);
+    assert_eq!(square.signature.as_deref(), Some("(square x)"));
+    assert_eq!(square.doc.as_deref(), Some("Multiplies x by itself."));
+    assert_eq!(square.value, None);
+
+    // A string that is the whole body is the return value
+    let answer = describe_symbol(env, "answer", &[]);
+    assert_eq!(answer.signature.as_deref(), Some("(answer)"));
+    assert_eq!(answer.doc, None);
+
+    let limit = describe_symbol(env, "limit", &[]);
+    assert_eq!(limit.kind, SymbolKind::Variable);
+    assert_eq!(limit.value.as_deref(), Some("(1 2 3)"));
+
+    assert_eq!(describe_symbol(env, "cdr", &[]).kind, SymbolKind::Builtin);
+    assert_eq!(describe_symbol(env, "car", &[]).kind, SymbolKind::Variable);
+    assert_eq!(
+        describe_symbol(env, "let", &[]).kind,
+        SymbolKind::SpecialForm
+    );
+    assert_eq!(describe_symbol(env, ":key", &[]).kind, SymbolKind::Keyword);
+    assert_eq!(
+        describe_symbol(env, "nowhere", &[]).kind,
+        SymbolKind::Unbound
+    );
+}
+
+#[test]
+fn test_describe_symbol_finds_the_last_definition() {
+    let source = "(define x 1)\n(defun f (a) a)\n\n(define  x 2)\n";
+    let index = XrefIndex::build(&[("main.lisp".to_string(), source.to_string())]);
+    let info = describe_symbol(&Evaluator::new().environment, "x", &index.definitions);
+    assert_eq!(info.kind, SymbolKind::Unbound);
+    let location = info.definition.unwrap();
+    assert_eq!(
+        (location.file.as_str(), location.line, location.column),
+        ("main.lisp", 4, 10)
+    );
+}
+
+#[test]
+fn test_describe_function() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define count 3)").unwrap();
+    eval.eval_str("(define car 5)").unwrap();
+    let callback = eval
+        .eval_str(
+            "(lambda (event &rest more)
+               (let ((seen (+ count 1)))
+                 (list (quote (label ignored)) seen event car cdr handler)))",
+        )
+        .unwrap();
+
+    let info = describe_function(&eval.environment, &callback, 60).unwrap();
+    assert_eq!(info.params, "(event &rest more)");
+    assert!(info.body.starts_with("(let ((seen (+ count 1)))"));
+    // Parameters, let bindings, special forms, builtins and quoted data aren't free; a
+    // rebound builtin is
+    assert_eq!(
+        info.free_variables,
+        vec![
+            ("count".to_string(), Some(Expr::Integer(3))),
+            ("car".to_string(), Some(Expr::Integer(5))),
+            ("handler".to_string(), None),
+        ]
+    );
+
+    assert_eq!(
+        describe_function(&eval.environment, &Expr::Integer(1), 60),
+        None
+    );
+}
@@ -49,3 +49,77 @@ fn test_let_with_lambda() {
         .unwrap();
     assert_eq!(result, Expr::Integer(20));
 }
+
+#[test]
+fn test_higher_order_builtins_accept_any_callable() {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(defun square (x) (* x x))").unwrap();
+    evaluator
+        .eval_str("(define add (lambda (a b) (+ a b)))")
+        .unwrap();
+
+    // A quoted name of a user function is resolved the same way as a builtin name
+    assert_eq!(
+        evaluator
+            .eval_str("(mapcar (quote square) (list 1 2 3))")
+            .unwrap(),
+        Expr::new_list(vec![Expr::Integer(1), Expr::Integer(4), Expr::Integer(9)])
+    );
+    assert_eq!(
+        evaluator.eval_str("(funcall (quote add) 2 3)").unwrap(),
+        Expr::Integer(5)
+    );
+    assert_eq!(
+        evaluator.eval_str("(apply add (list 4 5))").unwrap(),
+        Expr::Integer(9)
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(reduce (quote add) (list 1 2 3) 10)")
+            .unwrap(),
+        Expr::Integer(16)
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(filter (quote integerp) (list 1 \"a\" 2))")
+            .unwrap(),
+        Expr::new_list(vec![Expr::Integer(1), Expr::Integer(2)])
+    );
+
+    assert!(evaluator.eval_str("(mapcar 5 (list))").is_err());
+    assert!(evaluator
+        .eval_str("(funcall (quote undefined-fn) 1)")
+        .is_err());
+}
+
+#[test]
+fn test_rest_parameter() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str("(defun tag (name &rest items) (cons name items))")
+        .unwrap();
+    assert_eq!(
+        evaluator.eval_str("(tag :li 1 2 3)").unwrap(),
+        Evaluator::parse("(:li 1 2 3)").unwrap()
+    );
+    assert_eq!(
+        evaluator.eval_str("(tag :br)").unwrap(),
+        Evaluator::parse("(:br)").unwrap()
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(funcall (lambda (&rest xs) (length xs)) 1 2)")
+            .unwrap(),
+        Expr::Integer(2)
+    );
+
+    let error = evaluator.eval_str("(tag)").unwrap_err();
+    assert!(
+        error.contains("expects at least 1 arguments, got 0"),
+        "{}",
+        error
+    );
+    assert!(evaluator.eval_str("(lambda (&rest) 1)").is_err());
+    assert!(evaluator.eval_str("(lambda (&rest a b) 1)").is_err());
+    assert!(evaluator.eval_str("(defun f (a &rest :b) a)").is_err());
+}
@@ -18,7 +18,7 @@ fn test_eval_car() {
     assert_eq!(eval_to_number("(car (list 1 2 3))"), 1.0);
 
     let result = Evaluator::eval_once("(car (list))").unwrap();
-    assert_eq!(result, Expr::List(vec![]));
+    assert_eq!(result, Expr::new_list(vec![]));
 }
 
 #[test]
@@ -77,6 +77,32 @@ fn test_append() {
     assert_eq!(result, vec![]);
 }
 
+#[test]
+fn test_lists_are_shared_until_written() {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define xs (list 1 2 3))").unwrap();
+
+    // Reading a list hands out the same storage rather than a copy
+    let (Expr::List(first), Expr::List(second)) = (
+        evaluator.eval_str("xs").unwrap(),
+        evaluator.eval_str("xs").unwrap(),
+    ) else {
+        panic!("xs should be a list");
+    };
+    assert!(std::rc::Rc::ptr_eq(&first, &second));
+
+    // reverse copies before writing, so the shared original is untouched
+    evaluator.eval_str("(define ys (reverse xs))").unwrap();
+    assert_eq!(
+        evaluator.eval_str("xs").unwrap(),
+        Expr::new_list(vec![Expr::Integer(1), Expr::Integer(2), Expr::Integer(3)])
+    );
+    assert_eq!(
+        evaluator.eval_str("ys").unwrap(),
+        Expr::new_list(vec![Expr::Integer(3), Expr::Integer(2), Expr::Integer(1)])
+    );
+}
+
 #[test]
 fn test_reverse() {
     let result = eval_to_list("(reverse (list 1 2 3 4))");
@@ -147,7 +173,7 @@ fn test_member() {
     let result = Evaluator::eval_once("(member \"b\" (list \"a\" \"b\" \"c\"))").unwrap();
     assert_eq!(
         result,
-        Expr::List(vec![
+        Expr::new_list(vec![
             Expr::String("b".to_string()),
             Expr::String("c".to_string()),
         ])
@@ -165,7 +191,7 @@ fn test_list_operations_combination() {
     let result = evaluator.eval_str("(cons 0 (reverse (cdr lst)))").unwrap();
     assert_eq!(
         result,
-        Expr::List(vec![
+        Expr::new_list(vec![
             Expr::Integer(0),
             Expr::Integer(5),
             Expr::Integer(4),
@@ -174,3 +200,404 @@ fn test_list_operations_combination() {
         ])
     );
 }
+
+#[test]
+fn test_assoc_family() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define colors (list (cons \"red\" 1) (cons \"green\" 2) (list \"blue\" 3)))")
+        .unwrap();
+
+    assert_eq!(
+        eval.eval_str("(assoc \"green\" colors)").unwrap(),
+        Expr::Cons(
+            Box::new(Expr::String("green".to_string())),
+            Box::new(Expr::Integer(2))
+        )
+    );
+    assert_eq!(
+        eval.eval_str("(assoc \"blue\" colors)").unwrap(),
+        Expr::new_list(vec![Expr::String("blue".to_string()), Expr::Integer(3)])
+    );
+    assert_eq!(
+        eval.eval_str("(assoc \"purple\" colors)").unwrap(),
+        Expr::new_list(vec![])
+    );
+    assert_eq!(
+        eval.eval_str("(car (rassoc 3 colors))").unwrap(),
+        Expr::String("blue".to_string())
+    );
+
+    // assq compares atoms by identity, so strings never match
+    eval.eval_str("(define keyed (list (cons :a 1) (cons :b 2)))")
+        .unwrap();
+    assert_eq!(
+        eval.eval_str("(cdr (assq :b keyed))").unwrap(),
+        Expr::Integer(2)
+    );
+    assert_eq!(
+        eval.eval_str("(assq \"red\" colors)").unwrap(),
+        Expr::new_list(vec![])
+    );
+    assert!(eval.eval_str("(assoc 1 2)").is_err());
+}
+
+#[test]
+fn test_predicate_variants_and_keyword_arguments() {
+    let mut eval = Evaluator::new();
+    let mut check = |source: &str, expected: &str| {
+        assert_eq!(
+            printer::write_string(&eval.eval_str(source).unwrap()),
+            expected,
+            "{}",
+            source
+        );
+    };
+    check(
+        "(define people (list (list :name \"Ann\" :age 31) (list :name \"bo\" :age 17)))",
+        "((:name \"Ann\" :age 31) (:name \"bo\" :age 17))",
+    );
+    check("(member-if (lambda (x) (> x 2)) (list 1 3 2 5))", "(3 2 5)");
+    check("(find-if (lambda (x) (> x 2)) (list 1 3 2 5))", "3");
+    check("(find-if (lambda (x) (> x 9)) (list 1 3))", "()");
+    check(
+        "(remove-if-not (lambda (x) (> x 2)) (list 1 3 2 5))",
+        "(3 5)",
+    );
+    check("(remove-if (lambda (x) (> x 2)) (list 1 3 2 5))", "(1 2)");
+    check(
+        "(assoc-if (lambda (k) (> k 1)) (list (cons 1 :a) (cons 2 :b)))",
+        "(2 . :b)",
+    );
+
+    // :key projects each element before the predicate or comparison sees it
+    check(
+        "(find-if (lambda (age) (< age 18)) people :key :age)",
+        "(:name \"bo\" :age 17)",
+    );
+    check(
+        "(length (filter (lambda (age) (> age 18)) people :key :age))",
+        "1",
+    );
+    check(
+        "(member 4 (list 1 -4 2) :key (lambda (x) (- 0 x)))",
+        "(-4 2)",
+    );
+    check(
+        "(sort (list \"ccc\" \"a\" \"bb\") < :key length)",
+        "(\"a\" \"bb\" \"ccc\")",
+    );
+
+    // :test replaces equal?, e.g. for case-insensitive lookups
+    check(
+        "(member \"B\" (list \"a\" \"b\") :test string-ci=)",
+        "(\"b\")",
+    );
+    check(
+        "(assoc \"RED\" (list (cons \"red\" 1)) :test string-ci=)",
+        "(\"red\" . 1)",
+    );
+    check(
+        "(rassoc 2 (list (cons :a 1) (cons :b 3)) :test <)",
+        "(:b . 3)",
+    );
+    check(
+        "(assoc \"ann\" (list (list (list :name \"Ann\") 1)) :key :name :test string-ci=)",
+        "((:name \"Ann\") 1)",
+    );
+
+    assert_eq!(
+        eval.eval_str("(member 1 (list 1) :test)").unwrap_err(),
+        "member is missing a value for :test"
+    );
+    assert_eq!(
+        eval.eval_str("(assq :a (list (cons :a 1)) :test eq?)")
+            .unwrap_err(),
+        "assq expected :key but got :test"
+    );
+    assert!(eval.eval_str("(filter (lambda (x) x) (list 1) 3)").is_err());
+}
+
+#[test]
+fn test_plist_operations() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define props (list :name \"zeus\" :version 2))")
+        .unwrap();
+
+    assert_eq!(
+        eval.eval_str("(getf props :version)").unwrap(),
+        Expr::Integer(2)
+    );
+    assert_eq!(
+        eval.eval_str("(getf props :missing 42)").unwrap(),
+        Expr::Integer(42)
+    );
+    assert_eq!(
+        eval.eval_str("(plist-get props :missing)").unwrap(),
+        Expr::new_list(vec![])
+    );
+
+    eval.eval_str("(define updated (plist-put (plist-put props :version 3) :stable t))")
+        .unwrap();
+    assert_eq!(
+        eval.eval_str("(getf updated :version)").unwrap(),
+        Expr::Integer(3)
+    );
+    assert_eq!(eval.eval_str("(length updated)").unwrap(), Expr::Integer(6));
+    // The original plist is left untouched
+    assert_eq!(
+        eval.eval_str("(getf props :version)").unwrap(),
+        Expr::Integer(2)
+    );
+    assert!(eval.eval_str("(getf (list :a) :a)").is_err());
+}
+
+#[test]
+fn test_alist_hash_conversions() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define h (alist->hash (list (cons :x 1) (list :y 2) (cons :x 99))))")
+        .unwrap();
+
+    assert_eq!(eval.eval_str("(hash-ref h :x)").unwrap(), Expr::Integer(1));
+    assert_eq!(eval.eval_str("(hash-ref h :y)").unwrap(), Expr::Integer(2));
+    assert_eq!(
+        eval.eval_str("(length (hash->alist h))").unwrap(),
+        Expr::Integer(2)
+    );
+    assert_eq!(
+        eval.eval_str("(cdr (assoc :y (hash->alist h)))").unwrap(),
+        Expr::Integer(2)
+    );
+    assert!(eval.eval_str("(alist->hash (list 1 2))").is_err());
+}
+
+#[test]
+fn test_sort() {
+    assert_eq!(
+        eval_to_list("(sort (list 3 1 2 5 4) <)"),
+        vec![
+            Expr::Integer(1),
+            Expr::Integer(2),
+            Expr::Integer(3),
+            Expr::Integer(4),
+            Expr::Integer(5)
+        ]
+    );
+    assert_eq!(
+        eval_to_list("(sort (list 1 3 2) (lambda (a b) (> a b)))"),
+        vec![Expr::Integer(3), Expr::Integer(2), Expr::Integer(1)]
+    );
+    assert_eq!(
+        Evaluator::eval_once("(sort (vector 2 1) <)").unwrap(),
+        Expr::new_vector(vec![Expr::Integer(1), Expr::Integer(2)])
+    );
+    assert_eq!(eval_to_list("(sort (list) <)"), vec![]);
+    assert!(Evaluator::eval_once("(sort 5 <)").is_err());
+    assert!(Evaluator::eval_once("(sort (list 1 2) 7)").is_err());
+}
+
+#[test]
+fn test_sort_is_stable() {
+    let mut eval = Evaluator::new();
+    eval.eval_str(
+        "(define pairs (list (list 2 \"a\") (list 1 \"b\") (list 2 \"c\") (list 1 \"d\")))",
+    )
+    .unwrap();
+
+    let by_first =
+        "(mapcar (lambda (p) (nth 1 p)) (stable-sort pairs (lambda (x y) (< (car x) (car y)))))";
+    assert_eq!(
+        eval.eval_str(by_first).unwrap(),
+        Expr::new_list(vec![
+            Expr::String("b".to_string()),
+            Expr::String("d".to_string()),
+            Expr::String("a".to_string()),
+            Expr::String("c".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn test_sort_by() {
+    let mut eval = Evaluator::new();
+    assert_eq!(
+        eval.eval_str("(sort-by (list \"ccc\" \"a\" \"bb\") length)")
+            .unwrap(),
+        Expr::new_list(vec![
+            Expr::String("a".to_string()),
+            Expr::String("bb".to_string()),
+            Expr::String("ccc".to_string()),
+        ])
+    );
+    assert_eq!(
+        eval.eval_str("(sort-by (list 1 -3 2) (lambda (x) (* x x)) >)")
+            .unwrap(),
+        Expr::new_list(vec![Expr::Integer(-3), Expr::Integer(2), Expr::Integer(1)])
+    );
+    assert!(eval.eval_str("(sort-by (list 1 2))").is_err());
+}
+
+#[test]
+fn test_transduce_and_map_filter_reduce() {
+    let mut eval = Evaluator::new();
+    let mut check = |source: &str, expected: &str| {
+        assert_eq!(
+            printer::write_string(&eval.eval_str(source).unwrap()),
+            expected,
+            "{}",
+            source
+        );
+    };
+    check(
+        "(map-filter-reduce (lambda (x) (* x x)) (lambda (x) (> x 4)) + 0 (list 1 2 3 4))",
+        "25",
+    );
+    check(
+        "(transduce (list 1 2 3 4 5) :filter (lambda (x) (> x 1)) :map (lambda (x) (* x 10)) :remove (lambda (x) (= x 30)))",
+        "(20 40 50)",
+    );
+    check("(transduce (list 1 2 3) :reduce +)", "6");
+    check("(transduce (list) :map car :reduce + 7)", "7");
+
+    // Each element goes all the way through before the next one starts
+    check("(define trace [])", "[]");
+    check(
+        "(transduce (list 1 2) :map (lambda (x) (progn (vector-push trace (list :map x)) x)) :filter (lambda (x) (vector-push trace (list :filter x))))",
+        "(1 2)",
+    );
+    check("trace", "[(:map 1) (:filter 1) (:map 2) (:filter 2)]");
+
+    for (source, error) in [
+        (
+            "(transduce (list) :reduce +)",
+            "reduce of empty list with no initial value",
+        ),
+        (
+            "(transduce (list 1) :reduce + 0 :map car)",
+            "transduce takes :reduce f [init] only as its last step",
+        ),
+        (
+            "(transduce (list 1) :sum +)",
+            "transduce expected :map, :filter, :remove or :reduce but got :sum",
+        ),
+        (
+            "(transduce (list 1) :map)",
+            "transduce is missing a function after :map",
+        ),
+    ] {
+        assert_eq!(eval.eval_str(source).unwrap_err(), error, "{}", source);
+    }
+}
+
+#[test]
+fn test_folds_and_indexed_mapping() {
+    let mut eval = Evaluator::new();
+    let mut check = |source: &str, expected: &str| {
+        assert_eq!(
+            printer::write_string(&eval.eval_str(source).unwrap()),
+            expected,
+            "{}",
+            source
+        );
+    };
+    check("(foldl - 10 (list 1 2 3))", "4");
+    check("(foldr - 10 (list 1 2 3))", "-8");
+    check(
+        "(foldl (lambda (acc x) (cons x acc)) (list) (list 1 2 3))",
+        "(3 2 1)",
+    );
+    check("(foldr cons (list) (list 1 2 3))", "(1 2 3)");
+    check("(foldl + 0 (list))", "0");
+    check(
+        "(map-indexed (lambda (i x) (list i x)) (list \"a\" \"b\"))",
+        "((0 \"a\") (1 \"b\"))",
+    );
+    check("(map-indexed list (list))", "()");
+}
+
+#[test]
+fn test_zip_iota_and_slicing() {
+    let mut eval = Evaluator::new();
+    let mut check = |source: &str, expected: &str| {
+        assert_eq!(
+            printer::write_string(&eval.eval_str(source).unwrap()),
+            expected,
+            "{}",
+            source
+        );
+    };
+    check("(zip (list 1 2 3) (list 4 5 6))", "((1 4) (2 5) (3 6))");
+    // Stops at the shortest list
+    check("(zip (list 1 2 3) (list 4) (list 5 6))", "((1 4 5))");
+    check("(zip)", "()");
+    check(
+        "(unzip (list (list 1 4) (list 2 5) (list 3 6)))",
+        "((1 2 3) (4 5 6))",
+    );
+    check("(unzip (zip (list 1 2) (list 3 4)))", "((1 2) (3 4))");
+    check("(iota 5)", "(0 1 2 3 4)");
+    check("(iota 3 1)", "(1 2 3)");
+    check("(iota 4 10 -2)", "(10 8 6 4)");
+    check("(iota 3 0 0.5)", "(0.0 0.5 1.0)");
+    check("(iota 0)", "()");
+    check("(take 2 (list 1 2 3))", "(1 2)");
+    check("(take 5 (list 1 2 3))", "(1 2 3)");
+    check("(drop 2 (list 1 2 3))", "(3)");
+    check("(drop 5 (list 1 2 3))", "()");
+}
+
+#[test]
+fn test_partition_flatten_and_last() {
+    let mut eval = Evaluator::new();
+    let mut check = |source: &str, expected: &str| {
+        assert_eq!(
+            printer::write_string(&eval.eval_str(source).unwrap()),
+            expected,
+            "{}",
+            source
+        );
+    };
+    check(
+        "(partition (lambda (x) (> x 2)) (list 1 3 2 4))",
+        "((3 4) (1 2))",
+    );
+    check("(partition integerp (list))", "(() ())");
+    check(
+        "(flatten (list 1 (list 2 (list 3 (list)) 4) (list (list 5))))",
+        "(1 2 3 4 5)",
+    );
+    check("(flatten (list))", "()");
+    check("(last (list 1 2 3))", "3");
+    check("(last (list))", "()");
+
+    for (source, error) in [
+        (
+            "(foldl + 0 5)",
+            "foldl requires a list, vector, string, hash table or iterator as third argument",
+        ),
+        (
+            "(foldr 1 0 (list))",
+            "foldr requires a function as first argument",
+        ),
+        (
+            "(zip (list 1) 2)",
+            "zip requires list, vector, string, hash table or iterator arguments",
+        ),
+        ("(unzip (list 1 2))", "unzip requires a list of lists"),
+        ("(iota -1)", "iota requires a non-negative integer count"),
+        (
+            "(iota 9223372036854775807)",
+            "iota: count 9223372036854775807 is over the limit of 16777216",
+        ),
+        (
+            "(take -1 (list 1))",
+            "take requires a non-negative integer count",
+        ),
+        (
+            "(flatten 5)",
+            "flatten requires a list, vector, string, hash table or iterator as its argument",
+        ),
+        ("(last)", "last requires exactly 1 argument"),
+    ] {
+        assert_eq!(eval.eval_str(source).unwrap_err(), error, "{}", source);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::interpreter::json::Json;
+use crate::interpreter::lsp::{read_message, write_message, LspServer};
+use std::io::Cursor;
+
+fn message(text: &str) -> Json {
+    Json::parse(text).unwrap()
+}
+
+fn open(server: &mut LspServer, text: &str) -> Vec<Json> {
+    let params = Json::object(vec![(
+        "textDocument",
+        Json::object(vec![
+            ("uri", Json::string("untitled:demo.lisp")),
+            ("text", Json::string(text)),
+        ]),
+    )]);
+    server.handle(&Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("method", Json::string("textDocument/didOpen")),
+        ("params", params),
+    ]))
+}
+
+// The result of a request about the open document, with the cursor at line:character
+fn request_at(server: &mut LspServer, method: &str, line: usize, character: usize) -> Json {
+    let reply = server.handle(&message(&format!(
+        r#"{{"jsonrpc":"2.0","id":7,"method":"{}","params":{{"textDocument":{{"uri":"untitled:demo.lisp"}},"position":{{"line":{},"character":{}}}}}}}"#,
+        method, line, character
+    )));
+    assert_eq!(reply.len(), 1);
+    assert_eq!(reply[0].get("id"), Some(&Json::Number(7.0)));
+    reply[0].get("result").unwrap().clone()
+}
+
+#[test]
+fn test_json_round_trip() {
+    let text = r#"{"a":[1,2.5,-3,true,false,null],"b":{"c":"line\nquote\" é 😀"},"d":[]}"#;
+    let value = message(text);
+    assert_eq!(
+        value.path(&["b", "c"]).and_then(Json::as_str),
+        Some("line\nquote\" é 😀")
+    );
+    assert_eq!(Json::parse(&value.to_string()).unwrap(), value);
+    assert_eq!(message("[1, 2.5]").to_string(), "[1,2.5]");
+    assert!(Json::parse("{\"a\" 1}").is_err());
+    assert!(Json::parse("[1,]").is_err());
+    assert!(Json::parse("\"open").is_err());
+}
+
+#[test]
+fn test_framing() {
+    let mut out = Vec::new();
+    write_message(&mut out, &message(r#"{"id":1}"#)).unwrap();
+    assert_eq!(
+        String::from_utf8(out.clone()).unwrap(),
+        "Content-Length: 8\r\n\r\n{\"id\":1}"
+    );
+
+    out.extend_from_slice(
+        b"Content-Type: application/vscode-jsonrpc\r\nContent-Length: 2\r\n\r\n[]",
+    );
+    let mut reader = Cursor::new(out);
+    assert_eq!(
+        read_message(&mut reader).unwrap().as_deref(),
+        Some("{\"id\":1}")
+    );
+    assert_eq!(read_message(&mut reader).unwrap().as_deref(), Some("[]"));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
+
+#[test]
+fn test_lifecycle() {
+    let mut server = LspServer::new();
+    let reply = server.handle(&message(
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+    ));
+    let capabilities = reply[0].path(&["result", "capabilities"]).unwrap();
+    assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+    assert_eq!(
+        capabilities.get("textDocumentSync"),
+        Some(&Json::Number(1.0))
+    );
+
+    let unknown = server.handle(&message(r#"{"jsonrpc":"2.0","id":2,"method":"foo/bar"}"#));
+    assert_eq!(
+        unknown[0].path(&["error", "code"]),
+        Some(&Json::Number(-32601.0))
+    );
+    // Unknown notifications are ignored
+    assert!(server
+        .handle(&message(r#"{"jsonrpc":"2.0","method":"$/cancelRequest"}"#))
+        .is_empty());
+
+    assert_eq!(server.exit_code(), None);
+    server.handle(&message(r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#));
+    server.handle(&message(r#"{"jsonrpc":"2.0","method":"exit"}"#));
+    assert_eq!(server.exit_code(), Some(0));
+
+    let mut abrupt = LspServer::new();
+    abrupt.handle(&message(r#"{"jsonrpc":"2.0","method":"exit"}"#));
+    assert_eq!(abrupt.exit_code(), Some(1));
+}
+
+#[test]
+fn test_diagnostics_follow_the_document() {
+    let mut server = LspServer::new();
+    let published = open(&mut server, "(define x 1)  \n(defun car (y) y)\n");
+    assert_eq!(published.len(), 1);
+    assert_eq!(
+        published[0].get("method").and_then(Json::as_str),
+        Some("textDocument/publishDiagnostics")
+    );
+    let diagnostics = published[0].path(&["params", "diagnostics"]).unwrap();
+    let summary: Vec<(&str, usize, usize)> = diagnostics
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| {
+            (
+                d.get("code").and_then(Json::as_str).unwrap(),
+                d.path(&["range", "start", "line"])
+                    .and_then(Json::as_usize)
+                    .unwrap(),
+                d.path(&["range", "start", "character"])
+                    .and_then(Json::as_usize)
+                    .unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![("trailing-whitespace", 0, 12), ("redefined-builtin", 1, 7)]
+    );
+
+    let changed = server.handle(&message(
+        r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"untitled:demo.lisp","version":2},"contentChanges":[{"text":"(list 1"}]}}"#,
+    ));
+    let diagnostics = changed[0].path(&["params", "diagnostics"]).unwrap();
+    assert_eq!(
+        diagnostics.as_array().unwrap()[0].get("severity"),
+        Some(&Json::Number(1.0))
+    );
+
+    let closed = server.handle(&message(
+        r#"{"jsonrpc":"2.0","method":"textDocument/didClose","params":{"textDocument":{"uri":"untitled:demo.lisp"}}}"#,
+    ));
+    assert_eq!(
+        closed[0].path(&["params", "diagnostics"]),
+        Some(&Json::Array(vec![]))
+    );
+}
+
+#[test]
+fn test_completion_and_hover() {
+    let mut server = LspServer::new();
+    open(
+        &mut server,
+        "(defun square (x) \"Multiplies x by itself.\" (* x x))\n(define limit 10)\n(squ",
+    );
+
+    let labels = |result: &Json| -> Vec<String> {
+        result
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| {
+                item.get("label")
+                    .and_then(Json::as_str)
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    };
+    assert_eq!(
+        labels(&request_at(&mut server, "textDocument/completion", 2, 4)),
+        vec!["square"]
+    );
+    let with_builtins = labels(&request_at(&mut server, "textDocument/completion", 1, 2));
+    assert!(with_builtins.contains(&"define".to_string()));
+    assert!(with_builtins.contains(&"defun".to_string()));
+    assert!(!with_builtins.contains(&"car".to_string()));
+
+    let hover = request_at(&mut server, "textDocument/hover", 0, 9);
+    let text = hover
+        .path(&["contents", "value"])
+        .and_then(Json::as_str)
+        .unwrap();
+    assert!(text.contains("(square x)"), "{}", text);
+    assert!(text.contains("Multiplies x by itself."));
+    assert_eq!(
+        hover.path(&["range", "start", "character"]),
+        Some(&Json::Number(7.0))
+    );
+
+    let builtin = request_at(&mut server, "textDocument/hover", 0, 45);
+    assert!(builtin
+        .path(&["contents", "value"])
+        .and_then(Json::as_str)
+        .unwrap()
+        .contains("builtin"));
+    // Top-level code isn't run, so a variable only has its definition site
+    let limit = request_at(&mut server, "textDocument/hover", 1, 10);
+    assert!(limit
+        .path(&["contents", "value"])
+        .and_then(Json::as_str)
+        .unwrap()
+        .contains("Defined on line 2"));
+    assert_eq!(
+        request_at(&mut server, "textDocument/hover", 0, 0),
+        Json::Null
+    );
+}
+
+#[test]
+fn test_document_symbols_and_formatting() {
+    let mut server = LspServer::new();
+    open(
+        &mut server,
+        "(defun   twice (x)\n  (* 2 x))\n; kept\n(define limit (list 1   2))\n(when t ; note\n  1)\n",
+    );
+
+    let symbols = request_at(&mut server, "textDocument/documentSymbol", 0, 0);
+    let symbols = symbols.as_array().unwrap();
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols[0].get("name"), Some(&Json::string("twice")));
+    assert_eq!(symbols[0].get("kind"), Some(&Json::Number(12.0)));
+    assert_eq!(
+        symbols[0].path(&["range", "end", "line"]),
+        Some(&Json::Number(1.0))
+    );
+    assert_eq!(
+        symbols[0].path(&["selectionRange", "start", "character"]),
+        Some(&Json::Number(9.0))
+    );
+    assert_eq!(symbols[1].get("kind"), Some(&Json::Number(13.0)));
+
+    let edits = request_at(&mut server, "textDocument/formatting", 0, 0);
+    let edits: Vec<(usize, &str)> = edits
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|edit| {
+            (
+                edit.path(&["range", "start", "line"])
+                    .and_then(Json::as_usize)
+                    .unwrap(),
+                edit.get("newText").and_then(Json::as_str).unwrap(),
+            )
+        })
+        .collect();
+    // The form with a comment inside is left as written
+    assert_eq!(
+        edits,
+        vec![
+            (0, "(defun twice (x) (* 2 x))"),
+            (3, "(define limit (list 1 2))")
+        ]
+    );
+}
@@ -1,26 +1,45 @@
-#[cfg(test)]
-pub mod arithmetic_tests;
-#[cfg(test)]
-pub mod builtin_functions_tests;
-#[cfg(test)]
-pub mod data_types_tests;
-#[cfg(test)]
-pub mod defun_tests;
-#[cfg(test)]
-pub mod evaluator_tests;
-#[cfg(test)]
-pub mod helpers;
-#[cfg(test)]
-pub mod integration_tests;
-#[cfg(test)]
-pub mod lambda_tests;
-#[cfg(test)]
-pub mod list_tests;
-#[cfg(test)]
-pub mod parser_tests;
-#[cfg(test)]
-pub mod special_forms_tests;
-#[cfg(test)]
-pub mod symbol_tests;
-#[cfg(test)]
-pub mod tokenizer_tests;
+mod arithmetic_tests;
+mod batch_tests;
+mod bench_tests;
+mod builtin_functions_tests;
+mod check_tests;
+mod completion_tests;
+mod data_types_tests;
+mod defun_tests;
+mod doctest_tests;
+mod dynamic_wind_tests;
+mod error_tests;
+mod evaluator_tests;
+mod fuzz_tests;
+mod generics_tests;
+mod graph_tests;
+mod helpers;
+mod integration_tests;
+mod introspect_tests;
+mod lambda_tests;
+mod list_tests;
+mod lsp_tests;
+mod net_tests;
+mod optimizer_tests;
+mod ordered_map_tests;
+mod os_tests;
+mod packages_tests;
+mod parser_tests;
+mod ports_tests;
+mod prelude_tests;
+mod pretty_tests;
+mod printer_tests;
+mod profile_tests;
+mod recording_tests;
+mod regex_tests;
+mod remote_tests;
+mod restart_tests;
+mod session_tests;
+mod snapshot_tests;
+mod source_map_tests;
+mod special_forms_tests;
+mod symbol_map_tests;
+mod symbol_tests;
+mod tokenizer_tests;
+mod transaction_tests;
+mod xref_tests;
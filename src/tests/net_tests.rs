@@ -0,0 +1,239 @@
+use crate::interpreter::net::{parse_http_response, parse_http_url, TcpConnection};
+use crate::interpreter::printer::write_string;
+use crate::interpreter::*;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+fn eval(evaluator: &mut Evaluator, source: &str) -> String {
+    write_string(&evaluator.eval_str(source).unwrap())
+}
+
+#[test]
+fn test_tcp_connect_send_and_receive() {
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = server.local_addr().unwrap().port();
+    let echo = thread::spawn(move || {
+        let (stream, _) = server.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        (&stream)
+            .write_all(format!("echo {}", line).as_bytes())
+            .unwrap();
+    });
+
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str(&format!(
+            "(define conn (tcp-connect \"127.0.0.1\" {}))",
+            port
+        ))
+        .unwrap();
+    assert_eq!(
+        eval(&mut evaluator, "(tcp-send conn \"hello\\n\")"),
+        "\"hello\\n\""
+    );
+    assert_eq!(eval(&mut evaluator, "(tcp-receive conn)"), "\"echo hello\"");
+    echo.join().unwrap();
+    assert_eq!(eval(&mut evaluator, "(tcp-receive conn)"), "()");
+    assert_eq!(eval(&mut evaluator, "(tcp-close conn)"), "t");
+    assert_eq!(eval(&mut evaluator, "(tcp-close conn)"), "()");
+    assert_eq!(
+        evaluator.eval_str("(tcp-receive conn)").unwrap_err(),
+        format!("tcp-receive: {} is not an open connection", 1)
+    );
+}
+
+#[test]
+fn test_tcp_listen_and_accept() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str("(define server (tcp-listen 0))")
+        .unwrap();
+    let port: u16 = eval(&mut evaluator, "(tcp-port server)").parse().unwrap();
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.write_all(b"ping\r\n").unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        reply
+    });
+
+    evaluator
+        .eval_str("(define conn (tcp-accept server))")
+        .unwrap();
+    assert_eq!(eval(&mut evaluator, "(tcp-receive conn)"), "\"ping\"");
+    evaluator
+        .eval_str("(progn (tcp-send conn \"pong\\n\") (tcp-close conn) (tcp-close server))")
+        .unwrap();
+    assert_eq!(client.join().unwrap(), "pong\n");
+}
+
+// Answers one request with `response` and hands back the request it read
+fn serve_once(response: &'static str) -> (u16, thread::JoinHandle<String>) {
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = server.local_addr().unwrap().port();
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = server.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request = String::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(length) = line.strip_prefix("Content-Length: ") {
+                content_length = length.trim().parse().unwrap();
+            }
+            request.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        request.push_str(&String::from_utf8(body).unwrap());
+        stream.write_all(response.as_bytes()).unwrap();
+        request
+    });
+    (port, handle)
+}
+
+#[test]
+fn test_http_get_and_post() {
+    let (port, server) =
+        serve_once("HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\nX-Count: 2\r\n\r\nhi there");
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str(&format!(
+            "(define response (http-get \"http://127.0.0.1:{}/greet?x=1\"))",
+            port
+        ))
+        .unwrap();
+    let request = server.join().unwrap();
+    assert!(
+        request.starts_with("GET /greet?x=1 HTTP/1.0\r\n"),
+        "{}",
+        request
+    );
+    assert!(request.contains(&format!("Host: 127.0.0.1:{}\r\n", port)));
+    assert_eq!(eval(&mut evaluator, "(car response)"), "200");
+    assert_eq!(
+        eval(&mut evaluator, "(hash-ref (nth 1 response) \"x-count\")"),
+        "\"2\""
+    );
+    assert_eq!(eval(&mut evaluator, "(nth 2 response)"), "\"hi there\"");
+
+    let (port, server) = serve_once("HTTP/1.0 201 Created\r\n\r\n");
+    let status = eval(
+        &mut evaluator,
+        &format!(
+            "(car (http-post \"http://127.0.0.1:{}/items\" \"{{\\\"a\\\": 1}}\" \"application/json\"))",
+            port
+        ),
+    );
+    assert_eq!(status, "201");
+    let request = server.join().unwrap();
+    assert!(request.starts_with("POST /items HTTP/1.0\r\n"));
+    assert!(request.contains("Content-Type: application/json\r\n"));
+    assert!(request.ends_with("\r\n\r\n{\"a\": 1}"));
+}
+
+#[test]
+fn test_http_parsing() {
+    assert_eq!(
+        parse_http_url("http://example.com"),
+        Ok(("example.com".to_string(), 80, "/".to_string()))
+    );
+    assert_eq!(
+        parse_http_url("http://localhost:8080/a/b"),
+        Ok(("localhost".to_string(), 8080, "/a/b".to_string()))
+    );
+    assert_eq!(
+        parse_http_url("https://example.com"),
+        Err("https is not supported".to_string())
+    );
+    assert!(parse_http_url("http://host:99999/").is_err());
+    // A line break would let the URL add headers to the request
+    for injected in [
+        "http://example.com/a\r\nX-Injected: 1",
+        "http://exa\r\nmple.com/",
+        "http://example.com/a b",
+    ] {
+        assert!(parse_http_url(injected).is_err(), "{:?}", injected);
+    }
+
+    let response =
+        parse_http_response(b"HTTP/1.1 404 Not Found\r\nServer: x\r\n\r\nmissing").unwrap();
+    assert_eq!(response.status, 404);
+    assert_eq!(
+        response.headers,
+        vec![("server".to_string(), "x".to_string())]
+    );
+    assert_eq!(response.body, "missing");
+    assert!(parse_http_response(b"garbage").is_err());
+}
+
+#[test]
+fn test_network_can_be_disabled() {
+    let mut evaluator = Evaluator::new();
+    evaluator.allow_network = false;
+    assert_eq!(
+        evaluator.fork().eval_str("(tcp-listen 0)").unwrap_err(),
+        "tcp-listen is disabled in this evaluator"
+    );
+    assert!(evaluator
+        .eval_str("(http-get \"http://127.0.0.1/\")")
+        .is_err());
+}
+
+#[test]
+fn test_network_reads_stop_on_interrupt() {
+    // A server that accepts and then says nothing until the test is done
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = server.local_addr().unwrap().port();
+    let (done, finished) = mpsc::channel::<()>();
+    let silent = thread::spawn(move || {
+        let (_first, _) = server.accept().unwrap();
+        let (_second, _) = server.accept().unwrap();
+        let _ = finished.recv();
+    });
+
+    let mut evaluator = Evaluator::new();
+    for source in [
+        format!("(http-get \"http://127.0.0.1:{}/\")", port),
+        format!("(tcp-receive (tcp-connect \"127.0.0.1\" {}))", port),
+    ] {
+        let interrupt = evaluator.interrupt_handle();
+        let stopper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            interrupt.store(true, Ordering::SeqCst);
+        });
+        assert_eq!(evaluator.eval_str(&source).unwrap_err(), "interrupted");
+        stopper.join().unwrap();
+    }
+    done.send(()).unwrap();
+    silent.join().unwrap();
+}
+
+#[test]
+fn test_receive_all_is_capped() {
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = server.local_addr().unwrap().port();
+    let sender = thread::spawn(move || {
+        let (mut stream, _) = server.accept().unwrap();
+        let _ = stream.write_all(&[b'x'; 4096]);
+    });
+    let connection = TcpConnection::new(TcpStream::connect(("127.0.0.1", port)).unwrap()).unwrap();
+    let error = connection
+        .receive_all(1000, Duration::from_secs(5), &|| false)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "response is over the limit of 1000 bytes"
+    );
+    sender.join().unwrap();
+}
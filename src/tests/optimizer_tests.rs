@@ -0,0 +1,109 @@
+use crate::interpreter::*;
+
+// The optimized form of `source`, compared as source text against `expected`
+fn assert_optimizes(evaluator: &mut Evaluator, source: &str, expected: &str) {
+    let optimized = evaluator.optimize_expr(&Evaluator::parse(source).unwrap());
+    assert_eq!(optimized, Evaluator::parse(expected).unwrap(), "{}", source);
+}
+
+#[test]
+fn test_folds_constant_arithmetic() {
+    let mut evaluator = Evaluator::new();
+    assert_optimizes(&mut evaluator, "(+ 1 (* 2 3))", "7");
+    assert_optimizes(&mut evaluator, "(- 10 (expt 2 3))", "2");
+    assert_optimizes(&mut evaluator, "(* x (+ 1 2))", "(* x 3)");
+    // Division by zero is left to fail when it runs
+    assert_optimizes(&mut evaluator, "(/ 1 0)", "(/ 1 0)");
+
+    // Not folded where + might not be the builtin
+    assert_optimizes(
+        &mut evaluator,
+        "(let ((+ -)) (+ 1 2))",
+        "(let ((+ -)) (+ 1 2))",
+    );
+    assert_optimizes(
+        &mut evaluator,
+        "(for ((+ in fs) (x in (range (* 2 2)))) (+ x 1))",
+        "(for ((+ in fs) (x in (range 4))) (+ x 1))",
+    );
+    evaluator.eval_str("(defun * (a b) a)").unwrap();
+    assert_optimizes(&mut evaluator, "(* 2 3)", "(* 2 3)");
+}
+
+#[test]
+fn test_simplifies_progn_quote_and_cond() {
+    let mut evaluator = Evaluator::new();
+    assert_optimizes(&mut evaluator, "(progn (f (+ 1 1)))", "(f 2)");
+    assert_optimizes(
+        &mut evaluator,
+        "(list (quote 5) (quote a))",
+        "(list 5 (quote a))",
+    );
+    assert_optimizes(
+        &mut evaluator,
+        "(cond (() (f)) ((g) 1) (else 2) ((h) 3))",
+        "(cond ((g) 1) (else 2))",
+    );
+    assert_optimizes(&mut evaluator, "(cond (else (f) 2))", "(progn (f) 2)");
+    assert_optimizes(&mut evaluator, "(cond ((quote x) (+ 2 2)))", "4");
+    assert_optimizes(&mut evaluator, "(cond (() 1))", "()");
+
+    // Binding names, case datums and quoted data are left as written
+    assert_optimizes(
+        &mut evaluator,
+        "(let ((x (+ 1 1))) (case x ((1 2) (quote (+ 1 2)))))",
+        "(let ((x 2)) (case x ((1 2) (quote (+ 1 2)))))",
+    );
+}
+
+#[test]
+fn test_fuses_list_pipelines() {
+    let mut evaluator = Evaluator::new();
+    assert_optimizes(
+        &mut evaluator,
+        "(reduce + (mapcar f (filter p xs)) 0)",
+        "(transduce xs :filter p :map f :reduce + 0)",
+    );
+    assert_optimizes(
+        &mut evaluator,
+        "(remove q (mapcar (lambda (x) (* x (+ 1 1))) xs))",
+        "(transduce xs :map (lambda (x) (* x 2)) :remove q)",
+    );
+    assert_optimizes(
+        &mut evaluator,
+        "(reduce max (mapcar f xs))",
+        "(transduce xs :map f :reduce max)",
+    );
+
+    // A lone call, keyword arguments or a rebound builtin end the chain
+    assert_optimizes(&mut evaluator, "(mapcar f xs)", "(mapcar f xs)");
+    assert_optimizes(
+        &mut evaluator,
+        "(mapcar f (filter p xs :key k))",
+        "(mapcar f (filter p xs :key k))",
+    );
+    assert_optimizes(
+        &mut evaluator,
+        "(let ((filter g)) (mapcar f (filter p xs)))",
+        "(let ((filter g)) (mapcar f (filter p xs)))",
+    );
+}
+
+#[test]
+fn test_optimized_evaluation_gives_the_same_results() {
+    let program = [
+        "(defun fact (n) (cond ((= n 0) 1) (else (* n (fact (- n 1))))))",
+        "(fact (+ 2 3))",
+        "(let ((total 0)) (do ((i 0 (+ i 1))) ((= i (* 2 5)) total) (define total (+ total i))))",
+        "(progn (quote done))",
+        "(reduce + (mapcar (lambda (x) (* x x)) (filter (lambda (x) (> x 2)) (list 1 2 3 4))))",
+        "(mapcar fact (remove (lambda (n) (> n 4)) (list 3 5 4)))",
+        "(reduce + (mapcar fact (list)))",
+    ];
+    let mut plain = Evaluator::new();
+    let mut optimized = Evaluator::new();
+    optimized.optimize = true;
+    for form in program {
+        assert_eq!(plain.eval_str(form), optimized.eval_str(form), "{}", form);
+    }
+}
@@ -0,0 +1,64 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::ordered_map::OrderedMap;
+
+#[test]
+fn test_iterates_in_insertion_order() {
+    let mut map = OrderedMap::new();
+    for key in [5, 3, 9, 1] {
+        assert_eq!(map.insert(key, key * 10), None);
+    }
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec![5, 3, 9, 1]);
+
+    // Replacing a value keeps the key in place
+    assert_eq!(map.insert(3, 33), Some(30));
+    assert_eq!(
+        map.values().copied().collect::<Vec<_>>(),
+        vec![50, 33, 90, 10]
+    );
+    assert_eq!(map.len(), 4);
+
+    // A removed key that comes back goes at the end
+    assert_eq!(map.remove(&5), Some(50));
+    assert_eq!(map.remove(&5), None);
+    map.insert(5, 55);
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec![3, 9, 1, 5]);
+}
+
+#[test]
+fn test_removals_compact_without_losing_order() {
+    let mut map: OrderedMap<i64, i64> = (0..1000).map(|i| (i, -i)).collect();
+    for i in (0..1000).filter(|i| i % 3 != 0) {
+        assert_eq!(map.remove(&i), Some(-i));
+    }
+    assert_eq!(map.len(), 334);
+    let keys: Vec<i64> = map.keys().copied().collect();
+    assert_eq!(keys, (0..1000).step_by(3).collect::<Vec<_>>());
+    for key in keys {
+        assert_eq!(map.get(&key), Some(&-key));
+    }
+    assert!(!map.contains_key(&1));
+}
+
+#[test]
+fn test_hash_tables_keep_insertion_order() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define h (make-hash-table))").unwrap();
+    for key in ["\"zebra\"", ":mid", "7", "#\\a", "\"apple\""] {
+        eval.eval_str(&format!("(hash-set! h {} 0)", key)).unwrap();
+    }
+    eval.eval_str("(hash-remove! h :mid)").unwrap();
+    eval.eval_str("(hash-set! h 7 1)").unwrap();
+    assert_eq!(
+        eval.eval_str("(hash-keys h)").unwrap(),
+        Evaluator::parse("(\"zebra\" 7 #\\a \"apple\")").unwrap()
+    );
+    assert_eq!(
+        eval.eval_str("(hash-values h)").unwrap(),
+        Evaluator::parse("(0 1 0 0)").unwrap()
+    );
+    assert_eq!(
+        eval.eval_str("(mapcar car (hash->alist (alist->hash (list (cons :b 1) (cons :a 2)))))")
+            .unwrap(),
+        Evaluator::parse("(:b :a)").unwrap()
+    );
+}
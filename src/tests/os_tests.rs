@@ -0,0 +1,71 @@
+use super::helpers::*;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::*;
+
+#[test]
+fn test_getenv_and_setenv() {
+    let mut evaluator = Evaluator::new();
+    let eval = |evaluator: &mut Evaluator, source: &str| {
+        write_string(&evaluator.eval_str(source).unwrap())
+    };
+    assert_eq!(
+        eval(&mut evaluator, "(setenv \"ZEUS_OS_TEST\" \"on\")"),
+        "\"on\""
+    );
+    assert_eq!(eval(&mut evaluator, "(getenv \"ZEUS_OS_TEST\")"), "\"on\"");
+    assert_eq!(eval(&mut evaluator, "(setenv \"ZEUS_OS_TEST\" nil)"), "()");
+    assert_eq!(eval(&mut evaluator, "(getenv \"ZEUS_OS_TEST\")"), "()");
+    assert!(evaluator.eval_str("(setenv \"A=B\" \"x\")").is_err());
+    assert!(evaluator.eval_str("(setenv \"ZEUS_OS_TEST\" 5)").is_err());
+}
+
+#[test]
+fn test_command_line() {
+    let mut evaluator = Evaluator::new();
+    evaluator.command_line = vec!["input.txt".to_string(), "-v".to_string()];
+    assert_eq!(
+        write_string(&evaluator.fork().eval_str("(command-line)").unwrap()),
+        "(\"input.txt\" \"-v\")"
+    );
+}
+
+#[test]
+fn test_exit_unwinds_with_its_code() {
+    let mut evaluator = Evaluator::new();
+    let output = evaluator.capture_output();
+    let result = evaluator
+        .eval_source("(restart-case (unwind-protect (exit 2) (print \"bye\")) (skip () 0))");
+    assert!(matches!(result, Err(EvalError::Exit(2))));
+    assert_eq!(output.take(), "bye");
+    assert!(matches!(
+        evaluator.eval_source("(exit)"),
+        Err(EvalError::Exit(0))
+    ));
+    assert!(evaluator.eval_str("(exit \"no\")").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_process() {
+    assert_eq!(
+        eval_to_list("(run-process \"sh\" \"-c\" \"echo out; echo err >&2; exit 4\")"),
+        vec![
+            Expr::String("out\n".to_string()),
+            Expr::String("err\n".to_string()),
+            Expr::Integer(4)
+        ]
+    );
+    assert!(Evaluator::eval_once("(run-process \"/no/such/program\")")
+        .unwrap_err()
+        .starts_with("run-process: cannot run /no/such/program"));
+
+    let mut evaluator = Evaluator::new();
+    evaluator.allow_processes = false;
+    assert_eq!(
+        evaluator
+            .fork()
+            .eval_str("(run-process \"true\")")
+            .unwrap_err(),
+        "run-process is disabled in this evaluator"
+    );
+}
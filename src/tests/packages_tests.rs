@@ -0,0 +1,299 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::packages::{
+    add_dependency, add_package, install, Dependency, Manifest, PackageSource, PACKAGES_DIR,
+};
+use crate::interpreter::types::Expr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zeus_packages_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(path: &Path, text: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, text).unwrap();
+}
+
+#[test]
+fn test_manifest_dependencies() {
+    let manifest = Manifest::parse(
+        "[check]\nstrict-types = true\n\n[dependencies]\n\
+         json = { path = \"../zeus-json\" }  # vendored copy\n\
+         http = { git = \"https://example.com/http.git#main\", rev = \"v1.2\" }\n\
+         \"csv\" = { git = \"https://example.com/csv.git\" }\n",
+    )
+    .unwrap();
+    assert_eq!(
+        manifest.dependencies,
+        vec![
+            Dependency {
+                name: "json".to_string(),
+                source: PackageSource::Path(PathBuf::from("../zeus-json")),
+            },
+            Dependency {
+                name: "http".to_string(),
+                source: PackageSource::Git {
+                    url: "https://example.com/http.git#main".to_string(),
+                    rev: Some("v1.2".to_string()),
+                },
+            },
+            Dependency {
+                name: "csv".to_string(),
+                source: PackageSource::Git {
+                    url: "https://example.com/csv.git".to_string(),
+                    rev: None,
+                },
+            },
+        ]
+    );
+
+    for (bad, expected) in [
+        (
+            "json = \"../json\"",
+            "line 2: expected an inline table for `json`",
+        ),
+        (
+            "a/b = { path = \"x\" }",
+            "line 2: `a/b` isn't a package name",
+        ),
+        (
+            "json = { url = \"x\" }",
+            "line 2: unknown key `url` for `json`",
+        ),
+        (
+            "json = { path = \"x\", git = \"y\" }",
+            "line 2: `json` has both path and git",
+        ),
+        (
+            "json = { path = \"x\", rev = \"y\" }",
+            "line 2: `json`: rev needs git",
+        ),
+        ("json = { }", "line 2: `json` needs a path or git"),
+        (
+            "json = { git = \"--upload-pack=touch pwned\" }",
+            "line 2: `--upload-pack=touch pwned` can't start with `-` for `json`",
+        ),
+        (
+            "json = { git = \"https://example.com/json.git\", rev = \"--orphan=x\" }",
+            "line 2: `--orphan=x` can't start with `-` for `json`",
+        ),
+    ] {
+        let error = Manifest::parse(&format!("[dependencies]\n{}\n", bad)).unwrap_err();
+        assert!(error.starts_with(expected), "{}: {}", bad, error);
+    }
+}
+
+#[test]
+fn test_add_dependency_edits_the_manifest() {
+    let dir = scratch_dir("add");
+    let path = dir.join("zeus.toml");
+    let json = Dependency {
+        name: "json".to_string(),
+        source: PackageSource::Path(PathBuf::from("../json")),
+    };
+    add_dependency(&path, &json).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "[dependencies]\njson = { path = \"../json\" }\n"
+    );
+
+    fs::write(
+        &path,
+        "[dependencies]\njson = { path = \"../json\" }\n\n[check.rules]\ntrailing-whitespace = false\n",
+    )
+    .unwrap();
+    let http = Dependency {
+        name: "http".to_string(),
+        source: PackageSource::Git {
+            url: "https://example.com/http.git".to_string(),
+            rev: Some("v2".to_string()),
+        },
+    };
+    add_dependency(&path, &http).unwrap();
+    // Added again, it replaces the entry in place
+    let json = Dependency {
+        source: PackageSource::Path(PathBuf::from("vendor/json")),
+        ..json
+    };
+    add_dependency(&path, &json).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "[dependencies]\n\
+         json = { path = \"vendor/json\" }\n\
+         http = { git = \"https://example.com/http.git\", rev = \"v2\" }\n\
+         \n[check.rules]\ntrailing-whitespace = false\n"
+    );
+    assert_eq!(
+        Manifest::load(&path).unwrap().dependencies,
+        vec![json, http]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_failed_add_leaves_the_manifest_unchanged() {
+    let dir = scratch_dir("add_fails");
+    let manifest = dir.join("zeus.toml");
+    let original = "[dependencies]\njson = { path = \"json\" }\n";
+    fs::write(&manifest, original).unwrap();
+    write(&dir.join("docs/README.md"), "no sources here\n");
+
+    for source in [
+        PackageSource::Git {
+            url: "file:///nonexistent".to_string(),
+            rev: None,
+        },
+        PackageSource::Path(PathBuf::from("docs")),
+        PackageSource::Path(PathBuf::from("missing")),
+    ] {
+        let dependency = Dependency {
+            name: "broken".to_string(),
+            source,
+        };
+        assert!(add_package(&manifest, &dir, &dependency).is_err());
+        assert_eq!(fs::read_to_string(&manifest).unwrap(), original);
+    }
+
+    write(&dir.join("lib/util.lisp"), "(define util 1)\n");
+    let util = Dependency {
+        name: "util".to_string(),
+        source: PackageSource::Path(PathBuf::from("lib")),
+    };
+    assert_eq!(
+        add_package(&manifest, &dir, &util).unwrap(),
+        "util: 1 file from lib"
+    );
+    assert_eq!(
+        fs::read_to_string(&manifest).unwrap(),
+        format!("{}util = {{ path = \"lib\" }}\n", original)
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_install_and_require_packages() {
+    let dir = scratch_dir("install");
+    write(
+        &dir.join("lib/greet.lisp"),
+        "(defun greet (n) (format nil \"hi ~a\" n))\n",
+    );
+    write(
+        &dir.join("lib/text/shout.lisp"),
+        "(require \"greet\")\n(defun shout (n) (format nil \"~a!\" (greet n)))\n",
+    );
+    write(&dir.join("lib/README.md"), "not a source\n");
+    write(&dir.join("lib/.git/config"), "(not a source either)\n");
+    write(
+        &dir.join("app/zeus.toml"),
+        "[dependencies]\ngreet = { path = \"../lib\" }\n",
+    );
+    write(&dir.join("app/local.lisp"), "(define local-value 7)\n");
+
+    let installed = install(&dir.join("app/zeus.toml")).unwrap();
+    assert_eq!(installed, vec!["greet: 2 files from ../lib".to_string()]);
+    let vendored = dir.join("app").join(PACKAGES_DIR).join("greet");
+    assert!(vendored.join("greet.lisp").is_file());
+    assert!(vendored.join("text/shout.lisp").is_file());
+    assert!(!vendored.join("README.md").exists());
+    assert!(!vendored.join(".git").exists());
+
+    let mut eval = Evaluator::new();
+    eval.set_require_dir(&dir.join("app"));
+    assert_eq!(
+        eval.eval_str("(require \"local\")").unwrap(),
+        eval.eval_str("t").unwrap()
+    );
+    assert_eq!(eval.eval_str("local-value").unwrap(), Expr::Integer(7));
+    // A module requires its sibling by name; the package's entry file is then loaded already
+    eval.eval_str("(require \"greet/text/shout\")").unwrap();
+    assert_eq!(
+        eval.eval_str("(shout \"bob\")").unwrap(),
+        Expr::String("hi bob!".to_string())
+    );
+    assert_eq!(
+        eval.eval_str("(require \"greet\")").unwrap(),
+        Expr::new_list(vec![])
+    );
+
+    let error = eval.eval_str("(require \"missing\")").unwrap_err();
+    assert!(
+        error.to_string().contains("no installed package missing"),
+        "{}",
+        error
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_require_retries_a_file_that_failed() {
+    let dir = scratch_dir("retry");
+    write(
+        &dir.join("shaky.lisp"),
+        "(define shaky-loads (+ 1 1))\n(car 5)\n",
+    );
+    let mut eval = Evaluator::new();
+    eval.set_require_dir(&dir);
+    assert!(eval.eval_str("(require \"shaky\")").is_err());
+
+    write(&dir.join("shaky.lisp"), "(define shaky-loads 3)\n");
+    eval.eval_str("(require \"shaky\")").unwrap();
+    assert_eq!(eval.eval_str("shaky-loads").unwrap(), Expr::Integer(3));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_install_git_dependency_at_a_rev() {
+    let dir = scratch_dir("git");
+    let repo = dir.join("lib");
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args(["-c", "user.name=zeus", "-c", "user.email=zeus@example.com"])
+            .args(args)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    };
+    write(&repo.join("old.lisp"), "(define version 1)\n");
+    if !git(&["init", "--quiet"]) {
+        // No git to test with
+        return;
+    }
+    assert!(git(&["add", "."]));
+    assert!(git(&["commit", "--quiet", "-m", "one"]));
+    assert!(git(&["tag", "v1"]));
+    write(&repo.join("new.lisp"), "(define version 2)\n");
+    assert!(git(&["add", "."]));
+    assert!(git(&["commit", "--quiet", "-m", "two"]));
+
+    let url = repo.display().to_string();
+    write(
+        &dir.join("app/zeus.toml"),
+        &format!(
+            "[dependencies]\nlib = {{ git = \"{}\", rev = \"v1\" }}\n",
+            url
+        ),
+    );
+    let installed = install(&dir.join("app/zeus.toml")).unwrap();
+    assert_eq!(installed, vec![format!("lib: 1 file from {} at v1", url)]);
+    let vendored = dir.join("app").join(PACKAGES_DIR).join("lib");
+    assert!(vendored.join("old.lisp").is_file());
+    assert!(!vendored.join("new.lisp").exists());
+
+    // A rev that isn't in the repository fails without touching the installed copy
+    write(
+        &dir.join("app/zeus.toml"),
+        &format!(
+            "[dependencies]\nlib = {{ git = \"{}\", rev = \"v9\" }}\n",
+            url
+        ),
+    );
+    assert!(install(&dir.join("app/zeus.toml")).is_err());
+    assert!(vendored.join("old.lisp").is_file());
+    fs::remove_dir_all(&dir).unwrap();
+}
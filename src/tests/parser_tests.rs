@@ -1,4 +1,4 @@
-use crate::interpreter::types::SymbolData;
+use crate::interpreter::types::{Symbol, SymbolData};
 use crate::interpreter::*;
 
 #[test]
@@ -14,7 +14,10 @@ fn test_parser_symbol() {
     let tokens = vec![Token::Symbol("x".to_string())];
     let mut parser = Parser::new(tokens);
     let expr = parser.parse().unwrap();
-    assert_eq!(expr, Expr::Symbol(SymbolData::Interned("x".to_string())));
+    assert_eq!(
+        expr,
+        Expr::Symbol(SymbolData::Interned(Symbol::intern("x")))
+    );
 }
 
 #[test]
@@ -38,8 +41,8 @@ fn test_parser_list() {
     let expr = parser.parse().unwrap();
     assert_eq!(
         expr,
-        Expr::List(vec![
-            Expr::Symbol(SymbolData::Interned("+".to_string())),
+        Expr::new_list(vec![
+            Expr::Symbol(SymbolData::Interned(Symbol::intern("+"))),
             Expr::Integer(1),
             Expr::Integer(2),
         ])
@@ -63,10 +66,10 @@ fn test_parser_nested_list() {
     let expr = parser.parse().unwrap();
     assert_eq!(
         expr,
-        Expr::List(vec![
-            Expr::Symbol(SymbolData::Interned("+".to_string())),
-            Expr::List(vec![
-                Expr::Symbol(SymbolData::Interned("*".to_string())),
+        Expr::new_list(vec![
+            Expr::Symbol(SymbolData::Interned(Symbol::intern("+"))),
+            Expr::new_list(vec![
+                Expr::Symbol(SymbolData::Interned(Symbol::intern("*"))),
                 Expr::Integer(2),
                 Expr::Integer(3),
             ]),
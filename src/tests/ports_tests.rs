@@ -0,0 +1,212 @@
+use super::helpers::*;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::*;
+use std::rc::Rc;
+
+#[test]
+fn test_capture_output_collects_prints() {
+    let mut evaluator = Evaluator::new();
+    let output = evaluator.capture_output();
+    let errors = StringPort::new();
+    evaluator.error_output = Rc::new(errors.clone());
+    evaluator
+        .eval_str(
+            "(progn (print 1 \"a\") (println \" b\") (eprintln \"oops\") (format t \"~d~%\" 7))",
+        )
+        .unwrap();
+    assert_eq!(output.take(), "1a b\n7\n");
+    assert_eq!(errors.take(), "oops\n");
+    assert_eq!(output.take(), "");
+
+    // Forks write to the same ports
+    let mut fork = evaluator.fork();
+    fork.eval_str("(eprint 1 2)").unwrap();
+    assert_eq!(errors.take(), "12");
+}
+
+#[test]
+fn test_with_output_to_string() {
+    assert_eq!(
+        eval_to_string("(with-output-to-string (print \"x = \") (print (+ 1 2)))"),
+        "x = 3"
+    );
+    assert_eq!(eval_to_string("(with-output-to-string)"), "");
+
+    let mut evaluator = Evaluator::new();
+    let output = evaluator.capture_output();
+    let result = evaluator
+        .eval_str(
+            "(progn
+               (print \"outer \")
+               (define inner (with-output-to-string
+                               (print \"a\")
+                               (print (with-output-to-string (print \"b\")))
+                               (print \"c\")))
+               (println \"done\")
+               inner)",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::String("abc".to_string()));
+    assert_eq!(output.take(), "outer done\n");
+
+    // Leaving by throw or error puts the output back too
+    evaluator
+        .eval_str("(catch (quote out) (with-output-to-string (print 1) (throw (quote out) 2)))")
+        .unwrap();
+    assert!(evaluator
+        .eval_str("(with-output-to-string (print 1) (car 1))")
+        .is_err());
+    evaluator.eval_str("(print \"still here\")").unwrap();
+    assert_eq!(output.take(), "still here");
+}
+
+#[test]
+fn test_string_input_port_reads_lines() {
+    let input = StringInputPort::new("first\nsecond\n\nlast");
+    let reader = input.clone();
+    assert_eq!(input.read_line().as_deref(), Some("first\n"));
+    assert_eq!(reader.read_line().as_deref(), Some("second\n"));
+    assert_eq!(input.read_line().as_deref(), Some("\n"));
+    assert_eq!(input.read_line().as_deref(), Some("last"));
+    assert_eq!(reader.read_line(), None);
+    assert_eq!(StringInputPort::default().read_line(), None);
+}
+
+#[test]
+fn test_reading_from_the_input_port() {
+    let mut evaluator = Evaluator::new();
+    evaluator.input = Rc::new(StringInputPort::new("hello world\r\nλx\n\nlast"));
+    assert_eq!(
+        evaluator
+            .eval_str("(list (peek-char) (read-char) (peek-char))")
+            .unwrap(),
+        Expr::new_list(vec![
+            Expr::Character('h'),
+            Expr::Character('h'),
+            Expr::Character('e')
+        ])
+    );
+    // read-line finishes the line read-char started
+    assert_eq!(
+        evaluator.eval_str("(read-line)").unwrap(),
+        Expr::String("ello world".to_string())
+    );
+    assert_eq!(
+        evaluator.eval_str("(read-char)").unwrap(),
+        Expr::Character('λ')
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(list (read-char) (read-char))")
+            .unwrap(),
+        Expr::new_list(vec![Expr::Character('x'), Expr::Character('\n')])
+    );
+    assert_eq!(
+        evaluator.eval_str("(read-line)").unwrap(),
+        Expr::String(String::new())
+    );
+    assert_eq!(
+        evaluator.eval_str("(read-line)").unwrap(),
+        Expr::String("last".to_string())
+    );
+    for form in ["(read-line)", "(read-char)", "(peek-char)"] {
+        assert_eq!(evaluator.eval_str(form).unwrap(), Expr::new_list(vec![]));
+    }
+    assert!(evaluator.eval_str("(read-line 1)").is_err());
+}
+
+#[test]
+fn test_interactive_script_with_canned_input() {
+    let mut evaluator = Evaluator::new();
+    evaluator.input = Rc::new(StringInputPort::new("Ada\n3\n"));
+    let output = evaluator.capture_output();
+    evaluator
+        .eval_str(
+            "(progn
+               (print \"Name? \")
+               (define name (read-line))
+               (print \"Count? \")
+               (define count (read-line))
+               (println (format nil \"~a x ~a\" name count)))",
+        )
+        .unwrap();
+    assert_eq!(output.take(), "Name? Count? Ada x 3\n");
+}
+
+#[test]
+fn test_with_open_file_reads_and_writes_lines() {
+    let path = std::env::temp_dir().join(format!("zeus_ports_{}.txt", std::process::id()));
+    let path_text = format!("{:?}", path.display().to_string());
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_str(&format!("(define path {})", path_text))
+        .unwrap();
+
+    assert_eq!(
+        evaluator
+            .eval_str(
+                "(with-open-file (out path :direction :output)
+                   (write-line \"first\" out)
+                   (write-line 42 out)
+                   (type-of out))",
+            )
+            .unwrap(),
+        evaluator.eval_str("(quote port)").unwrap()
+    );
+    evaluator
+        .eval_str(
+            "(with-open-file (out path :direction :output :if-exists :append)
+               (write-line \"last\" out))",
+        )
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\n42\nlast\n");
+
+    assert_eq!(
+        evaluator
+            .eval_str(
+                "(with-open-file (in path)
+                   (let ((lines (list)) (line (read-line in)))
+                     (while line
+                       (define lines (cons line lines))
+                       (define line (read-line in)))
+                     (reverse lines)))",
+            )
+            .unwrap(),
+        evaluator
+            .eval_str("(list \"first\" \"42\" \"last\")")
+            .unwrap()
+    );
+
+    // The port is closed on the way out, even when the body fails, and stays usable as a value
+    evaluator.eval_str("(define kept (vector))").unwrap();
+    assert!(evaluator
+        .eval_str("(with-open-file (in path) (vector-push kept in) (car 5))")
+        .is_err());
+    evaluator
+        .eval_str("(define kept (vector-ref kept 0))")
+        .unwrap();
+    assert!(write_string(&evaluator.eval_str("kept").unwrap()).ends_with(" closed>"));
+    assert!(evaluator
+        .eval_str("(read-line kept)")
+        .unwrap_err()
+        .to_string()
+        .ends_with("is closed"));
+
+    // Without a port, write-line goes to the evaluator's output
+    let output = evaluator.capture_output();
+    evaluator.eval_str("(write-line (list 1 \"a\"))").unwrap();
+    assert_eq!(output.take(), "(1 a)\n");
+
+    for bad in [
+        "(with-open-file (in path) (write-line 1 in))",
+        "(with-open-file (out path :direction :sideways) 1)",
+        "(with-open-file (in path :if-exists :append) 1)",
+        "(with-open-file (in \"/no/such/zeus/file\") 1)",
+        "(with-open-file (in) 1)",
+        "(with-open-file in 1)",
+        "(write-line 1 2)",
+    ] {
+        assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,105 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::Expr;
+
+fn eval(source: &str) -> Expr {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(defun big (n) (if (> n 2) (* n 10) nil))")
+        .unwrap();
+    eval.eval_str(source).unwrap()
+}
+
+fn ints(values: &[i64]) -> Expr {
+    Expr::new_list(values.iter().map(|&n| Expr::Integer(n)).collect())
+}
+
+#[test]
+fn test_list_positions() {
+    assert_eq!(eval("(first (list 1 2 3))"), Expr::Integer(1));
+    assert_eq!(eval("(rest (list 1 2 3))"), ints(&[2, 3]));
+    assert_eq!(eval("(second (list 1 2 3))"), Expr::Integer(2));
+    assert_eq!(eval("(third (list 1 2 3))"), Expr::Integer(3));
+    // Past the end is nil, as is the first of nothing
+    assert_eq!(eval("(fourth (list 1 2 3))"), ints(&[]));
+    assert_eq!(eval("(first nil)"), ints(&[]));
+}
+
+#[test]
+fn test_assoc_default() {
+    assert_eq!(
+        eval("(assoc-default \"b\" (list (cons \"a\" 1) (cons \"b\" 2)) 0)"),
+        Expr::Integer(2)
+    );
+    assert_eq!(
+        eval("(assoc-default \"z\" (list (cons \"a\" 1)) 0)"),
+        Expr::Integer(0)
+    );
+}
+
+#[test]
+fn test_predicate_searches() {
+    assert_eq!(eval("(count-if big (list 1 2 3 4))"), Expr::Integer(2));
+    assert_eq!(eval("(some big (list 1 2 3 4))"), Expr::Integer(30));
+    assert_eq!(eval("(some big (list 1 2))"), ints(&[]));
+    assert_eq!(eval("(every big (list 3 4))"), eval("t"));
+    assert_eq!(eval("(every big (list 3 1))"), ints(&[]));
+    assert_eq!(eval("(every big nil)"), eval("t"));
+
+    // The predicate sees the caller's variables, not the searches' own
+    assert_eq!(
+        eval("(let ((pred 3)) (count-if (lambda (x) (> x pred)) (list 1 10)))"),
+        Expr::Integer(1)
+    );
+    assert_eq!(
+        eval("(let ((items 5)) (some (lambda (x) (> x items)) (list 1 10)))"),
+        eval("t")
+    );
+    assert_eq!(
+        eval("(let ((found 5) (holds 0)) (every (lambda (x) (> x (+ found holds))) (list 6 7)))"),
+        eval("t")
+    );
+}
+
+#[test]
+fn test_functions_that_build_functions() {
+    assert_eq!(eval("(funcall (funcall (curry -) 10) 3)"), Expr::Integer(7));
+    assert_eq!(
+        eval("(mapcar (funcall (curry +) 1) (list 1 2))"),
+        ints(&[2, 3])
+    );
+    assert_eq!(
+        eval("(filter (complement big) (list 1 2 3 4))"),
+        ints(&[1, 2])
+    );
+    assert_eq!(eval("(funcall (flip list) 1 2 3)"), ints(&[2, 1, 3]));
+
+    // The values are quoted in, so they don't depend on the caller's bindings later
+    assert_eq!(
+        eval("(let ((add (let ((x 5)) (funcall (curry +) x)))) (define x 100) (funcall add 1))"),
+        Expr::Integer(6)
+    );
+}
+
+#[test]
+fn test_prelude_definitions_are_ordinary_globals() {
+    let mut eval = Evaluator::new();
+    // Not changes the program made
+    assert!(eval.environment.take_changed_globals().is_empty());
+    eval.eval_str("(defun second (items) 42)").unwrap();
+    assert_eq!(
+        eval.eval_str("(second (list 1 2))").unwrap(),
+        Expr::Integer(42)
+    );
+
+    // Redefining it in one evaluator leaves the next one's alone
+    assert_eq!(
+        Evaluator::new().eval_str("(second (list 1 2))").unwrap(),
+        Expr::Integer(2)
+    );
+}
+
+#[test]
+fn test_without_prelude() {
+    let mut eval = Evaluator::without_prelude();
+    assert!(eval.eval_str("(second (list 1 2))").is_err());
+    assert_eq!(eval.eval_str("(car (list 1 2))").unwrap(), Expr::Integer(1));
+}
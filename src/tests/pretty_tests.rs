@@ -0,0 +1,58 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::pretty::pretty_print;
+use crate::interpreter::types::Expr;
+
+#[test]
+fn test_pretty_print_keeps_short_values_flat() {
+    let mut eval = Evaluator::new();
+    let value = eval.eval_str("(quote (a \"b\" #\\c :d [1 2]))").unwrap();
+    assert_eq!(pretty_print(&value, 80), "(a \"b\" #\\c :d [1 2])");
+}
+
+#[test]
+fn test_pretty_print_breaks_long_forms() {
+    let mut eval = Evaluator::new();
+    let value = eval
+        .eval_str("(quote (defun area (width height) (let ((w width) (h height)) (* w h))))")
+        .unwrap();
+    assert_eq!(
+        pretty_print(&value, 30),
+        "(defun area (width height)\n  (let ((w width) (h height))\n    (* w h)))"
+    );
+
+    let value = eval.eval_str("(quote (1 22 333 4444))").unwrap();
+    assert_eq!(pretty_print(&value, 8), "(1 22\n 333\n 4444)");
+
+    let value = eval.eval_str("(quote (+ alpha beta gamma))").unwrap();
+    assert_eq!(pretty_print(&value, 12), "(+ alpha\n   beta\n   gamma)");
+}
+
+#[test]
+fn test_pretty_print_fills_lines_of_data() {
+    let mut eval = Evaluator::new();
+    let value = eval.eval_str("(iota 12 1)").unwrap();
+    assert_eq!(pretty_print(&value, 12), "(1 2 3 4 5 6\n 7 8 9 10 11\n 12)");
+
+    let value = eval.eval_str("(list->vector (iota 12 1))").unwrap();
+    assert_eq!(pretty_print(&value, 12), "[1 2 3 4 5 6\n 7 8 9 10 11\n 12]");
+
+    // An element that doesn't fit on its own line is still broken up by its own layout
+    let value = eval
+        .eval_str("(quote (1 2 (when ready (start engine) (wait)) 3 4))")
+        .unwrap();
+    assert_eq!(
+        pretty_print(&value, 20),
+        "(1 2\n (when ready\n   (start engine)\n   (wait))\n 3 4)"
+    );
+}
+
+#[test]
+fn test_pprint_builtin() {
+    let mut eval = Evaluator::new();
+    assert_eq!(
+        eval.eval_str("(pprint (list 1 2 3) 10)").unwrap(),
+        Expr::new_list(vec![])
+    );
+    assert!(eval.eval_str("(pprint (list 1 2) 0)").is_err());
+    assert!(eval.eval_str("(pprint)").is_err());
+}
@@ -0,0 +1,151 @@
+use super::helpers::with_main_thread_stack;
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::pretty::pretty_print;
+use crate::interpreter::printer::{display_string, write_string, PrintMode, Printer};
+use crate::interpreter::types::Expr;
+
+#[test]
+fn test_display_and_write_modes() {
+    let mut eval = Evaluator::new();
+    let value = eval
+        .eval_str("(list \"a \\\"b\\\"\" #\\c #\\space :key (quote sym) (cons 1 2) [1 2.5])")
+        .unwrap();
+    assert_eq!(
+        display_string(&value),
+        "(a \"b\" c   key sym (1 . 2) [1 2.5])"
+    );
+    assert_eq!(
+        write_string(&value),
+        "(\"a \\\"b\\\"\" #\\c #\\space :key sym (1 . 2) [1 2.5])"
+    );
+
+    let gensym = eval.eval_str("(gensym \"g\")").unwrap();
+    assert!(write_string(&gensym).starts_with("#:g"));
+}
+
+#[test]
+fn test_depth_and_length_limits() {
+    let mut eval = Evaluator::new();
+    let value = eval.eval_str("(quote (1 (2 (3 (4))) 5 6 7))").unwrap();
+    let printer = Printer::new(PrintMode::Write);
+    assert_eq!(printer.print(&value), "(1 (2 (3 (4))) 5 6 7)");
+    assert_eq!(printer.with_max_depth(2).print(&value), "(1 (2 #) 5 6 7)");
+    assert_eq!(
+        printer.with_max_length(3).print(&value),
+        "(1 (2 (3 (4))) 5 ...)"
+    );
+    assert_eq!(printer.with_max_depth(0).print(&value), "#");
+
+    let dotted = eval.eval_str("(cons 1 (cons 2 3))").unwrap();
+    assert_eq!(printer.print(&dotted), "(1 2 . 3)");
+    assert_eq!(printer.with_max_length(1).print(&dotted), "(1 ...)");
+}
+
+#[test]
+fn test_self_referential_containers_print() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define v (vector 1 2))").unwrap();
+    eval.eval_str("(vector-set! v 1 v)").unwrap();
+    assert_eq!(
+        write_string(&eval.eval_str("v").unwrap()),
+        "[1 #<circular>]"
+    );
+
+    eval.eval_str("(define h (make-hash-table))").unwrap();
+    eval.eval_str("(hash-set! h :self h)").unwrap();
+    assert_eq!(
+        write_string(&eval.eval_str("h").unwrap()),
+        "#<hash-table :self #<circular>>"
+    );
+}
+
+#[test]
+fn test_printing_stops_at_the_nesting_limit() {
+    with_main_thread_stack(|| {
+        let mut deep = Expr::new_list(vec![]);
+        for _ in 0..1500 {
+            deep = Expr::new_list(vec![Expr::Integer(1), deep]);
+        }
+        let printed = write_string(&deep);
+        assert!(printed.starts_with("(1 (1 (1 "));
+        assert!(printed.contains(" #)"));
+        assert!(pretty_print(&deep, 40).contains('#'));
+    });
+}
+
+#[test]
+fn test_strings_round_trip_through_the_reader() {
+    let samples = [
+        "",
+        "plain",
+        "say \"hi\"",
+        "back\\slash",
+        "line\nbreak",
+        "tab\there",
+        "crlf\r\n",
+        "\\n is not a newline",
+        "trailing backslash \\",
+        "unicode λ → ok",
+        "bell \u{7}",
+    ];
+    for sample in samples {
+        let value = Expr::String(sample.to_string());
+        let written = write_string(&value);
+        assert!(!written[1..written.len() - 1].contains('\n'), "{}", written);
+        assert_eq!(Evaluator::parse(&written).unwrap(), value, "{}", written);
+    }
+
+    assert_eq!(
+        write_string(&Expr::String("a\"b\\c\nd\te\rf".to_string())),
+        r#""a\"b\\c\nd\te\rf""#
+    );
+
+    // Inside data too
+    let mut eval = Evaluator::new();
+    let value = eval
+        .eval_str("(list \"x\\ny\" (vector \"q\\\"\"))")
+        .unwrap();
+    let written = write_string(&value);
+    assert_eq!(written, r#"("x\ny" ["q\""])"#);
+    assert_eq!(
+        eval.eval_str(&format!("(quote {})", written)).unwrap(),
+        value
+    );
+}
+
+#[test]
+fn test_floats_round_trip_through_the_reader() {
+    let samples = [
+        0.0,
+        -0.0,
+        1.0,
+        -3.0,
+        0.1,
+        1.0 / 3.0,
+        2.5e-3,
+        123456.789,
+        1e21,
+        1e300,
+        -1.5e-10,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+    ];
+    for sample in samples {
+        let written = write_string(&Expr::Float(sample));
+        match Evaluator::parse(&written).unwrap() {
+            Expr::Float(read) => assert_eq!(read.to_bits(), sample.to_bits(), "{}", written),
+            other => panic!("{} read back as {:?}", written, other),
+        }
+    }
+
+    let print = |f: f64| write_string(&Expr::Float(f));
+    assert_eq!(print(2.0), "2.0");
+    assert_eq!(print(0.1), "0.1");
+    assert_eq!(print(1e300), "1e300");
+    assert_eq!(print(f64::INFINITY), "+inf.0");
+    assert_eq!(print(f64::NEG_INFINITY), "-inf.0");
+    assert_eq!(print(f64::NAN), "+nan.0");
+    assert!(matches!(Evaluator::parse("+nan.0").unwrap(), Expr::Float(f) if f.is_nan()));
+}
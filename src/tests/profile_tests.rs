@@ -0,0 +1,30 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::profile::profile_program;
+
+#[test]
+fn test_profile_times_each_top_level_form() {
+    let source = "(define limit 20000)\n\n(defun spin (n)\n  (for ((x in (range n))) (* x x)))\n(spin limit)\n(car 1)\n(define never 1)\n";
+    let mut evaluator = Evaluator::new();
+    let profile = profile_program(&mut evaluator, source);
+
+    let lines: Vec<(usize, usize)> = profile
+        .forms
+        .iter()
+        .map(|form| (form.line, form.end_line))
+        .collect();
+    assert_eq!(lines, vec![(1, 1), (3, 4), (5, 5), (6, 6)]);
+    assert_eq!(profile.error.as_ref().map(|(line, _)| *line), Some(6));
+    assert!(evaluator.eval_str("never").is_err());
+
+    // The call does far more work than the definitions
+    let spin = &profile.forms[2];
+    assert!(profile.forms.iter().all(|form| form.steps <= spin.steps));
+    assert!(spin.steps > 1000);
+    assert_eq!(profile.hottest(), Some(spin));
+    assert!(profile.share(spin) > 0.5);
+
+    let heat = profile.line_heat();
+    assert_eq!(heat.len(), 5);
+    assert!(heat.contains(&(5, 1.0)));
+    assert!(heat.iter().all(|(line, heat)| *line == 5 || *heat < 1.0));
+}
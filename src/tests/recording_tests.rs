@@ -0,0 +1,144 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::{Expr, Symbol};
+
+#[test]
+fn test_recording_logs_forms_and_global_changes() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define before-recording 1)").unwrap();
+    assert_eq!(
+        eval.eval_str("(recorded-forms)").unwrap(),
+        Expr::new_list(vec![])
+    );
+
+    eval.eval_str("(start-recording)").unwrap();
+    eval.eval_str("(define x 1)").unwrap();
+    eval.eval_str("(define x 2)").unwrap();
+    eval.eval_str("(+ x 1)").unwrap();
+    assert!(eval.eval_str("(progn (define y 5) (car 1))").is_err());
+    // An equal value is no change
+    eval.eval_str("(define x 2)").unwrap();
+
+    let recording = eval.recording.as_ref().unwrap();
+    let forms = recording.forms();
+    assert_eq!(forms.len(), 5);
+    assert_eq!(forms[0].source, "(define x 1)");
+    assert_eq!(forms[0].changes[0].before, None);
+    assert_eq!(forms[0].changes[0].after, Some(Expr::Integer(1)));
+    assert_eq!(forms[1].changes[0].before, Some(Expr::Integer(1)));
+    assert!(forms[2].changes.is_empty());
+    assert!(!forms[3].succeeded);
+    assert_eq!(forms[3].changes[0].name, "y");
+    assert!(forms[4].changes.is_empty());
+
+    // Session commands aren't recorded
+    let Expr::List(shown) = eval.eval_str("(recorded-forms)").unwrap() else {
+        panic!("recorded-forms should return a list");
+    };
+    assert_eq!(shown.len(), 5);
+    assert_eq!(
+        shown[0],
+        Evaluator::parse("(1 \"(define x 1)\" :ok ((x :added 1)))").unwrap()
+    );
+    assert_eq!(
+        shown[1],
+        Evaluator::parse("(2 \"(define x 2)\" :ok ((x :changed 1 2)))").unwrap()
+    );
+    assert_eq!(
+        shown[3],
+        Evaluator::parse("(4 \"(progn (define y 5) (car 1))\" :error ((y :added 5)))").unwrap()
+    );
+
+    assert_eq!(eval.eval_str("(stop-recording)").unwrap(), Expr::Integer(5));
+    assert!(eval.recording.is_none());
+    assert_eq!(
+        eval.eval_str("(stop-recording)").unwrap(),
+        Expr::new_list(vec![])
+    );
+}
+
+#[test]
+fn test_rewind_replays_the_forms_before() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define before-recording 1)").unwrap();
+    eval.eval_str("(start-recording)").unwrap();
+    eval.eval_str("(define counter 0)").unwrap();
+    eval.eval_str("(define counter (+ counter 1))").unwrap();
+    eval.eval_str("(print \"side effect\")").unwrap();
+    eval.eval_str("(defun twice (n) (* n 2))").unwrap();
+    eval.eval_str("(define counter (twice counter))").unwrap();
+    eval.eval_str("(define later (quote gone))").unwrap();
+    assert_eq!(eval.eval_str("counter").unwrap(), Expr::Integer(2));
+
+    let output = eval.capture_output();
+    // Rewinding to before counter was doubled, form 5
+    assert_eq!(eval.eval_str("(rewind 5)").unwrap(), Expr::Integer(4));
+    assert_eq!(output.take(), "");
+    assert_eq!(eval.recording.as_ref().unwrap().forms().len(), 4);
+    assert_eq!(eval.eval_str("counter").unwrap(), Expr::Integer(1));
+    assert!(eval.eval_str("later").is_err());
+    // Only what was recorded comes back
+    assert!(eval.eval_str("before-recording").is_err());
+    // The session log isn't replayed into
+    assert_eq!(
+        eval.session
+            .entries()
+            .iter()
+            .filter(|entry| entry.source == "(define counter (+ counter 1))")
+            .count(),
+        1
+    );
+
+    // Recording goes on from there, and can be rewound again
+    eval.eval_str("(define counter 10)").unwrap();
+    assert_eq!(eval.rewind(1), Ok(0));
+    assert!(eval.eval_str("counter").is_err());
+
+    let error = eval.eval_str("(rewind 3)").unwrap_err();
+    assert_eq!(error, "rewind: no form 3 among the 1 recorded");
+    eval.eval_str("(stop-recording)").unwrap();
+    assert!(eval
+        .eval_str("(rewind 1)")
+        .unwrap_err()
+        .contains("start-recording"));
+}
+
+#[test]
+fn test_recording_history_queries() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define x 0)").unwrap();
+    eval.eval_str("(start-recording)").unwrap();
+    eval.eval_str("(define x 1)").unwrap();
+    eval.eval_str("(define y (quote a))").unwrap();
+    eval.eval_str("(define x 2)").unwrap();
+
+    let recording = eval.recording.as_ref().unwrap();
+    let x = Symbol::intern("x");
+    let value_after = |form| recording.globals_after(form).unwrap().get(x).cloned();
+    assert_eq!(value_after(0), Some(Expr::Integer(0)));
+    assert_eq!(value_after(2), Some(Expr::Integer(1)));
+    assert_eq!(value_after(3), Some(Expr::Integer(2)));
+    assert!(recording.globals_after(4).is_none());
+
+    let changes = recording.changes_between(0, 3).unwrap();
+    let names: Vec<&str> = changes.iter().map(|change| change.name.as_str()).collect();
+    assert_eq!(names, vec!["x", "y"]);
+    assert_eq!(changes[0].before, Some(Expr::Integer(0)));
+    assert!(recording.changes_between(1, 1).unwrap().is_empty());
+    // Backwards, y goes away
+    assert_eq!(recording.changes_between(3, 1).unwrap()[1].after, None);
+
+    let forms: Vec<usize> = recording
+        .history_of("x")
+        .iter()
+        .map(|(form, _)| *form)
+        .collect();
+    assert_eq!(forms, vec![1, 3]);
+    assert_eq!(
+        eval.eval_str("(global-history (quote x))").unwrap(),
+        Evaluator::parse("((1 :changed 0 1) (3 :changed 1 2))").unwrap()
+    );
+    assert_eq!(
+        eval.eval_str("(global-history (quote nowhere))").unwrap(),
+        Expr::new_list(vec![])
+    );
+}
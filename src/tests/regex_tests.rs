@@ -0,0 +1,78 @@
+use super::helpers::*;
+use crate::interpreter::*;
+use printer::write_string;
+
+fn eval_to_text(input: &str) -> String {
+    write_string(&Evaluator::eval_once(input).unwrap())
+}
+
+#[test]
+fn test_regex_match() {
+    assert!(eval_to_bool("(regex-match? \"^[a-z]+\\\\d$\" \"abc7\")"));
+    assert!(eval_to_bool("(regex-match? \"b+\" \"abbc\")"));
+    assert!(!eval_to_bool("(regex-match? \"^b\" \"abbc\")"));
+}
+
+#[test]
+fn test_regex_find() {
+    assert_eq!(
+        eval_to_string("(regex-find \"\\\\d+\" \"room 42, floor 7\")"),
+        "42"
+    );
+    assert_eq!(eval_to_text("(regex-find \"\\\\d+\" \"no digits\")"), "()");
+
+    // With capture groups the match comes back as (whole group...)
+    assert_eq!(
+        eval_to_text("(regex-find \"(\\\\w+)@(\\\\w+)(\\\\.org)?\" \"mail bob@example now\")"),
+        "(\"bob@example\" \"bob\" \"example\" ())"
+    );
+}
+
+#[test]
+fn test_regex_find_all() {
+    assert_eq!(
+        eval_to_text("(regex-find-all \"\\\\d+\" \"1 22 333\")"),
+        "(\"1\" \"22\" \"333\")"
+    );
+    assert_eq!(
+        eval_to_text("(regex-find-all \"(\\\\w)=(\\\\d)\" \"a=1 b=2\")"),
+        "((\"a=1\" \"a\" \"1\") (\"b=2\" \"b\" \"2\"))"
+    );
+    assert!(eval_to_list("(regex-find-all \"x\" \"abc\")").is_empty());
+}
+
+#[test]
+fn test_regex_replace() {
+    assert_eq!(
+        eval_to_string("(regex-replace \"\\\\s+\" \"a   b \\t c\" \" \")"),
+        "a b c"
+    );
+    assert_eq!(
+        eval_to_string("(regex-replace \"(?P<key>\\\\w+)=(\\\\w+)\" \"x=1 y=2\" \"$2:${key}\")"),
+        "1:x 2:y"
+    );
+}
+
+#[test]
+fn test_regex_errors() {
+    let mut evaluator = Evaluator::new();
+    let err = evaluator
+        .eval_str("(regex-find \"(\" \"text\")")
+        .unwrap_err()
+        .to_string();
+    assert!(err.starts_with("regex-find: invalid pattern"), "{}", err);
+    assert_eq!(
+        evaluator
+            .eval_str("(regex-match? \"a\" 5)")
+            .unwrap_err()
+            .to_string(),
+        "regex-match? requires string arguments"
+    );
+    assert_eq!(
+        evaluator
+            .eval_str("(regex-replace \"a\" \"b\")")
+            .unwrap_err()
+            .to_string(),
+        "regex-replace requires exactly 3 arguments"
+    );
+}
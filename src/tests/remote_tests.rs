@@ -0,0 +1,192 @@
+use crate::interpreter::remote::{
+    definition_notice, encode_frame, paren_depth, parse_definition_notice, read_frame, take_frame,
+    RemoteServer, Response, MAX_MESSAGE_BYTES,
+};
+use std::cell::Cell;
+use std::io::Write;
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+fn poll_until(server: &mut RemoteServer, done: &dyn Fn(&RemoteServer) -> bool) {
+    for _ in 0..200 {
+        server.poll(&mut |_, source| Response::Ok(source.to_string()));
+        if done(server) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn read_response(stream: &mut TcpStream) -> Response {
+    let payload = read_frame(stream, MAX_MESSAGE_BYTES).unwrap().unwrap();
+    Response::parse(&payload).unwrap()
+}
+
+#[test]
+fn test_frames_round_trip() {
+    let response = Response::Ok("line one\nback\\slash".to_string());
+    assert_eq!(Response::parse(&response.to_payload()), Some(response));
+    assert_eq!(
+        Response::parse("err boom"),
+        Some(Response::Err("boom".to_string()))
+    );
+    assert_eq!(Response::parse("hello"), None);
+
+    let mut buffer = encode_frame("(+ 1 2)");
+    buffer.extend(encode_frame("héllo"));
+    let partial = buffer.split_off(buffer.len() - 2);
+    assert_eq!(take_frame(&mut buffer, 64), Ok(Some("(+ 1 2)".to_string())));
+    assert_eq!(take_frame(&mut buffer, 64), Ok(None));
+    buffer.extend(partial);
+    assert_eq!(take_frame(&mut buffer, 64), Ok(Some("héllo".to_string())));
+    assert!(buffer.is_empty());
+
+    let mut oversized = encode_frame("(this is too long)");
+    assert!(take_frame(&mut oversized, 8).is_err());
+}
+
+#[test]
+fn test_paren_depth() {
+    assert_eq!(paren_depth("(define x"), 1);
+    assert_eq!(paren_depth("(print \")\")"), 0);
+    assert_eq!(paren_depth("(list #\\( ; )\n"), 1);
+    assert_eq!(paren_depth("[1 2]"), 0);
+}
+
+#[test]
+fn test_server_answers_requests() {
+    let mut server = RemoteServer::bind("127.0.0.1:0").unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    for source in ["(+ 1 2)", "", "(fail)"] {
+        client.write_all(&encode_frame(source)).unwrap();
+    }
+
+    let answered = Cell::new(0);
+    let mut eval = |_peer: &str, source: &str| {
+        answered.set(answered.get() + 1);
+        match source {
+            "(+ 1 2)" => Response::Ok("3".to_string()),
+            _ => Response::Err(format!("bad: {}", source)),
+        }
+    };
+    for _ in 0..200 {
+        server.poll(&mut eval);
+        if server.client_count() == 1 && answered.get() == 2 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    assert_eq!(read_response(&mut client), Response::Ok("3".to_string()));
+    assert_eq!(
+        read_response(&mut client),
+        Response::Err("bad: (fail)".to_string())
+    );
+}
+
+#[test]
+fn test_server_enforces_size_and_rate_limits() {
+    let mut server = RemoteServer::bind("127.0.0.1:0")
+        .unwrap()
+        .with_limits(16, 2);
+    let addr = server.local_addr().unwrap();
+
+    // A burst past the rate gets errors instead of evaluations
+    let mut client = TcpStream::connect(addr).unwrap();
+    for source in ["1", "2", "3"] {
+        client.write_all(&encode_frame(source)).unwrap();
+    }
+    poll_until(&mut server, &|server| server.client_count() == 1);
+    thread::sleep(Duration::from_millis(20));
+    server.poll(&mut |_, source| Response::Ok(source.to_string()));
+    assert_eq!(read_response(&mut client), Response::Ok("1".to_string()));
+    assert_eq!(read_response(&mut client), Response::Ok("2".to_string()));
+    assert!(
+        matches!(read_response(&mut client), Response::Err(message) if message.contains("rate limit"))
+    );
+
+    // An oversized frame is refused and the connection dropped
+    let mut greedy = TcpStream::connect(addr).unwrap();
+    greedy
+        .write_all(&encode_frame("(a form longer than sixteen bytes)"))
+        .unwrap();
+    poll_until(&mut server, &|server| server.client_count() == 1);
+    assert!(
+        matches!(read_response(&mut greedy), Response::Err(message) if message.contains("too large"))
+    );
+    assert_eq!(read_frame(&mut greedy, MAX_MESSAGE_BYTES).unwrap(), None);
+}
+
+#[test]
+fn test_server_limits_connections_and_unread_output() {
+    let mut server = RemoteServer::bind("127.0.0.1:0")
+        .unwrap()
+        .with_limits(MAX_MESSAGE_BYTES, 1000)
+        .with_connection_limits(2, 2 * 1024 * 1024);
+    let addr = server.local_addr().unwrap();
+
+    let first = TcpStream::connect(addr).unwrap();
+    let _second = TcpStream::connect(addr).unwrap();
+    let mut third = TcpStream::connect(addr).unwrap();
+    poll_until(&mut server, &|server| server.client_count() == 2);
+    thread::sleep(Duration::from_millis(20));
+    server.poll(&mut |_, source| Response::Ok(source.to_string()));
+    assert_eq!(server.client_count(), 2);
+    // The third was closed unanswered
+    third.write_all(&encode_frame("1")).unwrap();
+    assert_eq!(read_frame(&mut third, MAX_MESSAGE_BYTES).unwrap(), None);
+
+    // A client that asks for large replies and never reads them is dropped instead of
+    // blocking the server
+    drop(first);
+    poll_until(&mut server, &|server| server.client_count() == 1);
+    let mut stalled = TcpStream::connect(addr).unwrap();
+    for _ in 0..40 {
+        (&stalled).write_all(&encode_frame("big")).unwrap();
+    }
+    let reply = "x".repeat(512 * 1024);
+    for _ in 0..200 {
+        server.poll(&mut |_, _| Response::Ok(reply.clone()));
+        if server.client_count() == 1 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(server.client_count(), 1);
+    // It got what fit in the socket before the connection was closed
+    assert!(matches!(read_response(&mut stalled), Response::Ok(text) if text == reply));
+    let mut frames = 1;
+    while let Ok(Some(_)) = read_frame(&mut stalled, MAX_MESSAGE_BYTES) {
+        frames += 1;
+    }
+    assert!(frames < 40, "{}", frames);
+}
+
+#[test]
+fn test_broadcast_skips_origin() {
+    assert_eq!(
+        parse_definition_notice(&definition_notice("ide", "square")),
+        Some(("ide", "square"))
+    );
+    assert_eq!(parse_definition_notice("ok 3"), None);
+
+    let mut server = RemoteServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let mut first = TcpStream::connect(addr).unwrap();
+    let mut second = TcpStream::connect(addr).unwrap();
+    let origin = first.local_addr().unwrap().to_string();
+    poll_until(&mut server, &|server| server.client_count() == 2);
+
+    server.broadcast(&definition_notice(&origin, "x"), Some(&origin));
+    server.broadcast(&definition_notice("ide", "y"), None);
+
+    assert_eq!(
+        read_frame(&mut second, MAX_MESSAGE_BYTES).unwrap(),
+        Some(definition_notice(&origin, "x"))
+    );
+    assert_eq!(
+        read_frame(&mut first, MAX_MESSAGE_BYTES).unwrap(),
+        Some(definition_notice("ide", "y"))
+    );
+}
@@ -0,0 +1,261 @@
+use super::helpers::*;
+use crate::interpreter::evaluator::Restart;
+use crate::interpreter::printer::write_string;
+use crate::interpreter::repl::{restart_menu, MenuChoice};
+use crate::interpreter::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_invoke_restart_returns_from_restart_case() {
+    assert_eq!(
+        eval_to_number(
+            "(restart-case (+ 1 (invoke-restart (quote use-value) 41))
+               (use-value (v) (+ v 1)))"
+        ),
+        42.0
+    );
+    // Without an invoke, restart-case is its form's value
+    assert_eq!(
+        eval_to_number("(restart-case (* 6 7) (use-value (v) v))"),
+        42.0
+    );
+    assert_eq!(
+        eval_to_string(
+            "(restart-case (invoke-restart (quote skip))
+               (retry () \"retry\")
+               (skip () \"skipped\"))"
+        ),
+        "skipped"
+    );
+}
+
+#[test]
+fn test_innermost_restart_wins_and_compute_restarts_lists_them() {
+    let names = eval_to_list(
+        "(restart-case
+           (restart-case (compute-restarts) (retry () 1) (skip () 2))
+           (abort () 3) (retry () 4))",
+    );
+    let names: Vec<String> = names.iter().map(write_string).collect();
+    assert_eq!(names, ["retry", "skip", "abort", "retry"]);
+    assert_eq!(eval_to_list("(compute-restarts)"), Vec::<Expr>::new());
+
+    assert_eq!(
+        eval_to_number(
+            "(restart-case
+               (restart-case (invoke-restart (quote retry)) (retry () 1))
+               (retry () 2))"
+        ),
+        1.0
+    );
+    // Outer restarts are reachable from inside inner ones
+    assert_eq!(
+        eval_to_number(
+            "(restart-case
+               (restart-case (invoke-restart (quote abort) 5) (retry () 1))
+               (abort (n) (* n 2)))"
+        ),
+        10.0
+    );
+}
+
+#[test]
+fn test_invoking_a_restart_unwinds_through_cleanups_and_scopes() {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define trail (vector))").unwrap();
+    let result = evaluator
+        .eval_str(
+            "(restart-case
+               (let ((x 1))
+                 (unwind-protect
+                   (invoke-restart (quote done) (+ x 1))
+                   (vector-push trail (quote cleaned))))
+               (done (v) (list v (vector-length trail))))",
+        )
+        .unwrap();
+    assert_eq!(write_string(&result), "(2 1)");
+    assert!(evaluator.eval_str("x").is_err());
+    assert_eq!(evaluator.environment.scope_depth(), 0);
+}
+
+#[test]
+fn test_restart_errors() {
+    let err = Evaluator::eval_once("(invoke-restart (quote nowhere))").unwrap_err();
+    assert!(err.contains("no active restart named nowhere"), "{}", err);
+    let err = Evaluator::eval_once("(restart-case (invoke-restart (quote r) 1 2) (r (a) a))")
+        .unwrap_err();
+    assert!(
+        err.contains("restart r expects 1 arguments, got 2"),
+        "{}",
+        err
+    );
+    let err = Evaluator::eval_once("(restart-case 1 (r))").unwrap_err();
+    assert!(err.contains("(name (params...) body...)"), "{}", err);
+    let err = Evaluator::eval_once("(restart-case 1 (r (1) 1))").unwrap_err();
+    assert!(err.contains("parameters must be symbols"), "{}", err);
+    assert!(Evaluator::eval_once("(restart-case)").is_err());
+}
+
+#[test]
+fn test_chooser_picks_a_restart_for_an_error() {
+    let mut evaluator = Evaluator::new();
+    let offered: Rc<RefCell<Vec<Vec<String>>>> = Rc::default();
+    let log = Rc::clone(&offered);
+    evaluator.restart_chooser = Some(Rc::new(
+        move |_: &mut Evaluator, err: &EvalError, restarts: &[Restart]| {
+            let names = restarts
+                .iter()
+                .map(|restart| restart.name.clone())
+                .collect();
+            assert!(err.to_string().contains("car"), "{}", err);
+            log.borrow_mut().push(names);
+            let use_value = restarts
+                .iter()
+                .find(|restart| restart.name == "use-value")?;
+            Some((use_value.id, vec![Expr::Integer(10)]))
+        },
+    ));
+
+    let result = evaluator
+        .eval_str(
+            "(restart-case
+               (restart-case (+ 1 (car 5)) (retry () 0))
+               (use-value (v) (* v 2)))",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::Integer(20));
+    // Asked once, by the innermost restart-case, with every active restart
+    let calls = offered.borrow_mut().split_off(0);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0], ["retry", "use-value"]);
+
+    // Control transfers aren't errors, and forms with no error don't ask
+    evaluator
+        .eval_str("(catch (quote t) (restart-case (throw (quote t) 1) (use-value (v) v)))")
+        .unwrap();
+    assert!(offered.borrow().is_empty());
+
+    // Declining lets the error through without asking the outer restart-case again
+    evaluator.restart_chooser = Some(Rc::new({
+        let log = Rc::clone(&offered);
+        move |_: &mut Evaluator, err: &EvalError, _: &[Restart]| {
+            log.borrow_mut().push(vec![err.to_string()]);
+            None
+        }
+    }));
+    assert!(evaluator
+        .eval_str("(restart-case (restart-case (car 5) (retry () 0)) (abort () 1))")
+        .is_err());
+    assert_eq!(offered.borrow().len(), 1);
+}
+
+#[test]
+fn test_restart_case_survives_the_optimizer() {
+    let mut evaluator = Evaluator::new();
+    evaluator.optimize = true;
+    let result = evaluator
+        .eval_str("(restart-case (invoke-restart (quote + ) (+ 1 2)) (+ (x) (+ x 1)))")
+        .unwrap();
+    assert_eq!(result, Expr::Integer(4));
+}
+
+// An evaluator that answers errors with the terminal REPL's restart menu, fed `answers`
+fn menu_evaluator(answers: &str) -> (Evaluator, StringPort) {
+    let mut evaluator = Evaluator::new();
+    evaluator.input = Rc::new(StringInputPort::new(answers));
+    let output = evaluator.capture_output();
+    evaluator.restart_chooser = Some(Rc::new(|evaluator, err, restarts| {
+        match restart_menu(evaluator, err, restarts) {
+            MenuChoice::Restart(id, args) => Some((id, args)),
+            _ => None,
+        }
+    }));
+    (evaluator, output)
+}
+
+#[test]
+fn test_restart_menu_takes_a_restart_with_evaluated_values() {
+    let (mut evaluator, output) = menu_evaluator("1\n(* 6 7)\n");
+    let result = evaluator
+        .eval_str("(restart-case (+ 1 (car 5)) (use-value (v) v))")
+        .unwrap();
+    assert_eq!(result, Expr::Integer(42));
+    let shown = output.take();
+    assert!(shown.starts_with("Error: "), "{}", shown);
+    for line in [
+        "  1: use-value (v)\n",
+        "  2: Retry the top-level form\n",
+        "  3: Abort to the top level\n",
+        "  4: Enter the debugger\n",
+        "Choose a restart: Value for v: ",
+    ] {
+        assert!(shown.contains(line), "{}", shown);
+    }
+}
+
+#[test]
+fn test_restart_menu_debugger_sees_the_bindings_at_the_error() {
+    let (mut evaluator, output) = menu_evaluator("9\n4\nx\n(car x)\n\n1\n(+ x 1)\n");
+    let result = evaluator
+        .eval_str("(let ((x 5)) (restart-case (car x) (use-value (v) v)))")
+        .unwrap();
+    assert_eq!(result, Expr::Integer(6));
+    let shown = output.take();
+    assert!(shown.contains("Choose a number from 1 to 4\n"), "{}", shown);
+    assert!(shown.contains("debug> 5\ndebug> Error: "), "{}", shown);
+    // The debugger's own error doesn't bring up another menu
+    assert_eq!(shown.matches("Restarts:").count(), 3, "{}", shown);
+}
+
+#[test]
+fn test_restart_menu_debugger_shows_the_scope_chain() {
+    let (mut evaluator, output) = menu_evaluator("4\n:scopes\n\n1\n(+ x y)\n");
+    let result = evaluator
+        .eval_str(
+            "(progn
+               (define x 1)
+               (defun inner (y) (restart-case (car y) (use-value (v) v)))
+               (let ((x 2) (items (list 1 2))) (inner 3)))",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::Integer(5));
+    let shown = output.take();
+    let expected = concat!(
+        "debug> global\n",
+        "  inner = (lambda (y) (restart-case (car y) (use-value # v)))\n",
+        "  x = 1  (shadowed)\n",
+        "  let\n",
+        "    x = 2\n",
+        "    items = (1 2)\n",
+        "    function call\n",
+        "      y = 3\n",
+        "debug> ",
+    );
+    assert!(shown.contains(expected), "{}", shown);
+}
+
+#[test]
+fn test_restart_menu_retry_abort_and_end_of_input() {
+    let restarts = [Restart {
+        id: 7,
+        name: "skip".to_string(),
+        params: Vec::new(),
+    }];
+    let err = EvalError::message("boom");
+    for (answers, expected) in [
+        ("2\n", MenuChoice::Retry),
+        ("3\n", MenuChoice::Abort),
+        ("", MenuChoice::Abort),
+        ("1\n", MenuChoice::Restart(7, Vec::new())),
+        ("4\n", MenuChoice::Abort),
+    ] {
+        let (mut evaluator, _) = menu_evaluator(answers);
+        assert_eq!(
+            restart_menu(&mut evaluator, &err, &restarts),
+            expected,
+            "{:?}",
+            answers
+        );
+    }
+}
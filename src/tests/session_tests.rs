@@ -0,0 +1,105 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::session::{SessionLog, MAX_SESSION_ENTRIES};
+use crate::interpreter::types::Expr;
+use std::fs;
+
+#[test]
+fn test_session_records_evaluations_in_order() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define x 10)").unwrap();
+    eval.eval_str("(+ x 1)").unwrap();
+
+    let entries = eval.session.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].source, "(define x 10)");
+    assert_eq!(entries[1].source, "(+ x 1)");
+    assert!(entries.iter().all(|e| e.succeeded));
+}
+
+#[test]
+fn test_session_script_skips_errored_forms() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define x 10)").unwrap();
+    assert!(eval.eval_str("(undefined-fn x)").is_err());
+    assert!(eval.eval_str("(+ x").is_err());
+    eval.eval_str("(defun double (n) (* n 2))").unwrap();
+
+    let script = eval.session.to_script();
+    assert!(script.contains("(define x 10)"));
+    assert!(script.contains("(defun double (n) (* n 2))"));
+    assert!(!script.contains("undefined-fn"));
+    assert!(!script.contains("(+ x\n"));
+}
+
+#[test]
+fn test_session_script_builtin_excludes_itself() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define y 5)").unwrap();
+    eval.eval_str("(session-script)").unwrap();
+
+    match eval.eval_str("(session-script)").unwrap() {
+        Expr::String(script) => {
+            assert!(script.contains("(define y 5)"));
+            assert!(!script.contains("session-script"));
+        }
+        other => panic!("Expected string, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_export_session_writes_runnable_script() {
+    let path = std::env::temp_dir().join(format!("zeus_session_{}.lisp", std::process::id()));
+    let path_str = path.display().to_string();
+
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define base 20)").unwrap();
+    let _ = eval.eval_str("(car 5)");
+    eval.eval_str("(defun add-base (n) (+ n base))").unwrap();
+
+    let written = eval
+        .eval_str(&format!("(export-session \"{}\")", path_str))
+        .unwrap();
+    assert_eq!(written, Expr::Integer(2));
+
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).ok();
+    assert!(!contents.contains("export-session"));
+
+    // Replaying the exported forms in a fresh evaluator recreates the definitions
+    let mut replay = Evaluator::new();
+    for form in eval.session.exportable_forms() {
+        replay.eval_str(form).unwrap();
+    }
+    assert_eq!(replay.eval_str("(add-base 1)").unwrap(), Expr::Integer(21));
+}
+
+#[test]
+fn test_forms_that_run_session_commands_stay_out_of_the_script() {
+    let mut eval = Evaluator::new();
+    eval.capture_output();
+    eval.eval_str("(define z 1)").unwrap();
+    eval.eval_str("(print (session-script))").unwrap();
+    eval.eval_str("(defun show () (session-script))").unwrap();
+    eval.eval_str("(show)").unwrap();
+
+    let sources: Vec<&str> = eval
+        .session
+        .entries()
+        .iter()
+        .map(|entry| entry.source.as_str())
+        .collect();
+    assert_eq!(
+        sources,
+        ["(define z 1)", "(defun show () (session-script))"]
+    );
+}
+
+#[test]
+fn test_session_log_keeps_only_the_latest_forms() {
+    let mut log = SessionLog::new();
+    for i in 0..MAX_SESSION_ENTRIES + 5 {
+        log.record(&format!("(define x {})", i), true);
+    }
+    assert_eq!(log.entries().len(), MAX_SESSION_ENTRIES);
+    assert_eq!(log.entries()[0].source, "(define x 5)");
+}
@@ -0,0 +1,88 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::snapshot::{restore_snapshot, snapshot_script};
+use crate::interpreter::types::Expr;
+
+#[test]
+fn test_snapshot_round_trips_data_and_functions() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define n 42)").unwrap();
+    eval.eval_str("(define ratio (string->number \"1/3\"))")
+        .unwrap();
+    eval.eval_str("(define greeting \"hello \\\"world\\\"\\n\")")
+        .unwrap();
+    eval.eval_str(
+        "(define items (list 1 2.5 (string->number \"7/2\") :key #\\a (list (quote sym))))",
+    )
+    .unwrap();
+    eval.eval_str("(define pair (cons 1 (string->number \"-2/3\")))")
+        .unwrap();
+    eval.eval_str("(define table (alist->hash (list (cons :a [1 2]))))")
+        .unwrap();
+    eval.eval_str("(define grid (list->matrix (list (list 1 2.5) (list -3 0))))")
+        .unwrap();
+    // Dequeuing then enqueuing wraps the queue's storage around
+    eval.eval_str("(define jobs (make-queue 0 :a))").unwrap();
+    eval.eval_str("(dequeue jobs)").unwrap();
+    eval.eval_str("(enqueue jobs \"b\")").unwrap();
+    eval.eval_str("(define pending (make-heap < 5 1 3))")
+        .unwrap();
+    eval.eval_str("(define origin (make-struct (quote point) (quote (x y)) 0 (list 1)))")
+        .unwrap();
+    eval.eval_str("(defun square (x) (* x x))").unwrap();
+
+    let script = snapshot_script(&eval);
+    assert!(script.contains("(define n 42)\n"));
+    assert!(!script.contains("(define car "));
+
+    let mut restored = Evaluator::new();
+    let (count, errors) = restore_snapshot(&mut restored, &script);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(count, 11);
+    assert!(restored.session.entries().is_empty());
+
+    for name in [
+        "n", "ratio", "greeting", "items", "pair", "table", "grid", "jobs", "pending", "origin",
+    ] {
+        assert_eq!(
+            restored.eval_str(name).unwrap(),
+            eval.eval_str(name).unwrap(),
+            "{}",
+            name
+        );
+    }
+    // Rationals come back exact, not as the floats (/ n d) would make
+    assert_eq!(
+        restored.eval_str("ratio").unwrap(),
+        Expr::Rational {
+            numerator: 1,
+            denominator: 3
+        }
+    );
+    assert_eq!(
+        restored.eval_str("(car (cdr (cdr items)))").unwrap(),
+        Expr::Rational {
+            numerator: 7,
+            denominator: 2
+        }
+    );
+    assert_eq!(
+        restored.eval_str("(square 7)").unwrap(),
+        restored.eval_str("49").unwrap()
+    );
+}
+
+#[test]
+fn test_snapshot_skips_unreadable_values_and_bad_lines() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define g (gensym))").unwrap();
+    eval.eval_str("(define ok 1)").unwrap();
+
+    let script = snapshot_script(&eval);
+    assert!(!script.contains("(define g "));
+
+    let mut restored = Evaluator::new();
+    let (count, errors) = restore_snapshot(&mut restored, &format!("{}(define broken\n", script));
+    assert_eq!(count, 1);
+    assert_eq!(errors.len(), 1);
+    assert!(restored.eval_str("ok").is_ok());
+}
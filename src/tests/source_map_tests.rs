@@ -0,0 +1,133 @@
+use crate::interpreter::source_map::{Position, Span};
+use crate::interpreter::*;
+use std::rc::Rc;
+
+fn span(start: (usize, usize), end: (usize, usize)) -> Span {
+    Span::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+}
+
+#[test]
+fn test_token_spans() {
+    let (tokens, spans) = Tokenizer::new("(foo\n  \"bar\" 12)")
+        .tokenize_spanned()
+        .unwrap();
+    assert_eq!(tokens.len(), 5);
+    assert_eq!(
+        spans,
+        vec![
+            span((1, 1), (1, 2)),
+            span((1, 2), (1, 5)),
+            span((2, 3), (2, 8)),
+            span((2, 9), (2, 11)),
+            span((2, 11), (2, 12)),
+        ]
+    );
+
+    // Only the first line is shifted by the origin's column
+    let (_, spans) = Tokenizer::with_origin("a\nb", Position::new(10, 5))
+        .tokenize_spanned()
+        .unwrap();
+    assert_eq!(spans, vec![span((10, 5), (10, 6)), span((11, 1), (11, 2))]);
+
+    let error = Tokenizer::new("(a\n  \"open")
+        .tokenize_spanned()
+        .unwrap_err();
+    assert_eq!(error.message, "Unterminated string");
+    assert_eq!(error.position, Position::new(2, 3));
+}
+
+#[test]
+fn test_read_errors_have_positions() {
+    let evaluator = Evaluator::new();
+    for (source, message, position) in [
+        ("(a b))", "Extra tokens after expression", (1, 6)),
+        (")", "Unexpected )", (1, 1)),
+        ("(a\n (b ]", "Unexpected ]", (2, 5)),
+        ("(a\n (b c)", "Unexpected end of input", (2, 7)),
+    ] {
+        let error = evaluator.read_at(source, Position::START).unwrap_err();
+        assert_eq!(error.message, message, "{}", source);
+        assert_eq!(
+            error.position,
+            Position::new(position.0, position.1),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn test_lists_keep_their_spans() {
+    let evaluator = Evaluator::new();
+    let expr = evaluator
+        .read_at("(define (f x)\n  (* x 2))", Position::new(3, 1))
+        .unwrap();
+    assert_eq!(
+        evaluator.source_map.span_of(&expr),
+        Some(span((3, 1), (4, 11)))
+    );
+    let Expr::List(items) = &expr else {
+        panic!("Expected a list, got {:?}", expr);
+    };
+    assert_eq!(
+        evaluator.source_map.span_of(&items[1]),
+        Some(span((3, 9), (3, 14)))
+    );
+    assert_eq!(
+        evaluator.source_map.span_of(&items[2]),
+        Some(span((4, 3), (4, 10)))
+    );
+    // Atoms and lists built at run time have none
+    assert_eq!(evaluator.source_map.span_of(&items[0]), None);
+    assert_eq!(
+        evaluator
+            .source_map
+            .span_of(&Expr::new_list(vec![Expr::Integer(1)])),
+        None
+    );
+}
+
+#[test]
+fn test_error_span_is_the_innermost_failing_form() {
+    let mut evaluator = Evaluator::new();
+    assert!(evaluator.eval_str("(list 1\n  (+ 2 (car 5)))").is_err());
+    assert_eq!(evaluator.error_span(), Some(span((2, 8), (2, 15))));
+
+    // Errors from a function body point into the definition, not the call
+    evaluator
+        .eval_source_at("(defun head (x)\n  (car x))", Position::new(20, 1))
+        .unwrap();
+    assert!(evaluator.eval_str("(head 7)").is_err());
+    assert_eq!(evaluator.error_span(), Some(span((21, 3), (21, 10))));
+
+    assert_eq!(evaluator.eval_str("(+ 1 2)").unwrap(), Expr::Integer(3));
+    assert_eq!(evaluator.error_span(), None);
+
+    // An error a restart handled doesn't stand in for a later one
+    evaluator.restart_chooser = Some(Rc::new(|_, _, restarts| Some((restarts[0].id, vec![]))));
+    assert!(evaluator
+        .eval_str("(progn (restart-case (car 5) (use-zero () 0))\n (vector-ref 1 2))")
+        .is_err());
+    assert_eq!(evaluator.error_span(), Some(span((2, 2), (2, 18))));
+}
+
+#[test]
+fn test_optimized_forms_keep_the_original_span() {
+    let mut evaluator = Evaluator::new();
+    evaluator.optimize = true;
+    assert!(evaluator.eval_str("(progn (car (+ 1 2)))").is_err());
+    assert_eq!(evaluator.error_span(), Some(span((1, 1), (1, 22))));
+}
+
+#[test]
+fn test_spans_of_dropped_forms_are_pruned() {
+    let mut evaluator = Evaluator::new();
+    for n in 0..5000 {
+        evaluator.eval_str(&format!("(+ {} (* 2 3))", n)).unwrap();
+    }
+    assert!(
+        evaluator.source_map.len() < 4096,
+        "{}",
+        evaluator.source_map.len()
+    );
+}
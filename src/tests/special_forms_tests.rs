@@ -91,11 +91,11 @@ fn test_and_basic() {
 #[test]
 fn test_and_short_circuit() {
     let result = Evaluator::eval_once("(and t nil 3)").unwrap();
-    assert_eq!(result, Expr::List(vec![]));
+    assert_eq!(result, Expr::new_list(vec![]));
 
     // Ensure short-circuiting prevents evaluation of later forms
     let result = Evaluator::eval_once("(and nil (/ 1 0))").unwrap();
-    assert_eq!(result, Expr::List(vec![]));
+    assert_eq!(result, Expr::new_list(vec![]));
 }
 
 #[test]
@@ -110,7 +110,10 @@ fn test_and_returns_last_truthy() {
 
 #[test]
 fn test_or_basic() {
-    assert_eq!(Evaluator::eval_once("(or)").unwrap(), Expr::List(vec![]));
+    assert_eq!(
+        Evaluator::eval_once("(or)").unwrap(),
+        Expr::new_list(vec![])
+    );
     assert_eq!(eval_to_number("(or nil 2)"), 2.0); // Returns first truthy
     assert_eq!(eval_to_number("(or nil nil 3)"), 3.0);
     let result = Evaluator::eval_once("(or 0 4)").unwrap();
@@ -252,6 +255,65 @@ fn test_loop_aliases_do() {
     );
 }
 
+#[test]
+fn test_while_and_until() {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define n 0)").unwrap();
+    evaluator.eval_str("(define sum 0)").unwrap();
+    let result = evaluator
+        .eval_str(
+            "(while (< n 5)
+               (define n (+ n 1))
+               (define sum (+ sum n)))",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::new_list(vec![]));
+    assert_eq!(evaluator.eval_str("sum").unwrap(), Expr::Integer(15));
+
+    evaluator
+        .eval_str("(until (= n 0) (define n (- n 1)))")
+        .unwrap();
+    assert_eq!(evaluator.eval_str("n").unwrap(), Expr::Integer(0));
+}
+
+#[test]
+fn test_break_and_continue() {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_str("(define n 0)").unwrap();
+    evaluator.eval_str("(define total 0)").unwrap();
+    let result = evaluator
+        .eval_str(
+            "(while t
+               (define n (+ n 1))
+               (when (> n 9) (break total))
+               (when (= n 5) (continue))
+               (define total (+ total n)))",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::Integer(40));
+
+    // break leaves only the innermost loop, and works in the infinite loop form
+    let result = evaluator
+        .eval_str(
+            "(let ((i 0) (hits 0))
+               (loop
+                 (define i (+ i 1))
+                 (when (> i 3) (break hits))
+                 (until nil (define hits (+ hits 1)) (break))))",
+        )
+        .unwrap();
+    assert_eq!(result, Expr::Integer(3));
+
+    assert_eq!(
+        evaluator.eval_str("(break 1)"),
+        Err("break outside of a loop".to_string())
+    );
+    assert_eq!(
+        evaluator.eval_str("(continue)"),
+        Err("continue outside of a loop".to_string())
+    );
+}
+
 #[test]
 fn test_catch_throw_basic() {
     assert_eq!(
@@ -300,7 +362,7 @@ fn test_block_return_from() {
 #[test]
 fn test_return_from_defaults_to_nil() {
     let result = Evaluator::eval_once("(block exit (return-from exit))").unwrap();
-    assert_eq!(result, Expr::List(vec![]));
+    assert_eq!(result, Expr::new_list(vec![]));
 }
 
 #[test]
@@ -359,3 +421,118 @@ fn test_nested_let() {
         30.0
     );
 }
+
+#[test]
+fn test_time_reports_and_returns_value() {
+    let mut evaluator = Evaluator::new();
+    let captured = evaluator.capture_output();
+    let result = evaluator.eval_str("(time (length (list 1 2 3)))").unwrap();
+    assert_eq!(result, Expr::Integer(3));
+    let output = captured.take();
+    assert!(output.starts_with("; time: "), "{}", output);
+    assert!(output.contains(" allocations, "), "{}", output);
+
+    assert!(evaluator.eval_str("(time)").is_err());
+}
+
+#[test]
+fn test_for_comprehension() {
+    let mut evaluator = Evaluator::new();
+    let cases = [
+        ("(for ((x in (list 1 2 3))) (* x x))", "(list 1 4 9)"),
+        (
+            "(for ((x in (range 1 4)) (y in (range 1 4)) :when (> x y)) (list x y))",
+            "(list (list 2 1) (list 3 1) (list 3 2))",
+        ),
+        // Later collections see earlier variables, and :when can filter at any depth
+        (
+            "(for ((x in (range 4)) :when (> x 1) (y in (range x))) (cons x y))",
+            "(list (cons 2 0) (cons 2 1) (cons 3 0) (cons 3 1) (cons 3 2))",
+        ),
+        (
+            "(for ((c in \"ab\") (n in [1 2])) (list c n))",
+            "(list (list #\\a 1) (list #\\a 2) (list #\\b 1) (list #\\b 2))",
+        ),
+        ("(for ((x in (list))) x)", "(list)"),
+        ("(for ((x in (list 1 2)) :when (> x 5)) x)", "(list)"),
+        // The body is a progn; with no clauses it runs once
+        ("(for () (define z 1) (+ z 1))", "(list 2)"),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(
+            evaluator.eval_str(expr).unwrap(),
+            evaluator.eval_str(expected).unwrap(),
+            "{}",
+            expr
+        );
+    }
+
+    // Loop variables don't leak, and an iterator is consumed as it is walked
+    evaluator.eval_str("(define x 100)").unwrap();
+    evaluator.eval_str("(define it (range 5))").unwrap();
+    assert_eq!(
+        evaluator
+            .eval_str("(for ((x in it) :when (< x 2)) x)")
+            .unwrap(),
+        evaluator.eval_str("(list 0 1)").unwrap()
+    );
+    assert_eq!(evaluator.eval_str("x").unwrap(), Expr::Integer(100));
+    assert_eq!(
+        evaluator.eval_str("(iter-done? it)").unwrap(),
+        evaluator.eval_str("t").unwrap()
+    );
+
+    for bad in [
+        "(for ((x in 5)) x)",
+        "(for ((x on (list 1))) x)",
+        "(for ((x in (list 1)) :when) x)",
+        "(for ((x in (list 1))))",
+        "(for x x)",
+    ] {
+        assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_dolist() {
+    let mut evaluator = Evaluator::new();
+    let cases = [
+        (
+            "(let ((total 0)) (dolist (x (list 1 2 3) total) (define total (+ total x))))",
+            "6",
+        ),
+        (
+            "(let ((seen (list))) (dolist (c \"ab\" seen) (define seen (cons c seen))))",
+            "(list #\\b #\\a)",
+        ),
+        ("(dolist (x [1 2 3]) x)", "()"),
+        // The result form sees the variable as nil
+        ("(dolist (x (range 3) x))", "()"),
+        ("(dolist (x (range 10)) (when (> x 2) (break x)))", "3"),
+        (
+            "(let ((big (list))) (dolist (x (range 5) big) (when (< x 3) (continue)) (define big (cons x big))))",
+            "(list 4 3)",
+        ),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(
+            evaluator.eval_str(expr).unwrap(),
+            evaluator.eval_str(expected).unwrap(),
+            "{}",
+            expr
+        );
+    }
+
+    evaluator.eval_str("(define x 100)").unwrap();
+    evaluator.eval_str("(dolist (x (list 1 2)) x)").unwrap();
+    assert_eq!(evaluator.eval_str("x").unwrap(), Expr::Integer(100));
+
+    for bad in [
+        "(dolist (x 5) x)",
+        "(dolist x x)",
+        "(dolist (x) x)",
+        "(dolist (x (list 1) 1 2) x)",
+    ] {
+        assert!(evaluator.eval_str(bad).is_err(), "{}", bad);
+    }
+}
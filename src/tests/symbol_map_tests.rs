@@ -0,0 +1,121 @@
+use crate::interpreter::symbol_map::SymbolMap;
+use crate::interpreter::types::Symbol;
+
+#[test]
+fn test_insert_and_get() {
+    let mut map = SymbolMap::new();
+    assert!(map.is_empty());
+    let symbols: Vec<Symbol> = (0..2000)
+        .map(|i| Symbol::intern(&format!("symbol-map-test-{}", i)))
+        .collect();
+    for (i, symbol) in symbols.iter().enumerate() {
+        assert!(map.insert(*symbol, i));
+    }
+    assert_eq!(map.len(), 2000);
+    for (i, symbol) in symbols.iter().enumerate() {
+        assert_eq!(map.get(*symbol), Some(&i));
+    }
+    assert!(!map.contains_key(Symbol::intern("symbol-map-test-missing")));
+
+    // Replacing a value doesn't change the size
+    assert!(!map.insert(symbols[7], 70));
+    assert_eq!(map.get(symbols[7]), Some(&70));
+    assert_eq!(map.len(), 2000);
+    assert_eq!(map.iter().count(), 2000);
+}
+
+#[test]
+fn test_clones_are_independent() {
+    let a = Symbol::intern("symbol-map-clone-a");
+    let b = Symbol::intern("symbol-map-clone-b");
+    let mut original = SymbolMap::new();
+    original.insert(a, 1);
+
+    let mut copy = original.clone();
+    assert!(copy.shares_root_with(&original));
+    copy.insert(a, 2);
+    copy.insert(b, 3);
+    assert!(!copy.shares_root_with(&original));
+
+    assert_eq!(original.get(a), Some(&1));
+    assert_eq!(original.get(b), None);
+    assert_eq!(original.len(), 1);
+    assert_eq!(copy.get(a), Some(&2));
+    assert_eq!(copy.get(b), Some(&3));
+}
+
+#[test]
+fn test_diff_against_an_earlier_clone() {
+    let symbols: Vec<Symbol> = (0..500)
+        .map(|i| Symbol::intern(&format!("symbol-map-diff-{}", i)))
+        .collect();
+    let mut map = SymbolMap::new();
+    for (i, symbol) in symbols.iter().enumerate().take(400) {
+        map.insert(*symbol, i);
+    }
+    assert!(map.diff(&map.clone()).is_empty());
+
+    let before = map.clone();
+    map.insert(symbols[3], 30);
+    for (i, symbol) in symbols.iter().enumerate().skip(400) {
+        map.insert(*symbol, i);
+    }
+    let mut changes: Vec<(Symbol, Option<usize>, Option<usize>)> = map
+        .diff(&before)
+        .into_iter()
+        .map(|(key, old, new)| (key, old.copied(), new.copied()))
+        .collect();
+    changes.sort_by_key(|(key, _, _)| key.id());
+
+    let mut expected = vec![(symbols[3], Some(3), Some(30))];
+    expected.extend((400..500).map(|i| (symbols[i], None, Some(i))));
+    expected.sort_by_key(|(key, _, _)| key.id());
+    assert_eq!(changes, expected);
+
+    // The other way round, the added keys are removals
+    let reversed = before.diff(&map);
+    assert_eq!(reversed.len(), 101);
+    assert_eq!(
+        reversed.iter().filter(|(_, _, new)| new.is_none()).count(),
+        100
+    );
+}
+
+#[test]
+fn test_remove() {
+    let symbols: Vec<Symbol> = (0..300)
+        .map(|i| Symbol::intern(&format!("symbol-map-remove-{}", i)))
+        .collect();
+    let mut map = SymbolMap::new();
+    for (i, symbol) in symbols.iter().enumerate() {
+        map.insert(*symbol, i);
+    }
+    let before = map.clone();
+
+    for (i, symbol) in symbols.iter().enumerate().step_by(2) {
+        assert_eq!(map.remove(*symbol), Some(i));
+    }
+    assert_eq!(map.remove(symbols[0]), None);
+    assert_eq!(map.len(), 150);
+    assert_eq!(map.iter().count(), 150);
+    for (i, symbol) in symbols.iter().enumerate() {
+        let expected = (i % 2 == 1).then_some(i);
+        assert_eq!(map.get(*symbol).copied(), expected);
+    }
+    // The clone taken before is untouched, and sees the removals as such
+    assert_eq!(before.len(), 300);
+    assert_eq!(before.get(symbols[0]), Some(&0));
+    let removed = map.diff(&before);
+    assert_eq!(removed.len(), 150);
+    assert!(removed
+        .iter()
+        .all(|(_, old, new)| old.is_some() && new.is_none()));
+
+    // Emptied, and filled again
+    for symbol in &symbols {
+        map.remove(*symbol);
+    }
+    assert!(map.is_empty());
+    map.insert(symbols[5], 5);
+    assert_eq!(map.get(symbols[5]), Some(&5));
+}
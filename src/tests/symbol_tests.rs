@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod symbol_tests {
     use crate::interpreter::evaluator::Evaluator;
-    use crate::interpreter::types::Expr;
+    use crate::interpreter::types::{Expr, Symbol, SymbolData};
 
     #[test]
     fn test_keyword_self_evaluation() {
@@ -40,6 +40,43 @@ mod symbol_tests {
         // Could be "let binding must start with a symbol" or similar
     }
 
+    #[test]
+    fn test_keyword_as_accessor() {
+        let mut eval = Evaluator::new();
+        eval.eval_str("(define person (make-hash-table))").unwrap();
+        eval.eval_str("(hash-set! person :name \"Ada\")").unwrap();
+        eval.eval_str("(define point (list :x 3 :y 4))").unwrap();
+
+        assert_eq!(
+            eval.eval_str("(:name person)").unwrap(),
+            Expr::String("Ada".to_string())
+        );
+        assert_eq!(eval.eval_str("(:y point)").unwrap(), Expr::Integer(4));
+        // Missing keys give () or the default
+        assert_eq!(
+            eval.eval_str("(:age person)").unwrap(),
+            Expr::new_list(vec![])
+        );
+        assert_eq!(eval.eval_str("(:z point 0)").unwrap(), Expr::Integer(0));
+        // Keywords that share a name with a special form are still accessors
+        eval.eval_str("(hash-set! person :if 1)").unwrap();
+        assert_eq!(eval.eval_str("(:if person)").unwrap(), Expr::Integer(1));
+        assert_eq!(
+            eval.eval_str("(mapcar :x (list point (list :x 5)))")
+                .unwrap(),
+            Evaluator::parse("(3 5)").unwrap()
+        );
+
+        assert!(eval.eval_str("(:x)").is_err());
+        assert!(eval.eval_str("(:x (list :x))").is_err());
+        let error = eval.eval_str("(:x 5)").unwrap_err();
+        assert!(
+            error.contains(":x requires a hash table or property list, got 5"),
+            "{}",
+            error
+        );
+    }
+
     #[test]
     fn test_gensym_basic() {
         let mut eval = Evaluator::new();
@@ -176,7 +213,7 @@ mod symbol_tests {
 
         // Different keywords should not be equal
         let result = eval.eval_str("(= :foo :bar)").unwrap();
-        assert_eq!(result, Expr::List(vec![]));
+        assert_eq!(result, Expr::new_list(vec![]));
     }
 
     #[test]
@@ -220,4 +257,26 @@ mod symbol_tests {
         let result = eval.eval_str("(get (quote test) (quote prop))").unwrap();
         assert_eq!(format!("{:?}", result), "Symbol(Interned(\"new\"))");
     }
+
+    #[test]
+    fn test_symbols_are_interned() {
+        let a = Symbol::intern("interned-test-name");
+        let b = Symbol::intern(&String::from("interned-test-name"));
+        assert_eq!(a.id(), b.id());
+        assert_eq!(a, b);
+        assert_ne!(a, Symbol::intern("interned-test-other"));
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert_eq!(&*a, "interned-test-name");
+
+        // Symbols read from source share the table with those built in Rust
+        let mut eval = Evaluator::new();
+        let result = eval.eval_str("(quote interned-test-name)").unwrap();
+        assert_eq!(result, Expr::Symbol(SymbolData::Interned(a)));
+        let result = eval.eval_str(":interned-test-name").unwrap();
+        assert_eq!(result, Expr::Symbol(SymbolData::Keyword(a)));
+        assert_ne!(
+            Expr::Symbol(SymbolData::Keyword(a)),
+            Expr::Symbol(SymbolData::Interned(a))
+        );
+    }
 }
@@ -15,6 +15,30 @@ fn test_tokenizer_numbers() {
     );
 }
 
+#[test]
+fn test_tokenizer_float_syntax() {
+    let mut tokenizer = Tokenizer::new("1e3 2.5E-3 -4e+2 +inf.0 -inf.0 (+nan.0) e10 1 e +inf");
+    let tokens = tokenizer.tokenize().unwrap();
+    assert_eq!(tokens[0], Token::Float(1000.0));
+    assert_eq!(tokens[1], Token::Float(0.0025));
+    assert_eq!(tokens[2], Token::Float(-400.0));
+    assert_eq!(tokens[3], Token::Float(f64::INFINITY));
+    assert_eq!(tokens[4], Token::Float(f64::NEG_INFINITY));
+    assert!(matches!(tokens[6], Token::Float(f) if f.is_nan()));
+    // An e without digits after it isn't an exponent, and the special names need their
+    // exact spelling
+    assert_eq!(
+        tokens[8..],
+        [
+            Token::Symbol("e10".to_string()),
+            Token::Integer(1),
+            Token::Symbol("e".to_string()),
+            Token::Symbol("+inf".to_string()),
+        ]
+    );
+    assert!(Tokenizer::new("-inf.0x").tokenize().is_err());
+}
+
 #[test]
 fn test_tokenizer_symbols() {
     let mut tokenizer = Tokenizer::new("+ define lambda x y");
@@ -65,3 +89,89 @@ fn test_tokenizer_lists() {
         ]
     );
 }
+
+#[test]
+fn test_tokenizer_character_literals() {
+    let mut tokenizer = Tokenizer::new(r"#\a #\space #\Newline #\( #\nul #\x41 #\1");
+    let tokens = tokenizer.tokenize().unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Character('a'),
+            Token::Character(' '),
+            Token::Character('\n'),
+            Token::Character('('),
+            Token::Character('\0'),
+            Token::Character('A'),
+            Token::Character('1'),
+        ]
+    );
+
+    assert!(Tokenizer::new(r"#\bogus").tokenize().is_err());
+}
+
+#[test]
+fn test_tokenizer_booleans() {
+    let mut tokenizer = Tokenizer::new("#t #f #true #false");
+    let tokens = tokenizer.tokenize().unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Boolean(true),
+            Token::Boolean(false),
+            Token::Boolean(true),
+            Token::Boolean(false),
+        ]
+    );
+
+    assert!(Tokenizer::new("#tru").tokenize().is_err());
+}
+
+#[test]
+fn test_tokenizer_radix_integers() {
+    let mut tokenizer = Tokenizer::new("#xFF #x-1a #o17 #b1011 #B-1");
+    let tokens = tokenizer.tokenize().unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Integer(255),
+            Token::Integer(-26),
+            Token::Integer(15),
+            Token::Integer(11),
+            Token::Integer(-1),
+        ]
+    );
+
+    assert!(Tokenizer::new("#b102").tokenize().is_err());
+    assert!(Tokenizer::new("#x").tokenize().is_err());
+
+    // Past i64 they're bignums, as decimal literals are
+    assert_eq!(
+        Tokenizer::new("#xFFFFFFFFFFFFFFFFFFFF #x-8000000000000000")
+            .tokenize()
+            .unwrap(),
+        vec![
+            Token::BigInt(BigInt::parse("1208925819614629174706175").unwrap()),
+            Token::Integer(i64::MIN),
+        ]
+    );
+}
+
+#[test]
+fn test_reader_literals_evaluate() {
+    let mut eval = Evaluator::new();
+    assert_eq!(
+        eval.eval_str("#t").unwrap(),
+        Expr::Symbol(types::SymbolData::Interned(types::Symbol::intern("t")))
+    );
+    assert_eq!(eval.eval_str("#f").unwrap(), Expr::new_list(vec![]));
+    assert_eq!(eval.eval_str("(if #f 1 2)").unwrap(), Expr::Integer(2));
+    assert_eq!(
+        eval.eval_str("(+ #x10 #b11 #o7)").unwrap(),
+        Expr::Integer(26)
+    );
+    assert_eq!(
+        eval.eval_str(r"(char->integer #\tab)").unwrap(),
+        Expr::Integer(9)
+    );
+}
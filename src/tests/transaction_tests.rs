@@ -0,0 +1,81 @@
+use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::types::Expr;
+
+#[test]
+fn test_undefine_removes_global_definitions() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define x 1)").unwrap();
+    eval.eval_str("(defun double (n) (* n 2))").unwrap();
+    eval.environment.take_changed_globals();
+
+    assert_eq!(
+        eval.eval_str("(undefine x)").unwrap(),
+        eval.eval_str("t").unwrap()
+    );
+    assert!(eval.eval_str("x").is_err());
+    assert_eq!(
+        eval.eval_str("(undefine x)").unwrap(),
+        Expr::new_list(vec![])
+    );
+    eval.eval_str("(undefine double)").unwrap();
+    assert!(eval.eval_str("(double 2)").is_err());
+    assert_eq!(
+        eval.environment.take_changed_globals(),
+        vec!["double".to_string(), "x".to_string()]
+    );
+
+    // A redefined builtin goes back to being the builtin
+    eval.eval_str("(define car (lambda (l) 0))").unwrap();
+    eval.eval_str("(undefine car)").unwrap();
+    assert_eq!(eval.eval_str("(car (list 5))").unwrap(), Expr::Integer(5));
+
+    assert!(eval.eval_str("(undefine \"x\")").is_err());
+    assert!(eval.eval_str("(undefine)").is_err());
+}
+
+#[test]
+fn test_transactions_roll_back_definitions() {
+    let mut eval = Evaluator::new();
+    eval.eval_str("(define kept 1)").unwrap();
+    eval.eval_str("(defun area (r) (* r r))").unwrap();
+    eval.eval_str("(put (quote area) (quote doc) \"squares\")")
+        .unwrap();
+
+    assert_eq!(
+        eval.eval_str("(begin-transaction)").unwrap(),
+        Expr::Integer(1)
+    );
+    eval.eval_str("(defun area (r) (car r))").unwrap();
+    eval.eval_str("(define added 2)").unwrap();
+    eval.eval_str("(undefine kept)").unwrap();
+    eval.eval_str("(put (quote area) (quote doc) \"broken\")")
+        .unwrap();
+    assert!(eval.eval_str("(area 3)").is_err());
+
+    assert_eq!(
+        eval.eval_str("(rollback-transaction)").unwrap(),
+        Evaluator::parse("(added area kept)").unwrap()
+    );
+    assert_eq!(eval.eval_str("(area 3)").unwrap(), Expr::Integer(9));
+    assert_eq!(eval.eval_str("kept").unwrap(), Expr::Integer(1));
+    assert!(eval.eval_str("added").is_err());
+    assert_eq!(
+        eval.eval_str("(get (quote area) (quote doc))").unwrap(),
+        Expr::String("squares".to_string())
+    );
+
+    // Nested, the inner one ends first; a commit keeps what was done
+    eval.eval_str("(begin-transaction)").unwrap();
+    eval.eval_str("(define outer 1)").unwrap();
+    eval.eval_str("(begin-transaction)").unwrap();
+    eval.eval_str("(define inner 1)").unwrap();
+    eval.eval_str("(commit-transaction)").unwrap();
+    assert_eq!(eval.environment.transaction_depth(), 1);
+    eval.eval_str("(rollback-transaction)").unwrap();
+    assert!(eval.eval_str("outer").is_err());
+    assert!(eval.eval_str("inner").is_err());
+
+    let error = eval.eval_str("(commit-transaction)").unwrap_err();
+    assert_eq!(error, "No transaction to commit");
+    assert!(eval.eval_str("(rollback-transaction)").is_err());
+}
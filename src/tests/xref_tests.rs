@@ -0,0 +1,28 @@
+use crate::interpreter::xref::find_references;
+
+fn positions(source: &str, name: &str) -> Vec<(usize, usize)> {
+    find_references("a.lisp", source, name)
+        .into_iter()
+        .map(|location| (location.line, location.column))
+        .collect()
+}
+
+#[test]
+fn test_find_references() {
+    let source = "(defun area (r) (* pi r r))\n(area 2) ; area again\n(list \"area\" 'area [area])";
+    assert_eq!(
+        positions(source, "area"),
+        vec![(1, 8), (2, 2), (3, 15), (3, 21)]
+    );
+    assert_eq!(positions(source, "r"), vec![(1, 14), (1, 23), (1, 25)]);
+    // Part of a longer name or a keyword doesn't count
+    assert!(positions("(area-of :area areas)", "area").is_empty());
+}
+
+#[test]
+fn test_find_references_skips_literals() {
+    // A character literal can be a delimiter, and a string can span lines
+    let source = "(list #\\( x #\\space \"x\n\\\" x\" x)";
+    assert_eq!(positions(source, "x"), vec![(1, 11), (2, 7)]);
+    assert_eq!(positions("λx x", "x"), vec![(1, 4)]);
+}
@@ -1,5 +1,6 @@
 use crate::fonts::load_monospace_font;
 use crate::interpreter::evaluator::Evaluator;
+use crate::interpreter::printer::write_string;
 use raylib::consts::{ConfigFlags, TextureFilter};
 use raylib::core::text::RaylibFont;
 use raylib::prelude::*;
@@ -127,7 +128,7 @@ pub fn run_ui() {
                         // Evaluate the expression
                         match evaluator.eval_str(&current_input) {
                             Ok(result) => {
-                                let formatted = format_expr(&result);
+                                let formatted = write_string(&result);
                                 history.push_back(ReplLine {
                                     text: formatted,
                                     is_input: false,
@@ -362,78 +363,3 @@ pub fn run_ui() {
         }
     }
 }
-
-fn format_expr(expr: &crate::interpreter::types::Expr) -> String {
-    use crate::interpreter::types::{Expr, SymbolData};
-
-    match expr {
-        Expr::Integer(n) => format!("{}", n),
-        Expr::Float(f) => format!("{}", f),
-        Expr::Rational {
-            numerator,
-            denominator,
-        } => format!("{}/{}", numerator, denominator),
-        Expr::Character(ch) => format!(
-            "#\\{}",
-            match *ch {
-                ' ' => "space".to_string(),
-                '\n' => "newline".to_string(),
-                '\t' => "tab".to_string(),
-                '\r' => "return".to_string(),
-                c => c.to_string(),
-            }
-        ),
-        Expr::Symbol(sym_data) => match sym_data {
-            SymbolData::Keyword(name) => format!(":{}", name),
-            SymbolData::Uninterned(name, id) => format!("#:{}#{}", name, id),
-            SymbolData::Interned(name) => name.clone(),
-        },
-        Expr::String(s) => format!("\"{}\"", s),
-        Expr::Vector(vec) => {
-            let items: Vec<String> = vec.iter().map(format_expr).collect();
-            format!("[{}]", items.join(" "))
-        }
-        Expr::HashTable(h) => {
-            format!("#<hash-table:{}>", h.len())
-        }
-        Expr::List(list) => {
-            if list.is_empty() {
-                "()".to_string()
-            } else {
-                let items: Vec<String> = list.iter().map(|e| format_expr(e)).collect();
-                format!("({})", items.join(" "))
-            }
-        }
-        Expr::Cons(car, cdr) => {
-            let mut repr = String::from("(");
-            repr.push_str(&format_expr(car));
-
-            let mut tail = cdr.as_ref();
-            loop {
-                match tail {
-                    Expr::Cons(next_car, next_cdr) => {
-                        repr.push(' ');
-                        repr.push_str(&format_expr(next_car));
-                        tail = next_cdr.as_ref();
-                    }
-                    Expr::List(list) => {
-                        for item in list {
-                            repr.push(' ');
-                            repr.push_str(&format_expr(item));
-                        }
-                        repr.push(')');
-                        break;
-                    }
-                    other => {
-                        repr.push_str(" . ");
-                        repr.push_str(&format_expr(other));
-                        repr.push(')');
-                        break;
-                    }
-                }
-            }
-
-            repr
-        }
-    }
-}